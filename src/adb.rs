@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::scanner::ScanConfig;
+use crate::source::{EntryKind, FileSource, RemoteSource, SourceEntry, SourceMetadata};
+
+/// Where phones keep their camera pictures
+pub const DEFAULT_FOLDER: &str = "/sdcard/DCIM";
+// Files share one USB link, so reading more of them at once gains nothing
+const DEVICE_READERS: usize = 3;
+
+/// A device as listed by `adb devices`
+#[derive(Clone, Debug)]
+pub struct DeviceInfo {
+    pub serial: String,
+    /// "device" once USB debugging is allowed, otherwise e.g. "unauthorized"
+    pub state: String,
+    pub model: String,
+}
+
+impl DeviceInfo {
+    pub fn label(&self) -> String {
+        if self.model.is_empty() {
+            self.serial.clone()
+        } else {
+            format!("{} ({})", self.model, self.serial)
+        }
+    }
+}
+
+/// The adb program; `ADB` overrides the one found on the PATH
+fn adb() -> Command {
+    Command::new(std::env::var_os("ADB").unwrap_or_else(|| "adb".into()))
+}
+
+fn run(command: &mut Command) -> Result<String, String> {
+    let output = command.stdin(Stdio::null()).output().map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => "adb was not found; install the Android platform tools or set ADB to its path".to_string(),
+        _ => format!("Cannot run adb: {}", e),
+    })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let message = if stderr.trim().is_empty() { stdout.trim() } else { stderr.trim() };
+        return Err(message.lines().next().unwrap_or("adb failed").to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Quote `text` for the device's shell
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// Devices adb can see, including those still waiting for USB debugging to be allowed
+pub fn list_devices() -> Result<Vec<DeviceInfo>, String> {
+    let output = run(adb().args(["devices", "-l"]))?;
+    Ok(output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let serial = fields.next()?.to_string();
+            let state = fields.next()?.to_string();
+            let model = fields
+                .find_map(|field| field.strip_prefix("model:"))
+                .map(|model| model.replace('_', " "))
+                .unwrap_or_default();
+            Some(DeviceInfo { serial, state, model })
+        })
+        .collect())
+}
+
+#[derive(Clone, Copy, Debug)]
+struct DeviceFile {
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+fn parse_stat(line: &str) -> Option<(DeviceFile, &str)> {
+    let mut fields = line.splitn(3, ' ');
+    let size = fields.next()?.parse().ok()?;
+    let modified = fields.next()?.parse().ok().map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+    Some((DeviceFile { size, modified }, fields.next()?))
+}
+
+/// An Android device reached over adb. Files are listed with one `find` per
+/// root and, where the device has `sha256sum`, hashed on the device itself,
+/// so comparing them with files on this PC sends no contents over USB.
+pub struct AdbDevice {
+    serial: String,
+    model: String,
+    prefix: PathBuf,
+    hash_on_device: bool,
+    /// Sizes and times from the last listing, so metadata needs no call per file
+    files: Mutex<HashMap<PathBuf, DeviceFile>>,
+}
+
+impl fmt::Debug for AdbDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdbDevice").field("prefix", &self.prefix).field("hash_on_device", &self.hash_on_device).finish()
+    }
+}
+
+impl AdbDevice {
+    /// Check that the device answers; `hash_on_device` is dropped when it has no `sha256sum`
+    pub fn connect(device: &DeviceInfo, hash_on_device: bool) -> Result<Self, String> {
+        match device.state.as_str() {
+            "device" => {}
+            "unauthorized" => return Err(format!("Allow USB debugging on {} when it asks, then refresh", device.label())),
+            state => return Err(format!("{} is {}", device.label(), state)),
+        }
+        let mut connected = Self {
+            serial: device.serial.clone(),
+            model: device.model.clone(),
+            prefix: PathBuf::from(format!("adb://{}", device.serial)),
+            hash_on_device: false,
+            files: Mutex::new(HashMap::new()),
+        };
+        if run(&mut connected.shell("echo ok"))?.trim() != "ok" {
+            return Err(format!("{} does not answer", device.label()));
+        }
+        connected.hash_on_device = hash_on_device
+            && run(&mut connected.shell("echo | sha256sum")).is_ok_and(|output| parse_sha256(&output).is_some());
+        Ok(connected)
+    }
+
+    pub fn hashes_on_device(&self) -> bool {
+        self.hash_on_device
+    }
+
+    /// The scan root for `folder` on this device
+    pub fn root_for(&self, folder: &str) -> String {
+        format!("{}/{}", self.prefix.display(), folder.trim().trim_start_matches('/'))
+    }
+
+    fn shell(&self, command: &str) -> Command {
+        let mut adb = adb();
+        adb.args(["-s", &self.serial, "shell", command]);
+        adb
+    }
+
+    fn device_path(&self, path: &Path) -> io::Result<String> {
+        path.strip_prefix(&self.prefix)
+            .ok()
+            .and_then(|relative| relative.to_str())
+            .map(|relative| format!("/{}", relative))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} is not on this device", path.display())))
+    }
+
+    fn result_path(&self, device_path: &str) -> PathBuf {
+        self.prefix.join(device_path.trim_start_matches('/'))
+    }
+
+    fn stat(&self, path: &Path) -> io::Result<DeviceFile> {
+        let device_path = self.device_path(path)?;
+        let output = run(&mut self.shell(&format!("stat -c '%s %Y %n' {}", shell_quote(&device_path)))).map_err(io::Error::other)?;
+        parse_stat(output.trim()).map(|(file, _)| file).ok_or_else(|| io::Error::other(format!("Cannot read the size of {}", device_path)))
+    }
+
+    /// Size of the file at `path` on the device now
+    pub fn current_size(&self, path: &Path) -> Result<u64, String> {
+        self.stat(path).map(|file| file.size).map_err(|e| e.to_string())
+    }
+
+    /// Copy the file at `path` to `destination` on this PC
+    pub fn pull(&self, path: &Path, destination: &Path) -> Result<(), String> {
+        let device_path = self.device_path(path).map_err(|e| e.to_string())?;
+        run(adb().args(["-s", &self.serial, "pull", &device_path]).arg(destination)).map(|_| ())
+    }
+
+    pub fn delete(&self, path: &Path) -> Result<(), String> {
+        let device_path = self.device_path(path).map_err(|e| e.to_string())?;
+        run(&mut self.shell(&format!("rm -- {}", shell_quote(&device_path))))?;
+        self.files.lock().unwrap().remove(path);
+        Ok(())
+    }
+}
+
+fn parse_sha256(output: &str) -> Option<String> {
+    let hash = output.split_whitespace().next()?;
+    (hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())).then(|| hash.to_ascii_lowercase())
+}
+
+/// The output of `adb exec-out cat`; a failed read ends in an error rather than a short file
+struct DeviceReader {
+    child: Child,
+    stdout: ChildStdout,
+}
+
+impl Read for DeviceReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.stdout.read(buf)?;
+        if count == 0 && !buf.is_empty() && !self.child.wait()?.success() {
+            return Err(io::Error::other("adb stopped while reading the file"));
+        }
+        Ok(count)
+    }
+}
+
+impl Drop for DeviceReader {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl FileSource for AdbDevice {
+    fn list<'a>(&'a self, root: &Path, max_depth: Option<usize>, config: &'a ScanConfig) -> Box<dyn Iterator<Item = SourceEntry> + 'a> {
+        let Ok(device_root) = self.device_path(root) else {
+            return Box::new(std::iter::empty());
+        };
+        let depth = max_depth.map(|depth| format!(" -maxdepth {}", depth)).unwrap_or_default();
+        let command = format!("find {}{} -type f -exec stat -c '%s %Y %n' {{}} +", shell_quote(&device_root), depth);
+        // Folders the device does not let adb read make find fail, but the rest is still listed
+        let output = self.shell(&command).stdin(Stdio::null()).stderr(Stdio::null()).output().map(|output| output.stdout).unwrap_or_default();
+        let output = String::from_utf8_lossy(&output);
+
+        let mut entries = vec![SourceEntry { path: root.to_path_buf(), kind: EntryKind::Dir }];
+        let mut files = self.files.lock().unwrap();
+        for (file, device_path) in output.lines().filter_map(parse_stat) {
+            let relative = device_path.strip_prefix(device_root.as_str()).unwrap_or(device_path);
+            if !config.include_hidden && relative.split('/').any(|name| name.starts_with('.')) {
+                continue;
+            }
+            let path = self.result_path(device_path);
+            files.insert(path.clone(), file);
+            entries.push(SourceEntry { path, kind: EntryKind::File });
+        }
+        Box::new(entries.into_iter())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<SourceMetadata> {
+        let listed = self.files.lock().unwrap().get(path).copied();
+        let file = match listed {
+            Some(file) => file,
+            None => self.stat(path)?,
+        };
+        Ok(SourceMetadata { size: file.size, modified: file.modified, ..SourceMetadata::default() })
+    }
+
+    fn open(&self, path: &Path) -> io::Result<(Box<dyn Read + Send>, u64)> {
+        let size = self.metadata(path)?.size;
+        let device_path = self.device_path(path)?;
+        let mut child = adb()
+            .args(["-s", &self.serial, "exec-out", &format!("cat {}", shell_quote(&device_path))])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdout = child.stdout.take().ok_or_else(|| io::Error::other("adb gave no output"))?;
+        Ok((Box::new(DeviceReader { child, stdout }), size))
+    }
+
+    fn max_readers(&self) -> Option<usize> {
+        Some(DEVICE_READERS)
+    }
+
+    fn stored_sha256(&self, path: &Path) -> Option<io::Result<String>> {
+        if !self.hash_on_device {
+            return None;
+        }
+        Some(self.device_path(path).and_then(|device_path| {
+            let output = run(&mut self.shell(&format!("sha256sum {}", shell_quote(&device_path)))).map_err(io::Error::other)?;
+            parse_sha256(&output).ok_or_else(|| io::Error::other(format!("Cannot hash {} on the device", device_path)))
+        }))
+    }
+}
+
+impl RemoteSource for AdbDevice {
+    fn prefix(&self) -> &Path {
+        &self.prefix
+    }
+
+    fn label(&self) -> String {
+        let hashing = if self.hash_on_device { "hashed on the device" } else { "hashed on this PC" };
+        if self.model.is_empty() {
+            format!("{} ({})", self.prefix.display(), hashing)
+        } else {
+            format!("{} – {} ({})", self.prefix.display(), self.model, hashing)
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::fs::Metadata;
+use std::path::Path;
+use crate::scanner::{FileInfo, SelectionStrategy};
+
+/// Permission bits (including setuid, setgid and sticky) where the platform has them
+#[cfg(unix)]
+pub fn permission_bits(metadata: &Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+pub fn permission_bits(_metadata: &Metadata) -> Option<u32> {
+    None
+}
+
+/// Names of the extended attributes set on `path`, sorted; empty where they
+/// cannot be read or the platform has none
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub fn xattr_names(path: &Path) -> Vec<String> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return Vec::new();
+    };
+    let list = |buf: *mut libc::c_char, size: usize| unsafe {
+        #[cfg(target_os = "linux")]
+        return libc::listxattr(c_path.as_ptr(), buf, size);
+        #[cfg(target_os = "macos")]
+        return libc::listxattr(c_path.as_ptr(), buf, size, 0);
+    };
+    // The list can grow between asking its size and reading it; one retry is enough in practice
+    for _ in 0..2 {
+        let size = list(std::ptr::null_mut(), 0);
+        if size <= 0 {
+            return Vec::new();
+        }
+        let mut buf = vec![0u8; size as usize];
+        let read = list(buf.as_mut_ptr().cast(), buf.len());
+        if read < 0 {
+            continue;
+        }
+        buf.truncate(read as usize);
+        let mut names: Vec<String> = buf
+            .split(|&b| b == 0)
+            .filter(|name| !name.is_empty())
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+            .collect();
+        names.sort();
+        return names;
+    }
+    Vec::new()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn xattr_names(_path: &Path) -> Vec<String> {
+    Vec::new()
+}
+
+/// "rwxr-xr-x", with s/t in the execute places for setuid, setgid and sticky
+pub fn format_mode(mode: u32) -> String {
+    let mut text = String::with_capacity(9);
+    for (shift, special, special_char) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = (mode >> shift) & 0o7;
+        text.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        text.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        text.push(match (bits & 0o1 != 0, mode & special != 0) {
+            (true, true) => special_char,
+            (false, true) => special_char.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    text
+}
+
+fn is_executable(file: &FileInfo) -> bool {
+    file.mode.is_some_and(|mode| mode & 0o111 != 0)
+}
+
+/// Which non-content attributes differ between the copies of a group, e.g.
+/// ["executable bit", "extended attributes"]; empty when they all agree
+pub fn metadata_differences(files: &[FileInfo]) -> Vec<&'static str> {
+    let Some(first) = files.first() else {
+        return Vec::new();
+    };
+    let mut differences = Vec::new();
+    if files.iter().any(|file| is_executable(file) != is_executable(first)) {
+        differences.push("executable bit");
+    } else if files.iter().any(|file| file.mode != first.mode) {
+        differences.push("permissions");
+    }
+    if files.iter().any(|file| file.xattrs != first.xattrs) {
+        differences.push("extended attributes");
+    }
+    differences
+}
+
+/// The file's attributes in a few characters, e.g. "rwxr-xr-x +2 xattr"
+pub fn metadata_summary(file: &FileInfo) -> String {
+    let mut summary = file.mode.map(format_mode).unwrap_or_else(|| "?".to_string());
+    if !file.xattrs.is_empty() {
+        summary.push_str(&format!(" +{} xattr", file.xattrs.len()));
+    }
+    summary
+}
+
+// Ranking key; higher carries more: extended attributes first, then the
+// executable bit, then permission bits set
+fn richness(file: &FileInfo) -> (usize, bool, u32) {
+    (file.xattrs.len(), is_executable(file), file.mode.map_or(0, u32::count_ones))
+}
+
+/// Runs another strategy, then swaps each kept copy for an unchecked one
+/// with richer metadata, so an executable bit or extended attributes that
+/// only one copy has are not lost. When several copies are kept, a swap
+/// stays within the kept copy's folder so per-folder choices hold.
+pub struct PreferRichMetadata<'a>(pub &'a dyn SelectionStrategy);
+
+impl SelectionStrategy for PreferRichMetadata<'_> {
+    fn select(&self, files: &[FileInfo]) -> Vec<bool> {
+        let mut selected = self.0.select(files);
+        if metadata_differences(files).is_empty() {
+            return selected;
+        }
+        let several = selected.iter().filter(|&&keep| keep).count() > 1;
+        let same_folder = |a: usize, b: usize| files[a].path.dir_id() == files[b].path.dir_id();
+        for kept in 0..files.len() {
+            if !selected[kept] {
+                continue;
+            }
+            let richer = (0..files.len())
+                .filter(|&idx| !selected[idx] && (!several || same_folder(kept, idx)))
+                .filter(|&idx| richness(&files[idx]) > richness(&files[kept]))
+                .max_by_key(|&idx| richness(&files[idx]));
+            if let Some(idx) = richer {
+                selected[kept] = false;
+                selected[idx] = true;
+            }
+        }
+        selected
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use crate::emptydirs::remove_emptied_parents;
+use crate::merge::{move_file, plan_merge, MergePlan};
+use crate::scanner::{filtered_walk, hash_file, ScanConfig, ScanError, ScanProgress};
+
+/// Sibling folders whose names differ only by case, e.g. `Photos` and
+/// `photos`, as left behind by syncs between case-insensitive and
+/// case-sensitive filesystems
+#[derive(Clone, Debug)]
+pub struct CaseVariants {
+    pub parent: PathBuf,
+    /// The folders, each with the number of files under it
+    pub dirs: Vec<(PathBuf, usize)>,
+}
+
+impl CaseVariants {
+    /// Index of the folder holding the most files, merged into by default
+    pub fn fullest(&self) -> usize {
+        self.dirs.iter().enumerate().max_by_key(|(_, (_, files))| *files).map_or(0, |(idx, _)| idx)
+    }
+}
+
+fn count_files(dir: &Path, config: &ScanConfig) -> usize {
+    filtered_walk(WalkDir::new(dir), config).filter(|entry| entry.file_type().is_file()).count()
+}
+
+/// Folders under `roots` with a sibling of the same name in another case.
+/// On a case-insensitive filesystem there are none.
+pub fn find_case_variants(roots: &[PathBuf], config: &ScanConfig) -> Vec<CaseVariants> {
+    let mut by_name: HashMap<(PathBuf, String), Vec<PathBuf>> = HashMap::new();
+    for root in roots {
+        for entry in filtered_walk(WalkDir::new(root).min_depth(1), config) {
+            if !entry.file_type().is_dir() {
+                continue;
+            }
+            let Some(parent) = entry.path().parent() else {
+                continue;
+            };
+            let folded = entry.file_name().to_string_lossy().to_lowercase();
+            by_name.entry((parent.to_path_buf(), folded)).or_default().push(entry.path().to_path_buf());
+        }
+    }
+
+    let mut found: Vec<CaseVariants> = by_name
+        .into_iter()
+        .filter(|(_, dirs)| dirs.len() > 1)
+        .map(|((parent, _), mut dirs)| {
+            // Overlapping roots list a folder once per walk
+            dirs.sort();
+            dirs.dedup();
+            let dirs = dirs.into_iter().map(|dir| {
+                let files = count_files(&dir, config);
+                (dir, files)
+            });
+            CaseVariants { parent, dirs: dirs.collect() }
+        })
+        .filter(|variants| variants.dirs.len() > 1)
+        .collect();
+    found.sort_by(|a, b| a.dirs[0].0.cmp(&b.dirs[0].0));
+    found
+}
+
+/// `relative` below `base`, with each part spelled as an existing entry that
+/// matches it ignoring case, so merging `photos/Trip` into `Photos` fills
+/// `Photos/trip` when that exists rather than creating a new case variant
+fn existing_spelling(base: &Path, relative: &Path) -> PathBuf {
+    let mut path = base.to_path_buf();
+    for part in relative.components() {
+        let wanted = part.as_os_str().to_string_lossy().to_lowercase();
+        let existing = fs::read_dir(&path).ok().and_then(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name())
+                .find(|name| name.to_string_lossy().to_lowercase() == wanted)
+        });
+        path.push(existing.unwrap_or_else(|| part.as_os_str().to_os_string()));
+    }
+    path
+}
+
+/// Merging one set of case variants: a merge plan from every other folder into the kept one
+#[derive(Clone, Debug)]
+pub struct CaseMergePlan {
+    pub parent: PathBuf,
+    pub target: PathBuf,
+    pub plans: Vec<MergePlan>,
+}
+
+impl CaseMergePlan {
+    pub fn to_move(&self) -> usize {
+        self.plans.iter().map(|plan| plan.to_copy.len()).sum()
+    }
+
+    pub fn duplicates(&self) -> usize {
+        self.plans.iter().map(|plan| plan.skipped.len()).sum()
+    }
+
+    pub fn conflicts(&self) -> usize {
+        self.plans.iter().map(|plan| plan.conflicts.len()).sum()
+    }
+}
+
+/// Plan merging every folder of `variants` into the one at `keep`: files
+/// found nowhere in it are moved over, files whose content it already holds
+/// are removed, and files whose name is taken by different content stay
+pub fn plan_case_merge<F>(variants: &CaseVariants, keep: usize, config: &ScanConfig, progress_callback: F) -> Result<CaseMergePlan, ScanError>
+where
+    F: Fn(ScanProgress) + Send + Sync,
+{
+    let target = variants.dirs[keep].0.clone();
+    let mut plans = Vec::new();
+    for (idx, (source, _)) in variants.dirs.iter().enumerate() {
+        if idx == keep {
+            continue;
+        }
+        let mut plan = plan_merge(source, &target, config, &progress_callback)?;
+        let mut to_move = Vec::new();
+        for mut copy in std::mem::take(&mut plan.to_copy) {
+            let relative = copy.from.strip_prefix(source).unwrap_or(&copy.from).to_path_buf();
+            copy.to = existing_spelling(&target, &relative);
+            if copy.to.exists() {
+                plan.conflicts.push(copy);
+            } else {
+                to_move.push(copy);
+            }
+        }
+        plan.to_copy = to_move;
+        plans.push(plan);
+    }
+    Ok(CaseMergePlan { parent: variants.parent.clone(), target, plans })
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CaseMergeReport {
+    pub moved: usize,
+    pub removed: usize,
+    pub removed_dirs: Vec<PathBuf>,
+    pub errors: Vec<String>,
+}
+
+/// Carry out `plan`. A duplicate is only removed once its content is read
+/// again and still matches the copy in the kept folder; folders the merge
+/// empties are removed, up to and including the merged folder itself.
+pub fn execute_case_merge(plan: &CaseMergePlan, config: &ScanConfig) -> CaseMergeReport {
+    let mut report = CaseMergeReport::default();
+    let mut emptied = Vec::new();
+    for merge in &plan.plans {
+        for copy in &merge.to_copy {
+            // Checked again: rename replaces an existing file without asking
+            if copy.to.exists() {
+                report.errors.push(format!("Not moved, {} appeared meanwhile", copy.to.display()));
+                continue;
+            }
+            match move_file(&copy.from, &copy.to) {
+                Ok(()) => {
+                    report.moved += 1;
+                    emptied.push(copy.from.clone());
+                }
+                Err(e) => report.errors.push(format!("Failed to move {}: {}", copy.from.display(), e)),
+            }
+        }
+        for skip in &merge.skipped {
+            let same = match (hash_file(&skip.source, config), hash_file(&skip.existing, config)) {
+                (Ok(a), Ok(b)) => a == b,
+                _ => false,
+            };
+            if !same {
+                report.errors.push(format!("Kept {}: no longer matches {}", skip.source.display(), skip.existing.display()));
+                continue;
+            }
+            match fs::remove_file(&skip.source) {
+                Ok(()) => {
+                    report.removed += 1;
+                    emptied.push(skip.source.clone());
+                }
+                Err(e) => report.errors.push(format!("Failed to remove {}: {}", skip.source.display(), e)),
+            }
+        }
+    }
+    let roots = [plan.parent.clone()];
+    for file in &emptied {
+        report.removed_dirs.extend(remove_emptied_parents(file, &roots));
+    }
+    report
+}
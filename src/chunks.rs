@@ -0,0 +1,126 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use fastcdc::v2020::StreamCDC;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+use crate::scanner::{build_thread_pool, filtered_walk, HashGroup, ScanConfig, ScanError};
+
+// Content-defined chunk sizes; boundaries follow the content, so an insertion
+// near the start of a file only changes the chunks around it
+const MIN_CHUNK: u32 = 16 * 1024;
+const AVG_CHUNK: u32 = 64 * 1024;
+const MAX_CHUNK: u32 = 256 * 1024;
+// Chunks shared by this many files (headers, padding) say little about any pair
+const MAX_FILES_PER_CHUNK: usize = 32;
+
+/// Two large files sharing part of their content, found by comparing chunks
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChunkSimilarity {
+    pub a: PathBuf,
+    pub b: PathBuf,
+    pub size_a: u64,
+    pub size_b: u64,
+    /// Bytes in chunks found in both files
+    pub shared_bytes: u64,
+}
+
+impl ChunkSimilarity {
+    /// Share of the larger file that also appears in the other one
+    pub fn identical_fraction(&self) -> f64 {
+        self.shared_bytes as f64 / self.size_a.max(self.size_b).max(1) as f64
+    }
+}
+
+// Chunk digest -> (occurrences, chunk length)
+type ChunkCounts = HashMap<u64, (u32, u32)>;
+
+fn chunk_file(path: &Path) -> io::Result<ChunkCounts> {
+    let mut chunks = ChunkCounts::new();
+    for chunk in StreamCDC::new(BufReader::new(File::open(path)?), MIN_CHUNK, AVG_CHUNK, MAX_CHUNK) {
+        let chunk = chunk.map_err(|e| io::Error::other(e.to_string()))?;
+        // Zero-filled regions (preallocated images, sparse files) would make unrelated files look alike
+        if chunk.data.iter().all(|&b| b == 0) {
+            continue;
+        }
+        let digest = blake3::hash(&chunk.data);
+        let key = u64::from_le_bytes(digest.as_bytes()[..8].try_into().unwrap());
+        chunks.entry(key).or_insert((0, chunk.length as u32)).0 += 1;
+    }
+    Ok(chunks)
+}
+
+fn shared_bytes(a: &ChunkCounts, b: &ChunkCounts) -> u64 {
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    small
+        .iter()
+        .filter_map(|(key, &(count, len))| large.get(key).map(|&(other, _)| count.min(other) as u64 * len as u64))
+        .sum()
+}
+
+/// Chunk every file of at least `chunk_similarity_min_size` under `roots` and
+/// report pairs sharing at least `chunk_similarity_threshold` of their content.
+/// Extra copies from the exact groups are left out; they are already listed there.
+pub fn find_chunk_similar_files(roots: &[String], exact: &[HashGroup], config: &ScanConfig) -> Result<Vec<ChunkSimilarity>, ScanError> {
+    let mut skipped: HashSet<PathBuf> = exact
+        .iter()
+        .flat_map(|group| group.files.iter().skip(1).map(|f| f.path.to_path_buf()))
+        .collect();
+    // Adding each file to `skipped` keeps nested roots from listing it twice
+    let files: Vec<(PathBuf, u64)> = roots
+        .iter()
+        .flat_map(|root| filtered_walk(WalkDir::new(root), config))
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let size = e.metadata().ok()?.len();
+            (size >= config.chunk_similarity_min_size).then(|| (e.into_path(), size))
+        })
+        .filter(|(path, _)| skipped.insert(path.clone()))
+        .collect();
+
+    // Unreadable files simply take no part in the comparison
+    let pool = build_thread_pool(config)?;
+    let chunked: Vec<(PathBuf, u64, ChunkCounts)> = pool.install(|| {
+        files
+            .into_par_iter()
+            .filter_map(|(path, size)| chunk_file(&path).ok().map(|chunks| (path, size, chunks)))
+            .collect()
+    });
+
+    let mut owners: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (idx, (_, _, chunks)) in chunked.iter().enumerate() {
+        for key in chunks.keys() {
+            owners.entry(*key).or_default().push(idx);
+        }
+    }
+    let mut pairs = HashSet::new();
+    for files in owners.values().filter(|files| files.len() > 1 && files.len() <= MAX_FILES_PER_CHUNK) {
+        for (i, &a) in files.iter().enumerate() {
+            for &b in &files[i + 1..] {
+                pairs.insert((a, b));
+            }
+        }
+    }
+
+    let mut similar: Vec<ChunkSimilarity> = pool.install(|| {
+        pairs
+            .into_par_iter()
+            .filter_map(|(a, b)| {
+                let (path_a, size_a, chunks_a) = &chunked[a];
+                let (path_b, size_b, chunks_b) = &chunked[b];
+                let pair = ChunkSimilarity {
+                    a: path_a.clone(),
+                    b: path_b.clone(),
+                    size_a: *size_a,
+                    size_b: *size_b,
+                    shared_bytes: shared_bytes(chunks_a, chunks_b),
+                };
+                (pair.identical_fraction() >= config.chunk_similarity_threshold).then_some(pair)
+            })
+            .collect()
+    });
+    similar.sort_by(|x, y| y.identical_fraction().total_cmp(&x.identical_fraction()).then_with(|| x.a.cmp(&y.a)));
+    Ok(similar)
+}
@@ -0,0 +1,199 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use crate::filetype::FileCategory;
+use crate::postcheck::{DeletedFile, SurvivingFile};
+use crate::originals::{has_copy_name, in_backup_dir, KeepLikelyOriginalStrategy};
+use crate::scanner::{FileInfo, KeepNewestStrategy, SelectionStrategy};
+use crate::similarity::is_source_file;
+use crate::source::is_remote;
+
+const PHOTO_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "webp", "heic", "heif", "avif", "tif", "tiff", "bmp",
+    "raw", "dng", "cr2", "cr3", "nef", "arw", "orf", "rw2",
+];
+const DOCUMENT_EXTENSIONS: &[&str] = &[
+    "pdf", "doc", "docx", "odt", "rtf", "xls", "xlsx", "ods", "csv", "ppt", "pptx", "odp", "txt", "md", "pages", "numbers", "key",
+];
+// Folder names where photo apps and websites keep reduced or regenerated copies
+const DERIVED_DIR_NAMES: &[&str] = &[
+    "thumbnails", ".thumbnails", "thumbs", "cache", ".cache", "previews", "resized", "export", "exports", "web", "small",
+];
+
+/// Kinds of duplicates the recommended cleanup keeps a different copy of
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PlanKind {
+    Photo,
+    Document,
+    Code,
+    Other,
+}
+
+impl PlanKind {
+    pub const ALL: [PlanKind; 4] = [PlanKind::Photo, PlanKind::Document, PlanKind::Code, PlanKind::Other];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PlanKind::Photo => "Photos",
+            PlanKind::Document => "Documents",
+            PlanKind::Code => "Source code",
+            PlanKind::Other => "Everything else",
+        }
+    }
+
+    /// The copy kept, as shown in the plan
+    pub fn rule(self) -> &'static str {
+        match self {
+            PlanKind::Photo => "keep the highest-quality copy",
+            PlanKind::Document => "keep the newest copy",
+            PlanKind::Code => "keep the copy inside a repository",
+            PlanKind::Other => "keep the likely original",
+        }
+    }
+
+    pub fn of(files: &[FileInfo]) -> Self {
+        let Some(first) = files.first() else {
+            return PlanKind::Other;
+        };
+        match first.detected_type.as_deref().map(FileCategory::from_mime) {
+            Some(FileCategory::Image) => return PlanKind::Photo,
+            Some(FileCategory::Document) => return PlanKind::Document,
+            _ => {}
+        }
+        let path = first.path.to_path_buf();
+        let ext = path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase).unwrap_or_default();
+        if PHOTO_EXTENSIONS.contains(&ext.as_str()) {
+            PlanKind::Photo
+        } else if DOCUMENT_EXTENSIONS.contains(&ext.as_str()) {
+            PlanKind::Document
+        } else if is_source_file(&path) {
+            PlanKind::Code
+        } else {
+            PlanKind::Other
+        }
+    }
+
+    fn strategy(self) -> &'static dyn SelectionStrategy {
+        match self {
+            PlanKind::Photo => &KeepBestPhotoStrategy,
+            PlanKind::Document => &KeepNewestStrategy,
+            PlanKind::Code => &KeepInRepositoryStrategy,
+            PlanKind::Other => &KeepLikelyOriginalStrategy,
+        }
+    }
+}
+
+fn keep_only(len: usize, idx: Option<usize>) -> Vec<bool> {
+    let mut selected = vec![false; len];
+    if let Some(idx) = idx {
+        selected[idx] = true;
+    }
+    selected
+}
+
+fn in_derived_dir(path: &Path) -> bool {
+    path.parent().is_some_and(|dir| {
+        dir.components().any(|c| DERIVED_DIR_NAMES.contains(&c.as_os_str().to_string_lossy().to_lowercase().as_str()))
+    })
+}
+
+/// Copies of a photo share their pixels, so quality is about which copy
+/// carries them best: one in the library rather than a thumbnail or export
+/// folder, fully allocated on disk, under its camera name and with the
+/// earliest date, which editing tools and syncs keep.
+pub struct KeepBestPhotoStrategy;
+
+impl SelectionStrategy for KeepBestPhotoStrategy {
+    fn select(&self, files: &[FileInfo]) -> Vec<bool> {
+        let best = files.iter().enumerate().min_by_key(|(_, file)| {
+            let path = file.path.to_path_buf();
+            let dated: Option<SystemTime> = file.created_time.or(file.modified_time);
+            // Undated copies sort last rather than first
+            (in_derived_dir(&path), file.is_sparse(), has_copy_name(&path), in_backup_dir(&path), dated.is_none(), dated, path.as_os_str().len())
+        });
+        keep_only(files.len(), best.map(|(idx, _)| idx))
+    }
+}
+
+/// Whether `path` lies in a Git checkout, so the copy there is the one under version control
+fn in_repository(path: &Path) -> bool {
+    !is_remote(path) && path.ancestors().skip(1).any(|dir| dir.join(".git").exists())
+}
+
+/// Keeps the copy that is part of a repository, the shortest path among
+/// several; groups with no copy in one keep the likely original.
+pub struct KeepInRepositoryStrategy;
+
+impl SelectionStrategy for KeepInRepositoryStrategy {
+    fn select(&self, files: &[FileInfo]) -> Vec<bool> {
+        let tracked = files
+            .iter()
+            .enumerate()
+            .filter(|(_, file)| in_repository(&file.path.to_path_buf()))
+            .min_by_key(|(_, file)| file.path.to_path_buf().as_os_str().len());
+        match tracked {
+            Some((idx, _)) => keep_only(files.len(), Some(idx)),
+            None => KeepLikelyOriginalStrategy.select(files),
+        }
+    }
+}
+
+/// The recommended plan: a rule per kind of file, applied group by group
+pub struct RecommendedStrategy;
+
+impl SelectionStrategy for RecommendedStrategy {
+    fn select(&self, files: &[FileInfo]) -> Vec<bool> {
+        PlanKind::of(files).strategy().select(files)
+    }
+}
+
+/// Groups, files to delete and bytes freed by one kind in a plan
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlanTotals {
+    pub groups: usize,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// What deleting the unchecked files of each group frees, per kind of file
+pub fn plan_totals<'a>(groups: impl IntoIterator<Item = (&'a [FileInfo], &'a [bool])>) -> Vec<(PlanKind, PlanTotals)> {
+    let mut totals: Vec<(PlanKind, PlanTotals)> = PlanKind::ALL.iter().map(|&kind| (kind, PlanTotals::default())).collect();
+    for (files, selected) in groups {
+        let kind = PlanKind::of(files);
+        let Some((_, entry)) = totals.iter_mut().find(|(k, _)| *k == kind) else {
+            continue;
+        };
+        let unchecked: Vec<&FileInfo> = files.iter().zip(selected).filter(|(_, &keep)| !keep).map(|(file, _)| file).collect();
+        if unchecked.is_empty() {
+            continue;
+        }
+        entry.groups += 1;
+        entry.files += unchecked.len();
+        entry.bytes += unchecked.iter().map(|file| file.disk_size()).sum::<u64>();
+    }
+    totals.retain(|(_, entry)| entry.groups > 0);
+    totals
+}
+
+/// A confirmed cleanup, carried out a few groups per frame so its progress can be shown
+#[derive(Default)]
+pub struct CleanupRun {
+    /// First file of each group still to clean up; it finds the group again as others leave the results
+    pub pending: VecDeque<PathBuf>,
+    pub total: usize,
+    pub done: usize,
+    pub deleted: Vec<DeletedFile>,
+    pub survivors: Vec<SurvivingFile>,
+    pub errors: Vec<String>,
+    /// Groups left alone because they changed or no longer keep a copy
+    pub skipped: usize,
+    /// Stop after the current group
+    pub stopped: bool,
+    pub finished: bool,
+}
+
+impl CleanupRun {
+    pub fn freed(&self) -> u64 {
+        self.deleted.iter().map(|file| file.size).sum()
+    }
+}
@@ -0,0 +1,108 @@
+use std::io::{self, BufWriter, Read, Write};
+use crate::scanner::{parse_file_list, scan_file_list, ScanConfig};
+
+const USAGE: &str = "\
+Usage: dupe-finder-gui --pipe [OPTIONS]
+
+Read paths from stdin (NUL-separated, e.g. `find -print0` / `fd -0`, or one per
+line) and write each duplicate group to stdout as one line of JSON.
+
+Options:
+  --skip-hidden      Ignore paths with a component starting with '.'
+  --min-size BYTES   Ignore files smaller than BYTES (default 1)
+  --threads N        Limit hashing threads
+  --dual-hash        Also compute SHA-512 and require both digests to match
+  --trust-sidecars   Take SHA-256 from fresh .sha256 sidecars and BagIt manifests
+  --unique           Write the files that have no other copy instead of the
+                     duplicate groups, one JSON object per line
+  --separate-projects
+                     Never group copies in two distinct projects (.git,
+                     Cargo.toml or package.json folders)
+  -h, --help         Show this help";
+
+fn parse_args(args: &[String]) -> Result<ScanConfig, String> {
+    // Paths were already selected upstream, so hidden files are kept by default
+    let mut config = ScanConfig { include_hidden: true, detect_file_types: false, ..ScanConfig::default() };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--skip-hidden" => config.include_hidden = false,
+            "--dual-hash" => config.dual_hash = true,
+            "--trust-sidecars" => config.trust_sidecars = true,
+            "--unique" => config.report_unique = true,
+            "--separate-projects" => config.separate_projects = true,
+            "--min-size" => {
+                let value = args.next().ok_or("--min-size needs a value")?;
+                config.min_file_size = value.parse().map_err(|_| format!("Invalid size: {}", value))?;
+            }
+            "--threads" => {
+                let value = args.next().ok_or("--threads needs a value")?;
+                config.max_threads = Some(value.parse().map_err(|_| format!("Invalid thread count: {}", value))?);
+            }
+            other => return Err(format!("Unknown option: {}", other)),
+        }
+    }
+    Ok(config)
+}
+
+/// Headless pipe mode; returns the process exit code
+pub fn run_pipe(args: &[String]) -> i32 {
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return 0;
+    }
+    let config = match parse_args(args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+
+    let mut input = Vec::new();
+    if let Err(e) = io::stdin().read_to_end(&mut input) {
+        eprintln!("Failed to read stdin: {}", e);
+        return 1;
+    }
+    let base = std::env::current_dir().unwrap_or_default();
+    let paths = parse_file_list(&input, &base);
+
+    let unique = config.report_unique;
+    let outcome = match scan_file_list(&paths, |_| {}, config) {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            eprintln!("Scan error: {}", e);
+            return 1;
+        }
+    };
+
+    for path in &outcome.sidecar_mismatches {
+        eprintln!("Warning: {} does not match its recorded checksum", path.display());
+    }
+    let mut out = BufWriter::new(io::stdout().lock());
+    let lines: Vec<serde_json::Result<String>> = if unique {
+        outcome.unique_files.iter().map(serde_json::to_string).collect()
+    } else {
+        outcome.groups.iter().map(serde_json::to_string).collect()
+    };
+    for line in lines {
+        let written = line
+            .map_err(io::Error::other)
+            .and_then(|line| writeln!(out, "{}", line));
+        if let Err(e) = written {
+            // A closed pipe (e.g. `| head`) is not an error worth reporting
+            if e.kind() != io::ErrorKind::BrokenPipe {
+                eprintln!("Failed to write output: {}", e);
+                return 1;
+            }
+            return 0;
+        }
+    }
+    match out.flush() {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+            eprintln!("Failed to write output: {}", e);
+            1
+        }
+        _ => 0,
+    }
+}
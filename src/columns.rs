@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::settings::{load_json, save_json};
+
+const LAYOUT_FILE: &str = "file-layout.json";
+
+/// Information a file row can show in split-column mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileColumn {
+    Directory,
+    Name,
+    Extension,
+    Size,
+    Modified,
+    Created,
+    Owner,
+    DetectedType,
+    HashPrefix,
+    Critical,
+}
+
+impl FileColumn {
+    pub const ALL: [FileColumn; 10] = [
+        FileColumn::Directory,
+        FileColumn::Name,
+        FileColumn::Extension,
+        FileColumn::Size,
+        FileColumn::Modified,
+        FileColumn::Created,
+        FileColumn::Owner,
+        FileColumn::DetectedType,
+        FileColumn::HashPrefix,
+        FileColumn::Critical,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FileColumn::Directory => "Directory",
+            FileColumn::Name => "Name",
+            FileColumn::Extension => "Extension",
+            FileColumn::Size => "Size",
+            FileColumn::Modified => "Modified",
+            FileColumn::Created => "Created",
+            FileColumn::Owner => "Owner",
+            FileColumn::DetectedType => "Detected type",
+            FileColumn::HashPrefix => "Hash prefix",
+            FileColumn::Critical => "Critical flag",
+        }
+    }
+
+    fn default_width(self) -> f32 {
+        match self {
+            FileColumn::Directory => 280.0,
+            FileColumn::Name => 220.0,
+            FileColumn::Modified | FileColumn::Created => 150.0,
+            FileColumn::DetectedType => 140.0,
+            FileColumn::HashPrefix => 110.0,
+            FileColumn::Extension | FileColumn::Size | FileColumn::Owner | FileColumn::Critical => 80.0,
+        }
+    }
+}
+
+/// One column of the split layout, in display order
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ColumnSpec {
+    pub column: FileColumn,
+    pub visible: bool,
+    pub width: f32,
+}
+
+/// How the file rows inside a group are laid out; kept between runs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileLayout {
+    /// Show the columns below in aligned cells instead of one full path
+    pub split_columns: bool,
+    /// List a group's files under one heading per containing directory
+    pub group_by_directory: bool,
+    /// Show paths relative to their scan root, which the group header names once
+    #[serde(default)]
+    pub relative_paths: bool,
+    #[serde(default = "default_columns")]
+    pub columns: Vec<ColumnSpec>,
+}
+
+fn default_columns() -> Vec<ColumnSpec> {
+    FileColumn::ALL
+        .into_iter()
+        .map(|column| ColumnSpec {
+            column,
+            visible: matches!(column, FileColumn::Directory | FileColumn::Name | FileColumn::Size | FileColumn::Modified),
+            width: column.default_width(),
+        })
+        .collect()
+}
+
+impl Default for FileLayout {
+    fn default() -> Self {
+        Self { split_columns: false, group_by_directory: false, relative_paths: false, columns: default_columns() }
+    }
+}
+
+impl FileLayout {
+    /// The layout saved by the last run, or the default one
+    pub fn load() -> Self {
+        let mut layout: Self = load_json(LAYOUT_FILE).unwrap_or_default();
+        // Columns added since the layout was saved go at the end, hidden
+        for column in FileColumn::ALL {
+            if !layout.columns.iter().any(|spec| spec.column == column) {
+                layout.columns.push(ColumnSpec { column, visible: false, width: column.default_width() });
+            }
+        }
+        layout
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        save_json(LAYOUT_FILE, self)
+    }
+
+    pub fn visible_columns(&self) -> impl Iterator<Item = &ColumnSpec> {
+        self.columns.iter().filter(|spec| spec.visible)
+    }
+}
+
+/// `path` relative to the outermost of `roots` holding it, with that root's
+/// index; None for paths outside every root, e.g. from an imported file list
+pub fn relative_to_root(path: &Path, roots: &[PathBuf]) -> Option<(usize, PathBuf)> {
+    let (idx, root) = roots
+        .iter()
+        .enumerate()
+        .filter(|(_, root)| path.starts_with(root))
+        .min_by_key(|(_, root)| root.components().count())?;
+    Some((idx, path.strip_prefix(root).ok()?.to_path_buf()))
+}
+
+/// Folder part of a relative path, "." for the root itself
+pub fn relative_dir(relative: &Path) -> String {
+    match relative.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        Some(dir) => dir.display().to_string(),
+        None => ".".to_string(),
+    }
+}
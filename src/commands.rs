@@ -0,0 +1,268 @@
+use eframe::egui;
+
+/// Actions reachable from the command palette
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    StartScan,
+    ScanFromFileList,
+    ScanSelectedFiles,
+    PauseScan,
+    CancelScan,
+    AddRoot,
+    AddSftpRoot,
+    AddS3Bucket,
+    AddGoogleDrive,
+    AddAndroidDevice,
+    AddToQueue,
+    RunQueue,
+    KeepNewest,
+    KeepOldest,
+    KeepLikelyOriginals,
+    KeepOnePerDirectory,
+    UncheckJunkCopies,
+    DeleteUnchecked,
+    CleanUpDuplicates,
+    ConsolidateToStore,
+    ExportResults,
+    ImportResults,
+    ImportExternalResults,
+    ExportChecksums,
+    ExportForOtherTools,
+    ComplianceReport,
+    SimulationReport,
+    ExportDeletionPaths,
+    ValidateResults,
+    RescanAffected,
+    MergeFolders,
+    FindCaseVariants,
+    CompareFolders,
+    CheckCorruption,
+    MatchAgainstManifest,
+    VerifyChecksums,
+    TogglePreviewMode,
+    ToggleHiddenFiles,
+    ToggleHighContrast,
+    ToggleUiMode,
+    OpenSensitivePatterns,
+    OpenJunkCopyPatterns,
+    StartTour,
+    CheckForUpdates,
+    OpenInsights,
+}
+
+impl Command {
+    pub const ALL: [Command; 45] = [
+        Command::StartScan,
+        Command::ScanFromFileList,
+        Command::ScanSelectedFiles,
+        Command::PauseScan,
+        Command::CancelScan,
+        Command::AddRoot,
+        Command::AddSftpRoot,
+        Command::AddS3Bucket,
+        Command::AddGoogleDrive,
+        Command::AddAndroidDevice,
+        Command::AddToQueue,
+        Command::RunQueue,
+        Command::KeepNewest,
+        Command::KeepOldest,
+        Command::KeepLikelyOriginals,
+        Command::KeepOnePerDirectory,
+        Command::UncheckJunkCopies,
+        Command::DeleteUnchecked,
+        Command::CleanUpDuplicates,
+        Command::ConsolidateToStore,
+        Command::ExportResults,
+        Command::ImportResults,
+        Command::ImportExternalResults,
+        Command::ExportChecksums,
+        Command::ExportForOtherTools,
+        Command::ComplianceReport,
+        Command::SimulationReport,
+        Command::ExportDeletionPaths,
+        Command::ValidateResults,
+        Command::RescanAffected,
+        Command::MergeFolders,
+        Command::FindCaseVariants,
+        Command::CompareFolders,
+        Command::CheckCorruption,
+        Command::MatchAgainstManifest,
+        Command::VerifyChecksums,
+        Command::TogglePreviewMode,
+        Command::ToggleHiddenFiles,
+        Command::ToggleHighContrast,
+        Command::ToggleUiMode,
+        Command::OpenSensitivePatterns,
+        Command::OpenJunkCopyPatterns,
+        Command::StartTour,
+        Command::CheckForUpdates,
+        Command::OpenInsights,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Command::StartScan => "Scan: Start scan",
+            Command::ScanFromFileList => "Scan: Scan from file list…",
+            Command::ScanSelectedFiles => "Scan: Scan selected files…",
+            Command::PauseScan => "Scan: Pause / resume",
+            Command::CancelScan => "Scan: Cancel",
+            Command::AddRoot => "Scan: Add root folder…",
+            Command::AddSftpRoot => "Scan: Add SFTP root…",
+            Command::AddS3Bucket => "Scan: Add S3 bucket…",
+            Command::AddGoogleDrive => "Scan: Add Google Drive…",
+            Command::AddAndroidDevice => "Scan: Add Android device…",
+            Command::AddToQueue => "Queue: Add scan to queue",
+            Command::RunQueue => "Queue: Run queue",
+            Command::KeepNewest => "Strategy: Keep newest",
+            Command::KeepOldest => "Strategy: Keep oldest",
+            Command::KeepLikelyOriginals => "Strategy: Keep likely originals",
+            Command::KeepOnePerDirectory => "Strategy: Keep one per folder",
+            Command::UncheckJunkCopies => "Strategy: Uncheck pattern-matched copies",
+            Command::DeleteUnchecked => "Delete unchecked files",
+            Command::CleanUpDuplicates => "Clean up duplicates (guided)…",
+            Command::ConsolidateToStore => "Consolidate to store…",
+            Command::ExportResults => "Export: Results (JSON)…",
+            Command::ImportResults => "Import: Results (JSON)…",
+            Command::ImportExternalResults => "Import: fdupes / jdupes / rdfind / rmlint results…",
+            Command::ExportChecksums => "Export: Checksums…",
+            Command::ExportForOtherTools => "Export: fdupes text / rmlint JSON…",
+            Command::ComplianceReport => "Export: Compliance report…",
+            Command::SimulationReport => "Export: Deletion simulation report…",
+            Command::ExportDeletionPaths => "Export: Unchecked paths as plain text…",
+            Command::ValidateResults => "Validate results",
+            Command::RescanAffected => "Rescan affected directories",
+            Command::MergeFolders => "Tools: Merge folders…",
+            Command::FindCaseVariants => "Tools: Case-variant folders…",
+            Command::CompareFolders => "Tools: Compare folders…",
+            Command::CheckCorruption => "Tools: Corruption check…",
+            Command::MatchAgainstManifest => "Tools: Match against manifest…",
+            Command::VerifyChecksums => "Tools: Verify checksums…",
+            Command::TogglePreviewMode => "Settings: Toggle preview mode",
+            Command::ToggleHiddenFiles => "Settings: Toggle include hidden files",
+            Command::ToggleHighContrast => "Settings: Toggle high contrast",
+            Command::ToggleUiMode => "Settings: Switch between simple and advanced mode",
+            Command::OpenSensitivePatterns => "Settings: Open sensitive file patterns",
+            Command::OpenJunkCopyPatterns => "Settings: Open junk copy patterns",
+            Command::StartTour => "Help: Start guided tour",
+            Command::CheckForUpdates => "Help: Check for updates",
+            Command::OpenInsights => "Help: Usage insights",
+        }
+    }
+}
+
+/// How well `query` matches `label` as a case-insensitive subsequence, or
+/// None when it doesn't. Consecutive letters and letters starting a word
+/// score higher, so "kn" ranks "Keep newest" above "Unchecked copies".
+pub fn fuzzy_score(query: &str, label: &str) -> Option<i32> {
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut matched_previous = false;
+    let mut query_chars = query.chars().filter(|c| !c.is_whitespace()).map(|c| c.to_ascii_lowercase()).peekable();
+    for c in label.chars() {
+        let Some(&wanted) = query_chars.peek() else {
+            break;
+        };
+        if c.to_ascii_lowercase() == wanted {
+            query_chars.next();
+            score += 1;
+            if matched_previous {
+                score += 4;
+            }
+            if previous.is_none_or(|p| !p.is_alphanumeric()) {
+                score += 6;
+            }
+            matched_previous = true;
+        } else {
+            matched_previous = false;
+        }
+        previous = Some(c);
+    }
+    query_chars.peek().is_none().then(|| score - label.len() as i32 / 16)
+}
+
+/// Ctrl+P overlay listing commands that match what was typed
+#[derive(Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    query: String,
+    /// Highlighted row among the current matches
+    selected: usize,
+}
+
+impl CommandPalette {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    /// Draw the palette over the window, offering `available` commands.
+    /// Returns the command chosen with Enter or a click; the palette closes then.
+    pub fn show(&mut self, ctx: &egui::Context, available: &[Command]) -> Option<Command> {
+        if !self.open {
+            return None;
+        }
+
+        let mut matches: Vec<(i32, Command)> = available
+            .iter()
+            .filter_map(|&command| fuzzy_score(&self.query, command.label()).map(|score| (score, command)))
+            .collect();
+        // Stable, so equal scores keep the listed order
+        matches.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        if down {
+            self.selected += 1;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+
+        let mut chosen = enter.then(|| matches.get(self.selected).map(|&(_, command)| command)).flatten();
+        let screen = ctx.screen_rect();
+        let width = 420.0;
+        egui::Area::new(egui::Id::new("command_palette"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(egui::pos2(screen.center().x - width / 2.0, screen.min.y + 60.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(width);
+                    let edit = ui.add(egui::TextEdit::singleline(&mut self.query)
+                        .hint_text("Type a command…")
+                        .desired_width(f32::INFINITY));
+                    edit.request_focus();
+                    if edit.changed() {
+                        self.selected = 0;
+                    }
+                    ui.separator();
+                    egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                        if matches.is_empty() {
+                            ui.weak("No matching command");
+                        }
+                        for (idx, &(_, command)) in matches.iter().enumerate() {
+                            let row = ui.selectable_label(idx == self.selected, command.label());
+                            if idx == self.selected && (up || down) {
+                                row.scroll_to_me(None);
+                            }
+                            if row.clicked() {
+                                chosen = Some(command);
+                            }
+                        }
+                    });
+                    ui.weak("↑↓ to choose, Enter to run, Esc to close");
+                });
+            });
+
+        if chosen.is_some() || escape {
+            self.toggle();
+        }
+        chosen
+    }
+}
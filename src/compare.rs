@@ -0,0 +1,241 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use rayon::prelude::*;
+use crate::merge::{collect_files, copy_file};
+use crate::scanner::{build_thread_pool, ScanConfig, ScanError, ScanPhase, ScanProgress};
+use crate::source::same_file;
+
+/// A file present in one or both trees, by its path relative to the tree root
+#[derive(Clone, Debug)]
+pub struct ComparedFile {
+    pub relative: PathBuf,
+    pub size: u64,
+}
+
+/// Result of comparing two directory trees path by path
+#[derive(Clone, Debug, Default)]
+pub struct DirComparison {
+    pub left: PathBuf,
+    pub right: PathBuf,
+    /// Same relative path and byte-identical content on both sides
+    pub identical: Vec<ComparedFile>,
+    pub only_left: Vec<ComparedFile>,
+    pub only_right: Vec<ComparedFile>,
+    /// Same relative path, different content; `size` is the left file's
+    pub different: Vec<ComparedFile>,
+    /// Pairs that could not be read for comparison
+    pub errors: Vec<String>,
+}
+
+/// Which side of a comparison an action works on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+// Compare two files byte by byte, stopping at the first difference
+fn files_identical(a: &Path, b: &Path, buffer_size: usize) -> io::Result<bool> {
+    let (mut a, mut b) = (File::open(a)?, File::open(b)?);
+    let mut buf_a = vec![0; buffer_size.max(4096)];
+    let mut buf_b = vec![0; buf_a.len()];
+    loop {
+        let read = a.read(&mut buf_a)?;
+        if read == 0 {
+            // Equal sizes were checked up front; only a file growing meanwhile leaves bytes in b
+            return Ok(b.read(&mut buf_b[..1])? == 0);
+        }
+        b.read_exact(&mut buf_b[..read])?;
+        if buf_a[..read] != buf_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+fn relative_sizes(root: &Path, other: &Path, config: &ScanConfig) -> BTreeMap<PathBuf, u64> {
+    collect_files(root, other, config)
+        .into_iter()
+        .filter_map(|(path, size)| Some((path.strip_prefix(root).ok()?.to_path_buf(), size)))
+        .collect()
+}
+
+/// Compare every file under `left` with the file at the same relative path
+/// under `right`, byte for byte. Files are never matched across different paths.
+/// Both roots are resolved first; the same folder reached twice, e.g.
+/// through a symlink, or one root inside the other is refused, as every
+/// file would then be "identical" to itself.
+pub fn compare_dirs<F>(left: &Path, right: &Path, config: &ScanConfig, progress_callback: F) -> Result<DirComparison, ScanError>
+where
+    F: Fn(ScanProgress) + Send + Sync,
+{
+    for dir in [left, right] {
+        if !dir.is_dir() {
+            return Err(ScanError::IoError(io::Error::new(io::ErrorKind::NotFound, format!("{} is not a directory", dir.display()))));
+        }
+    }
+    let (left, right) = (&left.canonicalize()?, &right.canonicalize()?);
+    if left.starts_with(right) || right.starts_with(left) {
+        return Err(ScanError::IoError(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} and {} are the same folder or one lies inside the other", left.display(), right.display()),
+        )));
+    }
+
+    progress_callback(ScanProgress {
+        current: 0,
+        total: 0,
+        discovered: 0,
+        current_file: left.display().to_string(),
+        phase: ScanPhase::Discovery,
+    });
+    let left_files = relative_sizes(left, right, config);
+    let right_files = relative_sizes(right, left, config);
+
+    let mut comparison = DirComparison { left: left.to_path_buf(), right: right.to_path_buf(), ..Default::default() };
+    let mut pairs = Vec::new();
+    for (relative, &size) in &left_files {
+        match right_files.get(relative) {
+            None => comparison.only_left.push(ComparedFile { relative: relative.clone(), size }),
+            Some(&other) if other != size => comparison.different.push(ComparedFile { relative: relative.clone(), size }),
+            Some(_) => pairs.push(ComparedFile { relative: relative.clone(), size }),
+        }
+    }
+    comparison.only_right = right_files
+        .iter()
+        .filter(|(relative, _)| !left_files.contains_key(*relative))
+        .map(|(relative, &size)| ComparedFile { relative: relative.clone(), size })
+        .collect();
+
+    let total = pairs.len();
+    let done = AtomicUsize::new(0);
+    let results: Vec<(ComparedFile, io::Result<bool>)> = build_thread_pool(config)?.install(|| {
+        pairs
+            .into_par_iter()
+            .map(|file| {
+                let same = files_identical(&left.join(&file.relative), &right.join(&file.relative), config.buffer_size);
+                progress_callback(ScanProgress {
+                    current: done.fetch_add(1, Ordering::Relaxed) + 1,
+                    total,
+                    discovered: left_files.len() + right_files.len(),
+                    current_file: file.relative.display().to_string(),
+                    phase: ScanPhase::Hashing,
+                });
+                (file, same)
+            })
+            .collect()
+    });
+
+    for (file, same) in results {
+        match same {
+            Ok(true) => comparison.identical.push(file),
+            Ok(false) => comparison.different.push(file),
+            Err(e) => comparison.errors.push(format!("{}: {}", file.relative.display(), e)),
+        }
+    }
+    comparison.different.sort_by(|a, b| a.relative.cmp(&b.relative));
+    Ok(comparison)
+}
+
+/// Per-category actions offered by the comparison window
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompareAction {
+    /// Copy the files only present on this side to the other side
+    CopyMissing(Side),
+    /// Overwrite differing files on the other side with this side's version
+    Overwrite(Side),
+    /// Delete identical files on this side; the other side keeps a copy
+    DeleteIdentical(Side),
+}
+
+impl DirComparison {
+    pub fn is_identical(&self) -> bool {
+        self.only_left.is_empty() && self.only_right.is_empty() && self.different.is_empty() && self.errors.is_empty()
+    }
+
+    pub fn root(&self, side: Side) -> &Path {
+        match side {
+            Side::Left => &self.left,
+            Side::Right => &self.right,
+        }
+    }
+
+    /// Files an action would touch
+    pub fn targets(&self, action: CompareAction) -> &[ComparedFile] {
+        match action {
+            CompareAction::CopyMissing(Side::Left) => &self.only_left,
+            CompareAction::CopyMissing(Side::Right) => &self.only_right,
+            CompareAction::Overwrite(_) => &self.different,
+            CompareAction::DeleteIdentical(_) => &self.identical,
+        }
+    }
+
+    /// Carry out an action and move each file it succeeded on into its new
+    /// category. Returns the absolute paths written or deleted, and the errors met.
+    pub fn apply(&mut self, action: CompareAction) -> (Vec<PathBuf>, Vec<String>) {
+        let mut done = Vec::new();
+        let mut errors = Vec::new();
+        let mut moved = Vec::new();
+        for file in self.targets(action) {
+            let result = match action {
+                CompareAction::CopyMissing(from) | CompareAction::Overwrite(from) => {
+                    let to = self.root(other(from)).join(&file.relative);
+                    copy_file(&self.root(from).join(&file.relative), &to, matches!(action, CompareAction::Overwrite(_)))
+                        .map(|size| (to, size))
+                        .map_err(|e| format!("Failed to copy {}: {}", file.relative.display(), e))
+                }
+                CompareAction::DeleteIdentical(side) => {
+                    let path = self.root(side).join(&file.relative);
+                    // The comparison may be old; never delete unless the other copy is still there
+                    let kept = self.root(other(side)).join(&file.relative);
+                    match same_file(&path, &kept) {
+                        // Both names reaching one file leave no other copy
+                        Ok(true) => Err(format!("Skipped {}: both sides are the same file", file.relative.display())),
+                        Ok(false) => match files_identical(&path, &kept, 64 * 1024) {
+                            Ok(true) => fs::remove_file(&path)
+                                .map(|()| (path, file.size))
+                                .map_err(|e| format!("Failed to delete {}: {}", file.relative.display(), e)),
+                            Ok(false) => Err(format!("Skipped {}: the copies no longer match", file.relative.display())),
+                            Err(e) => Err(format!("Skipped {}: cannot read both copies: {}", file.relative.display(), e)),
+                        },
+                        Err(e) => Err(format!("Skipped {}: cannot read both copies: {}", file.relative.display(), e)),
+                    }
+                }
+            };
+            match result {
+                Ok((path, size)) => {
+                    done.push(path);
+                    moved.push(ComparedFile { relative: file.relative.clone(), size });
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+
+        let moved_paths: HashSet<&PathBuf> = moved.iter().map(|f| &f.relative).collect();
+        let source = match action {
+            CompareAction::CopyMissing(Side::Left) => &mut self.only_left,
+            CompareAction::CopyMissing(Side::Right) => &mut self.only_right,
+            CompareAction::Overwrite(_) => &mut self.different,
+            CompareAction::DeleteIdentical(_) => &mut self.identical,
+        };
+        source.retain(|f| !moved_paths.contains(&f.relative));
+        let destination = match action {
+            CompareAction::CopyMissing(_) | CompareAction::Overwrite(_) => &mut self.identical,
+            // The file now exists only on the side that was not deleted from
+            CompareAction::DeleteIdentical(Side::Left) => &mut self.only_right,
+            CompareAction::DeleteIdentical(Side::Right) => &mut self.only_left,
+        };
+        destination.extend(moved);
+        destination.sort_by(|a, b| a.relative.cmp(&b.relative));
+        (done, errors)
+    }
+}
+
+fn other(side: Side) -> Side {
+    match side {
+        Side::Left => Side::Right,
+        Side::Right => Side::Left,
+    }
+}
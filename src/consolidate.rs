@@ -0,0 +1,353 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::format::format_size;
+use crate::instance::try_lock_file;
+use crate::report::{owner_id, owner_name};
+use crate::scanner::{hash_file, FileInfo, FileStatus, ScanConfig};
+
+/// Consolidation of one duplicate group onto its canonical store copy
+#[derive(Clone, Debug)]
+pub struct ConsolidationStep {
+    pub hash: String,
+    pub size: u64,
+    pub canonical: PathBuf,
+    /// Group member placed into the store when no canonical copy exists yet;
+    /// its original path stays as a link to the store copy
+    pub move_into_store: Option<PathBuf>,
+    /// Paths replaced by hard links to the canonical copy
+    pub links: Vec<PathBuf>,
+    /// Users owning the group's copies when they differ; every linked path
+    /// takes the canonical copy's owner
+    pub owners: Vec<String>,
+    /// User and group id the canonical copy is given before linking
+    pub chown: Option<(u32, u32)>,
+}
+
+/// What consolidation does with a group whose copies belong to different
+/// users: a hard link shares one owner, so some users would lose theirs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OwnerPolicy {
+    /// Link the copies anyway and list the group in the plan
+    #[default]
+    Warn,
+    /// Leave such groups untouched
+    Skip,
+    /// Link the copies and give the canonical copy the store folder's owner and group
+    ChownToStore,
+}
+
+impl OwnerPolicy {
+    pub const ALL: [OwnerPolicy; 3] = [OwnerPolicy::Warn, OwnerPolicy::Skip, OwnerPolicy::ChownToStore];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OwnerPolicy::Warn => "Link and warn",
+            OwnerPolicy::Skip => "Skip the group",
+            OwnerPolicy::ChownToStore => "Give the store's owner",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ConsolidationPlan {
+    pub store: PathBuf,
+    pub steps: Vec<ConsolidationStep>,
+    /// Groups or files left untouched, with the reason
+    pub skipped: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ConsolidationResult {
+    pub linked: usize,
+    pub moved: usize,
+    pub reclaimed_bytes: u64,
+    /// Hashes of groups that were fully consolidated
+    pub completed: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl ConsolidationPlan {
+    pub fn reclaimable_bytes(&self) -> u64 {
+        self.steps.iter().map(|s| s.size * s.links.len() as u64).sum()
+    }
+
+    /// Groups whose copies belong to different users
+    pub fn mixed_owner_groups(&self) -> usize {
+        self.steps.iter().filter(|s| !s.owners.is_empty()).count()
+    }
+
+    /// Human-readable dry-run report of every action the plan would take
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Consolidation plan for store {}", self.store.display());
+        let _ = writeln!(
+            out,
+            "{} group(s), {} link(s), {} reclaimable\n",
+            self.steps.len(),
+            self.steps.iter().map(|s| s.links.len()).sum::<usize>(),
+            format_size(self.reclaimable_bytes())
+        );
+        for step in &self.steps {
+            let _ = writeln!(out, "[{}] {} bytes", step.hash, step.size);
+            match &step.move_into_store {
+                Some(source) => {
+                    let _ = writeln!(out, "  STORE {} -> {}", source.display(), step.canonical.display());
+                }
+                None => {
+                    let _ = writeln!(out, "  USE   {}", step.canonical.display());
+                }
+            }
+            if !step.owners.is_empty() {
+                let _ = writeln!(out, "  OWNERS {} differ", step.owners.join(", "));
+            }
+            if let Some((uid, gid)) = step.chown {
+                let _ = writeln!(out, "  CHOWN {} -> {}:{}", step.canonical.display(), owner_name(uid), gid);
+            }
+            for link in &step.links {
+                let _ = writeln!(out, "  LINK  {} -> {}", link.display(), step.canonical.display());
+            }
+        }
+        if !self.skipped.is_empty() {
+            let _ = writeln!(out, "\nSkipped:");
+            for reason in &self.skipped {
+                let _ = writeln!(out, "  {}", reason);
+            }
+        }
+        out
+    }
+}
+
+#[cfg(unix)]
+fn device_of(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|m| m.dev())
+}
+
+#[cfg(not(unix))]
+fn device_of(_path: &Path) -> Option<u64> {
+    // Cross-volume links fail at execution time instead
+    Some(0)
+}
+
+#[cfg(unix)]
+fn owner_and_group(path: &Path) -> Option<(u32, u32)> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|m| (m.uid(), m.gid()))
+}
+
+#[cfg(not(unix))]
+fn owner_and_group(_path: &Path) -> Option<(u32, u32)> {
+    None
+}
+
+#[cfg(unix)]
+fn set_owner(path: &Path, (uid, gid): (u32, u32)) -> io::Result<()> {
+    std::os::unix::fs::chown(path, Some(uid), Some(gid))
+}
+
+#[cfg(not(unix))]
+fn set_owner(_path: &Path, _owner: (u32, u32)) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "file owners cannot be changed on this platform"))
+}
+
+/// Names of the users owning `paths`, when more than one does
+fn differing_owners<'a>(paths: impl IntoIterator<Item = &'a PathBuf>) -> Vec<String> {
+    let mut owners: Vec<u32> = paths.into_iter().filter_map(|path| owner_id(&fs::metadata(path).ok()?)).collect();
+    owners.sort_unstable();
+    owners.dedup();
+    if owners.len() < 2 {
+        return Vec::new();
+    }
+    owners.into_iter().map(owner_name).collect()
+}
+
+/// Content-addressed location of a group's canonical copy inside the store
+fn canonical_path(store: &Path, hash: &str, sample: &Path) -> PathBuf {
+    let mut name = hash.to_string();
+    if let Some(ext) = sample.extension().and_then(|e| e.to_str()) {
+        name.push('.');
+        name.push_str(ext);
+    }
+    store.join(&hash[..2.min(hash.len())]).join(name)
+}
+
+// The store's canonical path without creating it: its nearest existing
+// folder resolved, with the missing rest appended
+fn resolve_store(store: &Path) -> io::Result<(PathBuf, PathBuf)> {
+    let absolute = std::path::absolute(store)?;
+    let existing = absolute.ancestors().find(|dir| dir.exists()).unwrap_or(&absolute);
+    let rest = absolute.strip_prefix(existing).unwrap_or(Path::new(""));
+    let existing = existing.canonicalize()?;
+    Ok((existing.join(rest), existing))
+}
+
+/// Plan replacing every copy in `groups` with a hard link to one canonical
+/// copy per group kept under `store`, handling copies of different users by
+/// `owners`. Nothing is changed on disk; a store that does not exist yet is
+/// created when the plan is executed.
+pub fn plan_consolidation<'a, I>(groups: I, store: &Path, owners: OwnerPolicy) -> Result<ConsolidationPlan, String>
+where
+    I: IntoIterator<Item = (&'a str, &'a [FileInfo])>,
+{
+    let (store, existing) = resolve_store(store).map_err(|e| format!("Cannot resolve store {}: {}", store.display(), e))?;
+    // A store still to be created lands on its nearest existing folder's filesystem
+    let store_device = device_of(&existing);
+    let store_owner = owner_and_group(&existing);
+
+    let mut plan = ConsolidationPlan { store: store.clone(), ..Default::default() };
+
+    for (hash, files) in groups {
+        if hash.is_empty() {
+            plan.skipped.push(format!("Group of {} file(s) has no recorded hash; rescan before consolidating", files.len()));
+            continue;
+        }
+
+        let mut members = Vec::new();
+        for file in files {
+            let path = file.path.to_path_buf();
+            match file.check_status() {
+                FileStatus::Unchanged if device_of(&path) == store_device => members.push(path),
+                FileStatus::Unchanged => plan.skipped.push(format!("{}: on a different filesystem than the store", path.display())),
+                FileStatus::Changed => plan.skipped.push(format!("{}: changed since scan", path.display())),
+                FileStatus::Missing => plan.skipped.push(format!("{}: missing", path.display())),
+            }
+        }
+        let Some(first) = members.first().cloned() else {
+            continue;
+        };
+
+        // Prefer a copy that already lives in the store
+        let in_store = members.iter().position(|p| p.starts_with(&store));
+        let (canonical, move_into_store) = match in_store {
+            Some(idx) => (members.remove(idx), None),
+            None => {
+                let canonical = canonical_path(&store, hash, &first);
+                match fs::metadata(&canonical) {
+                    // Its content is hashed again before anything is linked to it
+                    Ok(meta) if meta.len() == files[0].size => (canonical, None),
+                    Ok(_) => {
+                        plan.skipped.push(format!("Group {}: the store copy {} has a different size; it was changed outside the store", hash, canonical.display()));
+                        continue;
+                    }
+                    Err(_) => (canonical, Some(members.remove(0))),
+                }
+            }
+        };
+
+        // Nothing to reclaim when no other copy remains to be linked
+        if members.is_empty() {
+            continue;
+        }
+        let kept = move_into_store.as_ref().unwrap_or(&canonical);
+        let group_owners = differing_owners(members.iter().chain([kept]));
+        if !group_owners.is_empty() && owners == OwnerPolicy::Skip {
+            plan.skipped.push(format!("Group {}: copies belong to different users ({})", hash, group_owners.join(", ")));
+            continue;
+        }
+        plan.steps.push(ConsolidationStep {
+            hash: hash.to_string(),
+            size: files[0].size,
+            canonical,
+            move_into_store,
+            links: members,
+            chown: if group_owners.is_empty() || owners != OwnerPolicy::ChownToStore { None } else { store_owner },
+            owners: group_owners,
+        });
+    }
+
+    Ok(plan)
+}
+
+// Held while a plan executes so two instances never rewrite the same store at once
+const STORE_LOCK_FILE: &str = ".dupefinder.lock";
+
+/// Suffix of the temporary link created next to a file while it is being replaced
+pub const TEMP_LINK_SUFFIX: &str = ".dupefinder-link";
+
+// Replace `path` with a hard link to `target` without a window where `path` is missing
+fn replace_with_link(target: &Path, path: &Path) -> io::Result<()> {
+    let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let temp = path.with_file_name(format!(".{}{}", file_name, TEMP_LINK_SUFFIX));
+    let _ = fs::remove_file(&temp);
+    fs::hard_link(target, &temp)?;
+    fs::rename(&temp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+fn move_into_store(source: &Path, canonical: &Path) -> io::Result<()> {
+    if let Some(parent) = canonical.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Link rather than rename so the source path keeps working; both names
+    // then share the stored content
+    fs::hard_link(source, canonical)
+}
+
+/// Execute a plan step by step. Every replacement is atomic, and a step that
+/// fails leaves the remaining members of that group untouched. The store copy
+/// of each group is hashed first, so nothing is linked to a copy that no
+/// longer holds the group's content.
+pub fn execute_consolidation(plan: &ConsolidationPlan, config: &ScanConfig) -> ConsolidationResult {
+    let mut result = ConsolidationResult::default();
+    if let Err(e) = fs::create_dir_all(&plan.store) {
+        result.errors.push(format!("Cannot create store {}: {}", plan.store.display(), e));
+        return result;
+    }
+    let _lock = match try_lock_file(&plan.store.join(STORE_LOCK_FILE)) {
+        Ok(lock) => lock,
+        Err(e) => {
+            result.errors.push(format!("Store is in use: {}", e));
+            return result;
+        }
+    };
+
+    for step in &plan.steps {
+        if let Some(source) = &step.move_into_store {
+            if let Err(e) = move_into_store(source, &step.canonical) {
+                result.errors.push(format!("Failed to store {}: {}", source.display(), e));
+                continue;
+            }
+            result.moved += 1;
+        }
+        match hash_file(&step.canonical, config) {
+            Ok(hash) if hash == step.hash => {}
+            Ok(_) => {
+                result.errors.push(format!("Skipped group {}: the store copy {} no longer matches it", step.hash, step.canonical.display()));
+                continue;
+            }
+            Err(e) => {
+                result.errors.push(format!("Failed to check the store copy {}: {}", step.canonical.display(), e));
+                continue;
+            }
+        }
+        if let Some(owner) = step.chown {
+            if let Err(e) = set_owner(&step.canonical, owner) {
+                result.errors.push(format!("Failed to change the owner of {}: {}", step.canonical.display(), e));
+                continue;
+            }
+        }
+
+        let mut failed = false;
+        for link in &step.links {
+            match replace_with_link(&step.canonical, link) {
+                Ok(()) => {
+                    result.linked += 1;
+                    result.reclaimed_bytes += step.size;
+                }
+                Err(e) => {
+                    result.errors.push(format!("Failed to link {}: {}", link.display(), e));
+                    failed = true;
+                    break;
+                }
+            }
+        }
+        if !failed {
+            result.completed.push(step.hash.clone());
+        }
+    }
+
+    result
+}
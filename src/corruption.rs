@@ -0,0 +1,141 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+use rayon::prelude::*;
+use crate::merge::collect_files;
+use crate::scanner::{build_thread_pool, hash_file, ScanConfig, ScanError, ScanPhase, ScanProgress};
+
+/// One side of a suspect pair
+#[derive(Clone, Debug)]
+pub struct SuspectFile {
+    pub path: PathBuf,
+    pub hash: String,
+    pub modified: Option<SystemTime>,
+}
+
+/// Two files, one under each root, with the same name and size but different content
+#[derive(Clone, Debug)]
+pub struct SuspectPair {
+    pub size: u64,
+    pub left: SuspectFile,
+    pub right: SuspectFile,
+}
+
+impl SuspectPair {
+    /// An edit changes the modification time; content that differs while it
+    /// stayed the same points to bit rot or a damaged copy
+    pub fn same_modified(&self) -> bool {
+        self.left.modified.is_some() && self.left.modified == self.right.modified
+    }
+}
+
+/// Outcome of checking two roots for corruption candidates
+#[derive(Clone, Debug, Default)]
+pub struct CorruptionReport {
+    pub left: PathBuf,
+    pub right: PathBuf,
+    /// Files with a same-name, same-size counterpart of identical content
+    pub matched: usize,
+    /// Likely corruption first: pairs whose modification times agree
+    pub suspects: Vec<SuspectPair>,
+    pub errors: Vec<String>,
+}
+
+type Key = (OsString, u64);
+
+fn by_name_and_size(files: Vec<(PathBuf, u64)>) -> HashMap<Key, Vec<PathBuf>> {
+    let mut index: HashMap<Key, Vec<PathBuf>> = HashMap::new();
+    for (path, size) in files {
+        if let Some(name) = path.file_name() {
+            index.entry((name.to_os_string(), size)).or_default().push(path);
+        }
+    }
+    index
+}
+
+fn read_file(path: &Path, config: &ScanConfig) -> io::Result<SuspectFile> {
+    let modified = fs::metadata(path)?.modified().ok();
+    Ok(SuspectFile { path: path.to_path_buf(), hash: hash_file(path, config)?, modified })
+}
+
+/// Pair files under `left` and `right` by file name and size, wherever they
+/// sit in each tree, and hash both sides. Pairs whose content differs are
+/// reported: a backup holding the same name and size with other bytes may
+/// be corrupted. Files without such a counterpart are left out.
+pub fn find_corruption<F>(left: &Path, right: &Path, config: &ScanConfig, progress_callback: F) -> Result<CorruptionReport, ScanError>
+where
+    F: Fn(ScanProgress) + Send + Sync,
+{
+    for dir in [left, right] {
+        if !dir.is_dir() {
+            return Err(ScanError::IoError(io::Error::new(io::ErrorKind::NotFound, format!("{} is not a directory", dir.display()))));
+        }
+    }
+    progress_callback(ScanProgress {
+        current: 0,
+        total: 0,
+        discovered: 0,
+        current_file: left.display().to_string(),
+        phase: ScanPhase::Discovery,
+    });
+    // A tree nested inside the other is left out of the outer side
+    let left_files = by_name_and_size(collect_files(left, right, config));
+    let mut right_files = by_name_and_size(collect_files(right, left, config));
+    let keyed: Vec<(u64, Vec<PathBuf>, Vec<PathBuf>)> = left_files
+        .into_iter()
+        .filter_map(|(key, lefts)| Some((key.1, lefts, right_files.remove(&key)?)))
+        .collect();
+
+    let to_hash: Vec<&Path> = keyed.iter().flat_map(|(_, lefts, rights)| lefts.iter().chain(rights)).map(PathBuf::as_path).collect();
+    let total = to_hash.len();
+    let done = AtomicUsize::new(0);
+    let read: Vec<(&Path, io::Result<SuspectFile>)> = build_thread_pool(config)?.install(|| {
+        to_hash
+            .into_par_iter()
+            .map(|path| {
+                let file = read_file(path, config);
+                progress_callback(ScanProgress {
+                    current: done.fetch_add(1, Ordering::Relaxed) + 1,
+                    total,
+                    discovered: total,
+                    current_file: path.display().to_string(),
+                    phase: ScanPhase::Hashing,
+                });
+                (path, file)
+            })
+            .collect()
+    });
+
+    let mut report = CorruptionReport { left: left.to_path_buf(), right: right.to_path_buf(), ..Default::default() };
+    let mut hashed = HashMap::new();
+    for (path, file) in read {
+        match file {
+            Ok(file) => {
+                hashed.insert(path.to_path_buf(), file);
+            }
+            Err(e) => report.errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+    for (size, lefts, rights) in keyed {
+        let lefts: Vec<&SuspectFile> = lefts.iter().filter_map(|path| hashed.get(path)).collect();
+        let rights: Vec<&SuspectFile> = rights.iter().filter_map(|path| hashed.get(path)).collect();
+        let left_hashes: HashSet<&str> = lefts.iter().map(|file| file.hash.as_str()).collect();
+        let right_hashes: HashSet<&str> = rights.iter().map(|file| file.hash.as_str()).collect();
+        // A file with an intact counterpart anywhere on the other side is fine
+        for left_file in &lefts {
+            if right_hashes.contains(left_file.hash.as_str()) {
+                report.matched += 1;
+                continue;
+            }
+            for right_file in rights.iter().filter(|file| !left_hashes.contains(file.hash.as_str())) {
+                report.suspects.push(SuspectPair { size, left: (*left_file).clone(), right: (*right_file).clone() });
+            }
+        }
+    }
+    report.suspects.sort_by(|a, b| b.same_modified().cmp(&a.same_modified()).then_with(|| a.left.path.cmp(&b.left.path)));
+    Ok(report)
+}
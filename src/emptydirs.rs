@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use crate::scanner::{filtered_walk, is_protected_path, ScanConfig};
+
+/// Remove the folders above `file` (already deleted or moved away) that are
+/// now empty, walking up until a folder still holds something. Stops below
+/// the outermost of `roots` holding the file, which is never removed itself,
+/// and at protected system paths. Returns the removed folders, innermost first.
+pub fn remove_emptied_parents(file: &Path, roots: &[PathBuf]) -> Vec<PathBuf> {
+    let Some(root) = roots
+        .iter()
+        .filter(|root| file.starts_with(root))
+        .min_by_key(|root| root.components().count())
+    else {
+        return Vec::new();
+    };
+
+    let mut removed = Vec::new();
+    let mut dir = file.parent();
+    while let Some(current) = dir {
+        if current == root || !current.starts_with(root) || is_protected_path(current) {
+            break;
+        }
+        // remove_dir only succeeds on an empty folder, so nothing else is at risk
+        if fs::remove_dir(current).is_err() {
+            break;
+        }
+        removed.push(current.to_path_buf());
+        dir = current.parent();
+    }
+    removed
+}
+
+/// Folders under `roots` holding nothing but other empty folders. Only the
+/// outermost folder of each such tree is listed; the roots themselves and
+/// protected system paths never are.
+pub fn find_empty_dirs(roots: &[String], config: &ScanConfig) -> Vec<PathBuf> {
+    let mut empty = HashSet::new();
+    for root in roots {
+        // Subfolders come before their parent, so a parent can check them in `empty`
+        for entry in filtered_walk(WalkDir::new(root).contents_first(true), config) {
+            if !entry.file_type().is_dir() {
+                continue;
+            }
+            // Listed directly rather than through the walk, so hidden or excluded entries still count as content
+            let Ok(children) = fs::read_dir(entry.path()) else {
+                continue;
+            };
+            if children.into_iter().all(|child| child.is_ok_and(|child| empty.contains(&child.path()))) {
+                empty.insert(entry.into_path());
+            }
+        }
+    }
+
+    let is_root = |dir: &Path| roots.iter().any(|root| Path::new(root) == dir);
+    let mut outermost: Vec<PathBuf> = empty
+        .iter()
+        .filter(|dir| !is_root(dir) && !is_protected_path(dir))
+        .filter(|dir| !dir.parent().is_some_and(|parent| empty.contains(parent) && !is_root(parent)))
+        .cloned()
+        .collect();
+    outermost.sort();
+    outermost
+}
+
+/// Remove an empty folder with its empty subfolders. Each folder is removed
+/// only while empty, so anything added since the scan stops the removal.
+pub fn remove_empty_tree(dir: &Path) -> io::Result<()> {
+    for entry in WalkDir::new(dir).contents_first(true) {
+        fs::remove_dir(entry.map_err(io::Error::other)?.path())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,350 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use serde::Deserialize;
+use crate::scanner::{unhashed_file_info, FileInfo, HashGroup, ScanConfig};
+
+/// Command-line duplicate finders whose results can be imported
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExternalTool {
+    /// fdupes or jdupes plain output: one path per line, groups separated by a blank line
+    Fdupes,
+    /// jdupes `-j` JSON
+    JdupesJson,
+    /// rdfind `results.txt`
+    Rdfind,
+    /// rmlint `rmlint.json`
+    Rmlint,
+}
+
+impl ExternalTool {
+    pub fn label(self) -> &'static str {
+        match self {
+            ExternalTool::Fdupes => "fdupes / jdupes",
+            ExternalTool::JdupesJson => "jdupes (JSON)",
+            ExternalTool::Rdfind => "rdfind",
+            ExternalTool::Rmlint => "rmlint (JSON)",
+        }
+    }
+
+    /// Tell the format from the text itself; anything unrecognized is read as fdupes output
+    pub fn detect(text: &str) -> Self {
+        let trimmed = text.trim_start();
+        if trimmed.starts_with('[') {
+            ExternalTool::Rmlint
+        } else if trimmed.starts_with('{') {
+            ExternalTool::JdupesJson
+        } else if text.lines().any(|line| line.starts_with("DUPTYPE_")) {
+            ExternalTool::Rdfind
+        } else {
+            ExternalTool::Fdupes
+        }
+    }
+}
+
+/// Duplicate sets as another tool reported them, in its order
+#[derive(Clone, Debug)]
+pub struct ExternalResults {
+    pub tool: ExternalTool,
+    pub groups: Vec<Vec<PathBuf>>,
+}
+
+impl ExternalResults {
+    pub fn file_count(&self) -> usize {
+        self.groups.iter().map(Vec::len).sum()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JdupesOutput {
+    match_sets: Vec<JdupesSet>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JdupesSet {
+    file_list: Vec<JdupesFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JdupesFile {
+    file_path: String,
+}
+
+// One element of rmlint's JSON array: the header, a lint entry or the footer
+#[derive(Deserialize)]
+struct RmlintEntry {
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    path: String,
+    #[serde(default)]
+    checksum: String,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    is_original: bool,
+}
+
+/// Parse the output of fdupes, jdupes, rdfind or rmlint. Relative paths are
+/// resolved against `base`; the tools print them relative to the folder
+/// they ran in, where rdfind also writes its `results.txt`. Sets with fewer
+/// than two files are left out.
+pub fn parse_external(text: &str, base: &Path) -> Result<ExternalResults, String> {
+    let tool = ExternalTool::detect(text);
+    let groups: Vec<Vec<String>> = match tool {
+        ExternalTool::Fdupes => parse_fdupes(text),
+        ExternalTool::JdupesJson => {
+            let output: JdupesOutput = serde_json::from_str(text).map_err(|e| format!("Not jdupes JSON output: {}", e))?;
+            output.match_sets.into_iter().map(|set| set.file_list.into_iter().map(|file| file.file_path).collect()).collect()
+        }
+        ExternalTool::Rdfind => parse_rdfind(text),
+        ExternalTool::Rmlint => {
+            let entries: Vec<RmlintEntry> = serde_json::from_str(text).map_err(|e| format!("Not rmlint JSON output: {}", e))?;
+            // Grouped by checksum; the original rmlint picked comes first
+            let mut sets: BTreeMap<(String, u64), Vec<RmlintEntry>> = BTreeMap::new();
+            for entry in entries.into_iter().filter(|entry| entry.kind == "duplicate_file" && !entry.path.is_empty()) {
+                sets.entry((entry.checksum.clone(), entry.size)).or_default().push(entry);
+            }
+            sets.into_values()
+                .map(|mut set| {
+                    set.sort_by_key(|entry| !entry.is_original);
+                    set.into_iter().map(|entry| entry.path).collect()
+                })
+                .collect()
+        }
+    };
+    let groups: Vec<Vec<PathBuf>> = groups
+        .into_iter()
+        .map(|group| group.into_iter().map(|path| base.join(path).components().collect()).collect::<Vec<PathBuf>>())
+        .filter(|group| group.len() > 1)
+        .collect();
+    if groups.is_empty() {
+        return Err(format!("No duplicate sets found in the {} output", tool.label()));
+    }
+    Ok(ExternalResults { tool, groups })
+}
+
+// Groups end at a blank line; `-S` adds an "N bytes each:" line before each
+fn parse_fdupes(text: &str) -> Vec<Vec<String>> {
+    let mut groups = Vec::new();
+    let mut current = Vec::new();
+    for line in text.lines().map(|line| line.trim_end_matches('\r')) {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                groups.push(std::mem::take(&mut current));
+            }
+        } else if !is_size_line(line) {
+            current.push(line.to_string());
+        }
+    }
+    if !current.is_empty() {
+        groups.push(current);
+    }
+    groups
+}
+
+fn is_size_line(line: &str) -> bool {
+    let Some((count, rest)) = line.split_once(' ') else {
+        return false;
+    };
+    !count.is_empty() && count.bytes().all(|b| b.is_ascii_digit()) && (rest == "bytes each:" || rest == "byte each:")
+}
+
+// `duptype id depth size device inode priority name`: a duplicate carries
+// the negated id of its first occurrence. The name may contain spaces.
+fn parse_rdfind(text: &str) -> Vec<Vec<String>> {
+    let mut order = Vec::new();
+    let mut groups: HashMap<i64, Vec<String>> = HashMap::new();
+    for line in text.lines().map(|line| line.trim_end_matches('\r')).filter(|line| line.starts_with("DUPTYPE_")) {
+        let fields: Vec<&str> = line.splitn(8, ' ').collect();
+        let [_, id, _, _, _, _, _, name] = fields[..] else {
+            continue;
+        };
+        let Ok(id) = id.parse::<i64>() else {
+            continue;
+        };
+        let members = groups.entry(id.abs()).or_insert_with(|| {
+            order.push(id.abs());
+            Vec::new()
+        });
+        // The first occurrence leads its group whatever the line order
+        if id > 0 {
+            members.insert(0, name.to_string());
+        } else {
+            members.push(name.to_string());
+        }
+    }
+    order.into_iter().filter_map(|id| groups.remove(&id)).collect()
+}
+
+/// Groups as the tool reported them, described from disk without hashing.
+/// A set is split by size, as files of different sizes cannot hold the same
+/// content, and files that are gone are left out. Returns the groups and
+/// the number of files not found.
+pub fn unverified_groups(results: &ExternalResults, config: &ScanConfig) -> (Vec<HashGroup>, usize) {
+    let mut groups = Vec::new();
+    let mut missing = 0;
+    for set in &results.groups {
+        let mut by_size: BTreeMap<u64, Vec<FileInfo>> = BTreeMap::new();
+        for path in set {
+            match unhashed_file_info(path, config) {
+                Ok(file) => by_size.entry(file.size).or_default().push(file),
+                Err(_) => missing += 1,
+            }
+        }
+        for files in by_size.into_values().rev().filter(|files| files.len() > 1) {
+            groups.push(HashGroup { hash: String::new(), secondary_hash: None, files, known: None });
+        }
+    }
+    (groups, missing)
+}
+
+/// Duplicate sets in fdupes' plain output: one path per line, a blank line
+/// after each set. Kept files come first, as `fdupes -d` keeps the first.
+pub fn fdupes_text(sets: &[Vec<(&FileInfo, bool)>]) -> String {
+    let mut out = String::new();
+    for set in sets {
+        for (file, _) in set.iter().filter(|(_, keep)| *keep).chain(set.iter().filter(|(_, keep)| !*keep)) {
+            out.push_str(&file.path.to_path_buf().to_string_lossy());
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Duplicate sets as the JSON array `rmlint -o json` writes: a header, one
+/// `duplicate_file` entry per file with kept files marked `is_original`, and
+/// a footer with totals. Checksums are SHA-256, and empty for groups
+/// imported without one.
+pub fn rmlint_json(sets: &[Vec<(&FileInfo, bool)>]) -> serde_json::Result<String> {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let mut entries = vec![serde_json::json!({
+        "description": "rmlint json-dump of lint files",
+        "cwd": cwd.to_string_lossy(),
+        "args": concat!("dupe-finder-gui ", env!("CARGO_PKG_VERSION")),
+        "progress": 0,
+        "checksum_type": "sha256",
+        "merge_directories": false,
+    })];
+    let (mut duplicates, mut lint_size) = (0, 0);
+    for set in sets {
+        for (file, keep) in set {
+            if !keep {
+                duplicates += 1;
+                lint_size += file.size;
+            }
+            let mtime = file.modified_time.and_then(|time| time.duration_since(UNIX_EPOCH).ok()).map(|age| age.as_secs_f64());
+            entries.push(serde_json::json!({
+                "id": entries.len(),
+                "type": "duplicate_file",
+                "progress": 100,
+                "checksum": file.hash,
+                "path": file.path.to_path_buf().to_string_lossy(),
+                "size": file.size,
+                "inode": file.file_id.map(|id| id.inode),
+                "disk_id": file.file_id.map(|id| id.device),
+                "is_original": keep,
+                "mtime": mtime,
+            }));
+        }
+    }
+    entries.push(serde_json::json!({
+        "aborted": false,
+        "progress": 100,
+        "total_files": entries.len() - 1,
+        "ignored_files": 0,
+        "ignored_folders": 0,
+        "duplicates": duplicates,
+        "duplicate_sets": sets.len(),
+        "total_lint_size": lint_size,
+    }));
+    serde_json::to_string_pretty(&entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(group: &[PathBuf]) -> Vec<String> {
+        group.iter().map(|path| path.display().to_string()).collect()
+    }
+
+    #[test]
+    fn fdupes_groups_end_at_blank_lines() {
+        let text = "12 bytes each:\r\n/a/one\r\n/b/one copy\r\n\r\n\r\n/a/two\n/b/two\n/c/two\n\n/lonely\n\n/a/three\n/b/three";
+        let results = parse_external(text, Path::new("/base")).unwrap();
+        assert_eq!(results.tool, ExternalTool::Fdupes);
+        let groups: Vec<Vec<String>> = results.groups.iter().map(|g| paths(g)).collect();
+        assert_eq!(groups, vec![
+            vec!["/a/one", "/b/one copy"],
+            vec!["/a/two", "/b/two", "/c/two"],
+            vec!["/a/three", "/b/three"],
+        ]);
+    }
+
+    #[test]
+    fn fdupes_relative_paths_resolve_against_base() {
+        let results = parse_external("./x\nsub/../y\n", Path::new("/base")).unwrap();
+        assert_eq!(paths(&results.groups[0]), vec!["/base/x", "/base/sub/../y"]);
+    }
+
+    #[test]
+    fn size_lines_need_the_exact_suffix() {
+        assert!(is_size_line("1 byte each:"));
+        assert!(is_size_line("4096 bytes each:"));
+        assert!(!is_size_line("4096 bytes each"));
+        assert!(!is_size_line("12ab bytes each:"));
+        assert!(!is_size_line(" bytes each:"));
+    }
+
+    #[test]
+    fn rdfind_groups_by_id_with_first_occurrence_leading() {
+        let text = "\
+# Automatically generated
+# duptype id depth size device inode priority name
+DUPTYPE_WITHIN_SAME_TREE -7 1 4 2049 11 1 dir/b with spaces.txt
+DUPTYPE_FIRST_OCCURRENCE 7 0 4 2049 10 1 a.txt
+DUPTYPE_FIRST_OCCURRENCE 9 0 8 2049 20 1 c.bin
+DUPTYPE_OUTSIDE_TREE -9 0 8 2049 21 2 /other/c.bin
+DUPTYPE_FIRST_OCCURRENCE x 0 8 2049 22 1 bad id
+DUPTYPE_FIRST_OCCURRENCE 3 0 8
+# end of file
+";
+        let results = parse_external(text, Path::new("/run")).unwrap();
+        assert_eq!(results.tool, ExternalTool::Rdfind);
+        let groups: Vec<Vec<String>> = results.groups.iter().map(|g| paths(g)).collect();
+        assert_eq!(groups, vec![
+            vec!["/run/a.txt", "/run/dir/b with spaces.txt"],
+            vec!["/run/c.bin", "/other/c.bin"],
+        ]);
+    }
+
+    #[test]
+    fn rmlint_json_groups_by_checksum_with_original_first() {
+        let text = r#"[
+            {"description": "rmlint json-dump of lint files", "cwd": "/home"},
+            {"type": "duplicate_file", "path": "/d/copy", "checksum": "aa", "size": 5, "is_original": false},
+            {"type": "duplicate_file", "path": "/d/orig", "checksum": "aa", "size": 5, "is_original": true},
+            {"type": "emptyfile", "path": "/d/empty", "size": 0},
+            {"type": "duplicate_file", "path": "/e/one", "checksum": "bb", "size": 9},
+            {"type": "duplicate_file", "path": "/e/two", "checksum": "bb", "size": 9},
+            {"type": "duplicate_file", "path": "/e/alone", "checksum": "cc", "size": 9},
+            {"aborted": false, "progress": 100}
+        ]"#;
+        let results = parse_external(text, Path::new("/")).unwrap();
+        assert_eq!(results.tool, ExternalTool::Rmlint);
+        let groups: Vec<Vec<String>> = results.groups.iter().map(|g| paths(g)).collect();
+        assert_eq!(groups, vec![vec!["/d/orig", "/d/copy"], vec!["/e/one", "/e/two"]]);
+    }
+
+    #[test]
+    fn malformed_json_and_empty_output_are_errors() {
+        assert!(parse_external("[{\"type\": ", Path::new("/")).is_err());
+        assert!(parse_external("{\"matchSets\": 3}", Path::new("/")).is_err());
+        assert!(parse_external("\n\n/only/one\n\n", Path::new("/")).is_err());
+    }
+}
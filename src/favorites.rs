@@ -0,0 +1,36 @@
+use std::io;
+use serde::{Deserialize, Serialize};
+use crate::settings::{load_json, save_json};
+
+const FAVORITES_FILE: &str = "favorite-dirs.json";
+
+/// Folders pinned to the directory dropdown, kept across sessions
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FavoriteDirs {
+    /// In the order they were pinned
+    #[serde(default)]
+    pub pinned: Vec<String>,
+}
+
+impl FavoriteDirs {
+    pub fn load() -> Self {
+        load_json(FAVORITES_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        save_json(FAVORITES_FILE, self)
+    }
+
+    pub fn is_pinned(&self, dir: &str) -> bool {
+        self.pinned.iter().any(|pinned| pinned == dir)
+    }
+
+    /// Pin `dir`, or unpin it when it already is
+    pub fn toggle(&mut self, dir: &str) {
+        if self.is_pinned(dir) {
+            self.pinned.retain(|pinned| pinned != dir);
+        } else {
+            self.pinned.push(dir.to_string());
+        }
+    }
+}
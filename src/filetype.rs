@@ -0,0 +1,161 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FileCategory {
+    Image,
+    Video,
+    Audio,
+    Document,
+    Archive,
+    Executable,
+    Text,
+    Other,
+}
+
+impl FileCategory {
+    pub const ALL: [FileCategory; 8] = [
+        FileCategory::Image,
+        FileCategory::Video,
+        FileCategory::Audio,
+        FileCategory::Document,
+        FileCategory::Archive,
+        FileCategory::Executable,
+        FileCategory::Text,
+        FileCategory::Other,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FileCategory::Image => "Images",
+            FileCategory::Video => "Video",
+            FileCategory::Audio => "Audio",
+            FileCategory::Document => "Documents",
+            FileCategory::Archive => "Archives",
+            FileCategory::Executable => "Executables",
+            FileCategory::Text => "Text",
+            FileCategory::Other => "Other",
+        }
+    }
+
+    /// Map a MIME type produced by `detect_type` to its broad category
+    pub fn from_mime(mime: &str) -> Self {
+        match mime {
+            m if m.starts_with("image/") => FileCategory::Image,
+            m if m.starts_with("video/") => FileCategory::Video,
+            m if m.starts_with("audio/") => FileCategory::Audio,
+            m if m.starts_with("text/") => FileCategory::Text,
+            "application/pdf" | "application/rtf" | "application/x-ole-storage" | "application/vnd.sqlite3" => FileCategory::Document,
+            "application/zip" | "application/gzip" | "application/x-7z-compressed" | "application/vnd.rar"
+            | "application/x-xz" | "application/x-bzip2" | "application/zstd" | "application/x-tar" => FileCategory::Archive,
+            "application/x-elf" | "application/x-msdownload" | "application/x-mach-binary" => FileCategory::Executable,
+            _ => FileCategory::Other,
+        }
+    }
+}
+
+// Enough to reach the tar "ustar" marker at offset 257
+const SNIFF_LEN: usize = 512;
+
+/// Detect a file's MIME type from its leading bytes, ignoring its extension
+pub fn detect_type(path: &Path) -> io::Result<&'static str> {
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    File::open(path)?.take(SNIFF_LEN as u64).read_to_end(&mut header)?;
+    Ok(sniff(&header))
+}
+
+pub fn sniff(bytes: &[u8]) -> &'static str {
+    let starts = |magic: &[u8]| bytes.starts_with(magic);
+    let at = |offset: usize, magic: &[u8]| bytes.get(offset..offset + magic.len()) == Some(magic);
+
+    if starts(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if starts(b"\xff\xd8\xff") {
+        "image/jpeg"
+    } else if starts(b"GIF87a") || starts(b"GIF89a") {
+        "image/gif"
+    } else if starts(b"RIFF") && at(8, b"WEBP") {
+        "image/webp"
+    } else if starts(b"RIFF") && at(8, b"WAVE") {
+        "audio/wav"
+    } else if starts(b"RIFF") && at(8, b"AVI ") {
+        "video/x-msvideo"
+    } else if starts(b"II*\0") || starts(b"MM\0*") {
+        "image/tiff"
+    } else if starts(b"\0\0\x01\0") {
+        "image/x-icon"
+    } else if starts(b"BM") && bytes.len() > 14 {
+        "image/bmp"
+    } else if at(4, b"ftyp") {
+        match bytes.get(8..12) {
+            Some(b"qt  ") => "video/quicktime",
+            Some(b"heic") | Some(b"heix") | Some(b"mif1") => "image/heic",
+            Some(b"avif") => "image/avif",
+            Some(b"M4A ") => "audio/mp4",
+            _ => "video/mp4",
+        }
+    } else if starts(b"\x1a\x45\xdf\xa3") {
+        "video/x-matroska"
+    } else if starts(b"ID3") || starts(b"\xff\xfb") || starts(b"\xff\xf3") {
+        "audio/mpeg"
+    } else if starts(b"fLaC") {
+        "audio/flac"
+    } else if starts(b"OggS") {
+        "audio/ogg"
+    } else if starts(b"%PDF") {
+        "application/pdf"
+    } else if starts(b"{\\rtf") {
+        "application/rtf"
+    } else if starts(b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1") {
+        "application/x-ole-storage"
+    } else if starts(b"SQLite format 3\0") {
+        "application/vnd.sqlite3"
+    } else if starts(b"PK\x03\x04") {
+        "application/zip"
+    } else if starts(b"\x1f\x8b") {
+        "application/gzip"
+    } else if starts(b"7z\xbc\xaf\x27\x1c") {
+        "application/x-7z-compressed"
+    } else if starts(b"Rar!\x1a\x07") {
+        "application/vnd.rar"
+    } else if starts(b"\xfd7zXZ\0") {
+        "application/x-xz"
+    } else if starts(b"BZh") {
+        "application/x-bzip2"
+    } else if starts(b"\x28\xb5\x2f\xfd") {
+        "application/zstd"
+    } else if at(257, b"ustar") {
+        "application/x-tar"
+    } else if starts(b"\x7fELF") {
+        "application/x-elf"
+    } else if starts(b"MZ") {
+        "application/x-msdownload"
+    } else if starts(b"\xfe\xed\xfa\xce") || starts(b"\xfe\xed\xfa\xcf")
+        || starts(b"\xce\xfa\xed\xfe") || starts(b"\xcf\xfa\xed\xfe") {
+        "application/x-mach-binary"
+    } else {
+        sniff_text(bytes)
+    }
+}
+
+fn sniff_text(bytes: &[u8]) -> &'static str {
+    if bytes.contains(&0) {
+        return "application/octet-stream";
+    }
+    // The sniffed prefix may cut a multi-byte character in half
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default(),
+        Err(_) => return "application/octet-stream",
+    };
+    let lower = text.trim_start().to_ascii_lowercase();
+    if lower.starts_with("<?xml") {
+        "text/xml"
+    } else if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+        "text/html"
+    } else {
+        "text/plain"
+    }
+}
@@ -0,0 +1,322 @@
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+/// Whether sizes are shown in powers of 1024 (KiB, MiB) or 1000 (kB, MB)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SizeUnits {
+    #[default]
+    Binary,
+    Decimal,
+}
+
+impl SizeUnits {
+    pub fn label(self) -> &'static str {
+        match self {
+            SizeUnits::Binary => "Binary (KiB, MiB)",
+            SizeUnits::Decimal => "Decimal (kB, MB)",
+        }
+    }
+}
+
+/// How file dates are shown in the results list
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DateStyle {
+    /// "3 days ago"
+    #[default]
+    Relative,
+    /// The calendar date in the locale's order
+    Absolute,
+    Both,
+}
+
+impl DateStyle {
+    pub fn label(self) -> &'static str {
+        match self {
+            DateStyle::Relative => "Relative",
+            DateStyle::Absolute => "Date",
+            DateStyle::Both => "Date and age",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormatSettings {
+    pub units: SizeUnits,
+    pub dates: DateStyle,
+    /// Locale name such as "de_DE"; empty to use LC_ALL / LC_NUMERIC / LANG
+    #[serde(default)]
+    pub locale: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DateOrder {
+    YearMonthDay,
+    DayMonthYear,
+    MonthDayYear,
+}
+
+/// Separators and date layout for one locale
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Locale {
+    decimal: char,
+    thousands: char,
+    date_order: DateOrder,
+    date_separator: char,
+}
+
+const DEFAULT_LOCALE: Locale = Locale { decimal: '.', thousands: ',', date_order: DateOrder::YearMonthDay, date_separator: '-' };
+
+// Languages writing "1.234,5" and "1 234,5" respectively; the rest use "1,234.5"
+const DOT_GROUPING: &[&str] = &["de", "es", "it", "pt", "nl", "da", "tr", "id", "el", "ro", "hr", "sl", "sr"];
+const SPACE_GROUPING: &[&str] = &["fr", "ru", "pl", "cs", "sk", "sv", "fi", "nb", "nn", "no", "uk", "hu", "bg", "lt", "lv", "et"];
+// Languages writing dates as 31.12.2024, and as 31/12/2024
+const DOTTED_DATES: &[&str] = &["de", "ru", "pl", "cs", "sk", "fi", "nb", "nn", "no", "da", "tr", "uk", "ro", "hr", "sl", "sr", "bg", "et", "lv"];
+const SLASHED_DATES: &[&str] = &["fr", "es", "it", "pt", "el", "id", "en"];
+// English-speaking territories that put the month first
+const MONTH_FIRST_TERRITORIES: &[&str] = &["US", "PH", "PR"];
+
+impl Locale {
+    /// Parse a POSIX locale name like "de_DE.UTF-8@euro"
+    fn from_name(name: &str) -> Locale {
+        let name = name.split(['.', '@']).next().unwrap_or_default();
+        let (language, territory) = name.split_once(['_', '-']).unwrap_or((name, ""));
+        let language = language.to_ascii_lowercase();
+        let language = language.as_str();
+        if language.is_empty() || language == "c" || language == "posix" {
+            return DEFAULT_LOCALE;
+        }
+
+        let (decimal, thousands) = if territory.eq_ignore_ascii_case("CH") {
+            ('.', '\'')
+        } else if DOT_GROUPING.contains(&language) {
+            (',', '.')
+        } else if SPACE_GROUPING.contains(&language) {
+            (',', '\u{a0}')
+        } else {
+            ('.', ',')
+        };
+        let (date_order, date_separator) = if language == "en" && MONTH_FIRST_TERRITORIES.iter().any(|t| t.eq_ignore_ascii_case(territory)) {
+            (DateOrder::MonthDayYear, '/')
+        } else if DOTTED_DATES.contains(&language) {
+            (DateOrder::DayMonthYear, '.')
+        } else if SLASHED_DATES.contains(&language) {
+            (DateOrder::DayMonthYear, '/')
+        } else {
+            (DateOrder::YearMonthDay, '-')
+        };
+        Locale { decimal, thousands, date_order, date_separator }
+    }
+
+    fn from_environment() -> Locale {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .map_or(DEFAULT_LOCALE, |name| Locale::from_name(&name))
+    }
+}
+
+struct Active {
+    units: SizeUnits,
+    dates: DateStyle,
+    locale: Option<Locale>,
+}
+
+// Formatting is needed deep inside reports and summaries, so the chosen
+// settings are process-wide rather than passed down every call chain
+static ACTIVE: RwLock<Active> = RwLock::new(Active { units: SizeUnits::Binary, dates: DateStyle::Relative, locale: None });
+
+/// Make `settings` the formatting used from now on
+pub fn apply_settings(settings: &FormatSettings) {
+    let locale = if settings.locale.trim().is_empty() { Locale::from_environment() } else { Locale::from_name(settings.locale.trim()) };
+    *ACTIVE.write().unwrap() = Active { units: settings.units, dates: settings.dates, locale: Some(locale) };
+}
+
+fn active() -> (SizeUnits, DateStyle, Locale) {
+    let active = ACTIVE.read().unwrap();
+    (active.units, active.dates, active.locale.unwrap_or(DEFAULT_LOCALE))
+}
+
+fn group_thousands(digits: &str, separator: char) -> String {
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(separator);
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Whole number with the locale's thousands separator, e.g. "1,234,567"
+pub fn format_count(n: u64) -> String {
+    group_thousands(&n.to_string(), active().2.thousands)
+}
+
+fn format_decimal(value: f64, places: usize, locale: Locale) -> String {
+    let text = format!("{:.*}", places, value);
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+    let mut out = group_thousands(whole, locale.thousands);
+    if !fraction.is_empty() {
+        out.push(locale.decimal);
+        out.push_str(fraction);
+    }
+    out
+}
+
+/// Human-readable size in the preferred units, e.g. "1.50 MiB" or "1,57 MB"
+pub fn format_size(bytes: u64) -> String {
+    let (units, _, locale) = active();
+    let (base, names) = match units {
+        SizeUnits::Binary => (1024.0, ["KiB", "MiB", "GiB", "TiB", "PiB"]),
+        SizeUnits::Decimal => (1000.0, ["kB", "MB", "GB", "TB", "PB"]),
+    };
+    let mut value = bytes as f64;
+    if value < base {
+        return format!("{} B", group_thousands(&bytes.to_string(), locale.thousands));
+    }
+    let mut unit = names[0];
+    value /= base;
+    for name in &names[1..] {
+        // Step up early rather than print "1024.00 KiB" after rounding
+        if value < base - 0.005 {
+            break;
+        }
+        value /= base;
+        unit = name;
+    }
+    format!("{} {}", format_decimal(value, 2, locale), unit)
+}
+
+fn split_timestamp(time: SystemTime) -> ((i64, u32, u32), i64) {
+    let secs = match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    };
+    (civil_from_days(secs.div_euclid(86_400)), secs.rem_euclid(86_400))
+}
+
+fn format_date(date: (i64, u32, u32), locale: Locale) -> String {
+    let (year, month, day) = date;
+    let sep = locale.date_separator;
+    match locale.date_order {
+        DateOrder::YearMonthDay => format!("{:04}{}{:02}{}{:02}", year, sep, month, sep, day),
+        DateOrder::DayMonthYear => format!("{:02}{}{:02}{}{:04}", day, sep, month, sep, year),
+        DateOrder::MonthDayYear => format!("{:02}{}{:02}{}{:04}", month, sep, day, sep, year),
+    }
+}
+
+/// Format a timestamp as the locale's date followed by "HH:MM:SS UTC"
+pub fn format_timestamp(time: SystemTime) -> String {
+    let (date, rem) = split_timestamp(time);
+    format!("{} {:02}:{:02}:{:02} UTC", format_date(date, active().2), rem / 3600, (rem % 3600) / 60, rem % 60)
+}
+
+/// "YYYYMMDDTHHMMSSZ", the compact UTC form used to sign S3 requests
+pub fn format_compact_utc(time: SystemTime) -> String {
+    let ((year, month, day), rem) = split_timestamp(time);
+    format!("{:04}{:02}{:02}T{:02}{:02}{:02}Z", year, month, day, rem / 3600, (rem % 3600) / 60, rem % 60)
+}
+
+/// "YYYY-MM-DDTHH:MM:SSZ", the fixed form used in the log file and in CSV exports
+pub fn format_iso_utc(time: SystemTime) -> String {
+    let ((year, month, day), rem) = split_timestamp(time);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, rem / 3600, (rem % 3600) / 60, rem % 60)
+}
+
+/// Parse a UTC timestamp such as "2024-05-01T12:30:00.000Z"; fractions of a second are dropped
+pub fn parse_utc_timestamp(text: &str) -> Option<SystemTime> {
+    let text = text.trim().strip_suffix('Z')?;
+    let (date, time) = text.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let time = time.split('.').next()?;
+    let mut time = time.splitn(3, ':').map(str::parse::<i64>);
+    let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let secs = days_from_civil(year, month as u32, day as u32) * 86_400 + hour * 3600 + minute * 60 + second;
+    u64::try_from(secs).ok().map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// A file's date for the results list, in the chosen date style
+pub fn format_file_date(time: SystemTime) -> String {
+    let (_, dates, locale) = active();
+    // Timestamps in the future (clock skew) count as today
+    let relative = match time.elapsed().map_or(0, |age| age.as_secs() / 86_400) {
+        0 => "today".to_string(),
+        1 => "1 day ago".to_string(),
+        days => format!("{} days ago", group_thousands(&days.to_string(), locale.thousands)),
+    };
+    match dates {
+        DateStyle::Relative => relative,
+        DateStyle::Absolute => format_date(split_timestamp(time).0, locale),
+        DateStyle::Both => format!("{}, {}", format_date(split_timestamp(time).0, locale), relative),
+    }
+}
+
+// Howard Hinnant's days-to-civil conversion for the proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+// The inverse of civil_from_days
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from(if month > 2 { month - 3 } else { month + 9 });
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_map_to_dates_across_leap_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        // 2000 is a leap year as a multiple of 400; 1900 and 2100 are not
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(11_017), (2000, 3, 1));
+        assert_eq!(civil_from_days(-25_508), (1900, 3, 1));
+        assert_eq!(civil_from_days(-25_509), (1900, 2, 28));
+        assert_eq!(civil_from_days(47_541), (2100, 3, 1));
+        assert_eq!(civil_from_days(47_540), (2100, 2, 28));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+        assert_eq!(civil_from_days(20_088), (2024, 12, 31));
+    }
+
+    #[test]
+    fn days_from_civil_reverses_civil_from_days() {
+        for days in (-800_000..800_000).step_by(97) {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn utc_timestamps_parse_and_format_back() {
+        let time = parse_utc_timestamp("2024-02-29T23:59:58.250Z").unwrap();
+        assert_eq!(format_iso_utc(time), "2024-02-29T23:59:58Z");
+        assert_eq!(format_compact_utc(time), "20240229T235958Z");
+        assert!(parse_utc_timestamp("2024-13-01T00:00:00Z").is_none());
+        assert!(parse_utc_timestamp("2024-02-29T00:00:00").is_none());
+        assert!(parse_utc_timestamp("1969-12-31T23:59:59Z").is_none());
+    }
+}
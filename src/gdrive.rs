@@ -0,0 +1,472 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use crate::format::parse_utc_timestamp;
+use crate::s3::uri_encode;
+use crate::scanner::{percent_decode, ScanConfig};
+use crate::source::{EntryKind, FileSource, RemoteSource, SourceEntry, SourceMetadata};
+
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const API_URL: &str = "https://www.googleapis.com/drive/v3";
+// Full access, as moving files to the trash needs more than read-only access
+const SCOPE: &str = "https://www.googleapis.com/auth/drive";
+const FOLDER_TYPE: &str = "application/vnd.google-apps.folder";
+const SIGN_IN_TIMEOUT: Duration = Duration::from_secs(300);
+const ACCEPT_POLL: Duration = Duration::from_millis(200);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+// Tokens are refreshed this long before they expire
+const TOKEN_MARGIN: Duration = Duration::from_secs(60);
+pub const DEFAULT_DOWNLOADS: usize = 4;
+pub const MAX_DOWNLOADS: usize = 16;
+
+/// OAuth client and options for signing in to Google Drive; never saved
+#[derive(Clone, Debug)]
+pub struct DriveLogin {
+    /// The OAuth client of a "Desktop app" created in the Google Cloud console
+    pub client_id: String,
+    pub client_secret: String,
+    /// Folder path in My Drive to scan, e.g. "Photos/2023"; empty scans all of it
+    pub folder: String,
+    /// Files downloaded at once, when they are compared with local files
+    pub downloads: usize,
+}
+
+impl Default for DriveLogin {
+    fn default() -> Self {
+        Self {
+            client_id: std::env::var("GOOGLE_CLIENT_ID").unwrap_or_default(),
+            client_secret: std::env::var("GOOGLE_CLIENT_SECRET").unwrap_or_default(),
+            folder: String::new(),
+            downloads: DEFAULT_DOWNLOADS,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+    refresh_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiFile {
+    id: String,
+    name: String,
+    mime_type: String,
+    #[serde(default)]
+    parents: Vec<String>,
+    /// Sizes are sent as strings; Google Docs files have none
+    size: Option<String>,
+    md5_checksum: Option<String>,
+    modified_time: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileList {
+    next_page_token: Option<String>,
+    #[serde(default)]
+    files: Vec<ApiFile>,
+}
+
+fn random_token() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+fn describe_error(error: ureq::Error) -> String {
+    match error {
+        ureq::Error::Status(code, response) => {
+            let body = response.into_string().unwrap_or_default();
+            let message = serde_json::from_str::<serde_json::Value>(&body).ok().and_then(|json| {
+                let error = &json["error"];
+                error["message"].as_str().or_else(|| json["error_description"].as_str()).or_else(|| error.as_str()).map(str::to_string)
+            });
+            match message {
+                Some(message) => format!("{}: {}", code, message),
+                None => format!("HTTP {}", code),
+            }
+        }
+        ureq::Error::Transport(transport) => transport.to_string(),
+    }
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(response: ureq::Response) -> Result<T, String> {
+    let body = response.into_string().map_err(|e| e.to_string())?;
+    serde_json::from_str(&body).map_err(|e| format!("Unexpected reply from Google: {}", e))
+}
+
+/// A sign-in waiting for the browser to send Google's answer to a local port
+pub struct PendingSignIn {
+    listener: TcpListener,
+    /// Where the user signs in; opened in the browser and shown as a link
+    pub url: String,
+    redirect_uri: String,
+    verifier: String,
+    state: String,
+}
+
+/// Start an OAuth sign-in with PKCE, answered on a loopback port as Google
+/// recommends for desktop apps
+pub fn begin_sign_in(login: &DriveLogin) -> Result<PendingSignIn, String> {
+    let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| format!("Cannot listen for the sign-in: {}", e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let redirect_uri = format!("http://127.0.0.1:{}", port);
+    let verifier = random_token()?;
+    let state = random_token()?;
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    let params = [
+        ("client_id", login.client_id.trim()),
+        ("redirect_uri", redirect_uri.as_str()),
+        ("response_type", "code"),
+        ("scope", SCOPE),
+        ("code_challenge", challenge.as_str()),
+        ("code_challenge_method", "S256"),
+        ("state", state.as_str()),
+        ("access_type", "offline"),
+    ];
+    let query = params.iter().map(|(name, value)| format!("{}={}", name, uri_encode(value, true))).collect::<Vec<_>>().join("&");
+    Ok(PendingSignIn { listener, url: format!("{}?{}", AUTH_URL, query), redirect_uri, verifier, state })
+}
+
+impl PendingSignIn {
+    /// Wait for the browser's redirect, then trade its code for tokens
+    pub fn finish(self, login: &DriveLogin) -> Result<DriveAccount, String> {
+        let code = self.wait_for_code()?;
+        let agent = ureq::AgentBuilder::new().timeout_connect(CONNECT_TIMEOUT).timeout_read(READ_TIMEOUT).build();
+        let response = agent
+            .post(TOKEN_URL)
+            .send_form(&[
+                ("code", code.as_str()),
+                ("client_id", login.client_id.trim()),
+                ("client_secret", login.client_secret.trim()),
+                ("redirect_uri", self.redirect_uri.as_str()),
+                ("grant_type", "authorization_code"),
+                ("code_verifier", self.verifier.as_str()),
+            ])
+            .map_err(describe_error)?;
+        let token: TokenResponse = read_json(response)?;
+        DriveAccount::new(agent, login, token)
+    }
+
+    fn wait_for_code(&self) -> Result<String, String> {
+        self.listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+        let deadline = Instant::now() + SIGN_IN_TIMEOUT;
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    if let Some(answer) = self.read_redirect(stream) {
+                        return answer;
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    if Instant::now() > deadline {
+                        return Err("Sign-in timed out; nothing came back from the browser".to_string());
+                    }
+                    thread::sleep(ACCEPT_POLL);
+                }
+                Err(e) => return Err(format!("Sign-in failed: {}", e)),
+            }
+        }
+    }
+
+    // None for requests that are not Google's redirect, e.g. for a favicon
+    fn read_redirect(&self, mut stream: TcpStream) -> Option<Result<String, String>> {
+        stream.set_nonblocking(false).ok()?;
+        stream.set_read_timeout(Some(READ_TIMEOUT)).ok()?;
+        let mut request_line = String::new();
+        BufReader::new(&stream).read_line(&mut request_line).ok()?;
+        let target = request_line.split_whitespace().nth(1)?;
+        let params: HashMap<String, String> = target
+            .split_once('?')
+            .map(|(_, query)| query)
+            .unwrap_or_default()
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(name, value)| (percent_decode(&name.replace('+', " ")), percent_decode(&value.replace('+', " "))))
+            .collect();
+        let answer = if let Some(error) = params.get("error") {
+            Err(format!("Google refused the sign-in: {}", error))
+        } else if let Some(code) = params.get("code") {
+            if params.get("state") == Some(&self.state) {
+                Ok(code.clone())
+            } else {
+                Err("The sign-in answer did not match the request; try again".to_string())
+            }
+        } else {
+            let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+            return None;
+        };
+        let page = match &answer {
+            Ok(_) => "Signed in. You can close this tab and return to Dupe Finder.",
+            Err(_) => "Sign-in failed. Return to Dupe Finder for details.",
+        };
+        let _ = write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nConnection: close\r\n\r\n<html><body><h3>{}</h3></body></html>",
+            page
+        );
+        Some(answer)
+    }
+}
+
+struct AccessToken {
+    value: String,
+    expires: Instant,
+    refresh: Option<String>,
+}
+
+/// A file in the Drive listing
+#[derive(Clone, Debug)]
+struct DriveFile {
+    id: String,
+    size: u64,
+    md5: Option<String>,
+    modified: Option<SystemTime>,
+}
+
+/// A signed-in Google Drive. Files appear in scans as "gdrive://account/folder/name".
+pub struct DriveAccount {
+    agent: ureq::Agent,
+    client_id: String,
+    client_secret: String,
+    token: Mutex<AccessToken>,
+    prefix: PathBuf,
+    downloads: usize,
+    /// Files found by the last listing, by the path they are shown under
+    files: Mutex<HashMap<PathBuf, DriveFile>>,
+}
+
+impl fmt::Debug for DriveAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DriveAccount").field("prefix", &self.prefix).finish()
+    }
+}
+
+impl DriveAccount {
+    fn new(agent: ureq::Agent, login: &DriveLogin, token: TokenResponse) -> Result<Self, String> {
+        let mut account = Self {
+            agent,
+            client_id: login.client_id.trim().to_string(),
+            client_secret: login.client_secret.trim().to_string(),
+            token: Mutex::new(AccessToken {
+                value: token.access_token,
+                expires: Instant::now() + Duration::from_secs(token.expires_in),
+                refresh: token.refresh_token,
+            }),
+            prefix: PathBuf::new(),
+            downloads: login.downloads.clamp(1, MAX_DOWNLOADS),
+            files: Mutex::default(),
+        };
+        let about: serde_json::Value = read_json(account.get(&format!("{}/about", API_URL), &[("fields", "user(emailAddress)")])?)?;
+        let email = about["user"]["emailAddress"].as_str().unwrap_or("drive");
+        account.prefix = PathBuf::from(format!("gdrive://{}", email));
+        Ok(account)
+    }
+
+    /// The scan root for `folder` in My Drive
+    pub fn root_for(&self, folder: &str) -> String {
+        match folder.trim().trim_matches('/') {
+            "" => self.prefix.display().to_string(),
+            folder => format!("{}/{}", self.prefix.display(), folder),
+        }
+    }
+
+    fn access_token(&self) -> Result<String, String> {
+        let mut token = self.token.lock().unwrap();
+        if Instant::now() + TOKEN_MARGIN < token.expires {
+            return Ok(token.value.clone());
+        }
+        let refresh = token.refresh.clone().ok_or("The Google sign-in has expired; sign in again")?;
+        let response = self.agent
+            .post(TOKEN_URL)
+            .send_form(&[
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+                ("refresh_token", refresh.as_str()),
+                ("grant_type", "refresh_token"),
+            ])
+            .map_err(describe_error)?;
+        let refreshed: TokenResponse = read_json(response)?;
+        token.value = refreshed.access_token;
+        token.expires = Instant::now() + Duration::from_secs(refreshed.expires_in);
+        Ok(token.value.clone())
+    }
+
+    fn get(&self, url: &str, query: &[(&str, &str)]) -> Result<ureq::Response, String> {
+        let mut request = self.agent.get(url).set("Authorization", &format!("Bearer {}", self.access_token()?));
+        for (name, value) in query {
+            request = request.query(name, value);
+        }
+        request.call().map_err(describe_error)
+    }
+
+    /// Every file and folder in My Drive that is not in the trash
+    fn list_all(&self) -> Result<Vec<ApiFile>, String> {
+        let mut files = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("q", "trashed = false"),
+                ("pageSize", "1000"),
+                ("fields", "nextPageToken, files(id, name, mimeType, parents, size, md5Checksum, modifiedTime)"),
+            ];
+            if let Some(token) = &page_token {
+                query.push(("pageToken", token));
+            }
+            let page: FileList = read_json(self.get(&format!("{}/files", API_URL), &query)?)?;
+            files.extend(page.files);
+            match page.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => return Ok(files),
+            }
+        }
+    }
+
+    /// List the drive again and give every file a path from its folders.
+    /// Drive allows equal names in one folder, so later ones get their ID appended.
+    fn refresh_listing(&self) -> Result<(), String> {
+        let root: serde_json::Value = read_json(self.get(&format!("{}/files/root", API_URL), &[("fields", "id")])?)?;
+        let root_id = root["id"].as_str().unwrap_or_default().to_string();
+        let entries = self.list_all()?;
+        let folders: HashMap<&str, (&str, Option<&str>)> = entries
+            .iter()
+            .filter(|entry| entry.mime_type == FOLDER_TYPE)
+            .map(|entry| (entry.id.as_str(), (entry.name.as_str(), entry.parents.first().map(String::as_str))))
+            .collect();
+        // Folder path below My Drive, or None for files shared from elsewhere
+        let folder_path = |parent: &str| -> Option<PathBuf> {
+            let mut id = parent;
+            let mut names = Vec::new();
+            while id != root_id {
+                let (name, parent) = folders.get(id)?;
+                names.push(name.replace('/', "_"));
+                id = (*parent)?;
+                if names.len() > 256 {
+                    return None;
+                }
+            }
+            Some(names.iter().rev().collect())
+        };
+
+        let mut files = HashMap::new();
+        for entry in entries.iter().filter(|entry| entry.mime_type != FOLDER_TYPE) {
+            // Google Docs, shortcuts and other native files have no bytes to compare
+            let Some(size) = entry.size.as_deref().and_then(|size| size.parse().ok()) else {
+                continue;
+            };
+            let Some(dir) = entry.parents.first().and_then(|parent| folder_path(parent)) else {
+                continue;
+            };
+            let name = entry.name.replace('/', "_");
+            let mut path = self.prefix.join(&dir).join(&name);
+            if files.contains_key(&path) {
+                let (stem, extension) = match name.rfind('.').filter(|&dot| dot > 0) {
+                    Some(dot) => name.split_at(dot),
+                    None => (name.as_str(), ""),
+                };
+                path = self.prefix.join(&dir).join(format!("{} [{}]{}", stem, entry.id, extension));
+            }
+            files.insert(path, DriveFile {
+                id: entry.id.clone(),
+                size,
+                md5: entry.md5_checksum.as_ref().map(|md5| format!("md5:{}", md5.to_ascii_lowercase())),
+                modified: entry.modified_time.as_deref().and_then(parse_utc_timestamp),
+            });
+        }
+        *self.files.lock().unwrap() = files;
+        Ok(())
+    }
+
+    fn file(&self, path: &Path) -> io::Result<DriveFile> {
+        self.files.lock().unwrap().get(path).cloned().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} is not in the drive", path.display())))
+    }
+
+    /// Size of the file at `path` as Drive reports it now
+    pub fn current_size(&self, path: &Path) -> Result<u64, String> {
+        let file = self.file(path).map_err(|e| e.to_string())?;
+        let current: serde_json::Value = read_json(self.get(&format!("{}/files/{}", API_URL, file.id), &[("fields", "size, trashed")])?)?;
+        if current["trashed"].as_bool() == Some(true) {
+            return Err(format!("{} is already in the trash", path.display()));
+        }
+        current["size"].as_str().and_then(|size| size.parse().ok()).ok_or_else(|| format!("{} has no size", path.display()))
+    }
+
+    /// Move the file at `path` to the Drive trash, from where it can be restored for 30 days
+    pub fn trash(&self, path: &Path) -> Result<(), String> {
+        let file = self.file(path).map_err(|e| e.to_string())?;
+        self.agent
+            .request("PATCH", &format!("{}/files/{}", API_URL, file.id))
+            .set("Authorization", &format!("Bearer {}", self.access_token()?))
+            .set("Content-Type", "application/json")
+            .send_string(r#"{"trashed": true}"#)
+            .map_err(describe_error)?;
+        self.files.lock().unwrap().remove(path);
+        Ok(())
+    }
+}
+
+impl FileSource for DriveAccount {
+    fn list<'a>(&'a self, root: &Path, max_depth: Option<usize>, config: &'a ScanConfig) -> Box<dyn Iterator<Item = SourceEntry> + 'a> {
+        if self.refresh_listing().is_err() {
+            return Box::new(std::iter::empty());
+        }
+        let root = root.to_path_buf();
+        let mut paths: Vec<PathBuf> = self.files
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|path| {
+                let Ok(relative) = path.strip_prefix(&root) else {
+                    return false;
+                };
+                let depth = relative.components().count();
+                let hidden = relative.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+                max_depth.is_none_or(|max| depth <= max) && (config.include_hidden || !hidden)
+            })
+            .cloned()
+            .collect();
+        paths.sort();
+        let files = paths.into_iter().map(|path| SourceEntry { path, kind: EntryKind::File });
+        Box::new(std::iter::once(SourceEntry { path: root.clone(), kind: EntryKind::Dir }).chain(files))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<SourceMetadata> {
+        let file = self.file(path)?;
+        Ok(SourceMetadata { size: file.size, modified: file.modified, digest: file.md5, ..SourceMetadata::default() })
+    }
+
+    fn open(&self, path: &Path) -> io::Result<(Box<dyn Read + Send>, u64)> {
+        let file = self.file(path)?;
+        let response = self.get(&format!("{}/files/{}", API_URL, file.id), &[("alt", "media")]).map_err(io::Error::other)?;
+        Ok((Box::new(response.into_reader()), file.size))
+    }
+
+    fn max_readers(&self) -> Option<usize> {
+        Some(self.downloads)
+    }
+}
+
+impl RemoteSource for DriveAccount {
+    fn prefix(&self) -> &Path {
+        &self.prefix
+    }
+
+    fn label(&self) -> String {
+        format!("{} ({} file(s) listed)", self.prefix.display(), self.files.lock().unwrap().len())
+    }
+}
@@ -0,0 +1,75 @@
+use std::io;
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use crate::settings::{load_json, save_json};
+
+const GOAL_FILE: &str = "goal.json";
+/// Goal amounts are entered in decimal gigabytes, as drives are sold
+pub const GOAL_UNIT: u64 = 1_000_000_000;
+
+/// Space the user means to reclaim, and how much of it has been, kept across sessions
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReclaimGoal {
+    /// Bytes to free; 0 when no goal is set
+    #[serde(default)]
+    pub target: u64,
+    /// Bytes deleted since the goal was set
+    #[serde(default)]
+    pub reclaimed: u64,
+    pub since: SystemTime,
+}
+
+impl Default for ReclaimGoal {
+    fn default() -> Self {
+        Self { target: 0, reclaimed: 0, since: SystemTime::now() }
+    }
+}
+
+impl ReclaimGoal {
+    pub fn load() -> Self {
+        load_json(GOAL_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        save_json(GOAL_FILE, self)
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.target > 0
+    }
+
+    /// Start over towards `target` bytes
+    pub fn set(&mut self, target: u64) {
+        *self = Self { target, ..Self::default() };
+    }
+
+    /// Bytes still to find once the deletions so far and the `selected` savings are counted
+    pub fn remaining(&self, selected: u64) -> u64 {
+        self.target.saturating_sub(self.reclaimed + selected)
+    }
+
+    /// Share of the goal met by the deletions so far plus `selected`, from 0 to 1
+    pub fn progress(&self, selected: u64) -> f32 {
+        if self.target == 0 {
+            return 0.0;
+        }
+        ((self.reclaimed + selected) as f64 / self.target as f64).min(1.0) as f32
+    }
+}
+
+/// Groups that reach `needed` bytes in as few groups as possible: the
+/// largest reclaimable first, the lower risk first among equals. Candidates
+/// are (group index, reclaimable bytes, risk score).
+pub fn suggest_groups(mut candidates: Vec<(usize, u64, u32)>, needed: u64) -> Vec<usize> {
+    candidates.sort_by(|a, b| b.1.cmp(&a.1).then(a.2.cmp(&b.2)));
+    let mut found = 0;
+    let mut suggested = Vec::new();
+    for (idx, bytes, _) in candidates {
+        if found >= needed || bytes == 0 {
+            break;
+        }
+        found += bytes;
+        suggested.push(idx);
+    }
+    suggested
+}
@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::settings::{data_dir, data_file, load_json, save_json, write_atomic};
+use crate::scanner::{hash_contents, Digests, ScanConfig};
+use crate::source::{change_time, file_id, FileId};
+
+const CACHE_FILE: &str = "hash-cache.json";
+const CHECK_FILE: &str = "hash-cache-check.json";
+
+/// How far a scan trusts cached digests
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CacheTrust {
+    /// Size, modification time, inode change time and inode must all match
+    #[default]
+    Strict,
+    /// Size and modification time must match
+    Relaxed,
+    /// Read every file again, refreshing the cache
+    Rehash,
+}
+
+impl CacheTrust {
+    pub const ALL: [CacheTrust; 3] = [CacheTrust::Strict, CacheTrust::Relaxed, CacheTrust::Rehash];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CacheTrust::Strict => "Strict",
+            CacheTrust::Relaxed => "Relaxed",
+            CacheTrust::Rehash => "Always rehash",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            CacheTrust::Strict => "Use a cached hash only when size, modification time, inode change time and inode are unchanged, so a file edited in place or replaced while keeping its time stamp is read again",
+            CacheTrust::Relaxed => "Use a cached hash when size and modification time are unchanged",
+            CacheTrust::Rehash => "Read every file this scan and refresh the cache with what was read",
+        }
+    }
+}
+
+/// Digests of one file, trusted while its size and modification time are unchanged
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub size: u64,
+    /// Whole seconds since the Unix epoch
+    pub modified: u64,
+    /// Nanoseconds past `modified`, so an edit within the same second that
+    /// keeps the size is still noticed. Unset in entries written before it
+    /// was recorded, which are not used until the check re-hashes them.
+    #[serde(default)]
+    pub modified_nanos: Option<u32>,
+    /// Inode change time as (seconds, nanoseconds); it only has meaning on
+    /// the machine that recorded it, so it is dropped on import
+    #[serde(default)]
+    pub changed: Option<(u64, u32)>,
+    pub hash: String,
+    #[serde(default)]
+    pub secondary_hash: Option<String>,
+    /// Unset in entries written before it was recorded, and where the platform has no inodes
+    #[serde(default)]
+    pub file_id: Option<FileId>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedFile {
+    path: PathBuf,
+    #[serde(flatten)]
+    entry: CacheEntry,
+}
+
+/// A cache written by "Export Hash Cache": paths relative to `root`, so
+/// another machine holding the same data elsewhere can import it
+#[derive(Serialize, Deserialize)]
+pub struct CacheExport {
+    pub root: PathBuf,
+    files: Vec<CachedFile>,
+}
+
+impl CacheExport {
+    pub fn read(path: &Path) -> io::Result<Self> {
+        serde_json::from_slice(&fs::read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+}
+
+// Seconds and nanoseconds since the Unix epoch
+fn unix_time(time: SystemTime) -> Option<(u64, u32)> {
+    time.duration_since(UNIX_EPOCH).ok().map(|d| (d.as_secs(), d.subsec_nanos()))
+}
+
+/// Digests computed by earlier scans, so unchanged files are not read again.
+/// Shared with scan threads, which look entries up and add new ones.
+#[derive(Debug, Default)]
+pub struct HashCache {
+    entries: Mutex<HashMap<PathBuf, CacheEntry>>,
+    /// Changed since it was loaded or last saved
+    dirty: AtomicBool,
+}
+
+impl HashCache {
+    /// Read the stored cache; an unreadable cache starts empty
+    pub fn load() -> Self {
+        let bytes = data_dir().and_then(|dir| fs::read(dir.join(CACHE_FILE)).ok());
+        let files: Vec<CachedFile> = match bytes.map(|bytes| serde_json::from_slice(&bytes)) {
+            Some(Ok(files)) => files,
+            Some(Err(e)) => {
+                log::warn!("Hash cache is unreadable, starting empty: {}", e);
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        log::info!("Hash cache loaded with {} entries", files.len());
+        Self {
+            entries: Mutex::new(files.into_iter().map(|f| (f.path, f.entry)).collect()),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// Write the cache if anything changed since it was loaded or last saved.
+    /// A lock file keeps two running instances from writing it at once.
+    pub fn save(&self) -> io::Result<()> {
+        // Cleared before the entries are copied, so one added meanwhile is saved next
+        // time, and set again when the write fails
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+        self.write().inspect_err(|_| self.dirty.store(true, Ordering::Relaxed))
+    }
+
+    fn write(&self) -> io::Result<()> {
+        let path = data_file(CACHE_FILE)?;
+        let lock = fs::OpenOptions::new().create(true).truncate(false).write(true).open(path.with_extension("json.lock"))?;
+        lock.lock()?;
+        let files: Vec<CachedFile> = self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, entry)| CachedFile { path: path.clone(), entry: entry.clone() })
+            .collect();
+        let json = serde_json::to_vec(&files).map_err(io::Error::other)?;
+        write_atomic(&path, &json)?;
+        log::debug!("Hash cache saved with {} entries", files.len());
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+        self.dirty.store(true, Ordering::Relaxed);
+        log::info!("Hash cache cleared");
+    }
+
+    /// Cached digests for a file whose size and modification time, to the
+    /// nanosecond, still match, and its inode change time and inode too when
+    /// `trust` is strict; never with `Rehash`. With `secondary`, entries
+    /// without a SHA-512 are not used.
+    pub fn lookup(&self, path: &Path, stamp: FileStamp, secondary: bool, trust: CacheTrust) -> Option<Digests> {
+        let (modified, nanos) = unix_time(stamp.modified?)?;
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(path).filter(|e| e.size == stamp.size && e.modified == modified && e.modified_nanos == Some(nanos))?;
+        match trust {
+            // Any write updates the change time, even one that restores the modification time
+            CacheTrust::Strict if entry.file_id != stamp.file_id || entry.changed != stamp.changed.and_then(unix_time) => return None,
+            CacheTrust::Rehash => return None,
+            _ => {}
+        }
+        if secondary && entry.secondary_hash.is_none() {
+            return None;
+        }
+        Some((entry.hash.clone(), entry.secondary_hash.clone().filter(|_| secondary)))
+    }
+
+    pub fn insert(&self, path: &Path, stamp: FileStamp, digests: &Digests) {
+        let FileStamp { size, file_id, .. } = stamp;
+        let Some((modified, nanos)) = stamp.modified.and_then(unix_time) else {
+            return;
+        };
+        let changed = stamp.changed.and_then(unix_time);
+        let mut entries = self.entries.lock().unwrap();
+        // A single-hash scan keeps a SHA-512 an earlier dual-hash scan stored for the same content
+        let secondary_hash = digests.1.clone().or_else(|| {
+            entries
+                .get(path)
+                .filter(|e| e.size == size && e.modified == modified && e.modified_nanos == Some(nanos) && e.hash == digests.0)
+                .and_then(|e| e.secondary_hash.clone())
+        });
+        let entry = CacheEntry { size, modified, modified_nanos: Some(nanos), changed, hash: digests.0.clone(), secondary_hash, file_id };
+        entries.insert(path.to_path_buf(), entry);
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Up to `count` entries picked at random, for a verification pass
+    pub fn sample(&self, count: usize) -> Vec<(PathBuf, CacheEntry)> {
+        let mut all: Vec<(PathBuf, CacheEntry)> = self.entries.lock().unwrap().iter().map(|(path, entry)| (path.clone(), entry.clone())).collect();
+        let count = count.min(all.len());
+        // A partial Fisher-Yates shuffle; the seed only needs to differ between runs
+        let mut seed = [0u8; 8];
+        let _ = getrandom::getrandom(&mut seed);
+        let mut state = u64::from_le_bytes(seed) | 1;
+        for i in 0..count {
+            // xorshift64
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let j = i + (state % (all.len() - i) as u64) as usize;
+            all.swap(i, j);
+        }
+        all.truncate(count);
+        all
+    }
+
+    /// Drop the entry for `path` if it still holds `hash`
+    pub fn forget(&self, path: &Path, hash: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.get(path).is_some_and(|entry| entry.hash == hash) {
+            entries.remove(path);
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Write the entries under `root` to `to`, with paths relative to `root`.
+    /// Returns how many entries were exported.
+    pub fn export(&self, root: &Path, to: &Path) -> io::Result<usize> {
+        let files: Vec<CachedFile> = self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(path, entry)| {
+                let relative = path.strip_prefix(root).ok()?;
+                Some(CachedFile { path: relative.to_path_buf(), entry: entry.clone() })
+            })
+            .collect();
+        let export = CacheExport { root: root.to_path_buf(), files };
+        fs::write(to, serde_json::to_vec(&export).map_err(io::Error::other)?)?;
+        Ok(export.files.len())
+    }
+
+    /// Add exported entries, placing their relative paths under `root` on this
+    /// machine. Entries are still checked against size and modification time
+    /// when used; their inode and change time belong to the other machine and
+    /// are dropped, so strict trust does not use them. Returns how many were added.
+    pub fn import(&self, export: CacheExport, root: &Path) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let count = export.files.len();
+        for file in export.files {
+            let entry = CacheEntry { changed: None, file_id: None, ..file.entry };
+            entries.insert(root.join(file.path), entry);
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+        log::info!("Imported {} cached hashes under {}", count, root.display());
+        count
+    }
+}
+
+/// What the cache compares to decide whether a file is unchanged
+#[derive(Clone, Copy, Debug)]
+pub struct FileStamp {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub changed: Option<SystemTime>,
+    pub file_id: Option<FileId>,
+}
+
+/// When the background check of the hash cache runs, kept across sessions
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheCheckSettings {
+    /// Days between checks; 0 turns the periodic check off
+    pub every_days: u32,
+    /// Entries re-hashed per check
+    pub sample: usize,
+    pub last_run: Option<SystemTime>,
+}
+
+impl Default for CacheCheckSettings {
+    fn default() -> Self {
+        Self { every_days: 7, sample: 200, last_run: None }
+    }
+}
+
+impl CacheCheckSettings {
+    pub fn load() -> Self {
+        load_json(CHECK_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        save_json(CHECK_FILE, self)
+    }
+
+    /// Whether the periodic check should run now
+    pub fn due(&self) -> bool {
+        let interval = Duration::from_secs(self.every_days as u64 * 86_400);
+        self.every_days > 0 && self.last_run.is_none_or(|last| last.elapsed().is_ok_and(|since| since >= interval))
+    }
+}
+
+/// Outcome of re-hashing a sample of the hash cache
+#[derive(Clone, Debug, Default)]
+pub struct CacheCheck {
+    /// Entries whose file was read and compared
+    pub checked: usize,
+    /// Files whose content changed while size and modification time did not;
+    /// their entries are dropped
+    pub drifted: Vec<PathBuf>,
+    /// Files changed the ordinary way, which the cache already ignores
+    pub stale: usize,
+    /// Files gone or unreadable
+    pub missing: usize,
+}
+
+/// Re-hash `sample` and compare with the cached digests. A file whose size
+/// and modification time match its entry but whose content does not is
+/// drift: a cache that trusts those would report it wrongly, so its entry
+/// is dropped.
+pub fn verify_sample(cache: &HashCache, sample: Vec<(PathBuf, CacheEntry)>, config: &ScanConfig) -> CacheCheck {
+    let mut check = CacheCheck::default();
+    for (path, entry) in sample {
+        let Ok(metadata) = fs::metadata(&path) else {
+            check.missing += 1;
+            continue;
+        };
+        let modified = metadata.modified().ok().and_then(unix_time);
+        // Entries from before nanoseconds were recorded are checked on whole seconds
+        let recorded = entry.modified_nanos.map_or(modified.map(|(secs, _)| secs) == Some(entry.modified), |nanos| {
+            modified == Some((entry.modified, nanos))
+        });
+        if metadata.len() != entry.size || !recorded {
+            check.stale += 1;
+            continue;
+        }
+        match hash_contents(&path, config, false) {
+            Ok((hash, _)) => {
+                check.checked += 1;
+                if hash != entry.hash {
+                    log::warn!("Hash cache drift: {} changed content without changing size or modification time", path.display());
+                    cache.forget(&path, &entry.hash);
+                    check.drifted.push(path);
+                } else if entry.file_id.is_none() || entry.modified_nanos.is_none() || entry.changed.is_none() {
+                    // Fill in what older and imported entries lack, so every trust level can use them
+                    let stamp = FileStamp {
+                        size: entry.size,
+                        modified: metadata.modified().ok(),
+                        changed: change_time(&metadata),
+                        file_id: file_id(&metadata),
+                    };
+                    cache.insert(&path, stamp, &(hash, entry.secondary_hash.clone()));
+                }
+            }
+            Err(_) => check.missing += 1,
+        }
+    }
+    log::info!(
+        "Hash cache check: {} file(s) re-hashed, {} drifted, {} changed, {} missing",
+        check.checked,
+        check.drifted.len(),
+        check.stale,
+        check.missing
+    );
+    check
+}
@@ -0,0 +1,263 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How often a paused worker looks at the switches again
+const PAUSE_POLL: Duration = Duration::from_millis(100);
+// How often a worker set aside by the scaling looks whether it is wanted again
+const IDLE_POLL: Duration = Duration::from_millis(50);
+// Throughput is compared over windows of this length
+const SCALE_WINDOW: Duration = Duration::from_secs(1);
+// Changes in throughput smaller than this share count as noise
+const SCALE_MARGIN: f64 = 0.05;
+// Windows without a clear change before another thread count is tried
+const STEADY_WINDOWS: u32 = 5;
+// Hashing threads and read rate of a low-impact scan, e.g. on battery
+const LOW_IMPACT_WORKERS: usize = 1;
+const LOW_IMPACT_BYTES_PER_SEC: f64 = 20_000_000.0;
+
+/// Pause and cancel switches shared by a running scan and the window
+#[derive(Debug, Default)]
+pub struct ScanControl {
+    cancelled: AtomicBool,
+    paused: AtomicBool,
+    /// The scan stops by itself at this time, keeping what it found
+    deadline: Option<Instant>,
+    timed_out: AtomicBool,
+    /// Threads hashing at the moment, for the progress display
+    workers: AtomicUsize,
+    /// One hashing thread and throttled reads, to spare the battery and the disk
+    low_impact: AtomicBool,
+}
+
+impl ScanControl {
+    /// Switches for a scan starting now that stops once `limit` has passed, paused time included
+    pub fn with_time_limit(limit: Option<Duration>) -> Self {
+        Self { deadline: limit.map(|limit| Instant::now() + limit), ..Self::default() }
+    }
+
+    /// Whether the scan was stopped by its time limit
+    pub fn timed_out(&self) -> bool {
+        self.timed_out.load(Ordering::Relaxed)
+    }
+
+    fn past_deadline(&self) -> bool {
+        let past = self.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        if past {
+            self.timed_out.store(true, Ordering::Relaxed);
+        }
+        past
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Threads hashing at the moment; 0 before hashing starts
+    pub fn workers(&self) -> usize {
+        self.allowed_workers(self.workers.load(Ordering::Relaxed))
+    }
+
+    pub fn set_workers(&self, workers: usize) {
+        self.workers.store(workers, Ordering::Relaxed);
+    }
+
+    /// Switch low-impact scanning on or off; takes effect with the next read
+    pub fn set_low_impact(&self, low_impact: bool) {
+        self.low_impact.store(low_impact, Ordering::Relaxed);
+    }
+
+    pub fn is_low_impact(&self) -> bool {
+        self.low_impact.load(Ordering::Relaxed)
+    }
+
+    /// How many of `active` threads may hash
+    fn allowed_workers(&self, active: usize) -> usize {
+        if self.is_low_impact() {
+            active.min(LOW_IMPACT_WORKERS)
+        } else {
+            active
+        }
+    }
+
+    /// Wait after reading `bytes` so a low-impact scan stays at its read rate
+    pub fn throttle(&self, bytes: usize) {
+        if self.is_low_impact() {
+            thread::sleep(Duration::from_secs_f64(bytes as f64 / LOW_IMPACT_BYTES_PER_SEC));
+        }
+    }
+
+    /// Block while paused; false once the scan is cancelled or out of time
+    pub fn proceed(&self) -> bool {
+        while self.is_paused() && !self.is_cancelled() && !self.past_deadline() {
+            thread::sleep(PAUSE_POLL);
+        }
+        !self.is_cancelled() && !self.past_deadline()
+    }
+}
+
+// Hill climbing over the thread count: keep stepping the same way while
+// throughput rises, turn around when it falls
+struct Climb {
+    window_start: Instant,
+    bytes: u64,
+    last_rate: Option<f64>,
+    up: bool,
+    steady: u32,
+}
+
+/// How many threads take tasks from the queue. A fixed count stays as set;
+/// a scaled one is adjusted from the throughput measured as results come
+/// in: it grows while more threads hash more data per second, as with files
+/// in the page cache or on a fast SSD, and shrinks when they only compete
+/// for a disk.
+pub struct Workers {
+    active: AtomicUsize,
+    max: usize,
+    climb: Option<Mutex<Climb>>,
+}
+
+impl Workers {
+    pub fn fixed(workers: usize) -> Self {
+        Self { active: AtomicUsize::new(workers.max(1)), max: workers.max(1), climb: None }
+    }
+
+    /// Start with `start` threads and scale between one and `max`
+    pub fn scaled(start: usize, max: usize) -> Self {
+        let max = max.max(1);
+        let climb = Climb { window_start: Instant::now(), bytes: 0, last_rate: None, up: true, steady: 0 };
+        Self { active: AtomicUsize::new(start.clamp(1, max)), max, climb: Some(Mutex::new(climb)) }
+    }
+
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Count `bytes` of finished work towards the current window, and pick
+    /// the thread count for the next one when the window is over
+    pub fn record(&self, bytes: u64) {
+        let Some(climb) = &self.climb else {
+            return;
+        };
+        let mut climb = climb.lock().unwrap();
+        climb.bytes += bytes;
+        let elapsed = climb.window_start.elapsed();
+        if elapsed < SCALE_WINDOW {
+            return;
+        }
+        let rate = climb.bytes as f64 / elapsed.as_secs_f64();
+        let step = match climb.last_rate {
+            None => true,
+            Some(last) if rate > last * (1.0 + SCALE_MARGIN) => true,
+            Some(last) if rate < last * (1.0 - SCALE_MARGIN) => {
+                climb.up = !climb.up;
+                true
+            }
+            Some(_) => {
+                climb.steady += 1;
+                climb.steady >= STEADY_WINDOWS
+            }
+        };
+        if step {
+            climb.steady = 0;
+            let active = self.active();
+            let next = if climb.up { (active + 1).min(self.max) } else { active.saturating_sub(1).max(1) };
+            if next == active {
+                // At a bound; the next step probes the other way
+                climb.up = !climb.up;
+            } else {
+                log::debug!("Hashing threads: {} → {} at {:.1} MB/s", active, next, rate / 1_000_000.0);
+                self.active.store(next, Ordering::Relaxed);
+            }
+        }
+        climb.last_rate = Some(rate);
+        climb.bytes = 0;
+        climb.window_start = Instant::now();
+    }
+}
+
+/// Run `work` on every task with up to `workers.active()` threads, handing each result to
+/// `on_result` on the calling thread as it completes. At most `depth` tasks
+/// wait for a worker and at most `depth` results wait for the caller, so a
+/// slow consumer holds back the workers and slow workers hold back the
+/// producer instead of either side piling up work. Returns false when
+/// `control` cancelled the run before every task was done.
+pub fn run_queue<T, R>(
+    tasks: impl Iterator<Item = T> + Send,
+    workers: &Workers,
+    depth: usize,
+    control: Option<&ScanControl>,
+    work: impl Fn(T) -> R + Sync,
+    mut on_result: impl FnMut(R),
+) -> bool
+where
+    T: Send,
+    R: Send,
+{
+    let proceed = || control.is_none_or(ScanControl::proceed);
+    let allowed = || control.map_or(workers.active(), |control| control.allowed_workers(workers.active()));
+    let (task_tx, task_rx) = sync_channel::<T>(depth.max(1));
+    let (result_tx, result_rx) = sync_channel::<R>(depth.max(1));
+    // Only workers hold the task receiver, so the producer stops when they all have
+    let task_rx: Arc<Mutex<Receiver<T>>> = Arc::new(Mutex::new(task_rx));
+    // Set once every task is queued; threads set aside are then no longer needed
+    let queued = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        let queued = &queued;
+        scope.spawn(move || {
+            for task in tasks {
+                if !proceed() || task_tx.send(task).is_err() {
+                    break;
+                }
+            }
+            queued.store(true, Ordering::Relaxed);
+        });
+        for index in 0..workers.max {
+            let task_rx = task_rx.clone();
+            let result_tx = result_tx.clone();
+            let work = &work;
+            scope.spawn(move || {
+                while proceed() {
+                    if index >= allowed() {
+                        if queued.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        thread::sleep(IDLE_POLL);
+                        continue;
+                    }
+                    // The lock is released before the work starts
+                    let task = task_rx.lock().unwrap().recv();
+                    let Ok(task) = task else {
+                        break;
+                    };
+                    if result_tx.send(work(task)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(task_rx);
+        drop(result_tx);
+
+        for result in result_rx {
+            on_result(result);
+        }
+    });
+
+    control.is_none_or(|control| !control.is_cancelled())
+}
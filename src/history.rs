@@ -0,0 +1,295 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::chunks::ChunkSimilarity;
+use crate::format::{format_count, format_size, format_timestamp};
+use crate::scanner::{ScanConfig, ScanRoot};
+use crate::settings::data_dir;
+use crate::similarity::NearDuplicateGroup;
+use crate::symlinks::SymlinkReport;
+use crate::trash::TrashedCopy;
+use crate::unique::UniqueFile;
+use crate::DuplicateGroup;
+
+const INDEX_FILE: &str = "index.json";
+
+/// A scan waiting in the queue, with the settings it was queued with
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScanJob {
+    pub dir: String,
+    /// Further folders scanned together with `dir`; unused for whole-drive scans
+    #[serde(default)]
+    pub extra_dirs: Vec<String>,
+    pub whole_drive: bool,
+    pub config: ScanConfig,
+}
+
+impl ScanJob {
+    pub fn label(&self) -> String {
+        match (self.whole_drive, self.dir.is_empty()) {
+            (true, true) => "Drive of the current directory".to_string(),
+            (true, false) => format!("Drive of {}", self.dir),
+            (false, _) => self.roots().join(" + "),
+        }
+    }
+
+    /// The folders a directory scan walks
+    pub fn roots(&self) -> Vec<String> {
+        std::iter::once(&self.dir).chain(&self.extra_dirs).filter(|dir| !dir.is_empty()).cloned().collect()
+    }
+
+    /// The settings the job runs with, for a tooltip
+    pub fn settings_summary(&self) -> String {
+        let config = &self.config;
+        let mut parts = vec![format!("min size {}", format_size(config.min_file_size))];
+        if config.include_hidden {
+            parts.push("hidden files".to_string());
+        }
+        if config.dual_hash {
+            parts.push("dual hash".to_string());
+        }
+        if config.near_duplicate_sources {
+            parts.push("near-duplicate sources".to_string());
+        }
+        if config.cross_root_only && !self.extra_dirs.is_empty() && !self.whole_drive {
+            parts.push("cross-root duplicates only".to_string());
+        }
+        parts.push(format!("trash: {}", config.trash_mode.label()));
+        parts.join(", ")
+    }
+}
+
+/// Everything a scan produced, as written to its results file
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StoredResults {
+    pub duplicate_groups: Vec<DuplicateGroup>,
+    #[serde(default)]
+    pub near_groups: Vec<NearDuplicateGroup>,
+    #[serde(default)]
+    pub chunk_pairs: Vec<ChunkSimilarity>,
+    #[serde(default)]
+    pub scan_roots: Vec<ScanRoot>,
+    #[serde(default)]
+    pub empty_dirs: Vec<PathBuf>,
+    #[serde(default)]
+    pub trashed: Vec<TrashedCopy>,
+    #[serde(default)]
+    pub symlinks: SymlinkReport,
+    #[serde(default)]
+    pub unique_files: Vec<UniqueFile>,
+}
+
+/// Summary of one finished scan, listed in the "Scan history" dropdown.
+/// The results themselves live in a separate file, read when reopened.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScanRecord {
+    /// Unique within the history; also names the results file
+    pub id: u64,
+    /// The scanned roots
+    pub label: String,
+    /// The local folders scanned, for the recent-directories list; empty for path lists
+    #[serde(default)]
+    pub roots: Vec<String>,
+    pub finished: SystemTime,
+    /// Why the scan failed; a failed scan has no results file
+    pub error: Option<String>,
+    pub group_count: usize,
+    pub savings: u64,
+    /// Size of the results file on disk
+    pub stored_bytes: u64,
+}
+
+impl ScanRecord {
+    /// One line for the dropdown: roots, finish time and outcome
+    pub fn summary(&self) -> String {
+        let outcome = match &self.error {
+            Some(_) => "failed".to_string(),
+            None => format!("{} group(s), {} reclaimable", format_count(self.group_count as u64), format_size(self.savings)),
+        };
+        format!("{} — {} — {}", self.label, format_timestamp(self.finished), outcome)
+    }
+}
+
+/// Limits applied when pruning the stored history; 0 disables a limit
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryLimits {
+    pub max_age_days: u32,
+    pub max_total_mb: u64,
+}
+
+impl Default for HistoryLimits {
+    fn default() -> Self {
+        Self { max_age_days: 90, max_total_mb: 512 }
+    }
+}
+
+fn history_dir() -> Option<PathBuf> {
+    Some(data_dir()?.join("history"))
+}
+
+/// Past scans stored on disk: an index of summaries plus one results file per scan
+#[derive(Default)]
+pub struct ScanHistory {
+    dir: Option<PathBuf>,
+    /// Oldest first
+    pub records: Vec<ScanRecord>,
+}
+
+impl ScanHistory {
+    /// Read the stored index; an unreadable index starts an empty history
+    pub fn load() -> Self {
+        let dir = history_dir();
+        let records = dir
+            .as_ref()
+            .and_then(|dir| fs::read(dir.join(INDEX_FILE)).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Self { dir, records }
+    }
+
+    pub fn get(&self, id: u64) -> Option<&ScanRecord> {
+        self.records.iter().find(|record| record.id == id)
+    }
+
+    fn dir(&self) -> io::Result<&PathBuf> {
+        self.dir.as_ref().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory for the scan history"))
+    }
+
+    fn results_path(&self, id: u64) -> io::Result<PathBuf> {
+        Ok(self.dir()?.join(format!("scan-{}.json", id)))
+    }
+
+    fn save_index(&self) -> io::Result<()> {
+        let dir = self.dir()?;
+        fs::create_dir_all(dir)?;
+        let json = serde_json::to_vec_pretty(&self.records).map_err(io::Error::other)?;
+        // Written aside and renamed so a crash never leaves half an index
+        let tmp = dir.join(format!("{}.tmp", INDEX_FILE));
+        fs::write(&tmp, json)?;
+        fs::rename(tmp, dir.join(INDEX_FILE))
+    }
+
+    fn write_results(&self, id: u64, results: &StoredResults) -> io::Result<u64> {
+        fs::create_dir_all(self.dir()?)?;
+        let json = serde_json::to_vec(results).map_err(io::Error::other)?;
+        fs::write(self.results_path(id)?, &json)?;
+        Ok(json.len() as u64)
+    }
+
+    fn next_id(&self) -> u64 {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64);
+        now.max(self.records.iter().map(|r| r.id + 1).max().unwrap_or(0))
+    }
+
+    fn new_record(&self, label: String, roots: Vec<String>) -> ScanRecord {
+        ScanRecord {
+            id: self.next_id(),
+            label,
+            roots,
+            finished: SystemTime::now(),
+            error: None,
+            group_count: 0,
+            savings: 0,
+            stored_bytes: 0,
+        }
+    }
+
+    /// Store a finished scan and return its id
+    pub fn add(&mut self, label: String, roots: Vec<String>, results: &StoredResults, savings: u64) -> io::Result<u64> {
+        let mut record = self.new_record(label, roots);
+        record.stored_bytes = self.write_results(record.id, results)?;
+        record.group_count = results.duplicate_groups.len();
+        record.savings = savings;
+        let id = record.id;
+        self.records.push(record);
+        self.save_index()?;
+        Ok(id)
+    }
+
+    /// Record a scan that ended in an error, so unattended runs show what failed
+    pub fn add_failed(&mut self, label: String, roots: Vec<String>, error: String) -> io::Result<u64> {
+        let mut record = self.new_record(label, roots);
+        record.error = Some(error);
+        let id = record.id;
+        self.records.push(record);
+        self.save_index()?;
+        Ok(id)
+    }
+
+    /// Replace a stored result set, e.g. after deletions changed its groups
+    pub fn update(&mut self, id: u64, results: &StoredResults, savings: u64) -> io::Result<()> {
+        if self.get(id).is_none_or(|record| record.error.is_some()) {
+            return Ok(());
+        }
+        let stored_bytes = self.write_results(id, results)?;
+        if let Some(record) = self.records.iter_mut().find(|record| record.id == id) {
+            record.group_count = results.duplicate_groups.len();
+            record.savings = savings;
+            record.stored_bytes = stored_bytes;
+        }
+        self.save_index()
+    }
+
+    /// Folders scanned lately, most recent first and each once
+    pub fn recent_dirs(&self, limit: usize) -> Vec<String> {
+        let mut dirs: Vec<String> = Vec::new();
+        for dir in self.records.iter().rev().flat_map(|record| &record.roots) {
+            if dirs.len() == limit {
+                break;
+            }
+            if !dirs.contains(dir) {
+                dirs.push(dir.clone());
+            }
+        }
+        dirs
+    }
+
+    pub fn load_results(&self, id: u64) -> io::Result<StoredResults> {
+        let bytes = fs::read(self.results_path(id)?)?;
+        serde_json::from_slice(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn remove(&mut self, id: u64) -> io::Result<()> {
+        self.records.retain(|record| record.id != id);
+        if let Ok(path) = self.results_path(id) {
+            if let Err(e) = fs::remove_file(path) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    return Err(e);
+                }
+            }
+        }
+        self.save_index()
+    }
+
+    /// Drop entries older than the age limit, then the oldest entries until
+    /// the stored results fit the size limit. `keep` (the shown result set)
+    /// is never pruned. Returns how many entries were removed.
+    pub fn prune(&mut self, limits: &HistoryLimits, keep: Option<u64>) -> io::Result<usize> {
+        let max_age = Duration::from_secs(limits.max_age_days as u64 * 86_400);
+        let mut doomed: Vec<u64> = self.records
+            .iter()
+            .filter(|record| limits.max_age_days > 0 && record.finished.elapsed().is_ok_and(|age| age > max_age))
+            .map(|record| record.id)
+            .collect();
+        if limits.max_total_mb > 0 {
+            let limit = limits.max_total_mb * 1024 * 1024;
+            let mut total: u64 = self.records.iter().filter(|r| !doomed.contains(&r.id)).map(|r| r.stored_bytes).sum();
+            for record in &self.records {
+                if total <= limit {
+                    break;
+                }
+                if !doomed.contains(&record.id) && Some(record.id) != keep {
+                    total -= record.stored_bytes;
+                    doomed.push(record.id);
+                }
+            }
+        }
+        doomed.retain(|&id| Some(id) != keep);
+        for &id in &doomed {
+            self.remove(id)?;
+        }
+        Ok(doomed.len())
+    }
+}
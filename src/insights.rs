@@ -0,0 +1,95 @@
+use std::io;
+use std::time::{Duration, SystemTime};
+use serde::{Deserialize, Serialize};
+use crate::format::{format_count, format_size, format_timestamp};
+use crate::settings::{load_json, save_json};
+
+const USAGE_FILE: &str = "usage.json";
+/// Rough time to find, compare and delete one duplicate by hand, for the time-saved estimate
+pub const MANUAL_SECONDS_PER_FILE: u64 = 30;
+
+/// Lifetime totals kept on this machine only; nothing is sent anywhere
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UsageStats {
+    /// When counting started, or was last reset
+    pub since: SystemTime,
+    #[serde(default)]
+    pub scans: u64,
+    #[serde(default)]
+    pub files_scanned: u64,
+    #[serde(default)]
+    pub groups_found: u64,
+    /// Bytes the found duplicates took beyond one copy each
+    #[serde(default)]
+    pub bytes_found: u64,
+    #[serde(default)]
+    pub files_deleted: u64,
+    #[serde(default)]
+    pub bytes_reclaimed: u64,
+}
+
+impl Default for UsageStats {
+    fn default() -> Self {
+        Self {
+            since: SystemTime::now(),
+            scans: 0,
+            files_scanned: 0,
+            groups_found: 0,
+            bytes_found: 0,
+            files_deleted: 0,
+            bytes_reclaimed: 0,
+        }
+    }
+}
+
+impl UsageStats {
+    pub fn load() -> Self {
+        load_json(USAGE_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        save_json(USAGE_FILE, self)
+    }
+
+    pub fn record_scan(&mut self, files: u64, groups: u64, bytes: u64) {
+        self.scans += 1;
+        self.files_scanned += files;
+        self.groups_found += groups;
+        self.bytes_found += bytes;
+    }
+
+    pub fn record_deletions(&mut self, files: u64, bytes: u64) {
+        self.files_deleted += files;
+        self.bytes_reclaimed += bytes;
+    }
+
+    /// Estimated time the deletions would have taken by hand
+    pub fn time_saved(&self) -> Duration {
+        Duration::from_secs(self.files_deleted * MANUAL_SECONDS_PER_FILE)
+    }
+
+    /// The totals as plain text, e.g. for a storage report
+    pub fn summary(&self) -> String {
+        format!(
+            "DupeFinder usage since {}\nScans run: {}\nFiles scanned: {}\nDuplicate groups found: {} ({} reclaimable)\nFiles deleted: {}\nSpace reclaimed: {}\nEstimated time saved: {}\n",
+            format_timestamp(self.since),
+            format_count(self.scans),
+            format_count(self.files_scanned),
+            format_count(self.groups_found),
+            format_size(self.bytes_found),
+            format_count(self.files_deleted),
+            format_size(self.bytes_reclaimed),
+            format_hours(self.time_saved())
+        )
+    }
+}
+
+/// "3 h 20 min", or minutes alone under an hour
+pub fn format_hours(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    if minutes < 60 {
+        format!("{} min", minutes)
+    } else {
+        format!("{} h {} min", format_count(minutes / 60), minutes % 60)
+    }
+}
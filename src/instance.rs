@@ -0,0 +1,91 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use eframe::egui;
+
+// Sent by a second instance to ask the running one to raise its window
+const FOCUS_REQUEST: &str = "focus";
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Held by the first running instance for its lifetime
+pub struct InstanceGuard {
+    // The OS releases the lock when the file is closed, including on a crash
+    lock: File,
+    listener: TcpListener,
+}
+
+pub enum Instance {
+    Primary(InstanceGuard),
+    /// Another instance holds the lock; its focus port, when it could be read
+    Secondary(Option<u16>),
+}
+
+// Per user, so instances of different users on one machine don't collide
+fn lock_path() -> PathBuf {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).unwrap_or_else(std::env::temp_dir);
+    let user = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_default();
+    dir.join(format!("dupe-finder-gui-{}.lock", user))
+}
+
+/// Take an exclusive, non-blocking lock on `path`, creating it if needed.
+/// Fails with `WouldBlock` while another process holds it.
+pub fn try_lock_file(path: &Path) -> io::Result<File> {
+    let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+    match file.try_lock() {
+        Ok(()) => Ok(file),
+        Err(TryLockError::WouldBlock) => Err(io::Error::new(io::ErrorKind::WouldBlock, format!("{} is locked by another process", path.display()))),
+        Err(TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// Claim the single-instance lock, or report the instance that already holds it
+pub fn acquire() -> io::Result<Instance> {
+    let path = lock_path();
+    match try_lock_file(&path) {
+        Ok(mut lock) => {
+            // The lock file records the port a second instance can reach us on
+            let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+            lock.set_len(0)?;
+            write!(lock, "{}", listener.local_addr()?.port())?;
+            lock.flush()?;
+            Ok(Instance::Primary(InstanceGuard { lock, listener }))
+        }
+        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+            // Locked files can't be opened for reading on Windows; the port is then unknown
+            let port = File::open(&path).ok().and_then(|mut file| {
+                let mut text = String::new();
+                file.read_to_string(&mut text).ok()?;
+                text.trim().parse().ok()
+            });
+            Ok(Instance::Secondary(port))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+impl InstanceGuard {
+    /// Raise the window whenever a second instance asks for focus
+    pub fn listen_for_focus(self, ctx: egui::Context) {
+        thread::spawn(move || {
+            // Held until the process exits
+            let _lock = self.lock;
+            for stream in self.listener.incoming().flatten() {
+                let mut line = String::new();
+                if BufReader::new(stream).read_line(&mut line).is_ok() && line.trim() == FOCUS_REQUEST {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                    ctx.request_repaint();
+                }
+            }
+        });
+    }
+}
+
+/// Ask the running instance listening on `port` to bring its window forward
+pub fn request_focus(port: u16) -> io::Result<()> {
+    let mut stream = TcpStream::connect_timeout(&SocketAddr::from((Ipv4Addr::LOCALHOST, port)), CONNECT_TIMEOUT)?;
+    writeln!(stream, "{}", FOCUS_REQUEST)
+}
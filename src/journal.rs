@@ -0,0 +1,222 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use crate::settings::data_dir;
+
+const JOURNAL_FILE: &str = "delete-journal.jsonl";
+// Appended to a file's name while it waits, set aside, for the batch to finish
+const STAGED_SUFFIX: &str = ".dupefinder-deleting";
+
+/// A file a bulk deletion means to remove, as it was when the deletion started
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlannedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// The files to remove from one group, and the copies it keeps
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlannedGroup {
+    pub kept: Vec<PathBuf>,
+    pub files: Vec<PlannedFile>,
+}
+
+// One line of the journal
+#[derive(Serialize, Deserialize)]
+enum Entry {
+    Begin { started: SystemTime, groups: Vec<PlannedGroup> },
+    Staged { path: PathBuf },
+    Deleted { path: PathBuf },
+}
+
+fn journal_path() -> Option<PathBuf> {
+    Some(data_dir()?.join(JOURNAL_FILE))
+}
+
+/// Where `path` waits between being set aside and being deleted: a hidden
+/// name in the same folder, so setting it aside is a rename on one filesystem
+pub fn staged_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!(".{}{}", name, STAGED_SUFFIX))
+}
+
+/// A bulk deletion in progress. Files are first set aside under a hidden
+/// name, each step written to the journal before the next; only once every
+/// file is set aside are they deleted. An interruption before that point can
+/// be rolled back completely.
+pub struct DeleteJournal {
+    path: PathBuf,
+    file: File,
+    staged: Vec<PathBuf>,
+}
+
+impl DeleteJournal {
+    /// Write the planned deletion to the journal. Fails while an interrupted
+    /// deletion is still waiting to be resumed or rolled back.
+    pub fn begin(groups: Vec<PlannedGroup>) -> io::Result<Self> {
+        let path = journal_path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+        if path.exists() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, "an interrupted deletion must be resumed or rolled back first"));
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create_new(true).append(true).open(&path)?;
+        let mut journal = Self { path, file, staged: Vec::new() };
+        journal.append(&Entry::Begin { started: SystemTime::now(), groups })?;
+        Ok(journal)
+    }
+
+    fn append(&mut self, entry: &Entry) -> io::Result<()> {
+        let mut line = serde_json::to_vec(entry).map_err(io::Error::other)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.sync_data()
+    }
+
+    /// Set `path` aside; it is deleted when the batch finishes
+    pub fn stage(&mut self, path: &Path) -> io::Result<()> {
+        fs::rename(path, staged_path(path))?;
+        self.staged.push(path.to_path_buf());
+        // Recovery looks at the disk, so a record lost here still gets the file back
+        let _ = self.append(&Entry::Staged { path: path.to_path_buf() });
+        Ok(())
+    }
+
+    /// Delete every file set aside and close the journal. A file that cannot
+    /// be deleted is put back under its own name; those files are returned
+    /// with the reason, as they were not deleted after all.
+    pub fn finish(mut self) -> Vec<(PathBuf, String)> {
+        let mut failed = Vec::new();
+        for path in std::mem::take(&mut self.staged) {
+            let staged = staged_path(&path);
+            match fs::remove_file(&staged) {
+                Ok(()) => {
+                    let _ = self.append(&Entry::Deleted { path });
+                }
+                Err(e) => {
+                    let restored = if fs::rename(&staged, &path).is_ok() { "kept" } else { "left set aside" };
+                    failed.push((path, format!("{} ({})", e, restored)));
+                }
+            }
+        }
+        let _ = fs::remove_file(&self.path);
+        failed
+    }
+}
+
+/// A bulk deletion that did not finish, found at startup
+pub struct InterruptedDeletion {
+    pub started: SystemTime,
+    pub groups: Vec<PlannedGroup>,
+}
+
+/// Outcome of resuming or rolling back an interrupted deletion
+#[derive(Debug, Default)]
+pub struct Recovery {
+    pub deleted: Vec<PathBuf>,
+    pub restored: usize,
+    /// Files that no longer exist under either name, deleted before the interruption
+    pub gone: usize,
+    /// Planned files left alone because they or every kept copy changed
+    pub skipped: usize,
+    pub errors: Vec<String>,
+}
+
+impl InterruptedDeletion {
+    /// The interrupted deletion left by an earlier session, if any. Status
+    /// lines after the plan are only informative: recovery goes by which
+    /// files exist under which name.
+    pub fn pending() -> Option<Self> {
+        let file = File::open(journal_path()?).ok()?;
+        let first = BufReader::new(file).lines().next()?.ok()?;
+        match serde_json::from_str(&first).ok()? {
+            Entry::Begin { started, groups } => Some(Self { started, groups }),
+            _ => None,
+        }
+    }
+
+    fn files(&self) -> impl Iterator<Item = &PlannedFile> {
+        self.groups.iter().flat_map(|group| group.files.iter())
+    }
+
+    pub fn planned(&self) -> usize {
+        self.files().count()
+    }
+
+    /// Files currently set aside, waiting to be deleted or put back
+    pub fn staged(&self) -> usize {
+        self.files().filter(|file| staged_path(&file.path).exists()).count()
+    }
+
+    /// Delete what the interrupted run set aside, and the planned files it
+    /// had not reached where they are still as planned. Nothing of a group is
+    /// deleted once none of its kept copies is left; what it set aside is put back.
+    pub fn resume(self) -> Recovery {
+        let mut recovery = Recovery::default();
+        for group in &self.groups {
+            let kept = group.kept.is_empty() || group.kept.iter().any(|path| path.exists());
+            for file in &group.files {
+                let staged = staged_path(&file.path);
+                let target = if staged.exists() {
+                    if !kept {
+                        match fs::rename(&staged, &file.path) {
+                            Ok(()) => recovery.restored += 1,
+                            Err(e) => recovery.errors.push(format!("Failed to restore {}: {}", file.path.display(), e)),
+                        }
+                        continue;
+                    }
+                    staged
+                } else if !file.path.exists() {
+                    recovery.gone += 1;
+                    continue;
+                } else {
+                    let unchanged = fs::metadata(&file.path)
+                        .is_ok_and(|meta| meta.len() == file.size && meta.modified().ok() == file.modified);
+                    if !unchanged || !kept {
+                        recovery.skipped += 1;
+                        continue;
+                    }
+                    file.path.clone()
+                };
+                match fs::remove_file(&target) {
+                    Ok(()) => recovery.deleted.push(file.path.clone()),
+                    Err(e) => recovery.errors.push(format!("Failed to delete {}: {}", file.path.display(), e)),
+                }
+            }
+        }
+        Self::close(&mut recovery);
+        recovery
+    }
+
+    /// Put every file that is still set aside back under its own name
+    pub fn roll_back(self) -> Recovery {
+        let mut recovery = Recovery::default();
+        for file in self.files() {
+            let staged = staged_path(&file.path);
+            if staged.exists() {
+                match fs::rename(&staged, &file.path) {
+                    Ok(()) => recovery.restored += 1,
+                    Err(e) => recovery.errors.push(format!("Failed to restore {}: {}", file.path.display(), e)),
+                }
+            } else if !file.path.exists() {
+                recovery.gone += 1;
+            }
+        }
+        Self::close(&mut recovery);
+        recovery
+    }
+
+    // The journal stays while a file could not be dealt with, so the choice is offered again
+    fn close(recovery: &mut Recovery) {
+        if !recovery.errors.is_empty() {
+            return;
+        }
+        if let Some(Err(e)) = journal_path().map(fs::remove_file) {
+            recovery.errors.push(format!("Failed to remove the deletion journal: {}", e));
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use md5::Md5;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::Digest;
+use crate::settings::{load_json, save_json};
+use crate::scanner::{build_thread_pool, HashGroup, ScanConfig};
+
+const KNOWN_SETS_FILE: &str = "known-files.json";
+
+/// What a scan does with duplicates whose content is in a known-files list
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KnownAction {
+    /// Leave the group out of the results
+    #[default]
+    Exclude,
+    /// Keep the group and tag it with the list's name
+    Flag,
+}
+
+impl KnownAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            KnownAction::Exclude => "Exclude",
+            KnownAction::Flag => "Flag",
+        }
+    }
+}
+
+/// A hash list on disk, such as a plain list of digests or the text form of
+/// the NSRL Reference Data Set (`NSRLFile.txt`)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KnownSet {
+    pub name: String,
+    pub path: PathBuf,
+    pub enabled: bool,
+    pub action: KnownAction,
+}
+
+impl KnownSet {
+    pub fn new(path: PathBuf) -> Self {
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_else(|| "known files".to_string());
+        Self { name, path, enabled: true, action: KnownAction::default() }
+    }
+}
+
+/// The hash lists set up in the settings, kept across sessions
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct KnownSets {
+    pub sets: Vec<KnownSet>,
+}
+
+impl KnownSets {
+    pub fn load() -> Self {
+        load_json(KNOWN_SETS_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        save_json(KNOWN_SETS_FILE, self)
+    }
+
+    pub fn enabled(&self) -> impl Iterator<Item = &KnownSet> {
+        self.sets.iter().filter(|set| set.enabled)
+    }
+}
+
+/// Digests of the enabled lists, each mapped to the first list naming it
+#[derive(Debug, Default)]
+pub struct KnownIndex {
+    /// The lists loaded, in the order their indices refer to
+    pub paths: Vec<PathBuf>,
+    md5: HashMap<[u8; 16], u16>,
+    sha1: HashMap<[u8; 20], u16>,
+    sha256: HashMap<[u8; 32], u16>,
+    /// File sizes of the MD5 and SHA-1 entries; None once an entry comes
+    /// without one, as then no size can be ruled out
+    sizes: Option<HashSet<u64>>,
+}
+
+impl KnownIndex {
+    pub fn len(&self) -> usize {
+        self.md5.len() + self.sha1.len() + self.sha256.len()
+    }
+
+    /// Whether MD5 or SHA-1 digests must be worked out for a group of `size`-byte files
+    fn needs_reading(&self, size: u64) -> bool {
+        (!self.md5.is_empty() || !self.sha1.is_empty()) && self.sizes.as_ref().is_none_or(|sizes| sizes.contains(&size))
+    }
+}
+
+fn parse_hex<const N: usize>(text: &str) -> Option<[u8; N]> {
+    let text = text.trim().trim_matches('"');
+    if text.len() != N * 2 {
+        return None;
+    }
+    let mut bytes = [0u8; N];
+    for (byte, pair) in bytes.iter_mut().zip(text.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+/// Fields of one CSV line. Quoted fields may hold commas and doubled quotes,
+/// as the file names in the NSRL RDS do.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Columns of a CSV hash list with a header, as in the NSRL RDS
+#[derive(Default)]
+struct Columns {
+    md5: Option<usize>,
+    sha1: Option<usize>,
+    sha256: Option<usize>,
+    size: Option<usize>,
+}
+
+impl Columns {
+    fn from_header(line: &str) -> Option<Self> {
+        let mut columns = Columns::default();
+        for (index, name) in csv_fields(line).iter().enumerate() {
+            match name.trim().to_ascii_lowercase().replace('-', "").as_str() {
+                "md5" => columns.md5 = Some(index),
+                "sha1" => columns.sha1 = Some(index),
+                "sha256" => columns.sha256 = Some(index),
+                "filesize" | "size" => columns.size = Some(index),
+                _ => {}
+            }
+        }
+        (columns.md5.is_some() || columns.sha1.is_some() || columns.sha256.is_some()).then_some(columns)
+    }
+}
+
+/// Add one list to `index`; the number of digests it held. Lines are read as
+/// CSV when the first one names hash columns, otherwise each line's first
+/// field is taken as an MD5, SHA-1 or SHA-256 by its length, so
+/// `sha256sum` / `md5sum` output works as it is.
+fn load_list(index: &mut KnownIndex, set: u16, path: &Path) -> io::Result<usize> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    let first = lines.next().transpose()?.unwrap_or_default();
+    let columns = Columns::from_header(&first);
+    let mut count = 0;
+    let mut sized = true;
+    let mut sizes: HashSet<u64> = HashSet::new();
+    let text_lines = columns.is_none().then_some(Ok(first));
+    for line in text_lines.into_iter().chain(lines) {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut digests = (None, None, None);
+        match &columns {
+            Some(columns) => {
+                let fields = csv_fields(line);
+                let field = |column: Option<usize>| column.and_then(|column| fields.get(column)).map(String::as_str);
+                digests = (field(columns.md5).and_then(parse_hex), field(columns.sha1).and_then(parse_hex), field(columns.sha256).and_then(parse_hex));
+                if digests.0.is_some() || digests.1.is_some() {
+                    match field(columns.size).and_then(|size| size.trim().parse().ok()) {
+                        Some(size) => {
+                            sizes.insert(size);
+                        }
+                        None => sized = false,
+                    }
+                }
+            }
+            None => {
+                let digest = line.split(|c: char| c.is_whitespace() || c == ',' || c == ';').next().unwrap_or_default();
+                // sha256sum marks escaped names with a leading backslash
+                let digest = digest.trim_start_matches('\\');
+                match digest.len() {
+                    32 => digests.0 = parse_hex(digest),
+                    40 => digests.1 = parse_hex(digest),
+                    64 => digests.2 = parse_hex(digest),
+                    _ => {}
+                }
+                // Without sizes, any group may be one of these files
+                sized &= digests.0.is_none() && digests.1.is_none();
+            }
+        }
+        let (md5, sha1, sha256) = digests;
+        if let Some(md5) = md5 {
+            index.md5.entry(md5).or_insert(set);
+        }
+        if let Some(sha1) = sha1 {
+            index.sha1.entry(sha1).or_insert(set);
+        }
+        if let Some(sha256) = sha256 {
+            index.sha256.entry(sha256).or_insert(set);
+        }
+        count += usize::from(md5.is_some() || sha1.is_some() || sha256.is_some());
+    }
+    index.sizes = match (index.sizes.take(), sized) {
+        (Some(mut known), true) => {
+            known.extend(sizes);
+            Some(known)
+        }
+        _ => None,
+    };
+    Ok(count)
+}
+
+/// Read the given lists into one index, with the number of digests in each
+pub fn load_known_sets(paths: &[PathBuf]) -> (KnownIndex, Vec<Result<usize, String>>) {
+    let mut index = KnownIndex { paths: paths.to_vec(), sizes: Some(HashSet::new()), ..KnownIndex::default() };
+    let counts = paths
+        .iter()
+        .enumerate()
+        .map(|(set, path)| load_list(&mut index, set as u16, path).map_err(|e| format!("{}: {}", path.display(), e)))
+        .collect();
+    (index, counts)
+}
+
+/// A loaded index with the name and action of each of its lists; attached
+/// to the scan configuration by the caller
+#[derive(Debug)]
+pub struct KnownFiles {
+    pub index: Arc<KnownIndex>,
+    pub sets: Vec<(String, KnownAction)>,
+}
+
+/// What a scan did with groups found in the known-files lists
+#[derive(Clone, Debug, Default)]
+pub struct KnownOutcome {
+    pub excluded: usize,
+    pub flagged: usize,
+}
+
+// MD5 and SHA-1 of a file, from one read through the configured source
+fn legacy_digests(path: &Path, config: &ScanConfig) -> io::Result<([u8; 16], [u8; 20])> {
+    let (mut file, _) = config.source().open(path)?;
+    let mut md5 = Md5::new();
+    let mut sha1 = Sha1::new();
+    let mut buffer = vec![0; config.buffer_size.max(4096)];
+    loop {
+        let count = file.read(&mut buffer)?;
+        if count == 0 {
+            break;
+        }
+        md5.update(&buffer[..count]);
+        sha1.update(&buffer[..count]);
+    }
+    Ok((md5.finalize().into(), sha1.finalize().into()))
+}
+
+impl KnownFiles {
+    /// The list naming the content of `group`, if any. Only one member is read, as all share the content.
+    fn lookup(&self, group: &HashGroup, config: &ScanConfig) -> Option<usize> {
+        let index = &self.index;
+        if let Some(&set) = parse_hex::<32>(&group.hash).and_then(|sha256| index.sha256.get(&sha256)) {
+            return Some(set as usize);
+        }
+        let first = group.files.first()?;
+        if !index.needs_reading(first.size) {
+            return None;
+        }
+        let (md5, sha1) = legacy_digests(&first.path.to_path_buf(), config).ok()?;
+        index.md5.get(&md5).or_else(|| index.sha1.get(&sha1)).map(|&set| set as usize)
+    }
+
+    /// Drop the groups of excluding lists and name the flagging list in the others
+    pub fn apply(&self, groups: Vec<HashGroup>, config: &ScanConfig) -> (Vec<HashGroup>, KnownOutcome) {
+        let matches: Vec<Option<usize>> = match build_thread_pool(config) {
+            Ok(pool) => pool.install(|| groups.par_iter().map(|group| self.lookup(group, config)).collect()),
+            Err(_) => groups.iter().map(|group| self.lookup(group, config)).collect(),
+        };
+        let mut outcome = KnownOutcome::default();
+        let groups = groups
+            .into_iter()
+            .zip(matches)
+            .filter_map(|(mut group, set)| {
+                match set.and_then(|set| self.sets.get(set)) {
+                    Some((_, KnownAction::Exclude)) => {
+                        outcome.excluded += 1;
+                        return None;
+                    }
+                    Some((name, KnownAction::Flag)) => {
+                        outcome.flagged += 1;
+                        group.known = Some(name.clone());
+                    }
+                    None => {}
+                }
+                Some(group)
+            })
+            .collect();
+        (groups, outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn quoted_fields_keep_commas_and_doubled_quotes() {
+        assert_eq!(csv_fields(r#""a, b.txt","say ""hi""",,3"#), vec!["a, b.txt", r#"say "hi""#, "", "3"]);
+        assert_eq!(csv_fields(""), vec![""]);
+        // An unterminated quote runs to the end of the line
+        assert_eq!(csv_fields(r#"1,"open, field"#), vec!["1", "open, field"]);
+    }
+
+    #[test]
+    fn headers_name_the_hash_and_size_columns() {
+        let columns = Columns::from_header(r#""SHA-1","MD5","CRC32","FileName","FileSize""#).unwrap();
+        assert_eq!((columns.sha1, columns.md5, columns.sha256, columns.size), (Some(0), Some(1), None, Some(4)));
+        assert!(Columns::from_header("name,size").is_none());
+        assert!(Columns::from_header(&"a".repeat(64)).is_none());
+    }
+
+    #[test]
+    fn nsrl_lists_skip_malformed_lines() {
+        let (md5, sha1) = ("0123456789abcdef0123456789ABCDEF", "00112233445566778899aabbccddeeff00112233");
+        let text = [
+            r#""SHA-1","MD5","CRC32","FileName","FileSize""#.to_string(),
+            format!(r#""{}","{}","1A2B3C4D","report, final.pdf","1024""#, sha1, md5),
+            r#""not hex","0123","00000000","short.txt","5""#.to_string(),
+            r#""truncated"#.to_string(),
+            String::new(),
+        ]
+        .join("\n");
+        let path = std::env::temp_dir().join(format!("dupe-finder-known-{}.csv", std::process::id()));
+        fs::write(&path, text).unwrap();
+        let mut index = KnownIndex { sizes: Some(HashSet::new()), ..KnownIndex::default() };
+        let count = load_list(&mut index, 0, &path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(count.unwrap(), 1);
+        assert!(index.md5.contains_key(&parse_hex::<16>(md5).unwrap()));
+        assert!(index.sha1.contains_key(&parse_hex::<20>(sha1).unwrap()));
+        assert!(index.needs_reading(1024));
+        assert!(!index.needs_reading(5));
+    }
+
+    #[test]
+    fn plain_lists_take_the_digest_by_its_length() {
+        let sha256 = "ab".repeat(32);
+        let text = format!("# sha256sum output\n\\{}  dir\\\\name\n{} *b.bin\nxyz  c.txt\n", sha256, "cd".repeat(16));
+        let path = std::env::temp_dir().join(format!("dupe-finder-known-{}.txt", std::process::id()));
+        fs::write(&path, text).unwrap();
+        let mut index = KnownIndex { sizes: Some(HashSet::new()), ..KnownIndex::default() };
+        let count = load_list(&mut index, 0, &path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(count.unwrap(), 2);
+        assert_eq!((index.sha256.len(), index.md5.len()), (1, 1));
+        // An MD5 without a size rules no size out
+        assert!(index.sizes.is_none());
+    }
+}
@@ -0,0 +1,132 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use log::{LevelFilter, Log, Metadata, Record};
+use crate::format::format_iso_utc;
+use crate::settings::{data_dir, load_json, save_json};
+
+const LOG_FILE: &str = "dupe-finder.log";
+const LEVEL_FILE: &str = "log-level.json";
+/// A log file is set aside once it grows past this size
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+/// Set-aside files kept next to the current one, as `dupe-finder.log.1` (newest) and up
+const KEPT_LOGS: usize = 3;
+// Messages from this program; other crates (the window toolkit, HTTP) only log warnings
+const OWN_TARGET: &str = env!("CARGO_CRATE_NAME");
+
+/// Levels offered in the settings, from quietest to most detailed
+pub const LEVELS: [LevelFilter; 5] = [LevelFilter::Off, LevelFilter::Error, LevelFilter::Warn, LevelFilter::Info, LevelFilter::Debug];
+
+/// Where the log files are written
+pub fn log_dir() -> Option<PathBuf> {
+    Some(data_dir()?.join("logs"))
+}
+
+pub fn level_label(level: LevelFilter) -> &'static str {
+    match level {
+        LevelFilter::Off => "Off",
+        LevelFilter::Error => "Errors",
+        LevelFilter::Warn => "Warnings",
+        LevelFilter::Info => "Info",
+        LevelFilter::Debug => "Debug",
+        LevelFilter::Trace => "Trace",
+    }
+}
+
+/// The level chosen in an earlier session; Info until one is chosen
+pub fn saved_level() -> LevelFilter {
+    load_json::<String>(LEVEL_FILE)
+        .and_then(|level| LevelFilter::from_str(&level).ok())
+        .unwrap_or(LevelFilter::Info)
+}
+
+/// Log at `level` from now on and in later sessions
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+    let _ = save_json(LEVEL_FILE, level.as_str());
+}
+
+struct LogFile {
+    dir: PathBuf,
+    file: File,
+    written: u64,
+}
+
+impl LogFile {
+    fn open(dir: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let file = OpenOptions::new().create(true).append(true).open(dir.join(LOG_FILE))?;
+        let written = file.metadata()?.len();
+        Ok(Self { dir, file, written })
+    }
+
+    /// Shift the set-aside files up by one, dropping the oldest, and start a new file
+    fn rotate(&mut self) -> io::Result<()> {
+        let name = |n: usize| self.dir.join(format!("{}.{}", LOG_FILE, n));
+        let _ = fs::remove_file(name(KEPT_LOGS));
+        for n in (1..KEPT_LOGS).rev() {
+            let _ = fs::rename(name(n), name(n + 1));
+        }
+        fs::rename(self.dir.join(LOG_FILE), name(1))?;
+        *self = Self::open(self.dir.clone())?;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.written + line.len() as u64 > MAX_LOG_BYTES && self.written > 0 {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// Writes records to the log file, one line each. Write errors are dropped:
+/// a full disk must not stop a scan or a deletion.
+struct FileLogger {
+    file: Mutex<LogFile>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let limit = if metadata.target().starts_with(OWN_TARGET) { log::max_level() } else { log::max_level().min(LevelFilter::Warn) };
+        metadata.level() <= limit
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let target = record.target().strip_prefix(OWN_TARGET).map(|rest| rest.trim_start_matches("::")).unwrap_or(record.target());
+        let target = if target.is_empty() { "main" } else { target };
+        let line = format!("{} {:<5} {}: {}\n", format_iso_utc(SystemTime::now()), record.level(), target, record.args());
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_line(&line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+/// Start writing the log at the saved level. Without a data directory, or
+/// when the file cannot be opened, nothing is logged.
+pub fn init() {
+    let Some(dir) = log_dir() else {
+        return;
+    };
+    let Ok(file) = LogFile::open(dir) else {
+        return;
+    };
+    if log::set_boxed_logger(Box::new(FileLogger { file: Mutex::new(file) })).is_ok() {
+        log::set_max_level(saved_level());
+        log::info!("DupeFinder {} started on {} {}", env!("CARGO_PKG_VERSION"), std::env::consts::OS, std::env::consts::ARCH);
+    }
+}
@@ -1,17 +1,164 @@
+mod adb;
+mod attrs;
+mod casedirs;
+mod chunks;
+mod cleanup;
+mod cli;
+mod columns;
+mod commands;
+mod compare;
+mod consolidate;
+mod corruption;
+mod emptydirs;
+mod external;
+mod favorites;
+mod filetype;
+mod format;
+mod goal;
+mod hashcache;
+mod hashqueue;
+mod history;
+mod insights;
+mod instance;
+mod journal;
+mod knownfiles;
+mod logging;
+mod manifest;
+mod merge;
+mod mounts;
+mod originals;
+mod paths;
+mod pdfpage;
+mod postcheck;
+mod power;
+mod preview;
+mod projects;
+mod renaming;
+mod report;
+mod gdrive;
+mod s3;
+mod settings;
+mod safemode;
 mod scanner;
+mod sftp;
+mod shortcuts;
+mod sidecar;
+mod similarity;
+mod snapshots;
+mod source;
+mod sparse;
+mod symlinks;
+mod taskbar;
+mod theme;
+mod tour;
+mod trash;
+mod uimode;
+mod unique;
+mod updates;
 
 use eframe::egui;
+use casedirs::{execute_case_merge, find_case_variants, plan_case_merge, CaseMergePlan, CaseVariants};
+use chunks::{find_chunk_similar_files, ChunkSimilarity};
+use cleanup::{plan_totals, CleanupRun, PlanKind};
+use columns::{relative_dir, relative_to_root, FileColumn, FileLayout};
+use commands::{Command, CommandPalette};
+use compare::{compare_dirs, CompareAction, DirComparison, Side};
+use corruption::{find_corruption, CorruptionReport};
+use consolidate::{execute_consolidation, plan_consolidation, ConsolidationPlan, OwnerPolicy};
+use emptydirs::{remove_emptied_parents, remove_empty_tree};
+use favorites::FavoriteDirs;
+use filetype::FileCategory;
+use format::{format_count, format_file_date, format_size, format_timestamp, DateStyle, FormatSettings, SizeUnits};
+use manifest::{digest_file, match_against_manifest, verify_manifest, write_manifest, ManifestCheck, ManifestFormat, ManifestMatches};
+use merge::{execute_merge, move_file, plan_merge, MergePlan, MergeReport};
+use hashcache::{verify_sample, CacheCheck, CacheCheckSettings, CacheExport, CacheTrust, HashCache};
+use projects::Projects;
+use external::{fdupes_text, parse_external, rmlint_json, unverified_groups, ExternalResults};
+use hashqueue::ScanControl;
+use goal::{suggest_groups, ReclaimGoal, GOAL_UNIT};
+use insights::{format_hours, UsageStats, MANUAL_SECONDS_PER_FILE};
+use journal::{DeleteJournal, InterruptedDeletion, PlannedFile, PlannedGroup};
+use history::{HistoryLimits, ScanHistory, ScanJob, ScanRecord, StoredResults};
+use mounts::volume_root;
+use originals::{likely_original, original_reasons, KeepLikelyOriginalStrategy, KeepOnePerDirectoryStrategy};
+use paths::{DirId, InternedPath};
+use postcheck::{verify_deletions, DeletedFile, PostDeleteReport, SurvivingFile};
+use power::{on_battery, PowerSettings, PowerState, SleepInhibitor};
+use preview::{load_preview, Preview};
+use renaming::{plan_canonical_renames, rename_no_clobber, RenamePlan};
+use report::{compliance_csv, compliance_html, corruption_csv, corruption_html, owner_name, path_list, simulation_csv, simulation_html, unique_csv, ActionRecord, DeletionSimulation, SimulatedFile, SimulatedOutcome};
 use scanner::{
-    scan_directory, FileInfo, ScanProgress, ScanPhase, ScanConfig, ScanError,
-    SelectionStrategy, KeepNewestStrategy, KeepOldestStrategy
+    scan_roots, scan_file_list, hash_file, parse_file_list, parse_pasted_paths, normalize_dir_input, rescan_directories, rehash_files, effective_threads, is_protected_path, ScanOutcome, TuningResult, is_critical_file, is_sensitive_file, is_junk_copy, FileInfo, FileStatus, HashGroup, ScanProgress, ScanPhase, ScanConfig, ScanError, ScanRoot, MAX_AUTO_BUFFER,
+    SelectionStrategy, KeepAllStrategy, KeepNewestStrategy, KeepOldestStrategy, catch_panic, install_panic_hook, take_panic_details
 };
+use attrs::{metadata_differences, metadata_summary, PreferRichMetadata};
+use adb::{AdbDevice, DeviceInfo, DEFAULT_FOLDER};
+use knownfiles::{load_known_sets, KnownAction, KnownFiles, KnownIndex, KnownSet, KnownSets};
+use gdrive::{DriveAccount, DriveLogin, MAX_DOWNLOADS};
+use s3::{S3Action, S3Bucket, S3Login, MAX_READERS};
+use sftp::{SftpAuth, SftpHost, SftpLogin, MAX_CONNECTIONS};
+use similarity::{find_near_duplicate_sources, NearDuplicateGroup};
+use snapshots::is_in_snapshot;
+use shortcuts::{capture_key, ShortcutAction, Shortcuts};
+use source::{is_remote, FileSource, MountedSources, RemoteSource};
+use sparse::SparseMode;
+use symlinks::SymlinkReport;
+use taskbar::{Taskbar, TaskbarProgress};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use theme::{accessible_checkbox, accessible_label, root_color, root_color_name, Palette};
+use tour::{Tour, TourTarget};
+use trash::{is_in_trash, TrashMode, TrashedCopy};
+use uimode::{CleanupStrategy, CleanupWizard, UiMode, WizardStep};
+use unique::{per_root, UniqueFile};
+use updates::{latest_release, NetworkSettings, Release, UpdateCheck};
 use std::fs;
-use std::sync::mpsc::{channel, Receiver};
+use rayon::prelude::*;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 use serde::{Deserialize, Serialize};
 
 fn main() -> eframe::Result<()> {
+    logging::init();
+    install_panic_hook();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "--pipe") {
+        std::process::exit(cli::run_pipe(&args[1..]));
+    }
+    
+    // Files named on the command line are scanned as a selection, folders added as roots
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let opened: Vec<PathBuf> = args.iter().filter(|arg| !arg.starts_with("--")).map(|arg| cwd.join(arg)).collect();
+    
+    // A second instance scanning or deleting the same tree could delete a
+    // file's last copy twice over, so offer to switch to the running one
+    let (guard, second_instance) = match instance::acquire() {
+        Ok(instance::Instance::Primary(guard)) => (Some(guard), false),
+        Ok(instance::Instance::Secondary(port)) => {
+            let switch = rfd::MessageDialog::new()
+                .set_title("DupeFinder is already running")
+                .set_description("Another DupeFinder window is open. Running two at once can delete the same duplicates twice.\n\nSwitch to the open window instead?")
+                .set_buttons(rfd::MessageButtons::YesNo)
+                .show();
+            if switch == rfd::MessageDialogResult::Yes {
+                if let Some(port) = port {
+                    if let Err(e) = instance::request_focus(port) {
+                        eprintln!("Could not reach the running instance: {}", e);
+                    }
+                }
+                return Ok(());
+            }
+            (None, true)
+        }
+        Err(e) => {
+            eprintln!("Instance check failed: {}", e);
+            (None, false)
+        }
+    };
+    
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([900.0, 700.0])
@@ -22,14 +169,317 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "DupeFinder",
         options,
-        Box::new(|_cc| Ok(Box::new(DupeFinderApp::default()))),
+        Box::new(move |cc| {
+            if let Some(guard) = guard {
+                guard.listen_for_focus(cc.egui_ctx.clone());
+            }
+            let mut app = DupeFinderApp::default();
+            app.reload_known_sets(&cc.egui_ctx);
+            if app.network.checks_updates() {
+                app.check_for_updates(&cc.egui_ctx, false);
+            }
+            if !opened.is_empty() {
+                app.open_paths(opened, &cc.egui_ctx);
+            }
+            if second_instance {
+                app.state.status_message = "⚠ Another DupeFinder window is running; avoid deleting from both at once".to_string();
+            }
+            Ok(Box::new(app))
+        }),
     )
 }
 
+// Number of file rows rendered per group before "Show more" is needed
+const GROUP_PAGE_SIZE: usize = 50;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DuplicateGroup {
     pub files: Vec<FileInfo>,
     pub selected: Vec<bool>,
+    /// SHA-256 shared by every file; empty for results imported from older exports
+    #[serde(default)]
+    pub hash: String,
+    /// SHA-512 recorded alongside the SHA-256 when dual hashing was enabled
+    #[serde(default)]
+    pub secondary_hash: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(skip, default = "default_visible_files")]
+    pub visible_files: usize,
+    /// Text being typed into the "add tag" field
+    #[serde(skip)]
+    pub tag_input: String,
+}
+
+fn default_visible_files() -> usize {
+    GROUP_PAGE_SIZE
+}
+
+impl DuplicateGroup {
+    pub fn new(group: HashGroup) -> Self {
+        let selected = KeepAllStrategy.select(&group.files);
+        Self {
+            files: group.files,
+            selected,
+            hash: group.hash,
+            secondary_hash: group.secondary_hash,
+            tags: group.known.map(|name| format!("known: {}", name)).into_iter().collect(),
+            notes: String::new(),
+            visible_files: GROUP_PAGE_SIZE,
+            tag_input: String::new(),
+        }
+    }
+
+    /// Broad content category, if the group's type was detected during the scan
+    pub fn category(&self) -> Option<FileCategory> {
+        self.files.first()?.detected_type.as_deref().map(FileCategory::from_mime)
+    }
+    
+    /// Number of group members per parent directory, largest first
+    pub fn directory_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<DirId, usize> = HashMap::new();
+        for file in &self.files {
+            *counts.entry(file.path.dir_id()).or_default() += 1;
+        }
+        let mut counts: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(dir, count)| (paths::dir_path(dir).display().to_string(), count))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+    
+    /// Reasons deleting from this group needs care, with their weight in the risk score
+    pub fn risk_factors(&self) -> Vec<(&'static str, u32)> {
+        let mut factors = Vec::new();
+        if self.files.iter().any(|f| f.is_critical) {
+            factors.push(("critical configuration file", 40));
+        }
+        if self.files.iter().any(|f| f.is_sensitive) {
+            factors.push(("sensitive file name", 30));
+        }
+        if self.files.iter().any(|f| is_protected_path(&f.path.to_path_buf())) {
+            factors.push(("under a protected system path", 20));
+        }
+        let recently_modified = self.files.iter().any(|f| {
+            f.modified_time.and_then(|t| t.elapsed().ok()).is_some_and(|age| age < RECENT_MODIFICATION)
+        });
+        if recently_modified {
+            factors.push(("modified in the last 7 days", 10));
+        }
+        factors
+    }
+    
+    /// 0 (safe) to 100 (review carefully)
+    pub fn risk_score(&self) -> u32 {
+        self.risk_factors().iter().map(|(_, weight)| weight).sum()
+    }
+    
+    /// Bytes reclaimable from this group: the unchecked files once some are
+    /// unchecked, otherwise every copy but the one taking most disk space.
+    /// Sparse files count with their allocated size.
+    pub fn wasted_bytes(&self) -> u64 {
+        let unchecked: u64 = self.files.iter()
+            .zip(&self.selected)
+            .filter(|(_, &keep)| !keep)
+            .map(|(file, _)| file.disk_size())
+            .sum();
+        if unchecked > 0 {
+            unchecked
+        } else {
+            let total: u64 = self.files.iter().map(FileInfo::disk_size).sum();
+            total - self.files.iter().map(FileInfo::disk_size).max().unwrap_or(0)
+        }
+    }
+}
+
+// Files changed more recently than this may still be in active use
+const RECENT_MODIFICATION: Duration = Duration::from_secs(7 * 86_400);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl RiskLevel {
+    pub const ALL: [RiskLevel; 3] = [RiskLevel::Low, RiskLevel::Medium, RiskLevel::High];
+    
+    pub fn from_score(score: u32) -> Self {
+        match score {
+            0..=19 => RiskLevel::Low,
+            20..=49 => RiskLevel::Medium,
+            _ => RiskLevel::High,
+        }
+    }
+    
+    pub fn label(self) -> &'static str {
+        match self {
+            RiskLevel::Low => "Low",
+            RiskLevel::Medium => "Medium",
+            RiskLevel::High => "High",
+        }
+    }
+}
+
+/// Order of groups in the results list
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GroupOrder {
+    #[default]
+    AsFound,
+    SafestFirst,
+    RiskiestFirst,
+    MostWastedFirst,
+    /// Groups of the same project next to each other
+    ByProject,
+}
+
+impl GroupOrder {
+    pub fn label(self) -> &'static str {
+        match self {
+            GroupOrder::AsFound => "As found",
+            GroupOrder::SafestFirst => "Safest first",
+            GroupOrder::RiskiestFirst => "Riskiest first",
+            GroupOrder::MostWastedFirst => "Most wasted space first",
+            GroupOrder::ByProject => "By project",
+        }
+    }
+}
+
+/// What the results area lists
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResultsView {
+    #[default]
+    Duplicates,
+    /// The uniqueness report: files with no other copy
+    Unique,
+}
+
+/// Which scan roots a group's files live in, for multi-root scans
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RootScope {
+    /// Files under at least two roots
+    CrossRoot,
+    /// Every file under the same root
+    SingleRoot,
+    /// At least one file under the root with this index
+    Includes(usize),
+}
+
+impl RootScope {
+    pub fn label(self, roots: &[ScanRoot]) -> String {
+        match self {
+            RootScope::CrossRoot => "Across roots".to_string(),
+            RootScope::SingleRoot => "Within one root".to_string(),
+            RootScope::Includes(idx) => match roots.get(idx) {
+                Some(root) => format!("Includes {}", root.path.display()),
+                None => "Includes a removed root".to_string(),
+            },
+        }
+    }
+    
+    fn matches(self, group: &DuplicateGroup, roots: &[ScanRoot]) -> bool {
+        let mut members: Vec<usize> = group.files.iter()
+            .filter_map(|f| ScanRoot::containing(roots, &f.path.parent()))
+            .collect();
+        members.sort_unstable();
+        members.dedup();
+        match self {
+            RootScope::CrossRoot => members.len() > 1,
+            RootScope::SingleRoot => members.len() == 1,
+            RootScope::Includes(idx) => members.contains(&idx),
+        }
+    }
+}
+
+/// Which projects a group's files live in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProjectScope {
+    /// Files in at least two projects, usually deliberate copies
+    CrossProject,
+    /// Every file in projects lies in the same one
+    SingleProject,
+    /// At least one file in the project with this index
+    Includes(usize),
+}
+
+impl ProjectScope {
+    pub fn label(self, projects: &Projects) -> String {
+        match self {
+            ProjectScope::CrossProject => "Across projects".to_string(),
+            ProjectScope::SingleProject => "Within one project".to_string(),
+            ProjectScope::Includes(idx) if idx < projects.roots.len() => format!("In {}", projects.name(idx)),
+            ProjectScope::Includes(_) => "In a project no longer found".to_string(),
+        }
+    }
+    
+    fn matches(self, group: &DuplicateGroup, projects: &Projects) -> bool {
+        let spanned = projects.spanned(&group.files);
+        match self {
+            ProjectScope::CrossProject => spanned.len() > 1,
+            ProjectScope::SingleProject => spanned.len() == 1,
+            ProjectScope::Includes(idx) => spanned.contains(&idx),
+        }
+    }
+}
+
+/// Which groups are shown in the results list
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ResultFilter {
+    /// Only show groups whose detected content type falls in this category
+    pub category: Option<FileCategory>,
+    /// Only show groups containing a file matching a sensitive pattern
+    pub sensitive_only: bool,
+    /// Only show groups at this risk level
+    #[serde(default)]
+    pub risk: Option<RiskLevel>,
+    /// Only show groups whose files lie in these scan roots
+    #[serde(default)]
+    pub root_scope: Option<RootScope>,
+    /// Roots of the shown results, kept here so `matches` can apply `root_scope`
+    #[serde(skip)]
+    pub roots: Vec<ScanRoot>,
+    /// Only show groups whose files lie in these projects
+    #[serde(default)]
+    pub project_scope: Option<ProjectScope>,
+    /// Projects of the shown results, detected when first needed
+    #[serde(skip)]
+    pub projects: Projects,
+}
+
+impl ResultFilter {
+    pub fn is_active(&self) -> bool {
+        self.category.is_some() || self.sensitive_only || self.risk.is_some() || self.root_scope.is_some() || self.project_scope.is_some()
+    }
+    
+    /// Show every group again
+    pub fn clear(&mut self) {
+        let roots = std::mem::take(&mut self.roots);
+        let projects = std::mem::take(&mut self.projects);
+        *self = Self { roots, projects, ..Self::default() };
+    }
+    
+    pub fn matches(&self, group: &DuplicateGroup) -> bool {
+        if self.root_scope.is_some_and(|scope| !scope.matches(group, &self.roots)) {
+            return false;
+        }
+        if self.project_scope.is_some_and(|scope| !scope.matches(group, &self.projects)) {
+            return false;
+        }
+        if self.category.is_some() && group.category() != self.category {
+            return false;
+        }
+        if self.sensitive_only && !group.files.iter().any(|f| f.is_sensitive) {
+            return false;
+        }
+        if self.risk.is_some_and(|risk| RiskLevel::from_score(group.risk_score()) != risk) {
+            return false;
+        }
+        true
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -41,6 +491,77 @@ pub struct AppState {
     pub status_message: String,
     pub config: ScanConfig,
     pub preview_mode: bool,
+    /// Every destructive action only previews, whatever `preview_mode` says;
+    /// turned off only through its own settings toggle
+    #[serde(skip)]
+    pub safe_mode: bool,
+    /// Files deleted or renamed on disk this session, for compliance reports
+    #[serde(default)]
+    pub action_log: Vec<ActionRecord>,
+    pub filter: ResultFilter,
+    #[serde(default)]
+    pub group_order: GroupOrder,
+    #[serde(default)]
+    pub file_layout: FileLayout,
+    pub high_contrast: bool,
+    /// Scan the whole volume containing the selected directory
+    pub scan_whole_drive: bool,
+    /// Further folders scanned together with `selected_dir`
+    #[serde(default)]
+    pub extra_dirs: Vec<String>,
+    /// Similar-but-not-identical source files, kept apart from the exact groups
+    #[serde(default)]
+    pub near_groups: Vec<NearDuplicateGroup>,
+    /// Large files that are partly identical, from the experimental chunk comparison
+    #[serde(default)]
+    pub chunk_pairs: Vec<ChunkSimilarity>,
+    /// Roots walked by the scan that produced the shown results, with file counts
+    #[serde(default)]
+    pub scan_roots: Vec<ScanRoot>,
+    /// Folders holding nothing but empty folders, when the scan looked for them
+    #[serde(default)]
+    pub empty_dirs: Vec<PathBuf>,
+    /// Trashed copies of files that still exist elsewhere
+    #[serde(default)]
+    pub trashed: Vec<TrashedCopy>,
+    /// Broken, redundant and chained symlinks from the last directory scan
+    #[serde(default)]
+    pub symlinks: SymlinkReport,
+    /// Files with no other copy, from a scan with the uniqueness report on
+    #[serde(default)]
+    pub unique_files: Vec<UniqueFile>,
+    #[serde(default)]
+    pub activity: ActivitySettings,
+    /// Size units, locale and date style used throughout the UI, exports and reports
+    #[serde(default)]
+    pub formatting: FormatSettings,
+    /// Scans waiting to run one after another
+    #[serde(default)]
+    pub scan_queue: VecDeque<ScanJob>,
+    /// Id of the scan history entry the shown results belong to
+    #[serde(default)]
+    pub active_scan: Option<u64>,
+    /// Age and size limits for the stored scan history
+    #[serde(default)]
+    pub history_limits: HistoryLimits,
+    /// Let a deletion remove every copy of a group's content, after a
+    /// separate confirmation. Off by default so at least one copy survives.
+    #[serde(default)]
+    pub allow_deleting_last_copy: bool,
+    /// After deleting or moving files, remove the folders they left empty
+    #[serde(default)]
+    pub remove_emptied_dirs: bool,
+    /// After a deletion, offer to strip " (2)" and "Copy of " from kept copies' names
+    #[serde(default)]
+    pub offer_canonical_renames: bool,
+    /// Selections keep the copy with the richer metadata (executable bit,
+    /// extended attributes) when copies of a group differ in it
+    #[serde(default)]
+    pub prefer_rich_metadata: bool,
+    #[serde(default)]
+    pub shortcuts: Shortcuts,
+    #[serde(default)]
+    pub known_sets: KnownSets,
 }
 
 impl Default for AppState {
@@ -53,366 +574,7940 @@ impl Default for AppState {
             status_message: String::new(),
             config: ScanConfig::default(),
             preview_mode: false,
+            // Sessions start from the saved choice; see `safemode::starts_enabled`
+            safe_mode: true,
+            action_log: Vec::new(),
+            filter: ResultFilter::default(),
+            group_order: GroupOrder::default(),
+            file_layout: FileLayout::default(),
+            high_contrast: false,
+            scan_whole_drive: false,
+            extra_dirs: Vec::new(),
+            near_groups: Vec::new(),
+            chunk_pairs: Vec::new(),
+            scan_roots: Vec::new(),
+            empty_dirs: Vec::new(),
+            trashed: Vec::new(),
+            symlinks: SymlinkReport::default(),
+            unique_files: Vec::new(),
+            activity: ActivitySettings::default(),
+            formatting: FormatSettings::default(),
+            scan_queue: VecDeque::new(),
+            active_scan: None,
+            history_limits: HistoryLimits::default(),
+            allow_deleting_last_copy: false,
+            remove_emptied_dirs: false,
+            offer_canonical_renames: false,
+            prefer_rich_metadata: false,
+            shortcuts: Shortcuts::default(),
+            known_sets: KnownSets::default(),
+        }
+    }
+}
+
+/// How eagerly the UI redraws while work runs in the background
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ActivitySettings {
+    /// Upper bound on repaints per second driven by progress updates
+    pub max_repaints_per_second: u32,
+    /// While the window is unfocused or minimized, redraw about once a
+    /// second and stop spinner animations
+    pub reduce_in_background: bool,
+}
+
+impl Default for ActivitySettings {
+    fn default() -> Self {
+        Self { max_repaints_per_second: 10, reduce_in_background: true }
+    }
+}
+
+impl AppState {
+    /// Destructive actions only report what they would do
+    fn previewing(&self) -> bool {
+        self.preview_mode || self.safe_mode
+    }
+}
+
+/// Group and file that keyboard shortcuts act on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ResultCursor {
+    group: usize,
+    file: usize,
+}
+
+/// In-progress inline rename of a single file row
+struct RenameEdit {
+    group_idx: usize,
+    file_idx: usize,
+    new_name: String,
+}
+
+/// State of the "Merge folders" window
+#[derive(Default)]
+struct MergeWizard {
+    open: bool,
+    source: String,
+    destination: String,
+    plan: Option<MergePlan>,
+    receiver: Option<Receiver<Result<MergePlan, ScanError>>>,
+    /// The copying in progress, with the number of duplicates it skips
+    copy_receiver: Option<Receiver<(MergeReport, usize)>>,
+    progress: Arc<Mutex<Option<ScanProgress>>>,
+    message: String,
+}
+
+/// State of the "Case-variant folders" window
+#[derive(Default)]
+struct CaseDirsWizard {
+    open: bool,
+    sets: Vec<CaseVariants>,
+    /// Per set, the folder the others are merged into
+    keep: Vec<usize>,
+    receiver: Option<Receiver<Vec<CaseVariants>>>,
+    /// The set being merged and its plan
+    plan: Option<(usize, CaseMergePlan)>,
+    plan_receiver: Option<Receiver<(usize, Result<CaseMergePlan, ScanError>)>>,
+    progress: Arc<Mutex<Option<ScanProgress>>>,
+    message: String,
+}
+
+/// State of the "Compare folders" window
+#[derive(Default)]
+struct CompareWizard {
+    open: bool,
+    left: String,
+    right: String,
+    result: Option<DirComparison>,
+    receiver: Option<Receiver<Result<DirComparison, ScanError>>>,
+    progress: Arc<Mutex<Option<ScanProgress>>>,
+    message: String,
+}
+
+/// State of the "Corruption check" window
+#[derive(Default)]
+struct CorruptionWizard {
+    open: bool,
+    left: String,
+    right: String,
+    result: Option<CorruptionReport>,
+    receiver: Option<Receiver<Result<CorruptionReport, ScanError>>>,
+    progress: Arc<Mutex<Option<ScanProgress>>>,
+    message: String,
+}
+
+/// State of the "Match against manifest" window
+#[derive(Default)]
+struct ManifestMatchWizard {
+    open: bool,
+    root: String,
+    manifest: String,
+    result: Option<ManifestMatches>,
+    receiver: Option<Receiver<Result<ManifestMatches, ScanError>>>,
+    progress: Arc<Mutex<Option<ScanProgress>>>,
+    message: String,
+}
+
+/// A scan as started, so one stopped by its time limit can be run again
+enum ScanRequest {
+    Job(ScanJob),
+    /// Listed or selected files, with their label and settings
+    Paths(Vec<PathBuf>, String, ScanConfig),
+}
+
+/// State of the "Consolidate to store" window
+#[derive(Default)]
+struct ConsolidationWizard {
+    open: bool,
+    store: String,
+    owners: OwnerPolicy,
+    plan: Option<ConsolidationPlan>,
+    report: String,
+    message: String,
+}
+
+/// A worker thread's panic, shown so its details can be copied into a bug report
+struct CrashReport {
+    operation: &'static str,
+    message: String,
+    details: String,
+}
+
+/// Closing the window while work is still running
+#[derive(Default)]
+struct QuitGuard {
+    /// Asking whether to stop the running work and quit
+    confirming: bool,
+    /// Quit confirmed; the window closes once the running deletions have stopped
+    quitting: bool,
+    /// Nothing holds the window open any more
+    close_allowed: bool,
+    /// Checked by background deletions before each file, so they stop between files
+    stop: Arc<AtomicBool>,
+}
+
+/// Files collected from any number of groups for a single operation
+#[derive(Default)]
+struct SelectionBasket {
+    paths: BTreeSet<PathBuf>,
+    tag_input: String,
+}
+
+/// Background re-hash of one group's members
+struct GroupVerification {
+    paths: Vec<std::path::PathBuf>,
+    receiver: Receiver<Vec<Option<String>>>,
+}
+
+/// Verification passes started after deletions; reports arrive as each finishes
+struct PostDeleteCheck {
+    sender: Sender<PostDeleteReport>,
+    receiver: Receiver<PostDeleteReport>,
+    running: usize,
+    /// Most recent report, shown in the completion dialog until closed
+    report: Option<PostDeleteReport>,
+}
+
+/// The "Scan selected files" window: paths pasted from a file manager
+#[derive(Default)]
+struct PastedSelection {
+    open: bool,
+    text: String,
+}
+
+/// Digests read from the enabled lists, and how many each gave
+type LoadedKnownSets = (KnownIndex, Vec<Result<usize, String>>);
+
+/// Known-files lists, read again in the background whenever the enabled ones change
+#[derive(Default)]
+struct KnownLists {
+    index: Option<Arc<KnownIndex>>,
+    receiver: Option<Receiver<LoadedKnownSets>>,
+    /// Digests read from each list, or why it could not be read
+    counts: HashMap<PathBuf, Result<usize, String>>,
+}
+
+/// The "Rename kept copies" window offered after a deletion
+#[derive(Default)]
+struct KeptRenames {
+    open: bool,
+    plan: RenamePlan,
+    message: String,
+}
+
+/// The "Add SFTP Root" dialog and the connection it is opening
+#[derive(Default)]
+struct SftpDialog {
+    open: bool,
+    login: SftpLogin,
+    remote_dir: String,
+    receiver: Option<Receiver<Result<SftpHost, String>>>,
+    message: String,
+}
+
+/// An unchecked duplicate in a remote store, with the kept copy that vouches for it
+struct RemoteTarget {
+    path: PathBuf,
+    size: u64,
+    kept: PathBuf,
+    /// Hash of the kept copy recorded at scan time
+    kept_hash: String,
+}
+
+/// Remote files an action would change, listed before it may be applied
+#[derive(Default)]
+struct RemotePlan {
+    targets: Vec<RemoteTarget>,
+    /// Unchecked files left out because nothing in their group is kept
+    unkept: usize,
+}
+
+/// Outcome of a remote action for each file it was applied to
+type RemoteResults = Vec<(PathBuf, Result<(), String>)>;
+
+/// Bucket objects an S3 action would change
+struct S3Plan {
+    action: S3Action,
+    remote: RemotePlan,
+}
+
+/// The "S3 Buckets" window: connecting buckets and acting on the duplicates stored in them
+#[derive(Default)]
+struct S3Dialog {
+    open: bool,
+    login: S3Login,
+    receiver: Option<Receiver<Result<S3Bucket, String>>>,
+    message: String,
+    /// Connected buckets, also mounted in the scan sources
+    buckets: Vec<Arc<S3Bucket>>,
+    action: S3Action,
+    plan: Option<S3Plan>,
+    action_receiver: Option<Receiver<RemoteResults>>,
+}
+
+/// Outcome of deleting each device file, with the backup copied off the device first, if any
+type DeviceResults = Vec<(PathBuf, Result<Option<PathBuf>, String>)>;
+
+/// The "Android Devices" window: adding devices seen by adb and deleting duplicates stored on them
+struct DeviceDialog {
+    open: bool,
+    devices: Vec<DeviceInfo>,
+    devices_receiver: Option<Receiver<Result<Vec<DeviceInfo>, String>>>,
+    folder: String,
+    hash_on_device: bool,
+    receiver: Option<Receiver<Result<AdbDevice, String>>>,
+    message: String,
+    /// Added devices, also mounted in the scan sources
+    connected: Vec<Arc<AdbDevice>>,
+    /// Where a device file is copied before deletion when no copy is kept on this PC
+    backup_dir: String,
+    plan: Option<RemotePlan>,
+    action_receiver: Option<Receiver<DeviceResults>>,
+}
+
+impl Default for DeviceDialog {
+    fn default() -> Self {
+        Self {
+            open: false,
+            devices: Vec::new(),
+            devices_receiver: None,
+            folder: DEFAULT_FOLDER.to_string(),
+            hash_on_device: true,
+            receiver: None,
+            message: String::new(),
+            connected: Vec::new(),
+            backup_dir: String::new(),
+            plan: None,
+            action_receiver: None,
         }
     }
 }
 
+/// The "Google Drive" window: signing in and moving duplicates stored in Drive to its trash
+#[derive(Default)]
+struct DriveDialog {
+    open: bool,
+    login: DriveLogin,
+    /// Sign-in page of the sign-in in progress, in case the browser did not open
+    sign_in_url: String,
+    receiver: Option<Receiver<Result<DriveAccount, String>>>,
+    message: String,
+    /// Signed-in accounts, also mounted in the scan sources
+    accounts: Vec<Arc<DriveAccount>>,
+    plan: Option<RemotePlan>,
+    action_receiver: Option<Receiver<RemoteResults>>,
+}
+
+impl Default for PostDeleteCheck {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self { sender, receiver, running: 0, report: None }
+    }
+}
+
+/// Which groups a deletion waiting for confirmation applies to
+enum DeleteTarget {
+    /// A group by index, with its first file to check the index still refers to it
+    Group(usize, PathBuf),
+    /// Every group shown by the current filter
+    Shown,
+}
+
+/// A deletion that would remove every copy of some content, waiting for confirmation
+struct LastCopyConfirmation {
+    target: DeleteTarget,
+    only_failed: bool,
+    groups: usize,
+    files: usize,
+}
+
+/// Background check of a checksum manifest against the disk
+#[derive(Default)]
+struct ManifestVerification {
+    receiver: Option<Receiver<Result<ManifestCheck, ScanError>>>,
+    progress: Arc<Mutex<Option<ScanProgress>>>,
+    /// Last finished check, shown until its window is closed
+    report: Option<ManifestCheck>,
+}
+
+/// An exported hash cache waiting for the local folder its root maps to
+struct CacheImport {
+    export: CacheExport,
+    source: PathBuf,
+    root: String,
+}
+
+/// Duplicate sets read from another tool's output, waiting for the choice
+/// whether to confirm them by hashing
+struct ExternalImport {
+    results: ExternalResults,
+    source: PathBuf,
+    verify: bool,
+}
+
+/// Text / PDF preview of one file, loaded in the background
+#[derive(Default)]
+struct FilePreview {
+    path: Option<PathBuf>,
+    /// Waiting to load; held back while the window is in the background
+    pending: bool,
+    receiver: Option<Receiver<Preview>>,
+    preview: Option<Preview>,
+    /// A PDF's first page, uploaded once the preview arrives
+    page_texture: Option<egui::TextureHandle>,
+}
+
 struct DupeFinderApp {
     state: AppState,
     scan_progress: Arc<Mutex<Option<ScanProgress>>>,
-    result_receiver: Option<Receiver<Result<Vec<Vec<FileInfo>>, ScanError>>>,
+    result_receiver: Option<Receiver<Result<ScanOutcome, ScanError>>>,
+    near_receiver: Option<Receiver<Result<Vec<NearDuplicateGroup>, ScanError>>>,
+    chunk_receiver: Option<Receiver<Result<Vec<ChunkSimilarity>, ScanError>>>,
+    /// The pending scan result updates existing groups instead of replacing them
+    rescanning: bool,
+    renaming: Option<RenameEdit>,
+    merge: MergeWizard,
+    case_dirs: CaseDirsWizard,
+    compare: CompareWizard,
+    corruption: CorruptionWizard,
+    manifest_match: ManifestMatchWizard,
+    consolidation: ConsolidationWizard,
+    /// High-contrast setting currently applied to the egui visuals
+    applied_high_contrast: Option<bool>,
+    /// Visuals in use before high contrast was turned on, put back when it is turned off
+    visuals_before_contrast: Option<egui::Visuals>,
+    /// Formatting settings currently in effect
+    applied_formatting: Option<FormatSettings>,
+    verification: Option<GroupVerification>,
+    /// Last title sent to the window, to avoid resending it every frame
+    window_title: String,
+    taskbar: Taskbar,
+    basket: SelectionBasket,
+    post_delete: PostDeleteCheck,
+    kept_renames: KeptRenames,
+    known_lists: KnownLists,
+    pasted_selection: PastedSelection,
+    sftp_dialog: SftpDialog,
+    s3_dialog: S3Dialog,
+    drive_dialog: DriveDialog,
+    device_dialog: DeviceDialog,
+    /// Connected SFTP servers, S3 buckets, Drive accounts and Android devices, read alongside the local disk by every scan
+    remote_sources: MountedSources,
+    command_palette: CommandPalette,
+    /// Settings section the command palette asked to expand on the next frame
+    reveal_settings: Option<Command>,
+    cursor: Option<ResultCursor>,
+    /// The cursor moved, so the results scroll to it on the next frame
+    scroll_to_cursor: bool,
+    /// Action whose new key the shortcuts editor is waiting for
+    capturing_shortcut: Option<ShortcutAction>,
+    /// Settings chosen by the last auto-tuned scan
+    tuning: Option<TuningResult>,
+    file_preview: FilePreview,
+    /// Minimum time between progress repaints in milliseconds, shared with worker threads
+    repaint_interval: Arc<AtomicU64>,
+    /// The window is unfocused or minimized and background activity is reduced
+    in_background: bool,
+    /// Start the next queued scan as soon as the current one finishes
+    running_queue: bool,
+    /// History label for the scan in progress; rescans have none
+    scan_label: Option<String>,
+    /// Local folders of the scan in progress, stored with its history entry
+    scan_dirs: Vec<String>,
+    /// Past scans stored on disk
+    history: ScanHistory,
+    last_copy_confirmation: Option<LastCopyConfirmation>,
+    /// A bulk deletion an earlier session did not finish, offered for resuming or rolling back
+    interrupted_delete: Option<InterruptedDeletion>,
+    /// Whether its window is open; "Later" closes it until the next bulk deletion
+    show_interrupted_delete: bool,
+    manifest_check: ManifestVerification,
+    /// First-run guided tour, also started from the "Tour" button
+    tour: Tour,
+    /// Whether every setting is shown or only the basics
+    ui_mode: UiMode,
+    cleanup: CleanupWizard,
+    quit: QuitGuard,
+    crash_report: Option<CrashReport>,
+    network: NetworkSettings,
+    power: PowerState,
+    update_check: UpdateCheck,
+    usage: UsageStats,
+    goal: ReclaimGoal,
+    favorites: FavoriteDirs,
+    insights_open: bool,
+    last_scan: Option<ScanRequest>,
+    /// The scan behind the shown results when its time limit cut it short
+    partial_scan: Option<ScanRequest>,
+    confirm_usage_reset: bool,
+    /// Digests from earlier scans, shared with scan threads
+    hash_cache: Arc<HashCache>,
+    cache_check: CacheCheckSettings,
+    cache_check_receiver: Option<Receiver<CacheCheck>>,
+    /// Outcome of the last check of the hash cache this session
+    last_cache_check: Option<CacheCheck>,
+    results_view: ResultsView,
+    /// Pause and cancel switches of the running scan
+    scan_control: Arc<ScanControl>,
+    cache_import: Option<CacheImport>,
+    external_import: Option<ExternalImport>,
 }
 
 impl Default for DupeFinderApp {
     fn default() -> Self {
         Self {
-            state: AppState::default(),
+            state: AppState {
+                file_layout: FileLayout::load(),
+                shortcuts: Shortcuts::load(),
+                known_sets: KnownSets::load(),
+                safe_mode: safemode::starts_enabled(),
+                ..Default::default()
+            },
             scan_progress: Arc::new(Mutex::new(None)),
             result_receiver: None,
+            near_receiver: None,
+            chunk_receiver: None,
+            rescanning: false,
+            renaming: None,
+            merge: MergeWizard::default(),
+            case_dirs: CaseDirsWizard::default(),
+            compare: CompareWizard::default(),
+            corruption: CorruptionWizard::default(),
+            manifest_match: ManifestMatchWizard::default(),
+            consolidation: ConsolidationWizard::default(),
+            applied_high_contrast: None,
+            visuals_before_contrast: None,
+            applied_formatting: None,
+            verification: None,
+            window_title: String::new(),
+            taskbar: Taskbar::default(),
+            basket: SelectionBasket::default(),
+            post_delete: PostDeleteCheck::default(),
+            kept_renames: KeptRenames::default(),
+            known_lists: KnownLists::default(),
+            pasted_selection: PastedSelection::default(),
+            sftp_dialog: SftpDialog::default(),
+            s3_dialog: S3Dialog::default(),
+            drive_dialog: DriveDialog::default(),
+            device_dialog: DeviceDialog::default(),
+            remote_sources: MountedSources::default(),
+            command_palette: CommandPalette::default(),
+            reveal_settings: None,
+            cursor: None,
+            scroll_to_cursor: false,
+            capturing_shortcut: None,
+            tuning: None,
+            file_preview: FilePreview::default(),
+            repaint_interval: Arc::new(AtomicU64::new(0)),
+            in_background: false,
+            running_queue: false,
+            scan_label: None,
+            scan_dirs: Vec::new(),
+            history: ScanHistory::load(),
+            last_copy_confirmation: None,
+            interrupted_delete: InterruptedDeletion::pending(),
+            show_interrupted_delete: true,
+            manifest_check: ManifestVerification::default(),
+            tour: Tour::load(),
+            ui_mode: UiMode::load(),
+            cleanup: CleanupWizard::default(),
+            quit: QuitGuard::default(),
+            crash_report: None,
+            network: NetworkSettings::load(),
+            power: PowerState { settings: PowerSettings::load(), ..PowerState::default() },
+            update_check: UpdateCheck::default(),
+            usage: UsageStats::load(),
+            goal: ReclaimGoal::load(),
+            favorites: FavoriteDirs::load(),
+            insights_open: false,
+            last_scan: None,
+            partial_scan: None,
+            confirm_usage_reset: false,
+            hash_cache: Arc::new(HashCache::load()),
+            cache_check: CacheCheckSettings::load(),
+            cache_check_receiver: None,
+            last_cache_check: None,
+            results_view: ResultsView::default(),
+            scan_control: Arc::new(ScanControl::default()),
+            cache_import: None,
+            external_import: None,
         }
     }
 }
 
 impl DupeFinderApp {
-    fn start_scan(&mut self, ctx: &egui::Context) {
-        if self.state.selected_dir.is_empty() || self.state.scanning {
+    /// The directory and settings currently selected, as a scan job
+    fn current_job(&self) -> Option<ScanJob> {
+        if self.state.selected_dir.is_empty() && !self.state.scan_whole_drive {
+            return None;
+        }
+        Some(ScanJob {
+            dir: self.state.selected_dir.clone(),
+            extra_dirs: self.state.extra_dirs.iter().filter(|dir| !dir.trim().is_empty()).cloned().collect(),
+            whole_drive: self.state.scan_whole_drive,
+            config: self.state.config.clone(),
+        })
+    }
+    
+    /// Read the enabled known-files lists again in the background
+    fn reload_known_sets(&mut self, ctx: &egui::Context) {
+        let paths: Vec<PathBuf> = self.state.known_sets.enabled().map(|set| set.path.clone()).collect();
+        self.known_lists.index = None;
+        self.known_lists.receiver = None;
+        if paths.is_empty() {
             return;
         }
-        
-        self.state.scanning = true;
-        self.state.duplicate_groups.clear();
-        self.state.total_size_savings = 0;
-        self.state.status_message.clear();
-        
-        let dir = self.state.selected_dir.clone();
-        let progress = self.scan_progress.clone();
         let ctx_clone = ctx.clone();
-        let config = self.state.config.clone();
-        
         let (tx, rx) = channel();
-        self.result_receiver = Some(rx);
-        
+        self.known_lists.receiver = Some(rx);
         thread::spawn(move || {
-            let progress_clone = progress.clone();
-            let ctx_clone_2 = ctx_clone.clone();
-            let result = scan_directory(&dir, move |p| {
-                *progress_clone.lock().unwrap() = Some(p);
-                ctx_clone_2.request_repaint();
-            }, config);
-            
-            *progress.lock().unwrap() = None;
-            let _ = tx.send(result);
+            let _ = tx.send(load_known_sets(&paths));
             ctx_clone.request_repaint();
         });
     }
     
-    fn calculate_savings(&mut self) {
-        self.state.total_size_savings = 0;
-        for group in &self.state.duplicate_groups {
-            let files_to_delete: Vec<_> = group.files.iter()
-                .zip(&group.selected)
-                .filter(|(_, &selected)| !selected)
-                .collect();
-            
-            for (file, _) in files_to_delete {
-                self.state.total_size_savings += file.size;
-            }
+    fn finish_known_sets(&mut self, (index, counts): LoadedKnownSets) {
+        self.known_lists.receiver = None;
+        self.known_lists.counts = index.paths.iter().cloned().zip(counts).collect();
+        self.known_lists.index = Some(Arc::new(index));
+    }
+    
+    /// The loaded lists with their current names and actions, for a scan.
+    /// None while no list is enabled or the enabled ones are still loading.
+    fn known_files(&self) -> Option<Arc<KnownFiles>> {
+        let index = self.known_lists.index.as_ref()?;
+        let enabled: Vec<&KnownSet> = self.state.known_sets.enabled().collect();
+        if !index.paths.iter().eq(enabled.iter().map(|set| &set.path)) {
+            return None;
         }
+        Some(Arc::new(KnownFiles {
+            index: index.clone(),
+            sets: enabled.iter().map(|set| (set.name.clone(), set.action)).collect(),
+        }))
     }
     
-    fn delete_unchecked(&mut self, group_idx: usize) {
-        if group_idx >= self.state.duplicate_groups.len() {
+    fn start_scan(&mut self, ctx: &egui::Context) {
+        if self.state.scanning {
             return;
         }
-        
-        let group = &self.state.duplicate_groups[group_idx];
-        let mut deleted_count = 0;
-        let mut errors = Vec::new();
-        let mut critical_files_found = Vec::new();
-        
-        if !self.state.preview_mode {
-            for (_idx, (file, &keep)) in group.files.iter().zip(&group.selected).enumerate() {
-                if !keep {
-                    if file.is_critical {
-                        critical_files_found.push(file.path.display().to_string());
-                    }
-                    match fs::remove_file(&file.path) {
-                        Ok(_) => deleted_count += 1,
-                        Err(e) => errors.push(format!("Failed to delete {}: {}", file.path.display(), e)),
-                    }
-                }
-            }
-        } else {
-            // In preview mode, just count what would be deleted
-            for (_idx, (file, &keep)) in group.files.iter().zip(&group.selected).enumerate() {
-                if !keep {
-                    if file.is_critical {
-                        critical_files_found.push(file.path.display().to_string());
-                    }
-                    deleted_count += 1;
-                }
-            }
-        }
-        
-        if errors.is_empty() {
-            let action = if self.state.preview_mode { "Would delete" } else { "Deleted" };
-            let mut message = format!("✓ {} {} file(s) from group {}", action, deleted_count, group_idx + 1);
-            
-            if !critical_files_found.is_empty() {
-                message.push_str(&format!(" ⚠️ {} CRITICAL file(s) detected!", critical_files_found.len()));
-                if self.state.preview_mode {
-                    message.push_str(&format!(" Files: {}", critical_files_found.join(", ")));
-                }
-            }
-            
-            self.state.status_message = message;
-            if !self.state.preview_mode {
-                self.state.duplicate_groups.remove(group_idx);
-                self.calculate_savings();
-            }
-        } else {
-            self.state.status_message = format!("⚠ Errors: {}", errors.join("; "));
+        if let Some(job) = self.current_job() {
+            self.run_scan_job(job, ctx);
         }
     }
     
-    fn apply_selection_strategy(&mut self, strategy: &dyn SelectionStrategy, group_idx: usize) {
-        if let Some(group) = self.state.duplicate_groups.get_mut(group_idx) {
-            group.selected = strategy.select(&group.files);
+    fn pick_scan_root(&mut self) {
+        if let Some(path) = rfd::FileDialog::new().pick_folder() {
+            self.add_scan_root(path);
         }
-        self.calculate_savings();
     }
     
-    fn select_newest(&mut self, group_idx: usize) {
-        self.apply_selection_strategy(&KeepNewestStrategy, group_idx);
-    }
-    
-    fn select_oldest(&mut self, group_idx: usize) {
-        self.apply_selection_strategy(&KeepOldestStrategy, group_idx);
+    /// Add a folder to scan alongside the selected one, refusing folders that
+    /// overlap a root already listed
+    fn add_scan_root(&mut self, path: PathBuf) {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+        let overlapping = std::iter::once(&self.state.selected_dir)
+            .chain(&self.state.extra_dirs)
+            .filter(|dir| !dir.is_empty())
+            .find(|dir| {
+                let existing = Path::new(dir.as_str()).canonicalize().unwrap_or_else(|_| PathBuf::from(dir.as_str()));
+                existing.starts_with(&canonical) || canonical.starts_with(&existing)
+            });
+        match overlapping {
+            Some(dir) => {
+                self.state.status_message = format!("⚠ {} overlaps {}, which is already scanned", path.display(), dir);
+            }
+            None if self.state.selected_dir.is_empty() => self.state.selected_dir = path.display().to_string(),
+            None => self.state.extra_dirs.push(path.display().to_string()),
+        }
     }
     
-    fn bulk_apply_selection_strategy(&mut self, strategy: &dyn SelectionStrategy) {
-        for group in &mut self.state.duplicate_groups {
-            group.selected = strategy.select(&group.files);
+    /// Add a folder on a connected SFTP server, refusing one that overlaps a root already listed
+    fn add_remote_root(&mut self, root: String) {
+        let overlapping = std::iter::once(&self.state.selected_dir)
+            .chain(&self.state.extra_dirs)
+            .filter(|dir| !dir.is_empty())
+            .find(|dir| Path::new(dir.as_str()).starts_with(&root) || Path::new(&root).starts_with(dir.as_str()));
+        match overlapping {
+            Some(dir) => self.state.status_message = format!("⚠ {} overlaps {}, which is already scanned", root, dir),
+            None if self.state.selected_dir.is_empty() => self.state.selected_dir = root,
+            None => self.state.extra_dirs.push(root),
         }
-        self.calculate_savings();
     }
     
-    fn bulk_select_newest(&mut self) {
-        self.bulk_apply_selection_strategy(&KeepNewestStrategy);
-    }
+    fn connect_sftp(&mut self, ctx: &egui::Context) {
+        if self.network.offline {
+            self.sftp_dialog.message = OFFLINE_MESSAGE.to_string();
+            return;
+        }
+        let login = self.sftp_dialog.login.clone();
+        let ctx_clone = ctx.clone();
+        let (tx, rx) = channel();
+        self.sftp_dialog.receiver = Some(rx);
+        self.sftp_dialog.message = format!("Connecting to {}...", login.host);
+        thread::spawn(move || {
+            let _ = tx.send(SftpHost::connect(&login));
+            ctx_clone.request_repaint();
+        });
+    }
     
-    fn bulk_select_oldest(&mut self) {
-        self.bulk_apply_selection_strategy(&KeepOldestStrategy);
+    fn finish_sftp_connection(&mut self, result: Result<SftpHost, String>) {
+        self.sftp_dialog.receiver = None;
+        match result {
+            Ok(host) => {
+                let root = self.sftp_dialog.login.root_for(&self.sftp_dialog.remote_dir);
+                let connections = host.connection_count();
+                self.remote_sources.mount(Arc::new(host));
+                self.state.status_message = format!("✓ Connected to {} with {} connection(s)", self.sftp_dialog.login.host, connections);
+                self.add_remote_root(root);
+                // Passwords and passphrases are kept only as long as needed
+                self.sftp_dialog.login.secret.clear();
+                self.sftp_dialog.message.clear();
+                self.sftp_dialog.open = false;
+            }
+            Err(e) => self.sftp_dialog.message = format!("⚠ {}", e),
+        }
     }
-
-    fn bulk_delete_unchecked(&mut self) {
-        let mut deleted_count = 0;
-        let mut errors = Vec::new();
-        let mut groups_to_remove = Vec::new();
-        let mut critical_files_found = Vec::new();
-
-        for (group_idx, group) in self.state.duplicate_groups.iter().enumerate() {
-            let mut group_deleted_count = 0;
-            
-            if !self.state.preview_mode {
-                for (file, &keep) in group.files.iter().zip(&group.selected) {
-                    if !keep {
-                        if file.is_critical {
-                            critical_files_found.push(file.path.display().to_string());
-                        }
-                        match fs::remove_file(&file.path) {
-                            Ok(_) => {
-                                deleted_count += 1;
-                                group_deleted_count += 1;
-                            },
-                            Err(e) => errors.push(format!("Failed to delete {}: {}", file.path.display(), e)),
+    
+    /// Drop a connected server, bucket, Drive account or device and the roots that lie on it
+    fn disconnect_remote(&mut self, prefix: &Path) {
+        self.remote_sources.unmount(prefix);
+        self.s3_dialog.buckets.retain(|bucket| bucket.prefix() != prefix);
+        self.s3_dialog.plan = None;
+        self.drive_dialog.accounts.retain(|account| account.prefix() != prefix);
+        self.drive_dialog.plan = None;
+        self.device_dialog.connected.retain(|device| device.prefix() != prefix);
+        self.device_dialog.plan = None;
+        self.state.extra_dirs.retain(|dir| !Path::new(dir).starts_with(prefix));
+        if Path::new(&self.state.selected_dir).starts_with(prefix) {
+            self.state.selected_dir.clear();
+        }
+        self.state.status_message = format!("Disconnected from {}", prefix.display());
+    }
+    
+    fn show_sftp_window(&mut self, ctx: &egui::Context, palette: &Palette) {
+        if !self.sftp_dialog.open {
+            return;
+        }
+        let mut open = true;
+        let mut connect = false;
+        let mut disconnect = None;
+        let connecting = self.sftp_dialog.receiver.is_some();
+        egui::Window::new("🌐 Add SFTP Root")
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.label("Scan a folder on a server over SSH without mounting it. Remote files are only read; they can't be deleted from the results.");
+                ui.separator();
+                let login = &mut self.sftp_dialog.login;
+                ui.add_enabled_ui(!connecting, |ui| {
+                    egui::Grid::new("sftp_login").num_columns(2).show(ui, |ui| {
+                        ui.label("Host:");
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(&mut login.host).hint_text("server.example.com").desired_width(220.0));
+                            ui.label("Port:");
+                            ui.add(egui::DragValue::new(&mut login.port).range(1..=65535));
+                        });
+                        ui.end_row();
+                        ui.label("User:");
+                        ui.add(egui::TextEdit::singleline(&mut login.user).desired_width(220.0));
+                        ui.end_row();
+                        ui.label("Sign in with:");
+                        ui.horizontal(|ui| {
+                            for auth in SftpAuth::ALL {
+                                ui.radio_value(&mut login.auth, auth, auth.label());
+                            }
+                        });
+                        ui.end_row();
+                        match login.auth {
+                            SftpAuth::Agent => {}
+                            SftpAuth::KeyFile => {
+                                ui.label("Key file:");
+                                ui.horizontal(|ui| {
+                                    ui.add(egui::TextEdit::singleline(&mut login.key_file).desired_width(220.0));
+                                    if ui.button("📁 Browse").clicked() {
+                                        if let Some(path) = rfd::FileDialog::new().pick_file() {
+                                            login.key_file = path.display().to_string();
+                                        }
+                                    }
+                                });
+                                ui.end_row();
+                                ui.label("Passphrase:");
+                                ui.add(egui::TextEdit::singleline(&mut login.secret).password(true).hint_text("if the key has one").desired_width(220.0));
+                                ui.end_row();
+                            }
+                            SftpAuth::Password => {
+                                ui.label("Password:");
+                                ui.add(egui::TextEdit::singleline(&mut login.secret).password(true).desired_width(220.0));
+                                ui.end_row();
+                            }
                         }
+                        ui.label("Remote folder:");
+                        ui.add(egui::TextEdit::singleline(&mut self.sftp_dialog.remote_dir).hint_text("/srv/share").desired_width(220.0));
+                        ui.end_row();
+                        ui.label("Connections:");
+                        ui.add(egui::Slider::new(&mut login.connections, 1..=MAX_CONNECTIONS))
+                            .on_hover_text("Files read from the server at once. More connections hide network latency but load the server more; hashing uses no more threads than this.");
+                        ui.end_row();
+                    });
+                    ui.checkbox(&mut login.trust_unknown_host, "Trust a host key missing from known_hosts")
+                        .on_hover_text("Otherwise only servers listed in ~/.ssh/known_hosts are accepted. A key that differs from the listed one is always refused.");
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let ready = !login.host.trim().is_empty() && !login.user.trim().is_empty() && !self.sftp_dialog.remote_dir.trim().is_empty();
+                    if ui.add_enabled(ready && !connecting, egui::Button::new("🔌 Connect & Add")).clicked() {
+                        connect = true;
                     }
-                }
-            } else {
-                // In preview mode, just count what would be deleted
-                for (file, &keep) in group.files.iter().zip(&group.selected) {
-                    if !keep {
-                        if file.is_critical {
-                            critical_files_found.push(file.path.display().to_string());
-                        }
-                        deleted_count += 1;
-                        group_deleted_count += 1;
+                    if connecting {
+                        activity_spinner(ui, self.in_background);
                     }
+                });
+                if !self.sftp_dialog.message.is_empty() {
+                    let color = if self.sftp_dialog.message.starts_with('⚠') { palette.warning } else { ui.visuals().text_color() };
+                    ui.colored_label(color, &self.sftp_dialog.message);
                 }
+                disconnect = connected_remotes_ui(ui, &self.remote_sources, "sftp://", "Connected servers:", self.state.scanning);
+            });
+        if connect {
+            self.connect_sftp(ctx);
+        }
+        if let Some(prefix) = disconnect {
+            self.disconnect_remote(&prefix);
+        }
+        if !open {
+            self.sftp_dialog.open = false;
+            self.sftp_dialog.login.secret.clear();
+        }
+    }
+    
+    fn connect_s3(&mut self, ctx: &egui::Context) {
+        if self.network.offline {
+            self.s3_dialog.message = OFFLINE_MESSAGE.to_string();
+            return;
+        }
+        let login = self.s3_dialog.login.clone();
+        let ctx_clone = ctx.clone();
+        let (tx, rx) = channel();
+        self.s3_dialog.receiver = Some(rx);
+        self.s3_dialog.message = format!("Connecting to bucket {}...", login.bucket);
+        thread::spawn(move || {
+            let _ = tx.send(S3Bucket::connect(&login));
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    fn finish_s3_connection(&mut self, result: Result<S3Bucket, String>) {
+        self.s3_dialog.receiver = None;
+        match result {
+            Ok(bucket) => {
+                let bucket = Arc::new(bucket);
+                self.s3_dialog.buckets.retain(|other| other.prefix() != bucket.prefix());
+                self.s3_dialog.buckets.push(bucket.clone());
+                self.remote_sources.mount(bucket);
+                self.state.status_message = format!("✓ Connected to bucket {}", self.s3_dialog.login.bucket);
+                self.add_remote_root(self.s3_dialog.login.root());
+                self.s3_dialog.message.clear();
             }
-            
-            // Only mark group for removal if files were actually deleted (or would be deleted in preview)
-            if group_deleted_count > 0 {
-                groups_to_remove.push(group_idx);
-            }
+            Err(e) => self.s3_dialog.message = format!("⚠ {}", e),
         }
-
-        if errors.is_empty() {
-            let action = if self.state.preview_mode { "Would bulk delete" } else { "Bulk deleted" };
-            let mut message = format!("✓ {} {} file(s) across {} group(s).", action, deleted_count, groups_to_remove.len());
-            
-            if !critical_files_found.is_empty() {
-                message.push_str(&format!(" ⚠️ {} CRITICAL file(s) detected!", critical_files_found.len()));
-                if self.state.preview_mode && critical_files_found.len() <= 5 {
-                    message.push_str(&format!(" Files: {}", critical_files_found.join(", ")));
-                } else if self.state.preview_mode {
-                    message.push_str(&format!(" First 5: {}", critical_files_found.iter().take(5).map(|s| s.as_str()).collect::<Vec<_>>().join(", ")));
+    }
+    
+    fn plan_s3_action(&mut self) {
+        let buckets = &self.s3_dialog.buckets;
+        let remote = plan_remote_targets(&self.state.duplicate_groups, |path| buckets.iter().any(|bucket| path.starts_with(bucket.prefix())));
+        self.s3_dialog.plan = Some(S3Plan { action: self.s3_dialog.action, remote });
+    }
+    
+    /// Apply the previewed plan in the background. Before an object is
+    /// changed, it is checked to still have the size and ETag the scan listed;
+    /// before it is deleted, its kept copy is checked the same way, or
+    /// hashed again when it is stored elsewhere.
+    fn apply_s3_plan(&mut self, ctx: &egui::Context) {
+        if self.state.previewing() {
+            return;
+        }
+        let Some(plan) = self.s3_dialog.plan.take() else {
+            return;
+        };
+        let buckets = self.s3_dialog.buckets.clone();
+        let sources = self.remote_sources.clone();
+        let ctx_clone = ctx.clone();
+        let (tx, rx) = channel();
+        self.s3_dialog.action_receiver = Some(rx);
+        self.s3_dialog.message = format!("{} {} object(s)...", plan.action.label(), plan.remote.targets.len());
+        let stop = self.quit.stop.clone();
+        thread::spawn(move || {
+            let bucket_of = |path: &Path| buckets.iter().find(|bucket| path.starts_with(bucket.prefix())).cloned();
+            let kept_unchanged = |target: &RemoteTarget| {
+                if let Some(bucket) = bucket_of(&target.kept) {
+                    return bucket.check_unchanged(&target.kept, target.size).is_ok();
                 }
-            }
-            
-            self.state.status_message = message;
-            
-            if !self.state.preview_mode {
-                // Remove groups in reverse order to maintain indices
-                for &group_idx in groups_to_remove.iter().rev() {
-                    self.state.duplicate_groups.remove(group_idx);
+                // A digest the store reported stands in for a hash, as it did in the scan
+                if target.kept_hash.starts_with("md5:") {
+                    return sources.metadata(&target.kept).ok().and_then(|metadata| metadata.digest).as_ref() == Some(&target.kept_hash);
+                }
+                let config = ScanConfig { source: Some(Arc::new(sources.clone())), ..ScanConfig::default() };
+                !target.kept_hash.is_empty() && hash_file(&target.kept, &config).ok().as_ref() == Some(&target.kept_hash)
+            };
+            let results = plan.remote.targets.iter().map(|target| {
+                let result = (|| {
+                    if stop.load(Ordering::Relaxed) {
+                        return Err(STOPPED_FOR_QUIT.to_string());
+                    }
+                    let bucket = bucket_of(&target.path).ok_or_else(|| "its bucket is no longer connected".to_string())?;
+                    bucket.check_unchanged(&target.path, target.size)?;
+                    if plan.action == S3Action::Delete && !kept_unchanged(target) {
+                        return Err(format!("kept copy {} is gone or changed", target.kept.display()));
+                    }
+                    bucket.apply(&target.path, plan.action)
+                })();
+                (target.path.clone(), result.map_err(|e| format!("Skipped {}: {}", target.path.display(), e)))
+            }).collect();
+            let _ = tx.send(results);
+            ctx_clone.request_repaint();
+        });
+        self.s3_dialog.action = plan.action;
+    }
+    
+    fn finish_s3_action(&mut self, results: RemoteResults) {
+        self.s3_dialog.action_receiver = None;
+        let action = self.s3_dialog.action;
+        let mut changed = HashSet::new();
+        let mut errors = Vec::new();
+        for (path, result) in results {
+            match result {
+                Ok(()) => {
+                    let record = match action {
+                        S3Action::Delete => ActionRecord::now("s3 delete", path.display().to_string()),
+                        S3Action::StorageClass(class) => ActionRecord::now("s3 storage class", format!("{} → {}", path.display(), class)),
+                    };
+                    self.state.action_log.push(record);
+                    changed.insert(path);
                 }
-                self.calculate_savings();
+                Err(e) => errors.push(e),
             }
+        }
+        let message = format!("{}: {} object(s) done", action.label(), changed.len());
+        log_errors(&errors);
+        self.s3_dialog.message = if errors.is_empty() {
+            format!("✓ {}", message)
         } else {
-            self.state.status_message = format!("⚠ Bulk delete finished with {} errors: {}", errors.len(), errors.iter().take(3).cloned().collect::<Vec<_>>().join("; "));
-            if !self.state.preview_mode {
-                // Still remove groups that were successfully processed
-                for &group_idx in groups_to_remove.iter().rev() {
-                    self.state.duplicate_groups.remove(group_idx);
+            format!("⚠ {}, {} error(s): {}", message, errors.len(), errors.iter().take(3).cloned().collect::<Vec<_>>().join("; "))
+        };
+        self.state.status_message = self.s3_dialog.message.clone();
+        if action == S3Action::Delete {
+            self.note_deletions(changed.len(), self.results_size(&changed));
+            self.forget_files(&changed);
+        }
+    }
+    
+    fn show_s3_window(&mut self, ctx: &egui::Context, palette: &Palette) {
+        if !self.s3_dialog.open {
+            return;
+        }
+        let mut open = true;
+        let mut connect = false;
+        let mut disconnect = None;
+        let mut preview = false;
+        let mut apply = false;
+        let connecting = self.s3_dialog.receiver.is_some();
+        let applying = self.s3_dialog.action_receiver.is_some();
+        let previewing = self.state.previewing();
+        egui::Window::new("🪣 S3 Buckets")
+            .open(&mut open)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                ui.label("Scan a bucket on AWS S3 or an S3-compatible service, alone or together with local folders. Objects are listed with their size and ETag; those uploaded in several parts are downloaded and hashed.");
+                ui.separator();
+                let login = &mut self.s3_dialog.login;
+                ui.add_enabled_ui(!connecting, |ui| {
+                    egui::Grid::new("s3_login").num_columns(2).show(ui, |ui| {
+                        ui.label("Endpoint:");
+                        ui.add(egui::TextEdit::singleline(&mut login.endpoint).hint_text("empty for AWS, e.g. http://localhost:9000").desired_width(260.0));
+                        ui.end_row();
+                        ui.label("Region:");
+                        ui.add(egui::TextEdit::singleline(&mut login.region).desired_width(260.0));
+                        ui.end_row();
+                        ui.label("Bucket:");
+                        ui.add(egui::TextEdit::singleline(&mut login.bucket).desired_width(260.0));
+                        ui.end_row();
+                        ui.label("Prefix:");
+                        ui.add(egui::TextEdit::singleline(&mut login.prefix).hint_text("empty for the whole bucket").desired_width(260.0));
+                        ui.end_row();
+                        ui.label("Access key:");
+                        ui.add(egui::TextEdit::singleline(&mut login.access_key).desired_width(260.0));
+                        ui.end_row();
+                        ui.label("Secret key:");
+                        ui.add(egui::TextEdit::singleline(&mut login.secret_key).password(true).desired_width(260.0));
+                        ui.end_row();
+                        ui.label("Session token:");
+                        ui.add(egui::TextEdit::singleline(&mut login.session_token).password(true).hint_text("only for temporary credentials").desired_width(260.0));
+                        ui.end_row();
+                        ui.label("Downloads:");
+                        ui.add(egui::Slider::new(&mut login.readers, 1..=MAX_READERS))
+                            .on_hover_text("Objects downloaded at once when they have to be hashed; hashing uses no more threads than this");
+                        ui.end_row();
+                    });
+                    ui.checkbox(&mut login.trust_etags, "Compare single-part uploads by ETag without downloading")
+                        .on_hover_text("The ETag of an object uploaded in one part is the MD5 of its contents. Objects compared with local files are always downloaded and hashed.");
+                });
+                ui.horizontal(|ui| {
+                    let ready = !login.bucket.trim().is_empty() && !login.region.trim().is_empty() && !login.access_key.is_empty() && !login.secret_key.is_empty();
+                    if ui.add_enabled(ready && !connecting, egui::Button::new("🔌 Connect & Add")).clicked() {
+                        connect = true;
+                    }
+                    if connecting || applying {
+                        activity_spinner(ui, self.in_background);
+                    }
+                });
+                if !self.s3_dialog.message.is_empty() {
+                    let color = if self.s3_dialog.message.starts_with('⚠') { palette.warning } else { ui.visuals().text_color() };
+                    ui.colored_label(color, &self.s3_dialog.message);
                 }
-                self.calculate_savings();
-            }
+                disconnect = connected_remotes_ui(ui, &self.remote_sources, "s3://", "Connected buckets:", self.state.scanning || applying);
+                
+                if self.s3_dialog.buckets.is_empty() {
+                    return;
+                }
+                ui.separator();
+                ui.strong("Unchecked duplicates stored in buckets");
+                ui.horizontal(|ui| {
+                    let before = self.s3_dialog.action;
+                    egui::ComboBox::from_id_salt("s3_action")
+                        .selected_text(self.s3_dialog.action.label())
+                        .show_ui(ui, |ui| {
+                            for action in S3Action::all() {
+                                ui.selectable_value(&mut self.s3_dialog.action, action, action.label());
+                            }
+                        });
+                    if self.s3_dialog.action != before {
+                        self.s3_dialog.plan = None;
+                    }
+                    if ui.add_enabled(!applying, egui::Button::new("🔍 Preview")).clicked() {
+                        preview = true;
+                    }
+                    let ready = self.s3_dialog.plan.as_ref().is_some_and(|plan| !plan.remote.targets.is_empty());
+                    if ui.add_enabled(ready && !previewing && !applying, egui::Button::new("✔ Apply"))
+                        .on_disabled_hover_text("Preview the action first; nothing is changed while preview or safe mode is on")
+                        .clicked()
+                    {
+                        apply = true;
+                    }
+                });
+                if previewing {
+                    ui.colored_label(palette.preview, "Preview mode is on: bucket actions are only listed, never applied");
+                }
+                if let Some(plan) = &self.s3_dialog.plan {
+                    remote_plan_ui(ui, palette, &plan.action.label(), &plan.remote);
+                }
+            });
+        if connect {
+            self.connect_s3(ctx);
+        }
+        if let Some(prefix) = disconnect {
+            self.disconnect_remote(&prefix);
+        }
+        if preview {
+            self.plan_s3_action();
+        }
+        if apply {
+            self.apply_s3_plan(ctx);
+        }
+        if !open {
+            self.s3_dialog.open = false;
         }
     }
     
-    fn export_results(&self) -> Result<String, String> {
-        match serde_json::to_string_pretty(&self.state.duplicate_groups) {
-            Ok(json) => Ok(json),
-            Err(e) => Err(format!("Failed to serialize results: {}", e)),
+    /// Start the sign-in: the browser opens Google's consent page, and a
+    /// background thread waits for it to send the answer back
+    fn sign_in_drive(&mut self, ctx: &egui::Context) {
+        if self.network.offline {
+            self.drive_dialog.message = OFFLINE_MESSAGE.to_string();
+            return;
         }
+        let login = self.drive_dialog.login.clone();
+        let pending = match gdrive::begin_sign_in(&login) {
+            Ok(pending) => pending,
+            Err(e) => {
+                self.drive_dialog.message = format!("⚠ {}", e);
+                return;
+            }
+        };
+        ctx.open_url(egui::OpenUrl::new_tab(&pending.url));
+        self.drive_dialog.sign_in_url = pending.url.clone();
+        let ctx_clone = ctx.clone();
+        let (tx, rx) = channel();
+        self.drive_dialog.receiver = Some(rx);
+        self.drive_dialog.message = "Waiting for the sign-in in the browser...".to_string();
+        thread::spawn(move || {
+            let _ = tx.send(pending.finish(&login));
+            ctx_clone.request_repaint();
+        });
     }
     
-    fn import_results(&mut self, json: &str) -> Result<(), String> {
-        match serde_json::from_str::<Vec<DuplicateGroup>>(json) {
-            Ok(groups) => {
-                self.state.duplicate_groups = groups;
-                self.calculate_savings();
-                self.state.status_message = format!("Imported {} duplicate group(s)", self.state.duplicate_groups.len());
-                Ok(())
-            },
-            Err(e) => Err(format!("Failed to import results: {}", e)),
+    fn finish_drive_sign_in(&mut self, result: Result<DriveAccount, String>) {
+        self.drive_dialog.receiver = None;
+        self.drive_dialog.sign_in_url.clear();
+        match result {
+            Ok(account) => {
+                let account = Arc::new(account);
+                self.drive_dialog.accounts.retain(|other| other.prefix() != account.prefix());
+                self.drive_dialog.accounts.push(account.clone());
+                self.remote_sources.mount(account.clone());
+                self.state.status_message = format!("✓ Signed in to {}", account.prefix().display());
+                self.add_remote_root(account.root_for(&self.drive_dialog.login.folder));
+                self.drive_dialog.message.clear();
+            }
+            Err(e) => self.drive_dialog.message = format!("⚠ {}", e),
         }
     }
-}
-
-impl eframe::App for DupeFinderApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Check for scan results
-        if let Some(rx) = &self.result_receiver {
-            if let Ok(result) = rx.try_recv() {
-                match result {
-                    Ok(groups) => {
-                        self.state.duplicate_groups = groups.into_iter()
-                            .map(|files| {
-                                let selected = vec![true; files.len()];
-                                DuplicateGroup { files, selected }
-                            })
-                            .collect();
-                        self.state.scanning = false;
-                        self.result_receiver = None;
-                        self.calculate_savings();
-                        
-                        if self.state.duplicate_groups.is_empty() {
-                            self.state.status_message = "No duplicates found.".to_string();
-                        } else {
-                            self.state.status_message = format!("Found {} duplicate group(s)!", self.state.duplicate_groups.len());
-                        }
+    
+    fn plan_drive_trash(&mut self) {
+        let accounts = &self.drive_dialog.accounts;
+        self.drive_dialog.plan = Some(plan_remote_targets(&self.state.duplicate_groups, |path| {
+            accounts.iter().any(|account| path.starts_with(account.prefix()))
+        }));
+    }
+    
+    /// Move the previewed files to the Drive trash in the background, after
+    /// checking that each file and its kept copy still have the scanned size
+    fn apply_drive_trash(&mut self, ctx: &egui::Context) {
+        if self.state.previewing() {
+            return;
+        }
+        let Some(plan) = self.drive_dialog.plan.take() else {
+            return;
+        };
+        let accounts = self.drive_dialog.accounts.clone();
+        let sources = self.remote_sources.clone();
+        let ctx_clone = ctx.clone();
+        let (tx, rx) = channel();
+        self.drive_dialog.action_receiver = Some(rx);
+        self.drive_dialog.message = format!("Moving {} file(s) to the Drive trash...", plan.targets.len());
+        let stop = self.quit.stop.clone();
+        thread::spawn(move || {
+            let account_of = |path: &Path| accounts.iter().find(|account| path.starts_with(account.prefix())).cloned();
+            let current_size = |path: &Path| match account_of(path) {
+                Some(account) => account.current_size(path),
+                None => sources.metadata(path).map(|metadata| metadata.size).map_err(|e| e.to_string()),
+            };
+            let results = plan.targets.iter().map(|target| {
+                let result = (|| {
+                    if stop.load(Ordering::Relaxed) {
+                        return Err(STOPPED_FOR_QUIT.to_string());
                     }
-                    Err(e) => {
-                        self.state.scanning = false;
-                        self.result_receiver = None;
-                        self.state.status_message = format!("Scan error: {:?}", e);
+                    let account = account_of(&target.path).ok_or_else(|| "its account is no longer signed in".to_string())?;
+                    if current_size(&target.path)? != target.size {
+                        return Err("changed since scan".to_string());
+                    }
+                    if current_size(&target.kept).ok() != Some(target.size) {
+                        return Err(format!("kept copy {} is gone or changed", target.kept.display()));
                     }
+                    account.trash(&target.path)
+                })();
+                (target.path.clone(), result.map_err(|e| format!("Skipped {}: {}", target.path.display(), e)))
+            }).collect();
+            let _ = tx.send(results);
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    fn finish_drive_trash(&mut self, results: RemoteResults) {
+        self.drive_dialog.action_receiver = None;
+        let mut trashed = HashSet::new();
+        let mut errors = Vec::new();
+        for (path, result) in results {
+            match result {
+                Ok(()) => {
+                    self.state.action_log.push(ActionRecord::now("drive trash", path.display().to_string()));
+                    trashed.insert(path);
                 }
+                Err(e) => errors.push(e),
             }
         }
-        
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("🔍 DupeFinder - Rust Duplicate File Finder");
-            ui.add_space(10.0);
-            
-            // Directory selection
-            ui.horizontal(|ui| {
-                ui.label("Directory:");
-                ui.add(egui::TextEdit::singleline(&mut self.state.selected_dir).desired_width(500.0));
-                
-                if ui.button("📁 Browse").clicked() {
-                    if let Some(path) = rfd::FileDialog::new().pick_folder() {
-                        self.state.selected_dir = path.display().to_string();
+        let message = format!("Moved {} file(s) to the Drive trash", trashed.len());
+        log_errors(&errors);
+        self.drive_dialog.message = if errors.is_empty() {
+            format!("✓ {}", message)
+        } else {
+            format!("⚠ {}, {} error(s): {}", message, errors.len(), errors.iter().take(3).cloned().collect::<Vec<_>>().join("; "))
+        };
+        self.state.status_message = self.drive_dialog.message.clone();
+        self.note_deletions(trashed.len(), self.results_size(&trashed));
+        self.forget_files(&trashed);
+    }
+    
+    fn show_drive_window(&mut self, ctx: &egui::Context, palette: &Palette) {
+        if !self.drive_dialog.open {
+            return;
+        }
+        let mut open = true;
+        let mut sign_in = false;
+        let mut disconnect = None;
+        let mut preview = false;
+        let mut apply = false;
+        let signing_in = self.drive_dialog.receiver.is_some();
+        let applying = self.drive_dialog.action_receiver.is_some();
+        let previewing = self.state.previewing();
+        egui::Window::new("☁ Google Drive")
+            .open(&mut open)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                ui.label("Scan My Drive, alone or together with local folders. Files in Drive are compared by the size and MD5 checksum Drive keeps for them, without downloading; Google Docs, Sheets and Slides have neither and are left out.");
+                ui.separator();
+                let login = &mut self.drive_dialog.login;
+                ui.add_enabled_ui(!signing_in, |ui| {
+                    egui::Grid::new("drive_login").num_columns(2).show(ui, |ui| {
+                        ui.label("Client ID:");
+                        ui.add(egui::TextEdit::singleline(&mut login.client_id).hint_text("OAuth client of type \"Desktop app\"").desired_width(260.0));
+                        ui.end_row();
+                        ui.label("Client secret:");
+                        ui.add(egui::TextEdit::singleline(&mut login.client_secret).password(true).desired_width(260.0));
+                        ui.end_row();
+                        ui.label("Folder:");
+                        ui.add(egui::TextEdit::singleline(&mut login.folder).hint_text("empty for all of My Drive").desired_width(260.0));
+                        ui.end_row();
+                        ui.label("Downloads:");
+                        ui.add(egui::Slider::new(&mut login.downloads, 1..=MAX_DOWNLOADS))
+                            .on_hover_text("Files downloaded at once when they are compared with local files");
+                        ui.end_row();
+                    });
+                });
+                ui.horizontal(|ui| {
+                    let ready = !login.client_id.trim().is_empty() && !login.client_secret.is_empty();
+                    if ui.add_enabled(ready && !signing_in, egui::Button::new("🔑 Sign in with Google")).clicked() {
+                        sign_in = true;
+                    }
+                    if signing_in || applying {
+                        activity_spinner(ui, self.in_background);
                     }
+                });
+                if signing_in && !self.drive_dialog.sign_in_url.is_empty() {
+                    ui.hyperlink_to("Open the sign-in page again", &self.drive_dialog.sign_in_url);
                 }
-            });
-            
-            ui.add_space(10.0);
-            
-            // Configuration and controls
-            ui.horizontal(|ui| {
-                ui.checkbox(&mut self.state.config.include_hidden, "Include hidden files");
-                ui.checkbox(&mut self.state.preview_mode, "Preview mode (no actual deletion)");
+                if !self.drive_dialog.message.is_empty() {
+                    let color = if self.drive_dialog.message.starts_with('⚠') { palette.warning } else { ui.visuals().text_color() };
+                    ui.colored_label(color, &self.drive_dialog.message);
+                }
+                disconnect = connected_remotes_ui(ui, &self.remote_sources, "gdrive://", "Signed-in accounts:", self.state.scanning || applying);
                 
-                ui.add(egui::Slider::new(&mut self.state.config.buffer_size, 1024..=1048576)
-                    .text("Buffer size"));
+                if self.drive_dialog.accounts.is_empty() {
+                    return;
+                }
+                ui.separator();
+                ui.strong("Unchecked duplicates stored in Drive");
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!applying, egui::Button::new("🔍 Preview")).clicked() {
+                        preview = true;
+                    }
+                    let ready = self.drive_dialog.plan.as_ref().is_some_and(|plan| !plan.targets.is_empty());
+                    if ui.add_enabled(ready && !previewing && !applying, egui::Button::new("🗑 Move to Drive Trash"))
+                        .on_disabled_hover_text("Preview first; nothing is moved while preview or safe mode is on")
+                        .clicked()
+                    {
+                        apply = true;
+                    }
+                });
+                if previewing {
+                    ui.colored_label(palette.preview, "Preview mode is on: Drive files are only listed, never moved");
+                }
+                if let Some(plan) = &self.drive_dialog.plan {
+                    remote_plan_ui(ui, palette, "Move to Drive trash", plan);
+                }
             });
-            
-            ui.add_space(10.0);
-            
-            // Scan button
-            ui.horizontal(|ui| {
-                if ui.add_enabled(!self.state.scanning, egui::Button::new("🔍 Scan Directory")).clicked() {
-                    self.start_scan(ctx);
+        if sign_in {
+            self.sign_in_drive(ctx);
+        }
+        if let Some(prefix) = disconnect {
+            self.disconnect_remote(&prefix);
+        }
+        if preview {
+            self.plan_drive_trash();
+        }
+        if apply {
+            self.apply_drive_trash(ctx);
+        }
+        if !open {
+            self.drive_dialog.open = false;
+        }
+    }
+    
+    fn open_device_window(&mut self, ctx: &egui::Context) {
+        self.device_dialog.open = true;
+        if self.device_dialog.devices_receiver.is_none() {
+            self.refresh_devices(ctx);
+        }
+    }
+    
+    fn refresh_devices(&mut self, ctx: &egui::Context) {
+        let ctx_clone = ctx.clone();
+        let (tx, rx) = channel();
+        self.device_dialog.devices_receiver = Some(rx);
+        thread::spawn(move || {
+            let _ = tx.send(adb::list_devices());
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    fn finish_device_list(&mut self, result: Result<Vec<DeviceInfo>, String>) {
+        self.device_dialog.devices_receiver = None;
+        match result {
+            Ok(devices) => {
+                if devices.is_empty() {
+                    self.device_dialog.message = "⚠ No device found; connect one by USB and turn on USB debugging".to_string();
+                } else if self.device_dialog.message.starts_with("⚠ No device") {
+                    self.device_dialog.message.clear();
+                }
+                self.device_dialog.devices = devices;
+            }
+            Err(e) => self.device_dialog.message = format!("⚠ {}", e),
+        }
+    }
+    
+    fn connect_device(&mut self, ctx: &egui::Context, device: DeviceInfo) {
+        let hash_on_device = self.device_dialog.hash_on_device;
+        let ctx_clone = ctx.clone();
+        let (tx, rx) = channel();
+        self.device_dialog.receiver = Some(rx);
+        self.device_dialog.message = format!("Connecting to {}...", device.label());
+        thread::spawn(move || {
+            let _ = tx.send(AdbDevice::connect(&device, hash_on_device));
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    fn finish_device_connection(&mut self, result: Result<AdbDevice, String>) {
+        self.device_dialog.receiver = None;
+        match result {
+            Ok(device) => {
+                let device = Arc::new(device);
+                self.device_dialog.connected.retain(|other| other.prefix() != device.prefix());
+                self.device_dialog.connected.push(device.clone());
+                self.remote_sources.mount(device.clone());
+                self.state.status_message = format!("✓ Added {}", device.label());
+                self.add_remote_root(device.root_for(&self.device_dialog.folder));
+                self.device_dialog.message = if self.device_dialog.hash_on_device && !device.hashes_on_device() {
+                    "⚠ The device has no sha256sum; its files are copied over USB to be hashed".to_string()
+                } else {
+                    String::new()
+                };
+            }
+            Err(e) => self.device_dialog.message = format!("⚠ {}", e),
+        }
+    }
+    
+    fn plan_device_delete(&mut self) {
+        let connected = &self.device_dialog.connected;
+        self.device_dialog.plan = Some(plan_remote_targets(&self.state.duplicate_groups, |path| {
+            connected.iter().any(|device| path.starts_with(device.prefix()))
+        }));
+    }
+    
+    /// Delete the previewed device files in the background. A file is only
+    /// deleted once its contents are known to be on this PC: in the kept
+    /// copy when that is local, or else in the backup folder, where it is
+    /// copied first. Either copy must hash the same as the file on the device.
+    fn apply_device_delete(&mut self, ctx: &egui::Context) {
+        if self.state.previewing() {
+            return;
+        }
+        let Some(plan) = self.device_dialog.plan.take() else {
+            return;
+        };
+        let connected = self.device_dialog.connected.clone();
+        let backup_dir = PathBuf::from(self.device_dialog.backup_dir.trim());
+        let ctx_clone = ctx.clone();
+        let (tx, rx) = channel();
+        self.device_dialog.action_receiver = Some(rx);
+        self.device_dialog.message = format!("Deleting {} file(s) from devices...", plan.targets.len());
+        let stop = self.quit.stop.clone();
+        thread::spawn(move || {
+            let results = plan.targets.iter().map(|target| {
+                let result = (|| {
+                    if stop.load(Ordering::Relaxed) {
+                        return Err(STOPPED_FOR_QUIT.to_string());
+                    }
+                    let device = connected.iter().find(|device| target.path.starts_with(device.prefix())).cloned()
+                        .ok_or_else(|| "its device is no longer added".to_string())?;
+                    if device.current_size(&target.path)? != target.size {
+                        return Err("changed since scan".to_string());
+                    }
+                    let device_config = ScanConfig { source: Some(device.clone()), ..ScanConfig::default() };
+                    let hash = hash_file(&target.path, &device_config).map_err(|e| format!("cannot hash it: {}", e))?;
+                    let backup = if !is_remote(&target.kept) {
+                        if hash_file(&target.kept, &ScanConfig::default()).ok() != Some(hash) {
+                            return Err(format!("kept copy {} is gone or changed", target.kept.display()));
+                        }
+                        None
+                    } else {
+                        if backup_dir.as_os_str().is_empty() {
+                            return Err("no copy is kept on this PC; choose a backup folder to copy it to first".to_string());
+                        }
+                        let backup = backup_dir.join(target.path.strip_prefix("adb://").unwrap_or(&target.path));
+                        if backup.exists() {
+                            return Err(format!("{} already exists", backup.display()));
+                        }
+                        if let Some(parent) = backup.parent() {
+                            fs::create_dir_all(parent).map_err(|e| format!("cannot create {}: {}", parent.display(), e))?;
+                        }
+                        device.pull(&target.path, &backup)?;
+                        if hash_file(&backup, &ScanConfig::default()).ok() != Some(hash) {
+                            let _ = fs::remove_file(&backup);
+                            return Err("the copy in the backup folder does not match".to_string());
+                        }
+                        Some(backup)
+                    };
+                    device.delete(&target.path)?;
+                    Ok(backup)
+                })();
+                (target.path.clone(), result.map_err(|e| format!("Skipped {}: {}", target.path.display(), e)))
+            }).collect();
+            let _ = tx.send(results);
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    fn finish_device_delete(&mut self, results: DeviceResults) {
+        self.device_dialog.action_receiver = None;
+        let mut deleted = HashSet::new();
+        let mut backed_up = 0;
+        let mut errors = Vec::new();
+        for (path, result) in results {
+            match result {
+                Ok(backup) => {
+                    let detail = match &backup {
+                        Some(backup) => format!("{} (copied to {})", path.display(), backup.display()),
+                        None => path.display().to_string(),
+                    };
+                    self.state.action_log.push(ActionRecord::now("device delete", detail));
+                    backed_up += usize::from(backup.is_some());
+                    deleted.insert(path);
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        let message = format!("Deleted {} file(s) from devices, {} copied to the backup folder first", deleted.len(), backed_up);
+        log_errors(&errors);
+        self.device_dialog.message = if errors.is_empty() {
+            format!("✓ {}", message)
+        } else {
+            format!("⚠ {}, {} error(s): {}", message, errors.len(), errors.iter().take(3).cloned().collect::<Vec<_>>().join("; "))
+        };
+        self.state.status_message = self.device_dialog.message.clone();
+        self.note_deletions(deleted.len(), self.results_size(&deleted));
+        self.forget_files(&deleted);
+    }
+    
+    fn show_device_window(&mut self, ctx: &egui::Context, palette: &Palette) {
+        if !self.device_dialog.open {
+            return;
+        }
+        let mut open = true;
+        let mut refresh = false;
+        let mut connect = None;
+        let mut disconnect = None;
+        let mut choose_backup = false;
+        let mut preview = false;
+        let mut apply = false;
+        let listing = self.device_dialog.devices_receiver.is_some();
+        let connecting = self.device_dialog.receiver.is_some();
+        let applying = self.device_dialog.action_receiver.is_some();
+        let previewing = self.state.previewing();
+        egui::Window::new("📱 Android Devices")
+            .open(&mut open)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                ui.label("Scan a folder on an Android phone or tablet connected by USB, through adb with USB debugging turned on. Files are hashed on the device where it can, so only their hashes cross the cable.");
+                ui.separator();
+                ui.add_enabled_ui(!connecting, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Folder:");
+                        ui.add(egui::TextEdit::singleline(&mut self.device_dialog.folder).hint_text(DEFAULT_FOLDER).desired_width(260.0));
+                    });
+                    ui.checkbox(&mut self.device_dialog.hash_on_device, "Hash files on the device")
+                        .on_hover_text("Run sha256sum on the device instead of copying every file over USB to hash it here");
+                });
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!listing, egui::Button::new("🔄 Refresh Devices")).clicked() {
+                        refresh = true;
+                    }
+                    if listing || connecting || applying {
+                        activity_spinner(ui, self.in_background);
+                    }
+                });
+                for device in &self.device_dialog.devices {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} – {}", device.label(), device.state));
+                        let ready = device.state == "device" && !self.device_dialog.folder.trim().is_empty();
+                        if ui.add_enabled(ready && !connecting, egui::Button::new("➕ Add")).clicked() {
+                            connect = Some(device.clone());
+                        }
+                    });
+                }
+                if !self.device_dialog.message.is_empty() {
+                    let color = if self.device_dialog.message.starts_with('⚠') { palette.warning } else { ui.visuals().text_color() };
+                    ui.colored_label(color, &self.device_dialog.message);
+                }
+                disconnect = connected_remotes_ui(ui, &self.remote_sources, "adb://", "Added devices:", self.state.scanning || applying);
+                
+                if self.device_dialog.connected.is_empty() {
+                    return;
+                }
+                ui.separator();
+                ui.strong("Unchecked duplicates stored on devices");
+                ui.horizontal(|ui| {
+                    ui.label("Backup folder:");
+                    ui.add(egui::TextEdit::singleline(&mut self.device_dialog.backup_dir).hint_text("for files whose kept copy is not on this PC").desired_width(240.0));
+                    if ui.button("📁").clicked() {
+                        choose_backup = true;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!applying, egui::Button::new("🔍 Preview")).clicked() {
+                        preview = true;
+                    }
+                    let ready = self.device_dialog.plan.as_ref().is_some_and(|plan| !plan.targets.is_empty());
+                    if ui.add_enabled(ready && !previewing && !applying, egui::Button::new("🗑 Delete from Device"))
+                        .on_disabled_hover_text("Preview first; nothing is deleted while preview or safe mode is on")
+                        .clicked()
+                    {
+                        apply = true;
+                    }
+                });
+                if previewing {
+                    ui.colored_label(palette.preview, "Preview mode is on: device files are only listed, never deleted");
+                }
+                if let Some(plan) = &self.device_dialog.plan {
+                    remote_plan_ui(ui, palette, "Delete from device", plan);
+                }
+            });
+        if refresh {
+            self.refresh_devices(ctx);
+        }
+        if let Some(device) = connect {
+            self.connect_device(ctx, device);
+        }
+        if let Some(prefix) = disconnect {
+            self.disconnect_remote(&prefix);
+        }
+        if choose_backup {
+            if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                self.device_dialog.backup_dir = folder.display().to_string();
+            }
+        }
+        if preview {
+            self.plan_device_delete();
+        }
+        if apply {
+            self.apply_device_delete(ctx);
+        }
+        if !open {
+            self.device_dialog.open = false;
+        }
+    }
+    
+    fn queue_scan(&mut self) {
+        if let Some(job) = self.current_job() {
+            self.state.status_message = format!("Queued scan of {} ({} waiting)", job.label(), self.state.scan_queue.len() + 1);
+            self.state.scan_queue.push_back(job);
+        }
+    }
+    
+    /// Set the shown results aside in the scan history and clear the view for a new scan
+    fn begin_new_results(&mut self) {
+        self.save_active_results();
+        self.partial_scan = None;
+        self.state.active_scan = None;
+        self.state.scanning = true;
+        self.renaming = None;
+        self.basket.paths.clear();
+        self.state.duplicate_groups.clear();
+        self.state.near_groups.clear();
+        self.state.chunk_pairs.clear();
+        self.set_scan_roots(Vec::new());
+        self.state.trashed.clear();
+        self.state.symlinks = SymlinkReport::default();
+        self.state.empty_dirs.clear();
+        self.state.unique_files.clear();
+        self.results_view = ResultsView::Duplicates;
+        self.state.total_size_savings = 0;
+    }
+    
+    fn run_scan_job(&mut self, mut job: ScanJob, ctx: &egui::Context) {
+        self.state.status_message.clear();
+        self.begin_new_results();
+        self.scan_label = Some(job.label());
+        // Remote roots are not folders the directory field can take
+        self.scan_dirs = if job.whole_drive { Vec::new() } else { job.roots().into_iter().filter(|dir| Path::new(dir).is_absolute()).collect() };
+        
+        let roots = if job.whole_drive {
+            let base = if job.dir.is_empty() {
+                std::env::current_dir().unwrap_or_default()
+            } else {
+                std::path::PathBuf::from(&job.dir)
+            };
+            let root = volume_root(&base).display().to_string();
+            self.state.status_message = format!("Scanning entire volume {}", root);
+            vec![root]
+        } else {
+            job.roots()
+        };
+        let progress = self.scan_progress.clone();
+        let ctx_clone = ctx.clone();
+        let repaint_interval = self.repaint_interval.clone();
+        job.config.attach_checkpoint();
+        self.last_scan = Some(ScanRequest::Job(job.clone()));
+        let mut config = job.config;
+        config.excluded_dirs = self.managed_dirs();
+        config.attach_mount_table();
+        config.hash_cache = Some(self.hash_cache.clone());
+        config.control = Some(self.new_scan_control(config.time_limit()));
+        config.source = self.file_source();
+        config.known_files = self.known_files();
+        log::info!("Scan started: {} (minimum {} bytes, hidden files {})", roots.join(", "), config.min_file_size, config.include_hidden);
+        if config.known_files.is_none() && self.state.known_sets.enabled().next().is_some() {
+            self.state.status_message = "⚠ Known-files lists are still loading; this scan does not use them".to_string();
+        }
+        
+        let (tx, rx) = channel();
+        self.result_receiver = Some(rx);
+        let near_tx = if config.near_duplicate_sources {
+            let (near_tx, near_rx) = channel();
+            self.near_receiver = Some(near_rx);
+            Some(near_tx)
+        } else {
+            self.near_receiver = None;
+            None
+        };
+        let chunk_tx = if config.chunk_similarity {
+            let (chunk_tx, chunk_rx) = channel();
+            self.chunk_receiver = Some(chunk_rx);
+            Some(chunk_tx)
+        } else {
+            self.chunk_receiver = None;
+            None
+        };
+        
+        thread::spawn(move || {
+            let result = catch_panic(|| scan_roots(&roots, progress_reporter(progress.clone(), ctx_clone.clone(), repaint_interval), config.clone()));
+            
+            // A panic while reporting progress leaves the lock poisoned
+            progress.clear_poison();
+            *progress.lock().unwrap() = None;
+            // Exact results are shown first; near-duplicate and chunk comparisons follow
+            let exact = match (near_tx.is_some() || chunk_tx.is_some(), &result) {
+                (true, Ok(outcome)) => Some(outcome.groups.clone()),
+                _ => None,
+            };
+            let _ = tx.send(result);
+            ctx_clone.request_repaint();
+            
+            let Some(exact) = exact else {
+                return;
+            };
+            if let Some(near_tx) = near_tx {
+                let _ = near_tx.send(catch_panic(|| find_near_duplicate_sources(&roots, &exact, &config)));
+                ctx_clone.request_repaint();
+            }
+            if let Some(chunk_tx) = chunk_tx {
+                let _ = chunk_tx.send(catch_panic(|| find_chunk_similar_files(&roots, &exact, &config)));
+                ctx_clone.request_repaint();
+            }
+        });
+    }
+    
+    /// Scan the files named in a list file instead of walking a directory
+    fn pick_file_list_to_scan(&mut self, ctx: &egui::Context) {
+        if let Some(list) = rfd::FileDialog::new().pick_file() {
+            self.start_list_scan(&list, ctx);
+        }
+    }
+    
+    fn start_list_scan(&mut self, list: &Path, ctx: &egui::Context) {
+        if self.state.scanning {
+            return;
+        }
+        let bytes = match fs::read(list) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.state.status_message = format!("Failed to read file list: {}", e);
+                return;
+            }
+        };
+        let base = list.parent().map(Path::to_path_buf).unwrap_or_default();
+        let paths = parse_file_list(&bytes, &base);
+        if paths.is_empty() {
+            self.state.status_message = format!("{} contains no paths", list.display());
+            return;
+        }
+        
+        let count = paths.len();
+        self.scan_paths(paths, format!("List {}", list.display()), self.state.config.clone(), ctx);
+        self.state.status_message = format!("Scanning {} listed path(s) from {}", count, list.display());
+    }
+    
+    /// Find duplicates among just the given files, e.g. a selection made in a
+    /// file manager; folders among them are not walked
+    fn scan_selected_files(&mut self, paths: Vec<PathBuf>, ctx: &egui::Context) {
+        if self.state.scanning {
+            self.state.status_message = "⚠ A scan is running; select the files again when it has finished".to_string();
+            return;
+        }
+        let (files, folders): (Vec<PathBuf>, Vec<PathBuf>) = paths.into_iter().partition(|path| !path.is_dir());
+        if files.is_empty() {
+            self.state.status_message = "⚠ No files among the selected paths".to_string();
+            return;
+        }
+        // The files were picked one by one, so hidden ones are not left out
+        let config = ScanConfig { include_hidden: true, ..self.state.config.clone() };
+        let label = format!("{} selected file(s)", files.len());
+        self.scan_paths(files, label.clone(), config, ctx);
+        self.state.status_message = format!("Scanning {}", label);
+        if !folders.is_empty() {
+            self.state.status_message.push_str(&format!(" ({} folder(s) skipped)", folders.len()));
+        }
+    }
+    
+    /// Paths dropped on the window or named on the command line: folders
+    /// become scan roots, files are scanned on their own as a selection
+    fn open_paths(&mut self, paths: Vec<PathBuf>, ctx: &egui::Context) {
+        if paths.iter().all(|path| path.is_dir()) {
+            for path in paths {
+                self.add_scan_root(path);
+            }
+        } else {
+            self.scan_selected_files(paths, ctx);
+        }
+    }
+    
+    /// Hash exactly `paths`, without walking any directory
+    fn scan_paths(&mut self, paths: Vec<PathBuf>, label: String, mut config: ScanConfig, ctx: &egui::Context) {
+        self.begin_new_results();
+        log::info!("Scan of {} selected path(s) started: {}", paths.len(), label);
+        config.attach_checkpoint();
+        self.last_scan = Some(ScanRequest::Paths(paths.clone(), label.clone(), config.clone()));
+        self.scan_label = Some(label);
+        self.scan_dirs.clear();
+        
+        let progress = self.scan_progress.clone();
+        let ctx_clone = ctx.clone();
+        let repaint_interval = self.repaint_interval.clone();
+        config.excluded_dirs = self.managed_dirs();
+        config.attach_mount_table();
+        config.hash_cache = Some(self.hash_cache.clone());
+        config.control = Some(self.new_scan_control(config.time_limit()));
+        config.source = self.file_source();
+        config.known_files = self.known_files();
+        
+        let (tx, rx) = channel();
+        self.result_receiver = Some(rx);
+        self.near_receiver = None;
+        self.chunk_receiver = None;
+        
+        thread::spawn(move || {
+            let result = catch_panic(|| scan_file_list(&paths, progress_reporter(progress.clone(), ctx_clone.clone(), repaint_interval), config));
+            
+            progress.clear_poison();
+            *progress.lock().unwrap() = None;
+            let _ = tx.send(result);
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    fn show_selection_window(&mut self, ctx: &egui::Context) {
+        if !self.pasted_selection.open {
+            return;
+        }
+        let mut open = true;
+        let mut scan = false;
+        egui::Window::new("📋 Scan Selected Files")
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.label("Paste the paths of the files to compare, one per line. Quoted paths and file:// URIs, as file managers copy them, are accepted; folders are skipped.");
+                let edit = ui.add(egui::TextEdit::multiline(&mut self.pasted_selection.text).desired_rows(10).desired_width(f32::INFINITY).hint_text("/home/me/Pictures/IMG_0001.jpg"));
+                accessible_label(&edit, egui::WidgetType::TextEdit, "Paths of the files to compare, one per line");
+                ui.horizontal(|ui| {
+                    let ready = !self.pasted_selection.text.trim().is_empty() && !self.state.scanning;
+                    if ui.add_enabled(ready, egui::Button::new("🔍 Find Duplicates")).clicked() {
+                        scan = true;
+                    }
+                    if ui.button("Clear").clicked() {
+                        self.pasted_selection.text.clear();
+                    }
+                });
+            });
+        if scan {
+            let base = std::env::current_dir().unwrap_or_default();
+            let paths = parse_pasted_paths(&self.pasted_selection.text, &base);
+            self.scan_selected_files(paths, ctx);
+            if self.state.scanning {
+                self.pasted_selection.open = false;
+            }
+        }
+        if !open {
+            self.pasted_selection.open = false;
+        }
+    }
+    
+    fn take_results(&mut self) -> StoredResults {
+        StoredResults {
+            duplicate_groups: std::mem::take(&mut self.state.duplicate_groups),
+            near_groups: std::mem::take(&mut self.state.near_groups),
+            chunk_pairs: std::mem::take(&mut self.state.chunk_pairs),
+            scan_roots: std::mem::take(&mut self.state.scan_roots),
+            empty_dirs: std::mem::take(&mut self.state.empty_dirs),
+            trashed: std::mem::take(&mut self.state.trashed),
+            symlinks: std::mem::take(&mut self.state.symlinks),
+            unique_files: std::mem::take(&mut self.state.unique_files),
+        }
+    }
+    
+    fn show_results(&mut self, results: StoredResults) {
+        self.state.duplicate_groups = results.duplicate_groups;
+        self.state.near_groups = results.near_groups;
+        self.state.chunk_pairs = results.chunk_pairs;
+        self.set_scan_roots(results.scan_roots);
+        self.state.empty_dirs = results.empty_dirs;
+        self.state.trashed = results.trashed;
+        self.state.symlinks = results.symlinks;
+        self.state.unique_files = results.unique_files;
+    }
+    
+    /// Write the shown results, with any edits made since, back to their history entry
+    fn save_active_results(&mut self) {
+        let Some(id) = self.state.active_scan else {
+            return;
+        };
+        let results = self.take_results();
+        if let Err(e) = self.history.update(id, &results, self.state.total_size_savings) {
+            self.state.status_message = format!("⚠ Could not update scan history: {}", e);
+        }
+        self.show_results(results);
+    }
+    
+    /// Reopen the results of a past scan from the history
+    fn open_history_entry(&mut self, id: u64) {
+        if self.state.active_scan == Some(id) {
+            return;
+        }
+        let Some(record) = self.history.get(id).cloned() else {
+            return;
+        };
+        let results = match &record.error {
+            Some(_) => StoredResults::default(),
+            None => match self.history.load_results(id) {
+                Ok(results) => results,
+                Err(e) => {
+                    self.state.status_message = format!("⚠ Could not open stored results of {}: {}", record.label, e);
+                    return;
+                }
+            },
+        };
+        self.save_active_results();
+        self.show_results(results);
+        self.state.active_scan = Some(id);
+        self.partial_scan = None;
+        self.renaming = None;
+        self.basket.paths.clear();
+        self.sort_groups();
+        // Files may have changed since the scan; same check as on import
+        let validation = self.validate_results();
+        self.state.status_message = match &record.error {
+            Some(e) => format!("⚠ Scan of {} failed: {}", record.label, e),
+            None => format!("Showing results of {}. {}", record.summary(), validation),
+        };
+    }
+    
+    /// Store the scan that just finished in the history and make it the shown entry
+    fn record_scan(&mut self, error: Option<String>) {
+        let Some(label) = self.scan_label.take() else {
+            return;
+        };
+        let roots = std::mem::take(&mut self.scan_dirs);
+        let stored = match error {
+            Some(e) => self.history.add_failed(label, roots, e),
+            None => {
+                let results = self.take_results();
+                let stored = self.history.add(label, roots, &results, self.state.total_size_savings);
+                self.show_results(results);
+                stored
+            }
+        };
+        match stored {
+            Ok(id) => {
+                self.state.active_scan = Some(id);
+                self.prune_history();
+            }
+            Err(e) => self.state.status_message = format!("{} ⚠ Not saved to scan history: {}", self.state.status_message, e),
+        }
+    }
+    
+    /// Apply the history age and size limits; the shown result set is kept
+    fn prune_history(&mut self) {
+        match self.history.prune(&self.state.history_limits, self.state.active_scan) {
+            Ok(0) => {}
+            Ok(removed) => self.state.status_message = format!("{} Pruned {} old scan(s) from history.", self.state.status_message, removed),
+            Err(e) => self.state.status_message = format!("⚠ Could not prune scan history: {}", e),
+        }
+    }
+    
+    /// Directories the app writes to itself, which scans always skip: the data
+    /// folder with its history, logs, hash cache, journal and settings, and the
+    /// consolidation store
+    fn managed_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = settings::data_dir().into_iter().collect();
+        let store = self.consolidation.store.trim();
+        if !store.is_empty() {
+            dirs.push(PathBuf::from(store));
+        }
+        dirs
+    }
+    
+    fn start_rescan(&mut self, ctx: &egui::Context) {
+        if self.state.scanning || self.state.duplicate_groups.is_empty() {
+            return;
+        }
+        
+        let mut dirs: Vec<std::path::PathBuf> = self.state.duplicate_groups
+            .iter()
+            .flat_map(|group| group.files.iter().map(|f| f.path.dir_id()))
+            .collect::<std::collections::HashSet<DirId>>()
+            .into_iter()
+            .map(|id| paths::dir_path(id).to_path_buf())
+            .collect();
+        dirs.sort();
+        
+        self.state.scanning = true;
+        self.rescanning = true;
+        self.renaming = None;
+        self.state.status_message = format!("Rescanning {} affected director(ies)...", dirs.len());
+        log::info!("Rescan of {} director(ies) started", dirs.len());
+        
+        let progress = self.scan_progress.clone();
+        let ctx_clone = ctx.clone();
+        let repaint_interval = self.repaint_interval.clone();
+        let mut config = self.state.config.clone();
+        config.excluded_dirs = self.managed_dirs();
+        config.attach_mount_table();
+        config.hash_cache = Some(self.hash_cache.clone());
+        // Part of a rescan would drop the groups it did not reach, so it runs to the end
+        config.control = Some(self.new_scan_control(None));
+        config.source = self.file_source();
+        config.known_files = self.known_files();
+        
+        let (tx, rx) = channel();
+        self.result_receiver = Some(rx);
+        
+        thread::spawn(move || {
+            let result = catch_panic(|| rescan_directories(&dirs, progress_reporter(progress.clone(), ctx_clone.clone(), repaint_interval), config));
+            
+            progress.clear_poison();
+            *progress.lock().unwrap() = None;
+            let _ = tx.send(result);
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    /// Move copies sitting in a trash folder out of the groups into the
+    /// "already trashed" list, as long as a live copy remains
+    fn separate_trashed(&mut self) {
+        if self.state.config.trash_mode != TrashMode::Separate {
+            return;
+        }
+        for group in &mut self.state.duplicate_groups {
+            let Some(live_copy) = group.files.iter()
+                .map(|f| f.path.to_path_buf())
+                .find(|path| !is_in_trash(path))
+            else {
+                // Every copy is in the trash; the group itself is the useful view
+                continue;
+            };
+            let mut files = Vec::with_capacity(group.files.len());
+            let mut selected = Vec::with_capacity(group.files.len());
+            for (file, keep) in group.files.drain(..).zip(group.selected.drain(..)) {
+                let path = file.path.to_path_buf();
+                if is_in_trash(&path) {
+                    if !self.state.trashed.iter().any(|t| t.path == path) {
+                        self.state.trashed.push(TrashedCopy { path, size: file.size, live_copy: live_copy.clone() });
+                    }
+                } else {
+                    files.push(file);
+                    selected.push(keep);
+                }
+            }
+            group.files = files;
+            group.selected = selected;
+        }
+        self.state.duplicate_groups.retain(|group| group.files.len() > 1);
+        self.calculate_savings();
+    }
+    
+    /// Permanently delete trashed copies whose live copy is still present
+    fn empty_trashed(&mut self) {
+        let total: u64 = self.state.trashed.iter().map(|t| t.size).sum();
+        if self.state.previewing() {
+            self.state.status_message = format!(
+                "✓ Would permanently delete {} file(s) from the trash ({})",
+                self.state.trashed.len(),
+                format_size(total)
+            );
+            return;
+        }
+        
+        let mut purged = 0;
+        let mut purged_bytes = 0;
+        let mut errors = Vec::new();
+        let mut remaining = Vec::new();
+        let trashed = std::mem::take(&mut self.state.trashed);
+        for copy in trashed {
+            // Something else may have been renamed or written to the live path
+            // since, so it must still hold the trashed file's content
+            let live = match hash_file(&copy.live_copy, &self.state.config) {
+                Ok(digest) => digest,
+                Err(e) => {
+                    errors.push(if e.kind() == std::io::ErrorKind::NotFound {
+                        format!("Kept {}: live copy {} is gone", copy.path.display(), copy.live_copy.display())
+                    } else {
+                        format!("Kept {}: cannot read live copy {}: {}", copy.path.display(), copy.live_copy.display(), e)
+                    });
+                    remaining.push(copy);
+                    continue;
+                }
+            };
+            match hash_file(&copy.path, &self.state.config) {
+                Ok(digest) if digest == live => {}
+                Ok(_) => {
+                    errors.push(format!("Kept {}: live copy {} no longer matches it", copy.path.display(), copy.live_copy.display()));
+                    remaining.push(copy);
+                    continue;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    errors.push(format!("Kept {}: cannot read it: {}", copy.path.display(), e));
+                    remaining.push(copy);
+                    continue;
+                }
+            }
+            match trash::purge(&copy.path) {
+                Ok(()) => {
+                    purged += 1;
+                    purged_bytes += copy.size;
+                    self.state.action_log.push(ActionRecord::now("purge from trash", copy.path.display().to_string()));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    errors.push(format!("Failed to delete {}: {}", copy.path.display(), e));
+                    remaining.push(copy);
+                }
+            }
+        }
+        self.state.trashed = remaining;
+        self.note_deletions(purged, purged_bytes);
+        
+        let message = format!("Emptied {} file(s) from the trash ({})", purged, format_size(purged_bytes));
+        log_errors(&errors);
+        self.state.status_message = if errors.is_empty() {
+            format!("✓ {}", message)
+        } else {
+            format!("⚠ {} with {} error(s): {}", message, errors.len(), errors.iter().take(3).cloned().collect::<Vec<_>>().join("; "))
+        };
+    }
+    
+    /// Remove symlinks (never their targets) and drop them from the symlink report
+    fn remove_links(&mut self, links: Vec<PathBuf>, what: &str) {
+        if self.state.previewing() {
+            self.state.status_message = format!("✓ Would remove {} {}", links.len(), what);
+            return;
+        }
+        
+        let mut removed = HashSet::new();
+        let mut errors = Vec::new();
+        for link in links {
+            // Re-check so a path replaced by a real file since the scan is left alone
+            if !link.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+                errors.push(format!("Skipped {}: no longer a symlink", link.display()));
+                continue;
+            }
+            match fs::remove_file(&link) {
+                Ok(()) => {
+                    self.state.action_log.push(ActionRecord::now("remove symlink", link.display().to_string()));
+                    removed.insert(link);
+                }
+                Err(e) => errors.push(format!("Failed to remove {}: {}", link.display(), e)),
+            }
+        }
+        self.state.symlinks.forget(&removed);
+        log_errors(&errors);
+        
+        self.state.status_message = if errors.is_empty() {
+            format!("✓ Removed {} {}", removed.len(), what)
+        } else {
+            format!("⚠ Removed {} {} with {} error(s): {}", removed.len(), what, errors.len(), errors.iter().take(3).cloned().collect::<Vec<_>>().join("; "))
+        };
+    }
+    
+    fn remove_empty_dirs(&mut self) {
+        if self.state.previewing() {
+            self.state.status_message = format!("✓ Would remove {} empty folder(s)", self.state.empty_dirs.len());
+            return;
+        }
+        
+        let mut errors = Vec::new();
+        let mut removed = 0;
+        for dir in std::mem::take(&mut self.state.empty_dirs) {
+            match remove_empty_tree(&dir) {
+                Ok(()) => {
+                    self.state.action_log.push(ActionRecord::now("remove empty folder", dir.display().to_string()));
+                    removed += 1;
+                }
+                Err(e) => {
+                    errors.push(format!("Failed to remove {}: {}", dir.display(), e));
+                    // Still listed so it can be looked at; it may have gained content since the scan
+                    if dir.exists() {
+                        self.state.empty_dirs.push(dir);
+                    }
+                }
+            }
+        }
+        log_errors(&errors);
+        
+        self.state.status_message = if errors.is_empty() {
+            format!("✓ Removed {} empty folder(s)", removed)
+        } else {
+            format!("⚠ Removed {} empty folder(s) with {} error(s): {}", removed, errors.len(), errors.iter().take(3).cloned().collect::<Vec<_>>().join("; "))
+        };
+    }
+    
+    /// Remember the buffer size and thread count picked by auto-tuning. Every
+    /// scan with auto-tuning on measures again, so the saved settings keep the
+    /// user's own choices for when it is turned off.
+    fn apply_tuning(&mut self, tuning: Option<TuningResult>) {
+        if tuning.is_some() {
+            self.tuning = tuning;
+        }
+    }
+    
+    /// Merge a rescan into the current results, keeping group order and
+    /// the user's Keep choices for files that are still present.
+    fn apply_rescan_results(&mut self, groups: Vec<HashGroup>) {
+        let previous_selection: HashMap<std::path::PathBuf, bool> = self.state.duplicate_groups
+            .iter()
+            .flat_map(|group| group.files.iter().zip(&group.selected).map(|(f, &keep)| (f.path.to_path_buf(), keep)))
+            .collect();
+        let mut fresh: HashMap<String, HashGroup> = groups.into_iter().map(|g| (g.hash.clone(), g)).collect();
+        
+        let before = self.state.duplicate_groups.len();
+        let mut updated = Vec::new();
+        for group in self.state.duplicate_groups.drain(..) {
+            let matched = if group.hash.is_empty() {
+                // Older imports carry no hash; match on any shared member
+                let key = fresh.iter()
+                    .find(|(_, g)| g.files.iter().any(|f| group.files.iter().any(|old| old.path == f.path)))
+                    .map(|(hash, _)| hash.clone());
+                key.and_then(|hash| fresh.remove(&hash))
+            } else {
+                fresh.remove(&group.hash)
+            };
+            if let Some(hash_group) = matched {
+                updated.push(DuplicateGroup {
+                    visible_files: group.visible_files,
+                    tags: group.tags,
+                    notes: group.notes,
+                    ..DuplicateGroup::new(hash_group)
+                });
+            }
+        }
+        let kept = updated.len();
+        let added = fresh.len();
+        updated.extend(fresh.into_values().map(DuplicateGroup::new));
+        
+        for group in &mut updated {
+            for (file, keep) in group.files.iter().zip(group.selected.iter_mut()) {
+                if let Some(&previous) = previous_selection.get(&file.path.to_path_buf()) {
+                    *keep = previous;
+                }
+            }
+        }
+        
+        self.state.duplicate_groups = updated;
+        self.separate_trashed();
+        self.sort_groups();
+        self.calculate_savings();
+        self.state.status_message = format!(
+            "Rescan complete: {} group(s) still present, {} removed, {} new",
+            kept,
+            before - kept,
+            added
+        );
+    }
+    
+    fn start_group_verification(&mut self, group_idx: usize, ctx: &egui::Context) {
+        if self.verification.is_some() {
+            return;
+        }
+        let Some(group) = self.state.duplicate_groups.get(group_idx) else {
+            return;
+        };
+        
+        let paths: Vec<std::path::PathBuf> = group.files.iter().map(|f| f.path.to_path_buf()).collect();
+        let thread_paths = paths.clone();
+        let config = self.state.config.clone();
+        let ctx_clone = ctx.clone();
+        let (tx, rx) = channel();
+        self.verification = Some(GroupVerification { paths, receiver: rx });
+        self.state.status_message = format!("Re-verifying group {}...", group_idx + 1);
+        
+        thread::spawn(move || {
+            let _ = tx.send(rehash_files(&thread_paths, &config));
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    /// Drop members whose content no longer matches the group's hash
+    fn finish_group_verification(&mut self, paths: &[std::path::PathBuf], hashes: Vec<Option<String>>) {
+        let Some(group_idx) = self.state.duplicate_groups.iter()
+            .position(|g| g.files.iter().any(|f| paths.first() == Some(&f.path.to_path_buf())))
+        else {
+            self.state.status_message = "Group changed before verification finished".to_string();
+            return;
+        };
+        let group = &mut self.state.duplicate_groups[group_idx];
+        
+        // Results imported without a hash fall back to the most common one
+        let expected = if group.hash.is_empty() {
+            let mut counts: HashMap<&str, usize> = HashMap::new();
+            for hash in hashes.iter().flatten() {
+                *counts.entry(hash.as_str()).or_default() += 1;
+            }
+            counts.into_iter().max_by_key(|(_, count)| *count).map(|(hash, _)| hash.to_string()).unwrap_or_default()
+        } else {
+            group.hash.clone()
+        };
+        let current: HashMap<&std::path::PathBuf, Option<&String>> = paths.iter().zip(hashes.iter().map(Option::as_ref)).collect();
+        
+        let mut removed = Vec::new();
+        let mut files = Vec::with_capacity(group.files.len());
+        let mut selected = Vec::with_capacity(group.files.len());
+        for (file, keep) in group.files.drain(..).zip(group.selected.drain(..)) {
+            let path = file.path.to_path_buf();
+            match current.get(&path) {
+                Some(Some(hash)) if **hash != expected => removed.push(file.path.to_string()),
+                Some(None) => removed.push(file.path.to_string()),
+                _ => {
+                    files.push(file);
+                    selected.push(keep);
+                }
+            }
+        }
+        group.files = files;
+        group.selected = selected;
+        group.hash = expected;
+        
+        let mut message = if removed.is_empty() {
+            format!("✓ Group {} verified: all {} file(s) unchanged", group_idx + 1, group.files.len())
+        } else {
+            format!(
+                "⚠ Group {}: removed {} file(s) whose content changed or could not be read: {}",
+                group_idx + 1,
+                removed.len(),
+                removed.iter().take(3).cloned().collect::<Vec<_>>().join(", ")
+            )
+        };
+        if group.files.len() < 2 {
+            self.state.duplicate_groups.remove(group_idx);
+            self.renaming = None;
+            message.push_str(" (group no longer has duplicates and was removed)");
+        }
+        self.calculate_savings();
+        self.state.status_message = message;
+    }
+    
+    fn start_merge_plan(&mut self, ctx: &egui::Context) {
+        if self.merge.receiver.is_some() {
+            return;
+        }
+        let source = std::path::PathBuf::from(self.merge.source.trim());
+        let destination = std::path::PathBuf::from(self.merge.destination.trim());
+        if source.as_os_str().is_empty() || destination.as_os_str().is_empty() {
+            self.merge.message = "Select both a source and a destination folder.".to_string();
+            return;
+        }
+        
+        self.merge.plan = None;
+        self.merge.message.clear();
+        let config = self.state.config.clone();
+        let progress = self.merge.progress.clone();
+        let ctx_clone = ctx.clone();
+        let repaint_interval = self.repaint_interval.clone();
+        let (tx, rx) = channel();
+        self.merge.receiver = Some(rx);
+        
+        thread::spawn(move || {
+            let result = plan_merge(&source, &destination, &config, progress_reporter(progress.clone(), ctx_clone.clone(), repaint_interval));
+            *progress.lock().unwrap() = None;
+            let _ = tx.send(result);
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    /// Copy the planned files in the background
+    fn run_merge(&mut self, ctx: &egui::Context) {
+        if self.merge.copy_receiver.is_some() {
+            return;
+        }
+        let Some(plan) = self.merge.plan.take() else {
+            return;
+        };
+        if self.state.previewing() {
+            self.merge.message = format!(
+                "✓ Would copy {} file(s) ({}), skipping {} duplicate(s)",
+                plan.to_copy.len(),
+                format_size(plan.copy_size()),
+                plan.skipped.len()
+            );
+            self.merge.plan = Some(plan);
+            return;
+        }
+        
+        self.merge.message.clear();
+        let progress = self.merge.progress.clone();
+        let ctx_clone = ctx.clone();
+        let repaint_interval = self.repaint_interval.clone();
+        let stop = self.quit.stop.clone();
+        let (tx, rx) = channel();
+        self.merge.copy_receiver = Some(rx);
+        
+        thread::spawn(move || {
+            let report = execute_merge(&plan, &stop, progress_reporter(progress.clone(), ctx_clone.clone(), repaint_interval));
+            *progress.lock().unwrap() = None;
+            let _ = tx.send((report, plan.skipped.len()));
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    fn finish_merge(&mut self, report: MergeReport, skipped: usize) {
+        self.merge.copy_receiver = None;
+        log_errors(&report.errors);
+        self.merge.message = if report.errors.is_empty() {
+            format!(
+                "✓ Copied {} file(s) ({}), skipped {} duplicate(s)",
+                report.copied,
+                format_size(report.copied_bytes),
+                skipped
+            )
+        } else {
+            format!(
+                "⚠ Copied {} file(s) with {} error(s): {}",
+                report.copied,
+                report.errors.len(),
+                report.errors.iter().take(3).cloned().collect::<Vec<_>>().join("; ")
+            )
+        };
+    }
+    
+    fn show_merge_window(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.merge.receiver {
+            if let Ok(result) = rx.try_recv() {
+                self.merge.receiver = None;
+                match result {
+                    Ok(plan) => self.merge.plan = Some(plan),
+                    Err(e) => self.merge.message = format!("Merge analysis error: {}", e),
+                }
+            }
+        }
+        if let Some(Ok((report, skipped))) = self.merge.copy_receiver.as_ref().map(|rx| rx.try_recv()) {
+            self.finish_merge(report, skipped);
+        }
+        
+        let palette = Palette::new(self.state.high_contrast);
+        let mut open = self.merge.open;
+        let mut analyze = false;
+        let mut execute = false;
+        egui::Window::new("🔀 Merge Folders")
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                ui.label("Copy files from the source that do not already exist (by content) in the destination.");
+                ui.add_space(5.0);
+                for (label, value) in [("Source:", &mut self.merge.source), ("Destination:", &mut self.merge.destination)] {
+                    ui.horizontal(|ui| {
+                        ui.label(label);
+                        ui.add(egui::TextEdit::singleline(value).desired_width(380.0));
+                        if ui.button("📁 Browse").clicked() {
+                            if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                                *value = path.display().to_string();
+                            }
+                        }
+                    });
+                }
+                
+                ui.add_space(5.0);
+                let planning = self.merge.receiver.is_some();
+                let copying = self.merge.copy_receiver.is_some();
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!planning && !copying, egui::Button::new("🔍 Analyze")).clicked() {
+                        analyze = true;
+                    }
+                    if planning || copying {
+                        activity_spinner(ui, self.in_background);
+                        if let Some(progress) = self.merge.progress.lock().unwrap().as_ref() {
+                            if copying {
+                                ui.label(format!("Copying {} of {}: {}", format_count(progress.current as u64 + 1), format_count(progress.total as u64), progress.current_file));
+                            } else {
+                                ui.label(&progress.current_file);
+                            }
+                        }
+                    }
+                });
+                
+                if let Some(plan) = &self.merge.plan {
+                    ui.separator();
+                    ui.strong(format!("{} → {}", plan.source.display(), plan.destination.display()));
+                    ui.label(format!("📄 {} file(s) to copy ({})", plan.to_copy.len(), format_size(plan.copy_size())));
+                    ui.label(format!("⏭ {} duplicate(s) already in destination", plan.skipped.len()));
+                    if !plan.conflicts.is_empty() {
+                        ui.colored_label(
+                            palette.caution,
+                            format!("⚠ {} file(s) skipped: a different file already uses the same name", plan.conflicts.len()),
+                        );
+                    }
+                    
+                    egui::CollapsingHeader::new("Skipped duplicates")
+                        .id_salt("merge_skipped")
+                        .show(ui, |ui| {
+                            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                                for skip in &plan.skipped {
+                                    ui.label(format!("{} = {}", skip.source.display(), skip.existing.display()));
+                                }
+                            });
+                        });
+                    if !plan.conflicts.is_empty() {
+                        egui::CollapsingHeader::new("Name conflicts")
+                            .id_salt("merge_conflicts")
+                            .show(ui, |ui| {
+                                egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                                    for conflict in &plan.conflicts {
+                                        ui.label(format!("{} → {}", conflict.from.display(), conflict.to.display()));
+                                    }
+                                });
+                            });
+                    }
+                    
+                    let copy_text = if self.state.previewing() { "🔍 Preview Copy" } else { "📥 Copy Files" };
+                    if ui.add_enabled(!plan.to_copy.is_empty(), egui::Button::new(copy_text)).clicked() {
+                        execute = true;
+                    }
+                }
+                
+                if !self.merge.message.is_empty() {
+                    ui.add_space(5.0);
+                    ui.label(&self.merge.message);
+                }
+            });
+        self.merge.open = open;
+        
+        if analyze {
+            self.start_merge_plan(ctx);
+        }
+        if execute {
+            self.run_merge(ctx);
+        }
+    }
+    
+    fn find_case_dirs(&mut self, ctx: &egui::Context) {
+        if self.case_dirs.receiver.is_some() {
+            return;
+        }
+        let roots = self.result_roots();
+        if roots.is_empty() {
+            self.case_dirs.message = "Select a folder to scan first.".to_string();
+            return;
+        }
+        self.case_dirs.sets.clear();
+        self.case_dirs.keep.clear();
+        self.case_dirs.plan = None;
+        self.case_dirs.message.clear();
+        let mut config = self.state.config.clone();
+        config.attach_mount_table();
+        let ctx_clone = ctx.clone();
+        let (tx, rx) = channel();
+        self.case_dirs.receiver = Some(rx);
+        
+        thread::spawn(move || {
+            let _ = tx.send(find_case_variants(&roots, &config));
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    fn start_case_merge_plan(&mut self, set: usize, ctx: &egui::Context) {
+        if self.case_dirs.plan_receiver.is_some() {
+            return;
+        }
+        let Some(variants) = self.case_dirs.sets.get(set).cloned() else {
+            return;
+        };
+        let keep = self.case_dirs.keep[set];
+        self.case_dirs.plan = None;
+        self.case_dirs.message.clear();
+        let config = self.state.config.clone();
+        let progress = self.case_dirs.progress.clone();
+        let ctx_clone = ctx.clone();
+        let repaint_interval = self.repaint_interval.clone();
+        let (tx, rx) = channel();
+        self.case_dirs.plan_receiver = Some(rx);
+        
+        thread::spawn(move || {
+            let result = plan_case_merge(&variants, keep, &config, progress_reporter(progress.clone(), ctx_clone.clone(), repaint_interval));
+            *progress.lock().unwrap() = None;
+            let _ = tx.send((set, result));
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    fn run_case_merge(&mut self) {
+        let Some((set, plan)) = self.case_dirs.plan.take() else {
+            return;
+        };
+        if self.state.previewing() {
+            self.case_dirs.message = format!(
+                "✓ Would move {} file(s) into {} and remove {} duplicate(s); {} conflict(s) stay",
+                plan.to_move(),
+                plan.target.display(),
+                plan.duplicates(),
+                plan.conflicts()
+            );
+            self.case_dirs.plan = Some((set, plan));
+            return;
+        }
+        
+        let report = execute_case_merge(&plan, &self.state.config);
+        log_errors(&report.errors);
+        self.state.action_log.push(ActionRecord::now(
+            "merge case variants",
+            format!("{} file(s) moved and {} duplicate(s) removed into {}", report.moved, report.removed, plan.target.display()),
+        ));
+        for dir in &report.removed_dirs {
+            self.state.action_log.push(ActionRecord::now("remove empty folder", dir.display().to_string()));
+        }
+        let merged = plan.plans.iter().all(|merge| !merge.source.exists());
+        self.case_dirs.message = if report.errors.is_empty() {
+            format!(
+                "✓ Moved {} file(s) into {}, removed {} duplicate(s){}",
+                report.moved,
+                plan.target.display(),
+                report.removed,
+                if merged { "; the other folders are gone" } else { "; files with conflicting names stay in place" }
+            )
+        } else {
+            format!(
+                "⚠ Merged with {} error(s): {}",
+                report.errors.len(),
+                report.errors.iter().take(3).cloned().collect::<Vec<_>>().join("; ")
+            )
+        };
+        if merged && set < self.case_dirs.sets.len() {
+            self.case_dirs.sets.remove(set);
+            self.case_dirs.keep.remove(set);
+        }
+    }
+    
+    fn show_case_dirs_window(&mut self, ctx: &egui::Context) {
+        if let Some(sets) = self.case_dirs.receiver.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.case_dirs.receiver = None;
+            self.case_dirs.keep = sets.iter().map(CaseVariants::fullest).collect();
+            if sets.is_empty() {
+                self.case_dirs.message = "✓ No folders differ only by case".to_string();
+            }
+            self.case_dirs.sets = sets;
+        }
+        if let Some((set, result)) = self.case_dirs.plan_receiver.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.case_dirs.plan_receiver = None;
+            match result {
+                Ok(plan) => self.case_dirs.plan = Some((set, plan)),
+                Err(e) => self.case_dirs.message = format!("⚠ Merge analysis error: {}", e),
+            }
+        }
+        
+        let palette = Palette::new(self.state.high_contrast);
+        let mut open = self.case_dirs.open;
+        let mut find = false;
+        let mut analyze = None;
+        let mut execute = false;
+        egui::Window::new("🔠 Case-Variant Folders")
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                ui.label("Folders next to each other whose names differ only by case, e.g. \"Photos\" and \"photos\". Merging moves the files found only in one into the kept folder and removes the ones it already holds.");
+                ui.add_space(5.0);
+                let finding = self.case_dirs.receiver.is_some();
+                let planning = self.case_dirs.plan_receiver.is_some();
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!finding, egui::Button::new("🔍 Find in Scanned Folders")).clicked() {
+                        find = true;
+                    }
+                    if finding || planning {
+                        activity_spinner(ui, self.in_background);
+                    }
+                    if let Some(progress) = self.case_dirs.progress.lock().unwrap().as_ref() {
+                        ui.label(&progress.current_file);
+                    }
+                });
+                
+                let planned = self.case_dirs.plan.as_ref().map(|(set, _)| *set);
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for (idx, variants) in self.case_dirs.sets.iter().enumerate() {
+                        ui.separator();
+                        ui.strong(variants.parent.display().to_string());
+                        for (dir_idx, (dir, files)) in variants.dirs.iter().enumerate() {
+                            let name = dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                            if ui.radio_value(&mut self.case_dirs.keep[idx], dir_idx, format!("{} ({} file(s))", name, format_count(*files as u64)))
+                                .on_hover_text("Keep this folder and merge the others into it")
+                                .changed()
+                                && planned == Some(idx)
+                            {
+                                self.case_dirs.plan = None;
+                            }
+                        }
+                        if ui.add_enabled(!planning, egui::Button::new("🔀 Plan Merge")).clicked() {
+                            analyze = Some(idx);
+                        }
+                    }
+                });
+                
+                if let Some((_, plan)) = &self.case_dirs.plan {
+                    ui.separator();
+                    ui.strong(format!("Into {}", plan.target.display()));
+                    ui.label(format!("📄 {} file(s) to move", plan.to_move()));
+                    ui.label(format!("🗑 {} duplicate(s) already in the kept folder, to remove", plan.duplicates()));
+                    if plan.conflicts() > 0 {
+                        ui.colored_label(
+                            palette.caution,
+                            format!("⚠ {} file(s) stay: a different file already uses the same name", plan.conflicts()),
+                        );
+                    }
+                    let merge_text = if self.state.previewing() { "🔍 Preview Merge" } else { "🔀 Merge" };
+                    if ui.add_enabled(plan.to_move() + plan.duplicates() > 0, egui::Button::new(merge_text)).clicked() {
+                        execute = true;
+                    }
+                }
+                
+                if !self.case_dirs.message.is_empty() {
+                    ui.add_space(5.0);
+                    ui.label(&self.case_dirs.message);
+                }
+            });
+        self.case_dirs.open = open;
+        
+        if find {
+            self.find_case_dirs(ctx);
+        }
+        if let Some(set) = analyze {
+            self.start_case_merge_plan(set, ctx);
+        }
+        if execute {
+            self.run_case_merge();
+        }
+    }
+    
+    fn start_comparison(&mut self, ctx: &egui::Context) {
+        if self.compare.receiver.is_some() {
+            return;
+        }
+        let left = PathBuf::from(self.compare.left.trim());
+        let right = PathBuf::from(self.compare.right.trim());
+        if left.as_os_str().is_empty() || right.as_os_str().is_empty() {
+            self.compare.message = "Select both folders to compare.".to_string();
+            return;
+        }
+        
+        self.compare.result = None;
+        self.compare.message.clear();
+        let config = self.state.config.clone();
+        let progress = self.compare.progress.clone();
+        let ctx_clone = ctx.clone();
+        let repaint_interval = self.repaint_interval.clone();
+        let (tx, rx) = channel();
+        self.compare.receiver = Some(rx);
+        
+        thread::spawn(move || {
+            let result = compare_dirs(&left, &right, &config, progress_reporter(progress.clone(), ctx_clone.clone(), repaint_interval));
+            *progress.lock().unwrap() = None;
+            let _ = tx.send(result);
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    fn run_compare_action(&mut self, action: CompareAction) {
+        let Some(comparison) = &mut self.compare.result else {
+            return;
+        };
+        let count = comparison.targets(action).len();
+        let (verb, past) = match action {
+            CompareAction::CopyMissing(_) => ("copy", "Copied"),
+            CompareAction::Overwrite(_) => ("overwrite", "Overwrote"),
+            CompareAction::DeleteIdentical(_) => ("delete", "Deleted"),
+        };
+        if self.state.previewing() {
+            self.compare.message = format!("✓ Would {} {} file(s)", verb, count);
+            return;
+        }
+        
+        let (done, errors) = comparison.apply(action);
+        let roots = [comparison.left.clone(), comparison.right.clone()];
+        // Copies into empty paths lose nothing; overwrites and deletes go in the audit log
+        if verb != "copy" {
+            for path in &done {
+                self.state.action_log.push(ActionRecord::now(verb, path.display().to_string()));
+            }
+        }
+        let emptied = match action {
+            CompareAction::DeleteIdentical(_) => self.remove_emptied_dirs(&done, &roots),
+            _ => String::new(),
+        };
+        self.compare.message = if errors.is_empty() {
+            format!("✓ {} {} file(s){}", past, done.len(), emptied)
+        } else {
+            format!(
+                "⚠ {} {} file(s) with {} error(s){}: {}",
+                past,
+                done.len(),
+                errors.len(),
+                emptied,
+                errors.iter().take(3).cloned().collect::<Vec<_>>().join("; ")
+            )
+        };
+    }
+    
+    fn show_compare_window(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.compare.receiver {
+            if let Ok(result) = rx.try_recv() {
+                self.compare.receiver = None;
+                match result {
+                    Ok(comparison) => self.compare.result = Some(comparison),
+                    Err(e) => self.compare.message = format!("Comparison error: {}", e),
+                }
+            }
+        }
+        
+        let palette = Palette::new(self.state.high_contrast);
+        let mut open = self.compare.open;
+        let mut compare = false;
+        let mut action = None;
+        let mut copied = None;
+        egui::Window::new("⚖ Compare Folders")
+            .open(&mut open)
+            .default_width(650.0)
+            .show(ctx, |ui| {
+                ui.label("Compare every file in A with the file at the same path in B, byte for byte.");
+                ui.add_space(5.0);
+                for (label, value) in [("A:", &mut self.compare.left), ("B:", &mut self.compare.right)] {
+                    ui.horizontal(|ui| {
+                        ui.label(label);
+                        ui.add(egui::TextEdit::singleline(value).desired_width(420.0));
+                        if ui.button("📁 Browse").clicked() {
+                            if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                                *value = path.display().to_string();
+                            }
+                        }
+                    });
+                }
+                
+                ui.add_space(5.0);
+                let comparing = self.compare.receiver.is_some();
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!comparing, egui::Button::new("⚖ Compare")).clicked() {
+                        compare = true;
+                    }
+                    if comparing {
+                        activity_spinner(ui, self.in_background);
+                        if let Some(progress) = self.compare.progress.lock().unwrap().as_ref() {
+                            if progress.total > 0 {
+                                ui.label(format!("{} / {}", format_count(progress.current as u64), format_count(progress.total as u64)));
+                            }
+                            ui.label(truncate_path(&progress.current_file, 60));
+                        }
+                    }
+                });
+                
+                let Some(comparison) = &self.compare.result else {
+                    if !self.compare.message.is_empty() {
+                        ui.label(&self.compare.message);
+                    }
+                    return;
+                };
+                ui.separator();
+                ui.strong(format!("A: {}", comparison.left.display()));
+                ui.strong(format!("B: {}", comparison.right.display()));
+                if comparison.is_identical() {
+                    ui.colored_label(palette.success, format!("✓ Identical: all {} file(s) match", format_count(comparison.identical.len() as u64)));
+                }
+                
+                let delete_text = if self.state.previewing() { "🔍 Preview Delete" } else { "🗑 Delete" };
+                let categories = [
+                    ("compare_identical", "✅ Identical", &comparison.identical, vec![
+                        (format!("{} from A", delete_text), CompareAction::DeleteIdentical(Side::Left)),
+                        (format!("{} from B", delete_text), CompareAction::DeleteIdentical(Side::Right)),
+                    ]),
+                    ("compare_only_left", "⬅ Only in A", &comparison.only_left, vec![
+                        ("📥 Copy to B".to_string(), CompareAction::CopyMissing(Side::Left)),
+                    ]),
+                    ("compare_only_right", "➡ Only in B", &comparison.only_right, vec![
+                        ("📥 Copy to A".to_string(), CompareAction::CopyMissing(Side::Right)),
+                    ]),
+                    ("compare_different", "≠ Different content", &comparison.different, vec![
+                        ("➡ Overwrite B with A".to_string(), CompareAction::Overwrite(Side::Left)),
+                        ("⬅ Overwrite A with B".to_string(), CompareAction::Overwrite(Side::Right)),
+                    ]),
+                ];
+                for (id, title, files, actions) in categories {
+                    let size: u64 = files.iter().map(|f| f.size).sum();
+                    egui::CollapsingHeader::new(format!("{} ({}, {})", title, format_count(files.len() as u64), format_size(size)))
+                        .id_salt(id)
+                        .show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                for (label, category_action) in actions {
+                                    if ui.add_enabled(!files.is_empty(), egui::Button::new(label)).clicked() {
+                                        action = Some(category_action);
+                                    }
+                                }
+                                if ui.add_enabled(!files.is_empty(), egui::Button::new("📋 Copy List")).clicked() {
+                                    ui.ctx().copy_text(files.iter().map(|f| f.relative.display().to_string()).collect::<Vec<_>>().join("\n"));
+                                    copied = Some(files.len());
+                                }
+                            });
+                            let row_height = ui.text_style_height(&egui::TextStyle::Body);
+                            egui::ScrollArea::vertical().max_height(200.0).show_rows(ui, row_height, files.len(), |ui, rows| {
+                                for file in &files[rows] {
+                                    ui.label(format!("{} ({})", file.relative.display(), format_size(file.size)));
+                                }
+                            });
+                        });
+                }
+                if !comparison.errors.is_empty() {
+                    ui.colored_label(palette.caution, format!("⚠ {} file(s) could not be read", comparison.errors.len()))
+                        .on_hover_text(comparison.errors.iter().take(20).cloned().collect::<Vec<_>>().join("\n"));
+                }
+                
+                if !self.compare.message.is_empty() {
+                    ui.add_space(5.0);
+                    ui.label(&self.compare.message);
+                }
+            });
+        self.compare.open = open;
+        
+        if compare {
+            self.start_comparison(ctx);
+        }
+        if let Some(count) = copied {
+            self.compare.message = format!("Copied {} path(s) to clipboard", count);
+        }
+        if let Some(action) = action {
+            self.run_compare_action(action);
+        }
+    }
+    
+    fn start_corruption_check(&mut self, ctx: &egui::Context) {
+        if self.corruption.receiver.is_some() {
+            return;
+        }
+        let left = PathBuf::from(self.corruption.left.trim());
+        let right = PathBuf::from(self.corruption.right.trim());
+        if left.as_os_str().is_empty() || right.as_os_str().is_empty() {
+            self.corruption.message = "Select both folders to check.".to_string();
+            return;
+        }
+        
+        self.corruption.result = None;
+        self.corruption.message.clear();
+        let config = self.state.config.clone();
+        let progress = self.corruption.progress.clone();
+        let ctx_clone = ctx.clone();
+        let repaint_interval = self.repaint_interval.clone();
+        let (tx, rx) = channel();
+        self.corruption.receiver = Some(rx);
+        
+        thread::spawn(move || {
+            let result = catch_panic(|| find_corruption(&left, &right, &config, progress_reporter(progress.clone(), ctx_clone.clone(), repaint_interval)));
+            *progress.lock().unwrap() = None;
+            let _ = tx.send(result);
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    /// Save the suspect pairs as HTML or CSV, picked by the file extension
+    /// The uniqueness report: every file no other file under the roots has
+    /// the content of, largest first
+    fn show_unique_files(&mut self, ui: &mut egui::Ui) {
+        let files = &self.state.unique_files;
+        let total: u64 = files.iter().map(|file| file.size).sum();
+        let mut export = false;
+        let mut copied = None;
+        ui.horizontal(|ui| {
+            ui.heading(format!("☝ {} file(s) with no other copy", format_count(files.len() as u64)));
+            ui.label("|");
+            ui.label(format!("💾 {}", format_size(total)));
+            export = ui.button("📤 Export CSV…").on_hover_text("Save the list with sizes and modification times").clicked();
+        });
+        ui.weak("No other file under the scanned roots has the same content. Before retiring a disk, these are the files to copy somewhere else first.");
+        if self.state.scan_roots.len() > 1 {
+            for ((count, bytes), root) in per_root(files, &self.state.scan_roots).into_iter().zip(&self.state.scan_roots) {
+                if root.covered_by.is_none() {
+                    ui.label(format!("📁 {}: {} file(s), {}", root.path.display(), format_count(count as u64), format_size(bytes)));
+                }
+            }
+        }
+        ui.add_space(5.0);
+        let row_height = ui.spacing().interact_size.y;
+        egui::ScrollArea::vertical().id_salt("unique_scroll").max_height(500.0).show_rows(ui, row_height, files.len(), |ui, rows| {
+            for file in &files[rows] {
+                ui.horizontal(|ui| {
+                    let copy_path = ui.small_button("📋").on_hover_text("Copy path");
+                    accessible_label(&copy_path, egui::WidgetType::Button, format!("Copy path of {}", file.path.display()));
+                    if copy_path.clicked() {
+                        copied = Some(file.path.display().to_string());
+                    }
+                    ui.label(format_size(file.size));
+                    ui.label(file.path.display().to_string());
+                    if let Some(modified) = file.modified {
+                        ui.weak(format!("({})", format_file_date(modified)));
+                    }
+                });
+            }
+        });
+        if let Some(path) = copied {
+            ui.ctx().copy_text(path);
+            self.state.status_message = "Copied path to clipboard".to_string();
+        }
+        if export {
+            self.export_unique_files();
+        }
+    }
+    
+    fn export_unique_files(&mut self) {
+        let Some(path) = rfd::FileDialog::new().add_filter("CSV", &["csv"]).set_file_name("no_other_copy.csv").save_file() else {
+            return;
+        };
+        self.state.status_message = match fs::write(&path, unique_csv(&self.state.unique_files)) {
+            Ok(()) => format!("✓ Exported {} file(s) with no other copy to {}", format_count(self.state.unique_files.len() as u64), path.display()),
+            Err(e) => format!("⚠ Failed to export: {}", e),
+        };
+    }
+    
+    fn export_corruption_report(&mut self) {
+        let Some(report) = &self.corruption.result else {
+            return;
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("HTML", &["html"])
+            .add_filter("CSV", &["csv"])
+            .set_file_name("corruption_check.html")
+            .save_file()
+        else {
+            return;
+        };
+        let is_csv = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let text = if is_csv { corruption_csv(report) } else { corruption_html(report) };
+        self.corruption.message = match fs::write(&path, text) {
+            Ok(()) => format!("✓ Report saved to {}", path.display()),
+            Err(e) => format!("⚠ Failed to save the report: {}", e),
+        };
+    }
+    
+    fn show_corruption_window(&mut self, ctx: &egui::Context) {
+        if let Some(result) = self.corruption.receiver.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.corruption.receiver = None;
+            match result {
+                Ok(report) => {
+                    log::info!(
+                        "Corruption check of {} and {}: {} matched, {} suspect pair(s)",
+                        report.left.display(),
+                        report.right.display(),
+                        report.matched,
+                        report.suspects.len()
+                    );
+                    self.corruption.result = Some(report);
+                }
+                Err(ScanError::Internal(message)) => self.report_crash("Corruption check", message),
+                Err(e) => self.corruption.message = format!("Check error: {}", e),
+            }
+        }
+        
+        let palette = Palette::new(self.state.high_contrast);
+        let mut open = self.corruption.open;
+        let mut check = false;
+        let mut export = false;
+        egui::Window::new("🩺 Corruption Check")
+            .open(&mut open)
+            .default_width(700.0)
+            .show(ctx, |ui| {
+                ui.label("Pair files in A and B by name and size, wherever they are in each folder, and flag pairs whose content differs: in a backup, these may be corrupted copies.");
+                ui.add_space(5.0);
+                for (label, value) in [("A:", &mut self.corruption.left), ("B:", &mut self.corruption.right)] {
+                    ui.horizontal(|ui| {
+                        ui.label(label);
+                        ui.add(egui::TextEdit::singleline(value).desired_width(420.0));
+                        if ui.button("📁 Browse").clicked() {
+                            if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                                *value = path.display().to_string();
+                            }
+                        }
+                    });
+                }
+                
+                ui.add_space(5.0);
+                let checking = self.corruption.receiver.is_some();
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!checking, egui::Button::new("🩺 Check")).clicked() {
+                        check = true;
+                    }
+                    if checking {
+                        activity_spinner(ui, self.in_background);
+                        if let Some(progress) = self.corruption.progress.lock().unwrap().as_ref() {
+                            if progress.total > 0 {
+                                ui.label(format!("{} / {}", format_count(progress.current as u64), format_count(progress.total as u64)));
+                            }
+                            ui.label(truncate_path(&progress.current_file, 60));
+                        }
+                    }
+                });
+                
+                if let Some(report) = &self.corruption.result {
+                    ui.separator();
+                    ui.label(format!("{} file(s) have a counterpart with the same name, size and content.", format_count(report.matched as u64)));
+                    if report.suspects.is_empty() {
+                        ui.colored_label(palette.success, "✓ No pair with the same name and size differs in content");
+                    } else {
+                        let likely = report.suspects.iter().filter(|pair| pair.same_modified()).count();
+                        ui.horizontal(|ui| {
+                            ui.colored_label(palette.critical, format!(
+                                "⚠ {} pair(s) differ in content, {} with the same modification time",
+                                format_count(report.suspects.len() as u64),
+                                format_count(likely as u64)
+                            )).on_hover_text("An edit changes the modification time; content that differs while the time agrees points to corruption");
+                            if ui.button("💾 Export Report…").clicked() {
+                                export = true;
+                            }
+                        });
+                        egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                            for pair in &report.suspects {
+                                let (color, verdict) = if pair.same_modified() {
+                                    (palette.critical, "same modification time: likely corrupted")
+                                } else {
+                                    (palette.caution, "modified at different times: may be an edit")
+                                };
+                                ui.horizontal(|ui| {
+                                    ui.colored_label(color, "⚠");
+                                    ui.strong(pair.left.path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default());
+                                    ui.label(format!("({})", format_size(pair.size)));
+                                    ui.weak(verdict);
+                                });
+                                for (side, file) in [("A", &pair.left), ("B", &pair.right)] {
+                                    ui.label(format!(
+                                        "    {}: {}  {}",
+                                        side,
+                                        file.path.display(),
+                                        file.modified.map(format_timestamp).unwrap_or_default()
+                                    )).on_hover_text(format!("SHA-256: {}", file.hash));
+                                }
+                            }
+                        });
+                    }
+                    if !report.errors.is_empty() {
+                        ui.colored_label(palette.caution, format!("⚠ {} file(s) could not be read", report.errors.len()))
+                            .on_hover_text(report.errors.iter().take(20).cloned().collect::<Vec<_>>().join("\n"));
+                    }
+                }
+                
+                if !self.corruption.message.is_empty() {
+                    ui.add_space(5.0);
+                    ui.label(&self.corruption.message);
+                }
+            });
+        self.corruption.open = open;
+        
+        if check {
+            self.start_corruption_check(ctx);
+        }
+        if export {
+            self.export_corruption_report();
+        }
+    }
+    
+    fn start_manifest_match(&mut self, ctx: &egui::Context) {
+        if self.manifest_match.receiver.is_some() {
+            return;
+        }
+        let root = PathBuf::from(self.manifest_match.root.trim());
+        let manifest = PathBuf::from(self.manifest_match.manifest.trim());
+        if root.as_os_str().is_empty() || manifest.as_os_str().is_empty() {
+            self.manifest_match.message = "Select a local folder and a manifest first.".to_string();
+            return;
+        }
+        
+        self.manifest_match.result = None;
+        self.manifest_match.message.clear();
+        let config = self.state.config.clone();
+        let progress = self.manifest_match.progress.clone();
+        let ctx_clone = ctx.clone();
+        let repaint_interval = self.repaint_interval.clone();
+        let (tx, rx) = channel();
+        self.manifest_match.receiver = Some(rx);
+        
+        thread::spawn(move || {
+            let result = match_against_manifest(&root, &manifest, &config, progress_reporter(progress.clone(), ctx_clone.clone(), repaint_interval));
+            *progress.lock().unwrap() = None;
+            let _ = tx.send(result);
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    fn delete_manifest_matches(&mut self) {
+        let Some(result) = &mut self.manifest_match.result else {
+            return;
+        };
+        if self.state.previewing() {
+            self.manifest_match.message = format!(
+                "✓ Would delete {} local file(s), freeing {}",
+                result.matches.len(),
+                format_size(result.total_size())
+            );
+            return;
+        }
+        
+        let (deleted, errors) = result.delete_matches(&self.state.config);
+        let roots = [result.root.clone()];
+        for path in &deleted {
+            self.state.action_log.push(ActionRecord::now("delete", path.display().to_string()));
+        }
+        let emptied = self.remove_emptied_dirs(&deleted, &roots);
+        self.manifest_match.message = if errors.is_empty() {
+            format!("✓ Deleted {} local file(s){}", deleted.len(), emptied)
+        } else {
+            format!(
+                "⚠ Deleted {} local file(s) with {} error(s){}: {}",
+                deleted.len(),
+                errors.len(),
+                emptied,
+                errors.iter().take(3).cloned().collect::<Vec<_>>().join("; ")
+            )
+        };
+    }
+    
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        if let Some(action) = self.capturing_shortcut {
+            if let Some(binding) = capture_key(ctx) {
+                self.capturing_shortcut = None;
+                if binding.key != egui::Key::Escape.name() {
+                    if let Some(previous) = self.state.shortcuts.bind(action, binding) {
+                        self.state.status_message = format!("⚠ That key was bound to \"{}\", which is now unbound", previous.label());
+                    }
+                }
+            }
+            return;
+        }
+        // The palette takes the arrow keys and Enter while it is open
+        if self.command_palette.open {
+            if self.state.shortcuts.triggered(ctx).contains(&ShortcutAction::CommandPalette) {
+                self.command_palette.toggle();
+            }
+            return;
+        }
+        
+        for action in self.state.shortcuts.triggered(ctx) {
+            match action {
+                ShortcutAction::Scan => self.start_scan(ctx),
+                ShortcutAction::CancelScan => {
+                    if self.state.scanning {
+                        self.scan_control.cancel();
+                        self.running_queue = false;
+                    }
+                }
+                ShortcutAction::DeleteGroup => {
+                    if let Some(cursor) = self.valid_cursor() {
+                        self.delete_unchecked(cursor.group, ctx);
+                    }
+                }
+                ShortcutAction::ToggleKeep => {
+                    if let Some(cursor) = self.valid_cursor() {
+                        let keep = &mut self.state.duplicate_groups[cursor.group].selected[cursor.file];
+                        *keep = !*keep;
+                        self.calculate_savings();
+                    }
+                }
+                ShortcutAction::NextGroup => self.move_cursor_group(true),
+                ShortcutAction::PreviousGroup => self.move_cursor_group(false),
+                ShortcutAction::NextFile => self.move_cursor_file(true),
+                ShortcutAction::PreviousFile => self.move_cursor_file(false),
+                ShortcutAction::CommandPalette => self.command_palette.toggle(),
+            }
+        }
+    }
+    
+    /// The cursor, if it still points at a shown file
+    fn valid_cursor(&self) -> Option<ResultCursor> {
+        let cursor = self.cursor?;
+        let group = self.state.duplicate_groups.get(cursor.group)?;
+        (self.state.filter.matches(group) && cursor.file < group.files.len()).then_some(cursor)
+    }
+    
+    fn shown_group_indices(&self) -> Vec<usize> {
+        self.state.duplicate_groups.iter()
+            .enumerate()
+            .filter(|(_, group)| self.state.filter.matches(group))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+    
+    /// Put the cursor on the first (or, going back, last) file of the next shown group
+    fn move_cursor_group(&mut self, forward: bool) {
+        let shown = self.shown_group_indices();
+        let current = self.cursor.map(|cursor| cursor.group);
+        let target = if forward {
+            shown.iter().copied().find(|&idx| current.is_none_or(|current| idx > current))
+        } else {
+            shown.iter().rev().copied().find(|&idx| current.is_none_or(|current| idx < current))
+        };
+        if let Some(group) = target {
+            let rows = file_rows(&self.state.duplicate_groups[group], self.state.file_layout.group_by_directory);
+            let file = if forward { rows.first() } else { rows.last() };
+            self.cursor = Some(ResultCursor { group, file: file.copied().unwrap_or(0) });
+            self.scroll_to_cursor = true;
+        }
+    }
+    
+    /// Step through the shown files in display order, crossing into the next group at the end of one
+    fn move_cursor_file(&mut self, forward: bool) {
+        let Some(cursor) = self.valid_cursor() else {
+            self.move_cursor_group(forward);
+            return;
+        };
+        let rows = file_rows(&self.state.duplicate_groups[cursor.group], self.state.file_layout.group_by_directory);
+        let position = rows.iter().position(|&idx| idx == cursor.file);
+        let next = match (position, forward) {
+            (Some(pos), true) => rows.get(pos + 1),
+            (Some(pos), false) => pos.checked_sub(1).and_then(|pos| rows.get(pos)),
+            (None, _) => rows.first(),
+        };
+        match next {
+            Some(&file) => {
+                self.cursor = Some(ResultCursor { group: cursor.group, file });
+                self.scroll_to_cursor = true;
+            }
+            None => self.move_cursor_group(forward),
+        }
+    }
+    
+    fn set_safe_mode(&mut self, enabled: bool) {
+        self.state.safe_mode = enabled;
+        safemode::remember(enabled);
+        self.state.status_message = if enabled {
+            "🛡 Safe mode on: every action only previews".to_string()
+        } else {
+            "⚠ Safe mode off: deletes, moves and overwrites now change files unless preview mode is on".to_string()
+        };
+        self.state.action_log.push(ActionRecord::now(if enabled { "safe mode on" } else { "safe mode off" }, String::new()));
+    }
+    
+    fn set_ui_mode(&mut self, mode: UiMode) {
+        self.ui_mode = mode;
+        mode.remember();
+    }
+    
+    /// Commands the palette offers right now; the rest would do nothing
+    fn available_commands(&self) -> Vec<Command> {
+        let scanning = self.state.scanning;
+        let has_results = !self.state.duplicate_groups.is_empty();
+        Command::ALL
+            .into_iter()
+            .filter(|command| match command {
+                Command::StartScan => !scanning && self.current_job().is_some(),
+                Command::ScanFromFileList | Command::ScanSelectedFiles => !scanning,
+                Command::PauseScan => scanning,
+                Command::CancelScan => scanning && !self.scan_control.is_cancelled(),
+                Command::AddRoot | Command::AddSftpRoot | Command::AddS3Bucket | Command::AddGoogleDrive | Command::AddAndroidDevice => !self.state.scan_whole_drive,
+                Command::AddToQueue => self.current_job().is_some(),
+                Command::RunQueue => !self.running_queue && !self.state.scan_queue.is_empty(),
+                Command::KeepNewest
+                | Command::KeepOldest
+                | Command::KeepLikelyOriginals
+                | Command::KeepOnePerDirectory
+                | Command::UncheckJunkCopies
+                | Command::DeleteUnchecked
+                | Command::ConsolidateToStore
+                | Command::ExportResults
+                | Command::ExportChecksums
+                | Command::ExportForOtherTools
+                | Command::ComplianceReport
+                | Command::SimulationReport
+                | Command::ExportDeletionPaths => has_results,
+                Command::ValidateResults | Command::RescanAffected => has_results && !scanning,
+                Command::VerifyChecksums => self.manifest_check.receiver.is_none(),
+                Command::StartTour => !self.tour.is_active(),
+                Command::CheckForUpdates => !self.network.offline && self.update_check.receiver.is_none(),
+                Command::CleanUpDuplicates => self.state.duplicate_groups.iter().any(|g| self.state.filter.matches(g)),
+                // Safe mode is only turned off through its settings toggle
+                Command::TogglePreviewMode => !self.state.safe_mode,
+                Command::ImportResults
+                | Command::ImportExternalResults
+                | Command::MergeFolders
+                | Command::FindCaseVariants
+                | Command::CompareFolders
+                | Command::CheckCorruption
+                | Command::MatchAgainstManifest
+                | Command::ToggleHiddenFiles
+                | Command::ToggleHighContrast
+                | Command::ToggleUiMode
+                | Command::OpenInsights
+                | Command::OpenSensitivePatterns
+                | Command::OpenJunkCopyPatterns => true,
+            })
+            .collect()
+    }
+    
+    fn run_command(&mut self, command: Command, ctx: &egui::Context) {
+        match command {
+            Command::StartScan => self.start_scan(ctx),
+            Command::ScanFromFileList => self.pick_file_list_to_scan(ctx),
+            Command::ScanSelectedFiles => self.pasted_selection.open = true,
+            Command::PauseScan => self.scan_control.set_paused(!self.scan_control.is_paused()),
+            Command::CancelScan => {
+                self.scan_control.cancel();
+                self.running_queue = false;
+            }
+            Command::AddRoot => self.pick_scan_root(),
+            Command::AddSftpRoot => self.sftp_dialog.open = true,
+            Command::AddS3Bucket => self.s3_dialog.open = true,
+            Command::AddGoogleDrive => self.drive_dialog.open = true,
+            Command::AddAndroidDevice => self.open_device_window(ctx),
+            Command::AddToQueue => self.queue_scan(),
+            Command::RunQueue => self.running_queue = true,
+            Command::KeepNewest => self.bulk_select_newest(),
+            Command::KeepOldest => self.bulk_select_oldest(),
+            Command::KeepLikelyOriginals => self.bulk_select_originals(),
+            Command::KeepOnePerDirectory => self.bulk_select_one_per_directory(),
+            Command::UncheckJunkCopies => self.bulk_uncheck_junk_copies(),
+            Command::DeleteUnchecked => self.bulk_delete_unchecked(ctx),
+            Command::ConsolidateToStore => self.consolidation.open = true,
+            Command::ExportResults => self.save_results_file(),
+            Command::ImportResults => self.open_results_file(),
+            Command::ImportExternalResults => self.pick_external_results(),
+            Command::ExportChecksums => self.export_checksums(),
+            Command::ExportForOtherTools => self.export_for_other_tools(),
+            Command::ComplianceReport => self.export_compliance_report(),
+            Command::SimulationReport => self.export_simulation_report(),
+            Command::ExportDeletionPaths => self.export_deletion_paths(b'\n'),
+            Command::ValidateResults => self.state.status_message = self.validate_results(),
+            Command::RescanAffected => self.start_rescan(ctx),
+            Command::MergeFolders => self.merge.open = true,
+            Command::FindCaseVariants => self.case_dirs.open = true,
+            Command::CompareFolders => self.compare.open = true,
+            Command::CheckCorruption => self.corruption.open = true,
+            Command::MatchAgainstManifest => self.open_manifest_match(),
+            Command::VerifyChecksums => self.pick_manifest_to_verify(ctx),
+            Command::TogglePreviewMode => {
+                self.state.preview_mode = !self.state.preview_mode;
+                self.state.status_message = format!("Preview mode {}", if self.state.preview_mode { "on" } else { "off" });
+            }
+            Command::ToggleHiddenFiles => {
+                self.state.config.include_hidden = !self.state.config.include_hidden;
+                self.state.status_message = format!("Hidden files {}", if self.state.config.include_hidden { "included" } else { "skipped" });
+            }
+            Command::ToggleHighContrast => self.state.high_contrast = !self.state.high_contrast,
+            Command::ToggleUiMode => self.set_ui_mode(match self.ui_mode {
+                UiMode::Simple => UiMode::Advanced,
+                UiMode::Advanced => UiMode::Simple,
+            }),
+            Command::CleanUpDuplicates => self.open_cleanup_wizard(),
+            Command::OpenSensitivePatterns | Command::OpenJunkCopyPatterns => self.reveal_settings = Some(command),
+            Command::StartTour => self.tour.start(),
+            Command::CheckForUpdates => self.check_for_updates(ctx, true),
+            Command::OpenInsights => self.insights_open = true,
+        }
+    }
+    
+    fn open_manifest_match(&mut self) {
+        if self.manifest_match.root.is_empty() {
+            self.manifest_match.root = self.state.selected_dir.clone();
+        }
+        self.manifest_match.open = true;
+    }
+    
+    fn show_manifest_match_window(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.manifest_match.receiver {
+            if let Ok(result) = rx.try_recv() {
+                self.manifest_match.receiver = None;
+                match result {
+                    Ok(matches) => self.manifest_match.result = Some(matches),
+                    Err(e) => self.manifest_match.message = format!("Matching error: {}", e),
+                }
+            }
+        }
+        
+        let palette = Palette::new(self.state.high_contrast);
+        let mut open = self.manifest_match.open;
+        let mut start = false;
+        let mut delete = false;
+        let mut copied = None;
+        egui::Window::new("🗄 Match Against Manifest")
+            .open(&mut open)
+            .default_width(650.0)
+            .show(ctx, |ui| {
+                ui.label("Find local files whose content is listed in a sha256sum or b3sum manifest, e.g. one written on a NAS.");
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    ui.label("Local folder:");
+                    ui.add(egui::TextEdit::singleline(&mut self.manifest_match.root).desired_width(380.0));
+                    if ui.button("📁 Browse").clicked() {
+                        if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                            self.manifest_match.root = path.display().to_string();
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Manifest:");
+                    ui.add(egui::TextEdit::singleline(&mut self.manifest_match.manifest).desired_width(400.0));
+                    if ui.button("📄 Browse").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("Checksum manifests", &["sha256", "b3", "txt"])
+                            .add_filter("All files", &["*"])
+                            .pick_file()
+                        {
+                            self.manifest_match.manifest = path.display().to_string();
+                        }
+                    }
+                });
+                
+                ui.add_space(5.0);
+                let matching = self.manifest_match.receiver.is_some();
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!matching, egui::Button::new("🔍 Find Matches")).clicked() {
+                        start = true;
+                    }
+                    if matching {
+                        activity_spinner(ui, self.in_background);
+                        if let Some(progress) = self.manifest_match.progress.lock().unwrap().as_ref() {
+                            if progress.total > 0 {
+                                ui.label(format!("{} / {}", format_count(progress.current as u64), format_count(progress.total as u64)));
+                            }
+                            ui.label(truncate_path(&progress.current_file, 60));
+                        }
+                    }
+                });
+                
+                if let Some(result) = &self.manifest_match.result {
+                    ui.separator();
+                    ui.strong(format!("Local: {}", result.root.display()));
+                    ui.strong(format!("Manifest: {}", result.manifest.display()));
+                    ui.label(format!(
+                        "{} of {} local file(s) already listed in the manifest ({})",
+                        format_count(result.matches.len() as u64),
+                        format_count(result.hashed as u64),
+                        format_size(result.total_size())
+                    ));
+                    ui.horizontal(|ui| {
+                        let delete_text = if self.state.previewing() { "🔍 Preview Delete Local Copies" } else { "🗑 Delete Local Copies" };
+                        if ui.add_enabled(!result.matches.is_empty(), egui::Button::new(delete_text))
+                            .on_hover_text("Each file is re-hashed first and kept if its content changed")
+                            .clicked()
+                        {
+                            delete = true;
+                        }
+                        if ui.add_enabled(!result.matches.is_empty(), egui::Button::new("📋 Copy List")).clicked() {
+                            ui.ctx().copy_text(result.matches.iter().map(|m| m.path.display().to_string()).collect::<Vec<_>>().join("\n"));
+                            copied = Some(result.matches.len());
+                        }
+                    });
+                    let row_height = ui.text_style_height(&egui::TextStyle::Body);
+                    egui::ScrollArea::vertical().max_height(300.0).show_rows(ui, row_height, result.matches.len(), |ui, rows| {
+                        for found in &result.matches[rows] {
+                            ui.label(format!("{} ({})", found.path.display(), format_size(found.size)))
+                                .on_hover_text(format!("Listed as {}", found.listed_as.display()));
+                        }
+                    });
+                    if result.malformed > 0 {
+                        ui.colored_label(palette.caution, format!("⚠ {} manifest line(s) could not be read", result.malformed));
+                    }
+                    if !result.errors.is_empty() {
+                        ui.colored_label(palette.caution, format!("⚠ {} local file(s) could not be read", result.errors.len()))
+                            .on_hover_text(result.errors.iter().take(20).cloned().collect::<Vec<_>>().join("\n"));
+                    }
+                }
+                
+                if !self.manifest_match.message.is_empty() {
+                    ui.add_space(5.0);
+                    ui.label(&self.manifest_match.message);
+                }
+            });
+        self.manifest_match.open = open;
+        
+        if start {
+            self.start_manifest_match(ctx);
+        }
+        if let Some(count) = copied {
+            self.manifest_match.message = format!("Copied {} path(s) to clipboard", count);
+        }
+        if delete {
+            self.delete_manifest_matches();
+        }
+    }
+    
+    /// Window (and taskbar) title summarising scan progress or current results
+    fn title_summary(&self) -> String {
+        if let Some(progress) = self.scan_progress.lock().unwrap().as_ref() {
+            return match progress.phase {
+                ScanPhase::Discovery => format!("DupeFinder — Discovering ({} files)", progress.current),
+                ScanPhase::Hashing => format!(
+                    "DupeFinder — Hashing {}% ({}/{} candidates)",
+                    progress.current * 100 / progress.total.max(1),
+                    progress.current,
+                    progress.total
+                ),
+            };
+        }
+        if self.state.scanning {
+            "DupeFinder — Scanning…".to_string()
+        } else if self.state.duplicate_groups.is_empty() {
+            "DupeFinder".to_string()
+        } else {
+            format!(
+                "DupeFinder — {} group(s), {} reclaimable",
+                self.state.duplicate_groups.len(),
+                format_size(self.state.total_size_savings)
+            )
+        }
+    }
+    
+    /// Taskbar button progress for the scan the title describes
+    fn taskbar_progress(&self) -> TaskbarProgress {
+        let progress = self.scan_progress.lock().unwrap();
+        let Some(progress) = progress.as_ref() else {
+            return if self.state.scanning { TaskbarProgress::Indeterminate } else { TaskbarProgress::None };
+        };
+        let (done, total) = (progress.current as u64, progress.total as u64);
+        match progress.phase {
+            ScanPhase::Discovery => TaskbarProgress::Indeterminate,
+            ScanPhase::Hashing if self.scan_control.is_paused() => TaskbarProgress::Paused { done, total },
+            ScanPhase::Hashing => TaskbarProgress::Normal { done, total },
+        }
+    }
+    
+    fn plan_store_consolidation(&mut self) {
+        let store = std::path::PathBuf::from(self.consolidation.store.trim());
+        if store.as_os_str().is_empty() {
+            self.consolidation.message = "Select a store directory first.".to_string();
+            return;
+        }
+        let groups = self.state.duplicate_groups.iter()
+            .filter(|g| self.state.filter.matches(g))
+            .map(|g| (g.hash.as_str(), g.files.as_slice()));
+        match plan_consolidation(groups, &store, self.consolidation.owners) {
+            Ok(plan) => {
+                self.consolidation.report = plan.report();
+                self.consolidation.message = format!(
+                    "Dry run: {} group(s) would free {}",
+                    plan.steps.len(),
+                    format_size(plan.reclaimable_bytes())
+                );
+                let mixed = plan.mixed_owner_groups();
+                if mixed > 0 {
+                    self.consolidation.message.push_str(&match self.consolidation.owners {
+                        OwnerPolicy::ChownToStore => format!(" ⚠ {} group(s) of copies owned by different users take the store's owner", mixed),
+                        _ => format!(" ⚠ {} group(s) hold copies of different users; their linked paths take one owner (see OWNERS)", mixed),
+                    });
+                }
+                self.consolidation.plan = Some(plan);
+            }
+            Err(e) => {
+                self.consolidation.plan = None;
+                self.consolidation.message = format!("⚠ {}", e);
+            }
+        }
+    }
+    
+    fn run_store_consolidation(&mut self) {
+        let Some(plan) = self.consolidation.plan.take() else {
+            return;
+        };
+        let result = execute_consolidation(&plan, &self.state.config);
+        
+        // Consolidated groups no longer waste space
+        self.renaming = None;
+        self.state.duplicate_groups.retain(|g| !result.completed.contains(&g.hash));
+        self.calculate_savings();
+        
+        self.consolidation.message = if result.errors.is_empty() {
+            format!(
+                "✓ Stored {} new file(s), linked {} copies, freed {}",
+                result.moved,
+                result.linked,
+                format_size(result.reclaimed_bytes)
+            )
+        } else {
+            format!(
+                "⚠ Linked {} copies with {} error(s): {}",
+                result.linked,
+                result.errors.len(),
+                result.errors.iter().take(3).cloned().collect::<Vec<_>>().join("; ")
+            )
+        };
+        self.state.status_message = self.consolidation.message.clone();
+    }
+    
+    fn show_consolidation_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.consolidation.open;
+        let mut plan = false;
+        let mut execute = false;
+        egui::Window::new("🔗 Consolidate to Store")
+            .open(&mut open)
+            .default_width(700.0)
+            .show(ctx, |ui| {
+                ui.label("Keep one copy of each group in a canonical store directory and replace every other copy with a hard link to it.");
+                ui.horizontal(|ui| {
+                    ui.label("Store:");
+                    ui.add(egui::TextEdit::singleline(&mut self.consolidation.store).desired_width(450.0));
+                    if ui.button("📁 Browse").clicked() {
+                        if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                            self.consolidation.store = path.display().to_string();
+                        }
+                    }
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.label("Copies of different users:");
+                    for policy in OwnerPolicy::ALL {
+                        // A plan made for another policy must be made again before it runs
+                        if ui.radio_value(&mut self.consolidation.owners, policy, policy.label()).changed() {
+                            self.consolidation.plan = None;
+                        }
+                    }
+                }).response.on_hover_text("Hard links share one owner, so on multi-user systems linking one user's copy to another's hands it to that user. Skipping leaves such groups alone; the store's owner (e.g. a shared service account) needs the app to run with permission to change owners.");
+                
+                ui.horizontal(|ui| {
+                    if ui.button("📝 Plan (dry run)").clicked() {
+                        plan = true;
+                    }
+                    let can_execute = self.consolidation.plan.as_ref().is_some_and(|p| !p.steps.is_empty());
+                    if self.state.previewing() {
+                        ui.weak("Preview mode is on: only dry runs are possible");
+                    } else if ui.add_enabled(can_execute, egui::Button::new("🔗 Consolidate")).clicked() {
+                        execute = true;
+                    }
+                    if !self.consolidation.report.is_empty() && ui.button("💾 Save Report").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("Text", &["txt"])
+                            .set_file_name("consolidation_plan.txt")
+                            .save_file()
+                        {
+                            self.consolidation.message = match fs::write(&path, &self.consolidation.report) {
+                                Ok(()) => format!("Report saved to {}", path.display()),
+                                Err(e) => format!("Failed to save report: {}", e),
+                            };
+                        }
+                    }
+                });
+                
+                if !self.consolidation.message.is_empty() {
+                    ui.label(&self.consolidation.message);
+                }
+                
+                if !self.consolidation.report.is_empty() {
+                    ui.separator();
+                    egui::ScrollArea::vertical().max_height(350.0).show(ui, |ui| {
+                        ui.add(egui::TextEdit::multiline(&mut self.consolidation.report.as_str())
+                            .font(egui::TextStyle::Monospace)
+                            .desired_width(f32::INFINITY));
+                    });
+                }
+            });
+        self.consolidation.open = open;
+        
+        if plan {
+            self.plan_store_consolidation();
+        }
+        if execute {
+            self.run_store_consolidation();
+        }
+    }
+    
+    /// Replace the roots of the shown results; a root filter only applies to the results it was chosen for
+    fn set_scan_roots(&mut self, roots: Vec<ScanRoot>) {
+        self.state.filter.root_scope = None;
+        self.state.filter.roots = roots.clone();
+        self.state.scan_roots = roots;
+        self.state.filter.project_scope = None;
+        self.state.filter.projects = Projects::default();
+    }
+    
+    // Projects are looked for once per set of results, when first needed
+    fn ensure_projects(&mut self) {
+        if !self.state.filter.projects.is_detected() {
+            self.state.filter.projects = Projects::detect(self.state.duplicate_groups.iter().flat_map(|group| &group.files));
+        }
+    }
+    
+    /// Run the scan that ran out of time again. Files it hashed are in the hash
+    /// cache, so only the rest are read; folders are walked again.
+    fn continue_partial_scan(&mut self, ctx: &egui::Context) {
+        let Some(request) = self.partial_scan.take() else {
+            return;
+        };
+        match request {
+            ScanRequest::Job(job) => self.run_scan_job(job, ctx),
+            ScanRequest::Paths(paths, label, config) => self.scan_paths(paths, label, config, ctx),
+        }
+        self.state.status_message = "Continuing the partial scan; files hashed before it stopped are not read again".to_string();
+    }
+    
+    /// Fresh switches for a scan about to start; the previous scan's stay with it
+    /// Where scans read files: the local disk plus any connected SFTP servers
+    fn file_source(&self) -> Option<Arc<dyn FileSource>> {
+        (!self.remote_sources.is_empty()).then(|| Arc::new(self.remote_sources.clone()) as Arc<dyn FileSource>)
+    }
+    
+    fn new_scan_control(&mut self, time_limit: Option<Duration>) -> Arc<ScanControl> {
+        self.scan_control = Arc::new(ScanControl::with_time_limit(time_limit));
+        self.scan_control.clone()
+    }
+    
+    /// Folders that bound empty-folder removal after deleting from the results
+    fn result_roots(&self) -> Vec<PathBuf> {
+        if !self.state.scan_roots.is_empty() {
+            return self.state.scan_roots.iter().map(|root| root.path.clone()).collect();
+        }
+        std::iter::once(&self.state.selected_dir)
+            .chain(&self.state.extra_dirs)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .collect()
+    }
+    
+    /// With "Remove emptied folders" on, remove the folders that deleting or
+    /// moving `paths` left empty, up to their root, and log each one.
+    /// Returns a note for the status message, empty when nothing was removed.
+    fn remove_emptied_dirs<'a>(&mut self, paths: impl IntoIterator<Item = &'a PathBuf>, roots: &[PathBuf]) -> String {
+        if !self.state.remove_emptied_dirs || self.state.previewing() {
+            return String::new();
+        }
+        let mut count = 0;
+        for path in paths {
+            for dir in remove_emptied_parents(path, roots) {
+                self.state.action_log.push(ActionRecord::now("remove empty folder", dir.display().to_string()));
+                count += 1;
+            }
+        }
+        if count == 0 {
+            String::new()
+        } else {
+            format!(" — removed {} emptied folder(s)", format_count(count))
+        }
+    }
+    
+    fn calculate_savings(&mut self) {
+        self.state.total_size_savings = 0;
+        for group in &self.state.duplicate_groups {
+            let files_to_delete: Vec<_> = group.files.iter()
+                .zip(&group.selected)
+                .filter(|(_, &selected)| !selected)
+                .collect();
+            
+            for (file, _) in files_to_delete {
+                self.state.total_size_savings += file.disk_size();
+            }
+        }
+    }
+    
+    fn delete_unchecked(&mut self, group_idx: usize, ctx: &egui::Context) {
+        self.delete_from_group(group_idx, false, false, ctx);
+    }
+    
+    /// Try again to delete only the files of a group whose deletion failed
+    fn retry_failed(&mut self, group_idx: usize, ctx: &egui::Context) {
+        self.delete_from_group(group_idx, true, false, ctx);
+    }
+    
+    /// `confirmed` is set once the user has agreed to delete every copy of the group
+    fn delete_from_group(&mut self, group_idx: usize, only_failed: bool, confirmed: bool, ctx: &egui::Context) {
+        if group_idx >= self.state.duplicate_groups.len() {
+            return;
+        }
+        
+        // Group indices may shift, so drop any pending rename
+        self.renaming = None;
+        let previewing = self.state.previewing();
+        let group = &mut self.state.duplicate_groups[group_idx];
+        
+        if previewing {
+            // In preview mode, just count what would be deleted
+            let targets: Vec<&FileInfo> = group.files.iter()
+                .zip(&group.selected)
+                .filter(|(file, &keep)| !keep && (!only_failed || file.delete_error.is_some()))
+                .map(|(file, _)| file)
+                .collect();
+            let critical: Vec<String> = targets.iter().filter(|f| f.is_critical).map(|f| f.path.to_string()).collect();
+            let mut message = format!("✓ Would delete {} file(s) from group {}", targets.len(), group_idx + 1);
+            if !critical.is_empty() {
+                message.push_str(&format!(" ⚠️ {} CRITICAL file(s) detected! Files: {}", critical.len(), critical.join(", ")));
+            }
+            if deletes_every_copy(group, only_failed) {
+                message.push_str(if self.state.allow_deleting_last_copy {
+                    " ⚠️ This removes EVERY copy of the content!"
+                } else {
+                    " ⚠️ Refused: every copy is unchecked"
+                });
+            }
+            self.state.status_message = message;
+            return;
+        }
+        
+        if deletes_every_copy(group, only_failed) {
+            if !self.state.allow_deleting_last_copy {
+                self.state.status_message = format!(
+                    "⚠ Group {}: every copy is unchecked; keep at least one file or allow deleting the last copy in the settings",
+                    group_idx + 1
+                );
+                return;
+            }
+            if !confirmed {
+                self.last_copy_confirmation = Some(LastCopyConfirmation {
+                    target: DeleteTarget::Group(group_idx, group.files[0].path.to_path_buf()),
+                    only_failed,
+                    groups: 1,
+                    files: deletion_targets(group, only_failed),
+                });
+                return;
+            }
+        }
+        
+        if let Err(e) = verify_kept_copy(group, &group.selected, &self.state.config) {
+            self.state.status_message = format!("⚠ Group {}: {}", group_idx + 1, e);
+            return;
+        }
+        let survivors = kept_copies(group);
+        let outcome = delete_group_files(group, only_failed, &mut |path| fs::remove_file(path));
+        outcome.settle(group, &mut self.state.action_log);
+        let resolved = outcome.errors.is_empty() && group.selected.iter().all(|&keep| keep);
+        
+        if outcome.errors.is_empty() {
+            let mut message = format!("✓ Deleted {} file(s) from group {}", outcome.deleted.len(), group_idx + 1);
+            if !outcome.critical.is_empty() {
+                message.push_str(&format!(" ⚠️ {} CRITICAL file(s) detected!", outcome.critical.len()));
+            }
+            self.state.status_message = message;
+        } else {
+            self.state.status_message = format!(
+                "⚠ Deleted {} file(s) from group {}; {} failed and remain in the group: {}",
+                outcome.deleted.len(),
+                group_idx + 1,
+                outcome.errors.len(),
+                outcome.errors.iter().take(3).cloned().collect::<Vec<_>>().join("; ")
+            );
+        }
+        if resolved {
+            self.state.duplicate_groups.remove(group_idx);
+        }
+        let roots = self.result_roots();
+        let emptied = self.remove_emptied_dirs(outcome.deleted.iter().map(|file| &file.path), &roots);
+        self.state.status_message.push_str(&emptied);
+        self.calculate_savings();
+        self.start_post_delete_check(outcome.deleted, survivors, ctx);
+    }
+    
+    /// Re-check the outcome of a delete operation in the background
+    fn start_post_delete_check(&mut self, deleted: Vec<DeletedFile>, survivors: Vec<SurvivingFile>, ctx: &egui::Context) {
+        if deleted.is_empty() {
+            return;
+        }
+        self.note_deletions(deleted.len(), deleted.iter().map(|file| file.size).sum());
+        if self.state.offer_canonical_renames {
+            self.offer_kept_renames(&survivors);
+        }
+        let config = self.state.config.clone();
+        let sender = self.post_delete.sender.clone();
+        let ctx_clone = ctx.clone();
+        self.post_delete.running += 1;
+        thread::spawn(move || {
+            let _ = sender.send(verify_deletions(&deleted, &survivors, &config));
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    fn finish_post_delete_check(&mut self, report: PostDeleteReport) {
+        self.post_delete.running -= 1;
+        self.state.action_log.push(ActionRecord::now("verify", report.summary()));
+        for path in &report.still_present {
+            self.state.action_log.push(ActionRecord::now("verify: still present", path.display().to_string()));
+        }
+        for (path, problem) in &report.survivor_problems {
+            self.state.action_log.push(ActionRecord::now(&format!("verify: kept copy {}", problem), path.display().to_string()));
+        }
+        self.post_delete.report = Some(report);
+    }
+    
+    fn offer_kept_renames(&mut self, survivors: &[SurvivingFile]) {
+        let paths: Vec<PathBuf> = survivors.iter().map(|survivor| survivor.path.clone()).collect();
+        let plan = plan_canonical_renames(&paths);
+        if !plan.is_empty() {
+            self.kept_renames = KeptRenames { open: true, plan, message: String::new() };
+        }
+    }
+    
+    fn apply_kept_renames(&mut self) {
+        let selected: Vec<(PathBuf, PathBuf)> = self.kept_renames.plan.renames
+            .iter()
+            .filter(|rename| rename.selected)
+            .map(|rename| (rename.from.clone(), rename.to.clone()))
+            .collect();
+        if self.state.previewing() {
+            self.kept_renames.message = format!("✓ Would rename {} kept file(s)", selected.len());
+            return;
+        }
+        
+        let mut renamed = 0;
+        let mut errors = Vec::new();
+        for (from, to) in &selected {
+            match rename_no_clobber(from, to) {
+                Ok(()) => {
+                    self.file_renamed(from, to);
+                    renamed += 1;
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        self.kept_renames.plan.renames.retain(|rename| !rename.selected);
+        self.kept_renames.message = if errors.is_empty() {
+            format!("✓ Renamed {} kept file(s)", renamed)
+        } else {
+            format!(
+                "⚠ Renamed {} kept file(s), {} failed: {}",
+                renamed,
+                errors.len(),
+                errors.iter().take(3).cloned().collect::<Vec<_>>().join("; ")
+            )
+        };
+        self.state.status_message = self.kept_renames.message.clone();
+    }
+    
+    fn show_kept_renames_window(&mut self, ctx: &egui::Context, palette: &Palette) {
+        if !self.kept_renames.open {
+            return;
+        }
+        let mut open = true;
+        let mut apply = false;
+        let verifying = self.post_delete.running > 0;
+        egui::Window::new("✏ Rename Kept Copies")
+            .open(&mut open)
+            .default_width(650.0)
+            .show(ctx, |ui| {
+                ui.label("These kept files still carry a copy decoration in their name. Check the renames to apply; a name already taken is never overwritten.");
+                ui.separator();
+                egui::ScrollArea::vertical().max_height(350.0).show(ui, |ui| {
+                    for rename in &mut self.kept_renames.plan.renames {
+                        let to = rename.to.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+                        ui.checkbox(&mut rename.selected, format!("{}  →  {}", rename.from.display(), to));
+                    }
+                    for (path, reason) in &self.kept_renames.plan.skipped {
+                        ui.colored_label(palette.warning, format!("⚠ {}: {}", path.display(), reason));
+                    }
+                });
+                ui.separator();
+                let selected = self.kept_renames.plan.renames.iter().filter(|rename| rename.selected).count();
+                ui.horizontal(|ui| {
+                    let label = if self.state.previewing() { "Preview Renames" } else { "Apply Renames" };
+                    if ui.add_enabled(selected > 0 && !verifying, egui::Button::new(format!("✏ {} ({})", label, selected)))
+                        .on_disabled_hover_text("Waits until the deletion has been verified")
+                        .clicked()
+                    {
+                        apply = true;
+                    }
+                    if verifying {
+                        activity_spinner(ui, self.in_background);
+                        ui.label("Verifying deletion...");
+                    }
+                });
+                if !self.kept_renames.message.is_empty() {
+                    ui.label(&self.kept_renames.message);
+                }
+            });
+        if apply {
+            self.apply_kept_renames();
+        }
+        if !open {
+            self.kept_renames = KeptRenames::default();
+        }
+    }
+    
+    fn open_cleanup_wizard(&mut self) {
+        if self.cleanup.run.as_ref().is_some_and(|run| !run.finished) {
+            return;
+        }
+        self.cleanup.run = None;
+        self.cleanup.step = Some(WizardStep::Strategy);
+    }
+    
+    /// Start deleting the unchecked files of the shown groups, a few groups per frame
+    fn start_cleanup_run(&mut self) {
+        if self.interrupted_delete.is_some() {
+            self.show_interrupted_delete = true;
+            self.state.status_message = "⚠ An interrupted deletion must be resumed or rolled back first".to_string();
+            return;
+        }
+        self.renaming = None;
+        let shown: Vec<&DuplicateGroup> = self.state.duplicate_groups.iter()
+            .filter(|g| self.state.filter.matches(g) && deletion_targets(g, false) > 0)
+            .collect();
+        let totals = plan_totals(shown.iter().map(|g| (g.files.as_slice(), g.selected.as_slice())));
+        let pending: VecDeque<PathBuf> = shown.iter().map(|g| g.files[0].path.to_path_buf()).collect();
+        let summary = totals.iter()
+            .map(|(kind, totals)| format!("{}: {} file(s) in {} group(s), {}", kind.label(), totals.files, totals.groups, format_size(totals.bytes)))
+            .collect::<Vec<_>>()
+            .join("; ");
+        self.state.action_log.push(ActionRecord::now(
+            "cleanup plan",
+            format!("{} — {}", self.cleanup.strategy.label(), summary),
+        ));
+        self.cleanup.run = Some(CleanupRun { total: pending.len(), pending, ..CleanupRun::default() });
+        self.cleanup.step = Some(WizardStep::Progress);
+    }
+    
+    /// Clean up the next groups of a running cleanup, for up to a frame's worth of time
+    fn advance_cleanup_run(&mut self, ctx: &egui::Context) {
+        let Some(run) = self.cleanup.run.as_mut().filter(|run| !run.finished) else {
+            return;
+        };
+        let started = Instant::now();
+        while !run.stopped && started.elapsed() < CLEANUP_FRAME_BUDGET {
+            let Some(first) = run.pending.pop_front() else {
+                break;
+            };
+            run.done += 1;
+            let Some(idx) = self.state.duplicate_groups.iter().position(|g| g.files.first().is_some_and(|f| f.path.to_path_buf() == first)) else {
+                run.skipped += 1;
+                continue;
+            };
+            let group = &mut self.state.duplicate_groups[idx];
+            if deletes_every_copy(group, false) {
+                run.skipped += 1;
+                run.errors.push(format!("Skipped the group of {}: every copy is unchecked", first.display()));
+                continue;
+            }
+            if let Err(e) = verify_kept_copy(group, &group.selected, &self.state.config) {
+                run.errors.push(format!("Group of {}: {}", first.display(), e));
+                continue;
+            }
+            let kept = kept_copies(group);
+            self.state.action_log.push(ActionRecord::now(
+                "cleanup keep",
+                format!(
+                    "{} ({}): {}",
+                    PlanKind::of(&group.files).label(),
+                    self.cleanup.strategy.label(),
+                    kept.iter().map(|file| file.path.display().to_string()).collect::<Vec<_>>().join(", ")
+                ),
+            ));
+            // Each group is journaled on its own, so stopping or an interruption leaves at most one behind
+            let mut journal = match DeleteJournal::begin(vec![planned_group(group, false)]) {
+                Ok(journal) => journal,
+                Err(e) => {
+                    run.errors.push(format!("Could not write the deletion journal, stopped before the group of {}: {}", first.display(), e));
+                    run.stopped = true;
+                    self.interrupted_delete = InterruptedDeletion::pending();
+                    break;
+                }
+            };
+            let mut outcome = delete_group_files(group, false, &mut |path| journal.stage(path));
+            for (path, reason) in journal.finish() {
+                outcome.undo(group, &path, reason);
+            }
+            let gone = outcome.settle(group, &mut self.state.action_log);
+            if !outcome.deleted.is_empty() {
+                run.survivors.extend(kept);
+            }
+            if gone > 0 && outcome.errors.is_empty() && group.selected.iter().all(|&keep| keep) {
+                self.state.duplicate_groups.remove(idx);
+            }
+            run.deleted.extend(outcome.deleted);
+            run.errors.extend(outcome.errors);
+        }
+        if !run.stopped && !run.pending.is_empty() {
+            ctx.request_repaint();
+            return;
+        }
+        
+        run.finished = true;
+        // The run keeps its deletions for the summary page
+        let deleted = run.deleted.clone();
+        let survivors = std::mem::take(&mut run.survivors);
+        let freed = run.freed();
+        log_errors(&run.errors);
+        let summary = format!(
+            "{} file(s) deleted from {} of {} group(s), {} freed; {} group(s) skipped, {} error(s)",
+            format_count(deleted.len() as u64),
+            format_count((run.done - run.skipped) as u64),
+            format_count(run.total as u64),
+            format_size(freed),
+            format_count(run.skipped as u64),
+            format_count(run.errors.len() as u64)
+        );
+        let verb = if run.stopped { "cleanup stopped" } else { "cleanup finished" };
+        self.state.status_message = if run.errors.is_empty() {
+            format!("✓ Cleanup: {}", summary)
+        } else {
+            format!("⚠ Cleanup: {}: {}", summary, run.errors.iter().take(3).cloned().collect::<Vec<_>>().join("; "))
+        };
+        self.state.action_log.push(ActionRecord::now(verb, summary));
+        let roots = self.result_roots();
+        let emptied = self.remove_emptied_dirs(deleted.iter().map(|file| &file.path), &roots);
+        self.state.status_message.push_str(&emptied);
+        self.calculate_savings();
+        self.start_post_delete_check(deleted, survivors, ctx);
+    }
+    
+    fn show_cleanup_wizard(&mut self, ctx: &egui::Context, palette: &Palette) {
+        let Some(step) = self.cleanup.step else {
+            return;
+        };
+        let mut open = true;
+        let mut go_to = None;
+        let mut apply_strategy = false;
+        let mut delete = false;
+        let mut close = false;
+        let previewing = self.state.previewing();
+        let shown: Vec<&DuplicateGroup> = self.state.duplicate_groups.iter().filter(|g| self.state.filter.matches(g)).collect();
+        let totals = plan_totals(shown.iter().map(|g| (g.files.as_slice(), g.selected.as_slice())));
+        let files: usize = totals.iter().map(|(_, totals)| totals.files).sum();
+        let bytes: u64 = totals.iter().map(|(_, totals)| totals.bytes).sum();
+        let critical = shown.iter()
+            .flat_map(|g| g.files.iter().zip(&g.selected).filter(|(file, &keep)| !keep && file.is_critical))
+            .count();
+        egui::Window::new("🧹 Clean Up Duplicates")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                match step {
+                    WizardStep::Strategy => {
+                        ui.strong("Step 1 of 3: Which copy should be kept?");
+                        ui.label(format!(
+                            "The files of each of the {} group(s) have identical content. One file per group is kept and the other copies are deleted.",
+                            format_count(shown.len() as u64)
+                        ));
+                        ui.add_space(5.0);
+                        for strategy in CleanupStrategy::ALL {
+                            ui.radio_value(&mut self.cleanup.strategy, strategy, strategy.label());
+                            ui.indent(strategy.label(), |ui| {
+                                ui.weak(strategy.description());
+                            });
+                        }
+                    }
+                    WizardStep::Review => {
+                        ui.strong("Step 2 of 3: Review what is deleted");
+                        ui.label(format!(
+                            "{} file(s) in {} group(s) would be deleted, freeing {}. Checks in the results can still be changed before going on.",
+                            format_count(files as u64),
+                            format_count(shown.len() as u64),
+                            format_size(bytes)
+                        ));
+                        if critical > 0 {
+                            ui.colored_label(palette.critical, format!("⚠ {} of them are marked CRITICAL", format_count(critical as u64)));
+                        }
+                        egui::Grid::new("cleanup_totals").num_columns(5).striped(true).show(ui, |ui| {
+                            ui.strong("Kind");
+                            ui.strong("Kept copy");
+                            ui.strong("Groups");
+                            ui.strong("Files deleted");
+                            ui.strong("Projected savings");
+                            ui.end_row();
+                            for (kind, totals) in &totals {
+                                ui.label(kind.label());
+                                if self.cleanup.strategy == CleanupStrategy::Recommended {
+                                    ui.label(kind.rule());
+                                } else {
+                                    ui.weak("as chosen");
+                                }
+                                ui.label(format_count(totals.groups as u64));
+                                ui.label(format_count(totals.files as u64));
+                                ui.label(format_size(totals.bytes));
+                                ui.end_row();
+                            }
+                        });
+                        ui.separator();
+                        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                            for group in shown.iter().take(uimode::REVIEW_GROUPS) {
+                                for (file, &keep) in group.files.iter().zip(&group.selected) {
+                                    if keep {
+                                        ui.colored_label(palette.success, format!("✔ Keep {}", file.path));
+                                    } else {
+                                        ui.weak(format!("    ✖ Delete {}", file.path));
+                                    }
+                                }
+                                ui.add_space(4.0);
+                            }
+                            if shown.len() > uimode::REVIEW_GROUPS {
+                                ui.weak(format!("…and {} more group(s)", format_count((shown.len() - uimode::REVIEW_GROUPS) as u64)));
+                            }
+                        });
+                    }
+                    WizardStep::Confirm => {
+                        ui.strong("Step 3 of 3: Confirm");
+                        if previewing {
+                            ui.colored_label(palette.preview, format!(
+                                "Safe mode or preview mode is on, so nothing is deleted: the {} file(s) that would go are only counted.",
+                                format_count(files as u64)
+                            ));
+                        } else {
+                            ui.colored_label(palette.critical, format!(
+                                "{} file(s) will be deleted permanently, not moved to the trash, freeing {}. This cannot be undone.",
+                                format_count(files as u64),
+                                format_size(bytes)
+                            ));
+                            ui.label("Groups are cleaned up one at a time. Each kept copy is checked before its duplicates go, and the plan, every kept copy and every deletion are recorded in the action log.");
+                        }
+                    }
+                    WizardStep::Progress => {
+                        let Some(run) = &mut self.cleanup.run else {
+                            return;
+                        };
+                        let fraction = if run.total == 0 { 1.0 } else { run.done as f32 / run.total as f32 };
+                        ui.add(egui::ProgressBar::new(fraction).text(format!(
+                            "{} / {} group(s)",
+                            format_count(run.done as u64),
+                            format_count(run.total as u64)
+                        )));
+                        ui.label(format!(
+                            "{} file(s) deleted, {} freed",
+                            format_count(run.deleted.len() as u64),
+                            format_size(run.freed())
+                        ));
+                        if run.skipped > 0 {
+                            ui.label(format!("{} group(s) skipped", format_count(run.skipped as u64)));
+                        }
+                        for error in run.errors.iter().take(10) {
+                            ui.colored_label(palette.warning, format!("⚠ {}", error));
+                        }
+                        if run.errors.len() > 10 {
+                            ui.weak(format!("…and {} more; see the action log", run.errors.len() - 10));
+                        }
+                        if run.finished {
+                            ui.colored_label(palette.success, if run.stopped { "Stopped; the remaining groups were left alone." } else { "✓ Cleanup finished." });
+                        }
+                    }
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    match step {
+                        WizardStep::Strategy => {
+                            if ui.button("Next ▶").clicked() {
+                                apply_strategy = true;
+                                go_to = Some(WizardStep::Review);
+                            }
+                        }
+                        WizardStep::Review => {
+                            if ui.button("◀ Back").clicked() {
+                                go_to = Some(WizardStep::Strategy);
+                            }
+                            if ui.add_enabled(files > 0, egui::Button::new("Next ▶"))
+                                .on_disabled_hover_text("Every file is kept")
+                                .clicked()
+                            {
+                                go_to = Some(WizardStep::Confirm);
+                            }
+                        }
+                        WizardStep::Confirm => {
+                            if ui.button("◀ Back").clicked() {
+                                go_to = Some(WizardStep::Review);
+                            }
+                            let delete_text = if previewing {
+                                "🔍 Preview Delete".to_string()
+                            } else {
+                                format!("🗑 Delete {} File(s)", format_count(files as u64))
+                            };
+                            if ui.button(delete_text).clicked() {
+                                delete = true;
+                            }
+                        }
+                        WizardStep::Progress => {
+                            if let Some(run) = &mut self.cleanup.run {
+                                if run.finished {
+                                    close = ui.button("Close").clicked();
+                                } else if ui.add_enabled(!run.stopped, egui::Button::new("⏹ Stop")).on_hover_text("Stop after the current group").clicked() {
+                                    run.stopped = true;
+                                }
+                            }
+                            return;
+                        }
+                    }
+                    if ui.button("Cancel").clicked() {
+                        close = true;
+                    }
+                });
+            });
+        if !open {
+            match &mut self.cleanup.run {
+                // Closing the window while deleting stops after the current group
+                Some(run) if !run.finished => run.stopped = true,
+                _ => close = true,
+            }
+        }
+        if close {
+            self.cleanup.step = None;
+            self.cleanup.run = None;
+        }
+        if apply_strategy {
+            self.bulk_apply_selection_strategy(self.cleanup.strategy.selection());
+        }
+        if let Some(step) = go_to {
+            self.cleanup.step = Some(step);
+        }
+        if delete {
+            if previewing {
+                self.cleanup.step = None;
+                self.bulk_delete_unchecked(ctx);
+            } else {
+                self.start_cleanup_run();
+            }
+        }
+    }
+    
+    /// Work that closing the window would cut off, in words
+    fn running_operations(&self) -> Vec<String> {
+        let mut operations = Vec::new();
+        if self.state.scanning {
+            operations.push(if self.rescanning { "Rescan running" } else { "Scan running" }.to_string());
+        }
+        if self.near_receiver.is_some() || self.chunk_receiver.is_some() {
+            operations.push("Comparing files found by the scan".to_string());
+        }
+        if let Some(run) = self.cleanup.run.as_ref().filter(|run| !run.finished) {
+            operations.push(format!("Deleting duplicates ({} of {} groups done)", format_count(run.done as u64), format_count(run.total as u64)));
+        }
+        if self.s3_dialog.action_receiver.is_some() {
+            operations.push(format!("Changing S3 objects: {}", self.s3_dialog.action.label()));
+        }
+        if self.drive_dialog.action_receiver.is_some() {
+            operations.push("Moving files to the Drive trash".to_string());
+        }
+        if self.device_dialog.action_receiver.is_some() {
+            operations.push("Deleting files on an Android device".to_string());
+        }
+        if self.post_delete.running > 0 {
+            operations.push("Verifying deletions".to_string());
+        }
+        if self.manifest_check.receiver.is_some() {
+            operations.push("Verifying checksums".to_string());
+        }
+        if self.merge.copy_receiver.is_some() {
+            operations.push("Copying files into the merge destination".to_string());
+        }
+        if self.merge.receiver.is_some() || self.compare.receiver.is_some() || self.manifest_match.receiver.is_some() {
+            operations.push("Comparing folders".to_string());
+        }
+        operations
+    }
+    
+    /// Deletions, and copies into a merge destination, that must reach the end
+    /// of the file they are on before the window closes
+    fn deletions_running(&self) -> bool {
+        self.cleanup.run.as_ref().is_some_and(|run| !run.finished)
+            || self.s3_dialog.action_receiver.is_some()
+            || self.drive_dialog.action_receiver.is_some()
+            || self.device_dialog.action_receiver.is_some()
+            || self.merge.copy_receiver.is_some()
+    }
+    
+    /// Keep the system awake while a scan or deletion runs, and pause a scan
+    /// or make it low-impact while the computer runs on battery, as the
+    /// power settings ask
+    fn manage_power(&mut self, ctx: &egui::Context) {
+        let busy = self.state.scanning || self.deletions_running();
+        if !busy {
+            self.power.inhibit_failed = false;
+        }
+        let keep_awake = busy && self.power.settings.keep_awake && !self.power.inhibit_failed;
+        if keep_awake && self.power.inhibitor.is_none() {
+            match SleepInhibitor::acquire("Scanning or deleting duplicate files") {
+                Ok(inhibitor) => self.power.inhibitor = Some(inhibitor),
+                Err(e) => {
+                    log::warn!("Could not keep the system awake: {}", e);
+                    self.power.inhibit_failed = true;
+                }
+            }
+        } else if !keep_awake {
+            self.power.inhibitor = None;
+        }
+        
+        let settings = &self.power.settings;
+        if !self.state.scanning {
+            self.power.paused_for_battery = false;
+            self.power.on_battery = None;
+            self.power.low_impact_override = None;
+            self.power.last_battery_check = None;
+            return;
+        }
+        // Switches of a new scan start off; set them whenever they differ
+        let low_impact = self.power.wants_low_impact();
+        if self.scan_control.is_low_impact() != low_impact {
+            self.scan_control.set_low_impact(low_impact);
+            log::info!("Low-impact scanning {}", if low_impact { "on" } else { "off" });
+        }
+        if !settings.pause_on_battery && !settings.low_impact_on_battery {
+            return;
+        }
+        // A paused scan sends no progress, so look again without waiting for input
+        ctx.request_repaint_after(POWER_CHECK_INTERVAL);
+        if self.power.last_battery_check.is_some_and(|checked| checked.elapsed() < POWER_CHECK_INTERVAL) {
+            return;
+        }
+        self.power.last_battery_check = Some(Instant::now());
+        self.power.on_battery = on_battery();
+        if !self.power.settings.pause_on_battery {
+            self.power.paused_for_battery = false;
+            return;
+        }
+        match self.power.on_battery {
+            Some(true) if !self.power.paused_for_battery && !self.scan_control.is_paused() => {
+                self.scan_control.set_paused(true);
+                self.power.paused_for_battery = true;
+                self.state.status_message = "⏸ Scan paused: running on battery. It resumes on mains power, or press Resume.".to_string();
+                log::info!("Scan paused on battery power");
+            }
+            Some(false) if self.power.paused_for_battery => {
+                self.scan_control.set_paused(false);
+                self.power.paused_for_battery = false;
+                self.state.status_message = "✓ Back on mains power; scan resumed".to_string();
+                log::info!("Scan resumed on mains power");
+            }
+            _ => {}
+        }
+    }
+    
+    /// Hold a close request while work is running and ask first; once quitting
+    /// is confirmed, close as soon as the deletions have stopped between files
+    fn guard_close(&mut self, ctx: &egui::Context) {
+        if self.quit.close_allowed {
+            return;
+        }
+        let close_requested = ctx.input(|i| i.viewport().close_requested());
+        if self.quit.quitting {
+            if !self.deletions_running() {
+                self.quit.close_allowed = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                return;
+            }
+            if close_requested {
+                ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            }
+            ctx.request_repaint_after(Duration::from_millis(100));
+            return;
+        }
+        if close_requested && !self.running_operations().is_empty() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.quit.confirming = true;
+        }
+    }
+    
+    /// Stop the running work, leaving every file either fully handled or untouched
+    fn stop_for_quit(&mut self) {
+        self.quit.confirming = false;
+        self.quit.quitting = true;
+        self.quit.stop.store(true, Ordering::Relaxed);
+        self.scan_control.cancel();
+        self.running_queue = false;
+        if let Some(run) = self.cleanup.run.as_mut() {
+            run.stopped = true;
+        }
+        self.state.action_log.push(ActionRecord::now("quit", self.running_operations().join("; ")));
+    }
+    
+    fn show_quit_window(&mut self, ctx: &egui::Context, palette: &Palette) {
+        if !self.quit.confirming && !self.quit.quitting {
+            return;
+        }
+        let operations = self.running_operations();
+        if self.quit.confirming && operations.is_empty() {
+            // Everything finished while the question was open
+            self.quit.confirming = false;
+            return;
+        }
+        let mut quit = false;
+        let mut keep = false;
+        let title = if self.quit.quitting { "⏳ Quitting…" } else { "⚠ Quit While Work Is Running?" };
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                if self.quit.quitting {
+                    ui.horizontal(|ui| {
+                        activity_spinner(ui, self.in_background);
+                        ui.label("Waiting for the running deletions to finish the file they are on…");
+                    });
+                    return;
+                }
+                for operation in &operations {
+                    ui.colored_label(palette.caution, format!("• {}", operation));
+                }
+                ui.label("Quitting cancels scans and comparisons. Deletions finish the file they are on and stop, so no file is left half-handled; the files done so far are recorded in the action log.");
+                ui.horizontal(|ui| {
+                    let label = if self.state.scanning { "Cancel Scan and Quit" } else { "Stop and Quit" };
+                    if ui.button(label).clicked() {
+                        quit = true;
+                    }
+                    if ui.button("Keep Running").clicked() {
+                        keep = true;
+                    }
+                });
+            });
+        if quit {
+            self.stop_for_quit();
+        } else if keep {
+            self.quit.confirming = false;
+        }
+    }
+    
+    /// Ask GitHub for the latest release in the background. `manual` checks
+    /// report "up to date" and failures; the startup check stays quiet.
+    fn check_for_updates(&mut self, ctx: &egui::Context, manual: bool) {
+        if self.network.offline {
+            self.update_check.message = "⚠ Offline mode is on; updates are not checked".to_string();
+            return;
+        }
+        if self.update_check.receiver.is_some() {
+            return;
+        }
+        let ctx_clone = ctx.clone();
+        let (tx, rx) = channel();
+        self.update_check.receiver = Some(rx);
+        self.update_check.manual = manual;
+        self.update_check.message = if manual { "Checking for updates...".to_string() } else { String::new() };
+        thread::spawn(move || {
+            let _ = tx.send(latest_release());
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    fn finish_update_check(&mut self, result: Result<Release, String>) {
+        self.update_check.receiver = None;
+        let manual = self.update_check.manual;
+        match result {
+            Ok(release) => {
+                log::info!("Latest release is {}", release.tag_name);
+                self.update_check.message = if release.is_newer() {
+                    format!("✓ DupeFinder {} is available", release.version())
+                } else if manual {
+                    format!("✓ DupeFinder {} is up to date", env!("CARGO_PKG_VERSION"))
+                } else {
+                    String::new()
+                };
+                if manual && release.is_newer() {
+                    self.update_check.dismissed = false;
+                }
+                self.update_check.latest = Some(release);
+            }
+            Err(e) => {
+                log::warn!("Update check failed: {}", e);
+                if manual {
+                    self.update_check.message = format!("⚠ Update check failed: {}", e);
+                }
+            }
+        }
+    }
+    
+    fn show_update_banner(&mut self, ui: &mut egui::Ui, palette: &Palette) {
+        if self.update_check.dismissed {
+            return;
+        }
+        let Some(release) = self.update_check.newer() else {
+            return;
+        };
+        let version = release.version().to_string();
+        let page = release.page().to_string();
+        ui.horizontal(|ui| {
+            ui.colored_label(palette.success, format!("⬆ DupeFinder {} is available (this is {})", version, env!("CARGO_PKG_VERSION")));
+            if ui.small_button("📰 What's New").clicked() {
+                self.update_check.show_changelog = true;
+            }
+            if ui.small_button("⬇ Download").on_hover_text(&page).clicked() {
+                ui.ctx().open_url(egui::OpenUrl::new_tab(&page));
+            }
+            if ui.small_button("✖").on_hover_text("Hide until the next start").clicked() {
+                self.update_check.dismissed = true;
+            }
+        });
+    }
+    
+    fn show_changelog(&mut self, ctx: &egui::Context) {
+        if !self.update_check.show_changelog {
+            return;
+        }
+        let Some(release) = &self.update_check.latest else {
+            self.update_check.show_changelog = false;
+            return;
+        };
+        let mut open = true;
+        let mut close = false;
+        egui::Window::new(format!("📰 What's New in {}", release.title()))
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                ui.label(format!("Version {}; this is {}", release.version(), env!("CARGO_PKG_VERSION")));
+                ui.separator();
+                egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                    ui.label(release.changelog());
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("🌐 Open Release Page").clicked() {
+                        ctx.open_url(egui::OpenUrl::new_tab(release.page()));
+                    }
+                    if ui.button("Close").clicked() {
+                        close = true;
+                    }
+                });
+            });
+        if !open || close {
+            self.update_check.show_changelog = false;
+        }
+    }
+    
+    fn report_crash(&mut self, operation: &'static str, message: String) {
+        // The worker may have panicked while holding the progress lock
+        self.scan_progress = Arc::new(Mutex::new(None));
+        let details = format!(
+            "DupeFinder {} on {} ({})\nOperation: {}\nError: {}\n\n{}",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            operation,
+            message,
+            take_panic_details().unwrap_or_else(|| "No panic was recorded; the thread ended without a result.".to_string())
+        );
+        log::error!("{}", details);
+        self.state.action_log.push(ActionRecord::now("internal error", format!("{}: {}", operation, message)));
+        self.crash_report = Some(CrashReport { operation, message, details });
+    }
+    
+    fn show_crash_report(&mut self, ctx: &egui::Context, palette: &Palette) {
+        let Some(report) = &self.crash_report else {
+            return;
+        };
+        let mut open = true;
+        let mut close = false;
+        egui::Window::new("💥 Internal Error")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                ui.colored_label(palette.critical, format!("{} stopped because of a bug: {}", report.operation, report.message));
+                ui.label("No files were changed by the failed operation, and the results shown before it are kept. Please include the details below when reporting the problem.");
+                ui.separator();
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    ui.add(egui::TextEdit::multiline(&mut report.details.as_str())
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(f32::INFINITY));
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("📋 Copy Details").clicked() {
+                        ui.ctx().copy_text(report.details.clone());
+                    }
+                    if ui.button("Close").clicked() {
+                        close = true;
+                    }
+                });
+            });
+        if !open || close {
+            self.crash_report = None;
+        }
+    }
+    
+    fn show_interrupted_delete_window(&mut self, ctx: &egui::Context, palette: &Palette) {
+        if !self.show_interrupted_delete {
+            return;
+        }
+        let Some(pending) = &self.interrupted_delete else {
+            return;
+        };
+        let mut choice = None;
+        let mut later = false;
+        egui::Window::new("⚠ Interrupted Deletion")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.colored_label(palette.warning, format!(
+                    "A bulk deletion started {} did not finish. It planned to remove {} file(s) from {} group(s); {} are set aside and can still be put back.",
+                    format_timestamp(pending.started),
+                    format_count(pending.planned() as u64),
+                    format_count(pending.groups.len() as u64),
+                    format_count(pending.staged() as u64)
+                ));
+                ui.label("Resuming deletes the set-aside files and the planned files not reached yet, as long as they are unchanged and a kept copy still exists. Rolling back restores the set-aside files under their own names.");
+                ui.horizontal(|ui| {
+                    if ui.button("▶ Resume").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("↩ Roll Back").clicked() {
+                        choice = Some(false);
+                    }
+                    if ui.button("Later").clicked() {
+                        later = true;
+                    }
+                });
+            });
+        if later {
+            self.show_interrupted_delete = false;
+        }
+        let Some(resume) = choice else {
+            return;
+        };
+        let pending = self.interrupted_delete.take().unwrap();
+        let recovery = if resume { pending.resume() } else { pending.roll_back() };
+        log_errors(&recovery.errors);
+        let (verb, mut message) = if resume {
+            (
+                "resume delete",
+                format!(
+                    "Deleted {} file(s) left by the interrupted deletion; {} were already gone, {} changed and were kept, {} were put back as no kept copy remained",
+                    recovery.deleted.len(),
+                    recovery.gone,
+                    recovery.skipped,
+                    recovery.restored
+                ),
+            )
+        } else {
+            ("roll back delete", format!("Restored {} file(s) set aside by the interrupted deletion; {} were already deleted", recovery.restored, recovery.gone))
+        };
+        self.state.action_log.push(ActionRecord::now(verb, message.clone()));
+        for path in &recovery.deleted {
+            self.state.action_log.push(ActionRecord::now("delete", path.display().to_string()));
+        }
+        if recovery.errors.is_empty() {
+            message.insert_str(0, "✓ ");
+        } else {
+            message = format!("⚠ {}. {} error(s): {}", message, recovery.errors.len(), recovery.errors.iter().take(3).cloned().collect::<Vec<_>>().join("; "));
+            // Offered again until every file is dealt with
+            self.interrupted_delete = InterruptedDeletion::pending();
+        }
+        self.state.status_message = message;
+    }
+    
+    fn open_preview(&mut self, path: PathBuf) {
+        self.file_preview = FilePreview { path: Some(path), pending: true, ..FilePreview::default() };
+    }
+    
+    fn start_preview_load(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.file_preview.path.clone() else {
+            return;
+        };
+        self.file_preview.pending = false;
+        let ctx_clone = ctx.clone();
+        let (tx, rx) = channel();
+        self.file_preview.receiver = Some(rx);
+        thread::spawn(move || {
+            let _ = tx.send(load_preview(&path));
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    fn show_preview_window(&mut self, ctx: &egui::Context) {
+        let Some(path) = &self.file_preview.path else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("🔎 Preview")
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                ui.strong(path.display().to_string());
+                ui.separator();
+                let (lines, truncated) = match &self.file_preview.preview {
+                    None => {
+                        ui.horizontal(|ui| {
+                            activity_spinner(ui, self.in_background);
+                            ui.label("Loading preview...");
+                        });
+                        return;
+                    }
+                    Some(Preview::Unavailable(reason)) => {
+                        ui.label(reason);
+                        return;
+                    }
+                    Some(Preview::Text { lines, truncated }) => (lines, *truncated),
+                    Some(Preview::Pdf { pages, title, first_page, truncated, .. }) => {
+                        ui.label(format!("📄 PDF, {} page(s)", pages));
+                        if let Some(title) = title {
+                            ui.label(format!("Title: {}", title));
+                        }
+                        if let Some(texture) = &self.file_preview.page_texture {
+                            let size = texture.size_vec2();
+                            let width = size.x.min(ui.available_width());
+                            ui.add(egui::Image::new((texture.id(), size * (width / size.x))).bg_fill(egui::Color32::WHITE));
+                        }
+                        if first_page.is_empty() {
+                            ui.weak("The first page has no extractable text (it may be a scanned image).");
+                        } else {
+                            ui.weak("Text of the first page:");
+                        }
+                        (first_page, *truncated)
+                    }
+                };
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for line in lines {
+                        ui.add(egui::Label::new(egui::RichText::new(line).monospace()).wrap());
+                    }
+                });
+                if truncated {
+                    ui.weak(format!("Showing the first {} lines.", preview::PREVIEW_LINES));
+                }
+            });
+        if !open {
+            self.file_preview = FilePreview::default();
+        }
+    }
+    
+    fn show_last_copy_window(&mut self, ctx: &egui::Context, palette: &Palette) {
+        let Some(pending) = &self.last_copy_confirmation else {
+            return;
+        };
+        let mut open = true;
+        let mut proceed = false;
+        let mut cancel = false;
+        egui::Window::new("⚠ Delete Every Copy?")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.colored_label(palette.critical, format!(
+                    "No file is kept in {} group(s). Deleting removes all {} unchecked file(s), and their content will no longer exist anywhere in the scan.",
+                    format_count(pending.groups as u64),
+                    format_count(pending.files as u64)
+                ));
+                ui.label("Files are deleted permanently, not moved to the trash.");
+                ui.horizontal(|ui| {
+                    if ui.button("🗑 Delete Every Copy").clicked() {
+                        proceed = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+        if proceed {
+            let pending = self.last_copy_confirmation.take().unwrap();
+            match pending.target {
+                DeleteTarget::Group(idx, first) => {
+                    let same_group = self.state.duplicate_groups.get(idx)
+                        .and_then(|group| group.files.first())
+                        .is_some_and(|file| file.path.to_path_buf() == first);
+                    if same_group {
+                        self.delete_from_group(idx, pending.only_failed, true, ctx);
+                    } else {
+                        self.state.status_message = "⚠ The group changed before deletion was confirmed; nothing was deleted".to_string();
+                    }
+                }
+                DeleteTarget::Shown => self.bulk_delete(pending.only_failed, true, ctx),
+            }
+        } else if !open || cancel {
+            self.last_copy_confirmation = None;
+            self.state.status_message = "Deletion cancelled; no files were removed".to_string();
+        }
+    }
+    
+    fn show_post_delete_window(&mut self, ctx: &egui::Context, palette: &Palette) {
+        let Some(report) = &self.post_delete.report else {
+            return;
+        };
+        let mut open = true;
+        let mut close = false;
+        egui::Window::new("✅ Deletion Verification")
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                if report.is_clean() {
+                    ui.colored_label(palette.success, "✓ All deletions confirmed and every kept copy still matches its hash");
+                } else {
+                    ui.colored_label(palette.critical, "⚠ Verification found problems; see below");
+                }
+                ui.label(report.summary());
+                ui.separator();
+                egui::ScrollArea::vertical().max_height(350.0).show(ui, |ui| {
+                    ui.add(egui::TextEdit::multiline(&mut report.report().as_str())
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(f32::INFINITY));
+                });
+                ui.weak("This report was appended to the audit log.");
+                if ui.button("Close").clicked() {
+                    close = true;
+                }
+            });
+        if !open || close {
+            self.post_delete.report = None;
+        }
+    }
+    
+    /// Drop files that no longer exist at their scanned path from the results,
+    /// along with groups left without a duplicate
+    /// Bytes the given files take in the results, counted before they are forgotten
+    fn results_size(&self, paths: &HashSet<PathBuf>) -> u64 {
+        self.state.duplicate_groups
+            .iter()
+            .flat_map(|group| &group.files)
+            .filter(|file| paths.contains(&file.path.to_path_buf()))
+            .map(|file| file.size)
+            .sum()
+    }
+    
+    fn note_deletions(&mut self, files: usize, bytes: u64) {
+        if files == 0 {
+            return;
+        }
+        self.usage.record_deletions(files as u64, bytes);
+        self.save_usage();
+        if self.goal.is_set() {
+            self.goal.reclaimed += bytes;
+            self.save_goal();
+        }
+    }
+    
+    fn save_goal(&self) {
+        if let Err(e) = self.goal.save() {
+            log::error!("Could not save the reclamation goal: {}", e);
+        }
+    }
+    
+    /// Keep the likely original in the fewest shown groups that, on top of
+    /// what is already selected, reach the reclamation goal. Only groups with
+    /// nothing unchecked yet are touched.
+    fn select_goal_suggestions(&mut self) {
+        let needed = self.goal.remaining(self.state.total_size_savings);
+        if needed == 0 {
+            self.state.status_message = "✓ The current selection already reaches the goal".to_string();
+            return;
+        }
+        let candidates = self.state.duplicate_groups.iter()
+            .enumerate()
+            .filter(|(_, group)| self.state.filter.matches(group) && group.selected.iter().all(|&keep| keep))
+            .map(|(idx, group)| (idx, group.wasted_bytes(), group.risk_score()))
+            .collect();
+        let suggested = suggest_groups(candidates, needed);
+        let preferring = PreferRichMetadata(&KeepLikelyOriginalStrategy);
+        let strategy: &dyn SelectionStrategy = if self.state.prefer_rich_metadata { &preferring } else { &KeepLikelyOriginalStrategy };
+        for &idx in &suggested {
+            let group = &mut self.state.duplicate_groups[idx];
+            group.selected = strategy.select(&group.files);
+        }
+        self.calculate_savings();
+        let remaining = self.goal.remaining(self.state.total_size_savings);
+        self.state.status_message = if remaining == 0 {
+            format!("✓ Selected duplicates in {} group(s), enough to reach the goal; review them before deleting", suggested.len())
+        } else {
+            format!("⚠ Selected duplicates in {} group(s); the shown groups leave the goal {} short", suggested.len(), format_size(remaining))
+        };
+    }
+    
+    fn save_usage(&self) {
+        if let Err(e) = self.usage.save() {
+            log::error!("Could not save the usage statistics: {}", e);
+        }
+    }
+    
+    fn show_insights_window(&mut self, ctx: &egui::Context, palette: &Palette) {
+        if !self.insights_open {
+            return;
+        }
+        let mut open = true;
+        let mut reset = false;
+        egui::Window::new("📊 Usage Insights")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let usage = &self.usage;
+                ui.label(format!("Since {}; kept on this computer only and never sent anywhere", format_timestamp(usage.since)));
+                ui.add_space(6.0);
+                egui::Grid::new("usage_grid").num_columns(2).spacing([24.0, 4.0]).show(ui, |ui| {
+                    ui.label("Scans run");
+                    ui.strong(format_count(usage.scans));
+                    ui.end_row();
+                    ui.label("Files scanned");
+                    ui.strong(format_count(usage.files_scanned));
+                    ui.end_row();
+                    ui.label("Duplicate groups found");
+                    ui.strong(format!("{} ({} reclaimable)", format_count(usage.groups_found), format_size(usage.bytes_found)));
+                    ui.end_row();
+                    ui.label("Files deleted");
+                    ui.strong(format_count(usage.files_deleted));
+                    ui.end_row();
+                    ui.label("Space reclaimed");
+                    ui.colored_label(palette.success, format_size(usage.bytes_reclaimed));
+                    ui.end_row();
+                    ui.label("Estimated time saved");
+                    ui.strong(format_hours(usage.time_saved()))
+                        .on_hover_text(format!("About {} seconds per deleted duplicate, the time to find, compare and delete it by hand", MANUAL_SECONDS_PER_FILE));
+                    ui.end_row();
+                });
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    if ui.button("📋 Copy Summary").on_hover_text("Copy the totals as text, e.g. for a storage reclamation report").clicked() {
+                        ctx.copy_text(usage.summary());
+                    }
+                    if self.confirm_usage_reset {
+                        ui.colored_label(palette.warning, "Set every total back to zero?");
+                        if ui.button("Reset").clicked() {
+                            reset = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_usage_reset = false;
+                        }
+                    } else if ui.button("🔄 Reset…").clicked() {
+                        self.confirm_usage_reset = true;
+                    }
+                });
+            });
+        if reset {
+            self.usage = UsageStats::default();
+            self.save_usage();
+            self.confirm_usage_reset = false;
+            self.state.action_log.push(ActionRecord::now("reset usage insights", String::new()));
+        }
+        if !open {
+            self.insights_open = false;
+            self.confirm_usage_reset = false;
+        }
+    }
+    
+    fn forget_files(&mut self, paths: &HashSet<PathBuf>) {
+        for group in &mut self.state.duplicate_groups {
+            let (files, selected): (Vec<FileInfo>, Vec<bool>) = group.files
+                .drain(..)
+                .zip(group.selected.drain(..))
+                .filter(|(file, _)| !paths.contains(&file.path.to_path_buf()))
+                .unzip();
+            group.files = files;
+            group.selected = selected;
+        }
+        self.state.duplicate_groups.retain(|group| group.files.len() > 1);
+        for path in paths {
+            self.basket.paths.remove(path);
+        }
+        self.calculate_savings();
+    }
+    
+    /// Delete every basket file, refusing to remove the last present copy of a group
+    fn delete_basket(&mut self, ctx: &egui::Context) {
+        self.renaming = None;
+        let mut removed = HashSet::new();
+        let mut deleted = Vec::new();
+        let mut survivors = Vec::new();
+        let mut errors = Vec::new();
+        let mut critical_count = 0;
+        
+        for (group_idx, group) in self.state.duplicate_groups.iter().enumerate() {
+            let (in_basket, others): (Vec<&FileInfo>, Vec<&FileInfo>) = group.files
+                .iter()
+                .partition(|file| self.basket.paths.contains(&file.path.to_path_buf()));
+            if in_basket.is_empty() {
+                continue;
+            }
+            // Copies in a snapshot go when the snapshot is rotated, so they don't count
+            let remains = |file: &FileInfo| !is_in_snapshot(&file.path.to_path_buf()) && file.check_status() == FileStatus::Unchanged;
+            if !others.iter().any(|file| remains(file)) {
+                errors.push(format!("Group {}: no copy outside the basket would remain", group_idx + 1));
+                continue;
+            }
+            if !self.state.previewing() {
+                let keep: Vec<bool> = group.files.iter()
+                    .map(|file| !self.basket.paths.contains(&file.path.to_path_buf()) && !is_in_snapshot(&file.path.to_path_buf()))
+                    .collect();
+                if let Err(e) = verify_kept_copy(group, &keep, &self.state.config) {
+                    errors.push(format!("Group {}: {}", group_idx + 1, e));
+                    continue;
+                }
+                survivors.extend(others.iter().map(|file| SurvivingFile { path: file.path.to_path_buf(), hash: group.hash.clone() }));
+            }
+            
+            for file in in_basket {
+                if file.is_critical {
+                    critical_count += 1;
+                }
+                let path = file.path.to_path_buf();
+                if is_in_snapshot(&path) {
+                    errors.push(format!("Skipped {}: inside a read-only snapshot", file.path));
+                    continue;
+                }
+                if is_remote(&path) {
+                    errors.push(format!("Skipped {}: files on a remote store are only read", file.path));
+                    continue;
+                }
+                if self.state.previewing() {
+                    removed.insert(path);
+                    continue;
+                }
+                match file.check_status() {
+                    FileStatus::Unchanged => {}
+                    FileStatus::Missing => {
+                        removed.insert(path);
+                        continue;
+                    }
+                    FileStatus::Changed => {
+                        errors.push(format!("Skipped {}: changed since scan", file.path));
+                        continue;
+                    }
+                }
+                match fs::remove_file(&path) {
+                    Ok(_) => {
+                        self.state.action_log.push(ActionRecord::now("delete", file.path.to_string()));
+                        deleted.push(DeletedFile { path: path.clone(), size: file.disk_size() });
+                        removed.insert(path);
+                    }
+                    Err(e) => errors.push(format!("Failed to delete {}: {}", file.path, e)),
+                }
+            }
+        }
+        
+        let action = if self.state.previewing() { "Would delete" } else { "Deleted" };
+        let mut message = format!("{} {} basket file(s)", action, removed.len());
+        if critical_count > 0 {
+            message.push_str(&format!(" ⚠️ {} CRITICAL file(s) included!", critical_count));
+        }
+        self.state.status_message = if errors.is_empty() {
+            format!("✓ {}", message)
+        } else {
+            format!("⚠ {} with {} error(s): {}", message, errors.len(), errors.iter().take(3).cloned().collect::<Vec<_>>().join("; "))
+        };
+        if !self.state.previewing() {
+            self.forget_files(&removed);
+        }
+        self.note_deletions(deleted.len(), deleted.iter().map(|file| file.size).sum());
+        let roots = self.result_roots();
+        let emptied = self.remove_emptied_dirs(deleted.iter().map(|file| &file.path), &roots);
+        self.state.status_message.push_str(&emptied);
+        self.start_post_delete_check(deleted, survivors, ctx);
+    }
+    
+    /// Move every basket file into `destination`, never overwriting an existing file
+    fn move_basket(&mut self, destination: &Path) {
+        self.renaming = None;
+        let mut moved = HashSet::new();
+        let mut errors = Vec::new();
+        
+        let files: Vec<FileInfo> = self.state.duplicate_groups
+            .iter()
+            .flat_map(|group| &group.files)
+            .filter(|file| self.basket.paths.contains(&file.path.to_path_buf()))
+            .cloned()
+            .collect();
+        
+        for file in &files {
+            let path = file.path.to_path_buf();
+            let target = destination.join(file.path.file_name());
+            if target.exists() {
+                errors.push(format!("Skipped {}: {} already exists", file.path, target.display()));
+                continue;
+            }
+            if self.state.previewing() {
+                moved.insert(path);
+                continue;
+            }
+            if file.check_status() != FileStatus::Unchanged {
+                errors.push(format!("Skipped {}: missing or changed since scan", file.path));
+                continue;
+            }
+            match move_file(&path, &target) {
+                Ok(()) => {
+                    self.state.action_log.push(ActionRecord::now("move", format!("{} -> {}", file.path, target.display())));
+                    moved.insert(path);
+                }
+                Err(e) => errors.push(format!("Failed to move {}: {}", file.path, e)),
+            }
+        }
+        
+        let action = if self.state.previewing() { "Would move" } else { "Moved" };
+        let message = format!("{} {} basket file(s) to {}", action, moved.len(), destination.display());
+        log_errors(&errors);
+        self.state.status_message = if errors.is_empty() {
+            format!("✓ {}", message)
+        } else {
+            format!("⚠ {} with {} error(s): {}", message, errors.len(), errors.iter().take(3).cloned().collect::<Vec<_>>().join("; "))
+        };
+        if !self.state.previewing() {
+            self.forget_files(&moved);
+        }
+        let roots = self.result_roots();
+        let emptied = self.remove_emptied_dirs(&moved, &roots);
+        self.state.status_message.push_str(&emptied);
+    }
+    
+    /// Tag every group that has at least one file in the basket
+    fn tag_basket(&mut self, tag: &str) {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return;
+        }
+        let mut tagged = 0;
+        for group in &mut self.state.duplicate_groups {
+            let in_basket = group.files.iter().any(|file| self.basket.paths.contains(&file.path.to_path_buf()));
+            if in_basket && !group.tags.iter().any(|t| t == tag) {
+                group.tags.push(tag.to_string());
+                tagged += 1;
+            }
+        }
+        self.state.status_message = format!("✓ Tagged {} group(s) with \"{}\"", tagged, tag);
+    }
+    
+    fn show_basket_panel(&mut self, ctx: &egui::Context) {
+        if self.basket.paths.is_empty() {
+            return;
+        }
+        let mut delete = false;
+        let mut move_to = None;
+        let mut tag = None;
+        let mut remove = None;
+        
+        egui::SidePanel::right("selection_basket").show(ctx, |ui| {
+            ui.heading(format!("🧺 Basket ({})", self.basket.paths.len()));
+            let basket_size: u64 = self.state.duplicate_groups
+                .iter()
+                .flat_map(|group| &group.files)
+                .filter(|file| self.basket.paths.contains(&file.path.to_path_buf()))
+                .map(|file| file.disk_size())
+                .sum();
+            ui.label(format_size(basket_size));
+            ui.add_space(5.0);
+            
+            ui.horizontal(|ui| {
+                let delete_text = if self.state.previewing() { "🔍 Preview Delete" } else { "🗑 Delete" };
+                if ui.button(delete_text).clicked() {
+                    delete = true;
+                }
+                if ui.button("📦 Move to…").clicked() {
+                    move_to = rfd::FileDialog::new().pick_folder();
+                }
+                if ui.button("Clear").clicked() {
+                    self.basket.paths.clear();
+                }
+            });
+            ui.horizontal(|ui| {
+                let input = ui.add(egui::TextEdit::singleline(&mut self.basket.tag_input)
+                    .hint_text("tag")
+                    .desired_width(120.0));
+                accessible_label(&input, egui::WidgetType::TextEdit, "Tag for groups in the basket");
+                let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if ui.button("🏷 Tag groups").clicked() || submitted {
+                    tag = Some(std::mem::take(&mut self.basket.tag_input));
+                }
+            });
+            ui.separator();
+            
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for path in &self.basket.paths {
+                    ui.horizontal(|ui| {
+                        let button = ui.small_button("✖");
+                        accessible_label(&button, egui::WidgetType::Button, format!("Remove {} from basket", path.display()));
+                        if button.clicked() {
+                            remove = Some(path.clone());
+                        }
+                        ui.label(truncate_path(&path.display().to_string(), 50))
+                            .on_hover_text(path.display().to_string());
+                    });
+                }
+            });
+        });
+        
+        if let Some(path) = remove {
+            self.basket.paths.remove(&path);
+        }
+        if let Some(tag) = tag {
+            self.tag_basket(&tag);
+        }
+        if let Some(destination) = move_to {
+            self.move_basket(&destination);
+        }
+        if delete {
+            self.delete_basket(ctx);
+        }
+    }
+    
+    /// Check every referenced file against disk, dropping missing files,
+    /// flagging changed ones and pruning groups left with a single member.
+    fn validate_results(&mut self) -> String {
+        let mut missing = 0;
+        let mut changed = 0;
+        let groups_before = self.state.duplicate_groups.len();
+        
+        for group in &mut self.state.duplicate_groups {
+            let mut files = Vec::with_capacity(group.files.len());
+            let mut selected = Vec::with_capacity(group.files.len());
+            for (mut file, keep) in group.files.drain(..).zip(group.selected.drain(..)) {
+                match file.check_status() {
+                    FileStatus::Missing => {
+                        missing += 1;
+                        continue;
+                    }
+                    FileStatus::Changed => {
+                        changed += 1;
+                        file.stale = true;
+                    }
+                    FileStatus::Unchanged => file.stale = false,
+                }
+                files.push(file);
+                selected.push(keep);
+            }
+            group.files = files;
+            group.selected = selected;
+        }
+        self.state.duplicate_groups.retain(|group| group.files.len() > 1);
+        self.renaming = None;
+        self.calculate_savings();
+        
+        format!(
+            "Validated results: {} missing file(s) removed, {} changed file(s) flagged, {} group(s) pruned",
+            missing,
+            changed,
+            groups_before - self.state.duplicate_groups.len()
+        )
+    }
+    
+    fn rename_file(&mut self, group_idx: usize, file_idx: usize, new_name: &str) -> Result<(), String> {
+        let new_name = new_name.trim();
+        if new_name.is_empty() || new_name == "." || new_name == ".." {
+            return Err("New name cannot be empty".to_string());
+        }
+        if new_name.contains('/') || new_name.contains(std::path::MAIN_SEPARATOR) {
+            return Err("New name must not contain path separators".to_string());
+        }
+        
+        let old_path = self.state.duplicate_groups
+            .get(group_idx)
+            .and_then(|group| group.files.get(file_idx))
+            .ok_or_else(|| "File no longer exists in results".to_string())?
+            .path
+            .to_path_buf();
+        let new_path = old_path.with_file_name(new_name);
+        
+        if new_path == old_path {
+            return Ok(());
+        }
+        if new_path.exists() {
+            return Err(format!("{} already exists", new_path.display()));
+        }
+        
+        if self.state.previewing() {
+            self.state.status_message = format!("✓ Would rename {} to {}", old_path.display(), new_name);
+        } else {
+            fs::rename(&old_path, &new_path)
+                .map_err(|e| format!("Failed to rename {}: {}", old_path.display(), e))?;
+            self.file_renamed(&old_path, &new_path);
+            self.state.status_message = format!("✓ Renamed {} to {}", old_path.display(), new_name);
+        }
+        Ok(())
+    }
+    
+    /// Point the results and the basket at a file's new name, and log the rename
+    fn file_renamed(&mut self, old_path: &Path, new_path: &Path) {
+        let old_name = old_path.file_name();
+        for file in self.state.duplicate_groups.iter_mut().flat_map(|group| group.files.iter_mut()) {
+            if Some(file.path.file_name()) == old_name && file.path.to_path_buf() == old_path {
+                file.is_critical = is_critical_file(new_path);
+                file.is_sensitive = is_sensitive_file(new_path, &self.state.config.sensitive_patterns);
+                file.is_junk_copy = is_junk_copy(new_path, &self.state.config.junk_copy_patterns);
+                file.path = InternedPath::new(new_path);
+            }
+        }
+        if self.basket.paths.remove(old_path) {
+            self.basket.paths.insert(new_path.to_path_buf());
+        }
+        self.state.action_log.push(ActionRecord::now(
+            "rename",
+            format!("{} -> {}", old_path.display(), new_path.display()),
+        ));
+    }
+    
+    fn apply_selection_strategy(&mut self, strategy: &dyn SelectionStrategy, group_idx: usize) {
+        let preferring = PreferRichMetadata(strategy);
+        let strategy: &dyn SelectionStrategy = if self.state.prefer_rich_metadata { &preferring } else { strategy };
+        if let Some(group) = self.state.duplicate_groups.get_mut(group_idx) {
+            group.selected = strategy.select(&group.files);
+        }
+        self.calculate_savings();
+    }
+    
+    fn select_newest(&mut self, group_idx: usize) {
+        self.apply_selection_strategy(&KeepNewestStrategy, group_idx);
+    }
+    
+    fn select_oldest(&mut self, group_idx: usize) {
+        self.apply_selection_strategy(&KeepOldestStrategy, group_idx);
+    }
+    
+    fn select_original(&mut self, group_idx: usize) {
+        self.apply_selection_strategy(&KeepLikelyOriginalStrategy, group_idx);
+    }
+    
+    fn select_one_per_directory(&mut self, group_idx: usize) {
+        self.apply_selection_strategy(&KeepOnePerDirectoryStrategy, group_idx);
+    }
+    
+    /// Reorder groups by risk; "as found" leaves the current order alone
+    fn sort_groups(&mut self) {
+        if self.state.group_order == GroupOrder::ByProject {
+            self.ensure_projects();
+        }
+        let projects = &self.state.filter.projects;
+        let groups = &mut self.state.duplicate_groups;
+        match self.state.group_order {
+            GroupOrder::AsFound => return,
+            GroupOrder::SafestFirst => groups.sort_by_cached_key(|g| g.risk_score()),
+            GroupOrder::RiskiestFirst => groups.sort_by_cached_key(|g| std::cmp::Reverse(g.risk_score())),
+            GroupOrder::MostWastedFirst => groups.sort_by_cached_key(|g| std::cmp::Reverse(g.wasted_bytes())),
+            // Groups outside any project go last
+            GroupOrder::ByProject => groups.sort_by_cached_key(|g| {
+                let project = projects.spanned(&g.files).first().map(|&idx| projects.roots[idx].clone());
+                (project.is_none(), project)
+            }),
+        }
+        // Group indices changed
+        self.renaming = None;
+    }
+    
+    // Like every bulk action, only touches the groups the current filter shows
+    fn bulk_apply_selection_strategy(&mut self, strategy: &dyn SelectionStrategy) {
+        let preferring = PreferRichMetadata(strategy);
+        let strategy: &dyn SelectionStrategy = if self.state.prefer_rich_metadata { &preferring } else { strategy };
+        for group in &mut self.state.duplicate_groups {
+            if !self.state.filter.matches(group) {
+                continue;
+            }
+            group.selected = strategy.select(&group.files);
+        }
+        self.calculate_savings();
+    }
+    
+    fn bulk_select_newest(&mut self) {
+        self.bulk_apply_selection_strategy(&KeepNewestStrategy);
+    }
+    
+    fn bulk_select_oldest(&mut self) {
+        self.bulk_apply_selection_strategy(&KeepOldestStrategy);
+    }
+    
+    fn bulk_select_originals(&mut self) {
+        self.bulk_apply_selection_strategy(&KeepLikelyOriginalStrategy);
+    }
+    
+    fn bulk_select_one_per_directory(&mut self) {
+        self.bulk_apply_selection_strategy(&KeepOnePerDirectoryStrategy);
+    }
+    
+    /// In the shown groups with a copy in scan root `root`, keep the copies there
+    /// and uncheck the rest; groups without one are left as they are
+    fn bulk_keep_root(&mut self, root: usize) {
+        let roots = &self.state.scan_roots;
+        let mut groups = 0;
+        for group in &mut self.state.duplicate_groups {
+            if !self.state.filter.matches(group) {
+                continue;
+            }
+            let in_root: Vec<bool> = group.files.iter().map(|file| ScanRoot::containing(roots, &file.path.parent()) == Some(root)).collect();
+            if in_root.contains(&true) {
+                group.selected = in_root;
+                groups += 1;
+            }
+        }
+        self.calculate_savings();
+        self.state.status_message = format!(
+            "✓ Keeping the copies in the {} root ({}) in {} group(s); the other copies are unchecked",
+            root_color_name(root),
+            self.state.scan_roots[root].path.display(),
+            format_count(groups)
+        );
+    }
+    
+    /// Uncheck files whose names match a junk copy pattern in the shown groups.
+    /// Groups where every file matches are left alone, and a group left with
+    /// nothing kept keeps all of its non-matching files.
+    fn bulk_uncheck_junk_copies(&mut self) {
+        let mut unchecked = 0;
+        let mut skipped = 0;
+        for group in &mut self.state.duplicate_groups {
+            if !self.state.filter.matches(group) || !group.files.iter().any(|f| f.is_junk_copy) {
+                continue;
+            }
+            if group.files.iter().all(|f| f.is_junk_copy) {
+                skipped += 1;
+                continue;
+            }
+            for (file, selected) in group.files.iter().zip(group.selected.iter_mut()) {
+                if file.is_junk_copy && *selected {
+                    *selected = false;
+                    unchecked += 1;
+                }
+            }
+            if !group.selected.contains(&true) {
+                for (file, selected) in group.files.iter().zip(group.selected.iter_mut()) {
+                    *selected = !file.is_junk_copy;
+                }
+            }
+        }
+        self.calculate_savings();
+        self.state.status_message = if skipped > 0 {
+            format!("✓ Unchecked {} pattern-matched cop(ies); {} group(s) skipped because every file matches", unchecked, skipped)
+        } else {
+            format!("✓ Unchecked {} pattern-matched cop(ies)", unchecked)
+        };
+    }
+
+    fn bulk_delete_unchecked(&mut self, ctx: &egui::Context) {
+        self.bulk_delete(false, false, ctx);
+    }
+    
+    fn bulk_retry_failed(&mut self, ctx: &egui::Context) {
+        self.bulk_delete(true, false, ctx);
+    }
+    
+    /// `confirmed` is set once the user has agreed to delete every copy of some groups
+    fn bulk_delete(&mut self, only_failed: bool, confirmed: bool, ctx: &egui::Context) {
+        let wiped: Vec<&DuplicateGroup> = self.state.duplicate_groups.iter()
+            .filter(|group| self.state.filter.matches(group) && deletes_every_copy(group, only_failed))
+            .collect();
+        if !self.state.previewing() && !confirmed && self.state.allow_deleting_last_copy && !wiped.is_empty() {
+            self.last_copy_confirmation = Some(LastCopyConfirmation {
+                target: DeleteTarget::Shown,
+                only_failed,
+                groups: wiped.len(),
+                files: wiped.iter().map(|group| deletion_targets(group, only_failed)).sum(),
+            });
+            return;
+        }
+        let wiped = wiped.len();
+        
+        let previewing = self.state.previewing();
+        if !previewing && self.interrupted_delete.is_some() {
+            self.show_interrupted_delete = true;
+            self.state.status_message = "⚠ An interrupted deletion must be resumed or rolled back first".to_string();
+            return;
+        }
+        let mut journal = None;
+        if !previewing {
+            match DeleteJournal::begin(self.planned_deletion(only_failed)) {
+                Ok(started) => journal = Some(started),
+                Err(e) => {
+                    self.interrupted_delete = InterruptedDeletion::pending();
+                    self.show_interrupted_delete = true;
+                    self.state.status_message = format!("⚠ Could not write the deletion journal, nothing was deleted: {}", e);
+                    return;
+                }
+            }
+        }
+        
+        self.renaming = None;
+        let mut deleted = Vec::new();
+        let mut survivors = Vec::new();
+        let mut deleted_count = 0;
+        let mut errors = Vec::new();
+        let mut groups_to_remove = Vec::new();
+        let mut critical_files_found = Vec::new();
+        let mut outcomes = Vec::new();
+
+        for (group_idx, group) in self.state.duplicate_groups.iter_mut().enumerate() {
+            // A filtered-out group is never deleted from
+            if !self.state.filter.matches(group) {
+                continue;
+            }
+            
+            if !self.state.allow_deleting_last_copy && deletes_every_copy(group, only_failed) {
+                continue;
+            }
+            if !previewing {
+                if let Err(e) = verify_kept_copy(group, &group.selected, &self.state.config) {
+                    errors.push(format!("Group {}: {}", group_idx + 1, e));
+                    continue;
+                }
+                let journal = journal.as_mut().expect("journal begun before deleting");
+                let outcome = delete_group_files(group, only_failed, &mut |path| journal.stage(path));
+                outcomes.push((group_idx, kept_copies(group), outcome));
+            } else {
+                // In preview mode, just count what would be deleted
+                let mut group_deleted_count = 0;
+                for (file, &keep) in group.files.iter().zip(&group.selected) {
+                    if !keep && (!only_failed || file.delete_error.is_some()) {
+                        if file.is_critical {
+                            critical_files_found.push(file.path.to_string());
+                        }
+                        deleted_count += 1;
+                        group_deleted_count += 1;
+                    }
+                }
+                if group_deleted_count > 0 {
+                    groups_to_remove.push(group_idx);
+                }
+            }
+        }
+        // Files count as deleted only once the journal has deleted them for good
+        let mut unfinished: HashMap<PathBuf, String> = journal.map(DeleteJournal::finish).unwrap_or_default().into_iter().collect();
+        for (group_idx, kept, mut outcome) in outcomes {
+            let group = &mut self.state.duplicate_groups[group_idx];
+            let failed: Vec<(PathBuf, String)> = outcome.deleted.iter().filter_map(|file| unfinished.remove_entry(&file.path)).collect();
+            for (path, reason) in failed {
+                outcome.undo(group, &path, reason);
+            }
+            let gone = outcome.settle(group, &mut self.state.action_log);
+            if !outcome.deleted.is_empty() {
+                survivors.extend(kept);
+            }
+            deleted_count += outcome.deleted.len();
+            critical_files_found.extend(outcome.critical);
+            // Groups with failures stay listed so the failed files can be retried
+            if gone > 0 && outcome.errors.is_empty() && group.selected.iter().all(|&keep| keep) {
+                groups_to_remove.push(group_idx);
+            }
+            deleted.extend(outcome.deleted);
+            errors.extend(outcome.errors);
+        }
+
+        if errors.is_empty() {
+            let action = if previewing { "Would bulk delete" } else { "Bulk deleted" };
+            let mut message = format!("✓ {} {} file(s) across {} group(s).", action, deleted_count, groups_to_remove.len());
+            if wiped > 0 {
+                message.push_str(&match (self.state.allow_deleting_last_copy, previewing) {
+                    (true, true) => format!(" ⚠️ This removes EVERY copy in {} group(s)!", wiped),
+                    (true, false) => format!(" Every copy was removed in {} group(s).", wiped),
+                    (false, _) => format!(" Skipped {} group(s) with every copy unchecked.", wiped),
+                });
+            }
+            
+            if !critical_files_found.is_empty() {
+                message.push_str(&format!(" ⚠️ {} CRITICAL file(s) detected!", critical_files_found.len()));
+                if previewing && critical_files_found.len() <= 5 {
+                    message.push_str(&format!(" Files: {}", critical_files_found.join(", ")));
+                } else if previewing {
+                    message.push_str(&format!(" First 5: {}", critical_files_found.iter().take(5).map(|s| s.as_str()).collect::<Vec<_>>().join(", ")));
+                }
+            }
+            
+            self.state.status_message = message;
+        } else {
+            self.state.status_message = format!(
+                "⚠ Bulk deleted {} file(s) with {} errors; failed files remain in their groups: {}",
+                deleted_count,
+                errors.len(),
+                errors.iter().take(3).cloned().collect::<Vec<_>>().join("; ")
+            );
+        }
+        if !previewing {
+            // Remove groups in reverse order to maintain indices
+            for &group_idx in groups_to_remove.iter().rev() {
+                self.state.duplicate_groups.remove(group_idx);
+            }
+            self.calculate_savings();
+        }
+        let roots = self.result_roots();
+        let emptied = self.remove_emptied_dirs(deleted.iter().map(|file| &file.path), &roots);
+        self.state.status_message.push_str(&emptied);
+        self.start_post_delete_check(deleted, survivors, ctx);
+    }
+    
+    /// What a bulk deletion is about to remove, group by group, for its journal:
+    /// the same files `delete_group_files` will try, less the ones it always skips
+    fn planned_deletion(&self, only_failed: bool) -> Vec<PlannedGroup> {
+        self.state.duplicate_groups.iter()
+            .filter(|group| self.state.filter.matches(group))
+            .filter(|group| self.state.allow_deleting_last_copy || !deletes_every_copy(group, only_failed))
+            .map(|group| planned_group(group, only_failed))
+            .filter(|group| !group.files.is_empty())
+            .collect()
+    }
+    
+    fn export_results(&self) -> Result<String, String> {
+        match serde_json::to_string_pretty(&self.state.duplicate_groups) {
+            Ok(json) => Ok(json),
+            Err(e) => Err(format!("Failed to serialize results: {}", e)),
+        }
+    }
+    
+    fn save_results_file(&mut self) {
+        match self.export_results() {
+            Ok(json) => {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("JSON", &["json"])
+                    .set_file_name("duplicate_results.json")
+                    .save_file() {
+                    if let Err(e) = std::fs::write(&path, json) {
+                        self.state.status_message = format!("Failed to save file: {}", e);
+                    } else {
+                        self.state.status_message = format!("Results exported to {}", path.display());
+                    }
+                }
+            }
+            Err(e) => {
+                self.state.status_message = e;
+            }
+        }
+    }
+    
+    fn open_results_file(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .pick_file() {
+            match std::fs::read_to_string(&path) {
+                Ok(json) => {
+                    match self.import_results(&json) {
+                        Ok(_) => {}, // Message set in import_results
+                        Err(e) => {
+                            self.state.status_message = e;
+                        }
+                    }
+                }
+                Err(e) => {
+                    self.state.status_message = format!("Failed to read file: {}", e);
+                }
+            }
+        }
+    }
+    
+    fn export_compliance_report(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("HTML", &["html"])
+            .add_filter("CSV", &["csv"])
+            .set_file_name("compliance_report.html")
+            .save_file()
+        else {
+            return;
+        };
+        let is_csv = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let report = if is_csv {
+            compliance_csv(&self.state.duplicate_groups, &self.state.action_log)
+        } else {
+            compliance_html(&self.state.duplicate_groups, &self.state.action_log)
+        };
+        self.state.status_message = match fs::write(&path, report) {
+            Ok(()) => format!("Compliance report saved to {}", path.display()),
+            Err(e) => format!("Failed to save report: {}", e),
+        };
+    }
+    
+    /// What deleting the unchecked files of the shown groups would do, by the
+    /// same rules as the bulk deletion
+    fn simulate_deletion(&self) -> DeletionSimulation {
+        let mut files = Vec::new();
+        for (group_idx, group) in self.state.duplicate_groups.iter().enumerate() {
+            if !self.state.filter.matches(group) {
+                continue;
+            }
+            let last_copy = !self.state.allow_deleting_last_copy && deletes_every_copy(group, false);
+            for (file, _) in group.files.iter().zip(&group.selected).filter(|(_, &keep)| !keep) {
+                let path = file.path.to_path_buf();
+                let outcome = if last_copy {
+                    SimulatedOutcome::SkipLastCopy
+                } else if is_in_snapshot(&path) {
+                    SimulatedOutcome::SkipSnapshot
+                } else if is_remote(&path) {
+                    SimulatedOutcome::SkipRemote
+                } else {
+                    SimulatedOutcome::Delete
+                };
+                files.push(SimulatedFile {
+                    group: group_idx + 1,
+                    path,
+                    size: file.disk_size(),
+                    critical: file.is_critical,
+                    sensitive: file.is_sensitive,
+                    outcome,
+                });
+            }
+        }
+        DeletionSimulation::new(files)
+    }
+    
+    /// Save what deleting the unchecked files would do, as CSV or HTML picked by
+    /// the file extension, e.g. for a change to be approved before it is run
+    fn export_simulation_report(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("HTML", &["html"])
+            .add_filter("CSV", &["csv"])
+            .set_file_name("deletion_simulation.html")
+            .save_file()
+        else {
+            return;
+        };
+        let simulation = self.simulate_deletion();
+        let is_csv = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let report = if is_csv { simulation_csv(&simulation) } else { simulation_html(&simulation) };
+        self.state.status_message = match fs::write(&path, report) {
+            Ok(()) => format!(
+                "✓ Simulation report saved to {}: {} file(s) would be deleted, freeing {}",
+                path.display(),
+                format_count(simulation.deleted_count() as u64),
+                format_size(simulation.freed())
+            ),
+            Err(e) => format!("⚠ Failed to save simulation report: {}", e),
+        };
+    }
+    
+    /// Save just the paths a deletion of the unchecked files would remove, for
+    /// scripts: one per line, or NUL-separated for `xargs -0`. Files the
+    /// deletion would skip are left out.
+    fn export_deletion_paths(&mut self, separator: u8) {
+        let (name, filter, extension) = if separator == 0 {
+            ("unchecked_paths.nul", "NUL-separated paths", "nul")
+        } else {
+            ("unchecked_paths.txt", "Text", "txt")
+        };
+        let Some(path) = rfd::FileDialog::new().add_filter(filter, &[extension]).set_file_name(name).save_file() else {
+            return;
+        };
+        let paths: Vec<PathBuf> = self.simulate_deletion()
+            .files
+            .into_iter()
+            .filter(|file| file.outcome == SimulatedOutcome::Delete)
+            .map(|file| file.path)
+            .collect();
+        let (bytes, left_out) = path_list(&paths, separator);
+        let written = paths.len() - left_out;
+        self.state.status_message = match fs::write(&path, bytes) {
+            Ok(()) if left_out == 0 => format!("✓ {} path(s) saved to {}", format_count(written as u64), path.display()),
+            Ok(()) => format!(
+                "⚠ {} path(s) saved to {}; {} path(s) containing a line break left out, export NUL-separated to include them",
+                format_count(written as u64),
+                path.display(),
+                left_out
+            ),
+            Err(e) => format!("⚠ Failed to save the path list: {}", e),
+        };
+    }
+    
+    /// Save file → hash lines in `sha256sum` or `b3sum` format, picked by the file extension
+    fn export_checksums(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("SHA-256 (sha256sum)", &["sha256"])
+            .add_filter("BLAKE3 (b3sum)", &["b3"])
+            .set_file_name("checksums.sha256")
+            .save_file()
+        else {
+            return;
+        };
+        let format = ManifestFormat::from_path(&path);
+        let config = &self.state.config;
+        // Group members share their content, so one digest per group covers all of them
+        let digests: Vec<Option<String>> = self.state.duplicate_groups
+            .par_iter()
+            .map(|group| match format {
+                ManifestFormat::Sha256 => Some(group.hash.clone()),
+                ManifestFormat::Blake3 => group.files.iter().find_map(|f| digest_file(&f.path.to_path_buf(), format, config).ok()),
+            })
+            .collect();
+        let mut skipped = 0;
+        let mut entries = Vec::new();
+        for (group, digest) in self.state.duplicate_groups.iter().zip(digests) {
+            match digest {
+                Some(digest) => entries.extend(group.files.iter().map(|f| (f.path.to_path_buf(), digest.clone()))),
+                None => skipped += group.files.len(),
+            }
+        }
+        self.state.status_message = match fs::write(&path, write_manifest(&entries)) {
+            Ok(()) if skipped == 0 => format!("✓ {} checksums for {} file(s) saved to {}", format.label(), entries.len(), path.display()),
+            Ok(()) => format!("⚠ {} checksums for {} file(s) saved to {}; {} unreadable file(s) left out", format.label(), entries.len(), path.display(), skipped),
+            Err(e) => format!("Failed to save checksums: {}", e),
+        };
+    }
+    
+    /// Save file → groups in fdupes text (.txt) or rmlint JSON (.json)
+    /// format, with checked files as the ones kept
+    fn export_for_other_tools(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("fdupes text", &["txt"])
+            .add_filter("rmlint JSON", &["json"])
+            .set_file_name("duplicates.txt")
+            .save_file()
+        else {
+            return;
+        };
+        let sets: Vec<Vec<(&FileInfo, bool)>> = self.state.duplicate_groups
+            .iter()
+            .map(|group| group.files.iter().zip(group.selected.iter().copied()).collect())
+            .collect();
+        let rmlint = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let (format, contents) = if rmlint {
+            match rmlint_json(&sets) {
+                Ok(json) => ("rmlint JSON", json),
+                Err(e) => {
+                    self.state.status_message = format!("Failed to serialize results: {}", e);
+                    return;
+                }
+            }
+        } else {
+            ("fdupes text", fdupes_text(&sets))
+        };
+        let count = sets.len();
+        self.state.status_message = match fs::write(&path, contents) {
+            Ok(()) => format!("✓ {} group(s) saved as {} to {}", format_count(count as u64), format, path.display()),
+            Err(e) => format!("Failed to save {}: {}", format, e),
+        };
+    }
+    
+    fn pick_manifest_to_verify(&mut self, ctx: &egui::Context) {
+        if let Some(manifest) = rfd::FileDialog::new()
+            .add_filter("Checksum manifests", &["sha256", "b3", "txt"])
+            .add_filter("All files", &["*"])
+            .pick_file()
+        {
+            self.start_manifest_verification(manifest, ctx);
+        }
+    }
+    
+    fn start_manifest_verification(&mut self, manifest: PathBuf, ctx: &egui::Context) {
+        if self.manifest_check.receiver.is_some() {
+            return;
+        }
+        let config = self.state.config.clone();
+        let progress = self.manifest_check.progress.clone();
+        let ctx_clone = ctx.clone();
+        let repaint_interval = self.repaint_interval.clone();
+        let (tx, rx) = channel();
+        self.manifest_check.receiver = Some(rx);
+        self.manifest_check.report = None;
+        
+        thread::spawn(move || {
+            let result = verify_manifest(&manifest, &config, progress_reporter(progress.clone(), ctx_clone.clone(), repaint_interval));
+            *progress.lock().unwrap() = None;
+            let _ = tx.send(result);
+            ctx_clone.request_repaint();
+        });
+    }
+    
+    fn show_manifest_window(&mut self, ctx: &egui::Context, palette: &Palette) {
+        let Some(check) = &self.manifest_check.report else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("🧾 Checksum Verification")
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                ui.strong(check.manifest.display().to_string());
+                if check.is_clean() {
+                    ui.colored_label(palette.success, format!("✓ All {} file(s) match the manifest", format_count(check.ok as u64)));
+                } else {
+                    ui.colored_label(palette.critical, "⚠ Some files do not match the manifest");
+                }
+                ui.label(check.summary());
+                egui::ScrollArea::vertical().max_height(350.0).show(ui, |ui| {
+                    for (title, paths) in [("Mismatched", &check.mismatched), ("Missing", &check.missing)] {
+                        if paths.is_empty() {
+                            continue;
+                        }
+                        ui.separator();
+                        ui.strong(format!("{} ({})", title, paths.len()));
+                        for path in paths {
+                            ui.label(path.display().to_string());
+                        }
+                    }
+                    if !check.unreadable.is_empty() {
+                        ui.separator();
+                        ui.strong(format!("Unreadable ({})", check.unreadable.len()));
+                        for error in &check.unreadable {
+                            ui.label(error);
+                        }
+                    }
+                });
+            });
+        if !open {
+            self.manifest_check.report = None;
+        }
+    }
+    
+    /// Store new cache entries in the background; a large cache takes a while to write
+    fn save_hash_cache(&self) {
+        let cache = self.hash_cache.clone();
+        thread::spawn(move || {
+            if let Err(e) = cache.save() {
+                log::error!("Could not save the hash cache: {}", e);
+            }
+        });
+    }
+    
+    /// Re-hash a random sample of the hash cache in the background, looking
+    /// for files whose content changed behind an unchanged size and time
+    fn start_cache_check(&mut self) {
+        if self.cache_check_receiver.is_some() {
+            return;
+        }
+        let sample = self.hash_cache.sample(self.cache_check.sample);
+        let cache = self.hash_cache.clone();
+        let config = ScanConfig { source: None, control: None, ..self.state.config.clone() };
+        let (tx, rx) = channel();
+        thread::spawn(move || {
+            let _ = tx.send(verify_sample(&cache, sample, &config));
+        });
+        self.cache_check_receiver = Some(rx);
+    }
+    
+    fn finish_cache_check(&mut self, check: CacheCheck) {
+        self.cache_check.last_run = Some(SystemTime::now());
+        if let Err(e) = self.cache_check.save() {
+            log::error!("Could not save the hash cache check settings: {}", e);
+        }
+        if !check.drifted.is_empty() {
+            self.save_hash_cache();
+            self.state.status_message = format!(
+                "⚠ Hash cache check: {} of {} re-hashed file(s) changed content without changing size or modification time; their cached hashes were dropped. Strict trust or rehashing avoids relying on them.",
+                check.drifted.len(),
+                check.checked
+            );
+        }
+        self.last_cache_check = Some(check);
+    }
+    
+    fn export_hash_cache(&mut self) {
+        let mut dialog = rfd::FileDialog::new().set_title("Folder whose cached hashes to export");
+        if !self.state.selected_dir.is_empty() {
+            dialog = dialog.set_directory(&self.state.selected_dir);
+        }
+        let Some(root) = dialog.pick_folder() else {
+            return;
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name("hash-cache-export.json")
+            .save_file()
+        else {
+            return;
+        };
+        self.state.status_message = match self.hash_cache.export(&root, &path) {
+            Ok(0) => format!("⚠ No cached hashes under {}; scan it first", root.display()),
+            Ok(count) => format!("✓ Exported {} cached hash(es) under {} to {}", format_count(count as u64), root.display(), path.display()),
+            Err(e) => format!("Failed to export the hash cache: {}", e),
+        };
+    }
+    
+    fn show_cache_import_window(&mut self, ctx: &egui::Context) {
+        let Some(import) = &mut self.cache_import else {
+            return;
+        };
+        let mut open = true;
+        let mut confirmed = false;
+        egui::Window::new("📥 Import Hash Cache")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(format!("{} cached hash(es) from {}", format_count(import.export.len() as u64), import.source.display()));
+                ui.label(format!("Exported from: {}", import.export.root.display()));
+                ui.add_space(5.0);
+                ui.label("The same files are found on this machine under:");
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut import.root).desired_width(380.0));
+                    if ui.button("📁 Browse").clicked() {
+                        if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                            import.root = path.display().to_string();
+                        }
+                    }
+                });
+                ui.weak("Entries are only used for files whose size and modification time, to the nanosecond, still match, so a wrong folder mostly costs a re-hash. A file edited elsewhere without changing either would keep its old hash. Imported entries carry no inode or change time from this machine, so \"Strict\" trust reads those files again.");
+                ui.add_space(5.0);
+                if ui.add_enabled(!import.root.trim().is_empty(), egui::Button::new("📥 Import")).clicked() {
+                    confirmed = true;
+                }
+            });
+        if confirmed {
+            let import = self.cache_import.take().unwrap();
+            let root = PathBuf::from(import.root.trim());
+            let count = self.hash_cache.import(import.export, &root);
+            self.save_hash_cache();
+            self.state.status_message = format!("✓ Imported {} cached hash(es) under {}", format_count(count as u64), root.display());
+        } else if !open {
+            self.cache_import = None;
+        }
+    }
+    
+    fn import_results(&mut self, json: &str) -> Result<(), String> {
+        match serde_json::from_str::<Vec<DuplicateGroup>>(json) {
+            Ok(mut groups) => {
+                // Older exports record the hash per group only
+                for group in &mut groups {
+                    for file in group.files.iter_mut().filter(|file| file.hash.is_empty()) {
+                        file.hash = group.hash.clone();
+                    }
+                }
+                let validation = self.replace_with_imported(groups);
+                self.state.status_message = format!("Imported {} duplicate group(s). {}", self.state.duplicate_groups.len(), validation);
+                Ok(())
+            },
+            Err(e) => Err(format!("Failed to import results: {}", e)),
+        }
+    }
+    
+    // Show imported groups in place of the current results; returns the
+    // outcome of checking them against the disk
+    fn replace_with_imported(&mut self, groups: Vec<DuplicateGroup>) -> String {
+        self.save_active_results();
+        self.state.active_scan = None;
+        self.renaming = None;
+        self.basket.paths.clear();
+        self.set_scan_roots(Vec::new());
+        self.state.empty_dirs.clear();
+        self.state.duplicate_groups = groups;
+        self.separate_trashed();
+        self.sort_groups();
+        self.validate_results()
+    }
+    
+    /// Read the output of fdupes, jdupes, rdfind or rmlint
+    fn pick_external_results(&mut self) {
+        let Some(source) = rfd::FileDialog::new().pick_file() else {
+            return;
+        };
+        let text = match fs::read(&source) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => {
+                self.state.status_message = format!("⚠ Failed to read {}: {}", source.display(), e);
+                return;
+            }
+        };
+        let base = source.parent().map(Path::to_path_buf).unwrap_or_default();
+        match parse_external(&text, &base) {
+            Ok(results) => self.external_import = Some(ExternalImport { results, source, verify: true }),
+            Err(e) => self.state.status_message = format!("⚠ {}: {}", source.display(), e),
+        }
+    }
+    
+    fn show_external_import_window(&mut self, ctx: &egui::Context) {
+        let Some(import) = &mut self.external_import else {
+            return;
+        };
+        let mut open = true;
+        let mut confirmed = false;
+        egui::Window::new("📥 Import from Other Tools")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(format!("{} output: {}", import.results.tool.label(), import.source.display()));
+                ui.label(format!(
+                    "{} duplicate set(s), {} file(s)",
+                    format_count(import.results.groups.len() as u64),
+                    format_count(import.results.file_count() as u64)
+                ));
+                ui.add_space(5.0);
+                ui.checkbox(&mut import.verify, "🔍 Verify by hashing")
+                    .on_hover_text("Hash every listed file and group them by content, as a scan would. Without it the sets are taken as the tool reported them, split only where sizes differ.");
+                if !import.verify {
+                    ui.weak("Unverified groups carry no hash: until they are deleted, files changed since the tool ran look like true copies. Each file is compared with the kept copy just before it is deleted.");
+                }
+                ui.add_space(5.0);
+                if ui.add_enabled(!self.state.scanning, egui::Button::new("📥 Import")).clicked() {
+                    confirmed = true;
+                }
+            });
+        if confirmed {
+            let import = self.external_import.take().unwrap();
+            let label = format!("{} results {}", import.results.tool.label(), import.source.display());
+            if import.verify {
+                let mut paths: Vec<PathBuf> = import.results.groups.into_iter().flatten().collect();
+                paths.sort();
+                paths.dedup();
+                let count = paths.len();
+                // The tool already chose the files, so hidden ones are not left out
+                let config = ScanConfig { include_hidden: true, ..self.state.config.clone() };
+                self.scan_paths(paths, label, config, ctx);
+                self.state.status_message = format!("Verifying {} file(s) from {}", format_count(count as u64), import.source.display());
+            } else {
+                let (groups, missing) = unverified_groups(&import.results, &self.state.config);
+                let groups: Vec<DuplicateGroup> = groups.into_iter().map(DuplicateGroup::new).collect();
+                let validation = self.replace_with_imported(groups);
+                self.state.status_message =
+                    format!("Imported {} duplicate group(s) from {} without verifying. {}", self.state.duplicate_groups.len(), import.results.tool.label(), validation);
+                if missing > 0 {
+                    self.state.status_message.push_str(&format!(" {} listed file(s) no longer exist.", format_count(missing as u64)));
+                }
+            }
+        } else if !open {
+            self.external_import = None;
+        }
+    }
+}
+
+// Repaint interval while the window is in the background with reduced activity
+const BACKGROUND_REPAINT_INTERVAL: Duration = Duration::from_secs(1);
+/// The scan's result, or an internal error once its thread has ended without
+/// sending one, e.g. after a panic outside `catch_panic`
+fn receive_result<T>(rx: &Receiver<Result<T, ScanError>>) -> Option<Result<T, ScanError>> {
+    match rx.try_recv() {
+        Ok(result) => Some(result),
+        Err(std::sync::mpsc::TryRecvError::Empty) => None,
+        Err(std::sync::mpsc::TryRecvError::Disconnected) => Some(Err(ScanError::Internal("the scan thread ended without a result".to_string()))),
+    }
+}
+
+// Why files left alone by a deletion stopped for closing the window were skipped
+const STOPPED_FOR_QUIT: &str = "stopped because the app was closing";
+const OFFLINE_MESSAGE: &str = "⚠ Offline mode is on; turn it off in the Network settings to connect";
+// How often the battery state is read while a scan may pause on battery
+const POWER_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+// Time a running cleanup may spend deleting per frame, so its progress stays visible
+const CLEANUP_FRAME_BUDGET: Duration = Duration::from_millis(40);
+// Recently scanned folders offered under the 🕘 button next to the directory field
+const RECENT_DIRS: usize = 10;
+
+/// Progress callback that publishes the latest update and schedules a
+/// throttled repaint, so tiny files don't trigger one repaint each. The
+/// interval is re-read on every update so settings and focus changes apply
+/// to a scan already in progress.
+fn progress_reporter(
+    progress: Arc<Mutex<Option<ScanProgress>>>,
+    ctx: egui::Context,
+    interval_ms: Arc<AtomicU64>,
+) -> impl Fn(ScanProgress) + Send + Sync + 'static {
+    let last_repaint: Mutex<Option<Instant>> = Mutex::new(None);
+    move |p| {
+        *progress.lock().unwrap() = Some(p);
+        let interval = Duration::from_millis(interval_ms.load(Ordering::Relaxed));
+        let mut last = last_repaint.lock().unwrap();
+        if last.is_none_or(|t| t.elapsed() >= interval) {
+            *last = Some(Instant::now());
+            ctx.request_repaint();
+        } else {
+            // Make sure the final update of a burst still gets drawn
+            ctx.request_repaint_after(interval);
+        }
+    }
+}
+
+/// A spinner, or a static marker when animations are suspended in the background
+fn activity_spinner(ui: &mut egui::Ui, in_background: bool) {
+    if in_background {
+        ui.label("⏳");
+    } else {
+        ui.spinner();
+    }
+}
+
+/// A fixed-width, left-aligned cell so file rows line up as columns
+fn column_label(ui: &mut egui::Ui, width: f32, text: impl Into<egui::WidgetText>) -> egui::Response {
+    let height = ui.spacing().interact_size.y;
+    ui.allocate_ui_with_layout(egui::vec2(width, height), egui::Layout::left_to_right(egui::Align::Center), |ui| {
+        ui.set_min_width(width);
+        ui.add(egui::Label::new(text).truncate())
+    })
+    .inner
+}
+
+/// Shorten a path for display, keeping its end
+fn truncate_path(path: &str, max_chars: usize) -> String {
+    let count = path.chars().count();
+    if count <= max_chars {
+        return path.to_string();
+    }
+    let tail: String = path.chars().skip(count - (max_chars - 3)).collect();
+    format!("...{}", tail)
+}
+
+/// Copies marked Keep, which must still match the group hash after a deletion
+fn kept_copies(group: &DuplicateGroup) -> Vec<SurvivingFile> {
+    group.files.iter()
+        .zip(&group.selected)
+        .filter(|(_, &keep)| keep)
+        .map(|(file, _)| {
+            // Results stored before files carried their own hash fall back to the group's
+            let hash = if file.hash.is_empty() { &group.hash } else { &file.hash };
+            SurvivingFile { path: file.path.to_path_buf(), hash: hash.clone() }
+        })
+        .collect()
+}
+
+/// Outcome of deleting the unchecked files of one group
+#[derive(Default)]
+struct GroupDeletion {
+    deleted: Vec<DeletedFile>,
+    /// For each file of the group, whether it is no longer on disk, deleted now or already missing
+    gone: Vec<bool>,
+    errors: Vec<String>,
+    critical: Vec<String>,
+}
+
+impl GroupDeletion {
+    /// Take back a deletion the journal could not finish: the file was put
+    /// back, so it stays in the group, marked with the reason
+    fn undo(&mut self, group: &mut DuplicateGroup, path: &Path, reason: String) {
+        self.deleted.retain(|file| file.path != path);
+        if let Some(idx) = group.files.iter().position(|file| file.path.to_path_buf() == path) {
+            self.gone[idx] = false;
+            group.files[idx].delete_error = Some(reason.clone());
+        }
+        let error = format!("Failed to delete {}: {}", path.display(), reason);
+        log::warn!("{}", error);
+        self.errors.push(error);
+    }
+    
+    /// Log the deletions and drop the files that are gone from the group.
+    /// Returns how many left it.
+    fn settle(&self, group: &mut DuplicateGroup, log: &mut Vec<ActionRecord>) -> usize {
+        for file in &self.deleted {
+            log.push(ActionRecord::now("delete", file.path.display().to_string()));
+        }
+        let gone = self.gone.iter().filter(|&&gone| gone).count();
+        if gone > 0 {
+            let (files, selected) = group.files.drain(..)
+                .zip(group.selected.drain(..))
+                .zip(&self.gone)
+                .filter(|(_, &gone)| !gone)
+                .map(|(pair, _)| pair)
+                .unzip();
+            group.files = files;
+            group.selected = selected;
+        }
+        gone
+    }
+}
+
+/// Delete a group's unchecked files (only those that failed before when
+/// `only_failed`). Files that are gone afterwards leave the group; files that
+/// could not be deleted stay in it, marked with the reason.
+/// Indices of a group's shown files in the order they are listed
+fn file_rows(group: &DuplicateGroup, group_by_directory: bool) -> Vec<usize> {
+    let mut rows: Vec<usize> = (0..group.visible_files.min(group.files.len())).collect();
+    if group_by_directory {
+        rows.sort_by_key(|&idx| group.files[idx].path.parent());
+    }
+    rows
+}
+
+/// Write every error of a batch to the log; status lines only show the first few
+/// Status text for files that contradict their sidecar checksums, each logged
+fn sidecar_warning(mismatches: &[PathBuf]) -> String {
+    if mismatches.is_empty() {
+        return String::new();
+    }
+    for path in mismatches {
+        log::warn!("{} does not match its recorded checksum; it may be corrupted", path.display());
+    }
+    format!(" ⚠ {} file(s) do not match their sidecar checksums and may be corrupted; see the log.", format_count(mismatches.len() as u64))
+}
+
+fn log_errors(errors: &[String]) {
+    for e in errors {
+        log::warn!("{}", e);
+    }
+}
+
+/// `remove` deletes one file; journaled deletions pass one that sets it aside.
+/// The group is left as it is until the outcome is settled.
+fn delete_group_files(
+    group: &mut DuplicateGroup,
+    only_failed: bool,
+    remove: &mut dyn FnMut(&Path) -> std::io::Result<()>,
+) -> GroupDeletion {
+    let mut outcome = GroupDeletion { gone: vec![false; group.files.len()], ..GroupDeletion::default() };
+    for ((file, &keep), gone) in group.files.iter_mut().zip(&group.selected).zip(outcome.gone.iter_mut()) {
+        if keep || (only_failed && file.delete_error.is_none()) {
+            continue;
+        }
+        if file.is_critical {
+            outcome.critical.push(file.path.to_string());
+        }
+        if is_in_snapshot(&file.path.to_path_buf()) {
+            outcome.errors.push(format!("Skipped {}: inside a read-only snapshot", file.path));
+            continue;
+        }
+        // Remote files would look missing to the local checks below and silently leave the group
+        if is_remote(&file.path.to_path_buf()) {
+            outcome.errors.push(format!("Skipped {}: files on a remote store are only read", file.path));
+            continue;
+        }
+        match file.check_status() {
+            FileStatus::Unchanged => {}
+            // Already gone, nothing left to delete
+            FileStatus::Missing => {
+                *gone = true;
+                continue;
+            }
+            FileStatus::Changed => {
+                file.stale = true;
+                outcome.errors.push(format!("Skipped {}: changed since scan", file.path));
+                continue;
+            }
+        }
+        match remove(&file.path.to_path_buf()) {
+            Ok(_) => {
+                *gone = true;
+                outcome.deleted.push(DeletedFile { path: file.path.to_path_buf(), size: file.disk_size() });
+            }
+            Err(e) => {
+                outcome.errors.push(format!("Failed to delete {}: {}", file.path, e));
+                file.delete_error = Some(e.to_string());
+            }
+        }
+    }
+    log_errors(&outcome.errors);
+    outcome
+}
+
+/// What deleting a group's unchecked files is about to remove, for the journal
+fn planned_group(group: &DuplicateGroup, only_failed: bool) -> PlannedGroup {
+    PlannedGroup {
+        kept: kept_copies(group).into_iter().map(|kept| kept.path).collect(),
+        files: group.files.iter()
+            .zip(&group.selected)
+            .filter(|(file, &keep)| !keep && (!only_failed || file.delete_error.is_some()))
+            .map(|(file, _)| PlannedFile { path: file.path.to_path_buf(), size: file.size, modified: file.modified_time })
+            .filter(|file| !is_in_snapshot(&file.path) && !is_remote(&file.path))
+            .collect(),
+    }
+}
+
+/// Number of files a deletion of this group would remove
+fn deletion_targets(group: &DuplicateGroup, only_failed: bool) -> usize {
+    group.files.iter()
+        .zip(&group.selected)
+        .filter(|(file, &keep)| !keep && (!only_failed || file.delete_error.is_some()))
+        .count()
+}
+
+/// Nothing in the group is kept, so deleting would leave no copy of its
+/// content. Copies in a snapshot go when the snapshot is rotated, so they don't count.
+fn deletes_every_copy(group: &DuplicateGroup, only_failed: bool) -> bool {
+    !group.files.iter().zip(&group.selected).any(|(file, &keep)| keep && !is_in_snapshot(&file.path.to_path_buf()))
+        && deletion_targets(group, only_failed) > 0
+}
+
+/// The unchecked remote files for which `in_store` holds. Only groups that
+/// keep a copy take part, so no content loses its last copy.
+fn plan_remote_targets(groups: &[DuplicateGroup], in_store: impl Fn(&Path) -> bool) -> RemotePlan {
+    let mut plan = RemotePlan::default();
+    for group in groups {
+        let targets: Vec<&FileInfo> = group.files.iter()
+            .zip(&group.selected)
+            .filter(|(file, &keep)| !keep && in_store(&file.path.to_path_buf()))
+            .map(|(file, _)| file)
+            .collect();
+        if targets.is_empty() {
+            continue;
+        }
+        // A local copy is preferred, as it is the cheapest to check again before acting
+        let mut kept: Vec<&FileInfo> = group.files.iter().zip(&group.selected).filter(|(_, &keep)| keep).map(|(file, _)| file).collect();
+        kept.sort_by_key(|file| is_remote(&file.path.to_path_buf()));
+        let Some(kept) = kept.first() else {
+            plan.unkept += targets.len();
+            continue;
+        };
+        plan.targets.extend(targets.into_iter().map(|file| RemoteTarget {
+            path: file.path.to_path_buf(),
+            size: file.size,
+            kept: kept.path.to_path_buf(),
+            kept_hash: kept.hash.clone(),
+        }));
+    }
+    plan
+}
+
+/// Summary and file list of a remote plan under the name of its action
+fn remote_plan_ui(ui: &mut egui::Ui, palette: &Palette, action: &str, plan: &RemotePlan) {
+    let size: u64 = plan.targets.iter().map(|target| target.size).sum();
+    ui.label(format!("{}: {} file(s), {}", action, plan.targets.len(), format_size(size)));
+    egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+        for target in &plan.targets {
+            ui.label(format!("{}  (kept: {})", target.path.display(), target.kept.display()));
+        }
+    });
+    if plan.unkept > 0 {
+        ui.colored_label(palette.warning, format!("⚠ {} file(s) left out: nothing in their group is kept", plan.unkept));
+    }
+}
+
+/// List the connected stores whose prefix starts with `scheme`, each with a
+/// Disconnect button; returns the prefix of the one to disconnect
+fn connected_remotes_ui(ui: &mut egui::Ui, sources: &MountedSources, scheme: &str, heading: &str, scanning: bool) -> Option<PathBuf> {
+    let mut disconnect = None;
+    let remotes: Vec<_> = sources.remotes().iter().filter(|remote| remote.prefix().to_string_lossy().starts_with(scheme)).collect();
+    if remotes.is_empty() {
+        return None;
+    }
+    ui.separator();
+    ui.label(heading);
+    for remote in remotes {
+        ui.horizontal(|ui| {
+            ui.label(remote.label());
+            if ui.add_enabled(!scanning, egui::Button::new("Disconnect").small())
+                .on_hover_text("Close the connection and remove its roots")
+                .clicked()
+            {
+                disconnect = Some(remote.prefix().to_path_buf());
+            }
+        });
+    }
+    disconnect
+}
+
+/// Refuse to delete from a group whose kept copies have all vanished or changed.
+/// `keep` marks the files that stay, e.g. the group's selection.
+fn verify_kept_copy(group: &DuplicateGroup, keep: &[bool], config: &ScanConfig) -> Result<(), String> {
+    let mut kept = group.files.iter()
+        .zip(keep)
+        .filter(|(_, &keep)| keep)
+        .map(|(file, _)| file)
+        .peekable();
+    if kept.peek().is_none() {
+        return Ok(());
+    }
+    let Some(present) = kept.find(|file| file.check_status() == FileStatus::Unchanged) else {
+        return Err("no kept copy is still present and unchanged, skipping deletion".to_string());
+    };
+    // Groups taken unverified from another tool carry no hash, and size and
+    // time say nothing about content: compare it now, before anything goes
+    if group.hash.is_empty() {
+        let kept_digest = hash_file(&present.path.to_path_buf(), config)
+            .map_err(|e| format!("cannot read the kept copy to confirm the match, skipping deletion: {}", e))?;
+        for (file, _) in group.files.iter().zip(keep).filter(|(_, &keep)| !keep) {
+            let path = file.path.to_path_buf();
+            match hash_file(&path, config) {
+                Ok(digest) if digest == kept_digest => {}
+                Ok(_) => return Err(format!("{} does not match the kept copy, skipping deletion", path.display())),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(format!("cannot read {} to confirm the match, skipping deletion: {}", path.display(), e)),
+            }
+        }
+    }
+    Ok(())
+}
+
+impl eframe::App for DupeFinderApp {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.in_background = self.state.activity.reduce_in_background
+            && ctx.input(|i| i.viewport().minimized == Some(true) || i.viewport().focused == Some(false));
+        let repaint_interval = if self.in_background {
+            BACKGROUND_REPAINT_INTERVAL
+        } else {
+            Duration::from_secs(1) / self.state.activity.max_repaints_per_second.max(1)
+        };
+        self.repaint_interval.store(repaint_interval.as_millis() as u64, Ordering::Relaxed);
+        
+        if self.applied_high_contrast != Some(self.state.high_contrast) {
+            theme::apply_visuals(ctx, self.state.high_contrast, &mut self.visuals_before_contrast);
+            self.applied_high_contrast = Some(self.state.high_contrast);
+        }
+        if self.applied_formatting.as_ref() != Some(&self.state.formatting) {
+            format::apply_settings(&self.state.formatting);
+            self.applied_formatting = Some(self.state.formatting.clone());
+        }
+        let palette = Palette::new(self.state.high_contrast);
+        
+        if let Some(verification) = &self.verification {
+            if let Ok(hashes) = verification.receiver.try_recv() {
+                let verification = self.verification.take().unwrap();
+                self.finish_group_verification(&verification.paths, hashes);
+            }
+        }
+        
+        while let Ok(report) = self.post_delete.receiver.try_recv() {
+            self.finish_post_delete_check(report);
+        }
+        
+        if let Some(loaded) = self.known_lists.receiver.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.finish_known_sets(loaded);
+        }
+        
+        if let Some(rx) = &self.cache_check_receiver {
+            match rx.try_recv() {
+                Ok(check) => {
+                    self.cache_check_receiver = None;
+                    self.finish_cache_check(check);
+                }
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => self.cache_check_receiver = None,
+                Err(std::sync::mpsc::TryRecvError::Empty) => {}
+            }
+        } else if !self.state.scanning && self.cache_check.due() && self.hash_cache.len() > 0 {
+            // Runs while no scan competes for the disk
+            self.start_cache_check();
+        }
+        
+        if let Some(result) = self.update_check.receiver.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.finish_update_check(result);
+        }
+        if let Some(result) = self.sftp_dialog.receiver.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.finish_sftp_connection(result);
+        }
+        if let Some(result) = self.s3_dialog.receiver.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.finish_s3_connection(result);
+        }
+        if let Some(results) = self.s3_dialog.action_receiver.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.finish_s3_action(results);
+        }
+        if let Some(result) = self.drive_dialog.receiver.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.finish_drive_sign_in(result);
+        }
+        if let Some(results) = self.drive_dialog.action_receiver.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.finish_drive_trash(results);
+        }
+        if let Some(result) = self.device_dialog.devices_receiver.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.finish_device_list(result);
+        }
+        if let Some(result) = self.device_dialog.receiver.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.finish_device_connection(result);
+        }
+        if let Some(results) = self.device_dialog.action_receiver.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.finish_device_delete(results);
+        }
+        
+        if let Some(result) = self.manifest_check.receiver.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.manifest_check.receiver = None;
+            match result {
+                Ok(check) => {
+                    self.state.status_message = format!("Checksum verification: {}", check.summary());
+                    self.manifest_check.report = Some(check);
+                }
+                Err(e) => self.state.status_message = format!("⚠ Checksum verification failed: {}", e),
+            }
+        }
+        
+        if self.file_preview.pending && !self.in_background {
+            self.start_preview_load(ctx);
+        }
+        if let Some(mut preview) = self.file_preview.receiver.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            if let Preview::Pdf { rendered: Some(page), .. } = &mut preview {
+                let page = std::mem::take(page);
+                self.file_preview.page_texture = Some(ctx.load_texture("pdf_first_page", page, egui::TextureOptions::LINEAR));
+            }
+            self.file_preview.preview = Some(preview);
+            self.file_preview.receiver = None;
+        }
+        
+        if let Some(rx) = &self.near_receiver {
+            match rx.try_recv() {
+                Ok(Ok(groups)) => {
+                    self.near_receiver = None;
+                    self.state.near_groups = groups;
+                }
+                Ok(Err(e)) => {
+                    self.near_receiver = None;
+                    self.state.status_message = format!("Near-duplicate search error: {}", e);
+                    log::error!("Near-duplicate search failed: {}", e);
+                    if let ScanError::Internal(message) = e {
+                        self.report_crash("Near-duplicate search", message);
+                    }
+                }
+                // The scan failed before comparing sources
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => self.near_receiver = None,
+                Err(std::sync::mpsc::TryRecvError::Empty) => {}
+            }
+        }
+        
+        if let Some(rx) = &self.chunk_receiver {
+            match rx.try_recv() {
+                Ok(Ok(pairs)) => {
+                    self.chunk_receiver = None;
+                    self.state.chunk_pairs = pairs;
+                }
+                Ok(Err(e)) => {
+                    self.chunk_receiver = None;
+                    self.state.status_message = format!("Chunk comparison error: {}", e);
+                    log::error!("Chunk comparison failed: {}", e);
+                    if let ScanError::Internal(message) = e {
+                        self.report_crash("Chunk comparison", message);
+                    }
+                }
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => self.chunk_receiver = None,
+                Err(std::sync::mpsc::TryRecvError::Empty) => {}
+            }
+        }
+        
+        self.advance_cleanup_run(ctx);
+        self.manage_power(ctx);
+        self.guard_close(ctx);
+        
+        // Check for scan results
+        if let Some(rx) = &self.result_receiver {
+            if let Some(result) = receive_result(rx) {
+                self.save_hash_cache();
+                match result {
+                    Ok(outcome) if self.rescanning => {
+                        self.state.scanning = false;
+                        self.rescanning = false;
+                        self.result_receiver = None;
+                        self.apply_tuning(outcome.tuning);
+                        log::info!("Rescan finished with {} group(s)", outcome.groups.len());
+                        self.apply_rescan_results(outcome.groups);
+                        self.state.status_message.push_str(&sidecar_warning(&outcome.sidecar_mismatches));
+                    }
+                    Ok(outcome) => {
+                        self.basket.paths.clear();
+                        let files_scanned: usize = outcome.roots.iter().filter(|root| root.covered_by.is_none()).map(|root| root.files_scanned).sum();
+                        self.apply_tuning(outcome.tuning);
+                        self.state.symlinks = outcome.symlinks;
+                        self.set_scan_roots(outcome.roots);
+                        self.state.empty_dirs = outcome.empty_dirs;
+                        self.state.unique_files = outcome.unique_files;
+                        self.state.duplicate_groups = outcome.groups.into_iter()
+                            .map(DuplicateGroup::new)
+                            .collect();
+                        self.separate_trashed();
+                        self.sort_groups();
+                        self.state.scanning = false;
+                        self.result_receiver = None;
+                        self.calculate_savings();
+                        // File lists and selections are not walked, so only their duplicates are counted
+                        let files_scanned = if files_scanned > 0 {
+                            files_scanned
+                        } else {
+                            self.state.duplicate_groups.iter().map(|group| group.files.len()).sum()
+                        };
+                        self.usage.record_scan(files_scanned as u64, self.state.duplicate_groups.len() as u64, self.state.total_size_savings);
+                        if outcome.partial {
+                            log::info!("Scan reached its time limit; keeping the partial result");
+                            self.partial_scan = self.last_scan.take();
+                            self.scan_label = self.scan_label.take().map(|label| format!("{} (partial)", label));
+                        }
+                        self.save_usage();
+                        log::info!(
+                            "Scan finished: {} group(s), {} known excluded, {} known flagged",
+                            self.state.duplicate_groups.len(),
+                            outcome.known.excluded,
+                            outcome.known.flagged
+                        );
+                        
+                        if self.state.duplicate_groups.is_empty() {
+                            self.state.status_message = "No duplicates found.".to_string();
+                        } else {
+                            self.state.status_message = format!("Found {} duplicate group(s)!", format_count(self.state.duplicate_groups.len() as u64));
+                        }
+                        if outcome.partial {
+                            self.state.status_message = format!(
+                                "⏱ Time limit reached: partial result with {} group(s) confirmed so far.",
+                                format_count(self.state.duplicate_groups.len() as u64)
+                            );
+                        }
+                        if outcome.known.excluded > 0 || outcome.known.flagged > 0 {
+                            self.state.status_message.push_str(&format!(
+                                " Known files: {} group(s) excluded, {} flagged.",
+                                outcome.known.excluded, outcome.known.flagged
+                            ));
+                        }
+                        self.state.status_message.push_str(&sidecar_warning(&outcome.sidecar_mismatches));
+                        if !self.state.unique_files.is_empty() {
+                            let unique_size: u64 = self.state.unique_files.iter().map(|file| file.size).sum();
+                            self.state.status_message.push_str(&format!(
+                                " {} file(s) ({}) have no other copy.",
+                                format_count(self.state.unique_files.len() as u64),
+                                format_size(unique_size)
+                            ));
+                            self.results_view = ResultsView::Unique;
+                        }
+                        self.record_scan(None);
+                        if self.ui_mode == UiMode::Simple && !self.running_queue && !self.state.duplicate_groups.is_empty() {
+                            self.open_cleanup_wizard();
+                        }
+                    }
+                    Err(ScanError::Cancelled) => {
+                        self.state.scanning = false;
+                        self.rescanning = false;
+                        self.result_receiver = None;
+                        self.state.status_message = "Scan cancelled".to_string();
+                        log::info!("Scan cancelled");
+                    }
+                    Err(e) => {
+                        self.state.scanning = false;
+                        self.rescanning = false;
+                        self.result_receiver = None;
+                        self.state.status_message = format!("Scan error: {}", e);
+                        log::error!("Scan failed: {}", e);
+                        self.record_scan(Some(e.to_string()));
+                        if let ScanError::Internal(message) = e {
+                            self.report_crash("Scan", message);
+                        }
+                    }
+                }
+            }
+        }
+        
+        // Near-duplicate results belong to the finished scan, so the next one waits for them
+        if self.running_queue && !self.state.scanning && self.near_receiver.is_none() && self.chunk_receiver.is_none() {
+            match self.state.scan_queue.pop_front() {
+                Some(job) => self.run_scan_job(job, ctx),
+                None => {
+                    self.running_queue = false;
+                    self.state.status_message = format!("✓ Scan queue finished; {} scan(s) in Scan history", self.history.records.len());
+                }
+            }
+        }
+        
+        self.handle_shortcuts(ctx);
+        
+        self.show_basket_panel(ctx);
+        
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading("🔍 DupeFinder - Rust Duplicate File Finder");
+                if ui.add_enabled(!self.tour.is_active(), egui::Button::new("❓ Tour"))
+                    .on_hover_text("Walk through choosing a folder, preview mode, selection strategies and deletion safety")
+                    .clicked()
+                {
+                    self.tour.start();
+                }
+                if ui.button("⌨ Commands")
+                    .on_hover_text(self.state.shortcuts.hint(ctx, ShortcutAction::CommandPalette, "Search every action by name and run it from the keyboard"))
+                    .clicked()
+                {
+                    self.command_palette.toggle();
+                }
+                if ui.button("📊 Insights").on_hover_text("Lifetime scans, files deleted and space reclaimed on this computer").clicked() {
+                    self.insights_open = !self.insights_open;
+                }
+                ui.separator();
+                let mut mode = self.ui_mode;
+                for option in [UiMode::Simple, UiMode::Advanced] {
+                    ui.selectable_value(&mut mode, option, option.label());
+                }
+                if mode != self.ui_mode {
+                    self.set_ui_mode(mode);
+                }
+            }).response.on_hover_text("Simple shows a folder, the scan button and a guided cleanup; advanced shows every setting, tool and bulk action. Settings chosen in advanced mode still apply in simple mode.");
+            let advanced = self.ui_mode == UiMode::Advanced;
+            self.show_update_banner(ui, &palette);
+            ui.add_space(10.0);
+            
+            // Directory selection
+            let directory_row = ui.horizontal(|ui| {
+                ui.label("Directory:");
+                let dir_edit = ui.add(egui::TextEdit::singleline(&mut self.state.selected_dir).desired_width(500.0))
+                    .on_hover_text("A path, a quoted path from a shell, ~/folder or a file:// URL from a file manager");
+                accessible_label(&dir_edit, egui::WidgetType::TextEdit, "Directory to scan");
+                // Pasted text is cleaned up at once; typed text once the field is left
+                let pasted = dir_edit.changed() && ui.input(|i| i.events.iter().any(|event| matches!(event, egui::Event::Paste(_))));
+                if pasted || dir_edit.lost_focus() {
+                    let normalized = normalize_dir_input(&self.state.selected_dir);
+                    if normalized != self.state.selected_dir {
+                        self.state.selected_dir = normalized;
+                    }
+                }
+                
+                let recent_button = ui.button("🕘 ▾").on_hover_text("Pinned and recently scanned folders");
+                accessible_label(&recent_button, egui::WidgetType::Button, "Pinned and recently scanned folders");
+                let popup_id = ui.make_persistent_id("recent_dirs");
+                if recent_button.clicked() {
+                    ui.memory_mut(|memory| memory.toggle_popup(popup_id));
+                }
+                let mut chosen = None;
+                let mut toggled = None;
+                egui::popup_below_widget(ui, popup_id, &recent_button, egui::PopupCloseBehavior::CloseOnClickOutside, |ui| {
+                    ui.set_min_width(300.0);
+                    let recent: Vec<String> = self.history
+                        .recent_dirs(RECENT_DIRS)
+                        .into_iter()
+                        .filter(|dir| !self.favorites.is_pinned(dir))
+                        .collect();
+                    let mut dir_row = |ui: &mut egui::Ui, dir: &String, pinned: bool| {
+                        ui.horizontal(|ui| {
+                            let pin = ui.small_button(if pinned { "✖" } else { "📌" })
+                                .on_hover_text(if pinned { "Unpin this folder" } else { "Pin this folder" });
+                            if pin.clicked() {
+                                toggled = Some(dir.clone());
+                            }
+                            let item = ui.add_enabled(Path::new(dir).is_dir(), egui::Button::new(dir.as_str()).frame(false))
+                                .on_disabled_hover_text("This folder does not exist or is not reachable");
+                            if item.clicked() {
+                                chosen = Some(dir.clone());
+                            }
+                        });
+                    };
+                    ui.strong("📌 Pinned");
+                    if self.favorites.pinned.is_empty() {
+                        ui.weak("Pin a folder to keep it here");
+                    }
+                    for dir in &self.favorites.pinned {
+                        dir_row(ui, dir, true);
+                    }
+                    ui.separator();
+                    ui.strong("🕘 Recent");
+                    if recent.is_empty() {
+                        ui.weak("Folders you scan are listed here");
+                    }
+                    for dir in &recent {
+                        dir_row(ui, dir, false);
+                    }
+                    let current = self.state.selected_dir.trim();
+                    if !current.is_empty() && !self.favorites.is_pinned(current) {
+                        ui.separator();
+                        if ui.button("📌 Pin the current folder").clicked() {
+                            toggled = Some(current.to_string());
+                        }
+                    }
+                });
+                if let Some(dir) = chosen {
+                    self.state.selected_dir = dir;
+                    ui.memory_mut(|memory| memory.close_popup());
+                }
+                if let Some(dir) = toggled {
+                    self.favorites.toggle(&dir);
+                    if let Err(e) = self.favorites.save() {
+                        log::error!("Could not save the pinned folders: {}", e);
+                    }
+                }
+                
+                let browse = ui.button("📁 Browse");
+                accessible_label(&browse, egui::WidgetType::Button, "Browse for directory to scan");
+                if browse.clicked() {
+                    if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                        self.state.selected_dir = path.display().to_string();
+                    }
+                }
+                
+                if advanced {
+                    let add_root = ui.add_enabled(!self.state.scan_whole_drive, egui::Button::new("➕ Add Root"))
+                        .on_hover_text("Scan another folder together with this one, e.g. a second drive or a backup, so duplicates across them are found");
+                    accessible_label(&add_root, egui::WidgetType::Button, "Add another folder to scan");
+                    if add_root.clicked() {
+                        self.pick_scan_root();
+                    }
+                    
+                    let add_sftp = ui.add_enabled(!self.state.scan_whole_drive, egui::Button::new("🌐 Add SFTP Root…"))
+                        .on_hover_text("Scan a folder on a server over SSH/SFTP together with the local roots, without mounting it");
+                    accessible_label(&add_sftp, egui::WidgetType::Button, "Add a folder on an SFTP server to scan");
+                    if add_sftp.clicked() {
+                        self.sftp_dialog.open = true;
+                    }
+                    
+                    let add_s3 = ui.add_enabled(!self.state.scan_whole_drive, egui::Button::new("🪣 Add S3 Bucket…"))
+                        .on_hover_text("Scan a bucket on S3 or an S3-compatible service, alone or together with local folders");
+                    accessible_label(&add_s3, egui::WidgetType::Button, "Add an S3 bucket to scan");
+                    if add_s3.clicked() {
+                        self.s3_dialog.open = true;
+                    }
+                    
+                    let add_drive = ui.add_enabled(!self.state.scan_whole_drive, egui::Button::new("☁ Add Google Drive…"))
+                        .on_hover_text("Scan My Drive, alone or together with local folders, comparing files by Drive's checksums");
+                    accessible_label(&add_drive, egui::WidgetType::Button, "Add a Google Drive folder to scan");
+                    if add_drive.clicked() {
+                        self.drive_dialog.open = true;
+                    }
+                    
+                    let add_device = ui.add_enabled(!self.state.scan_whole_drive, egui::Button::new("📱 Add Android Device…"))
+                        .on_hover_text("Scan a folder on a phone or tablet connected by USB, through adb");
+                    accessible_label(&add_device, egui::WidgetType::Button, "Add a folder on an Android device to scan");
+                    if add_device.clicked() {
+                        self.open_device_window(ui.ctx());
+                    }
+                }
+            });
+            let mut removed_root = None;
+            for (idx, dir) in self.state.extra_dirs.iter_mut().enumerate() {
+                ui.add_enabled_ui(!self.state.scan_whole_drive, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Root {}:", idx + 2));
+                        let edit = ui.add(egui::TextEdit::singleline(dir).desired_width(500.0));
+                        accessible_label(&edit, egui::WidgetType::TextEdit, format!("Additional root {} to scan", idx + 2));
+                        let remove = ui.small_button("✖").on_hover_text("Don't scan this folder");
+                        accessible_label(&remove, egui::WidgetType::Button, format!("Remove root {}", idx + 2));
+                        if remove.clicked() {
+                            removed_root = Some(idx);
+                        }
+                    });
+                });
+            }
+            if let Some(idx) = removed_root {
+                self.state.extra_dirs.remove(idx);
+            }
+            if !self.state.extra_dirs.is_empty() {
+                ui.add_enabled(
+                    !self.state.scan_whole_drive,
+                    egui::Checkbox::new(&mut self.state.config.cross_root_only, "🔀 Only report duplicates spanning two or more roots"),
+                )
+                .on_hover_text("Leave out groups whose copies all lie under the same root, e.g. to compare a reference folder with its backup");
+            }
+            self.tour.anchor(TourTarget::Directory, &directory_row.response);
+            
+            ui.add_space(10.0);
+            
+            // Configuration and controls
+            ui.horizontal(|ui| {
+                let mut safe_mode = self.state.safe_mode;
+                let toggle = ui.checkbox(&mut safe_mode, egui::RichText::new("🛡 Safe mode — nothing is deleted, moved or overwritten").strong())
+                    .on_hover_text("While on, every destructive button only previews what it would do, whatever the preview mode checkbox says. New installations start in safe mode; turning it off is remembered for later sessions.");
+                accessible_checkbox(&toggle, safe_mode, "Safe mode");
+                if toggle.changed() {
+                    self.set_safe_mode(safe_mode);
+                }
+                if self.state.safe_mode {
+                    ui.colored_label(palette.preview, "Turn off to let actions change files on disk");
+                } else {
+                    ui.colored_label(palette.caution, "Off: actions change files on disk unless preview mode is on");
+                }
+            });
+            
+            if advanced {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.state.config.include_hidden, "Include hidden files")
+                        .on_hover_text("Also scan dot-files and folders, and files marked hidden on Windows");
+                    let preview = if self.state.safe_mode {
+                        let mut forced = true;
+                        ui.add_enabled(false, egui::Checkbox::new(&mut forced, "Preview mode (no actual deletion)"))
+                            .on_disabled_hover_text("Safe mode is on, so every action previews; turn safe mode off below to change this")
+                    } else {
+                        ui.checkbox(&mut self.state.preview_mode, "Preview mode (no actual deletion)")
+                            .on_hover_text("Deletes, moves and overwrites only report what they would do; nothing on disk changes")
+                    };
+                    self.tour.anchor(TourTarget::PreviewMode, &preview);
+                    let last_copy = ui.checkbox(&mut self.state.allow_deleting_last_copy, "Allow deleting the last copy")
+                        .on_hover_text("Let a group with every file unchecked be deleted entirely, after a separate confirmation. When off, such groups are never deleted.");
+                    self.tour.anchor(TourTarget::LastCopyGuard, &last_copy);
+                    ui.checkbox(&mut self.state.remove_emptied_dirs, "Remove emptied folders")
+                        .on_hover_text("After deleting or moving files, remove folders left empty, up to the scanned folder (never the scanned folder itself or protected system paths). Each removal is logged.");
+                    ui.checkbox(&mut self.state.prefer_rich_metadata, "Keep richest metadata")
+                        .on_hover_text("When copies differ in permissions or extended attributes, the Keep buttons keep the copy that is executable or carries the most attributes instead of the one they would pick");
+                    ui.checkbox(&mut self.state.offer_canonical_renames, "Offer to rename kept copies")
+                        .on_hover_text("After a deletion, list kept files named like \"IMG_1234 (2).jpg\" or \"Copy of report.pdf\" and offer to rename them to their plain names, with a preview first");
+                    ui.checkbox(&mut self.state.high_contrast, "High contrast")
+                        .on_hover_text("Black background, white text and saturated status colors");
+                    ui.checkbox(&mut self.state.activity.reduce_in_background, "Reduce activity in background")
+                        .on_hover_text("While the window is unfocused or minimized, redraw about once a second and pause animations");
+                    ui.add(egui::Slider::new(&mut self.state.activity.max_repaints_per_second, 1..=60)
+                        .text("max redraws/s"))
+                        .on_hover_text("How often scan progress may redraw the window");
+                    ui.checkbox(&mut self.state.config.detect_file_types, "Detect file types")
+                        .on_hover_text("Identify duplicate content by its magic bytes rather than the file extension");
+                    ui.checkbox(&mut self.state.config.dual_hash, "Dual hash (SHA-256 + SHA-512)")
+                        .on_hover_text("Group files only when both digests match, and record both in exports and reports");
+                    
+                    ui.add_enabled_ui(!self.state.config.auto_tune, |ui| {
+                        egui::ComboBox::from_id_salt("buffer_mode")
+                            .selected_text(if self.state.config.auto_buffer { "Buffer: auto (recommended)" } else { "Buffer: fixed" })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.state.config.auto_buffer, true, "Auto (recommended)")
+                                    .on_hover_text("Read small files whole and give large files larger buffers, up to 4 MiB");
+                                ui.selectable_value(&mut self.state.config.auto_buffer, false, "Fixed size")
+                                    .on_hover_text("Use the same buffer for every file");
+                            });
+                        if !self.state.config.auto_buffer {
+                            ui.add(egui::Slider::new(&mut self.state.config.buffer_size, 1024..=MAX_AUTO_BUFFER)
+                                .logarithmic(true)
+                                .text("Buffer size"))
+                                .on_hover_text("Bytes read at a time while hashing; larger buffers help on fast disks");
+                        }
+                    });
+                });
+                
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut self.state.scan_whole_drive, "🖴 Scan entire drive/volume")
+                        .on_hover_text("Scan from the root of the volume containing the selected directory")
+                        .changed()
+                        && self.state.scan_whole_drive
+                    {
+                        self.state.config.same_filesystem_only = true;
+                    }
+                    ui.checkbox(&mut self.state.config.same_filesystem_only, "Stay on one filesystem")
+                        .on_hover_text("Don't cross into other mounted volumes or network shares");
+                    ui.checkbox(&mut self.state.config.skip_pseudo_filesystems, "Skip system pseudo-filesystems")
+                        .on_hover_text("Skip /proc, /sys, /dev and similar virtual filesystems");
+                    ui.checkbox(&mut self.state.config.exclude_snapshots, "📸 Skip snapshots")
+                        .on_hover_text("Don't descend into Btrfs / ZFS snapshot folders (.snapshots, .zfs, @snapshots, timeshift-btrfs, .snapshot), which hold a read-only copy of every file");
+                    ui.checkbox(&mut self.state.config.find_empty_dirs, "📭 Find empty folders")
+                        .on_hover_text("Also list folders that hold nothing but empty folders, so they can be removed along with the duplicates");
+                    ui.checkbox(&mut self.state.config.separate_projects, "📦 Keep projects apart")
+                        .on_hover_text("Never group copies that lie in two different projects (checkouts with .git, or folders with Cargo.toml or package.json); copies shared between checkouts are usually intentional. Copies within one project are still reported.");
+                    ui.checkbox(&mut self.state.config.report_unique, "☝ Uniqueness report")
+                        .on_hover_text("Also list the files that exist only once across all roots, i.e. with no backup copy, e.g. before retiring a disk. They get their own tab next to the duplicates.");
+                    ui.label("Trash:").on_hover_text("Trash / recycle bin folders; most are hidden, so include hidden files to scan them");
+                    egui::ComboBox::from_id_salt("trash_mode")
+                        .selected_text(self.state.config.trash_mode.label())
+                        .show_ui(ui, |ui| {
+                            for mode in [TrashMode::Separate, TrashMode::Exclude, TrashMode::Include] {
+                                ui.selectable_value(&mut self.state.config.trash_mode, mode, mode.label());
+                            }
+                        });
+                    ui.label("Sparse files:").on_hover_text("Disk images and VM files with large unallocated regions; they hash slowly for the space they use");
+                    egui::ComboBox::from_id_salt("sparse_mode")
+                        .selected_text(self.state.config.sparse_files.label())
+                        .show_ui(ui, |ui| {
+                            for mode in [SparseMode::Demote, SparseMode::Skip, SparseMode::Include] {
+                                ui.selectable_value(&mut self.state.config.sparse_files, mode, mode.label());
+                            }
+                        });
+                });
+                
+                ui.horizontal(|ui| {
+                    let formatting = &mut self.state.formatting;
+                    ui.label("Sizes:").on_hover_text("Binary units (KiB, MiB) count in 1024s, decimal units (KB, MB) in 1000s");
+                    egui::ComboBox::from_id_salt("size_units")
+                        .selected_text(formatting.units.label())
+                        .show_ui(ui, |ui| {
+                            for units in [SizeUnits::Binary, SizeUnits::Decimal] {
+                                ui.selectable_value(&mut formatting.units, units, units.label());
+                            }
+                        });
+                    ui.label("Dates:").on_hover_text("How modification dates are shown in the results, exports and reports");
+                    egui::ComboBox::from_id_salt("date_style")
+                        .selected_text(formatting.dates.label())
+                        .show_ui(ui, |ui| {
+                            for style in [DateStyle::Relative, DateStyle::Absolute, DateStyle::Both] {
+                                ui.selectable_value(&mut formatting.dates, style, style.label());
+                            }
+                        });
+                    ui.label("Locale:");
+                    let locale = ui.add(egui::TextEdit::singleline(&mut formatting.locale).hint_text("system").desired_width(80.0))
+                        .on_hover_text("e.g. en_US, de_DE or fr_FR; leave empty to follow LANG / LC_ALL");
+                    accessible_label(&locale, egui::WidgetType::TextEdit, "Locale for numbers and dates");
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.state.config.near_duplicate_sources, "≈ Find near-duplicate source code")
+                        .on_hover_text("Also group source files that match after ignoring whitespace and comments (copy-pasted or vendored code)");
+                    if self.state.config.near_duplicate_sources {
+                        ui.add(egui::Slider::new(&mut self.state.config.near_duplicate_threshold, 0.5..=1.0)
+                            .text("min similarity"))
+                            .on_hover_text("Share of normalized lines two sources must have in common to be grouped");
+                    }
+                });
+                
+                ui.horizontal(|ui| {
+                    let config = &mut self.state.config;
+                    ui.checkbox(&mut config.chunk_similarity, "🧩 Find partly identical large files (experimental)")
+                        .on_hover_text("Split large files into content-defined chunks (FastCDC) and report pairs sharing many of them, e.g. VM images or database dumps. Reads every large file in full.");
+                    if config.chunk_similarity {
+                        let mut min_mb = config.chunk_similarity_min_size / (1024 * 1024);
+                        if ui.add(egui::DragValue::new(&mut min_mb).range(1..=1_000_000).prefix("files ≥ ").suffix(" MiB"))
+                            .on_hover_text("Smaller files are not compared by chunks")
+                            .changed()
+                        {
+                            config.chunk_similarity_min_size = min_mb * 1024 * 1024;
+                        }
+                        ui.add(egui::Slider::new(&mut config.chunk_similarity_threshold, 0.1..=1.0)
+                            .text("min identical"))
+                            .on_hover_text("Share of the larger file that must also appear in the other one");
+                    }
+                });
+                
+                egui::CollapsingHeader::new("🔒 Sensitive file patterns")
+                    .id_salt("sensitive_patterns")
+                    .open((self.reveal_settings == Some(Command::OpenSensitivePatterns)).then_some(true))
+                    .show(ui, |ui| {
+                        ui.label("One pattern per line; * and ? wildcards, matched case-insensitively against file names.");
+                        let mut text = self.state.config.sensitive_patterns.join("\n");
+                        let edit = ui.add(egui::TextEdit::multiline(&mut text).desired_rows(4).desired_width(300.0));
+                        accessible_label(&edit, egui::WidgetType::TextEdit, "Sensitive file patterns, one per line");
+                        if edit.changed() {
+                            self.state.config.sensitive_patterns = text.lines().map(str::to_string).collect();
+                        }
+                    });
+                
+                egui::CollapsingHeader::new("✂ Junk copy patterns")
+                    .id_salt("junk_copy_patterns")
+                    .open((self.reveal_settings == Some(Command::OpenJunkCopyPatterns)).then_some(true))
+                    .show(ui, |ui| {
+                        ui.label("File names matching these are marked [COPY] and unchecked by \"Uncheck Pattern-Matched Copies\".");
+                        let mut text = self.state.config.junk_copy_patterns.join("\n");
+                        let edit = ui.add(egui::TextEdit::multiline(&mut text).desired_rows(4).desired_width(300.0));
+                        accessible_label(&edit, egui::WidgetType::TextEdit, "Junk copy patterns, one per line");
+                        if edit.changed() {
+                            self.state.config.junk_copy_patterns = text.lines().map(str::to_string).collect();
+                        }
+                    });
+                
+                egui::CollapsingHeader::new("📚 Known-files lists")
+                    .id_salt("known_files_lists")
+                    .show(ui, |ui| {
+                        ui.label("Hash lists of known files, such as a sha256sum / md5sum listing or the text form of the NSRL Reference Data Set (NSRLFile.txt). Duplicates whose content is listed are left out of the results or tagged \"known: <list>\".");
+                        let mut reload = false;
+                        let mut removed = None;
+                        egui::Grid::new("known_sets_grid").num_columns(5).striped(true).show(ui, |ui| {
+                            for (idx, set) in self.state.known_sets.sets.iter_mut().enumerate() {
+                                reload |= ui.checkbox(&mut set.enabled, "").on_hover_text("Use this list in scans").changed();
+                                ui.add(egui::TextEdit::singleline(&mut set.name).desired_width(120.0))
+                                    .on_hover_text(set.path.display().to_string());
+                                egui::ComboBox::from_id_salt(("known_action", idx))
+                                    .selected_text(set.action.label())
+                                    .show_ui(ui, |ui| {
+                                        for action in [KnownAction::Exclude, KnownAction::Flag] {
+                                            ui.selectable_value(&mut set.action, action, action.label());
+                                        }
+                                    });
+                                match self.known_lists.counts.get(&set.path) {
+                                    _ if !set.enabled => ui.weak("off"),
+                                    Some(Ok(count)) => ui.label(format!("{} hash(es)", format_count(*count as u64))),
+                                    Some(Err(e)) => ui.colored_label(palette.warning, "⚠ unreadable").on_hover_text(e),
+                                    None => ui.weak("loading…"),
+                                };
+                                if ui.small_button("🗑").on_hover_text("Remove this list").clicked() {
+                                    removed = Some(idx);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            if ui.button("➕ Add List…").clicked() {
+                                if let Some(path) = rfd::FileDialog::new().add_filter("Hash lists", &["txt", "csv", "sha256", "md5", "sha1", "hash"]).add_filter("All files", &["*"]).pick_file() {
+                                    self.state.known_sets.sets.push(KnownSet::new(path));
+                                    reload = true;
+                                }
+                            }
+                            if self.known_lists.receiver.is_some() {
+                                activity_spinner(ui, self.in_background);
+                                ui.label("Loading lists…");
+                            } else if let Some(index) = &self.known_lists.index {
+                                ui.label(format!("{} hash(es) loaded", format_count(index.len() as u64)));
+                            }
+                        });
+                        if let Some(idx) = removed {
+                            self.state.known_sets.sets.remove(idx);
+                            reload = true;
+                        }
+                        if reload {
+                            self.reload_known_sets(ctx);
+                        }
+                    });
+                
+                egui::CollapsingHeader::new("⌨ Keyboard shortcuts")
+                    .id_salt("keyboard_shortcuts")
+                    .show(ui, |ui| {
+                        ui.label("Shortcuts without Ctrl or Alt are ignored while typing in a text field. The file and group at the cursor are marked ▶ in the results.");
+                        let mut rebind = None;
+                        let mut unbind = None;
+                        egui::Grid::new("shortcut_grid").num_columns(3).striped(true).show(ui, |ui| {
+                            for action in ShortcutAction::ALL {
+                                ui.label(action.label());
+                                if self.capturing_shortcut == Some(action) {
+                                    ui.colored_label(palette.preview, "Press a key… (Esc cancels)");
+                                } else {
+                                    ui.monospace(self.state.shortcuts.describe(ctx, action).unwrap_or_else(|| "—".to_string()));
+                                }
+                                ui.horizontal(|ui| {
+                                    if ui.small_button("Set…").clicked() {
+                                        rebind = Some(action);
+                                    }
+                                    let clear = ui.add_enabled(self.state.shortcuts.bindings.contains_key(&action), egui::Button::new("✖").small())
+                                        .on_hover_text("Remove this shortcut");
+                                    accessible_label(&clear, egui::WidgetType::Button, format!("Remove shortcut for {}", action.label()));
+                                    if clear.clicked() {
+                                        unbind = Some(action);
+                                    }
+                                });
+                                ui.end_row();
+                            }
+                        });
+                        if ui.button("↺ Reset Shortcuts").clicked() {
+                            self.state.shortcuts = Shortcuts::default();
+                            self.capturing_shortcut = None;
+                        }
+                        if rebind.is_some() {
+                            self.capturing_shortcut = rebind;
+                        }
+                        if let Some(action) = unbind {
+                            self.state.shortcuts.bindings.remove(&action);
+                        }
+                    });
+                
+                ui.horizontal(|ui| {
+                    let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+                    ui.add_enabled_ui(!self.state.config.auto_tune, |ui| {
+                        let mut limit_threads = self.state.config.max_threads.is_some();
+                        if ui.checkbox(&mut limit_threads, "Limit hashing threads")
+                            .on_hover_text("Hash with fewer threads to keep the machine responsive, or more for network drives")
+                            .changed()
+                        {
+                            self.state.config.max_threads = limit_threads.then_some(cores);
+                        }
+                        if let Some(threads) = self.state.config.max_threads.as_mut() {
+                            ui.add(egui::Slider::new(threads, 1..=cores.max(2) * 2).text("threads"));
+                        } else {
+                            ui.weak(format!("(auto: 1 to {} threads, by throughput)", cores))
+                                .on_hover_text("Hashing starts with half the cores and adds or drops threads as long as that raises throughput");
+                        }
+                    });
+                    
+                    // While auto-tuning is on, the last tuned values are the ones in use
+                    let tuned = self.tuning.filter(|_| self.state.config.auto_tune);
+                    let threads = tuned.map_or_else(|| effective_threads(&self.state.config), |tuning| tuning.threads);
+                    let (buffer, qualifier) = match tuned {
+                        Some(tuning) => (tuning.buffer_size, ""),
+                        None if self.state.config.auto_buffer => (MAX_AUTO_BUFFER, "up to "),
+                        None => (self.state.config.buffer_size, ""),
+                    };
+                    ui.weak(format!(
+                        "Hash buffers: {} × {}{} = {}{}",
+                        threads,
+                        qualifier,
+                        format_size(buffer as u64),
+                        qualifier,
+                        format_size((threads * buffer) as u64)
+                    )).on_hover_text("Each hashing thread reuses one buffer, grown to the largest size it has needed");
+                    ui.add(egui::DragValue::new(&mut self.state.config.hash_queue_depth).range(1..=4096).prefix("queue depth: "))
+                        .on_hover_text("Files waiting for a hashing thread, and hashed files waiting to be grouped, at most. Larger queues smooth out mixed file sizes; smaller ones use less memory.");
+                    ui.add(egui::DragValue::new(&mut self.state.config.time_limit_minutes).range(0..=10_080).prefix("⏱ time limit: ").suffix(" min"))
+                        .on_hover_text("Stop a scan after this long and show the duplicates confirmed so far as a partial result, which can be continued later. 0 = no limit.");
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.state.config.auto_tune, "⚙ Auto-tune buffer size and threads")
+                        .on_hover_text("Measure hashing throughput on the scanned volume during the first seconds and use the fastest combination");
+                    if let Some(tuning) = &self.tuning {
+                        ui.weak(format!(
+                            "Last tuned: {} × {} thread(s) at {}/s",
+                            format_size(tuning.buffer_size as u64),
+                            tuning.threads,
+                            format_size(tuning.throughput as u64)
+                        ));
+                    }
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.state.config.use_hash_cache, "🗃 Cache hashes")
+                        .on_hover_text("Reuse the digests of files whose size and modification time (to the nanosecond) have not changed since an earlier scan. Off by default, as a file rewritten with the same size and time stamp would keep its old hash.");
+                    ui.weak(format!("({} file(s) cached)", format_count(self.hash_cache.len() as u64)));
+                    ui.add_enabled_ui(self.state.config.use_hash_cache, |ui| {
+                        egui::ComboBox::from_id_salt("cache_trust")
+                            .selected_text(format!("Trust: {}", self.state.config.cache_trust.label()))
+                            .show_ui(ui, |ui| {
+                                for trust in CacheTrust::ALL {
+                                    ui.selectable_value(&mut self.state.config.cache_trust, trust, trust.label())
+                                        .on_hover_text(trust.description());
+                                }
+                            })
+                            .response
+                            .on_hover_text(self.state.config.cache_trust.description());
+                    });
+                    ui.checkbox(&mut self.state.config.trust_sidecars, "🧾 Trust sidecar checksums")
+                        .on_hover_text("Skip reading files that have a .sha256 file beside them or are listed in a BagIt manifest, when that checksum was written after the file last changed. Files read anyway (with dual hashing, or to settle a disagreement with the hash cache) that contradict it are reported as possibly corrupted.");
+                    if ui.button("📤 Export Hash Cache…")
+                        .on_hover_text("Save the cached hashes under a folder with relative paths, for scanning the same data on another machine")
+                        .clicked()
+                    {
+                        self.export_hash_cache();
+                    }
+                    if ui.add_enabled(!self.state.scanning, egui::Button::new("📥 Import Hash Cache…"))
+                        .on_hover_text("Load hashes exported on another machine and map them onto a local folder")
+                        .clicked()
+                    {
+                        if let Some(source) = rfd::FileDialog::new().add_filter("JSON", &["json"]).pick_file() {
+                            match CacheExport::read(&source) {
+                                Ok(export) => {
+                                    let root = if self.state.selected_dir.is_empty() {
+                                        export.root.display().to_string()
+                                    } else {
+                                        self.state.selected_dir.clone()
+                                    };
+                                    self.cache_import = Some(CacheImport { export, source, root });
+                                }
+                                Err(e) => self.state.status_message = format!("Failed to read hash cache export: {}", e),
+                            }
+                        }
+                    }
+                    if ui.add_enabled(!self.state.scanning, egui::Button::new("🧹 Clear")).on_hover_text("Forget all cached hashes").clicked() {
+                        self.hash_cache.clear();
+                        self.save_hash_cache();
+                    }
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.label("🔍 Cache check:");
+                    let checking = self.cache_check_receiver.is_some();
+                    if ui.add_enabled(!checking && !self.state.scanning && self.hash_cache.len() > 0, egui::Button::new("Verify Now"))
+                        .on_hover_text("Re-hash a random sample of cached files and report any whose content changed while size and modification time stayed the same")
+                        .clicked()
+                    {
+                        self.start_cache_check();
+                    }
+                    let mut changed = ui.add(egui::DragValue::new(&mut self.cache_check.every_days).range(0..=365).prefix("every ").suffix(" day(s)"))
+                        .on_hover_text("How often the check runs by itself, while no scan is running; 0 turns it off")
+                        .changed();
+                    changed |= ui.add(egui::DragValue::new(&mut self.cache_check.sample).range(10..=10_000).suffix(" files"))
+                        .on_hover_text("Cached files re-hashed per check")
+                        .changed();
+                    if changed {
+                        if let Err(e) = self.cache_check.save() {
+                            self.state.status_message = format!("⚠ Could not save the cache check settings: {}", e);
+                        }
+                    }
+                    if checking {
+                        activity_spinner(ui, self.in_background);
+                    } else if let Some(check) = &self.last_cache_check {
+                        let summary = format!(
+                            "{} re-hashed, {} drifted, {} changed, {} missing",
+                            check.checked,
+                            check.drifted.len(),
+                            check.stale,
+                            check.missing
+                        );
+                        if check.drifted.is_empty() {
+                            ui.weak(summary);
+                        } else {
+                            let drifted: Vec<String> = check.drifted.iter().map(|path| path.display().to_string()).collect();
+                            ui.colored_label(palette.warning, summary).on_hover_text(drifted.join("\n"));
+                        }
+                    } else if let Some(last) = self.cache_check.last_run {
+                        ui.weak(format!("last run {}", format_timestamp(last)));
+                    }
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.label("📜 Log file:");
+                    let current = log::max_level();
+                    let mut level = current;
+                    egui::ComboBox::from_id_salt("log_level")
+                        .selected_text(logging::level_label(level))
+                        .show_ui(ui, |ui| {
+                            for option in logging::LEVELS {
+                                ui.selectable_value(&mut level, option, logging::level_label(option));
+                            }
+                        })
+                        .response
+                        .on_hover_text("How much is written to the log: scans, deletions, errors and hash cache events at Info; per-step detail at Debug");
+                    if level != current {
+                        logging::set_level(level);
+                    }
+                    if let Some(dir) = logging::log_dir() {
+                        if ui.button("📂 Open Log Folder")
+                            .on_hover_text(format!("{}; attach dupe-finder.log to bug reports", dir.display()))
+                            .clicked()
+                        {
+                            let _ = fs::create_dir_all(&dir);
+                            ui.ctx().open_url(egui::OpenUrl::new_tab(format!("file://{}", dir.display())));
+                        }
+                    }
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.label("🌐 Network:");
+                    let mut changed = ui.checkbox(&mut self.network.offline, "Offline")
+                        .on_hover_text("No network access at all: no update checks and no SFTP, S3 or Google Drive connections. Connections already open keep working until they are closed.")
+                        .changed();
+                    ui.add_enabled_ui(!self.network.offline, |ui| {
+                        changed |= ui.checkbox(&mut self.network.check_updates, "Check for updates at startup")
+                            .on_hover_text("Ask GitHub for the latest release when the app starts and show a banner when it is newer; nothing is downloaded or installed")
+                            .changed();
+                        if ui.add_enabled(self.update_check.receiver.is_none(), egui::Button::new("🔄 Check Now")).clicked() {
+                            self.check_for_updates(ui.ctx(), true);
+                        }
+                    });
+                    if changed {
+                        if let Err(e) = self.network.save() {
+                            self.state.status_message = format!("⚠ Could not save the network settings: {}", e);
+                        }
+                    }
+                    if self.update_check.latest.is_some() && ui.button("📰 What's New").on_hover_text("Release notes of the latest version").clicked() {
+                        self.update_check.show_changelog = true;
+                    }
+                    if !self.update_check.message.is_empty() {
+                        ui.weak(&self.update_check.message);
+                    }
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.label("🔋 Power:");
+                    let mut changed = ui.checkbox(&mut self.power.settings.keep_awake, "Keep awake while working")
+                        .on_hover_text("Stop the computer from sleeping while a scan or deletion runs; the screen may still turn off")
+                        .changed();
+                    changed |= ui.checkbox(&mut self.power.settings.pause_on_battery, "Pause scans on battery")
+                        .on_hover_text("Pause a running scan when the computer switches to battery power and resume it on mains power")
+                        .changed();
+                    changed |= ui.checkbox(&mut self.power.settings.low_impact_on_battery, "Low-impact scans on battery")
+                        .on_hover_text("While on battery power, hash with one thread and read at most about 20 MB/s; full speed returns on mains power. The 🐢 toggle next to the progress bar overrides this for the running scan.")
+                        .changed();
+                    if changed {
+                        if let Err(e) = self.power.settings.save() {
+                            self.state.status_message = format!("⚠ Could not save the power settings: {}", e);
+                        }
+                    }
+                });
+            }
+            
+            ui.add_space(10.0);
+            
+            // Scan button
+            ui.horizontal(|ui| {
+                let scan_text = if self.state.scan_whole_drive { "🔍 Scan Drive" } else { "🔍 Scan Directory" };
+                let scan = ui.add_enabled(!self.state.scanning, egui::Button::new(scan_text))
+                    .on_hover_text(self.state.shortcuts.hint(ctx, ShortcutAction::Scan, "Find files with identical content in the selected folders"));
+                self.tour.anchor(TourTarget::ScanButton, &scan);
+                if scan.clicked() {
+                    self.start_scan(ctx);
+                }
+                
+                if advanced {
+                    if ui.add_enabled(!self.state.scanning, egui::Button::new("📄 Scan from file list…"))
+                        .on_hover_text("Hash the paths listed in a text file (one per line, or NUL-separated from find -print0); relative paths are resolved against the list's folder")
+                        .clicked()
+                    {
+                        self.pick_file_list_to_scan(ctx);
+                    }
+                    
+                    if ui.add_enabled(!self.state.scanning, egui::Button::new("📋 Scan selected files…"))
+                        .on_hover_text("Find duplicates among files pasted from a file manager, without walking any folder. Files can also be dropped on the window or named on the command line.")
+                        .clicked()
+                    {
+                        self.pasted_selection.open = true;
+                    }
+                    
+                    if ui.add_enabled(!self.state.scanning, egui::Button::new("📥 Import from other tools…"))
+                        .on_hover_text("Review and delete duplicates found by fdupes, jdupes, rdfind or rmlint: open their output (plain or JSON) or rdfind's results.txt")
+                        .clicked()
+                    {
+                        self.pick_external_results();
+                    }
+                }
+                
+                if self.state.scanning {
+                    let paused = self.scan_control.is_paused();
+                    if paused {
+                        ui.label("⏸ Paused");
+                    } else {
+                        activity_spinner(ui, self.in_background);
+                        ui.label("Scanning...");
+                    }
+                    let (pause_text, pause_hint) = if paused {
+                        ("▶ Resume", "Continue the scan where it stopped")
+                    } else {
+                        ("⏸ Pause", "Stop reading files until resumed, e.g. to free the disk for something else")
+                    };
+                    if ui.button(pause_text).on_hover_text(pause_hint).clicked() {
+                        self.scan_control.set_paused(!paused);
+                    }
+                    if ui.add_enabled(!self.scan_control.is_cancelled(), egui::Button::new("⏹ Cancel"))
+                        .on_hover_text(self.state.shortcuts.hint(ctx, ShortcutAction::CancelScan, "Stop this scan; queued scans are not started"))
+                        .clicked()
+                    {
+                        self.scan_control.cancel();
+                        self.running_queue = false;
+                    }
+                }
+                
+                let managed = self.managed_dirs();
+                if !managed.is_empty() {
+                    ui.weak(format!("(skipping {} app-managed folder(s))", managed.len()))
+                        .on_hover_text(managed.iter().map(|d| d.display().to_string()).collect::<Vec<_>>().join("\n"));
+                }
+                
+                if self.near_receiver.is_some() && !self.state.scanning {
+                    activity_spinner(ui, self.in_background);
+                    ui.label("Comparing source files...");
+                }
+                
+                if self.chunk_receiver.is_some() && !self.state.scanning {
+                    activity_spinner(ui, self.in_background);
+                    ui.label("Comparing large files by chunks...");
+                }
+                
+                if self.post_delete.running > 0 {
+                    activity_spinner(ui, self.in_background);
+                    ui.label("Verifying deletions...");
+                }
+                
+                if self.manifest_check.receiver.is_some() {
+                    activity_spinner(ui, self.in_background);
+                    let counts = self.manifest_check.progress.lock().unwrap().as_ref()
+                        .map(|p| format!(" {} / {}", format_count(p.current as u64), format_count(p.total as u64)))
+                        .unwrap_or_default();
+                    ui.label(format!("Verifying checksums...{}", counts));
+                }
+                
+                if advanced {
+                    if ui.button("🔀 Merge Folders").clicked() {
+                        self.merge.open = true;
+                    }
+                    if ui.add_enabled(self.manifest_check.receiver.is_none(), egui::Button::new("🧾 Verify Checksums…"))
+                        .on_hover_text("Check files against a sha256sum or b3sum manifest")
+                        .clicked()
+                    {
+                        self.pick_manifest_to_verify(ctx);
+                    }
+                    if ui.button("🗄 Match Against Manifest")
+                        .on_hover_text("Find local files already stored elsewhere, using that copy's checksum manifest")
+                        .clicked()
+                    {
+                        self.open_manifest_match();
+                    }
+                    if ui.button("⚖ Compare Folders")
+                        .on_hover_text("Compare two folders file by file, e.g. to verify a backup")
+                        .clicked()
+                    {
+                        self.compare.open = true;
+                    }
+                    if ui.button("🩺 Corruption Check")
+                        .on_hover_text("Find files with the same name and size but different content in two folders, e.g. damaged copies in a backup")
+                        .clicked()
+                    {
+                        self.corruption.open = true;
+                    }
+                    if ui.button("🔠 Case-Variant Folders")
+                        .on_hover_text("Find folders that differ only by case (Photos / photos), as left by syncs between systems, and merge them")
+                        .clicked()
+                    {
+                        self.case_dirs.open = true;
+                    }
+                }
+            });
+            
+            if advanced {
+                // Scan queue and history
+                let waiting = self.state.scan_queue.len();
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(self.current_job().is_some(), egui::Button::new("➕ Add to Queue"))
+                        .on_hover_text("Queue a scan of the selected directory with the current settings; queued scans run one after another")
+                        .clicked()
+                    {
+                        self.queue_scan();
+                    }
+                    if self.running_queue {
+                        if ui.button("⏹ Stop Queue After Current").clicked() {
+                            self.running_queue = false;
+                        }
+                    } else if ui.add_enabled(waiting > 0, egui::Button::new(format!("▶ Run Queue ({})", waiting))).clicked() {
+                        self.running_queue = true;
+                    }
+                    
+                    if !self.history.records.is_empty() {
+                        ui.label("Scan history:");
+                        let selected = self.state.active_scan
+                            .and_then(|id| self.history.get(id))
+                            .map_or_else(|| "(not from a scan)".to_string(), ScanRecord::summary);
+                        let busy = self.state.scanning || self.near_receiver.is_some() || self.chunk_receiver.is_some();
+                        let mut open = None;
+                        ui.add_enabled_ui(!busy, |ui| {
+                            egui::ComboBox::from_id_salt("scan_history")
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    for record in self.history.records.iter().rev() {
+                                        if ui.selectable_label(self.state.active_scan == Some(record.id), record.summary()).clicked() {
+                                            open = Some(record.id);
+                                        }
+                                    }
+                                });
+                        }).response.on_disabled_hover_text("Available once the current scan finishes");
+                        if let Some(id) = open {
+                            self.open_history_entry(id);
+                        }
+                        if let Some(id) = self.state.active_scan {
+                            let remove = ui.add_enabled(!busy, egui::Button::new("🗑"))
+                                .on_hover_text("Remove the shown scan from the history; the results stay on screen");
+                            accessible_label(&remove, egui::WidgetType::Button, "Remove the shown scan from the history");
+                            if remove.clicked() {
+                                self.state.active_scan = None;
+                                if let Err(e) = self.history.remove(id) {
+                                    self.state.status_message = format!("⚠ Could not update scan history: {}", e);
+                                }
+                            }
+                        }
+                    }
+                });
+                
+                ui.horizontal(|ui| {
+                    let limits = &mut self.state.history_limits;
+                    ui.label("Keep scan history for");
+                    ui.add(egui::DragValue::new(&mut limits.max_age_days).range(0..=3650).suffix(" days"))
+                        .on_hover_text("0 keeps scans of any age");
+                    ui.label("and up to");
+                    ui.add(egui::DragValue::new(&mut limits.max_total_mb).range(0..=1_000_000).suffix(" MB"))
+                        .on_hover_text("Oldest scans are removed first once the stored results exceed this; 0 means no limit");
+                    if ui.button("🧹 Prune Now").clicked() {
+                        self.state.status_message = match self.history.prune(limits, self.state.active_scan) {
+                            Ok(removed) => format!("✓ Pruned {} scan(s) from history", removed),
+                            Err(e) => format!("⚠ Could not prune scan history: {}", e),
+                        };
+                    }
+                });
+                
+                if waiting > 0 {
+                    egui::CollapsingHeader::new(format!("📋 Scan queue ({})", waiting))
+                        .id_salt("scan_queue")
+                        .show(ui, |ui| {
+                            let mut remove = None;
+                            for (idx, job) in self.state.scan_queue.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    let remove_button = ui.small_button("✖");
+                                    accessible_label(&remove_button, egui::WidgetType::Button, format!("Remove {} from the queue", job.label()));
+                                    if remove_button.clicked() {
+                                        remove = Some(idx);
+                                    }
+                                    ui.label(format!("{}. {}", idx + 1, job.label()))
+                                        .on_hover_text(job.settings_summary());
+                                });
+                            }
+                            if let Some(idx) = remove {
+                                self.state.scan_queue.remove(idx);
+                            }
+                        });
+                }
+            }
+            
+            ui.add_space(10.0);
+            
+            // Progress bar
+            if let Some(progress) = self.scan_progress.lock().unwrap().as_ref() {
+                match progress.phase {
+                    ScanPhase::Discovery => {
+                        // The total is unknown until the walk finishes
+                        ui.add(egui::ProgressBar::new(0.0)
+                            .animate(!self.in_background)
+                            .text(format!("Discovering files: {} found", format_count(progress.current as u64))));
+                        ui.label(format!("📂 {}", truncate_path(&progress.current_file, 80)));
+                    }
+                    ScanPhase::Hashing => {
+                        // Only same-size candidates are hashed; an empty candidate set is already done
+                        let fraction = if progress.total == 0 { 1.0 } else { progress.current as f32 / progress.total as f32 };
+                        ui.add(egui::ProgressBar::new(fraction)
+                            .text(format!(
+                                "Hashing files: {} / {} candidates ({} files scanned)",
+                                format_count(progress.current as u64),
+                                format_count(progress.total as u64),
+                                format_count(progress.discovered as u64)
+                            )));
+                        ui.horizontal(|ui| {
+                            let workers = self.scan_control.workers();
+                            if workers > 0 {
+                                ui.weak(format!("🧵 {} thread(s)", workers))
+                                    .on_hover_text("Hashing threads at work. Unless limited, their number follows the measured throughput: more while files come from memory or a fast drive, fewer while they wait on one disk.");
+                            }
+                            ui.label(format!("📄 {}", truncate_path(&progress.current_file, 80)));
+                        });
+                    }
+                }
+                if self.state.scanning {
+                    ui.horizontal(|ui| {
+                        let mut low_impact = self.scan_control.is_low_impact();
+                        let hint = match self.power.low_impact_override {
+                            Some(_) => "Switched by hand for this scan",
+                            None if self.power.settings.low_impact_on_battery => "Switches on by itself on battery power; click to decide for this scan",
+                            None => "One hashing thread and reads of at most about 20 MB/s, to spare the battery and keep the computer responsive",
+                        };
+                        if ui.toggle_value(&mut low_impact, "🐢 Low impact").on_hover_text(hint).changed() {
+                            self.power.low_impact_override = Some(low_impact);
+                        }
+                        if self.power.on_battery == Some(true) {
+                            ui.weak("🔋 On battery");
+                        }
+                    });
+                }
+            }
+            
+            // Status message
+            if !self.state.status_message.is_empty() {
+                ui.add_space(5.0);
+                let color = if self.state.previewing() {
+                    palette.preview // Blue for preview mode
+                } else {
+                    palette.success // Green for normal mode
+                };
+                ui.colored_label(color, &self.state.status_message);
+            }
+            
+            ui.add_space(10.0);
+            ui.separator();
+            ui.add_space(10.0);
+            
+            if !self.state.symlinks.is_empty() {
+                let symlinks = &self.state.symlinks;
+                let mut remove = None;
+                let remove_text = if self.state.previewing() { "🔍 Preview Remove" } else { "🗑 Remove" };
+                egui::CollapsingHeader::new(format!(
+                    "🔗 Symlinks: {} broken, {} duplicating files, {} shared target(s), {} chain(s)",
+                    symlinks.broken.len(),
+                    symlinks.duplicating.len(),
+                    symlinks.shared_targets.len(),
+                    symlinks.chains.len()
+                ))
+                .id_salt("symlinks")
+                .show(ui, |ui| {
+                    ui.weak("Only the links themselves are removed, never the files they point to.");
+                    let redundant = symlinks.redundant_links();
+                    if !redundant.is_empty() && ui.button(format!("{} all {} redundant link(s)", remove_text, redundant.len())).clicked() {
+                        remove = Some((redundant, "redundant link(s)"));
+                    }
+                    egui::ScrollArea::vertical().id_salt("symlink_scroll").max_height(250.0).show(ui, |ui| {
+                        if !symlinks.broken.is_empty() {
+                            ui.horizontal(|ui| {
+                                ui.strong(format!("Broken links ({})", symlinks.broken.len()));
+                                if ui.small_button(remove_text).clicked() {
+                                    remove = Some((symlinks.broken.clone(), "broken link(s)"));
+                                }
+                            });
+                            for link in &symlinks.broken {
+                                ui.label(link.display().to_string());
+                            }
+                        }
+                        if !symlinks.duplicating.is_empty() {
+                            ui.horizontal(|ui| {
+                                ui.strong(format!("Links to files already in the tree ({})", symlinks.duplicating.len()));
+                                if ui.small_button(remove_text).clicked() {
+                                    remove = Some((symlinks.duplicating.iter().map(|d| d.link.clone()).collect(), "link(s) duplicating files"));
+                                }
+                            });
+                            for dup in &symlinks.duplicating {
+                                ui.label(format!("{} → {}", dup.link.display(), dup.target.display()));
+                            }
+                        }
+                        if !symlinks.shared_targets.is_empty() {
+                            ui.horizontal(|ui| {
+                                ui.strong(format!("Several links to one target ({})", symlinks.shared_targets.len()));
+                                if ui.small_button(format!("{} all but one", remove_text)).clicked() {
+                                    let extra = symlinks.shared_targets.iter().flat_map(|s| s.links.iter().skip(1).cloned()).collect();
+                                    remove = Some((extra, "extra link(s)"));
+                                }
+                            });
+                            for shared in &symlinks.shared_targets {
+                                ui.label(format!("{} ← {} links (keeping {})", shared.target.display(), shared.links.len(), shared.links[0].display()));
+                            }
+                        }
+                        if !symlinks.chains.is_empty() {
+                            ui.strong(format!("Link chains ({})", symlinks.chains.len()));
+                            for chain in &symlinks.chains {
+                                ui.label(chain.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(" → "));
+                            }
+                        }
+                    });
+                });
+                if let Some((links, what)) = remove {
+                    self.remove_links(links, what);
+                }
+                ui.add_space(10.0);
+            }
+            
+            if !self.state.empty_dirs.is_empty() {
+                let mut remove = false;
+                egui::CollapsingHeader::new(format!("📭 Empty folders: {}", self.state.empty_dirs.len()))
+                    .id_salt("empty_dirs")
+                    .show(ui, |ui| {
+                        ui.weak("Each listed folder holds nothing but empty folders; they are removed with it. A folder that has gained content since the scan is left alone.");
+                        let remove_text = if self.state.previewing() { "🔍 Preview Remove Empty Folders" } else { "🗑 Remove Empty Folders" };
+                        if ui.button(remove_text).clicked() {
+                            remove = true;
+                        }
+                        egui::ScrollArea::vertical().id_salt("empty_dirs_scroll").max_height(200.0).show(ui, |ui| {
+                            for dir in &self.state.empty_dirs {
+                                ui.label(dir.display().to_string());
+                            }
+                        });
+                    });
+                if remove {
+                    self.remove_empty_dirs();
                 }
-                
-                if self.state.scanning {
-                    ui.spinner();
-                    ui.label("Scanning...");
+                ui.add_space(10.0);
+            }
+            
+            if !self.state.trashed.is_empty() {
+                let trashed_size: u64 = self.state.trashed.iter().map(|t| t.size).sum();
+                let mut empty = false;
+                egui::CollapsingHeader::new(format!(
+                    "🗑 Already in trash: {} file(s), {}",
+                    self.state.trashed.len(),
+                    format_size(trashed_size)
+                ))
+                .id_salt("already_trashed")
+                .show(ui, |ui| {
+                    ui.weak("These trashed files are copies of files that still exist outside the trash.");
+                    let empty_text = if self.state.previewing() { "🔍 Preview Empty" } else { "🗑 Empty these from trash" };
+                    if ui.button(empty_text).clicked() {
+                        empty = true;
+                    }
+                    egui::ScrollArea::vertical().id_salt("trashed_scroll").max_height(200.0).show(ui, |ui| {
+                        for copy in &self.state.trashed {
+                            ui.label(format!("{}  (copy of {})", copy.path.display(), copy.live_copy.display()));
+                        }
+                    });
+                });
+                if empty {
+                    self.empty_trashed();
                 }
-            });
+                ui.add_space(10.0);
+            }
             
-            ui.add_space(10.0);
+            if !self.state.near_groups.is_empty() {
+                egui::CollapsingHeader::new(format!(
+                    "≈ Near-duplicate source files: {} group(s) (similar, not identical)",
+                    self.state.near_groups.len()
+                ))
+                .id_salt("near_duplicates")
+                .show(ui, |ui| {
+                    ui.weak("These files match after ignoring whitespace and comments. They differ on disk, so no delete actions are offered.");
+                    let mut copied = false;
+                    egui::ScrollArea::vertical().id_salt("near_scroll").max_height(250.0).show(ui, |ui| {
+                        for (group_idx, group) in self.state.near_groups.iter().enumerate() {
+                            ui.group(|ui| {
+                                ui.strong(format!(
+                                    "Near group {} ({} files, ≥{:.0}% similar)",
+                                    group_idx + 1,
+                                    group.files.len(),
+                                    group.similarity * 100.0
+                                ));
+                                for path in &group.files {
+                                    ui.horizontal(|ui| {
+                                        ui.label(path.display().to_string());
+                                        let copy_path = ui.small_button("📋").on_hover_text("Copy path");
+                                        accessible_label(&copy_path, egui::WidgetType::Button, format!("Copy path of {}", path.display()));
+                                        if copy_path.clicked() {
+                                            ui.ctx().copy_text(path.display().to_string());
+                                            copied = true;
+                                        }
+                                    });
+                                }
+                            });
+                        }
+                    });
+                    if copied {
+                        self.state.status_message = "Copied path to clipboard".to_string();
+                    }
+                });
+                ui.add_space(10.0);
+            }
             
-            // Progress bar
-            if let Some(progress) = self.scan_progress.lock().unwrap().as_ref() {
-                let fraction = progress.current as f32 / progress.total.max(1) as f32;
-                let phase_text = match progress.phase {
-                    ScanPhase::Discovery => "Discovering files",
-                    ScanPhase::Hashing => "Hashing files",
-                };
-                ui.add(egui::ProgressBar::new(fraction)
-                    .text(format!("{}: {} / {} files", phase_text, progress.current, progress.total)));
-                
-                let current_file = &progress.current_file;
-                let display_path = if current_file.len() > 80 {
-                    format!("...{}", &current_file[current_file.len()-77..])
-                } else {
-                    current_file.clone()
-                };
-                ui.label(format!("📄 {}", display_path));
+            if !self.state.chunk_pairs.is_empty() {
+                egui::CollapsingHeader::new(format!(
+                    "🧩 Partly identical large files: {} pair(s) (experimental)",
+                    self.state.chunk_pairs.len()
+                ))
+                .id_salt("chunk_similarity")
+                .show(ui, |ui| {
+                    ui.weak("These files share content-defined chunks, e.g. VM images or database dumps made from one another. They differ on disk, so no delete actions are offered.");
+                    let mut copied = false;
+                    egui::ScrollArea::vertical().id_salt("chunk_scroll").max_height(250.0).show(ui, |ui| {
+                        for pair in &self.state.chunk_pairs {
+                            ui.group(|ui| {
+                                ui.strong(format!(
+                                    "{:.0}% identical — {} shared",
+                                    pair.identical_fraction() * 100.0,
+                                    format_size(pair.shared_bytes)
+                                ));
+                                for (path, size) in [(&pair.a, pair.size_a), (&pair.b, pair.size_b)] {
+                                    ui.horizontal(|ui| {
+                                        ui.label(format!("{} ({})", path.display(), format_size(size)));
+                                        let copy_path = ui.small_button("📋").on_hover_text("Copy path");
+                                        accessible_label(&copy_path, egui::WidgetType::Button, format!("Copy path of {}", path.display()));
+                                        if copy_path.clicked() {
+                                            ui.ctx().copy_text(path.display().to_string());
+                                            copied = true;
+                                        }
+                                    });
+                                }
+                            });
+                        }
+                    });
+                    if copied {
+                        self.state.status_message = "Copied path to clipboard".to_string();
+                    }
+                });
+                ui.add_space(10.0);
             }
             
-            // Status message
-            if !self.state.status_message.is_empty() {
+            if self.partial_scan.is_some() {
+                let mut continue_scan = false;
+                ui.horizontal(|ui| {
+                    ui.colored_label(palette.warning, "⏱ Partial result: the scan reached its time limit, so some duplicates may be missing.");
+                    continue_scan = ui.add_enabled(!self.state.scanning, egui::Button::new("▶ Continue Scan"))
+                        .on_hover_text("Scan the same folders again; files already hashed are not read again")
+                        .clicked();
+                });
+                if continue_scan {
+                    self.continue_partial_scan(ctx);
+                }
                 ui.add_space(5.0);
-                let color = if self.state.preview_mode {
-                    egui::Color32::from_rgb(100, 150, 200) // Blue for preview mode
-                } else {
-                    egui::Color32::from_rgb(100, 200, 100) // Green for normal mode
-                };
-                ui.colored_label(color, &self.state.status_message);
             }
             
-            ui.add_space(10.0);
-            ui.separator();
-            ui.add_space(10.0);
+            if !self.state.unique_files.is_empty() {
+                ui.horizontal(|ui| {
+                    let duplicates = format!("📊 Duplicates ({})", format_count(self.state.duplicate_groups.len() as u64));
+                    ui.selectable_value(&mut self.results_view, ResultsView::Duplicates, duplicates);
+                    let unique = format!("☝ No other copy ({})", format_count(self.state.unique_files.len() as u64));
+                    ui.selectable_value(&mut self.results_view, ResultsView::Unique, unique);
+                });
+                ui.add_space(5.0);
+            }
             
             // Results
-            if !self.state.duplicate_groups.is_empty() {
+            if self.results_view == ResultsView::Unique && !self.state.unique_files.is_empty() {
+                self.show_unique_files(ui);
+            } else if !self.state.duplicate_groups.is_empty() {
                 // Check for critical files and show warning
                 let critical_files_count: usize = self.state.duplicate_groups
                     .iter()
@@ -422,134 +8517,788 @@ impl eframe::App for DupeFinderApp {
                 if critical_files_count > 0 {
                     ui.add_space(5.0);
                     ui.horizontal(|ui| {
-                        ui.colored_label(egui::Color32::from_rgb(255, 100, 100), 
+                        ui.colored_label(palette.critical, 
                             format!("⚠️ WARNING: {} critical system/user configuration files detected!", critical_files_count));
-                        ui.colored_label(egui::Color32::from_rgb(200, 200, 100), 
+                        ui.colored_label(palette.warning, 
                             "These files may be important for your system or applications.");
                     });
                     ui.add_space(5.0);
                 }
                 
+                let sensitive_files_count: usize = self.state.duplicate_groups
+                    .iter()
+                    .map(|group| group.files.iter().filter(|f| f.is_sensitive).count())
+                    .sum();
+                
+                if sensitive_files_count > 0 {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(palette.sensitive,
+                            format!("🔒 {} file(s) match sensitive name patterns (keys, passwords, credentials).", sensitive_files_count));
+                        if !self.state.filter.sensitive_only && ui.small_button("Review them").clicked() {
+                            self.state.filter.sensitive_only = true;
+                        }
+                    });
+                    ui.add_space(5.0);
+                }
+                
                 ui.horizontal(|ui| {
-                    ui.heading(format!("📊 Found {} duplicate group(s)", self.state.duplicate_groups.len()));
+                    ui.heading(format!("📊 Found {} duplicate group(s)", format_count(self.state.duplicate_groups.len() as u64)));
                     ui.label("|");
                     ui.colored_label(
-                        egui::Color32::from_rgb(255, 200, 100),
-                        format!("💾 Potential savings: {:.2} MB", self.state.total_size_savings as f64 / 1_048_576.0)
+                        palette.caution,
+                        format!("💾 Potential savings: {}", format_size(self.state.total_size_savings))
                     );
-                    if self.state.preview_mode {
+                    if self.state.previewing() {
                         ui.colored_label(
-                            egui::Color32::from_rgb(100, 150, 200),
+                            palette.preview,
                             "🔍 PREVIEW MODE"
                         );
                     }
                 });
                 
-                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    let mut goal_units = self.goal.target as f64 / GOAL_UNIT as f64;
+                    ui.label("🎯 Goal:");
+                    let edit = ui.add(egui::DragValue::new(&mut goal_units).range(0.0..=1_000_000.0).speed(1.0).suffix(" GB"))
+                        .on_hover_text("Space to reclaim; 0 for no goal. Setting it starts counting deletions towards it afresh.");
+                    if edit.changed() {
+                        self.goal.set((goal_units * GOAL_UNIT as f64) as u64);
+                        self.save_goal();
+                    }
+                    if !self.goal.is_set() {
+                        return;
+                    }
+                    let selected = self.state.total_size_savings;
+                    let progress = self.goal.progress(selected);
+                    ui.add(egui::ProgressBar::new(progress)
+                        .desired_width(220.0)
+                        .text(format!("{:.0}%", progress * 100.0)))
+                        .on_hover_text(format!(
+                            "{} deleted since {} plus {} selected now, of {}",
+                            format_size(self.goal.reclaimed),
+                            format_timestamp(self.goal.since),
+                            format_size(selected),
+                            format_size(self.goal.target)
+                        ));
+                    let remaining = self.goal.remaining(selected);
+                    if remaining == 0 {
+                        ui.colored_label(palette.success, "✓ Goal reached with the current selection");
+                    } else {
+                        ui.label(format!("{} to go", format_size(remaining)));
+                        if ui.button("✨ Suggest").on_hover_text("Keep the likely original in the fewest shown groups that reach the goal, largest first; groups you already changed are left alone").clicked() {
+                            self.select_goal_suggestions();
+                        }
+                    }
+                    if self.goal.reclaimed > 0 && ui.small_button("↺").on_hover_text("Count deletions towards the goal from zero again").clicked() {
+                        self.goal.set(self.goal.target);
+                        self.save_goal();
+                    }
+                });
                 
-                // Export/Import and Bulk actions
-                ui.horizontal(|ui| {
-                    ui.label("File Actions:");
-                    if ui.button("📤 Export Results").clicked() {
-                        match self.export_results() {
-                            Ok(json) => {
-                                if let Some(path) = rfd::FileDialog::new()
-                                    .add_filter("JSON", &["json"])
-                                    .set_file_name("duplicate_results.json")
-                                    .save_file() {
-                                    if let Err(e) = std::fs::write(&path, json) {
-                                        self.state.status_message = format!("Failed to save file: {}", e);
-                                    } else {
-                                        self.state.status_message = format!("Results exported to {}", path.display());
+                if self.state.scan_roots.len() > 1 {
+                    let mut scope = None;
+                    let mut keep_root = None;
+                    egui::CollapsingHeader::new(format!("🗂 Per-root statistics ({} roots)", self.state.scan_roots.len()))
+                        .id_salt("root_stats")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            let roots = &self.state.scan_roots;
+                            let mut duplicates = vec![0usize; roots.len()];
+                            let mut groups = vec![0usize; roots.len()];
+                            let mut wasted = vec![0u64; roots.len()];
+                            let mut cross_root = 0;
+                            for group in &self.state.duplicate_groups {
+                                let mut members = vec![false; roots.len()];
+                                for (idx, file) in group.files.iter().enumerate() {
+                                    let Some(root) = ScanRoot::containing(roots, &file.path.parent()) else {
+                                        continue;
+                                    };
+                                    duplicates[root] += 1;
+                                    members[root] = true;
+                                    // The first copy of a group is its original; the rest are waste where they lie
+                                    if idx > 0 {
+                                        wasted[root] += file.disk_size();
                                     }
                                 }
+                                groups.iter_mut().zip(&members).filter(|(_, &member)| member).for_each(|(count, _)| *count += 1);
+                                if members.iter().filter(|&&member| member).count() > 1 {
+                                    cross_root += 1;
+                                }
                             }
-                            Err(e) => {
-                                self.state.status_message = e;
-                            }
-                        }
-                    }
-                    
-                    if ui.button("📥 Import Results").clicked() {
-                        if let Some(path) = rfd::FileDialog::new()
-                            .add_filter("JSON", &["json"])
-                            .pick_file() {
-                            match std::fs::read_to_string(&path) {
-                                Ok(json) => {
-                                    match self.import_results(&json) {
-                                        Ok(_) => {}, // Message set in import_results
-                                        Err(e) => {
-                                            self.state.status_message = e;
+                            egui::Grid::new("root_stats_grid").striped(true).show(ui, |ui| {
+                                ui.strong("Root");
+                                ui.strong("Files scanned");
+                                ui.strong("Duplicate files");
+                                ui.strong("Groups");
+                                ui.strong("Wasted").on_hover_text("Space taken by every copy after the first one of each group, counted in the root holding the copy");
+                                ui.end_row();
+                                for (idx, root) in roots.iter().enumerate() {
+                                    ui.horizontal(|ui| {
+                                        ui.colored_label(root_color(idx), "■").on_hover_text(root_color_name(idx));
+                                        ui.label(root.path.display().to_string());
+                                    });
+                                    match root.covered_by {
+                                        Some(outer) => {
+                                            ui.weak(format!("in root {}", outer + 1))
+                                                .on_hover_text(format!("Lies inside {}, so its files were scanned once, as part of that root", roots[outer].path.display()));
+                                        }
+                                        None => {
+                                            ui.label(format_count(root.files_scanned as u64));
                                         }
                                     }
+                                    ui.label(format_count(duplicates[idx] as u64));
+                                    ui.label(format_count(groups[idx] as u64));
+                                    ui.label(format_size(wasted[idx]));
+                                    ui.horizontal(|ui| {
+                                        if ui.small_button("🔍").on_hover_text("Show only groups with a file in this root").clicked() {
+                                            scope = Some(RootScope::Includes(idx));
+                                        }
+                                        if ui.small_button(format!("✅ Keep all {}", root_color_name(idx)))
+                                            .on_hover_text("In every shown group with a copy in this root, keep the copies here and uncheck the others")
+                                            .clicked()
+                                        {
+                                            keep_root = Some(idx);
+                                        }
+                                    });
+                                    ui.end_row();
                                 }
-                                Err(e) => {
-                                    self.state.status_message = format!("Failed to read file: {}", e);
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label(format!("🔀 {} group(s) span more than one root", format_count(cross_root)));
+                                if cross_root > 0 && ui.small_button("Show them").clicked() {
+                                    scope = Some(RootScope::CrossRoot);
                                 }
-                            }
-                        }
+                            });
+                        });
+                    if scope.is_some() {
+                        self.state.filter.root_scope = scope;
                     }
-                });
+                    if let Some(root) = keep_root {
+                        self.bulk_keep_root(root);
+                    }
+                }
                 
                 ui.add_space(5.0);
                 
-                // Bulk actions
-                ui.horizontal(|ui| {
-                    ui.label("Bulk Actions:");
-                    if ui.button("📅 Keep Newest in All Groups").clicked() {
-                        self.bulk_select_newest();
-                    }
-                    if ui.button("🕰 Keep Oldest in All Groups").clicked() {
-                        self.bulk_select_oldest();
-                    }
-                    let delete_text = if self.state.preview_mode { "🔍 Preview Delete" } else { "🗑 Delete Unchecked" };
-                    if ui.button(delete_text).clicked() {
-                        self.bulk_delete_unchecked();
+                if advanced {
+                    // Export/Import and Bulk actions
+                    ui.horizontal(|ui| {
+                        ui.label("File Actions:");
+                        if ui.button("📤 Export Results").clicked() {
+                            self.save_results_file();
+                        }
+                        
+                        if ui.button("🧾 Export Checksums")
+                            .on_hover_text("Save every file with its digest in sha256sum (.sha256) or b3sum (.b3) format, verifiable with the standard tools")
+                            .clicked()
+                        {
+                            self.export_checksums();
+                        }
+                        
+                        if ui.button("🔁 Export for Other Tools")
+                            .on_hover_text("Save the groups as fdupes prints them (.txt) or as rmlint JSON (.json), for scripts built around those tools; checked files count as the ones kept")
+                            .clicked()
+                        {
+                            self.export_for_other_tools();
+                        }
+                        
+                        if ui.button("📑 Compliance Report")
+                            .on_hover_text("Export groups, tags, notes, owners, critical flags and deletion actions as CSV or HTML")
+                            .clicked()
+                        {
+                            self.export_compliance_report();
+                        }
+                        
+                        if ui.add_enabled(!self.state.scanning, egui::Button::new("✅ Validate Results"))
+                            .on_hover_text("Check that every file still exists with the same size and modification time")
+                            .clicked()
+                        {
+                            self.state.status_message = self.validate_results();
+                        }
+                        
+                        if ui.add_enabled(!self.state.scanning, egui::Button::new("🔄 Rescan Affected Directories"))
+                            .on_hover_text("Re-check only the directories that contain files in these results")
+                            .clicked()
+                        {
+                            self.start_rescan(ctx);
+                        }
+                        
+                        if ui.button("📥 Import Results").clicked() {
+                            self.open_results_file();
+                        }
+                    });
+                    
+                    ui.add_space(5.0);
+                    
+                    // Bulk actions
+                    let bulk_actions = ui.horizontal_wrapped(|ui| {
+                        // Spelled out on every button so a filtered-out group is never touched by surprise
+                        let scope = if self.state.filter.is_active() {
+                            let shown = self.state.duplicate_groups.iter().filter(|g| self.state.filter.matches(g)).count();
+                            ui.colored_label(palette.preview, format!("Bulk Actions — Filtered ({} of {} groups):", shown, self.state.duplicate_groups.len()))
+                                .on_hover_text("Bulk actions apply only to the groups the filter shows");
+                            format!(" — Filtered ({} groups)", shown)
+                        } else {
+                            ui.label("Bulk Actions:");
+                            " in All Groups".to_string()
+                        };
+                        if ui.button(format!("📅 Keep Newest{}", scope)).clicked() {
+                            self.bulk_select_newest();
+                        }
+                        if ui.button(format!("🕰 Keep Oldest{}", scope)).clicked() {
+                            self.bulk_select_oldest();
+                        }
+                        if ui.button(format!("⭐ Keep Likely Originals{}", scope))
+                            .on_hover_text("Keep the file in each group that looks like the original (earliest created, no \"(1)\" or \"copy\" in the name, not in a backup folder, shortest path)")
+                            .clicked()
+                        {
+                            self.bulk_select_originals();
+                        }
+                        if ui.button(format!("📂 Keep One per Folder{}", scope))
+                            .on_hover_text("Keep one copy in each folder (its likely original) and uncheck the extra copies inside the same folder, e.g. for a photo filed in several albums")
+                            .clicked()
+                        {
+                            self.bulk_select_one_per_directory();
+                        }
+                        if ui.button(format!("🧭 Recommended Cleanup{}…", scope))
+                        .on_hover_text("Propose a cleanup plan with a rule per kind of file, show its savings and carry it out group by group")
+                        .clicked()
+                    {
+                        self.open_cleanup_wizard();
                     }
-                });
+                    if ui.button(format!("✂ Uncheck Pattern-Matched Copies{}", scope))
+                            .on_hover_text("Mark files named like leftover copies (\"(1)\", \"Copy of\", \"*.bak\", ...) for deletion")
+                            .clicked()
+                        {
+                            self.bulk_uncheck_junk_copies();
+                        }
+                        let delete_text = if self.state.previewing() { "🔍 Preview Delete" } else { "🗑 Delete Unchecked" };
+                        if ui.button(format!("{}{}", delete_text, scope)).clicked() {
+                            self.bulk_delete_unchecked(ctx);
+                        }
+                        if self.state.previewing() && ui.button(format!("📋 Simulation Report{}…", scope))
+                            .on_hover_text("Save every file the deletion would remove or skip, totals per folder, critical and sensitive flags and the free space each volume would have afterwards, as HTML or CSV")
+                            .clicked()
+                        {
+                            self.export_simulation_report();
+                        }
+                        ui.menu_button(format!("📝 Export Paths{}", scope), |ui| {
+                            if ui.button("One per line (.txt)…").on_hover_text("For xargs -d '\\n', robocopy /IF lists and the like").clicked() {
+                                ui.close_menu();
+                                self.export_deletion_paths(b'\n');
+                            }
+                            if ui.button("NUL-separated (.nul)…").on_hover_text("For xargs -0; safe with any file name").clicked() {
+                                ui.close_menu();
+                                self.export_deletion_paths(0);
+                            }
+                        })
+                        .response
+                        .on_hover_text("Save just the paths the deletion would remove, without deleting anything, to feed to scripts");
+                        let failed: usize = self.state.duplicate_groups.iter()
+                            .filter(|g| self.state.filter.matches(g))
+                            .map(|g| g.files.iter().filter(|f| f.delete_error.is_some()).count())
+                            .sum();
+                        if failed > 0 && ui.button(format!("🔁 Retry Failed Deletions ({}){}", failed, scope)).clicked() {
+                            self.bulk_retry_failed(ctx);
+                        }
+                        if ui.button(format!("🔗 Consolidate to Store{}", scope))
+                            .on_hover_text("Replace all copies with hard links to one copy in a canonical store directory")
+                            .clicked()
+                        {
+                            self.consolidation.open = true;
+                        }
+                    });
+                    self.tour.anchor(TourTarget::Strategies, &bulk_actions.response);
+                    
+                    ui.add_space(5.0);
+                    
+                    self.ensure_projects();
+                    ui.horizontal(|ui| {
+                        ui.label("Show type:");
+                        egui::ComboBox::from_id_salt("type_filter")
+                            .selected_text(self.state.filter.category.map_or("All", |c| c.label()))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.state.filter.category, None, "All");
+                                for category in FileCategory::ALL {
+                                    ui.selectable_value(&mut self.state.filter.category, Some(category), category.label());
+                                }
+                            });
+                        ui.checkbox(&mut self.state.filter.sensitive_only, "🔒 Sensitive only");
+                        ui.label("Risk:");
+                        egui::ComboBox::from_id_salt("risk_filter")
+                            .selected_text(self.state.filter.risk.map_or("All", |r| r.label()))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.state.filter.risk, None, "All");
+                                for risk in RiskLevel::ALL {
+                                    ui.selectable_value(&mut self.state.filter.risk, Some(risk), risk.label());
+                                }
+                            });
+                        if self.state.scan_roots.len() > 1 {
+                            let roots = &self.state.scan_roots;
+                            ui.label("Roots:");
+                            egui::ComboBox::from_id_salt("root_filter")
+                                .selected_text(self.state.filter.root_scope.map_or("All".to_string(), |scope| scope.label(roots)))
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.state.filter.root_scope, None, "All");
+                                    let scopes = [RootScope::CrossRoot, RootScope::SingleRoot].into_iter().chain((0..roots.len()).map(RootScope::Includes));
+                                    for scope in scopes {
+                                        ui.selectable_value(&mut self.state.filter.root_scope, Some(scope), scope.label(roots));
+                                    }
+                                });
+                        }
+                        if !self.state.filter.projects.roots.is_empty() {
+                            let projects = &self.state.filter.projects;
+                            ui.label("Projects:").on_hover_text("Checkouts with .git, or folders with Cargo.toml or package.json");
+                            egui::ComboBox::from_id_salt("project_filter")
+                                .selected_text(self.state.filter.project_scope.map_or("All".to_string(), |scope| scope.label(projects)))
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.state.filter.project_scope, None, "All");
+                                    let scopes = [ProjectScope::CrossProject, ProjectScope::SingleProject]
+                                        .into_iter()
+                                        .chain((0..projects.roots.len()).map(ProjectScope::Includes));
+                                    for scope in scopes {
+                                        ui.selectable_value(&mut self.state.filter.project_scope, Some(scope), scope.label(projects));
+                                    }
+                                });
+                        }
+                        ui.label("Sort:");
+                        let mut order_changed = false;
+                        egui::ComboBox::from_id_salt("group_order")
+                            .selected_text(self.state.group_order.label())
+                            .show_ui(ui, |ui| {
+                                for order in [GroupOrder::AsFound, GroupOrder::SafestFirst, GroupOrder::RiskiestFirst, GroupOrder::MostWastedFirst, GroupOrder::ByProject] {
+                                    order_changed |= ui.selectable_value(&mut self.state.group_order, order, order.label()).changed();
+                                }
+                            });
+                        if order_changed {
+                            self.sort_groups();
+                        }
+                        ui.separator();
+                        ui.checkbox(&mut self.state.file_layout.split_columns, "▥ Columns")
+                            .on_hover_text("Show the chosen file details in separate, aligned columns");
+                        ui.add_enabled_ui(self.state.file_layout.split_columns, |ui| {
+                            ui.menu_button("⚙", |ui| {
+                                let columns = &mut self.state.file_layout.columns;
+                                let mut swap = None;
+                                egui::Grid::new("column_settings").show(ui, |ui| {
+                                    let last = columns.len() - 1;
+                                    for (idx, spec) in columns.iter_mut().enumerate() {
+                                        ui.checkbox(&mut spec.visible, spec.column.label());
+                                        ui.add(egui::DragValue::new(&mut spec.width).range(30.0..=600.0).suffix(" px"))
+                                            .on_hover_text("Column width");
+                                        if ui.add_enabled(idx > 0, egui::Button::new("⬆").small()).clicked() {
+                                            swap = Some((idx - 1, idx));
+                                        }
+                                        if ui.add_enabled(idx < last, egui::Button::new("⬇").small()).clicked() {
+                                            swap = Some((idx, idx + 1));
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                                if let Some((a, b)) = swap {
+                                    columns.swap(a, b);
+                                }
+                                if ui.button("↺ Reset Columns").clicked() {
+                                    let split_columns = self.state.file_layout.split_columns;
+                                    let group_by_directory = self.state.file_layout.group_by_directory;
+                                    let relative_paths = self.state.file_layout.relative_paths;
+                                    self.state.file_layout = FileLayout { split_columns, group_by_directory, relative_paths, ..Default::default() };
+                                }
+                            })
+                            .response
+                            .on_hover_text("Choose which columns are shown, their order and width");
+                        });
+                        ui.checkbox(&mut self.state.file_layout.group_by_directory, "📂 Group by folder")
+                            .on_hover_text("List the files of each group under their containing folder, e.g. to tell backup copies from live ones");
+                        ui.checkbox(&mut self.state.file_layout.relative_paths, "📁 Relative paths")
+                            .on_hover_text("Show paths relative to their scan root, named once in each group's header: shorter rows, and screenshots don't show your user name. Hover a file for its full path.");
+                        if self.state.filter.is_active() {
+                            let shown = self.state.duplicate_groups.iter()
+                                .filter(|g| self.state.filter.matches(g))
+                                .count();
+                            ui.label(format!("({} of {} groups)", shown, self.state.duplicate_groups.len()));
+                        }
+                    });
+                } else {
+                    let cleanup = ui.horizontal(|ui| {
+                        let shown = self.state.duplicate_groups.iter().filter(|g| self.state.filter.matches(g)).count();
+                        let cleanup = ui.add_enabled(shown > 0, egui::Button::new(egui::RichText::new("🧹 Clean Up Duplicates…").strong()))
+                            .on_hover_text("Choose which copy to keep in every group, review what goes and confirm before anything is deleted");
+                        if cleanup.clicked() {
+                            self.open_cleanup_wizard();
+                        }
+                        if self.state.filter.is_active() {
+                            ui.colored_label(palette.preview, format!("Showing {} of {} groups", shown, self.state.duplicate_groups.len()));
+                            if ui.button("Show All").on_hover_text("Clear the filter chosen in advanced mode").clicked() {
+                                self.state.filter.clear();
+                            }
+                        }
+                        ui.weak("Or check the files to keep in the groups below by hand.");
+                    });
+                    self.tour.anchor(TourTarget::Strategies, &cleanup.response);
+                }
                 
                 ui.add_space(10.0);
                 
                 egui::ScrollArea::vertical().show(ui, |ui| {
                     let mut group_to_delete = None;
+                    let mut retry_failed_for = None;
+                    let mut preview_request = None;
                     let mut recalculate = false;
                     let mut select_newest_for = None;
                     let mut select_oldest_for = None;
+                    let mut select_original_for = None;
+                    let mut select_per_directory_for = None;
+                    let mut copied = None;
+                    let mut rename_request = None;
+                    let mut rename_cancelled = false;
+                    let mut verify_group = None;
+                    let mut moved_cursor = None;
+                    let verification_paths = self.verification.as_ref().map(|v| &v.paths);
+                    let in_background = self.in_background;
+                    let cursor = self.cursor;
+                    let scroll_to_cursor = std::mem::take(&mut self.scroll_to_cursor);
+                    let previewing = self.state.previewing();
+                    let result_roots = self.result_roots();
+                    let shortcuts = &self.state.shortcuts;
                     
                     for (group_idx, group) in self.state.duplicate_groups.iter_mut().enumerate() {
-                        ui.group(|ui| {
+                        if !self.state.filter.matches(group) {
+                            continue;
+                        }
+                        let mut group_roots: Vec<usize> = group.files.iter()
+                            .filter_map(|file| relative_to_root(&file.path.to_path_buf(), &result_roots))
+                            .map(|(root, _)| root)
+                            .collect();
+                        group_roots.sort_unstable();
+                        group_roots.dedup();
+                        let metadata_differs = metadata_differences(&group.files);
+                        
+                        let mut frame = egui::Frame::group(ui.style());
+                        if cursor.is_some_and(|cursor| cursor.group == group_idx) {
+                            frame = frame.stroke(egui::Stroke::new(2.0, palette.preview));
+                        }
+                        frame.show(ui, |ui| {
                             ui.horizontal(|ui| {
-                                ui.strong(format!("Group {} ", group_idx + 1));
-                                ui.label(format!("({} files, {:.2} MB each)", 
+                                let wasted = group.wasted_bytes();
+                                let header = ui.strong(format!("Group {} ", group_idx + 1));
+                                accessible_label(&header, egui::WidgetType::Label, format!(
+                                    "Duplicate group {}, {} files of {} each, {} wasted",
+                                    group_idx + 1,
+                                    group.files.len(),
+                                    format_size(group.files[0].size),
+                                    format_size(wasted)
+                                ));
+                                ui.label(format!("({} files, {} each)", 
                                     group.files.len(),
-                                    group.files[0].size as f64 / 1_048_576.0
+                                    format_size(group.files[0].size)
                                 ));
+                                ui.colored_label(palette.caution, format!("{} wasted", format_size(wasted)))
+                                    .on_hover_text("Space freed by deleting the unchecked files, or every copy but one while all are kept");
+                                if let Some(mime) = &group.files[0].detected_type {
+                                    ui.weak(format!("[{}]", mime));
+                                }
+                                if !metadata_differs.is_empty() {
+                                    ui.colored_label(palette.caution, "🔐 metadata differs")
+                                        .on_hover_text(format!(
+                                            "Same content, but the copies differ in {}; each row shows its own",
+                                            metadata_differs.join(" and ")
+                                        ));
+                                }
+                                for &root in group_roots.iter().filter(|_| self.state.file_layout.relative_paths) {
+                                    let text = egui::RichText::new(format!("📁 {}", result_roots[root].display()));
+                                    let text = if result_roots.len() > 1 { text.color(root_color(root)) } else { text.weak() };
+                                    ui.label(text).on_hover_text("Scan root; the paths below are relative to it");
+                                }
+                                let projects = &self.state.filter.projects;
+                                for project in projects.spanned(&group.files) {
+                                    ui.weak(format!("📦 {}", projects.name(project)))
+                                        .on_hover_text(format!("Project: {}", projects.roots[project].display()));
+                                }
+                                let risk_factors = group.risk_factors();
+                                let risk_score: u32 = risk_factors.iter().map(|(_, weight)| weight).sum();
+                                let risk = RiskLevel::from_score(risk_score);
+                                let risk_color = match risk {
+                                    RiskLevel::Low => palette.success,
+                                    RiskLevel::Medium => palette.caution,
+                                    RiskLevel::High => palette.critical,
+                                };
+                                let risk_hover = if risk_factors.is_empty() {
+                                    "No risk factors".to_string()
+                                } else {
+                                    risk_factors.iter().map(|(reason, weight)| format!("+{} {}", weight, reason)).collect::<Vec<_>>().join("\n")
+                                };
+                                ui.colored_label(risk_color, format!("Risk {} ({})", risk_score, risk.label()))
+                                    .on_hover_text(risk_hover);
+                                if let Some(sha512) = &group.secondary_hash {
+                                    ui.weak("[SHA-256 + SHA-512]")
+                                        .on_hover_text(format!("SHA-256: {}\nSHA-512: {}", group.hash, sha512));
+                                }
+                                for tag in &group.tags {
+                                    ui.label(egui::RichText::new(format!("🏷 {}", tag)).small().color(palette.preview));
+                                }
                             });
                             
+                            egui::CollapsingHeader::new("🏷 Tags & notes")
+                                .id_salt(("annotations", group_idx))
+                                .show(ui, |ui| {
+                                    ui.horizontal_wrapped(|ui| {
+                                        let mut remove_tag = None;
+                                        for (tag_idx, tag) in group.tags.iter().enumerate() {
+                                            let remove = ui.small_button(format!("{} ✖", tag));
+                                            accessible_label(&remove, egui::WidgetType::Button, format!("Remove tag {}", tag));
+                                            if remove.clicked() {
+                                                remove_tag = Some(tag_idx);
+                                            }
+                                        }
+                                        if let Some(tag_idx) = remove_tag {
+                                            group.tags.remove(tag_idx);
+                                        }
+                                        let input = ui.add(egui::TextEdit::singleline(&mut group.tag_input)
+                                            .hint_text("add tag")
+                                            .desired_width(120.0));
+                                        accessible_label(&input, egui::WidgetType::TextEdit, format!("Add tag to group {}", group_idx + 1));
+                                        if input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                                            let tag = group.tag_input.trim().to_string();
+                                            if !tag.is_empty() && !group.tags.contains(&tag) {
+                                                group.tags.push(tag);
+                                            }
+                                            group.tag_input.clear();
+                                        }
+                                    });
+                                    let notes = ui.add(egui::TextEdit::multiline(&mut group.notes)
+                                        .hint_text("Notes")
+                                        .desired_rows(2)
+                                        .desired_width(f32::INFINITY));
+                                    accessible_label(&notes, egui::WidgetType::TextEdit, format!("Notes for group {}", group_idx + 1));
+                                });
+                            
+                            if group.files.len() > GROUP_PAGE_SIZE {
+                                egui::CollapsingHeader::new(format!("📂 Directory breakdown ({} files)", group.files.len()))
+                                    .id_salt(("dir_breakdown", group_idx))
+                                    .show(ui, |ui| {
+                                        for (dir, count) in group.directory_counts() {
+                                            ui.label(format!("{} × {}", count, dir));
+                                        }
+                                    });
+                            }
+                            
                             ui.add_space(5.0);
                             
-                            for (idx, file) in group.files.iter().enumerate() {
-                                ui.horizontal(|ui| {
-                                    let checkbox_response = ui.checkbox(&mut group.selected[idx], "Keep");
+                            let visible = group.visible_files.min(group.files.len());
+                            let original = likely_original(&group.files);
+                            let layout = &self.state.file_layout;
+                            let rows = file_rows(group, layout.group_by_directory);
+                            let mut folder_sizes: HashMap<DirId, usize> = HashMap::new();
+                            if layout.group_by_directory {
+                                for file in &group.files {
+                                    *folder_sizes.entry(file.path.dir_id()).or_default() += 1;
+                                }
+                            }
+                            let mut current_folder = None;
+                            for idx in rows {
+                                let file = &group.files[idx];
+                                let placed = relative_to_root(&file.path.to_path_buf(), &result_roots);
+                                let relative = placed.as_ref().filter(|_| layout.relative_paths);
+                                let shown_dir = relative.map_or_else(|| file.path.parent().display().to_string(), |(_, rel)| relative_dir(rel));
+                                if layout.group_by_directory && current_folder != Some(file.path.dir_id()) {
+                                    current_folder = Some(file.path.dir_id());
+                                    ui.label(egui::RichText::new(format!(
+                                        "📂 {} ({} of {})",
+                                        shown_dir,
+                                        folder_sizes[&file.path.dir_id()],
+                                        group.files.len()
+                                    )).strong());
+                                }
+                                let at_cursor = cursor == Some(ResultCursor { group: group_idx, file: idx });
+                                let row = ui.horizontal(|ui| {
+                                    if at_cursor {
+                                        ui.colored_label(palette.preview, "▶");
+                                    }
+                                    if layout.group_by_directory {
+                                        ui.add_space(16.0);
+                                    }
+                                    let checkbox_response = ui.checkbox(&mut group.selected[idx], "Keep")
+                                        .on_hover_text(shortcuts.hint(ui.ctx(), ShortcutAction::ToggleKeep, "Unchecked files are deleted"));
+                                    accessible_checkbox(&checkbox_response, group.selected[idx], format!("Keep {}", file.path));
                                     if checkbox_response.changed() {
                                         recalculate = true;
+                                        moved_cursor = Some(ResultCursor { group: group_idx, file: idx });
+                                    }
+                                    
+                                    let path = file.path.to_path_buf();
+                                    let mut in_basket = self.basket.paths.contains(&path);
+                                    let basket_response = ui.checkbox(&mut in_basket, "🧺")
+                                        .on_hover_text("Add to the selection basket");
+                                    accessible_checkbox(&basket_response, in_basket, format!("Add {} to basket", file.path));
+                                    if basket_response.changed() {
+                                        if in_basket {
+                                            self.basket.paths.insert(path);
+                                        } else {
+                                            self.basket.paths.remove(&path);
+                                        }
                                     }
                                     
                                     // Show warning for critical files
                                     if file.is_critical {
-                                        ui.colored_label(egui::Color32::from_rgb(255, 100, 100), "⚠️ ");
+                                        ui.colored_label(palette.critical, "⚠️ ");
                                     }
                                     
-                                    ui.label(file.path.display().to_string());
-                                    if let Some(modified) = file.modified_time {
-                                        if let Ok(datetime) = modified.elapsed() {
-                                            ui.label(format!("({} days ago)", datetime.as_secs() / 86400));
+                                    let editing = self.renaming.as_mut()
+                                        .filter(|edit| edit.group_idx == group_idx && edit.file_idx == idx);
+                                    if let Some(edit) = editing {
+                                        if let Some(parent) = file.path.to_path_buf().parent() {
+                                            ui.label(format!("{}{}", parent.display(), std::path::MAIN_SEPARATOR));
+                                        }
+                                        let response = ui.add(egui::TextEdit::singleline(&mut edit.new_name).desired_width(200.0));
+                                        accessible_label(&response, egui::WidgetType::TextEdit, format!("New name for {}", file.path));
+                                        let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                                        if ui.small_button("✔ Rename").clicked() || submitted {
+                                            rename_request = Some((group_idx, idx, edit.new_name.clone()));
+                                        }
+                                        if ui.small_button("✖ Cancel").clicked() {
+                                            rename_cancelled = true;
+                                        }
+                                    } else {
+                                        if original == Some(idx) {
+                                            let reasons = original_reasons(&group.files, idx);
+                                            let star = ui.label("⭐").on_hover_text(format!("Likely original: {}", reasons.join(", ")));
+                                            accessible_label(&star, egui::WidgetType::Label, format!("Likely original: {}", file.path));
+                                        } else if layout.split_columns {
+                                            // Keeps the columns aligned with rows that show a star
+                                            column_label(ui, 18.0, "");
+                                        }
+                                        // Tells the roots apart at a glance
+                                        if let Some((root, _)) = placed.as_ref().filter(|_| result_roots.len() > 1) {
+                                            let path = &result_roots[*root];
+                                            let name = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+                                            ui.colored_label(root_color(*root), format!("■ {}", name))
+                                                .on_hover_text(format!("Root {} ({}): {}", root + 1, root_color_name(*root), path.display()));
                                         }
+                                        if layout.split_columns {
+                                            for spec in layout.visible_columns() {
+                                                let text: egui::WidgetText = match spec.column {
+                                                    // The folder heading already names it
+                                                    FileColumn::Directory if layout.group_by_directory => continue,
+                                                    FileColumn::Directory => egui::RichText::new(shown_dir.as_str()).weak().into(),
+                                                    FileColumn::Name => file.path.file_name().to_string_lossy().into(),
+                                                    FileColumn::Extension => Path::new(file.path.file_name())
+                                                        .extension()
+                                                        .map_or(String::new(), |ext| ext.to_string_lossy().to_lowercase())
+                                                        .into(),
+                                                    FileColumn::Size => format_size(file.size).into(),
+                                                    FileColumn::Modified => file.modified_time.map_or("—".to_string(), format_file_date).into(),
+                                                    FileColumn::Created => file.created_time.map_or("—".to_string(), format_file_date).into(),
+                                                    FileColumn::Owner => file.owner.map_or("—".to_string(), owner_name).into(),
+                                                    FileColumn::DetectedType => file.detected_type.clone().unwrap_or_else(|| "—".to_string()).into(),
+                                                    FileColumn::HashPrefix => egui::RichText::new(group.hash.chars().take(12).collect::<String>()).monospace().into(),
+                                                    FileColumn::Critical if file.is_critical => egui::RichText::new("critical").color(palette.critical).into(),
+                                                    FileColumn::Critical => "".into(),
+                                                };
+                                                column_label(ui, spec.width, text).on_hover_text(file.path.to_string());
+                                            }
+                                        } else if layout.group_by_directory {
+                                            ui.label(file.path.file_name().to_string_lossy()).on_hover_text(file.path.to_string());
+                                        } else if let Some((_, rel)) = relative {
+                                            ui.label(rel.display().to_string()).on_hover_text(file.path.to_string());
+                                        } else {
+                                            ui.label(file.path.to_string());
+                                        }
+                                        let rename = ui.small_button("✏").on_hover_text("Rename…");
+                                        accessible_label(&rename, egui::WidgetType::Button, format!("Rename {}", file.path));
+                                        if rename.clicked() {
+                                            self.renaming = Some(RenameEdit {
+                                                group_idx,
+                                                file_idx: idx,
+                                                new_name: file.path.file_name().to_string_lossy().into_owned(),
+                                            });
+                                        }
+                                    }
+                                    let copy_path = ui.small_button("📋").on_hover_text("Copy path");
+                                    accessible_label(&copy_path, egui::WidgetType::Button, format!("Copy path of {}", file.path));
+                                    if copy_path.clicked() {
+                                        ui.ctx().copy_text(file.path.to_string());
+                                        copied = Some("path");
+                                    }
+                                    let preview = ui.small_button("👁").on_hover_text("Preview text or PDF contents");
+                                    accessible_label(&preview, egui::WidgetType::Button, format!("Preview {}", file.path));
+                                    if preview.clicked() {
+                                        preview_request = Some(file.path.to_path_buf());
+                                    }
+                                    let copy_hash = ui.add_enabled(!group.hash.is_empty(), egui::Button::new("#").small())
+                                        .on_hover_text("Copy SHA-256 hash");
+                                    accessible_label(&copy_hash, egui::WidgetType::Button, format!("Copy hash of {}", file.path));
+                                    if copy_hash.clicked() {
+                                        ui.ctx().copy_text(group.hash.clone());
+                                        copied = Some("hash");
+                                    }
+                                    if let Some(modified) = file.modified_time.filter(|_| !layout.split_columns) {
+                                        ui.label(format!("({})", format_file_date(modified)));
                                     }
                                     
                                     if file.is_critical {
-                                        ui.colored_label(egui::Color32::from_rgb(255, 100, 100), "[CRITICAL]");
+                                        ui.colored_label(palette.critical, "[CRITICAL]");
+                                    }
+                                    
+                                    if file.is_sensitive {
+                                        ui.colored_label(palette.sensitive, "[SENSITIVE]");
+                                    }
+                                    
+                                    if !metadata_differs.is_empty() {
+                                        let hover = if file.xattrs.is_empty() {
+                                            "No extended attributes".to_string()
+                                        } else {
+                                            format!("Extended attributes:\n{}", file.xattrs.join("\n"))
+                                        };
+                                        ui.label(egui::RichText::new(format!("[{}]", metadata_summary(file))).monospace().small())
+                                            .on_hover_text(hover);
+                                    }
+                                    
+                                    if file.is_junk_copy {
+                                        ui.colored_label(palette.caution, "[COPY]")
+                                            .on_hover_text("Name matches a junk copy pattern");
+                                    }
+                                    
+                                    if is_in_snapshot(&file.path.to_path_buf()) {
+                                        ui.colored_label(palette.preview, "[SNAPSHOT]")
+                                            .on_hover_text("Read-only copy inside a filesystem snapshot: it is never deleted, and it goes away with the snapshot, so it does not count as a kept copy");
+                                    }
+                                    
+                                    if let Some(allocated) = file.allocated_size {
+                                        ui.colored_label(palette.caution, "[SPARSE]")
+                                            .on_hover_text(format!(
+                                                "{} logical, {} allocated on disk; savings count the allocated size",
+                                                format_size(file.size),
+                                                format_size(allocated)
+                                            ));
                                     }
+                                    
+                                    if file.stale {
+                                        ui.colored_label(palette.caution, "[CHANGED]")
+                                            .on_hover_text("Size or modification time differs from the scan; this file will not be deleted");
+                                    }
+                                    
+                                    if let Some(reason) = &file.delete_error {
+                                        ui.colored_label(palette.critical, format!("❌ {}", reason))
+                                            .on_hover_text("Deleting this file failed; use \"Retry Failed\" once the cause is fixed");
+                                    }
+                                });
+                                if at_cursor && scroll_to_cursor {
+                                    row.response.scroll_to_me(Some(egui::Align::Center));
+                                }
+                            }
+                            
+                            let hidden = group.files.len() - visible;
+                            if hidden > 0 {
+                                ui.horizontal(|ui| {
+                                    let next_page = hidden.min(GROUP_PAGE_SIZE);
+                                    if ui.button(format!("⬇ Show {} more", next_page)).clicked() {
+                                        group.visible_files = visible + next_page;
+                                    }
+                                    if ui.button(format!("Show all {}", group.files.len())).clicked() {
+                                        group.visible_files = group.files.len();
+                                    }
+                                    ui.label(format!("({} hidden)", hidden));
                                 });
                             }
                             
@@ -562,10 +9311,38 @@ impl eframe::App for DupeFinderApp {
                                 if ui.button("🕰 Keep Oldest").clicked() {
                                     select_oldest_for = Some(group_idx);
                                 }
-                                let delete_text = if self.state.preview_mode { "🔍 Preview Delete" } else { "🗑 Delete Unchecked" };
-                                if ui.button(delete_text).clicked() {
+                                if ui.button("⭐ Keep Original").on_hover_text("Keep only the file marked as the likely original").clicked() {
+                                    select_original_for = Some(group_idx);
+                                }
+                                if ui.button("📂 One per Folder").on_hover_text("Keep one copy in each folder and uncheck the extra copies inside the same folder").clicked() {
+                                    select_per_directory_for = Some(group_idx);
+                                }
+                                let delete_text = if previewing { "🔍 Preview Delete" } else { "🗑 Delete Unchecked" };
+                                if ui.button(delete_text)
+                                    .on_hover_text(shortcuts.hint(ui.ctx(), ShortcutAction::DeleteGroup, "Delete the unchecked files of this group; with the keyboard, of the group at the cursor"))
+                                    .clicked()
+                                {
                                     group_to_delete = Some(group_idx);
                                 }
+                                let failed = group.files.iter().filter(|f| f.delete_error.is_some()).count();
+                                if failed > 0 && ui.button(format!("🔁 Retry Failed ({})", failed))
+                                    .on_hover_text("Try deleting only the files that failed last time")
+                                    .clicked()
+                                {
+                                    retry_failed_for = Some(group_idx);
+                                }
+                                
+                                let verifying_this = verification_paths
+                                    .is_some_and(|paths| paths.first() == group.files.first().map(|f| f.path.to_path_buf()).as_ref());
+                                if verifying_this {
+                                    activity_spinner(ui, in_background);
+                                    ui.label("Re-verifying...");
+                                } else if ui.add_enabled(verification_paths.is_none(), egui::Button::new("🔁 Re-verify Group"))
+                                    .on_hover_text("Re-hash every file and remove any whose content changed since the scan")
+                                    .clicked()
+                                {
+                                    verify_group = Some(group_idx);
+                                }
                             });
                         });
                         
@@ -576,6 +9353,25 @@ impl eframe::App for DupeFinderApp {
                         self.calculate_savings();
                     }
                     
+                    if moved_cursor.is_some() {
+                        self.cursor = moved_cursor;
+                    }
+                    
+                    if let Some(what) = copied {
+                        self.state.status_message = format!("Copied {} to clipboard", what);
+                    }
+                    
+                    if rename_cancelled {
+                        self.renaming = None;
+                    }
+                    
+                    if let Some((group_idx, file_idx, new_name)) = rename_request {
+                        match self.rename_file(group_idx, file_idx, &new_name) {
+                            Ok(()) => self.renaming = None,
+                            Err(e) => self.state.status_message = format!("⚠ {}", e),
+                        }
+                    }
+                    
                     if let Some(idx) = select_newest_for {
                         self.select_newest(idx);
                     }
@@ -584,8 +9380,28 @@ impl eframe::App for DupeFinderApp {
                         self.select_oldest(idx);
                     }
                     
+                    if let Some(idx) = select_original_for {
+                        self.select_original(idx);
+                    }
+                    
+                    if let Some(idx) = select_per_directory_for {
+                        self.select_one_per_directory(idx);
+                    }
+                    
+                    if let Some(idx) = verify_group {
+                        self.start_group_verification(idx, ctx);
+                    }
+                    
+                    if let Some(path) = preview_request {
+                        self.open_preview(path);
+                    }
+                    
+                    if let Some(idx) = retry_failed_for {
+                        self.retry_failed(idx, ctx);
+                    }
+                    
                     if let Some(idx) = group_to_delete {
-                        self.delete_unchecked(idx);
+                        self.delete_unchecked(idx, ctx);
                     }
                 });
             } else if !self.state.scanning {
@@ -602,5 +9418,79 @@ impl eframe::App for DupeFinderApp {
                 });
             }
         });
+        
+        self.show_merge_window(ctx);
+        self.show_case_dirs_window(ctx);
+        self.show_compare_window(ctx);
+        self.show_corruption_window(ctx);
+        self.show_manifest_match_window(ctx);
+        self.show_consolidation_window(ctx);
+        self.show_post_delete_window(ctx, &palette);
+        self.show_kept_renames_window(ctx, &palette);
+        self.show_sftp_window(ctx, &palette);
+        self.show_s3_window(ctx, &palette);
+        self.show_drive_window(ctx, &palette);
+        self.show_device_window(ctx, &palette);
+        self.show_selection_window(ctx);
+        self.show_cleanup_wizard(ctx, &palette);
+        self.show_quit_window(ctx, &palette);
+        self.show_crash_report(ctx, &palette);
+        self.show_interrupted_delete_window(ctx, &palette);
+        self.show_changelog(ctx);
+        self.show_insights_window(ctx, &palette);
+        
+        let dropped: Vec<PathBuf> = ctx.input(|i| i.raw.dropped_files.iter().filter_map(|file| file.path.clone()).collect());
+        if !dropped.is_empty() {
+            self.open_paths(dropped, ctx);
+        }
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let screen = ctx.screen_rect();
+            let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("drop_hint")));
+            painter.rect_filled(screen, 0.0, egui::Color32::from_black_alpha(180));
+            painter.text(
+                screen.center(),
+                egui::Align2::CENTER_CENTER,
+                "Drop files to find duplicates among them,\nor folders to add them as scan roots",
+                egui::FontId::proportional(20.0),
+                egui::Color32::WHITE,
+            );
+        }
+        self.show_last_copy_window(ctx, &palette);
+        self.show_manifest_window(ctx, &palette);
+        self.show_preview_window(ctx);
+        self.show_cache_import_window(ctx);
+        self.show_external_import_window(ctx);
+        self.tour.show(ctx, palette.preview);
+        
+        self.reveal_settings = None;
+        let available = self.available_commands();
+        if let Some(command) = self.command_palette.show(ctx, &available) {
+            self.run_command(command, ctx);
+        }
+        
+        let title = self.title_summary();
+        if title != self.window_title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.window_title = title;
+        }
+        let progress = self.taskbar_progress();
+        self.taskbar.set(frame, progress);
+    }
+    
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // Keep edits made to the shown results since they were last stored
+        self.save_active_results();
+        if let Err(e) = self.state.file_layout.save() {
+            log::error!("Could not save the column layout: {}", e);
+        }
+        if let Err(e) = self.state.shortcuts.save() {
+            log::error!("Could not save the keyboard shortcuts: {}", e);
+        }
+        if let Err(e) = self.state.known_sets.save() {
+            log::error!("Could not save the known-files lists: {}", e);
+        }
+        if let Err(e) = self.hash_cache.save() {
+            log::error!("Could not save the hash cache: {}", e);
+        }
     }
 }
@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use rayon::prelude::*;
+use crate::merge::collect_files;
+use crate::scanner::{build_thread_pool, hash_file, ScanConfig, ScanError, ScanPhase, ScanProgress};
+use crate::source::same_file;
+
+/// Checksum manifest layouts written by `sha256sum` and `b3sum`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ManifestFormat {
+    Sha256,
+    Blake3,
+}
+
+impl ManifestFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            ManifestFormat::Sha256 => "SHA-256",
+            ManifestFormat::Blake3 => "BLAKE3",
+        }
+    }
+
+    /// Both digests are 64 hex digits, so untagged lines rely on the file name
+    pub fn from_path(path: &Path) -> Self {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+        if name.ends_with(".b3") || name.contains("blake3") || name.contains("b3sum") {
+            ManifestFormat::Blake3
+        } else {
+            ManifestFormat::Sha256
+        }
+    }
+
+    // Algorithm names used by `--tag` (BSD-style) lines
+    fn tag(self) -> &'static str {
+        match self {
+            ManifestFormat::Sha256 => "SHA256",
+            ManifestFormat::Blake3 => "BLAKE3",
+        }
+    }
+}
+
+pub fn blake3_file(path: &Path) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(File::open(path)?)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Digest of a file in the given format
+pub fn digest_file(path: &Path, format: ManifestFormat, config: &ScanConfig) -> io::Result<String> {
+    match format {
+        ManifestFormat::Sha256 => hash_file(path, config),
+        ManifestFormat::Blake3 => blake3_file(path),
+    }
+}
+
+/// Write `digest  path` lines as `sha256sum` / `b3sum` print them. Names
+/// containing a backslash or newline are escaped with a leading backslash,
+/// as GNU coreutils does, so `sha256sum -c` reads them back unchanged.
+pub fn write_manifest(entries: &[(PathBuf, String)]) -> String {
+    let mut out = String::new();
+    for (path, digest) in entries {
+        let name = path.to_string_lossy();
+        if name.contains(['\\', '\n', '\r']) {
+            let escaped = name.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r");
+            out.push_str(&format!("\\{}  {}\n", digest, escaped));
+        } else {
+            out.push_str(&format!("{}  {}\n", digest, name));
+        }
+    }
+    out
+}
+
+/// One line of a manifest
+#[derive(Clone, Debug)]
+pub struct ManifestEntry {
+    pub path: PathBuf,
+    pub digest: String,
+    pub format: ManifestFormat,
+}
+
+fn unescape(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+pub fn is_digest(text: &str) -> bool {
+    text.len() == 64 && text.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+// One manifest line: GNU `digest  name` / `digest *name`, or BSD `ALGO (name) = digest`
+fn parse_line(line: &str, default_format: ManifestFormat) -> Option<(String, String, ManifestFormat)> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (name, digest, format) = if let Some((algo, rest)) = line.split_once(" (") {
+        let format = [ManifestFormat::Sha256, ManifestFormat::Blake3].into_iter().find(|f| f.tag() == algo)?;
+        let (name, digest) = rest.rsplit_once(") = ")?;
+        (name, digest, format)
+    } else {
+        let (digest, rest) = line.split_once(' ')?;
+        // A space for text mode or '*' for binary mode; both hash the same bytes
+        let name = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*'))?;
+        (name, digest, default_format)
+    };
+    if !is_digest(digest) || name.is_empty() {
+        return None;
+    }
+    let name = if escaped { unescape(name) } else { name.to_string() };
+    Some((name, digest.to_lowercase(), format))
+}
+
+/// Parse a manifest; relative paths are resolved against `base`. Returns the
+/// entries and the number of lines that could not be parsed.
+pub fn parse_manifest(text: &str, base: &Path, default_format: ManifestFormat) -> (Vec<ManifestEntry>, usize) {
+    let mut entries = Vec::new();
+    let mut malformed = 0;
+    for line in text.lines().map(|l| l.trim_end_matches('\r')).filter(|l| !l.trim().is_empty() && !l.starts_with('#')) {
+        match parse_line(line, default_format) {
+            Some((name, digest, format)) => entries.push(ManifestEntry { path: base.join(name), digest, format }),
+            None => malformed += 1,
+        }
+    }
+    (entries, malformed)
+}
+
+/// Outcome of checking a manifest against the files on disk
+#[derive(Clone, Debug, Default)]
+pub struct ManifestCheck {
+    pub manifest: PathBuf,
+    pub ok: usize,
+    pub mismatched: Vec<PathBuf>,
+    pub missing: Vec<PathBuf>,
+    pub unreadable: Vec<String>,
+    /// Lines that are not checksum entries
+    pub malformed: usize,
+}
+
+impl ManifestCheck {
+    pub fn is_clean(&self) -> bool {
+        self.mismatched.is_empty() && self.missing.is_empty() && self.unreadable.is_empty() && self.malformed == 0
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} OK, {} mismatched, {} missing, {} unreadable",
+            self.ok,
+            self.mismatched.len(),
+            self.missing.len(),
+            self.unreadable.len()
+        );
+        if self.malformed > 0 {
+            summary.push_str(&format!(", {} improperly formatted line(s)", self.malformed));
+        }
+        summary
+    }
+}
+
+/// Re-hash every file listed in a `sha256sum` / `b3sum` manifest and compare
+pub fn verify_manifest<F>(manifest: &Path, config: &ScanConfig, progress_callback: F) -> Result<ManifestCheck, ScanError>
+where
+    F: Fn(ScanProgress) + Send + Sync,
+{
+    let text = String::from_utf8_lossy(&fs::read(manifest)?).into_owned();
+    let base = manifest.parent().unwrap_or(Path::new("."));
+    let (entries, malformed) = parse_manifest(&text, base, ManifestFormat::from_path(manifest));
+
+    let total = entries.len();
+    let done = AtomicUsize::new(0);
+    let results: Vec<(&ManifestEntry, io::Result<String>)> = build_thread_pool(config)?.install(|| {
+        entries
+            .par_iter()
+            .map(|entry| {
+                let digest = digest_file(&entry.path, entry.format, config);
+                progress_callback(ScanProgress {
+                    current: done.fetch_add(1, Ordering::Relaxed) + 1,
+                    total,
+                    discovered: total,
+                    current_file: entry.path.display().to_string(),
+                    phase: ScanPhase::Hashing,
+                });
+                (entry, digest)
+            })
+            .collect()
+    });
+
+    let mut check = ManifestCheck { manifest: manifest.to_path_buf(), malformed, ..Default::default() };
+    for (entry, digest) in results {
+        match digest {
+            Ok(digest) if digest.eq_ignore_ascii_case(&entry.digest) => check.ok += 1,
+            Ok(_) => check.mismatched.push(entry.path.clone()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => check.missing.push(entry.path.clone()),
+            Err(e) => check.unreadable.push(format!("{}: {}", entry.path.display(), e)),
+        }
+    }
+    Ok(check)
+}
+
+/// A local file whose content is listed in an external manifest
+#[derive(Clone, Debug)]
+pub struct ManifestMatch {
+    pub path: PathBuf,
+    pub size: u64,
+    /// Path of the same content in the manifest, resolved against its folder
+    pub listed_as: PathBuf,
+    digest: String,
+    format: ManifestFormat,
+}
+
+/// Local files found in an external manifest, e.g. one written on a NAS
+#[derive(Clone, Debug, Default)]
+pub struct ManifestMatches {
+    pub manifest: PathBuf,
+    pub root: PathBuf,
+    pub matches: Vec<ManifestMatch>,
+    /// Local files hashed
+    pub hashed: usize,
+    pub errors: Vec<String>,
+    pub malformed: usize,
+}
+
+impl ManifestMatches {
+    pub fn total_size(&self) -> u64 {
+        self.matches.iter().map(|m| m.size).sum()
+    }
+
+    /// Delete the matched local files, re-hashing each first so a file changed
+    /// since the match is never removed. Returns the deleted paths and the errors met.
+    pub fn delete_matches(&mut self, config: &ScanConfig) -> (Vec<PathBuf>, Vec<String>) {
+        let mut deleted = Vec::new();
+        let mut errors = Vec::new();
+        self.matches.retain(|m| {
+            // A listed copy that is this very file is no copy at all
+            if same_file(&m.path, &m.listed_as).unwrap_or(false) {
+                errors.push(format!("Skipped {}: the manifest lists this same file", m.path.display()));
+                return true;
+            }
+            let result = match digest_file(&m.path, m.format, config) {
+                Ok(digest) if digest.eq_ignore_ascii_case(&m.digest) => {
+                    fs::remove_file(&m.path).map_err(|e| format!("Failed to delete {}: {}", m.path.display(), e))
+                }
+                Ok(_) => Err(format!("Skipped {}: its content changed since the match", m.path.display())),
+                Err(e) => Err(format!("Skipped {}: {}", m.path.display(), e)),
+            };
+            match result {
+                Ok(()) => {
+                    deleted.push(m.path.clone());
+                    false
+                }
+                Err(e) => {
+                    errors.push(e);
+                    true
+                }
+            }
+        });
+        (deleted, errors)
+    }
+}
+
+/// Hash every file under `root` and report those whose digest appears in the
+/// manifest, so content already stored elsewhere can be found without
+/// mounting and scanning that copy. Manifest entries under `root` itself,
+/// as in a manifest exported from the same folder, are not copies elsewhere
+/// and are ignored.
+pub fn match_against_manifest<F>(root: &Path, manifest: &Path, config: &ScanConfig, progress_callback: F) -> Result<ManifestMatches, ScanError>
+where
+    F: Fn(ScanProgress) + Send + Sync,
+{
+    if !root.is_dir() {
+        return Err(ScanError::IoError(io::Error::new(io::ErrorKind::NotFound, format!("{} is not a directory", root.display()))));
+    }
+    let text = String::from_utf8_lossy(&fs::read(manifest)?).into_owned();
+    let base = manifest.parent().unwrap_or(Path::new("."));
+    let (entries, malformed) = parse_manifest(&text, base, ManifestFormat::from_path(manifest));
+    let canonical_root = root.canonicalize()?;
+    let mut listed: HashMap<(ManifestFormat, String), PathBuf> = HashMap::new();
+    for entry in entries {
+        let resolved = entry.path.canonicalize().unwrap_or_else(|_| entry.path.clone());
+        if resolved.starts_with(&canonical_root) || entry.path.starts_with(root) {
+            continue;
+        }
+        listed.entry((entry.format, entry.digest)).or_insert(entry.path);
+    }
+    let formats: Vec<ManifestFormat> = [ManifestFormat::Sha256, ManifestFormat::Blake3]
+        .into_iter()
+        .filter(|format| listed.keys().any(|(f, _)| f == format))
+        .collect();
+
+    progress_callback(ScanProgress {
+        current: 0,
+        total: 0,
+        discovered: 0,
+        current_file: root.display().to_string(),
+        phase: ScanPhase::Discovery,
+    });
+    // Empty files all share one digest and say nothing about what is stored elsewhere
+    let files: Vec<(PathBuf, u64)> = collect_files(root, manifest, config)
+        .into_iter()
+        .filter(|(_, size)| *size > 0 && *size >= config.min_file_size)
+        .collect();
+
+    let total = files.len();
+    let done = AtomicUsize::new(0);
+    let results: Vec<Result<Option<ManifestMatch>, String>> = build_thread_pool(config)?.install(|| {
+        files
+            .into_par_iter()
+            .map(|(path, size)| {
+                let mut result = Ok(None);
+                for &format in &formats {
+                    match digest_file(&path, format, config) {
+                        Ok(digest) => {
+                            let listed_as = listed.get(&(format, digest.clone())).filter(|listed_as| !same_file(&path, listed_as).unwrap_or(false));
+                            if let Some(listed_as) = listed_as {
+                                let listed_as = listed_as.clone();
+                                result = Ok(Some(ManifestMatch { path: path.clone(), size, listed_as, digest, format }));
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            result = Err(format!("{}: {}", path.display(), e));
+                            break;
+                        }
+                    }
+                }
+                progress_callback(ScanProgress {
+                    current: done.fetch_add(1, Ordering::Relaxed) + 1,
+                    total,
+                    discovered: total,
+                    current_file: path.display().to_string(),
+                    phase: ScanPhase::Hashing,
+                });
+                result
+            })
+            .collect()
+    });
+
+    let mut matches = ManifestMatches { manifest: manifest.to_path_buf(), root: root.to_path_buf(), hashed: total, malformed, ..Default::default() };
+    for result in results {
+        match result {
+            Ok(Some(found)) => matches.matches.push(found),
+            Ok(None) => {}
+            Err(e) => matches.errors.push(e),
+        }
+    }
+    matches.matches.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    #[test]
+    fn text_and_binary_mode_lines_resolve_against_the_base() {
+        let text = format!("{}  a.txt\r\n{} *sub/b c.bin\n", DIGEST.to_uppercase(), DIGEST);
+        let (entries, malformed) = parse_manifest(&text, Path::new("/base"), ManifestFormat::Sha256);
+        assert_eq!(malformed, 0);
+        let paths: Vec<&Path> = entries.iter().map(|entry| entry.path.as_path()).collect();
+        assert_eq!(paths, vec![Path::new("/base/a.txt"), Path::new("/base/sub/b c.bin")]);
+        assert!(entries.iter().all(|entry| entry.digest == DIGEST && entry.format == ManifestFormat::Sha256));
+    }
+
+    #[test]
+    fn tagged_lines_choose_their_format() {
+        let text = format!("BLAKE3 (notes (draft).txt) = {}\nSHA256 (x) = {}\n", DIGEST, DIGEST);
+        let (entries, malformed) = parse_manifest(&text, Path::new("/base"), ManifestFormat::Sha256);
+        assert_eq!(malformed, 0);
+        assert_eq!(entries[0].path, Path::new("/base/notes (draft).txt"));
+        assert_eq!(entries[0].format, ManifestFormat::Blake3);
+        assert_eq!(entries[1].format, ManifestFormat::Sha256);
+    }
+
+    #[test]
+    fn malformed_lines_are_counted_and_comments_skipped() {
+        let text = format!(
+            "# made by sha256sum\n\n{}\n{}  \nabc  short.txt\n{}x  long.txt\nMD5 (a) = {}\n{} one-space\n",
+            DIGEST, DIGEST, DIGEST, DIGEST, DIGEST
+        );
+        let (entries, malformed) = parse_manifest(&text, Path::new("/base"), ManifestFormat::Sha256);
+        assert!(entries.is_empty());
+        assert_eq!(malformed, 6);
+    }
+
+    #[test]
+    fn escaped_names_read_back_unchanged() {
+        let names = [PathBuf::from("back\\slash"), PathBuf::from("new\nline"), PathBuf::from("plain")];
+        let entries: Vec<(PathBuf, String)> = names.iter().map(|name| (name.clone(), DIGEST.to_string())).collect();
+        let (parsed, malformed) = parse_manifest(&write_manifest(&entries), Path::new(""), ManifestFormat::Sha256);
+        assert_eq!(malformed, 0);
+        assert_eq!(parsed.into_iter().map(|entry| entry.path).collect::<Vec<_>>(), names);
+    }
+}
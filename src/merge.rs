@@ -0,0 +1,233 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use walkdir::WalkDir;
+use rayon::prelude::*;
+use crate::scanner::{build_thread_pool, hash_file, is_hidden, ScanConfig, ScanError, ScanPhase, ScanProgress};
+
+#[derive(Clone, Debug)]
+pub struct MergeCopy {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub size: u64,
+}
+
+/// A source file that was not copied because its content is already present
+#[derive(Clone, Debug)]
+pub struct MergeSkip {
+    pub source: PathBuf,
+    pub existing: PathBuf,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MergePlan {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub to_copy: Vec<MergeCopy>,
+    pub skipped: Vec<MergeSkip>,
+    /// Source files whose target path is taken by a file with different content
+    pub conflicts: Vec<MergeCopy>,
+}
+
+impl MergePlan {
+    pub fn copy_size(&self) -> u64 {
+        self.to_copy.iter().map(|c| c.size).sum()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MergeReport {
+    pub copied: usize,
+    pub copied_bytes: u64,
+    pub errors: Vec<String>,
+}
+
+/// Regular files under `root` with their sizes, leaving out anything under `exclude`
+pub fn collect_files(root: &Path, exclude: &Path, config: &ScanConfig) -> Vec<(PathBuf, u64)> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| (config.include_hidden || !is_hidden(e)) && !e.path().starts_with(exclude))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let size = e.metadata().ok()?.len();
+            Some((e.into_path(), size))
+        })
+        .collect()
+}
+
+/// Work out which files from `source` need copying into `destination`,
+/// comparing by content so renamed or moved copies are recognised.
+pub fn plan_merge<F>(source: &Path, destination: &Path, config: &ScanConfig, progress_callback: F) -> Result<MergePlan, ScanError>
+where
+    F: Fn(ScanProgress) + Send + Sync,
+{
+    if !source.is_dir() {
+        return Err(ScanError::IoError(std::io::Error::new(std::io::ErrorKind::NotFound, format!("{} is not a directory", source.display()))));
+    }
+
+    progress_callback(ScanProgress {
+        current: 0,
+        total: 0,
+        discovered: 0,
+        current_file: destination.display().to_string(),
+        phase: ScanPhase::Discovery,
+    });
+
+    // Avoid treating a nested destination as part of the source and vice versa
+    let source_files = collect_files(source, destination, config);
+    let dest_files = collect_files(destination, source, config);
+
+    let source_sizes: HashSet<u64> = source_files.iter().map(|(_, size)| *size).collect();
+    let dest_sizes: HashSet<u64> = dest_files.iter().map(|(_, size)| *size).collect();
+
+    // Only files whose size appears on both sides (or twice in the source) can match
+    let mut source_size_counts: HashMap<u64, usize> = HashMap::new();
+    for (_, size) in &source_files {
+        *source_size_counts.entry(*size).or_default() += 1;
+    }
+    let to_hash: Vec<&PathBuf> = source_files
+        .iter()
+        .filter(|(_, size)| dest_sizes.contains(size) || source_size_counts[size] > 1)
+        .chain(dest_files.iter().filter(|(_, size)| source_sizes.contains(size)))
+        .map(|(path, _)| path)
+        .collect();
+
+    let total = to_hash.len();
+    let hashes: HashMap<&PathBuf, String> = build_thread_pool(config)?.install(|| {
+        to_hash
+            .par_iter()
+            .filter_map(|path| hash_file(path, config).ok().map(|hash| (*path, hash)))
+            .collect()
+    });
+
+    progress_callback(ScanProgress {
+        current: total,
+        total,
+        discovered: source_files.len() + dest_files.len(),
+        current_file: "Comparing contents".to_string(),
+        phase: ScanPhase::Hashing,
+    });
+
+    let mut known: HashMap<(u64, &str), PathBuf> = HashMap::new();
+    for (path, size) in &dest_files {
+        if let Some(hash) = hashes.get(path) {
+            known.entry((*size, hash.as_str())).or_insert_with(|| path.clone());
+        }
+    }
+
+    let mut plan = MergePlan {
+        source: source.to_path_buf(),
+        destination: destination.to_path_buf(),
+        ..Default::default()
+    };
+
+    for (path, size) in &source_files {
+        let relative = path.strip_prefix(source).unwrap_or(path);
+        let target = destination.join(relative);
+        let hash = hashes.get(path).map(String::as_str);
+
+        if let Some(existing) = hash.and_then(|h| known.get(&(*size, h))) {
+            plan.skipped.push(MergeSkip { source: path.clone(), existing: existing.clone() });
+            continue;
+        }
+
+        let copy = MergeCopy { from: path.clone(), to: target, size: *size };
+        if copy.to.exists() {
+            plan.conflicts.push(copy);
+            continue;
+        }
+        // Later identical source files resolve to this copy instead
+        if let Some(h) = hash {
+            known.insert((*size, h), copy.to.clone());
+        }
+        plan.to_copy.push(copy);
+    }
+
+    Ok(plan)
+}
+
+/// Copy one file, creating parent directories and keeping its permissions and
+/// modification time where possible. A file already at `to` is only replaced
+/// when `overwrite` is set; otherwise the copy fails with `AlreadyExists`.
+pub fn copy_file(from: &Path, to: &Path, overwrite: bool) -> io::Result<u64> {
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut source = File::open(from)?;
+    let mut options = File::options();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    let mut target = options.open(to)?;
+    let bytes = io::copy(&mut source, &mut target).inspect_err(|_| {
+        if !overwrite {
+            let _ = fs::remove_file(to);
+        }
+    })?;
+    if let Ok(metadata) = source.metadata() {
+        let _ = target.set_permissions(metadata.permissions());
+        if let Ok(modified) = metadata.modified() {
+            let _ = target.set_modified(modified);
+        }
+    }
+    Ok(bytes)
+}
+
+/// Rename `from` to `to`, falling back to copy-and-delete across filesystems;
+/// parent directories of `to` are created
+pub fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_file(from, to, false)?;
+    fs::remove_file(from)
+}
+
+/// Copy every planned file, preserving modification times where possible.
+/// A destination that does not exist yet is created first; planning leaves
+/// the disk alone. A file that has appeared at a target since planning is
+/// never overwritten, and `stop` ends the copying between two files.
+pub fn execute_merge<F>(plan: &MergePlan, stop: &AtomicBool, progress_callback: F) -> MergeReport
+where
+    F: Fn(ScanProgress),
+{
+    let mut report = MergeReport::default();
+    if let Err(e) = fs::create_dir_all(&plan.destination) {
+        report.errors.push(format!("Cannot create {}: {}", plan.destination.display(), e));
+        return report;
+    }
+    let total = plan.to_copy.len();
+    for (done, copy) in plan.to_copy.iter().enumerate() {
+        if stop.load(Ordering::Relaxed) {
+            report.errors.push(format!("Stopped with {} file(s) left to copy", total - done));
+            break;
+        }
+        progress_callback(ScanProgress {
+            current: done,
+            total,
+            discovered: total,
+            current_file: copy.from.display().to_string(),
+            phase: ScanPhase::Hashing,
+        });
+        match copy_file(&copy.from, &copy.to, false) {
+            Ok(bytes) => {
+                report.copied += 1;
+                report.copied_bytes += bytes;
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                report.errors.push(format!("Skipped {}: {} appeared since the analysis", copy.from.display(), copy.to.display()));
+            }
+            Err(e) => report.errors.push(format!("Failed to copy {}: {}", copy.from.display(), e)),
+        }
+    }
+    report
+}
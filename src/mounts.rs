@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+// Virtual and kernel filesystems that never hold user data worth hashing,
+// plus automount points that may hang while a remote share is mounted
+const PSEUDO_FILESYSTEMS: &[&str] = &[
+    "proc", "sysfs", "devtmpfs", "devpts", "cgroup", "cgroup2", "securityfs",
+    "debugfs", "tracefs", "pstore", "bpf", "configfs", "fusectl", "mqueue", "hugetlbfs",
+    "autofs", "binfmt_misc", "efivarfs", "rpc_pipefs", "nsfs",
+];
+
+/// Mount points of pseudo filesystems that scans should skip, told apart by
+/// filesystem type so ordinary volumes mounted under /run or /dev are kept
+pub fn pseudo_mount_points() -> HashSet<PathBuf> {
+    let mut mounts: HashSet<PathBuf> = HashSet::new();
+    if cfg!(target_os = "linux") {
+        if let Ok(table) = std::fs::read_to_string("/proc/self/mounts") {
+            for line in table.lines() {
+                let mut fields = line.split_whitespace();
+                let (Some(_device), Some(mount_point), Some(fs_type)) = (fields.next(), fields.next(), fields.next()) else {
+                    continue;
+                };
+                if PSEUDO_FILESYSTEMS.contains(&fs_type) {
+                    mounts.insert(PathBuf::from(unescape_mount_path(mount_point)));
+                }
+            }
+        }
+    } else if cfg!(target_os = "macos") {
+        mounts.insert(PathBuf::from("/dev"));
+        mounts.insert(PathBuf::from("/System/Volumes/VM"));
+    }
+    mounts
+}
+
+// /proc/self/mounts escapes whitespace and backslashes as octal, e.g. "\040"
+fn unescape_mount_path(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && bytes.len() >= i + 4 && bytes[i + 1..i + 4].iter().all(|b| (b'0'..=b'7').contains(b)) {
+            let value = bytes[i + 1..i + 4].iter().fold(0u32, |acc, b| acc * 8 + (b - b'0') as u32);
+            out.push(value as u8);
+            i += 4;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Root of the volume containing `path`: the mount point on Unix, the drive on Windows
+pub fn volume_root(path: &Path) -> PathBuf {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let Ok(device) = std::fs::metadata(&path).map(|m| m.dev()) else {
+            return PathBuf::from("/");
+        };
+        let mut root = path.clone();
+        for ancestor in path.ancestors().skip(1) {
+            match std::fs::metadata(ancestor) {
+                Ok(meta) if meta.dev() == device => root = ancestor.to_path_buf(),
+                _ => break,
+            }
+        }
+        root
+    }
+
+    #[cfg(not(unix))]
+    {
+        path.ancestors().last().map(Path::to_path_buf).unwrap_or(path)
+    }
+}
+
+/// Space available to this user on the volume holding `path`
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `c_path` is NUL-terminated and `stats` is a valid statvfs to fill in
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Some(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn free_space(_path: &Path) -> Option<u64> {
+    None
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::SystemTime;
+use crate::paths::DirId;
+use crate::scanner::{matches_pattern, FileInfo, SelectionStrategy};
+
+// Folder names that usually hold second copies rather than the working file
+const BACKUP_DIR_NAMES: &[&str] = &["bak", "old", "archive", "archives", "copies", "snapshots", ".snapshot"];
+
+/// Names like "report (1).pdf" or "photo copy.jpg" that file managers and
+/// browsers give to a second copy
+pub fn has_copy_name(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    if matches_pattern(name, "*copy*") {
+        return true;
+    }
+    // A parenthesised number anywhere in the name
+    name.match_indices('(').any(|(open, _)| {
+        let rest = &name[open + 1..];
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        digits > 0 && rest[digits..].starts_with(')')
+    })
+}
+
+pub fn in_backup_dir(path: &Path) -> bool {
+    path.parent().is_some_and(|dir| {
+        dir.components().any(|c| {
+            let name = c.as_os_str().to_string_lossy().to_lowercase();
+            name.contains("backup") || BACKUP_DIR_NAMES.contains(&name.as_str())
+        })
+    })
+}
+
+// Falls back to the modification time where the filesystem records no creation time
+fn birth_time(file: &FileInfo) -> Option<SystemTime> {
+    file.created_time.or(file.modified_time)
+}
+
+/// Ranking key; lower is more likely the original. Name and location are
+/// the strongest signals, then the earliest creation time, then the shortest path.
+fn original_rank(file: &FileInfo) -> (bool, bool, bool, Option<SystemTime>, usize) {
+    let path = file.path.to_path_buf();
+    let born = birth_time(file);
+    (has_copy_name(&path), in_backup_dir(&path), born.is_none(), born, path.as_os_str().len())
+}
+
+/// Index of the file in a group most likely to be the original
+pub fn likely_original(files: &[FileInfo]) -> Option<usize> {
+    files.iter().enumerate().min_by_key(|(_, f)| original_rank(f)).map(|(idx, _)| idx)
+}
+
+/// Which of the heuristics favour the file at `idx`, for display
+pub fn original_reasons(files: &[FileInfo], idx: usize) -> Vec<&'static str> {
+    let Some(file) = files.get(idx) else {
+        return Vec::new();
+    };
+    let path = file.path.to_path_buf();
+    let mut reasons = Vec::new();
+    if birth_time(file).is_some() && files.iter().all(|f| birth_time(f).is_none_or(|t| Some(t) >= birth_time(file))) {
+        reasons.push("earliest created");
+    }
+    if !has_copy_name(&path) {
+        reasons.push("no copy-style name");
+    }
+    if !in_backup_dir(&path) {
+        reasons.push("not in a backup folder");
+    }
+    let len = path.as_os_str().len();
+    if files.iter().all(|f| f.path.to_path_buf().as_os_str().len() >= len) {
+        reasons.push("shortest path");
+    }
+    reasons
+}
+
+pub struct KeepLikelyOriginalStrategy;
+
+impl SelectionStrategy for KeepLikelyOriginalStrategy {
+    fn select(&self, files: &[FileInfo]) -> Vec<bool> {
+        let mut selected = vec![false; files.len()];
+        if let Some(idx) = likely_original(files) {
+            selected[idx] = true;
+        }
+        selected
+    }
+}
+
+/// Keeps one copy in each folder, the likely original among those there:
+/// an image filed in several albums stays in each, while extra copies
+/// inside one album go
+pub struct KeepOnePerDirectoryStrategy;
+
+impl SelectionStrategy for KeepOnePerDirectoryStrategy {
+    fn select(&self, files: &[FileInfo]) -> Vec<bool> {
+        let mut by_dir: HashMap<DirId, Vec<usize>> = HashMap::new();
+        for (idx, file) in files.iter().enumerate() {
+            by_dir.entry(file.path.dir_id()).or_default().push(idx);
+        }
+        let mut selected = vec![false; files.len()];
+        for indices in by_dir.values() {
+            let copies: Vec<FileInfo> = indices.iter().map(|&idx| files[idx].clone()).collect();
+            if let Some(kept) = likely_original(&copies) {
+                selected[indices[kept]] = true;
+            }
+        }
+        selected
+    }
+}
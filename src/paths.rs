@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// The table is not swept for unused directories until it holds this many
+const MIN_SWEEP: usize = 1024;
+
+/// Identifier of a directory stored in the shared path table. Interned
+/// directories are shared, so ids compare by identity.
+#[derive(Clone, Debug)]
+pub struct DirId(Arc<Path>);
+
+impl PartialEq for DirId {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for DirId {}
+
+impl Hash for DirId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).cast::<u8>().hash(state);
+    }
+}
+
+struct PathTable {
+    dirs: HashSet<Arc<Path>>,
+    /// Size at which directories no path refers to any more are dropped
+    sweep_at: usize,
+}
+
+impl PathTable {
+    fn intern(&mut self, dir: &Path) -> DirId {
+        if let Some(dir) = self.dirs.get(dir) {
+            return DirId(dir.clone());
+        }
+        if self.dirs.len() >= self.sweep_at {
+            // Only the table holds these: the results that used them are gone
+            self.dirs.retain(|dir| Arc::strong_count(dir) > 1);
+            self.sweep_at = (self.dirs.len() * 2).max(MIN_SWEEP);
+        }
+        let dir: Arc<Path> = Arc::from(dir);
+        self.dirs.insert(dir.clone());
+        DirId(dir)
+    }
+}
+
+// Every FileInfo shares one table so files in the same directory only store
+// the directory once, no matter how deep the tree is. Directories leave it
+// once no path refers to them, so replaced results do not pile up.
+fn table() -> &'static RwLock<PathTable> {
+    static TABLE: OnceLock<RwLock<PathTable>> = OnceLock::new();
+    TABLE.get_or_init(|| RwLock::new(PathTable { dirs: HashSet::new(), sweep_at: MIN_SWEEP }))
+}
+
+pub fn intern_dir(dir: &Path) -> DirId {
+    if let Some(dir) = table().read().unwrap().dirs.get(dir) {
+        return DirId(dir.clone());
+    }
+    table().write().unwrap().intern(dir)
+}
+
+pub fn dir_path(id: DirId) -> Arc<Path> {
+    id.0
+}
+
+/// A path stored as an interned parent directory plus its file name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InternedPath {
+    dir: DirId,
+    name: Box<OsStr>,
+}
+
+impl InternedPath {
+    pub fn new(path: &Path) -> Self {
+        match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => Self { dir: intern_dir(parent), name: name.into() },
+            // Roots and other paths without a file name are kept whole
+            _ => Self { dir: intern_dir(path), name: OsStr::new("").into() },
+        }
+    }
+
+    pub fn dir_id(&self) -> DirId {
+        self.dir.clone()
+    }
+
+    pub fn parent(&self) -> Arc<Path> {
+        self.dir.0.clone()
+    }
+
+    pub fn file_name(&self) -> &OsStr {
+        &self.name
+    }
+
+    pub fn to_path_buf(&self) -> PathBuf {
+        let dir = self.parent();
+        if self.name.is_empty() {
+            dir.to_path_buf()
+        } else {
+            dir.join(&*self.name)
+        }
+    }
+}
+
+impl From<&Path> for InternedPath {
+    fn from(path: &Path) -> Self {
+        Self::new(path)
+    }
+}
+
+impl From<PathBuf> for InternedPath {
+    fn from(path: PathBuf) -> Self {
+        Self::new(&path)
+    }
+}
+
+impl fmt::Display for InternedPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_path_buf().display())
+    }
+}
+
+// Serialized as a plain path so exported results stay readable and compatible.
+// A name that is not valid UTF-8 is written lossily rather than failing the
+// whole export; importing it back reports the file as missing.
+impl Serialize for InternedPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_path_buf().to_string_lossy())
+    }
+}
+
+impl<'de> Deserialize<'de> for InternedPath {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        PathBuf::deserialize(deserializer).map(Self::from)
+    }
+}
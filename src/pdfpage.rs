@@ -0,0 +1,607 @@
+use ab_glyph::{Font, FontRef, OutlineCurve};
+use lopdf::{Dictionary, Document, Encoding, Object, ObjectId};
+use tiny_skia::{Color, FillRule, Paint, PathBuilder, Pixmap, PixmapPaint, Rect, Stroke, Transform};
+
+// The rendered page fits in this box, in pixels
+const MAX_WIDTH: f32 = 480.0;
+const MAX_HEIGHT: f32 = 640.0;
+// Forms nested deeper than this are not drawn
+const MAX_FORM_DEPTH: usize = 8;
+// Work done for one page at most, so a file whose forms draw each other many
+// times over cannot keep the preview busy: operators interpreted, and forms and
+// images drawn
+const MAX_OPERATIONS: usize = 200_000;
+const MAX_XOBJECTS: usize = 2_000;
+// Larger embedded images are drawn as a grey box instead of being decoded
+const MAX_IMAGE_PIXELS: usize = 16 * 1024 * 1024;
+// Text-only rendering modes that leave nothing on the page (invisible OCR layers, clipping)
+const INVISIBLE_TEXT: [i64; 2] = [3, 7];
+
+/// Draw the first page of `doc` as an image. Fonts embedded in the PDF are not
+/// used; text is drawn with the UI's own fonts at the positions and sizes the
+/// page gives, which is enough to recognise a document at a glance
+pub fn render_first_page(doc: &Document) -> Option<egui::ColorImage> {
+    let (_, &page_id) = doc.get_pages().iter().next()?;
+    let [llx, lly, urx, ury] = media_box(doc, page_id)?;
+    let (width, height) = (urx - llx, ury - lly);
+    if width <= 0.0 || height <= 0.0 {
+        return None;
+    }
+    let scale = (MAX_WIDTH / width).min(MAX_HEIGHT / height);
+    let mut pixmap = Pixmap::new((width * scale).ceil() as u32, (height * scale).ceil() as u32)?;
+    pixmap.fill(Color::WHITE);
+
+    let fonts = PageFonts {
+        sans: FontRef::try_from_slice(epaint_default_fonts::UBUNTU_LIGHT).ok()?,
+        mono: FontRef::try_from_slice(epaint_default_fonts::HACK_REGULAR).ok()?,
+    };
+    let mut resources = Vec::new();
+    if let Ok((inline, ids)) = doc.get_page_resources(page_id) {
+        resources.extend(inline);
+        resources.extend(ids.into_iter().filter_map(|id| doc.get_dictionary(id).ok()));
+    }
+    let content = doc.get_page_content(page_id);
+    let mut page = PageRenderer {
+        doc,
+        fonts: &fonts,
+        pixmap: &mut pixmap,
+        state: GraphicsState::new(Transform::from_row(scale, 0.0, 0.0, -scale, -llx * scale, ury * scale)),
+        saved: Vec::new(),
+        path: PathBuilder::new(),
+        text: TextState::default(),
+        operations: MAX_OPERATIONS,
+        xobjects: MAX_XOBJECTS,
+    };
+    page.run(&content, &resources, 0);
+
+    let size = [pixmap.width() as usize, pixmap.height() as usize];
+    Some(egui::ColorImage::from_rgba_premultiplied(size, pixmap.data()))
+}
+
+// MediaBox of a page, which may be inherited from its parents in the page tree
+fn media_box(doc: &Document, page_id: ObjectId) -> Option<[f32; 4]> {
+    let mut node = doc.get_dictionary(page_id).ok()?;
+    for _ in 0..MAX_FORM_DEPTH {
+        if let Ok(media_box) = node.get_deref(b"MediaBox", doc).and_then(Object::as_array) {
+            let values: Vec<f32> = media_box.iter().filter_map(|v| v.as_float().ok()).collect();
+            let [x0, y0, x1, y1] = values[..] else {
+                return None;
+            };
+            return Some([x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)]);
+        }
+        node = doc.get_dictionary(node.get(b"Parent").and_then(Object::as_reference).ok()?).ok()?;
+    }
+    None
+}
+
+struct PageFonts<'f> {
+    sans: FontRef<'f>,
+    mono: FontRef<'f>,
+}
+
+#[derive(Clone)]
+struct GraphicsState {
+    ctm: Transform,
+    fill: Color,
+    stroke: Color,
+    line_width: f32,
+}
+
+impl GraphicsState {
+    fn new(ctm: Transform) -> Self {
+        GraphicsState { ctm, fill: Color::BLACK, stroke: Color::BLACK, line_width: 1.0 }
+    }
+}
+
+struct TextState<'d> {
+    matrix: Transform,
+    line_matrix: Transform,
+    font: Option<PdfFont<'d>>,
+    size: f32,
+    char_spacing: f32,
+    word_spacing: f32,
+    horizontal_scale: f32,
+    leading: f32,
+    render_mode: i64,
+}
+
+impl Default for TextState<'_> {
+    fn default() -> Self {
+        TextState {
+            matrix: Transform::identity(),
+            line_matrix: Transform::identity(),
+            font: None,
+            size: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scale: 1.0,
+            leading: 0.0,
+            render_mode: 0,
+        }
+    }
+}
+
+// What of a PDF font is needed to place its text
+struct PdfFont<'d> {
+    encoding: Encoding<'d>,
+    /// Simple fonts use one byte per character; composite (Type0) fonts use more
+    simple: bool,
+    first_char: i64,
+    /// Advance of each character from `first_char` on, in thousandths of the font size
+    widths: Vec<f32>,
+    mono: bool,
+}
+
+impl<'d> PdfFont<'d> {
+    fn load(doc: &'d Document, font: &'d Dictionary) -> Option<Self> {
+        let encoding = font.get_font_encoding(doc).ok()?;
+        let simple = font.get(b"Subtype").and_then(Object::as_name).map_or(true, |subtype| subtype != b"Type0");
+        let first_char = font.get(b"FirstChar").and_then(Object::as_i64).unwrap_or(0);
+        let widths = font.get_deref(b"Widths", doc)
+            .and_then(Object::as_array)
+            .map(|widths| widths.iter().map(|w| doc.dereference(w).and_then(|(_, w)| w.as_float()).unwrap_or(0.0)).collect())
+            .unwrap_or_default();
+        let base = font.get(b"BaseFont").and_then(Object::as_name).map(|name| String::from_utf8_lossy(name).to_lowercase()).unwrap_or_default();
+        let mono = base.contains("courier") || base.contains("mono");
+        Some(PdfFont { encoding, simple, first_char, widths, mono })
+    }
+
+    fn width(&self, code: u8) -> Option<f32> {
+        let idx = usize::try_from(i64::from(code) - self.first_char).ok()?;
+        self.widths.get(idx).copied().filter(|&w| w > 0.0).map(|w| w / 1000.0)
+    }
+}
+
+struct PageRenderer<'a, 'd, 'f> {
+    doc: &'d Document,
+    fonts: &'a PageFonts<'f>,
+    pixmap: &'a mut Pixmap,
+    state: GraphicsState,
+    saved: Vec<GraphicsState>,
+    path: PathBuilder,
+    text: TextState<'d>,
+    /// What is left of the page's budget of operators and XObjects
+    operations: usize,
+    xobjects: usize,
+}
+
+impl<'d> PageRenderer<'_, 'd, '_> {
+    // Interpret a content stream; `resources` are searched in order for named fonts and images
+    fn run(&mut self, content: &[u8], resources: &[&'d Dictionary], depth: usize) {
+        let Ok(content) = lopdf::content::Content::decode(content) else {
+            return;
+        };
+        for op in content.operations {
+            if self.operations == 0 {
+                return;
+            }
+            self.operations -= 1;
+            let nums: Vec<f32> = op.operands.iter().filter_map(|o| o.as_float().ok()).collect();
+            let num = |i: usize| nums.get(i).copied().unwrap_or(0.0);
+            match op.operator.as_str() {
+                "q" => self.saved.push(self.state.clone()),
+                "Q" => {
+                    if let Some(state) = self.saved.pop() {
+                        self.state = state;
+                    }
+                }
+                "cm" if nums.len() == 6 => {
+                    self.state.ctm = Transform::from_row(num(0), num(1), num(2), num(3), num(4), num(5)).post_concat(self.state.ctm);
+                }
+                "w" => self.state.line_width = num(0),
+                "g" | "rg" | "k" | "sc" | "scn" => self.state.fill = color(&nums).unwrap_or(self.state.fill),
+                "G" | "RG" | "K" | "SC" | "SCN" => self.state.stroke = color(&nums).unwrap_or(self.state.stroke),
+                "cs" => self.state.fill = Color::BLACK,
+                "CS" => self.state.stroke = Color::BLACK,
+
+                "m" => self.path.move_to(num(0), num(1)),
+                "l" => self.path.line_to(num(0), num(1)),
+                "c" => self.path.cubic_to(num(0), num(1), num(2), num(3), num(4), num(5)),
+                "v" => {
+                    if let Some(current) = self.path.last_point() {
+                        self.path.cubic_to(current.x, current.y, num(0), num(1), num(2), num(3));
+                    }
+                }
+                "y" => self.path.cubic_to(num(0), num(1), num(2), num(3), num(2), num(3)),
+                "h" => self.path.close(),
+                "re" => {
+                    let (x0, y0, x1, y1) = (num(0), num(1), num(0) + num(2), num(1) + num(3));
+                    if let Some(rect) = Rect::from_ltrb(x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)) {
+                        self.path.push_rect(rect);
+                    }
+                }
+                "f" | "F" => self.paint_path(Some(FillRule::Winding), false),
+                "f*" => self.paint_path(Some(FillRule::EvenOdd), false),
+                "S" => self.paint_path(None, true),
+                "s" => {
+                    self.path.close();
+                    self.paint_path(None, true);
+                }
+                "B" => self.paint_path(Some(FillRule::Winding), true),
+                "B*" => self.paint_path(Some(FillRule::EvenOdd), true),
+                "b" | "b*" => {
+                    self.path.close();
+                    let rule = if op.operator == "b" { FillRule::Winding } else { FillRule::EvenOdd };
+                    self.paint_path(Some(rule), true);
+                }
+                // Clipping is not applied; the path is only discarded
+                "n" => self.path.clear(),
+
+                "BT" => {
+                    self.text.matrix = Transform::identity();
+                    self.text.line_matrix = Transform::identity();
+                }
+                "Tf" => {
+                    self.text.size = num(0);
+                    self.text.font = op.operands.first()
+                        .and_then(|name| name.as_name().ok())
+                        .and_then(|name| lookup(self.doc, resources, b"Font", name))
+                        .and_then(|font| font.as_dict().ok())
+                        .and_then(|font| PdfFont::load(self.doc, font));
+                }
+                "Tc" => self.text.char_spacing = num(0),
+                "Tw" => self.text.word_spacing = num(0),
+                "Tz" => self.text.horizontal_scale = num(0) / 100.0,
+                "TL" => self.text.leading = num(0),
+                "Tr" => self.text.render_mode = op.operands.first().and_then(|o| o.as_i64().ok()).unwrap_or(0),
+                "Td" => self.next_line(num(0), num(1)),
+                "TD" => {
+                    self.text.leading = -num(1);
+                    self.next_line(num(0), num(1));
+                }
+                "Tm" if nums.len() == 6 => {
+                    self.text.matrix = Transform::from_row(num(0), num(1), num(2), num(3), num(4), num(5));
+                    self.text.line_matrix = self.text.matrix;
+                }
+                "T*" => self.next_line(0.0, -self.text.leading),
+                "Tj" => self.show_operand(op.operands.first()),
+                "'" => {
+                    self.next_line(0.0, -self.text.leading);
+                    self.show_operand(op.operands.first());
+                }
+                "\"" => {
+                    self.text.word_spacing = num(0);
+                    self.text.char_spacing = num(1);
+                    self.next_line(0.0, -self.text.leading);
+                    self.show_operand(op.operands.get(2));
+                }
+                "TJ" => {
+                    let parts = op.operands.first().and_then(|o| o.as_array().ok()).cloned().unwrap_or_default();
+                    for part in &parts {
+                        match part.as_float() {
+                            Ok(adjust) => self.advance(-adjust / 1000.0 * self.text.size * self.text.horizontal_scale),
+                            Err(_) => self.show_operand(Some(part)),
+                        }
+                    }
+                }
+
+                "Do" => {
+                    let xobject = op.operands.first()
+                        .and_then(|name| name.as_name().ok())
+                        .and_then(|name| lookup(self.doc, resources, b"XObject", name))
+                        .and_then(|xobject| xobject.as_stream().ok());
+                    if let Some(xobject) = xobject {
+                        self.draw_xobject(xobject, resources, depth);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn paint_path(&mut self, fill: Option<FillRule>, stroke: bool) {
+        let Some(path) = std::mem::take(&mut self.path).finish() else {
+            return;
+        };
+        let ctm = self.state.ctm;
+        if let Some(rule) = fill {
+            self.pixmap.fill_path(&path, &paint(self.state.fill), rule, ctm, None);
+        }
+        if stroke {
+            let stroke = Stroke { width: self.state.line_width, ..Stroke::default() };
+            self.pixmap.stroke_path(&path, &paint(self.state.stroke), &stroke, ctm, None);
+        }
+    }
+
+    fn next_line(&mut self, tx: f32, ty: f32) {
+        self.text.line_matrix = Transform::from_translate(tx, ty).post_concat(self.text.line_matrix);
+        self.text.matrix = self.text.line_matrix;
+    }
+
+    fn advance(&mut self, tx: f32) {
+        self.text.matrix = Transform::from_translate(tx, 0.0).post_concat(self.text.matrix);
+    }
+
+    fn show_operand(&mut self, operand: Option<&Object>) {
+        let Some(bytes) = operand.and_then(|o| o.as_str().ok()) else {
+            return;
+        };
+        let Some(font) = self.text.font.take() else {
+            return;
+        };
+        let glyphs = if font.mono { &self.fonts.mono } else { &self.fonts.sans };
+        if font.simple {
+            // One character per byte, each advanced by the width the PDF gives it
+            for &code in bytes {
+                let text = Document::decode_text(&font.encoding, &[code]).unwrap_or_default();
+                let ch = text.chars().next().unwrap_or(' ');
+                let width = font.width(code).unwrap_or_else(|| glyph_advance(glyphs, ch));
+                self.show_char(glyphs, ch, width);
+            }
+        } else {
+            let text = Document::decode_text(&font.encoding, bytes).unwrap_or_default();
+            for ch in text.chars() {
+                self.show_char(glyphs, ch, glyph_advance(glyphs, ch));
+            }
+        }
+        self.text.font = Some(font);
+    }
+
+    // Draw one character at the text position and move past it; `width` is in text space units of one
+    fn show_char(&mut self, glyphs: &FontRef, ch: char, width: f32) {
+        let size = self.text.size;
+        let scale_x = self.text.horizontal_scale;
+        if !ch.is_whitespace() && !INVISIBLE_TEXT.contains(&self.text.render_mode) {
+            if let Some(path) = glyph_path(glyphs, ch) {
+                let em = glyphs.units_per_em().unwrap_or(1000.0);
+                let transform = Transform::from_scale(size * scale_x / em, size / em)
+                    .post_concat(self.text.matrix)
+                    .post_concat(self.state.ctm);
+                self.pixmap.fill_path(&path, &paint(self.state.fill), FillRule::Winding, transform, None);
+            }
+        }
+        let spacing = self.text.char_spacing + if ch == ' ' { self.text.word_spacing } else { 0.0 };
+        self.advance((width * size + spacing) * scale_x);
+    }
+
+    fn draw_xobject(&mut self, xobject: &'d lopdf::Stream, resources: &[&'d Dictionary], depth: usize) {
+        if self.xobjects == 0 {
+            return;
+        }
+        self.xobjects -= 1;
+        let subtype = xobject.dict.get(b"Subtype").and_then(Object::as_name).unwrap_or_default();
+        if subtype == b"Form" && depth < MAX_FORM_DEPTH {
+            let Ok(content) = xobject.decompressed_content() else {
+                return;
+            };
+            let saved = self.state.clone();
+            let matrix: Vec<f32> = xobject.dict.get(b"Matrix")
+                .and_then(Object::as_array)
+                .map(|m| m.iter().filter_map(|v| v.as_float().ok()).collect())
+                .unwrap_or_default();
+            if let [a, b, c, d, e, f] = matrix[..] {
+                self.state.ctm = Transform::from_row(a, b, c, d, e, f).post_concat(self.state.ctm);
+            }
+            let mut form_resources: Vec<&Dictionary> = xobject.dict.get_deref(b"Resources", self.doc)
+                .and_then(Object::as_dict)
+                .into_iter()
+                .collect();
+            form_resources.extend_from_slice(resources);
+            self.run(&content, &form_resources, depth + 1);
+            self.state = saved;
+        } else if subtype == b"Image" {
+            // Images fill the unit square of user space, with their first row at the top
+            let image = decode_image(xobject);
+            let (width, height) = image.as_ref().map_or((1, 1), |image| (image.width(), image.height()));
+            let transform = Transform::from_row(1.0 / width as f32, 0.0, 0.0, -1.0 / height as f32, 0.0, 1.0).post_concat(self.state.ctm);
+            match image {
+                Some(image) => self.pixmap.draw_pixmap(0, 0, image.as_ref(), &PixmapPaint::default(), transform, None),
+                None => {
+                    if let Some(rect) = Rect::from_xywh(0.0, 0.0, 1.0, 1.0) {
+                        self.pixmap.fill_rect(rect, &paint(Color::from_rgba8(200, 200, 200, 255)), transform, None);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// A named resource of a kind ("Font", "XObject") from the first resource dictionary that has it
+fn lookup<'d>(doc: &'d Document, resources: &[&'d Dictionary], kind: &[u8], name: &[u8]) -> Option<&'d Object> {
+    resources.iter()
+        .filter_map(|res| res.get_deref(kind, doc).and_then(Object::as_dict).ok())
+        .find_map(|named| named.get_deref(name, doc).ok())
+}
+
+// Gray, RGB or CMYK by the number of components
+fn color(nums: &[f32]) -> Option<Color> {
+    let (r, g, b) = match *nums {
+        [gray] => (gray, gray, gray),
+        [r, g, b] => (r, g, b),
+        [c, m, y, k] => ((1.0 - c) * (1.0 - k), (1.0 - m) * (1.0 - k), (1.0 - y) * (1.0 - k)),
+        _ => return None,
+    };
+    Color::from_rgba(r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0), 1.0)
+}
+
+fn paint(color: Color) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(color);
+    paint
+}
+
+// Advance of `ch` in the UI font, in text space units of one
+fn glyph_advance(glyphs: &FontRef, ch: char) -> f32 {
+    glyphs.h_advance_unscaled(glyphs.glyph_id(ch)) / glyphs.units_per_em().unwrap_or(1000.0)
+}
+
+// Outline of `ch` in font units, y up like PDF text space
+fn glyph_path(glyphs: &FontRef, ch: char) -> Option<tiny_skia::Path> {
+    let outline = glyphs.outline(glyphs.glyph_id(ch))?;
+    let mut builder = PathBuilder::new();
+    let mut last = None;
+    for curve in &outline.curves {
+        let (start, end) = match *curve {
+            OutlineCurve::Line(p0, p1) => (p0, p1),
+            OutlineCurve::Quad(p0, _, p2) => (p0, p2),
+            OutlineCurve::Cubic(p0, _, _, p3) => (p0, p3),
+        };
+        if last != Some(start) {
+            if last.is_some() {
+                builder.close();
+            }
+            builder.move_to(start.x, start.y);
+        }
+        match *curve {
+            OutlineCurve::Line(_, p1) => builder.line_to(p1.x, p1.y),
+            OutlineCurve::Quad(_, p1, p2) => builder.quad_to(p1.x, p1.y, p2.x, p2.y),
+            OutlineCurve::Cubic(_, p1, p2, p3) => builder.cubic_to(p1.x, p1.y, p2.x, p2.y, p3.x, p3.y),
+        }
+        last = Some(end);
+    }
+    builder.close();
+    builder.finish()
+}
+
+// JPEG images, and uncompressed or deflated 8-bit gray, RGB and CMYK ones
+fn decode_image(xobject: &lopdf::Stream) -> Option<Pixmap> {
+    let dict = &xobject.dict;
+    if dict.get(b"ImageMask").and_then(Object::as_bool).unwrap_or(false) {
+        return None;
+    }
+    let width = usize::try_from(dict.get(b"Width").and_then(Object::as_i64).ok()?).ok()?;
+    let height = usize::try_from(dict.get(b"Height").and_then(Object::as_i64).ok()?).ok()?;
+    if width == 0 || height == 0 || width.saturating_mul(height) > MAX_IMAGE_PIXELS {
+        return None;
+    }
+    let filters = xobject.filters().unwrap_or_default();
+    let rgba = if filters.last().is_some_and(|&f| f == b"DCTDecode") {
+        image::load_from_memory_with_format(&xobject.content, image::ImageFormat::Jpeg).ok()?.to_rgba8().into_raw()
+    } else {
+        if dict.get(b"BitsPerComponent").and_then(Object::as_i64).ok()? != 8 {
+            return None;
+        }
+        let data = if filters.is_empty() { xobject.content.clone() } else { xobject.decompressed_content().ok()? };
+        let components = data.len() / (width * height);
+        data.chunks_exact(components.max(1))
+            .take(width * height)
+            .flat_map(|px| match *px {
+                [gray] => [gray, gray, gray, 255],
+                [r, g, b] => [r, g, b, 255],
+                [c, m, y, k] => {
+                    let ink = |v: u8| ((255 - u16::from(v)) * (255 - u16::from(k)) / 255) as u8;
+                    [ink(c), ink(m), ink(y), 255]
+                }
+                _ => [200, 200, 200, 255],
+            })
+            .collect()
+    };
+    Pixmap::from_vec(rgba, tiny_skia::IntSize::from_wh(width as u32, height as u32)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Stream};
+
+    // A one-page document of 100 by 100 points; `forms` are added as named
+    // form XObjects whose resources also hold every form, so they may draw each other
+    fn document(content: &str, forms: &[(&str, String)]) -> Document {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let form_ids: Vec<ObjectId> = forms.iter().map(|_| doc.new_object_id()).collect();
+        let mut xobjects = Dictionary::new();
+        for ((name, _), &id) in forms.iter().zip(&form_ids) {
+            xobjects.set(name.as_bytes(), id);
+        }
+        let resources_id = doc.add_object(dictionary! { "XObject" => xobjects });
+        for ((_, form), &id) in forms.iter().zip(&form_ids) {
+            let dict = dictionary! { "Type" => "XObject", "Subtype" => "Form", "Resources" => resources_id };
+            doc.objects.insert(id, Object::Stream(Stream::new(dict, form.as_bytes().to_vec())));
+        }
+        let content_id = doc.add_object(Stream::new(Dictionary::new(), content.as_bytes().to_vec()));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+            "Contents" => content_id,
+            "Resources" => resources_id,
+        });
+        doc.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }));
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+        doc
+    }
+
+    // Colour of the rendered pixel at a point of the 100-point page, y up
+    fn pixel(image: &egui::ColorImage, x: f32, y: f32) -> egui::Color32 {
+        let scale = image.size[0] as f32 / 100.0;
+        image[((x * scale) as usize, ((100.0 - y) * scale) as usize)]
+    }
+
+    // Interpret `content` of a page made by `document` and return what is left of the budget
+    fn remaining_budget(doc: &Document, content: &str) -> (usize, usize) {
+        let fonts = PageFonts {
+            sans: FontRef::try_from_slice(epaint_default_fonts::UBUNTU_LIGHT).unwrap(),
+            mono: FontRef::try_from_slice(epaint_default_fonts::HACK_REGULAR).unwrap(),
+        };
+        let mut pixmap = Pixmap::new(10, 10).unwrap();
+        let (_, &page_id) = doc.get_pages().iter().next().unwrap();
+        let (_, ids) = doc.get_page_resources(page_id).unwrap();
+        let resources: Vec<&Dictionary> = ids.into_iter().filter_map(|id| doc.get_dictionary(id).ok()).collect();
+        let mut page = PageRenderer {
+            doc,
+            fonts: &fonts,
+            pixmap: &mut pixmap,
+            state: GraphicsState::new(Transform::identity()),
+            saved: Vec::new(),
+            path: PathBuilder::new(),
+            text: TextState::default(),
+            operations: MAX_OPERATIONS,
+            xobjects: MAX_XOBJECTS,
+        };
+        page.run(content.as_bytes(), &resources, 0);
+        (page.operations, page.xobjects)
+    }
+
+    #[test]
+    fn paths_are_filled_in_their_colour() {
+        let doc = document("0 0 1 rg 10 10 50 50 re f 1 0 0 RG 4 w 80 0 m 80 100 l S", &[]);
+        let image = render_first_page(&doc).unwrap();
+        assert_eq!(pixel(&image, 35.0, 35.0), egui::Color32::from_rgb(0, 0, 255));
+        assert_eq!(pixel(&image, 80.0, 50.0), egui::Color32::from_rgb(255, 0, 0));
+        assert_eq!(pixel(&image, 70.0, 70.0), egui::Color32::WHITE);
+    }
+
+    #[test]
+    fn state_is_restored_and_unbalanced_restores_are_ignored() {
+        let doc = document("Q q 0 1 0 rg 50 0 0 1 0 0 cm Q 0 0 10 100 re f Q", &[]);
+        let image = render_first_page(&doc).unwrap();
+        // The colour and the shift set inside q … Q are undone by its Q
+        assert_eq!(pixel(&image, 5.0, 50.0), egui::Color32::BLACK);
+        assert_eq!(pixel(&image, 55.0, 50.0), egui::Color32::WHITE);
+    }
+
+    #[test]
+    fn forms_draw_with_their_own_matrix() {
+        let mut doc = document("/Box Do", &[("Box", "0 0 1 rg 0 0 10 10 re f".to_string())]);
+        let form = doc.get_object_mut((2, 0)).unwrap().as_stream_mut().unwrap();
+        form.dict.set("Matrix", vec![1.into(), 0.into(), 0.into(), 1.into(), 50.into(), 50.into()]);
+        let image = render_first_page(&doc).unwrap();
+        assert_eq!(pixel(&image, 55.0, 55.0), egui::Color32::from_rgb(0, 0, 255));
+        assert_eq!(pixel(&image, 5.0, 5.0), egui::Color32::WHITE);
+    }
+
+    #[test]
+    fn a_form_that_draws_itself_stops_at_the_depth_limit() {
+        let doc = document("/Loop Do", &[("Loop", "/Loop Do".to_string())]);
+        assert_eq!(remaining_budget(&doc, "/Loop Do"), (MAX_OPERATIONS - MAX_FORM_DEPTH - 1, MAX_XOBJECTS - MAX_FORM_DEPTH - 1));
+    }
+
+    #[test]
+    fn forms_fanning_out_stop_at_the_xobject_budget() {
+        // Ten draws per level, eight levels deep: a hundred million draws without the budget
+        let fan = "/Fan Do ".repeat(10);
+        let doc = document("/Fan Do", &[("Fan", fan)]);
+        let (operations, xobjects) = remaining_budget(&doc, "/Fan Do");
+        assert_eq!(xobjects, 0);
+        assert!(operations > 0);
+    }
+
+    #[test]
+    fn long_content_stops_at_the_operator_budget() {
+        let doc = document("", &[]);
+        let content = "0 0 m ".repeat(MAX_OPERATIONS + 10);
+        assert_eq!(remaining_budget(&doc, &content), (0, MAX_XOBJECTS));
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use crate::format::format_size;
+use crate::mounts::volume_root;
+use crate::scanner::{rehash_files, ScanConfig};
+
+/// A file removed by a delete operation
+#[derive(Clone, Debug)]
+pub struct DeletedFile {
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// A copy that was kept, with the content hash it must still have
+#[derive(Clone, Debug)]
+pub struct SurvivingFile {
+    pub path: PathBuf,
+    /// Empty for results imported without hashes; only presence is checked then
+    pub hash: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PostDeleteReport {
+    pub deleted_confirmed: usize,
+    /// Deleted files that still exist on disk
+    pub still_present: Vec<PathBuf>,
+    pub survivors_verified: usize,
+    /// Kept copies that are missing, unreadable or no longer match their group hash
+    pub survivor_problems: Vec<(PathBuf, &'static str)>,
+    /// Space freed per volume root, from deletions confirmed gone
+    pub reclaimed_by_volume: Vec<(PathBuf, u64)>,
+}
+
+impl PostDeleteReport {
+    pub fn is_clean(&self) -> bool {
+        self.still_present.is_empty() && self.survivor_problems.is_empty()
+    }
+
+    pub fn reclaimed_bytes(&self) -> u64 {
+        self.reclaimed_by_volume.iter().map(|(_, bytes)| bytes).sum()
+    }
+
+    /// One-line summary, also used for the audit log
+    pub fn summary(&self) -> String {
+        format!(
+            "{} deletion(s) confirmed, {} still present; {} kept cop(ies) verified, {} problem(s); {} reclaimed",
+            self.deleted_confirmed,
+            self.still_present.len(),
+            self.survivors_verified,
+            self.survivor_problems.len(),
+            format_size(self.reclaimed_bytes())
+        )
+    }
+
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "{}\n", self.summary());
+        if !self.reclaimed_by_volume.is_empty() {
+            let _ = writeln!(out, "Reclaimed space by volume:");
+            for (volume, bytes) in &self.reclaimed_by_volume {
+                let _ = writeln!(out, "  {:>12}  {}", format_size(*bytes), volume.display());
+            }
+        }
+        if !self.still_present.is_empty() {
+            let _ = writeln!(out, "\nDeleted files still present:");
+            for path in &self.still_present {
+                let _ = writeln!(out, "  {}", path.display());
+            }
+        }
+        if !self.survivor_problems.is_empty() {
+            let _ = writeln!(out, "\nKept copies that failed verification:");
+            for (path, problem) in &self.survivor_problems {
+                let _ = writeln!(out, "  {}: {}", path.display(), problem);
+            }
+        }
+        out
+    }
+}
+
+/// Confirm deleted files are gone and re-hash the copies that were kept
+pub fn verify_deletions(deleted: &[DeletedFile], survivors: &[SurvivingFile], config: &ScanConfig) -> PostDeleteReport {
+    let mut report = PostDeleteReport::default();
+
+    // Resolving a volume walks up the tree, so do it once per directory
+    let mut volumes: HashMap<PathBuf, PathBuf> = HashMap::new();
+    let mut reclaimed: BTreeMap<PathBuf, u64> = BTreeMap::new();
+    for file in deleted {
+        if file.path.symlink_metadata().is_ok() {
+            report.still_present.push(file.path.clone());
+            continue;
+        }
+        report.deleted_confirmed += 1;
+        let dir = file.path.parent().map(PathBuf::from).unwrap_or_default();
+        let volume = volumes.entry(dir).or_insert_with_key(|dir| volume_root(dir)).clone();
+        *reclaimed.entry(volume).or_default() += file.size;
+    }
+    report.reclaimed_by_volume = reclaimed.into_iter().collect();
+
+    let paths: Vec<PathBuf> = survivors.iter().map(|s| s.path.clone()).collect();
+    let hashes = rehash_files(&paths, config);
+    for (survivor, hash) in survivors.iter().zip(hashes) {
+        match hash {
+            None if !survivor.path.exists() => report.survivor_problems.push((survivor.path.clone(), "missing")),
+            None => report.survivor_problems.push((survivor.path.clone(), "unreadable")),
+            Some(hash) if !survivor.hash.is_empty() && hash != survivor.hash => {
+                report.survivor_problems.push((survivor.path.clone(), "content no longer matches"))
+            }
+            Some(_) => report.survivors_verified += 1,
+        }
+    }
+
+    report
+}
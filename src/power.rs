@@ -0,0 +1,200 @@
+use std::fs;
+use std::io;
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use crate::settings::{load_json, save_json};
+
+const POWER_FILE: &str = "power.json";
+
+/// How scans and deletions deal with sleep and battery power, kept across sessions
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerSettings {
+    /// Keep the computer from sleeping while a scan or deletion runs
+    pub keep_awake: bool,
+    /// Pause a running scan while the computer runs on battery
+    pub pause_on_battery: bool,
+    /// Scan with one thread and throttled reads while on battery
+    pub low_impact_on_battery: bool,
+}
+
+impl Default for PowerSettings {
+    fn default() -> Self {
+        Self { keep_awake: true, pause_on_battery: false, low_impact_on_battery: true }
+    }
+}
+
+impl PowerSettings {
+    pub fn load() -> Self {
+        load_json(POWER_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        save_json(POWER_FILE, self)
+    }
+}
+
+/// Power management while the window runs
+#[derive(Default)]
+pub struct PowerState {
+    pub settings: PowerSettings,
+    pub inhibitor: Option<SleepInhibitor>,
+    /// Keeping awake failed for the work in progress; not retried until it ends
+    pub inhibit_failed: bool,
+    /// The running scan was paused because the computer switched to battery
+    pub paused_for_battery: bool,
+    pub last_battery_check: Option<Instant>,
+    /// The last battery reading during the running scan
+    pub on_battery: Option<bool>,
+    /// Low-impact scanning as switched by hand for the running scan, over the battery rule
+    pub low_impact_override: Option<bool>,
+}
+
+impl PowerState {
+    /// Whether the running scan should be low-impact
+    pub fn wants_low_impact(&self) -> bool {
+        self.low_impact_override
+            .unwrap_or(self.settings.low_impact_on_battery && self.on_battery == Some(true))
+    }
+}
+
+/// Keeps the system from sleeping until dropped. On Linux this holds a
+/// systemd-inhibit lock, on macOS a caffeinate assertion and on Windows a
+/// thread execution state.
+pub struct SleepInhibitor {
+    #[cfg(not(windows))]
+    child: std::process::Child,
+}
+
+impl SleepInhibitor {
+    #[cfg(target_os = "linux")]
+    pub fn acquire(reason: &str) -> io::Result<Self> {
+        let child = std::process::Command::new("systemd-inhibit")
+            .args(["--what=idle:sleep", "--who=Dupe Finder", "--mode=block"])
+            .arg(format!("--why={}", reason))
+            // tail exits with this process, ending the lock should it exit without dropping it
+            .args(["tail", &format!("--pid={}", std::process::id()), "-f", "/dev/null"])
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()?;
+        Ok(Self { child })
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn acquire(_reason: &str) -> io::Result<Self> {
+        // -w ends the assertion with this process should it exit without dropping it
+        let child = std::process::Command::new("caffeinate")
+            .args(["-i", "-w", &std::process::id().to_string()])
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()?;
+        Ok(Self { child })
+    }
+
+    #[cfg(windows)]
+    pub fn acquire(_reason: &str) -> io::Result<Self> {
+        // Called from the UI thread, which lives as long as the window
+        if unsafe { windows::SetThreadExecutionState(windows::ES_CONTINUOUS | windows::ES_SYSTEM_REQUIRED) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {})
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+    pub fn acquire(_reason: &str) -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "keeping the system awake is not supported on this platform"))
+    }
+}
+
+impl Drop for SleepInhibitor {
+    #[cfg(not(windows))]
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+
+    #[cfg(windows)]
+    fn drop(&mut self) {
+        unsafe {
+            windows::SetThreadExecutionState(windows::ES_CONTINUOUS);
+        }
+    }
+}
+
+/// Whether the computer runs on battery; None when it cannot tell, e.g. on
+/// a desktop without one
+#[cfg(target_os = "linux")]
+pub fn on_battery() -> Option<bool> {
+    let mut has_battery = false;
+    for entry in fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let read = |name: &str| fs::read_to_string(entry.path().join(name)).map(|text| text.trim().to_string()).unwrap_or_default();
+        match read("type").as_str() {
+            "Mains" if read("online") == "1" => return Some(false),
+            "Battery" => {
+                has_battery = true;
+                if read("status") == "Discharging" {
+                    return Some(true);
+                }
+            }
+            _ => {}
+        }
+    }
+    has_battery.then_some(false)
+}
+
+#[cfg(target_os = "macos")]
+pub fn on_battery() -> Option<bool> {
+    let output = std::process::Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let first = text.lines().next()?;
+    if first.contains("Battery Power") {
+        Some(true)
+    } else if first.contains("AC Power") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(windows)]
+pub fn on_battery() -> Option<bool> {
+    let mut status = windows::SystemPowerStatus::default();
+    if unsafe { windows::GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+    match status.ac_line_status {
+        0 => Some(true),
+        1 => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn on_battery() -> Option<bool> {
+    None
+}
+
+#[cfg(windows)]
+mod windows {
+    pub const ES_CONTINUOUS: u32 = 0x8000_0000;
+    pub const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+
+    #[repr(C)]
+    #[derive(Default)]
+    pub struct SystemPowerStatus {
+        pub ac_line_status: u8,
+        pub battery_flag: u8,
+        pub battery_life_percent: u8,
+        pub system_status_flag: u8,
+        pub battery_life_time: u32,
+        pub battery_full_life_time: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn SetThreadExecutionState(flags: u32) -> u32;
+        pub fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+    }
+}
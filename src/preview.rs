@@ -0,0 +1,84 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use crate::filetype::sniff;
+use crate::pdfpage::render_first_page;
+
+/// Lines shown for a text file or a PDF's first page
+pub const PREVIEW_LINES: usize = 40;
+// Only the head of a text file is read; enough for PREVIEW_LINES of normal text
+const TEXT_PREVIEW_BYTES: u64 = 64 * 1024;
+// Parsing a PDF loads all of it, so very large documents are not previewed
+const MAX_PDF_SIZE: u64 = 64 * 1024 * 1024;
+
+pub enum Preview {
+    Text {
+        lines: Vec<String>,
+        truncated: bool,
+    },
+    Pdf {
+        pages: usize,
+        title: Option<String>,
+        /// Text extracted from the first page; empty for scanned or image-only pages
+        first_page: Vec<String>,
+        /// The first page drawn as an image, when it could be rendered
+        rendered: Option<egui::ColorImage>,
+        truncated: bool,
+    },
+    /// No preview for this file, with the reason
+    Unavailable(String),
+}
+
+// First PREVIEW_LINES lines of `text`, and whether any were left out
+fn head_lines(text: &str) -> (Vec<String>, bool) {
+    let mut lines = text.lines().map(|line| line.trim_end().to_string());
+    let head: Vec<String> = lines.by_ref().take(PREVIEW_LINES).collect();
+    (head, lines.next().is_some())
+}
+
+/// Build a quick preview of a text or PDF file without launching another program
+pub fn load_preview(path: &Path) -> Preview {
+    let mut head = Vec::new();
+    let read = File::open(path).and_then(|file| file.take(TEXT_PREVIEW_BYTES).read_to_end(&mut head));
+    if let Err(e) = read {
+        return Preview::Unavailable(format!("Cannot read file: {}", e));
+    }
+
+    let mime = sniff(&head);
+    if mime == "application/pdf" {
+        return load_pdf(path);
+    }
+    if !mime.starts_with("text/") {
+        return Preview::Unavailable(format!("No preview for {} content", mime));
+    }
+    let (lines, more) = head_lines(&String::from_utf8_lossy(&head));
+    let file_is_longer = std::fs::metadata(path).is_ok_and(|m| m.len() > TEXT_PREVIEW_BYTES);
+    Preview::Text { lines, truncated: more || file_is_longer }
+}
+
+fn load_pdf(path: &Path) -> Preview {
+    if std::fs::metadata(path).is_ok_and(|m| m.len() > MAX_PDF_SIZE) {
+        return Preview::Unavailable("PDF is too large to preview".to_string());
+    }
+    let doc = match lopdf::Document::load(path) {
+        Ok(doc) => doc,
+        Err(e) => return Preview::Unavailable(format!("Cannot parse PDF: {}", e)),
+    };
+    if doc.is_encrypted() {
+        return Preview::Unavailable("PDF is encrypted".to_string());
+    }
+    let pages = doc.get_pages();
+    let title = doc.trailer
+        .get(b"Info")
+        .and_then(|info| doc.dereference(info))
+        .and_then(|(_, info)| info.as_dict())
+        .and_then(|info| info.get(b"Title"))
+        .and_then(|title| title.as_str())
+        .map(|title| String::from_utf8_lossy(title).trim().to_string())
+        .ok()
+        .filter(|title| !title.is_empty());
+    let text = pages.keys().next().and_then(|&first| doc.extract_text(&[first]).ok()).unwrap_or_default();
+    let (first_page, truncated) = head_lines(text.trim());
+    let rendered = render_first_page(&doc);
+    Preview::Pdf { pages: pages.len(), title, first_page, rendered, truncated }
+}
@@ -0,0 +1,124 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use crate::paths::DirId;
+use crate::scanner::{FileInfo, HashGroup};
+
+/// A folder holding this is a version-control checkout
+const CHECKOUT_MARKER: &str = ".git";
+/// A folder holding one of these is a project even outside a checkout
+const MANIFEST_MARKERS: [&str; 2] = ["Cargo.toml", "package.json"];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Marker {
+    Checkout,
+    Manifest,
+    None,
+}
+
+impl Marker {
+    fn of(dir: &Path) -> Self {
+        if dir.join(CHECKOUT_MARKER).exists() {
+            Marker::Checkout
+        } else if MANIFEST_MARKERS.iter().any(|name| dir.join(name).is_file()) {
+            Marker::Manifest
+        } else {
+            Marker::None
+        }
+    }
+}
+
+// The nearest checkout holding `dir`; outside any checkout, the outermost
+// folder with a manifest, so a package's dependencies and workspace members
+// belong to it rather than forming projects of their own
+fn project_root(dir: &Path, markers: &mut HashMap<PathBuf, Marker>) -> Option<PathBuf> {
+    let mut outermost_manifest = None;
+    for ancestor in dir.ancestors() {
+        let marker = *markers.entry(ancestor.to_path_buf()).or_insert_with(|| Marker::of(ancestor));
+        match marker {
+            Marker::Checkout => return Some(ancestor.to_path_buf()),
+            Marker::Manifest => outermost_manifest = Some(ancestor),
+            Marker::None => {}
+        }
+    }
+    outermost_manifest.map(Path::to_path_buf)
+}
+
+/// The projects (checkouts with `.git`, or folders with `Cargo.toml` or
+/// `package.json`) the files of a set of results lie in, detected once and
+/// looked up by folder
+#[derive(Clone, Debug, Default)]
+pub struct Projects {
+    /// Root folder of each project, in the order found
+    pub roots: Vec<PathBuf>,
+    by_dir: HashMap<DirId, Option<usize>>,
+    detected: bool,
+}
+
+impl Projects {
+    pub fn detect<'a>(files: impl IntoIterator<Item = &'a FileInfo>) -> Self {
+        let mut projects = Self { detected: true, ..Self::default() };
+        let mut markers = HashMap::new();
+        let mut indices: HashMap<PathBuf, usize> = HashMap::new();
+        for file in files {
+            let dir = file.path.dir_id();
+            if projects.by_dir.contains_key(&dir) {
+                continue;
+            }
+            let project = project_root(&file.path.parent(), &mut markers).map(|root| {
+                *indices.entry(root.clone()).or_insert_with(|| {
+                    projects.roots.push(root);
+                    projects.roots.len() - 1
+                })
+            });
+            projects.by_dir.insert(dir, project);
+        }
+        projects
+    }
+
+    pub fn is_detected(&self) -> bool {
+        self.detected
+    }
+
+    /// Index in `roots` of the project holding `file`
+    pub fn of(&self, file: &FileInfo) -> Option<usize> {
+        self.by_dir.get(&file.path.dir_id()).copied().flatten()
+    }
+
+    /// Distinct projects holding the given files, in index order
+    pub fn spanned<'a>(&self, files: impl IntoIterator<Item = &'a FileInfo>) -> Vec<usize> {
+        let mut spanned: Vec<usize> = files.into_iter().filter_map(|file| self.of(file)).collect();
+        spanned.sort_unstable();
+        spanned.dedup();
+        spanned
+    }
+
+    /// Folder name of a project, as shown in the results
+    pub fn name(&self, idx: usize) -> String {
+        let root = &self.roots[idx];
+        root.file_name().map_or_else(|| root.display().to_string(), |name| name.to_string_lossy().into_owned())
+    }
+}
+
+/// Split groups whose copies lie in two or more distinct projects: copies
+/// shared between checkouts are usually intentional. The copies within each
+/// project stay a group, as do those outside any project; parts left with
+/// a single file are dropped.
+pub fn separate_projects(groups: Vec<HashGroup>) -> Vec<HashGroup> {
+    let projects = Projects::detect(groups.iter().flat_map(|group| &group.files));
+    let mut separated = Vec::with_capacity(groups.len());
+    for group in groups {
+        if projects.spanned(&group.files).len() < 2 {
+            separated.push(group);
+            continue;
+        }
+        let HashGroup { hash, secondary_hash, files, known } = group;
+        let mut parts: BTreeMap<Option<usize>, Vec<FileInfo>> = BTreeMap::new();
+        for file in files {
+            parts.entry(projects.of(&file)).or_default().push(file);
+        }
+        for files in parts.into_values().filter(|files| files.len() > 1) {
+            separated.push(HashGroup { hash: hash.clone(), secondary_hash: secondary_hash.clone(), files, known: known.clone() });
+        }
+    }
+    separated
+}
@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Strip ` (N)`, e.g. "IMG_1234 (2)" from browsers and Windows Explorer
+fn strip_counter(stem: &str) -> Option<&str> {
+    let inner = stem.strip_suffix(')')?;
+    let open = inner.rfind(" (")?;
+    let digits = &inner[open + 2..];
+    (!digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())).then(|| &stem[..open])
+}
+
+// Strip "Copy of " and "Copy (N) of " (older Windows)
+fn strip_copy_prefix(stem: &str) -> Option<&str> {
+    let lower = stem.to_ascii_lowercase();
+    if lower.starts_with("copy of ") {
+        return Some(&stem["copy of ".len()..]);
+    }
+    let rest = lower.strip_prefix("copy (")?;
+    let close = rest.find(") of ")?;
+    rest[..close].bytes().all(|b| b.is_ascii_digit()).then(|| &stem["copy (".len() + close + ") of ".len()..])
+}
+
+// Strip " - Copy" (Windows), " copy" and " copy N" (macOS), and "-copy"
+fn strip_copy_suffix(stem: &str) -> Option<&str> {
+    let lower = stem.to_ascii_lowercase();
+    let without_number = lower.trim_end_matches(|c: char| c.is_ascii_digit());
+    let numbered = without_number.len() < lower.len() && without_number.ends_with(" copy ");
+    let lower = if numbered { without_number.trim_end() } else { lower.as_str() };
+    [" - copy", " copy", "-copy"]
+        .iter()
+        .find(|suffix| lower.ends_with(*suffix))
+        .map(|suffix| &stem[..lower.len() - suffix.len()])
+}
+
+fn strip_decorations(stem: &str) -> &str {
+    let mut stem = stem;
+    while let Some(stripped) = strip_counter(stem).or_else(|| strip_copy_prefix(stem)).or_else(|| strip_copy_suffix(stem)) {
+        stem = stripped.trim_end();
+    }
+    stem
+}
+
+/// The name a copy had before a browser, file manager or copy tool
+/// decorated it, e.g. "IMG_1234 (2).jpg" -> "IMG_1234.jpg" or
+/// "Copy of report.pdf" -> "report.pdf". None when nothing needs stripping.
+pub fn canonical_name(name: &str) -> Option<String> {
+    // "archive (1).tar.gz" carries the counter before a double extension
+    let dots: Vec<usize> = name.match_indices('.').map(|(i, _)| i).filter(|&i| i > 0).collect();
+    let splits = dots.iter().rev().take(2).copied().chain([name.len()]);
+    for split in splits {
+        let (stem, extension) = name.split_at(split);
+        let stripped = strip_decorations(stem);
+        if stripped.len() < stem.len() {
+            return (!stripped.is_empty()).then(|| format!("{}{}", stripped, extension));
+        }
+    }
+    None
+}
+
+/// One kept file and the name it would get
+#[derive(Clone, Debug)]
+pub struct PlannedRename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    /// Apply this rename; every rename starts selected
+    pub selected: bool,
+}
+
+/// Renames of kept files to their undecorated names, for review before applying
+#[derive(Clone, Debug, Default)]
+pub struct RenamePlan {
+    pub renames: Vec<PlannedRename>,
+    /// Files with a decorated name that cannot take the plain one, with the reason
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+impl RenamePlan {
+    pub fn is_empty(&self) -> bool {
+        self.renames.is_empty() && self.skipped.is_empty()
+    }
+}
+
+/// Plan renaming each of `paths` whose name is decorated. A rename never
+/// takes a name that exists on disk or that another planned rename claims.
+pub fn plan_canonical_renames(paths: &[PathBuf]) -> RenamePlan {
+    let mut plan = RenamePlan::default();
+    let mut claimed = HashSet::new();
+    for path in paths {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()).and_then(canonical_name) else {
+            continue;
+        };
+        let target = path.with_file_name(&name);
+        if target.exists() {
+            plan.skipped.push((path.clone(), format!("{} already exists", name)));
+        } else if !claimed.insert(target.clone()) {
+            plan.skipped.push((path.clone(), format!("another kept file is also renamed to {}", name)));
+        } else {
+            plan.renames.push(PlannedRename { from: path.clone(), to: target, selected: true });
+        }
+    }
+    plan
+}
+
+/// Rename `from` to `to` unless `to` has appeared since the plan was made
+pub fn rename_no_clobber(from: &Path, to: &Path) -> Result<(), String> {
+    if to.exists() {
+        return Err(format!("Skipped {}: {} already exists", from.display(), to.display()));
+    }
+    fs::rename(from, to).map_err(|e| format!("Failed to rename {}: {}", from.display(), e))
+}
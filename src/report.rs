@@ -0,0 +1,528 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::corruption::CorruptionReport;
+use crate::unique::UniqueFile;
+use crate::format::{format_count, format_iso_utc, format_size, format_timestamp};
+use crate::mounts::{free_space, volume_root};
+use crate::DuplicateGroup;
+
+/// A file operation that was actually carried out on disk
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ActionRecord {
+    pub time: SystemTime,
+    pub action: String,
+    pub path: String,
+}
+
+impl ActionRecord {
+    /// A record of an action taken just now, also written to the log file
+    pub fn now(action: &str, path: impl Into<String>) -> Self {
+        let path = path.into();
+        log::info!("{}: {}", action, path);
+        Self { time: SystemTime::now(), action: action.to_string(), path }
+    }
+}
+
+#[cfg(unix)]
+fn user_names() -> &'static HashMap<u32, String> {
+    static USERS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+    USERS.get_or_init(|| {
+        std::fs::read_to_string("/etc/passwd")
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(':');
+                let name = fields.next()?;
+                let uid = fields.nth(1)?.parse().ok()?;
+                Some((uid, name.to_string()))
+            })
+            .collect()
+    })
+}
+
+/// Numeric id of a file's owning user, where the platform has one
+#[cfg(unix)]
+pub fn owner_id(metadata: &std::fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.uid())
+}
+
+#[cfg(not(unix))]
+pub fn owner_id(_metadata: &std::fs::Metadata) -> Option<u32> {
+    None
+}
+
+/// User name for an owner id, or the id itself when it cannot be resolved
+#[cfg(unix)]
+pub fn owner_name(uid: u32) -> String {
+    user_names().get(&uid).cloned().unwrap_or_else(|| uid.to_string())
+}
+
+#[cfg(not(unix))]
+pub fn owner_name(uid: u32) -> String {
+    uid.to_string()
+}
+
+/// Owning user of a file, by name when it can be resolved
+pub fn file_owner(path: &Path) -> Option<String> {
+    owner_id(&std::fs::metadata(path).ok()?).map(owner_name)
+}
+
+struct ReportRow {
+    group: usize,
+    hash: String,
+    secondary_hash: String,
+    path: String,
+    size: u64,
+    modified: Option<SystemTime>,
+    owner: String,
+    critical: bool,
+    sensitive: bool,
+    tags: String,
+    notes: String,
+    planned_action: &'static str,
+}
+
+fn report_rows(groups: &[DuplicateGroup]) -> Vec<ReportRow> {
+    let mut rows = Vec::new();
+    for (idx, group) in groups.iter().enumerate() {
+        for (file, &keep) in group.files.iter().zip(&group.selected) {
+            let path = file.path.to_path_buf();
+            rows.push(ReportRow {
+                group: idx + 1,
+                hash: group.hash.clone(),
+                secondary_hash: group.secondary_hash.clone().unwrap_or_default(),
+                path: path.display().to_string(),
+                size: file.size,
+                modified: file.modified_time,
+                owner: file_owner(&path).unwrap_or_default(),
+                critical: file.is_critical,
+                sensitive: file.is_sensitive,
+                tags: group.tags.join(", "),
+                notes: group.notes.clone(),
+                planned_action: if keep { "keep" } else { "delete" },
+            });
+        }
+    }
+    rows
+}
+
+/// Program and version named in reports
+const GENERATOR: &str = concat!("dupe-finder-gui ", env!("CARGO_PKG_VERSION"));
+
+// SHA-256 of a report's text, to tell a copy handed over from an altered one
+fn report_digest(body: &str) -> String {
+    hex::encode(Sha256::digest(body.as_bytes()))
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Compliance report as CSV: one row per file, followed by executed actions,
+/// and last the program version and the SHA-256 of everything before that
+pub fn compliance_csv(groups: &[DuplicateGroup], log: &[ActionRecord]) -> String {
+    let mut out = String::from("group,hash,sha512,path,size,modified,owner,critical,sensitive,tags,notes,planned_action\n");
+    for row in report_rows(groups) {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            row.group,
+            row.hash,
+            row.secondary_hash,
+            csv_field(&row.path),
+            row.size,
+            modified_label(row.modified),
+            csv_field(&row.owner),
+            row.critical,
+            row.sensitive,
+            csv_field(&row.tags),
+            csv_field(&row.notes),
+            row.planned_action
+        );
+    }
+    if !log.is_empty() {
+        out.push_str("\ntime,action,path\n");
+        for record in log {
+            let _ = writeln!(out, "{},{},{}", format_iso_utc(record.time), csv_field(&record.action), csv_field(&record.path));
+        }
+    }
+    let digest = report_digest(&out);
+    let _ = write!(out, "\ngenerator,sha256_of_report_above\n{},{}\n", GENERATOR, digest);
+    out
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Self-contained HTML version of the compliance report, ending with the
+/// SHA-256 of the text before that line
+pub fn compliance_html(groups: &[DuplicateGroup], log: &[ActionRecord]) -> String {
+    let rows = report_rows(groups);
+    let critical = rows.iter().filter(|r| r.critical).count();
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Duplicate File Compliance Report</title>\n");
+    out.push_str("<style>body{font-family:sans-serif}table{border-collapse:collapse}td,th{border:1px solid #999;padding:2px 6px}\
+                  tr.critical{background:#fdd}td.num{text-align:right}</style></head><body>\n");
+    out.push_str("<h1>Duplicate File Compliance Report</h1>\n");
+    let _ = writeln!(
+        out,
+        "<p>Generated {} by {}. {} group(s), {} file(s), {} critical file(s), {} executed action(s).</p>",
+        format_timestamp(SystemTime::now()),
+        GENERATOR,
+        groups.len(),
+        rows.len(),
+        critical,
+        log.len()
+    );
+    out.push_str("<h2>Duplicate files</h2>\n<table><tr><th>Group</th><th>Hash</th><th>SHA-512</th><th>Path</th><th>Size</th><th>Modified</th>\
+                  <th>Owner</th><th>Critical</th><th>Sensitive</th><th>Tags</th><th>Notes</th><th>Planned action</th></tr>\n");
+    for row in &rows {
+        let _ = writeln!(
+            out,
+            "<tr{}><td class=\"num\">{}</td><td><code>{}</code></td><td><code>{}</code></td><td>{}</td><td class=\"num\">{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            if row.critical { " class=\"critical\"" } else { "" },
+            row.group,
+            html_escape(&row.hash),
+            html_escape(&row.secondary_hash),
+            html_escape(&row.path),
+            format_size(row.size),
+            modified_text(row.modified),
+            html_escape(&row.owner),
+            if row.critical { "yes" } else { "" },
+            if row.sensitive { "yes" } else { "" },
+            html_escape(&row.tags),
+            html_escape(&row.notes),
+            row.planned_action
+        );
+    }
+    out.push_str("</table>\n");
+    if !log.is_empty() {
+        out.push_str("<h2>Executed actions</h2>\n<table><tr><th>Time</th><th>Action</th><th>Path</th></tr>\n");
+        for record in log {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                format_timestamp(record.time),
+                html_escape(&record.action),
+                html_escape(&record.path)
+            );
+        }
+        out.push_str("</table>\n");
+    }
+    let digest = report_digest(&out);
+    let _ = writeln!(out, "<p>SHA-256 of this report up to this line: <code>{}</code></p>", digest);
+    out.push_str("</body></html>\n");
+    out
+}
+
+/// What a deletion would do with one unchecked file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimulatedOutcome {
+    Delete,
+    /// Inside a read-only snapshot
+    SkipSnapshot,
+    /// On a remote store, which deletion only reads
+    SkipRemote,
+    /// Its group keeps no copy and deleting the last copy is not allowed
+    SkipLastCopy,
+}
+
+impl SimulatedOutcome {
+    pub fn label(self) -> &'static str {
+        match self {
+            SimulatedOutcome::Delete => "delete",
+            SimulatedOutcome::SkipSnapshot => "skip: in a snapshot",
+            SimulatedOutcome::SkipRemote => "skip: remote file",
+            SimulatedOutcome::SkipLastCopy => "skip: group keeps no copy",
+        }
+    }
+}
+
+pub struct SimulatedFile {
+    pub group: usize,
+    pub path: PathBuf,
+    /// Space on disk freed by deleting it
+    pub size: u64,
+    pub critical: bool,
+    pub sensitive: bool,
+    pub outcome: SimulatedOutcome,
+}
+
+/// Free space of one volume now and once the simulated deletion has run
+pub struct VolumeProjection {
+    pub root: PathBuf,
+    pub free: Option<u64>,
+    pub freed: u64,
+}
+
+/// A deletion worked out in full without touching anything, for approval
+/// before it is carried out
+pub struct DeletionSimulation {
+    pub created: SystemTime,
+    pub files: Vec<SimulatedFile>,
+    /// Files and bytes deleted per folder, by path
+    pub directories: BTreeMap<PathBuf, (usize, u64)>,
+    pub volumes: Vec<VolumeProjection>,
+}
+
+impl DeletionSimulation {
+    pub fn new(files: Vec<SimulatedFile>) -> Self {
+        let mut directories: BTreeMap<PathBuf, (usize, u64)> = BTreeMap::new();
+        let mut volumes: BTreeMap<PathBuf, u64> = BTreeMap::new();
+        let mut volume_of: HashMap<PathBuf, PathBuf> = HashMap::new();
+        for file in files.iter().filter(|file| file.outcome == SimulatedOutcome::Delete) {
+            let dir = file.path.parent().map(Path::to_path_buf).unwrap_or_default();
+            let volume = volume_of.entry(dir.clone()).or_insert_with_key(|dir| volume_root(dir)).clone();
+            *volumes.entry(volume).or_default() += file.size;
+            let entry = directories.entry(dir).or_default();
+            entry.0 += 1;
+            entry.1 += file.size;
+        }
+        let volumes = volumes
+            .into_iter()
+            .map(|(root, freed)| VolumeProjection { free: free_space(&root), root, freed })
+            .collect();
+        Self { created: SystemTime::now(), files, directories, volumes }
+    }
+
+    fn deleted(&self) -> impl Iterator<Item = &SimulatedFile> {
+        self.files.iter().filter(|file| file.outcome == SimulatedOutcome::Delete)
+    }
+
+    pub fn deleted_count(&self) -> usize {
+        self.deleted().count()
+    }
+
+    pub fn freed(&self) -> u64 {
+        self.deleted().map(|file| file.size).sum()
+    }
+}
+
+fn yes(flag: bool) -> &'static str {
+    if flag { "yes" } else { "" }
+}
+
+/// Simulation report as CSV: every file with its outcome, then per-folder
+/// totals and the free-space projection per volume
+pub fn simulation_csv(sim: &DeletionSimulation) -> String {
+    let mut out = String::from("group,path,size,critical,sensitive,outcome\n");
+    for file in &sim.files {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{}",
+            file.group,
+            csv_field(&file.path.display().to_string()),
+            file.size,
+            file.critical,
+            file.sensitive,
+            file.outcome.label()
+        );
+    }
+    out.push_str("\ndirectory,files_deleted,bytes_freed\n");
+    for (dir, (files, bytes)) in &sim.directories {
+        let _ = writeln!(out, "{},{},{}", csv_field(&dir.display().to_string()), files, bytes);
+    }
+    out.push_str("\nvolume,free_now,bytes_freed,free_after\n");
+    for volume in &sim.volumes {
+        let free = volume.free.map(|free| free.to_string()).unwrap_or_default();
+        let after = volume.free.map(|free| (free + volume.freed).to_string()).unwrap_or_default();
+        let _ = writeln!(out, "{},{},{},{}", csv_field(&volume.root.display().to_string()), free, volume.freed, after);
+    }
+    out
+}
+
+/// Self-contained HTML version of the simulation report
+pub fn simulation_html(sim: &DeletionSimulation) -> String {
+    let critical = sim.deleted().filter(|file| file.critical).count();
+    let sensitive = sim.deleted().filter(|file| file.sensitive).count();
+    let skipped = sim.files.len() - sim.deleted_count();
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Deletion Simulation Report</title>\n");
+    out.push_str("<style>body{font-family:sans-serif}table{border-collapse:collapse}td,th{border:1px solid #999;padding:2px 6px}\
+                  tr.critical{background:#fdd}tr.skipped{color:#777}td.num{text-align:right}</style></head><body>\n");
+    out.push_str("<h1>Deletion Simulation Report</h1>\n");
+    let _ = writeln!(
+        out,
+        "<p>Generated {} in preview: nothing has been deleted. The deletion would remove {} file(s) and free {}; {} unchecked file(s) would be skipped. \
+         {} critical and {} sensitive file(s) are among those deleted.</p>",
+        format_timestamp(sim.created),
+        format_count(sim.deleted_count() as u64),
+        format_size(sim.freed()),
+        format_count(skipped as u64),
+        critical,
+        sensitive
+    );
+
+    out.push_str("<h2>Free space</h2>\n<table><tr><th>Volume</th><th>Free now</th><th>Freed</th><th>Free after</th></tr>\n");
+    for volume in &sim.volumes {
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            html_escape(&volume.root.display().to_string()),
+            volume.free.map_or("unknown".to_string(), format_size),
+            format_size(volume.freed),
+            volume.free.map_or("unknown".to_string(), |free| format_size(free + volume.freed))
+        );
+    }
+    out.push_str("</table>\n");
+
+    out.push_str("<h2>Per folder</h2>\n<table><tr><th>Folder</th><th>Files deleted</th><th>Freed</th></tr>\n");
+    for (dir, (files, bytes)) in &sim.directories {
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            html_escape(&dir.display().to_string()),
+            format_count(*files as u64),
+            format_size(*bytes)
+        );
+    }
+    out.push_str("</table>\n");
+
+    out.push_str("<h2>Files</h2>\n<table><tr><th>Group</th><th>Path</th><th>Size</th><th>Critical</th><th>Sensitive</th><th>Outcome</th></tr>\n");
+    for file in &sim.files {
+        let class = if file.outcome != SimulatedOutcome::Delete {
+            " class=\"skipped\""
+        } else if file.critical {
+            " class=\"critical\""
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            out,
+            "<tr{}><td class=\"num\">{}</td><td>{}</td><td class=\"num\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            class,
+            file.group,
+            html_escape(&file.path.display().to_string()),
+            format_size(file.size),
+            yes(file.critical),
+            yes(file.sensitive),
+            file.outcome.label()
+        );
+    }
+    out.push_str("</table>\n</body></html>\n");
+    out
+}
+
+// CSV dates are ISO 8601 in UTC, so the tools that read them get the same form in any locale
+fn modified_label(modified: Option<SystemTime>) -> String {
+    modified.map(format_iso_utc).unwrap_or_default()
+}
+
+// HTML reports are read by people, so their dates follow the chosen locale
+fn modified_text(modified: Option<SystemTime>) -> String {
+    modified.map(format_timestamp).unwrap_or_default()
+}
+
+/// Corruption check as CSV: one row per suspect pair
+pub fn corruption_csv(report: &CorruptionReport) -> String {
+    let mut out = String::from("size,path_a,hash_a,modified_a,path_b,hash_b,modified_b,same_modified\n");
+    for pair in &report.suspects {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            pair.size,
+            csv_field(&pair.left.path.display().to_string()),
+            pair.left.hash,
+            modified_label(pair.left.modified),
+            csv_field(&pair.right.path.display().to_string()),
+            pair.right.hash,
+            modified_label(pair.right.modified),
+            pair.same_modified()
+        );
+    }
+    out
+}
+
+/// Paths one after another, each followed by `separator` (`\n` or NUL), as
+/// `xargs` reads them. Written as raw bytes where the platform allows, so
+/// names that are not UTF-8 survive. With a newline separator, paths
+/// holding a newline cannot be told apart and are left out; their number is
+/// returned alongside.
+pub fn path_list(paths: &[PathBuf], separator: u8) -> (Vec<u8>, usize) {
+    let mut out = Vec::new();
+    let mut left_out = 0;
+    for path in paths {
+        #[cfg(unix)]
+        let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+        #[cfg(not(unix))]
+        let bytes = path.to_string_lossy().into_owned().into_bytes();
+        if bytes.contains(&separator) || bytes.contains(&0) {
+            left_out += 1;
+            continue;
+        }
+        out.extend_from_slice(&bytes);
+        out.push(separator);
+    }
+    (out, left_out)
+}
+
+/// Uniqueness report as CSV: one row per file with no other copy
+pub fn unique_csv(files: &[UniqueFile]) -> String {
+    let mut out = String::from("size,path,modified\n");
+    for file in files {
+        let _ = writeln!(out, "{},{},{}", file.size, csv_field(&file.path.display().to_string()), modified_label(file.modified));
+    }
+    out
+}
+
+/// Self-contained HTML version of the corruption check
+pub fn corruption_html(report: &CorruptionReport) -> String {
+    let likely = report.suspects.iter().filter(|pair| pair.same_modified()).count();
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Corruption Check Report</title>\n");
+    out.push_str("<style>body{font-family:sans-serif}table{border-collapse:collapse}td,th{border:1px solid #999;padding:2px 6px}\
+                  tr.likely{background:#fdd}td.num{text-align:right}code{font-size:85%}</style></head><body>\n");
+    out.push_str("<h1>Corruption Check Report</h1>\n");
+    let _ = writeln!(
+        out,
+        "<p>Generated {}. A: {}<br>B: {}</p>\n<p>{} file(s) have a counterpart of the same name, size and content. \
+         {} pair(s) share name and size but not content; in {} of them the modification times also agree, which points to corruption rather than an edit.</p>",
+        format_timestamp(SystemTime::now()),
+        html_escape(&report.left.display().to_string()),
+        html_escape(&report.right.display().to_string()),
+        format_count(report.matched as u64),
+        format_count(report.suspects.len() as u64),
+        format_count(likely as u64)
+    );
+    out.push_str("<table><tr><th>Size</th><th>A</th><th>Modified</th><th>B</th><th>Modified</th></tr>\n");
+    for pair in &report.suspects {
+        let class = if pair.same_modified() { " class=\"likely\"" } else { "" };
+        let _ = writeln!(
+            out,
+            "<tr{}><td class=\"num\">{}</td><td>{}<br><code>{}</code></td><td>{}</td><td>{}<br><code>{}</code></td><td>{}</td></tr>",
+            class,
+            format_size(pair.size),
+            html_escape(&pair.left.path.display().to_string()),
+            pair.left.hash,
+            modified_text(pair.left.modified),
+            html_escape(&pair.right.path.display().to_string()),
+            pair.right.hash,
+            modified_text(pair.right.modified)
+        );
+    }
+    out.push_str("</table>\n");
+    if !report.errors.is_empty() {
+        out.push_str("<h2>Unreadable files</h2>\n<ul>\n");
+        for error in &report.errors {
+            let _ = writeln!(out, "<li>{}</li>", html_escape(error));
+        }
+        out.push_str("</ul>\n");
+    }
+    out.push_str("</body></html>\n");
+    out
+}
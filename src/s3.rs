@@ -0,0 +1,486 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use crate::format::{format_compact_utc, parse_utc_timestamp};
+use crate::scanner::ScanConfig;
+use crate::source::{EntryKind, FileSource, RemoteSource, SourceEntry, SourceMetadata};
+
+// SHA-256 of an empty body; no request sent here carries one
+const EMPTY_PAYLOAD_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+// Between reads of a response, so a stalled download fails instead of hanging the scan
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+pub const DEFAULT_READERS: usize = 8;
+pub const MAX_READERS: usize = 32;
+
+/// Cheaper storage classes duplicates can be moved to instead of being deleted
+pub const STORAGE_CLASSES: [&str; 5] = ["STANDARD_IA", "ONEZONE_IA", "GLACIER_IR", "GLACIER", "DEEP_ARCHIVE"];
+
+/// Bucket, credentials and options for one S3 or S3-compatible bucket; never saved
+#[derive(Clone, Debug)]
+pub struct S3Login {
+    /// Empty for AWS, otherwise the URL of an S3-compatible service such as MinIO
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    /// Key prefix to scan, e.g. "photos/2023"; empty scans the whole bucket
+    pub prefix: String,
+    pub access_key: String,
+    pub secret_key: String,
+    /// Only needed for temporary credentials
+    pub session_token: String,
+    /// Group objects with matching single-part ETags without downloading them
+    pub trust_etags: bool,
+    /// Objects downloaded at once when contents have to be hashed
+    pub readers: usize,
+}
+
+impl Default for S3Login {
+    fn default() -> Self {
+        let env = |name: &str| std::env::var(name).unwrap_or_default();
+        let region = std::env::var("AWS_REGION").or_else(|_| std::env::var("AWS_DEFAULT_REGION")).unwrap_or_else(|_| "us-east-1".to_string());
+        Self {
+            endpoint: env("AWS_ENDPOINT_URL"),
+            region,
+            bucket: String::new(),
+            prefix: String::new(),
+            access_key: env("AWS_ACCESS_KEY_ID"),
+            secret_key: env("AWS_SECRET_ACCESS_KEY"),
+            session_token: env("AWS_SESSION_TOKEN"),
+            trust_etags: true,
+            readers: DEFAULT_READERS,
+        }
+    }
+}
+
+impl S3Login {
+    /// The scan root for the chosen prefix, e.g. "s3://my-bucket/photos/2023"
+    pub fn root(&self) -> String {
+        match self.prefix.trim().trim_matches('/') {
+            "" => format!("s3://{}", self.bucket.trim()),
+            prefix => format!("s3://{}/{}", self.bucket.trim(), prefix),
+        }
+    }
+}
+
+/// One object as listed by the bucket
+#[derive(Clone, Debug)]
+struct S3Object {
+    size: u64,
+    etag: String,
+    modified: Option<SystemTime>,
+}
+
+impl S3Object {
+    /// The ETag of an object uploaded in one part is the MD5 of its contents.
+    /// Multipart ETags depend on the part size, so those objects are hashed instead.
+    fn content_digest(&self) -> Option<String> {
+        let etag = self.etag.trim_matches('"');
+        (etag.len() == 32 && etag.bytes().all(|b| b.is_ascii_hexdigit())).then(|| format!("md5:{}", etag.to_ascii_lowercase()))
+    }
+}
+
+/// One page of a listing, with the token that fetches the next page
+struct ListPage {
+    objects: Vec<(String, S3Object)>,
+    next: Option<String>,
+}
+
+/// What to do with the unchecked duplicates that live in a bucket
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum S3Action {
+    #[default]
+    Delete,
+    /// Keep the object but move it to a cheaper storage class
+    StorageClass(&'static str),
+}
+
+impl S3Action {
+    pub fn all() -> impl Iterator<Item = S3Action> {
+        std::iter::once(S3Action::Delete).chain(STORAGE_CLASSES.into_iter().map(S3Action::StorageClass))
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            S3Action::Delete => "Delete".to_string(),
+            S3Action::StorageClass(class) => format!("Move to {}", class),
+        }
+    }
+}
+
+/// Percent-encode for a canonical request: everything but unreserved characters
+pub fn uri_encode(text: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+// The texts of each <tag>…</tag> in `xml`, in order. S3's listings are flat
+// enough that this needs no XML parser.
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let (open, close) = (format!("<{}>", tag), format!("</{}>", tag));
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        let Some(end) = after.find(&close) else {
+            break;
+        };
+        found.push(&after[..end]);
+        rest = &after[end + close.len()..];
+    }
+    found
+}
+
+fn xml_element(xml: &str, tag: &str) -> Option<String> {
+    xml_elements(xml, tag).first().map(|text| xml_unescape(text))
+}
+
+fn xml_unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        unescaped.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                unescaped.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+fn describe_error(error: ureq::Error) -> String {
+    match error {
+        ureq::Error::Status(code, response) => {
+            let status = response.status_text().to_string();
+            let body = response.into_string().unwrap_or_default();
+            match xml_element(&body, "Message").or_else(|| xml_element(&body, "Code")) {
+                Some(message) => format!("{} {}: {}", code, status, message),
+                None => format!("{} {}", code, status),
+            }
+        }
+        ureq::Error::Transport(transport) => transport.to_string(),
+    }
+}
+
+/// A connected bucket. Objects appear in scans as "s3://bucket/key".
+pub struct S3Bucket {
+    login: S3Login,
+    agent: ureq::Agent,
+    prefix: PathBuf,
+    /// Objects seen by listings, so a scan needs no request per object to size it
+    listed: Mutex<HashMap<String, S3Object>>,
+    /// ETags as the last scan listed them, which an object must still have to be changed
+    scanned: Mutex<HashMap<String, String>>,
+}
+
+impl fmt::Debug for S3Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Bucket").field("prefix", &self.prefix).field("endpoint", &self.login.endpoint).finish()
+    }
+}
+
+impl S3Bucket {
+    /// Check the credentials and the bucket with a one-object listing
+    pub fn connect(login: &S3Login) -> Result<Self, String> {
+        let mut login = login.clone();
+        login.bucket = login.bucket.trim().to_string();
+        login.endpoint = login.endpoint.trim().trim_end_matches('/').to_string();
+        let agent = ureq::AgentBuilder::new().timeout_connect(CONNECT_TIMEOUT).timeout_read(READ_TIMEOUT).build();
+        let bucket = Self { prefix: PathBuf::from(format!("s3://{}", login.bucket)), login, agent, listed: Mutex::default(), scanned: Mutex::default() };
+        bucket.list_page("", None, Some(1))?;
+        Ok(bucket)
+    }
+
+    fn key_for(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.prefix).ok()?;
+        Some(relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.prefix.join(key)
+    }
+
+    // Origin, Host header and request path. AWS is addressed by virtual host,
+    // other services by path, which is what S3-compatible servers expect.
+    fn locate(&self, key: &str) -> (String, String, String) {
+        if self.login.endpoint.is_empty() {
+            let host = format!("{}.s3.{}.amazonaws.com", self.login.bucket, self.login.region);
+            return (format!("https://{}", host), host, format!("/{}", uri_encode(key, false)));
+        }
+        let without_scheme = self.login.endpoint.split_once("://").map_or(self.login.endpoint.as_str(), |(_, rest)| rest);
+        let host = without_scheme.split('/').next().unwrap_or(without_scheme).to_string();
+        let origin = self.login.endpoint.clone();
+        (origin, host, format!("/{}/{}", uri_encode(&self.login.bucket, false), uri_encode(key, false)))
+    }
+
+    /// The Authorization header of a request, signed with AWS Signature
+    /// Version 4. `signed` holds the headers to sign, lowercased and sorted.
+    fn authorization(&self, method: &str, path: &str, query: &str, signed: &[(String, String)], amz_date: &str) -> String {
+        let date = &amz_date[..8];
+        let canonical_headers: String = signed.iter().map(|(name, value)| format!("{}:{}\n", name, value)).collect();
+        let signed_names = signed.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(";");
+        let canonical_request = format!("{}\n{}\n{}\n{}\n{}\n{}", method, path, query, canonical_headers, signed_names, EMPTY_PAYLOAD_SHA256);
+        let scope = format!("{}/{}/s3/aws4_request", date, self.login.region);
+        let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, hex::encode(Sha256::digest(canonical_request.as_bytes())));
+        let signing_key = [date, self.login.region.as_str(), "s3", "aws4_request"]
+            .iter()
+            .fold(format!("AWS4{}", self.login.secret_key).into_bytes(), |key, part| hmac_sha256(&key, part));
+        let signature = hex::encode(hmac_sha256(&signing_key, &string_to_sign));
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.login.access_key, scope, signed_names, signature
+        )
+    }
+
+    /// Send a signed request
+    fn request(&self, method: &str, key: &str, query: &[(&str, String)], headers: &[(&str, String)]) -> Result<ureq::Response, String> {
+        let (origin, host, path) = self.locate(key);
+        let amz_date = format_compact_utc(SystemTime::now());
+
+        let mut query: Vec<(String, String)> = query.iter().map(|(name, value)| (uri_encode(name, true), uri_encode(value, true))).collect();
+        query.sort();
+        let query = query.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<_>>().join("&");
+
+        let mut signed: Vec<(String, String)> = vec![
+            ("host".to_string(), host),
+            ("x-amz-content-sha256".to_string(), EMPTY_PAYLOAD_SHA256.to_string()),
+            ("x-amz-date".to_string(), amz_date.clone()),
+        ];
+        if !self.login.session_token.is_empty() {
+            signed.push(("x-amz-security-token".to_string(), self.login.session_token.clone()));
+        }
+        signed.extend(headers.iter().map(|(name, value)| (name.to_ascii_lowercase(), value.trim().to_string())));
+        signed.sort();
+        let authorization = self.authorization(method, &path, &query, &signed, &amz_date);
+
+        let url = if query.is_empty() { format!("{}{}", origin, path) } else { format!("{}{}?{}", origin, path, query) };
+        let mut request = self.agent.request(method, &url).set("Authorization", &authorization);
+        for (name, value) in signed.iter().filter(|(name, _)| name != "host") {
+            request = request.set(name, value);
+        }
+        request.call().map_err(describe_error)
+    }
+
+    /// One page of the objects under `key_prefix`
+    fn list_page(&self, key_prefix: &str, token: Option<&str>, max_keys: Option<usize>) -> Result<ListPage, String> {
+        let mut query = vec![("list-type", "2".to_string()), ("prefix", key_prefix.to_string())];
+        if let Some(token) = token {
+            query.push(("continuation-token", token.to_string()));
+        }
+        if let Some(max_keys) = max_keys {
+            query.push(("max-keys", max_keys.to_string()));
+        }
+        let body = self.request("GET", "", &query, &[])?.into_string().map_err(|e| e.to_string())?;
+        let objects = xml_elements(&body, "Contents")
+            .into_iter()
+            .filter_map(|contents| {
+                let key = xml_element(contents, "Key")?;
+                let object = S3Object {
+                    size: xml_element(contents, "Size")?.parse().ok()?,
+                    etag: xml_element(contents, "ETag").unwrap_or_default(),
+                    modified: xml_element(contents, "LastModified").and_then(|text| parse_utc_timestamp(&text)),
+                };
+                Some((key, object))
+            })
+            .collect();
+        let next = (xml_element(&body, "IsTruncated").as_deref() == Some("true"))
+            .then(|| xml_element(&body, "NextContinuationToken"))
+            .flatten();
+        Ok(ListPage { objects, next })
+    }
+
+    fn stat(&self, key: &str) -> Result<S3Object, String> {
+        if let Some(object) = self.listed.lock().unwrap().get(key) {
+            return Ok(object.clone());
+        }
+        // A listing limited to the key itself gives the same fields as the scan's listings
+        let object = self.list_page(key, None, Some(1))?
+            .objects
+            .into_iter()
+            .find(|(listed, _)| listed == key)
+            .map(|(_, object)| object)
+            .ok_or_else(|| format!("{} does not exist", key))?;
+        self.listed.lock().unwrap().insert(key.to_string(), object.clone());
+        Ok(object)
+    }
+
+    /// Check that the object at `path` still has `size` and the ETag the last scan listed
+    pub fn check_unchanged(&self, path: &Path, size: u64) -> Result<(), String> {
+        let key = self.key_for(path).ok_or_else(|| format!("{} is not in this bucket", path.display()))?;
+        let scanned = self.scanned.lock().unwrap().get(&key).cloned()
+            .ok_or_else(|| "not listed by a scan since the bucket was connected".to_string())?;
+        self.listed.lock().unwrap().remove(&key);
+        let object = self.stat(&key)?;
+        if object.size != size || object.etag != scanned {
+            return Err("changed since scan".to_string());
+        }
+        Ok(())
+    }
+
+    /// Delete the object at `path`, or move it to another storage class
+    pub fn apply(&self, path: &Path, action: S3Action) -> Result<(), String> {
+        let key = self.key_for(path).ok_or_else(|| format!("{} is not in this bucket", path.display()))?;
+        match action {
+            S3Action::Delete => self.request("DELETE", &key, &[], &[]).map(|_| ())?,
+            S3Action::StorageClass(class) => {
+                // Copying an object onto itself is how S3 changes its storage class
+                let source = format!("/{}/{}", self.login.bucket, uri_encode(&key, false));
+                let headers = [
+                    ("x-amz-copy-source", source),
+                    ("x-amz-storage-class", class.to_string()),
+                    ("x-amz-metadata-directive", "COPY".to_string()),
+                ];
+                self.request("PUT", &key, &[], &headers).map(|_| ())?
+            }
+        }
+        self.listed.lock().unwrap().remove(&key);
+        Ok(())
+    }
+}
+
+/// Lists the objects under a prefix one page at a time as the scan asks for entries
+struct S3Walk<'a> {
+    bucket: &'a S3Bucket,
+    key_prefix: String,
+    include_hidden: bool,
+    max_depth: Option<usize>,
+    token: Option<String>,
+    finished: bool,
+    pending: Vec<SourceEntry>,
+}
+
+impl Iterator for S3Walk<'_> {
+    type Item = SourceEntry;
+
+    fn next(&mut self) -> Option<SourceEntry> {
+        loop {
+            if let Some(entry) = self.pending.pop() {
+                return Some(entry);
+            }
+            if self.finished {
+                return None;
+            }
+            let Ok(ListPage { objects, next }) = self.bucket.list_page(&self.key_prefix, self.token.as_deref(), None) else {
+                self.finished = true;
+                continue;
+            };
+            self.finished = next.is_none();
+            self.token = next;
+            let mut listed = self.bucket.listed.lock().unwrap();
+            let mut scanned = self.bucket.scanned.lock().unwrap();
+            for (key, object) in objects {
+                let relative = &key[self.key_prefix.len().min(key.len())..];
+                // Keys ending in "/" are folder markers made by consoles, not files
+                if relative.is_empty() || relative.ends_with('/') {
+                    continue;
+                }
+                if self.max_depth.is_some_and(|max| relative.split('/').count() > max) {
+                    continue;
+                }
+                if !self.include_hidden && relative.split('/').any(|part| part.starts_with('.')) {
+                    continue;
+                }
+                self.pending.push(SourceEntry { path: self.bucket.path_for(&key), kind: EntryKind::File });
+                scanned.insert(key.clone(), object.etag.clone());
+                listed.insert(key, object);
+            }
+            self.pending.reverse();
+        }
+    }
+}
+
+impl FileSource for S3Bucket {
+    fn list<'a>(&'a self, root: &Path, max_depth: Option<usize>, config: &'a ScanConfig) -> Box<dyn Iterator<Item = SourceEntry> + 'a> {
+        let Some(key) = self.key_for(root) else {
+            return Box::new(std::iter::empty());
+        };
+        let key_prefix = if key.is_empty() { key } else { format!("{}/", key) };
+        Box::new(S3Walk {
+            bucket: self,
+            key_prefix,
+            include_hidden: config.include_hidden,
+            max_depth,
+            token: None,
+            finished: false,
+            pending: vec![SourceEntry { path: root.to_path_buf(), kind: EntryKind::Dir }],
+        })
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<SourceMetadata> {
+        let key = self.key_for(path).ok_or_else(|| io::Error::other(format!("{} is not in this bucket", path.display())))?;
+        let object = self.stat(&key).map_err(io::Error::other)?;
+        Ok(SourceMetadata {
+            size: object.size,
+            modified: object.modified,
+            digest: if self.login.trust_etags { object.content_digest() } else { None },
+            ..SourceMetadata::default()
+        })
+    }
+
+    fn open(&self, path: &Path) -> io::Result<(Box<dyn Read + Send>, u64)> {
+        let key = self.key_for(path).ok_or_else(|| io::Error::other(format!("{} is not in this bucket", path.display())))?;
+        let response = self.request("GET", &key, &[], &[]).map_err(io::Error::other)?;
+        let size = response.header("Content-Length").and_then(|length| length.parse().ok()).unwrap_or(0);
+        Ok((Box::new(response.into_reader()), size))
+    }
+
+    fn max_readers(&self) -> Option<usize> {
+        Some(self.login.readers.clamp(1, MAX_READERS))
+    }
+}
+
+impl RemoteSource for S3Bucket {
+    fn prefix(&self) -> &Path {
+        &self.prefix
+    }
+
+    fn label(&self) -> String {
+        match self.login.endpoint.as_str() {
+            "" => format!("{} ({})", self.prefix.display(), self.login.region),
+            endpoint => format!("{} ({})", self.prefix.display(), endpoint),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use std::fs;
+use std::path::PathBuf;
+use crate::settings::data_dir;
+
+// Written when safe mode is turned off, so later sessions start without it
+const DISENGAGED_FILE: &str = "safe-mode-off";
+
+fn disengaged_marker() -> Option<PathBuf> {
+    Some(data_dir()?.join(DISENGAGED_FILE))
+}
+
+/// Whether a session starts in safe mode: until the user turns it off once,
+/// and again after they turn it back on
+pub fn starts_enabled() -> bool {
+    !disengaged_marker().is_some_and(|marker| marker.exists())
+}
+
+/// Make later sessions start the way this one was left. Without a data
+/// directory every session simply starts in safe mode.
+pub fn remember(enabled: bool) {
+    let Some(marker) = disengaged_marker() else {
+        return;
+    };
+    if enabled {
+        let _ = fs::remove_file(marker);
+    } else {
+        if let Some(dir) = marker.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(marker, b"");
+    }
+}
@@ -1,25 +1,127 @@
-use std::collections::HashMap;
-use std::fs::File;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::io::{self, Read};
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::backtrace::Backtrace;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
 use walkdir::{DirEntry, WalkDir};
-use sha2::{Sha256, Digest};
+use sha2::{Sha256, Sha512, Digest};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use crate::attrs::xattr_names;
+use crate::consolidate::TEMP_LINK_SUFFIX;
+use crate::emptydirs::find_empty_dirs;
+use crate::filetype::detect_type;
+use crate::hashcache::{CacheTrust, FileStamp, HashCache};
+use crate::hashqueue::{run_queue, ScanControl, Workers};
+use crate::knownfiles::{KnownFiles, KnownOutcome};
+use crate::mounts::pseudo_mount_points;
+use crate::paths::InternedPath;
+use crate::projects::separate_projects;
+use crate::source::{EntryKind, FileId, FileSource, LocalSource};
+use crate::sparse::SparseMode;
+use crate::symlinks::{analyze_links, SymlinkReport};
+use crate::sidecar::Sidecars;
+use crate::snapshots::{is_in_snapshot, is_snapshot_dir};
+use crate::trash::{is_in_trash, is_trash_dir, TrashMode};
+use crate::unique::UniqueFile;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FileInfo {
-    pub path: PathBuf,
+    pub path: InternedPath,
+    /// SHA-256 of the contents, as hashed when the file joined its group;
+    /// empty for results imported from older exports
+    #[serde(default)]
+    pub hash: String,
     pub size: u64,
     pub modified_time: Option<SystemTime>,
+    /// Creation (birth) time, where the filesystem records one
+    #[serde(default)]
+    pub created_time: Option<SystemTime>,
     pub is_critical: bool,
+    /// File name matches one of the configured sensitive patterns
+    #[serde(default)]
+    pub is_sensitive: bool,
+    /// Set when the file on disk no longer matches the size/mtime recorded at scan time
+    #[serde(default)]
+    pub stale: bool,
+    /// Why the last attempt to delete this file failed
+    #[serde(skip)]
+    pub delete_error: Option<String>,
+    /// File name matches one of the junk copy patterns, e.g. "report (1).pdf"
+    #[serde(default)]
+    pub is_junk_copy: bool,
+    /// MIME type sniffed from the file's content when type detection is enabled
+    #[serde(default)]
+    pub detected_type: Option<String>,
+    /// Bytes allocated on disk when the file is sparse; None when fully allocated
+    #[serde(default)]
+    pub allocated_size: Option<u64>,
+    /// Numeric id of the owning user, where the platform has one
+    #[serde(default)]
+    pub owner: Option<u32>,
+    /// Permission bits, where the source has them
+    #[serde(default)]
+    pub mode: Option<u32>,
+    /// Names of the extended attributes set on the file
+    #[serde(default)]
+    pub xattrs: Vec<String>,
+    /// Device and inode, where the source has them
+    #[serde(default)]
+    pub file_id: Option<FileId>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileStatus {
+    Unchanged,
+    Changed,
+    Missing,
+}
+
+impl FileInfo {
+    pub fn is_sparse(&self) -> bool {
+        self.allocated_size.is_some()
+    }
+    
+    /// Space freed by deleting the file: the allocated size for sparse files
+    pub fn disk_size(&self) -> u64 {
+        self.allocated_size.unwrap_or(self.size)
+    }
+    
+    /// Compare the recorded size and modification time against the file on disk
+    pub fn check_status(&self) -> FileStatus {
+        match get_file_metadata(&self.path.to_path_buf()) {
+            Ok((size, modified)) if size == self.size && modified == self.modified_time => FileStatus::Unchanged,
+            Ok(_) => FileStatus::Changed,
+            Err(_) => FileStatus::Missing,
+        }
+    }
+}
+
+/// Files sharing the same SHA-256 content hash
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HashGroup {
+    pub hash: String,
+    /// SHA-512 of the same content, when dual hashing was enabled for the scan
+    #[serde(default)]
+    pub secondary_hash: Option<String>,
+    pub files: Vec<FileInfo>,
+    /// Name of the known-files list that flags this content
+    #[serde(default)]
+    pub known: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScanProgress {
     pub current: usize,
     pub total: usize,
+    /// All files found by discovery; during hashing `total` only counts the
+    /// candidates that share a size with another file
+    #[serde(default)]
+    pub discovered: usize,
     pub current_file: String,
     pub phase: ScanPhase,
 }
@@ -32,19 +134,221 @@ pub enum ScanPhase {
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScanConfig {
+    /// Read buffer for hashing when `auto_buffer` is off
     pub buffer_size: usize,
+    /// Pick the read buffer per file from its size instead of using `buffer_size`
+    #[serde(default = "default_auto_buffer")]
+    pub auto_buffer: bool,
     pub include_hidden: bool,
     pub min_file_size: u64,
     pub max_threads: Option<usize>,
+    pub detect_file_types: bool,
+    /// Don't descend into other mounted filesystems below the scan root
+    pub same_filesystem_only: bool,
+    /// Skip /proc, /sys, /dev and other virtual filesystems
+    pub skip_pseudo_filesystems: bool,
+    /// Mount points skipped for `skip_pseudo_filesystems`, read once when a
+    /// scan starts; each walk reads the mount table itself when not attached
+    #[serde(skip)]
+    pub pseudo_mounts: Option<Arc<HashSet<PathBuf>>>,
+    /// File name globs (`*` and `?`, case-insensitive) flagging sensitive files
+    pub sensitive_patterns: Vec<String>,
+    /// File name globs marking leftover copies (`* (1).*`, `*.bak`, ...)
+    #[serde(default = "default_junk_copy_patterns")]
+    pub junk_copy_patterns: Vec<String>,
+    /// Directories managed by the tool itself (e.g. the consolidation store);
+    /// scanning them would report the tool's own copies as duplicates
+    #[serde(skip)]
+    pub excluded_dirs: Vec<PathBuf>,
+    /// Also group source files that match after normalizing whitespace and comments
+    #[serde(default)]
+    pub near_duplicate_sources: bool,
+    /// Minimum estimated similarity (0.0 - 1.0) for near-duplicate source files
+    #[serde(default = "default_near_duplicate_threshold")]
+    pub near_duplicate_threshold: f64,
+    /// Whether trash / recycle bin folders are scanned, skipped or reported separately
+    #[serde(default)]
+    pub trash_mode: TrashMode,
+    /// Compute SHA-512 alongside SHA-256 and group only files matching on both
+    #[serde(default)]
+    pub dual_hash: bool,
+    /// Measure throughput at the start of hashing and pick buffer size and
+    /// thread count automatically
+    #[serde(default)]
+    pub auto_tune: bool,
+    /// Whether sparse files are hashed normally, listed last or skipped
+    #[serde(default)]
+    pub sparse_files: SparseMode,
+    /// Reuse digests of files whose size and modification time are unchanged;
+    /// off by default, as a same-size rewrite that keeps the time stamp would
+    /// go unnoticed
+    #[serde(default = "default_use_hash_cache")]
+    pub use_hash_cache: bool,
+    /// Cache consulted and filled when `use_hash_cache` is set; attached by
+    /// the caller at scan time
+    #[serde(skip)]
+    pub hash_cache: Option<Arc<HashCache>>,
+    /// Digests a time-limited scan computed, kept apart from the hash cache
+    /// so continuing the scan skips those files even with the cache off
+    #[serde(skip)]
+    pub checkpoint: Option<Arc<HashCache>>,
+    /// Which cached digests are trusted: matching size and modification
+    /// time, those plus the inode, or none
+    #[serde(default)]
+    pub cache_trust: CacheTrust,
+    /// Also list the files that have no other copy under the roots
+    #[serde(default)]
+    pub report_unique: bool,
+    /// Never group copies that lie in two distinct projects (checkouts with
+    /// `.git`, or folders with `Cargo.toml` / `package.json`)
+    #[serde(default)]
+    pub separate_projects: bool,
+    /// Take a file's SHA-256 from a fresh `.sha256` sidecar or BagIt manifest
+    /// instead of reading it; ignored with dual hashing, which needs SHA-512 too
+    #[serde(default)]
+    pub trust_sidecars: bool,
+    /// Experimental: compare large files chunk by chunk and report pairs
+    /// that are partly identical
+    #[serde(default)]
+    pub chunk_similarity: bool,
+    /// Smallest file compared by chunks
+    #[serde(default = "default_chunk_similarity_min_size")]
+    pub chunk_similarity_min_size: u64,
+    /// Minimum share (0.0 - 1.0) of the larger file found in the other one
+    #[serde(default = "default_chunk_similarity_threshold")]
+    pub chunk_similarity_threshold: f64,
+    /// In a scan of several roots, drop groups whose files all lie under one root
+    #[serde(default)]
+    pub cross_root_only: bool,
+    /// Also list folders under the roots that hold nothing but empty folders
+    #[serde(default)]
+    pub find_empty_dirs: bool,
+    /// Don't descend into Btrfs / ZFS snapshot folders, which repeat every file they hold
+    #[serde(default = "default_exclude_snapshots")]
+    pub exclude_snapshots: bool,
+    /// Stop after this many minutes with the groups confirmed so far; 0 for no limit
+    #[serde(default)]
+    pub time_limit_minutes: u32,
+    /// Files waiting for a hashing thread, and hashed files waiting to be
+    /// grouped, at most; bounds memory and keeps the threads evenly busy
+    #[serde(default = "default_hash_queue_depth")]
+    pub hash_queue_depth: usize,
+    /// Pause and cancel switches for the running scan; attached by the caller
+    #[serde(skip)]
+    pub control: Option<Arc<ScanControl>>,
+    /// Where files are listed and read; the local filesystem when unset
+    #[serde(skip)]
+    pub source: Option<Arc<dyn FileSource>>,
+    /// Enabled hash lists of known files (e.g. the NSRL), loaded; attached by
+    /// the caller at scan time
+    #[serde(skip)]
+    pub known_files: Option<Arc<KnownFiles>>,
+}
+
+fn default_exclude_snapshots() -> bool {
+    true
+}
+
+fn default_auto_buffer() -> bool {
+    true
+}
+
+fn default_hash_queue_depth() -> usize {
+    64
+}
+
+fn default_chunk_similarity_min_size() -> u64 {
+    64 * 1024 * 1024
+}
+
+fn default_chunk_similarity_threshold() -> f64 {
+    0.5
+}
+
+fn default_use_hash_cache() -> bool {
+    false
+}
+
+fn default_near_duplicate_threshold() -> f64 {
+    0.8
+}
+
+fn default_junk_copy_patterns() -> Vec<String> {
+    ["* (?).*", "* (??).*", "*Copy of*", "*-copy.*", "* copy.*", "~$*", "*.bak", "*.orig"]
+        .iter()
+        .map(|p| p.to_string())
+        .collect()
 }
 
 impl Default for ScanConfig {
     fn default() -> Self {
         Self {
             buffer_size: 65536, // 64KB buffer for better performance
+            auto_buffer: default_auto_buffer(),
             include_hidden: false,
             min_file_size: 1,
             max_threads: None,
+            detect_file_types: true,
+            same_filesystem_only: false,
+            skip_pseudo_filesystems: true,
+            pseudo_mounts: None,
+            sensitive_patterns: [
+                "*.key", "*.pem", "*.p12", "*.pfx", "*.kdbx", "id_rsa*", "id_ed25519*", "id_ecdsa*",
+                "*password*", "*passwd*", "*secret*", "*credential*", "*.ovpn", "wallet.dat",
+            ]
+            .iter()
+            .map(|p| p.to_string())
+            .collect(),
+            junk_copy_patterns: default_junk_copy_patterns(),
+            excluded_dirs: Vec::new(),
+            near_duplicate_sources: false,
+            near_duplicate_threshold: default_near_duplicate_threshold(),
+            dual_hash: false,
+            trash_mode: TrashMode::default(),
+            auto_tune: false,
+            sparse_files: SparseMode::default(),
+            use_hash_cache: default_use_hash_cache(),
+            hash_cache: None,
+            checkpoint: None,
+            cache_trust: CacheTrust::default(),
+            report_unique: false,
+            separate_projects: false,
+            trust_sidecars: false,
+            chunk_similarity: false,
+            chunk_similarity_min_size: default_chunk_similarity_min_size(),
+            chunk_similarity_threshold: default_chunk_similarity_threshold(),
+            cross_root_only: false,
+            find_empty_dirs: false,
+            exclude_snapshots: default_exclude_snapshots(),
+            time_limit_minutes: 0,
+            hash_queue_depth: default_hash_queue_depth(),
+            control: None,
+            source: None,
+            known_files: None,
+        }
+    }
+}
+
+impl ScanConfig {
+    /// The source discovery and hashing go through
+    pub fn source(&self) -> &dyn FileSource {
+        self.source.as_deref().unwrap_or(&LocalSource)
+    }
+
+    pub fn time_limit(&self) -> Option<Duration> {
+        (self.time_limit_minutes > 0).then(|| Duration::from_secs(u64::from(self.time_limit_minutes) * 60))
+    }
+
+    /// Read the mount table for every walk of the scan about to start
+    pub fn attach_mount_table(&mut self) {
+        self.pseudo_mounts = self.skip_pseudo_filesystems.then(|| Arc::new(pseudo_mount_points()));
+    }
+
+    /// Start a checkpoint for a time-limited scan; one carried over from the
+    /// scan being continued is kept
+    pub fn attach_checkpoint(&mut self) {
+        if self.time_limit().is_some() && self.checkpoint.is_none() {
+            self.checkpoint = Some(Arc::default());
         }
     }
 }
@@ -53,7 +357,22 @@ impl Default for ScanConfig {
 pub enum ScanError {
     IoError(io::Error),
     WalkdirError(walkdir::Error),
-    HashError(String),
+    ThreadPool(rayon::ThreadPoolBuildError),
+    Cancelled,
+    /// A worker thread panicked; the panic's message
+    Internal(String),
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::IoError(e) => write!(f, "I/O error: {}", e),
+            ScanError::WalkdirError(e) => write!(f, "Directory traversal error: {}", e),
+            ScanError::ThreadPool(e) => write!(f, "Failed to start hashing threads: {}", e),
+            ScanError::Cancelled => write!(f, "Scan cancelled"),
+            ScanError::Internal(e) => write!(f, "Internal error: {}", e),
+        }
+    }
 }
 
 impl From<io::Error> for ScanError {
@@ -68,14 +387,46 @@ impl From<walkdir::Error> for ScanError {
     }
 }
 
-fn is_hidden(entry: &DirEntry) -> bool {
+// Where and why the last panic happened, with a backtrace, for crash reports
+static LAST_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+/// Record every panic for `take_panic_details`, then report it as before
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let details = format!("{}\n\n{}", info, Backtrace::force_capture());
+        log::error!("{}", details);
+        *LAST_PANIC.lock().unwrap_or_else(PoisonError::into_inner) = Some(details);
+        default_hook(info);
+    }));
+}
+
+/// The location and backtrace of the last panic, once
+pub fn take_panic_details() -> Option<String> {
+    LAST_PANIC.lock().unwrap_or_else(PoisonError::into_inner).take()
+}
+
+/// Run a worker's job, turning a panic into `ScanError::Internal` so the
+/// window hears back instead of waiting for a result that never comes
+pub fn catch_panic<T>(job: impl FnOnce() -> Result<T, ScanError>) -> Result<T, ScanError> {
+    panic::catch_unwind(AssertUnwindSafe(job)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(ScanError::Internal(message))
+    })
+}
+
+pub fn is_hidden(entry: &DirEntry) -> bool {
     entry.file_name()
         .to_str()
         .map(|s| s.starts_with('.'))
         .unwrap_or(false)
 }
 
-fn is_critical_file(path: &Path) -> bool {
+pub fn is_critical_file(path: &Path) -> bool {
     if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
         // List of critical system/user configuration files
         let critical_files = [
@@ -115,6 +466,65 @@ fn is_critical_file(path: &Path) -> bool {
     false
 }
 
+// System and application directories where removing a copy can break installed software
+const PROTECTED_PREFIXES: &[&str] = &[
+    "/etc", "/usr", "/bin", "/sbin", "/lib", "/lib64", "/boot", "/opt", "/var/lib",
+    "/System", "/Library", "/Applications",
+    "C:\\Windows", "C:\\Program Files", "C:\\Program Files (x86)", "C:\\ProgramData",
+];
+
+pub fn is_protected_path(path: &Path) -> bool {
+    PROTECTED_PREFIXES.iter().any(|prefix| path.starts_with(prefix))
+}
+
+/// Case-insensitive glob match supporting `*` and `?`
+pub fn matches_pattern(name: &str, pattern: &str) -> bool {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let (mut n, mut p) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            n += 1;
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            // Let the last `*` swallow one more character
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, n));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn name_matches_any(path: &Path, patterns: &[String]) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|name| patterns.iter().any(|p| matches_pattern(name, p)))
+}
+
+pub fn is_sensitive_file(path: &Path, patterns: &[String]) -> bool {
+    name_matches_any(path, patterns)
+}
+
+pub fn is_junk_copy(path: &Path, patterns: &[String]) -> bool {
+    name_matches_any(path, patterns)
+}
+
+// Tool-managed directories, and temporary files left by an interrupted consolidation
+fn is_excluded(entry: &DirEntry, excluded: &HashSet<PathBuf>) -> bool {
+    if entry.file_type().is_dir() {
+        !excluded.is_empty() && entry.path().canonicalize().is_ok_and(|p| excluded.contains(&p))
+    } else {
+        entry.file_name().to_string_lossy().ends_with(TEMP_LINK_SUFFIX)
+    }
+}
+
 fn get_file_metadata(path: &Path) -> io::Result<(u64, Option<SystemTime>)> {
     let metadata = std::fs::metadata(path)?;
     let size = metadata.len();
@@ -122,118 +532,848 @@ fn get_file_metadata(path: &Path) -> io::Result<(u64, Option<SystemTime>)> {
     Ok((size, modified))
 }
 
-pub fn scan_directory<F>(dir: &str, progress_callback: F, config: ScanConfig) -> Result<Vec<Vec<FileInfo>>, ScanError>
+// A discovered file with the metadata cached for its FileInfo
+struct Candidate {
+    path: InternedPath,
+    modified: Option<SystemTime>,
+    created: Option<SystemTime>,
+    is_critical: bool,
+    allocated: Option<u64>,
+    owner: Option<u32>,
+    mode: Option<u32>,
+    file_id: Option<FileId>,
+    changed: Option<SystemTime>,
+    /// Content digest the source reported without reading the file
+    digest: Option<String>,
+}
+
+// Files discovered so far, bucketed by size
+type SizeBuckets = HashMap<u64, Vec<Candidate>>;
+
+/// Walk honouring the hidden-file, filesystem-boundary and exclusion settings,
+/// skipping entries that cannot be read
+pub fn filtered_walk(walker: WalkDir, config: &ScanConfig) -> impl Iterator<Item = DirEntry> + '_ {
+    let skipped_mounts = match &config.pseudo_mounts {
+        _ if !config.skip_pseudo_filesystems => Arc::default(),
+        Some(mounts) => mounts.clone(),
+        None => Arc::new(pseudo_mount_points()),
+    };
+    // Compared canonically so an excluded directory reached via a symlink or relative path still matches
+    let excluded: HashSet<PathBuf> = config.excluded_dirs.iter().filter_map(|d| d.canonicalize().ok()).collect();
+    walker
+        .same_file_system(config.same_filesystem_only)
+        .into_iter()
+        .filter_entry(move |e| {
+            (config.include_hidden || !is_hidden(e))
+                // The scan root itself is always honoured, even inside a skipped mount
+                && (e.depth() == 0 || !skipped_mounts.contains(e.path()))
+                && !is_excluded(e, &excluded)
+                && !(config.trash_mode == TrashMode::Exclude && e.file_type().is_dir() && is_trash_dir(e.path()))
+                // A root chosen inside a snapshot is still scanned
+                && !(config.exclude_snapshots && e.depth() > 0 && e.file_type().is_dir() && is_snapshot_dir(e.path()))
+        })
+        .filter_map(|e| e.ok())
+}
+
+// SHA-256 of a file's contents, plus SHA-512 when dual hashing
+pub type Digests = (String, Option<String>);
+
+// Discovery reports progress every this many directory entries
+const DISCOVERY_PROGRESS_INTERVAL: usize = 256;
+
+fn discover<F>(
+    root: &Path,
+    max_depth: Option<usize>,
+    config: &ScanConfig,
+    files_by_size: &mut SizeBuckets,
+    symlinks: &mut Vec<PathBuf>,
+    found_before: usize,
+    progress_callback: &F,
+) -> usize
 where
-    F: Fn(ScanProgress) + Send + Sync + 'static,
+    F: Fn(ScanProgress),
 {
-    let mut files_by_size: HashMap<u64, Vec<(PathBuf, Option<SystemTime>, bool)>> = HashMap::new();
     let mut total_files = 0;
 
-    // Phase 1: Discovery
-    let walker = WalkDir::new(dir)
-        .into_iter()
-        .filter_entry(|e| config.include_hidden || !is_hidden(e));
-
-    for entry in walker.filter_map(|e| e.ok()) {
-        if entry.file_type().is_file() {
-            match get_file_metadata(entry.path()) {
-                Ok((size, modified)) => {
-                    if size >= config.min_file_size {
-                        let path = entry.path().to_path_buf();
-                        let is_critical = is_critical_file(&path);
-                        files_by_size.entry(size).or_default().push((path, modified, is_critical));
-                        total_files += 1;
+    for (seen, entry) in config.source().list(root, max_depth, config).enumerate() {
+        if config.control.as_deref().is_some_and(|control| !control.proceed()) {
+            break;
+        }
+        if seen % DISCOVERY_PROGRESS_INTERVAL == 0 {
+            let dir = if entry.kind == EntryKind::Dir { &entry.path } else { entry.path.parent().unwrap_or(&entry.path) };
+            progress_callback(ScanProgress {
+                current: found_before + total_files,
+                total: 0,
+                discovered: found_before + total_files,
+                current_file: dir.display().to_string(),
+                phase: ScanPhase::Discovery,
+            });
+        }
+
+        match entry.kind {
+            EntryKind::File if add_candidate(&entry.path, config, files_by_size) => total_files += 1,
+            EntryKind::Symlink => symlinks.push(entry.path),
+            _ => {}
+        }
+    }
+    total_files
+}
+
+// Bucket a regular file by size; false when it is unreadable, below the
+// minimum size, or a sparse file the configuration skips
+fn add_candidate(path: &Path, config: &ScanConfig, files_by_size: &mut SizeBuckets) -> bool {
+    match config.source().metadata(path) {
+        Ok(metadata) if metadata.size >= config.min_file_size => {
+            if metadata.allocated.is_some() && config.sparse_files == SparseMode::Skip {
+                return false;
+            }
+            files_by_size.entry(metadata.size).or_default().push(Candidate {
+                path: InternedPath::new(path),
+                modified: metadata.modified,
+                created: metadata.created,
+                is_critical: is_critical_file(path),
+                allocated: metadata.allocated,
+                owner: metadata.owner,
+                mode: metadata.mode,
+                file_id: metadata.file_id,
+                changed: metadata.changed,
+                digest: metadata.digest,
+            });
+            true
+        }
+        // Skip files we can't read, but continue scanning
+        _ => false,
+    }
+}
+
+/// Describe one file as a group member without hashing it, for groups
+/// matched elsewhere; the content hash is left empty
+pub fn unhashed_file_info(path: &Path, config: &ScanConfig) -> io::Result<FileInfo> {
+    let metadata = config.source().metadata(path)?;
+    Ok(FileInfo {
+        path: InternedPath::new(path),
+        hash: String::new(),
+        size: metadata.size,
+        modified_time: metadata.modified,
+        created_time: metadata.created,
+        is_critical: is_critical_file(path),
+        is_sensitive: is_sensitive_file(path, &config.sensitive_patterns),
+        stale: false,
+        delete_error: None,
+        is_junk_copy: is_junk_copy(path, &config.junk_copy_patterns),
+        detected_type: None,
+        allocated_size: metadata.allocated,
+        owner: metadata.owner,
+        xattrs: if metadata.mode.is_some() { xattr_names(path) } else { Vec::new() },
+        mode: metadata.mode,
+        file_id: metadata.file_id,
+    })
+}
+
+/// Parse a list of paths: one per line, or NUL-separated as written by
+/// `find -print0`. Relative paths are resolved against `base`. The list is
+/// taken as raw bytes so names that are not valid UTF-8 still resolve on Unix.
+pub fn parse_file_list(list: &[u8], base: &Path) -> Vec<PathBuf> {
+    let separator = if list.contains(&0) { 0 } else { b'\n' };
+    list.split(|&b| b == separator)
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.trim_ascii().is_empty())
+        // Rebuilding from components drops the "./" prefix find prints
+        .map(|line| base.join(path_from_bytes(line)).components().collect())
+        .collect()
+}
+
+// A path exactly as listed on Unix; elsewhere paths are Unicode, so the list is read as UTF-8
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+    }
+
+    #[cfg(not(unix))]
+    {
+        PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+/// Parse paths pasted by hand or copied from a file manager: one per line,
+/// optionally in quotes (as "Copy as path" gives them) or as `file://` URIs
+pub fn parse_pasted_paths(text: &str, base: &Path) -> Vec<PathBuf> {
+    text.lines()
+        .map(|line| line.trim().trim_matches(|c| c == '"' || c == '\''))
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.strip_prefix("file://") {
+            Some(uri) => PathBuf::from(file_uri_path(uri)),
+            None => base.join(line).components().collect(),
+        })
+        .collect()
+}
+
+// The path of a `file://` URI with the scheme removed. file:///C:/dir is a
+// Windows path; the host part is empty for local files.
+fn file_uri_path(uri: &str) -> String {
+    let path = percent_decode(uri.trim_start_matches("localhost"));
+    let windows = path.len() > 2 && path.as_bytes()[2] == b':';
+    if windows { path[1..].to_string() } else { path }
+}
+
+/// Turn a directory pasted from a file manager or a shell into a plain path:
+/// surrounding quotes are dropped, a `file://` URL is decoded and a leading
+/// `~` becomes the home folder. Text that is none of these is only trimmed.
+pub fn normalize_dir_input(text: &str) -> String {
+    let text = text.trim();
+    let unquoted = match text.as_bytes() {
+        [first @ (b'"' | b'\''), .., last] if first == last => &text[1..text.len() - 1],
+        _ => text,
+    };
+    if let Some(uri) = unquoted.strip_prefix("file://") {
+        return file_uri_path(uri);
+    }
+    // A shell escapes spaces and quotes outside of quotes; Windows paths use \ as separator
+    let unescaped = if cfg!(windows) || unquoted.len() != text.len() {
+        unquoted.to_string()
+    } else {
+        unescape_shell(unquoted)
+    };
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+    match (unescaped.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            format!("{}{}", Path::new(&home).display(), rest)
+        }
+        _ => unescaped,
+    }
+}
+
+// Drop the backslashes of `My\ Files`
+fn unescape_shell(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => plain.extend(chars.next()),
+            c => plain.push(c),
+        }
+    }
+    plain
+}
+
+/// Decode %XX escapes, as in URIs; invalid escapes are kept as they are
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
                     }
-                }
-                Err(_) => {
-                    // Skip files we can't read, but continue scanning
-                    continue;
+                    None => decoded.push(b'%'),
                 }
             }
+            byte => decoded.push(byte),
         }
+        i += 1;
     }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
 
-    progress_callback(ScanProgress {
-        current: total_files,
-        total: total_files,
-        current_file: "Discovery complete".to_string(),
-        phase: ScanPhase::Hashing,
-    });
+/// Hash the files named in a precomputed list instead of walking a directory,
+/// applying the same hidden-file, trash, exclusion and size filters as a scan
+pub fn scan_file_list<F>(paths: &[PathBuf], progress_callback: F, config: ScanConfig) -> Result<ScanOutcome, ScanError>
+where
+    F: Fn(ScanProgress) + Send + Sync + 'static,
+{
+    let excluded: Vec<PathBuf> = config.excluded_dirs.iter().filter_map(|d| d.canonicalize().ok()).collect();
+    let mut seen = HashSet::new();
+    let mut files_by_size = SizeBuckets::new();
+    let mut total_files = 0;
 
-    // Filter to only files with potential duplicates
-    let potential_duplicates: Vec<_> = files_by_size
-        .into_iter()
-        .filter(|(_, paths)| paths.len() > 1)
-        .collect();
+    for (idx, path) in paths.iter().enumerate() {
+        if idx % DISCOVERY_PROGRESS_INTERVAL == 0 {
+            progress_callback(ScanProgress {
+                current: total_files,
+                total: 0,
+                discovered: total_files,
+                current_file: path.display().to_string(),
+                phase: ScanPhase::Discovery,
+            });
+        }
 
-    let mut duplicates: Vec<Vec<FileInfo>> = Vec::new();
-    let mut processed_count = 0;
+        let hidden = path.components().any(|c| c.as_os_str().to_str().is_some_and(|s| s.starts_with('.') && s != "." && s != ".."));
+        if hidden && !config.include_hidden {
+            continue;
+        }
+        if config.trash_mode == TrashMode::Exclude && is_in_trash(path) {
+            continue;
+        }
+        if config.exclude_snapshots && is_in_snapshot(path) {
+            continue;
+        }
+        if path.file_name().is_some_and(|n| n.to_string_lossy().ends_with(TEMP_LINK_SUFFIX)) {
+            continue;
+        }
+        // Listed paths may repeat or reach the same file through different spellings
+        let Ok(canonical) = path.canonicalize() else {
+            continue;
+        };
+        if !canonical.is_file() || excluded.iter().any(|dir| canonical.starts_with(dir)) || !seen.insert(canonical) {
+            continue;
+        }
+        if add_candidate(path, &config, &mut files_by_size) {
+            total_files += 1;
+        }
+    }
 
-    for (size, paths_with_time) in potential_duplicates {
-        let paths: Vec<PathBuf> = paths_with_time.iter().map(|(p, _, _)| p.clone()).collect();
-        
-        // Parallel hashing using rayon
-        let hash_results: Vec<(PathBuf, Result<String, ScanError>)> = paths
-            .par_iter()
-            .map(|path| {
-                let path_clone = path.clone();
-                let config_clone = config.clone();
-                let _local_processed = 0;
-                
-                let hash_result = move || {
-                    hash_file(&path_clone, &config_clone)
-                        .map_err(|e| ScanError::HashError(format!("Failed to hash {}: {}", path_clone.display(), e)))
+    hash_candidates(files_by_size, total_files, &progress_callback, config)
+}
+
+/// Buffer size and thread count picked by auto-tuning
+#[derive(Clone, Copy, Debug)]
+pub struct TuningResult {
+    pub buffer_size: usize,
+    pub threads: usize,
+    /// Measured hashing throughput in bytes per second
+    pub throughput: f64,
+}
+
+/// Duplicate groups from a scan, with the settings auto-tuning chose if enabled
+#[derive(Debug)]
+pub struct ScanOutcome {
+    pub groups: Vec<HashGroup>,
+    pub tuning: Option<TuningResult>,
+    /// Symlinks met during a directory scan; empty for rescans and file lists
+    pub symlinks: SymlinkReport,
+    /// The walked roots with their file counts; empty for rescans and file lists
+    pub roots: Vec<ScanRoot>,
+    /// Empty folders found when `find_empty_dirs` is set
+    pub empty_dirs: Vec<PathBuf>,
+    /// Groups left out or flagged by the known-files lists
+    pub known: KnownOutcome,
+    /// The time limit stopped the scan: files not yet hashed are missing
+    pub partial: bool,
+    /// Files whose content was read and does not match their sidecar or bag
+    /// checksum: changed without a new modification time, or corrupted
+    pub sidecar_mismatches: Vec<PathBuf>,
+    /// Files with no other copy, largest first, when `report_unique` is set
+    pub unique_files: Vec<UniqueFile>,
+}
+
+/// One folder of a directory scan and how many files were found under it
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ScanRoot {
+    pub path: PathBuf,
+    pub files_scanned: usize,
+    /// Index of the root this one lies inside (or repeats); its files were
+    /// found and counted by that root's walk
+    #[serde(default)]
+    pub covered_by: Option<usize>,
+}
+
+impl ScanRoot {
+    /// Index of the root holding `path`. Where roots are nested the outer one
+    /// wins, matching the root whose walk found the file.
+    pub fn containing(roots: &[ScanRoot], path: &Path) -> Option<usize> {
+        roots
+            .iter()
+            .enumerate()
+            .filter(|(_, root)| path.starts_with(&root.path))
+            .min_by_key(|(_, root)| root.path.components().count())
+            .map(|(idx, _)| idx)
+    }
+}
+
+const TUNING_BUFFER_SIZES: [usize; 3] = [64 * 1024, 256 * 1024, 1024 * 1024];
+// Each trial hashes roughly this much data; larger files are left out of
+// trials so a single file cannot dominate a measurement
+const TUNING_TRIAL_BYTES: u64 = 32 * 1024 * 1024;
+// Smaller scans shrink trials down to this size so several can still run
+const TUNING_MIN_TRIAL_BYTES: u64 = 4 * 1024 * 1024;
+// Trials stop after this long in total even if combinations remain
+const TUNING_TIME_LIMIT: Duration = Duration::from_secs(5);
+
+/// Hash the first candidate files under several buffer size / thread count
+/// combinations and keep the fastest. Each trial uses different files so the
+/// page cache does not flatter later trials; their digests are kept for reuse.
+fn auto_tune<F>(files_by_size: &SizeBuckets, total_files: usize, progress_callback: &F, config: &ScanConfig) -> (Option<TuningResult>, HashMap<PathBuf, Digests>)
+where
+    F: Fn(ScanProgress) + Sync,
+{
+    let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let mut thread_counts = vec![1, (cores / 2).max(1), cores];
+    thread_counts.dedup();
+
+    let candidates: usize = files_by_size.values().filter(|paths| paths.len() > 1).map(Vec::len).sum();
+    let sample_buckets = || files_by_size.iter().filter(|(size, paths)| paths.len() > 1 && **size <= TUNING_TRIAL_BYTES);
+    let sample_bytes: u64 = sample_buckets().map(|(size, paths)| size * paths.len() as u64).sum();
+    let combinations = (thread_counts.len() * TUNING_BUFFER_SIZES.len()) as u64;
+    let trial_bytes = (sample_bytes / combinations).clamp(TUNING_MIN_TRIAL_BYTES, TUNING_TRIAL_BYTES);
+    let mut samples = sample_buckets()
+        .flat_map(|(size, paths)| paths.iter().map(move |c| (c.path.to_path_buf(), *size)));
+
+    let started = Instant::now();
+    let mut precomputed = HashMap::new();
+    let mut best: Option<TuningResult> = None;
+    let mut trials = 0;
+    'trials: for &threads in &thread_counts {
+        for &buffer_size in &TUNING_BUFFER_SIZES {
+            if started.elapsed() > TUNING_TIME_LIMIT {
+                break 'trials;
+            }
+            let mut batch = Vec::new();
+            let mut batch_bytes = 0;
+            while batch_bytes < trial_bytes {
+                let Some((path, size)) = samples.next() else {
+                    break;
                 };
+                batch_bytes += size;
+                batch.push(path);
+            }
+            if batch.is_empty() {
+                break 'trials;
+            }
+
+            progress_callback(ScanProgress {
+                current: precomputed.len(),
+                total: candidates,
+                discovered: total_files,
+                current_file: format!("Auto-tuning: {} KB buffer × {} thread(s)", buffer_size / 1024, threads),
+                phase: ScanPhase::Hashing,
+            });
+            let trial_config = ScanConfig { buffer_size, auto_buffer: false, max_threads: Some(threads), ..config.clone() };
+            let Ok(pool) = build_thread_pool(&trial_config) else {
+                continue;
+            };
+            let trial_start = Instant::now();
+            let digests: Vec<(PathBuf, io::Result<Digests>)> = pool.install(|| {
+                batch
+                    .into_par_iter()
+                    .map(|path| {
+                        let digests = hash_contents(&path, &trial_config, config.dual_hash);
+                        (path, digests)
+                    })
+                    .collect()
+            });
+            let throughput = batch_bytes as f64 / trial_start.elapsed().as_secs_f64().max(1e-6);
+            precomputed.extend(digests.into_iter().filter_map(|(path, d)| d.ok().map(|d| (path, d))));
+            trials += 1;
+
+            if best.is_none_or(|b| throughput > b.throughput) {
+                best = Some(TuningResult { buffer_size, threads, throughput });
+            }
+        }
+    }
 
-                let result = hash_result();
-                (path.clone(), result)
-            })
-            .collect();
+    // A single trial has nothing to compare against
+    (best.filter(|_| trials > 1), precomputed)
+}
 
-        let mut files_by_hash: HashMap<String, Vec<(PathBuf, Option<SystemTime>, bool)>> = HashMap::new();
+/// Hashing phase shared by full scans and rescans
+fn hash_candidates<F>(files_by_size: SizeBuckets, total_files: usize, progress_callback: &F, config: ScanConfig) -> Result<ScanOutcome, ScanError>
+where
+    F: Fn(ScanProgress) + Sync,
+{
+    let (tuning, precomputed) = if config.auto_tune {
+        auto_tune(&files_by_size, total_files, progress_callback, &config)
+    } else {
+        (None, HashMap::new())
+    };
+    let config = match tuning {
+        Some(t) => ScanConfig { buffer_size: t.buffer_size, auto_buffer: false, max_threads: Some(t.threads), ..config },
+        None => config,
+    };
 
-        for ((path, hash_result), (_, time, is_critical)) in hash_results.into_iter().zip(paths_with_time) {
-            processed_count += 1;
+    let (groups, sidecar_mismatches, unique_files) = find_duplicates(files_by_size, total_files, progress_callback, &config, &precomputed)?;
+    let (groups, known) = match &config.known_files {
+        Some(known_files) => {
             progress_callback(ScanProgress {
-                current: processed_count,
+                current: total_files,
                 total: total_files,
-                current_file: path.display().to_string(),
+                discovered: total_files,
+                current_file: "Checking known-files lists".to_string(),
                 phase: ScanPhase::Hashing,
             });
+            known_files.apply(groups, &config)
+        }
+        None => (groups, KnownOutcome::default()),
+    };
+    let groups = if config.separate_projects { separate_projects(groups) } else { groups };
+    let partial = config.control.as_deref().is_some_and(ScanControl::timed_out);
+    Ok(ScanOutcome { groups, tuning, symlinks: SymlinkReport::default(), roots: Vec::new(), empty_dirs: Vec::new(), known, partial, sidecar_mismatches, unique_files })
+}
+
+// One file can be discovered under two spellings, e.g. through overlapping
+// roots given via a symlink or ".."; keep the first so no file is its own duplicate
+fn drop_repeated_files(files: Vec<Candidate>) -> Vec<Candidate> {
+    let mut seen = HashSet::new();
+    files
+        .into_iter()
+        .filter(|candidate| {
+            let path = candidate.path.to_path_buf();
+            seen.insert(path.canonicalize().unwrap_or(path))
+        })
+        .collect()
+}
+
+// Duplicate groups, files contradicting their sidecar checksum, and files with no other copy
+type Findings = (Vec<HashGroup>, Vec<PathBuf>, Vec<UniqueFile>);
+
+fn find_duplicates<F>(
+    files_by_size: SizeBuckets,
+    total_files: usize,
+    progress_callback: &F,
+    config: &ScanConfig,
+    precomputed: &HashMap<PathBuf, Digests>,
+) -> Result<Findings, ScanError>
+where
+    F: Fn(ScanProgress) + Sync,
+{
+    // A file alone in its size needs no reading to be known unique
+    let mut unique = Vec::new();
+    let mut keep_unique = |candidates: Vec<Candidate>, size: u64| {
+        if config.report_unique {
+            unique.extend(candidates.into_iter().map(|candidate| UniqueFile { path: candidate.path.to_path_buf(), size, modified: candidate.modified }));
+        }
+    };
+    // Filter to only files with potential duplicates
+    let potential_duplicates: Vec<(u64, Vec<Candidate>)> = files_by_size
+        .into_iter()
+        .map(|(size, paths)| (size, if paths.len() > 1 { drop_repeated_files(paths) } else { paths }))
+        .filter_map(|(size, paths)| {
+            if paths.len() > 1 {
+                return Some((size, paths));
+            }
+            keep_unique(paths, size);
+            None
+        })
+        .collect();
+    let candidates: usize = potential_duplicates.iter().map(|(_, paths)| paths.len()).sum();
+    let cache = config.hash_cache.as_deref().filter(|_| config.use_hash_cache);
+    // A time-boxed scan keeps what it hashed, so continuing it skips those files
+    let checkpoint = config.checkpoint.as_deref();
+    let sidecars = config.trust_sidecars.then(Sidecars::default);
+    let mismatches = Mutex::new(Vec::new());
+    // A size bucket whose files all carry a known digest, e.g. S3 objects with
+    // single-part ETags, is grouped on those digests without reading anything;
+    // a bucket mixing them with local files is read and hashed as usual
+    let known_digests: Vec<bool> = potential_duplicates
+        .iter()
+        .map(|(_, files)| !config.dual_hash && files.iter().all(|candidate| candidate.digest.is_some()))
+        .collect();
+
+    progress_callback(ScanProgress {
+        current: 0,
+        total: candidates,
+        discovered: total_files,
+        current_file: "Discovery complete".to_string(),
+        phase: ScanPhase::Hashing,
+    });
+
+    // Every candidate file is one task on a shared queue, so all hashing
+    // threads stay busy across size buckets instead of meeting at the end of each
+    let tasks = potential_duplicates
+        .iter()
+        .enumerate()
+        .flat_map(|(bucket, (_, files))| (0..files.len()).map(move |index| (bucket, index)));
+    let hash_task = |(bucket, index): (usize, usize)| {
+        let size = potential_duplicates[bucket].0;
+        let candidate = &potential_duplicates[bucket].1[index];
+        if known_digests[bucket] {
+            return (bucket, index, candidate.digest.clone().map(|digest| (digest, None)));
+        }
+        let path = candidate.path.to_path_buf();
+        let stamp = FileStamp { size, modified: candidate.modified, changed: candidate.changed, file_id: candidate.file_id };
+        let recorded = sidecars.as_ref().and_then(|sidecars| sidecars.digest(&path, candidate.modified));
+        // A cached digest the sidecar contradicts is settled by reading the file
+        let digests = cache
+            .and_then(|c| c.lookup(&path, stamp, config.dual_hash, config.cache_trust))
+            .or_else(|| checkpoint.and_then(|c| c.lookup(&path, stamp, config.dual_hash, CacheTrust::Strict)))
+            .filter(|digests| recorded.as_ref().is_none_or(|recorded| *recorded == digests.0))
+            .or_else(|| recorded.clone().filter(|_| !config.dual_hash).map(|digest| (digest, None)))
+            .or_else(|| {
+                // Unreadable files are left out of the results
+                let digests = match precomputed.get(&path) {
+                    Some(digests) => digests.clone(),
+                    None => hash_contents(&path, config, config.dual_hash).ok()?,
+                };
+                for store in [cache, checkpoint].into_iter().flatten() {
+                    store.insert(&path, stamp, &digests);
+                }
+                Some(digests)
+            });
+        if let (Some(recorded), Some(digests)) = (&recorded, &digests) {
+            if *recorded != digests.0 {
+                mismatches.lock().unwrap_or_else(PoisonError::into_inner).push(path);
+            }
+        }
+        (bucket, index, digests)
+    };
 
-            if let Ok(hash) = hash_result {
-                files_by_hash.entry(hash).or_default().push((path, time, is_critical));
+    let mut digests: Vec<Vec<Option<Digests>>> = potential_duplicates.iter().map(|(_, files)| vec![None; files.len()]).collect();
+    let mut processed_count = 0;
+    let workers = hash_workers(config);
+    let control = config.control.as_deref();
+    if let Some(control) = control {
+        control.set_workers(workers.active());
+    }
+    let completed = run_queue(tasks, &workers, config.hash_queue_depth, control, hash_task, |(bucket, index, result)| {
+        processed_count += 1;
+        workers.record(potential_duplicates[bucket].0);
+        if let Some(control) = control {
+            control.set_workers(workers.active());
+        }
+        progress_callback(ScanProgress {
+            current: processed_count,
+            total: candidates,
+            discovered: total_files,
+            current_file: potential_duplicates[bucket].1[index].path.to_string(),
+            phase: ScanPhase::Hashing,
+        });
+        digests[bucket][index] = result;
+    });
+    // Out of time, the files hashed so far still form confirmed groups
+    if !completed && !config.control.as_deref().is_some_and(ScanControl::timed_out) {
+        return Err(ScanError::Cancelled);
+    }
+
+    let mut duplicates: Vec<HashGroup> = Vec::new();
+    for ((size, paths_with_time), bucket_digests) in potential_duplicates.into_iter().zip(digests) {
+        // Keyed on both digests, so with dual hashing a file only joins a group when both match
+        let mut files_by_hash: HashMap<Digests, Vec<Candidate>> = HashMap::new();
+        for (candidate, digests) in paths_with_time.into_iter().zip(bucket_digests) {
+            if let Some(digests) = digests {
+                files_by_hash.entry(digests).or_default().push(candidate);
             }
         }
 
-        for (_, paths_with_time) in files_by_hash {
-            if paths_with_time.len() > 1 {
-                let group: Vec<FileInfo> = paths_with_time
+        for ((hash, secondary_hash), paths_with_time) in files_by_hash {
+            if paths_with_time.len() == 1 {
+                keep_unique(paths_with_time, size);
+            } else {
+                // Every member has the same content, so sniffing one is enough
+                let detected_type = if config.detect_file_types {
+                    detect_type(&paths_with_time[0].path.to_path_buf()).ok().map(str::to_string)
+                } else {
+                    None
+                };
+                let files: Vec<FileInfo> = paths_with_time
                     .into_iter()
-                    .map(|(path, modified, is_critical)| FileInfo { path, size, modified_time: modified, is_critical })
+                    .map(|candidate| FileInfo {
+                        is_sensitive: is_sensitive_file(&candidate.path.to_path_buf(), &config.sensitive_patterns),
+                        is_junk_copy: is_junk_copy(&candidate.path.to_path_buf(), &config.junk_copy_patterns),
+                        // Only local files have permission bits, and only they have attributes to list
+                        xattrs: if candidate.mode.is_some() { xattr_names(&candidate.path.to_path_buf()) } else { Vec::new() },
+                        path: candidate.path,
+                        hash: hash.clone(),
+                        size,
+                        modified_time: candidate.modified,
+                        created_time: candidate.created,
+                        is_critical: candidate.is_critical,
+                        stale: false,
+                        delete_error: None,
+                        detected_type: detected_type.clone(),
+                        allocated_size: candidate.allocated,
+                        owner: candidate.owner,
+                        mode: candidate.mode,
+                        file_id: candidate.file_id,
+                    })
                     .collect();
-                duplicates.push(group);
+                duplicates.push(HashGroup { hash, secondary_hash, files, known: None });
             }
         }
     }
 
-    Ok(duplicates)
+    if config.sparse_files == SparseMode::Demote {
+        // Stable, so the remaining order is kept within each part
+        duplicates.sort_by_key(|group| group.files.iter().any(FileInfo::is_sparse));
+    }
+    let mismatches = mismatches.into_inner().unwrap_or_else(PoisonError::into_inner);
+    unique.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    Ok((duplicates, mismatches, unique))
 }
 
-fn hash_file(path: &Path, config: &ScanConfig) -> io::Result<String> {
-    let mut file = File::open(path)?;
-    let mut hasher = Sha256::new();
-    let mut buffer = vec![0u8; config.buffer_size];
+/// Scan one or more directories together, so duplicates are also found
+/// across them. A root lying inside another one is walked only once, as part
+/// of the outer root.
+pub fn scan_roots<F>(dirs: &[String], progress_callback: F, config: ScanConfig) -> Result<ScanOutcome, ScanError>
+where
+    F: Fn(ScanProgress) + Send + Sync + 'static,
+{
+    let mut files_by_size = SizeBuckets::new();
+    let mut symlinks = SymlinkReport::default();
+    let mut roots = Vec::new();
+    let mut total_files = 0;
 
-    loop {
-        let count = file.read(&mut buffer)?;
-        if count == 0 {
-            break;
+    // Phase 1: Discovery
+    let canonical: Vec<Option<PathBuf>> = dirs.iter().map(|dir| Path::new(dir).canonicalize().ok()).collect();
+    for (idx, dir) in dirs.iter().enumerate() {
+        // Compared canonically, so a root reached through a symlink or ".." is recognised too
+        let covered_by = canonical[idx].as_ref().and_then(|own| {
+            canonical
+                .iter()
+                .enumerate()
+                .filter_map(|(other, path)| Some((other, path.as_ref()?)))
+                .filter(|&(other, path)| other != idx && own.starts_with(path) && (own != path || other < idx))
+                .min_by_key(|(_, path)| path.components().count())
+                .map(|(other, _)| other)
+        });
+        let mut found = 0;
+        if covered_by.is_none() {
+            let mut links = Vec::new();
+            found = discover(Path::new(dir), None, &config, &mut files_by_size, &mut links, total_files, &progress_callback);
+            let report = analyze_links(Path::new(dir), links);
+            symlinks.broken.extend(report.broken);
+            symlinks.duplicating.extend(report.duplicating);
+            symlinks.shared_targets.extend(report.shared_targets);
+            symlinks.chains.extend(report.chains);
+        }
+        total_files += found;
+        roots.push(ScanRoot { path: PathBuf::from(dir), files_scanned: found, covered_by });
+    }
+
+    let empty_dirs = if config.find_empty_dirs { find_empty_dirs(dirs, &config) } else { Vec::new() };
+
+    // Phase 2: Hashing
+    let cross_root_only = config.cross_root_only && roots.len() > 1;
+    let mut outcome = hash_candidates(files_by_size, total_files, &progress_callback, config)?;
+    if cross_root_only {
+        outcome.groups.retain(|group| {
+            let mut members = group.files.iter().filter_map(|f| ScanRoot::containing(&roots, &f.path.parent()));
+            let first = members.next();
+            members.any(|root| Some(root) != first)
+        });
+    }
+    outcome.symlinks = symlinks;
+    outcome.roots = roots;
+    outcome.empty_dirs = empty_dirs;
+    Ok(outcome)
+}
+
+/// Re-scan only the given directories (not their subdirectories), e.g. the
+/// parents of files in an existing result set.
+pub fn rescan_directories<F>(dirs: &[PathBuf], progress_callback: F, config: ScanConfig) -> Result<ScanOutcome, ScanError>
+where
+    F: Fn(ScanProgress) + Send + Sync + 'static,
+{
+    let mut files_by_size = SizeBuckets::new();
+    let mut total_files = 0;
+
+    for dir in dirs {
+        // Symlinks are only analyzed by full scans
+        total_files += discover(dir, Some(1), &config, &mut files_by_size, &mut Vec::new(), total_files, &progress_callback);
+    }
+
+    hash_candidates(files_by_size, total_files, &progress_callback, config)
+}
+
+/// Thread pool used for hashing, limited to `max_threads` when set
+pub fn build_thread_pool(config: &ScanConfig) -> Result<rayon::ThreadPool, ScanError> {
+    let mut builder = rayon::ThreadPoolBuilder::new();
+    if let Some(threads) = config.max_threads {
+        builder = builder.num_threads(threads.max(1));
+    }
+    builder.build().map_err(ScanError::ThreadPool)
+}
+
+/// Number of threads hashing will use with this configuration; a remote
+/// source caps it at the reads it can serve at once
+pub fn effective_threads(config: &ScanConfig) -> usize {
+    let threads = config.max_threads.unwrap_or_else(|| {
+        std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    });
+    config.source().max_readers().map_or(threads, |readers| threads.min(readers.max(1)))
+}
+
+/// Threads for hashing: the set number when threads are limited or tuned,
+/// otherwise scaled with throughput from half the cores up to all of them
+fn hash_workers(config: &ScanConfig) -> Workers {
+    let max = effective_threads(config);
+    if config.max_threads.is_some() {
+        Workers::fixed(max)
+    } else {
+        Workers::scaled(max / 2, max)
+    }
+}
+
+/// Hash each file in parallel, `None` where the file could not be read
+pub fn rehash_files(paths: &[PathBuf], config: &ScanConfig) -> Vec<Option<String>> {
+    match build_thread_pool(config) {
+        Ok(pool) => pool.install(|| paths.par_iter().map(|p| hash_file(p, config).ok()).collect()),
+        Err(_) => paths.iter().map(|p| hash_file(p, config).ok()).collect(),
+    }
+}
+
+// Files up to this size are read whole in a single call
+const WHOLE_READ_LIMIT: u64 = 256 * 1024;
+// Largest buffer automatic sizing picks, kept per hashing thread
+pub const MAX_AUTO_BUFFER: usize = 4 * 1024 * 1024;
+
+/// Read buffer for hashing a file of `size` bytes. Automatic sizing reads
+/// small files whole and gives larger files larger buffers, so configs need
+/// one read and ISOs few system calls.
+pub fn buffer_for(size: u64, config: &ScanConfig) -> usize {
+    if !config.auto_buffer {
+        return config.buffer_size.max(1);
+    }
+    if size <= WHOLE_READ_LIMIT {
+        // One byte over the size, so the end of the file shows in the same read
+        size as usize + 1
+    } else if size <= 16 * 1024 * 1024 {
+        256 * 1024
+    } else if size <= 1024 * 1024 * 1024 {
+        1024 * 1024
+    } else {
+        MAX_AUTO_BUFFER
+    }
+}
+
+thread_local! {
+    // One read buffer per hashing thread, reused across files
+    static HASH_BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+pub fn hash_file(path: &Path, config: &ScanConfig) -> io::Result<String> {
+    hash_contents(path, config, false).map(|(hash, _)| hash)
+}
+
+/// SHA-256 of a file, plus SHA-512 from the same read pass when `secondary`
+/// is set. The file is read through the configured source, unless the
+/// source can hash it where it is stored.
+pub fn hash_contents(path: &Path, config: &ScanConfig, secondary: bool) -> io::Result<Digests> {
+    if !secondary {
+        if let Some(hash) = config.source().stored_sha256(path) {
+            return hash.map(|hash| (hash, None));
         }
-        hasher.update(&buffer[..count]);
     }
+    let (mut file, size) = config.source().open(path)?;
+    let buffer_size = buffer_for(size, config);
+    let mut hasher = Sha256::new();
+    let mut secondary_hasher = secondary.then(Sha512::new);
 
-    Ok(hex::encode(hasher.finalize()))
+    HASH_BUFFER.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        buffer.resize(buffer_size, 0);
+        loop {
+            let count = file.read(&mut buffer)?;
+            if count == 0 {
+                break;
+            }
+            hasher.update(&buffer[..count]);
+            if let Some(secondary_hasher) = secondary_hasher.as_mut() {
+                secondary_hasher.update(&buffer[..count]);
+            }
+            if let Some(control) = &config.control {
+                control.throttle(count);
+            }
+        }
+        Ok::<_, io::Error>(())
+    })?;
+
+    Ok((hex::encode(hasher.finalize()), secondary_hasher.map(|h| hex::encode(h.finalize()))))
 }
 
 // Selection strategies
@@ -244,6 +1384,7 @@ pub trait SelectionStrategy {
 pub struct KeepNewestStrategy;
 pub struct KeepOldestStrategy;
 pub struct KeepAllStrategy;
+#[allow(dead_code)]
 pub struct KeepNoneStrategy;
 
 impl SelectionStrategy for KeepNewestStrategy {
@@ -281,3 +1422,44 @@ impl SelectionStrategy for KeepNoneStrategy {
         vec![false; files.len()]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stars_match_any_run_of_characters() {
+        assert!(matches_pattern("report.pdf", "*.pdf"));
+        assert!(matches_pattern(".pdf", "*.pdf"));
+        assert!(matches_pattern("Copy of notes.txt", "*Copy of*"));
+        assert!(matches_pattern("a-b-c-copy.txt", "*-copy.*"));
+        assert!(matches_pattern("anything", "*"));
+        assert!(matches_pattern("", "*"));
+        assert!(matches_pattern("abc", "a**c"));
+        assert!(!matches_pattern("report.pdf.bak", "*.pdf"));
+        assert!(!matches_pattern("", "?"));
+    }
+
+    #[test]
+    fn a_star_backtracks_to_a_later_occurrence() {
+        assert!(matches_pattern("photo (1) (1).jpg", "* (1).*"));
+        assert!(matches_pattern("aaab", "*ab"));
+        assert!(matches_pattern("mississippi", "m*iss*ppi"));
+        assert!(!matches_pattern("mississippi", "m*iss*x"));
+    }
+
+    #[test]
+    fn question_marks_match_exactly_one_character() {
+        assert!(matches_pattern("~$budget.xlsx", "~$*"));
+        assert!(matches_pattern("file1.txt", "file?.txt"));
+        assert!(!matches_pattern("file.txt", "file?.txt"));
+        assert!(!matches_pattern("file12.txt", "file?.txt"));
+        assert!(matches_pattern("é.txt", "?.txt"));
+    }
+
+    #[test]
+    fn patterns_ignore_case() {
+        assert!(matches_pattern("IMG_0001.JPG", "img_*.jpg"));
+        assert!(matches_pattern("notes.BAK", "*.bak"));
+    }
+}
@@ -0,0 +1,45 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Folder holding the history, logs, hash cache, journal and settings
+pub fn data_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))?;
+    Some(base.join("dupe-finder-gui"))
+}
+
+/// `name` in the data folder, creating the folder
+pub fn data_file(name: &str) -> io::Result<PathBuf> {
+    let dir = data_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join(name))
+}
+
+/// Replace `path` with `bytes`, written aside under a name of this process
+/// and renamed over it, so a crash or a second instance never leaves half a file
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let tmp = path.with_file_name(format!("{}.{}.tmp", name, std::process::id()));
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+/// Settings stored as `name` in the data folder; None while there are none
+/// or they cannot be read
+pub fn load_json<T: DeserializeOwned>(name: &str) -> Option<T> {
+    let bytes = fs::read(data_dir()?.join(name)).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Store settings as `name` in the data folder
+pub fn save_json<T: Serialize + ?Sized>(name: &str, value: &T) -> io::Result<()> {
+    let json = serde_json::to_vec_pretty(value).map_err(io::Error::other)?;
+    write_atomic(&data_file(name)?, &json)
+}
@@ -0,0 +1,275 @@
+use std::fmt;
+use std::io::{self, Read};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, UNIX_EPOCH};
+use ssh2::{CheckResult, KnownHostFileKind, Session, Sftp};
+use crate::scanner::ScanConfig;
+use crate::source::{EntryKind, FileSource, RemoteSource, SourceEntry, SourceMetadata};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+// Per-request limit, so a server that stops answering fails the read instead of hanging the scan
+const SESSION_TIMEOUT_MS: u32 = 60_000;
+pub const DEFAULT_CONNECTIONS: usize = 4;
+pub const MAX_CONNECTIONS: usize = 16;
+
+/// How to log in to the server
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SftpAuth {
+    #[default]
+    Agent,
+    KeyFile,
+    Password,
+}
+
+impl SftpAuth {
+    pub const ALL: [SftpAuth; 3] = [SftpAuth::Agent, SftpAuth::KeyFile, SftpAuth::Password];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SftpAuth::Agent => "SSH agent",
+            SftpAuth::KeyFile => "Key file",
+            SftpAuth::Password => "Password",
+        }
+    }
+}
+
+/// Server, account and credentials for one SFTP connection; never saved
+#[derive(Clone, Debug)]
+pub struct SftpLogin {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub auth: SftpAuth,
+    pub key_file: String,
+    /// The password, or the key file's passphrase
+    pub secret: String,
+    /// Connections opened to the server; each reads one file at a time
+    pub connections: usize,
+    /// Accept a server whose host key is missing from known_hosts
+    pub trust_unknown_host: bool,
+}
+
+impl Default for SftpLogin {
+    fn default() -> Self {
+        let key_file = home_dir()
+            .map(|home| home.join(".ssh").join("id_ed25519"))
+            .filter(|key| key.exists())
+            .map(|key| key.display().to_string())
+            .unwrap_or_default();
+        Self {
+            host: String::new(),
+            port: 22,
+            user: std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_default(),
+            auth: SftpAuth::default(),
+            key_file,
+            secret: String::new(),
+            connections: DEFAULT_CONNECTIONS,
+            trust_unknown_host: false,
+        }
+    }
+}
+
+impl SftpLogin {
+    /// What paths on this server start with in scan results, e.g. "sftp://me@nas:22"
+    pub fn prefix(&self) -> String {
+        format!("sftp://{}@{}:{}", self.user, self.host, self.port)
+    }
+
+    /// The scan root for `remote_dir` on this server
+    pub fn root_for(&self, remote_dir: &str) -> String {
+        format!("{}/{}", self.prefix(), remote_dir.trim().trim_start_matches('/'))
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")).map(PathBuf::from)
+}
+
+fn check_host_key(session: &Session, login: &SftpLogin) -> Result<(), String> {
+    let (key, _) = session.host_key().ok_or("The server sent no host key")?;
+    let mut known = session.known_hosts().map_err(|e| format!("Cannot check the host key: {}", e))?;
+    if let Some(file) = home_dir().map(|home| home.join(".ssh").join("known_hosts")) {
+        // A missing file just means no host is known yet
+        let _ = known.read_file(&file, KnownHostFileKind::OpenSSH);
+    }
+    match known.check_port(&login.host, login.port, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::NotFound if login.trust_unknown_host => Ok(()),
+        CheckResult::NotFound => Err(format!(
+            "{} is not in ~/.ssh/known_hosts; connect once with ssh to verify its key, or choose to trust it",
+            login.host
+        )),
+        CheckResult::Mismatch => Err(format!(
+            "The host key of {} does not match ~/.ssh/known_hosts; refusing to connect",
+            login.host
+        )),
+        CheckResult::Failure => Err(format!("Cannot check the host key of {}", login.host)),
+    }
+}
+
+fn connect_one(login: &SftpLogin) -> Result<(Session, Sftp), String> {
+    let address = (login.host.as_str(), login.port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Cannot resolve {}: {}", login.host, e))?
+        .next()
+        .ok_or_else(|| format!("Cannot resolve {}", login.host))?;
+    let tcp = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+        .map_err(|e| format!("Cannot connect to {}: {}", address, e))?;
+    let mut session = Session::new().map_err(|e| e.to_string())?;
+    session.set_tcp_stream(tcp);
+    session.set_timeout(SESSION_TIMEOUT_MS);
+    session.handshake().map_err(|e| format!("SSH handshake failed: {}", e))?;
+    check_host_key(&session, login)?;
+
+    let passphrase = (!login.secret.is_empty()).then_some(login.secret.as_str());
+    match login.auth {
+        SftpAuth::Agent => session.userauth_agent(&login.user),
+        SftpAuth::KeyFile => session.userauth_pubkey_file(&login.user, None, Path::new(&login.key_file), passphrase),
+        SftpAuth::Password => session.userauth_password(&login.user, &login.secret),
+    }
+    .map_err(|e| format!("Authentication as {} failed: {}", login.user, e))?;
+
+    let sftp = session.sftp().map_err(|e| format!("The server offers no SFTP: {}", e))?;
+    Ok((session, sftp))
+}
+
+/// Open connections to one server. Files are read over them in turn, so
+/// that many files are in flight at once to hide the network's latency.
+pub struct SftpHost {
+    prefix: PathBuf,
+    // The sessions stay open as long as their SFTP channels are in use
+    connections: Vec<(Session, Sftp)>,
+    next: AtomicUsize,
+}
+
+impl fmt::Debug for SftpHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SftpHost")
+            .field("prefix", &self.prefix)
+            .field("connections", &self.connections.len())
+            .finish()
+    }
+}
+
+impl SftpHost {
+    pub fn connect(login: &SftpLogin) -> Result<Self, String> {
+        let connections = (0..login.connections.clamp(1, MAX_CONNECTIONS))
+            .map(|_| connect_one(login))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { prefix: PathBuf::from(login.prefix()), connections, next: AtomicUsize::new(0) })
+    }
+
+    pub fn connection_count(&self) -> usize {
+        self.connections.len()
+    }
+
+    fn sftp(&self) -> &Sftp {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.connections.len();
+        &self.connections[index].1
+    }
+
+    fn remote_path(&self, path: &Path) -> Option<PathBuf> {
+        path.strip_prefix(&self.prefix).ok().map(|relative| Path::new("/").join(relative))
+    }
+
+    fn result_path(&self, remote: &Path) -> PathBuf {
+        self.prefix.join(remote.strip_prefix("/").unwrap_or(remote))
+    }
+}
+
+/// Lists a remote tree one directory at a time as the scan asks for entries
+struct SftpWalk<'a> {
+    host: &'a SftpHost,
+    include_hidden: bool,
+    max_depth: Option<usize>,
+    dirs: Vec<(PathBuf, usize)>,
+    pending: Vec<SourceEntry>,
+}
+
+impl Iterator for SftpWalk<'_> {
+    type Item = SourceEntry;
+
+    fn next(&mut self) -> Option<SourceEntry> {
+        loop {
+            if let Some(entry) = self.pending.pop() {
+                return Some(entry);
+            }
+            let (dir, depth) = self.dirs.pop()?;
+            if self.max_depth.is_some_and(|max| depth >= max) {
+                continue;
+            }
+            let Ok(entries) = self.host.sftp().readdir(&dir) else {
+                continue;
+            };
+            for (path, stat) in entries.into_iter().rev() {
+                let hidden = path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with('.'));
+                if hidden && !self.include_hidden {
+                    continue;
+                }
+                let file_type = stat.file_type();
+                // Links on the server are not followed; they cannot be told apart from local ones by path
+                let kind = if file_type.is_file() {
+                    EntryKind::File
+                } else if file_type.is_dir() {
+                    self.dirs.push((path.clone(), depth + 1));
+                    EntryKind::Dir
+                } else {
+                    EntryKind::Other
+                };
+                self.pending.push(SourceEntry { path: self.host.result_path(&path), kind });
+            }
+        }
+    }
+}
+
+fn outside(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{} is not on this server", path.display()))
+}
+
+impl FileSource for SftpHost {
+    fn list<'a>(&'a self, root: &Path, max_depth: Option<usize>, config: &'a ScanConfig) -> Box<dyn Iterator<Item = SourceEntry> + 'a> {
+        let Some(remote) = self.remote_path(root) else {
+            return Box::new(std::iter::empty());
+        };
+        Box::new(SftpWalk {
+            host: self,
+            include_hidden: config.include_hidden,
+            max_depth,
+            dirs: vec![(remote.clone(), 0)],
+            pending: vec![SourceEntry { path: self.result_path(&remote), kind: EntryKind::Dir }],
+        })
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<SourceMetadata> {
+        let remote = self.remote_path(path).ok_or_else(|| outside(path))?;
+        let stat = self.sftp().stat(&remote)?;
+        Ok(SourceMetadata {
+            size: stat.size.unwrap_or(0),
+            modified: stat.mtime.map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+            ..SourceMetadata::default()
+        })
+    }
+
+    fn open(&self, path: &Path) -> io::Result<(Box<dyn Read + Send>, u64)> {
+        let remote = self.remote_path(path).ok_or_else(|| outside(path))?;
+        let mut file = self.sftp().open(&remote)?;
+        let size = file.stat()?.size.unwrap_or(0);
+        Ok((Box::new(file), size))
+    }
+
+    fn max_readers(&self) -> Option<usize> {
+        Some(self.connections.len())
+    }
+}
+
+impl RemoteSource for SftpHost {
+    fn prefix(&self) -> &Path {
+        &self.prefix
+    }
+
+    fn label(&self) -> String {
+        format!("{} ({} connection(s))", self.prefix.display(), self.connections.len())
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use crate::settings::{load_json, save_json};
+
+const SHORTCUTS_FILE: &str = "shortcuts.json";
+
+/// Actions that can be bound to a key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ShortcutAction {
+    Scan,
+    CancelScan,
+    DeleteGroup,
+    ToggleKeep,
+    NextGroup,
+    PreviousGroup,
+    NextFile,
+    PreviousFile,
+    CommandPalette,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 9] = [
+        ShortcutAction::Scan,
+        ShortcutAction::CancelScan,
+        ShortcutAction::DeleteGroup,
+        ShortcutAction::ToggleKeep,
+        ShortcutAction::NextGroup,
+        ShortcutAction::PreviousGroup,
+        ShortcutAction::NextFile,
+        ShortcutAction::PreviousFile,
+        ShortcutAction::CommandPalette,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ShortcutAction::Scan => "Start scan",
+            ShortcutAction::CancelScan => "Cancel scan",
+            ShortcutAction::DeleteGroup => "Delete unchecked in current group",
+            ShortcutAction::ToggleKeep => "Toggle keep on current file",
+            ShortcutAction::NextGroup => "Next group",
+            ShortcutAction::PreviousGroup => "Previous group",
+            ShortcutAction::NextFile => "Next file",
+            ShortcutAction::PreviousFile => "Previous file",
+            ShortcutAction::CommandPalette => "Command palette",
+        }
+    }
+}
+
+/// A key with modifiers; the key is stored by name so settings stay readable
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub key: String,
+    /// Ctrl, or Cmd on macOS
+    #[serde(default)]
+    pub command: bool,
+    #[serde(default)]
+    pub shift: bool,
+    #[serde(default)]
+    pub alt: bool,
+}
+
+impl KeyBinding {
+    pub fn new(key: egui::Key, modifiers: egui::Modifiers) -> Self {
+        Self {
+            key: key.name().to_string(),
+            command: modifiers.command,
+            shift: modifiers.shift,
+            alt: modifiers.alt,
+        }
+    }
+
+    fn plain(key: egui::Key) -> Self {
+        Self::new(key, egui::Modifiers::NONE)
+    }
+
+    /// None when the stored key name is unknown to this egui version
+    pub fn shortcut(&self) -> Option<egui::KeyboardShortcut> {
+        let key = egui::Key::from_name(&self.key)?;
+        let mut modifiers = egui::Modifiers::NONE;
+        if self.command {
+            modifiers = modifiers | egui::Modifiers::COMMAND;
+        }
+        if self.shift {
+            modifiers = modifiers | egui::Modifiers::SHIFT;
+        }
+        if self.alt {
+            modifiers = modifiers | egui::Modifiers::ALT;
+        }
+        Some(egui::KeyboardShortcut::new(modifiers, key))
+    }
+
+    fn modifier_count(&self) -> usize {
+        [self.command, self.shift, self.alt].iter().filter(|&&m| m).count()
+    }
+}
+
+/// Key bindings of the shortcut actions; an action without an entry is unbound
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Shortcuts {
+    pub bindings: HashMap<ShortcutAction, KeyBinding>,
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        let bindings = HashMap::from([
+            (ShortcutAction::Scan, KeyBinding::plain(egui::Key::F5)),
+            (ShortcutAction::CancelScan, KeyBinding::new(egui::Key::F5, egui::Modifiers::SHIFT)),
+            (ShortcutAction::DeleteGroup, KeyBinding::new(egui::Key::Delete, egui::Modifiers::COMMAND)),
+            (ShortcutAction::ToggleKeep, KeyBinding::plain(egui::Key::Space)),
+            (ShortcutAction::NextGroup, KeyBinding::plain(egui::Key::PageDown)),
+            (ShortcutAction::PreviousGroup, KeyBinding::plain(egui::Key::PageUp)),
+            (ShortcutAction::NextFile, KeyBinding::plain(egui::Key::ArrowDown)),
+            (ShortcutAction::PreviousFile, KeyBinding::plain(egui::Key::ArrowUp)),
+            (ShortcutAction::CommandPalette, KeyBinding::new(egui::Key::P, egui::Modifiers::COMMAND)),
+        ]);
+        Self { bindings }
+    }
+}
+
+impl Shortcuts {
+    /// The bindings saved by the last run, or the default ones
+    pub fn load() -> Self {
+        load_json(SHORTCUTS_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        save_json(SHORTCUTS_FILE, self)
+    }
+
+    /// Human-readable binding of `action`, e.g. "Ctrl+P" or "⌘P" on macOS
+    pub fn describe(&self, ctx: &egui::Context, action: ShortcutAction) -> Option<String> {
+        let shortcut = self.bindings.get(&action)?.shortcut()?;
+        Some(ctx.format_shortcut(&shortcut))
+    }
+
+    /// `text` followed by the action's binding, for tooltips
+    pub fn hint(&self, ctx: &egui::Context, action: ShortcutAction, text: &str) -> String {
+        match self.describe(ctx, action) {
+            Some(keys) if text.is_empty() => format!("Shortcut: {}", keys),
+            Some(keys) => format!("{} ({})", text, keys),
+            None => text.to_string(),
+        }
+    }
+
+    /// Bind `binding` to `action`, taking it away from any other action.
+    /// Returns the action that lost it.
+    pub fn bind(&mut self, action: ShortcutAction, binding: KeyBinding) -> Option<ShortcutAction> {
+        let previous = self.bindings
+            .iter()
+            .find(|(other, bound)| **other != action && **bound == binding)
+            .map(|(other, _)| *other);
+        if let Some(other) = previous {
+            self.bindings.remove(&other);
+        }
+        self.bindings.insert(action, binding);
+        previous
+    }
+
+    /// Consume the key presses of this frame that trigger an action. While a
+    /// text field has focus only bindings with Ctrl/Cmd or Alt apply, so typing
+    /// is never taken for a shortcut.
+    pub fn triggered(&self, ctx: &egui::Context) -> Vec<ShortcutAction> {
+        let typing = ctx.wants_keyboard_input();
+        let mut bindings: Vec<(&ShortcutAction, &KeyBinding)> = self.bindings
+            .iter()
+            .filter(|(_, binding)| !typing || binding.command || binding.alt)
+            .collect();
+        // egui ignores extra Shift and Alt when matching, so Shift+F5 must be tried before F5
+        bindings.sort_by_key(|(_, binding)| std::cmp::Reverse(binding.modifier_count()));
+        ctx.input_mut(|i| {
+            bindings
+                .into_iter()
+                .filter_map(|(action, binding)| Some((*action, binding.shortcut()?)))
+                .filter(|(_, shortcut)| i.consume_shortcut(shortcut))
+                .map(|(action, _)| action)
+                .collect()
+        })
+    }
+}
+
+/// Take the key pressed this frame, for binding it to an action, so no
+/// widget reacts to it. Modifier keys on their own are not events in egui,
+/// so a binding always has a real key.
+pub fn capture_key(ctx: &egui::Context) -> Option<KeyBinding> {
+    ctx.input_mut(|i| {
+        let index = i.events.iter().position(|event| matches!(event, egui::Event::Key { pressed: true, .. }))?;
+        match i.events.remove(index) {
+            egui::Event::Key { key, modifiers, .. } => Some(KeyBinding::new(key, modifiers)),
+            _ => None,
+        }
+    })
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use crate::manifest::{is_digest, parse_manifest, ManifestFormat};
+
+/// Extensions of per-file checksum files, as in `photo.jpg.sha256`
+const SIDECAR_EXTENSIONS: [&str; 2] = ["sha256", "sha256sum"];
+const BAG_DECLARATION: &str = "bagit.txt";
+const BAG_MANIFEST: &str = "manifest-sha256.txt";
+
+// The SHA-256 manifest of one BagIt bag
+struct Bag {
+    modified: Option<SystemTime>,
+    digests: HashMap<PathBuf, String>,
+}
+
+/// SHA-256 checksums other tools keep next to files: a `.sha256` sidecar
+/// beside the file, or the manifest of a BagIt bag holding it. A checksum
+/// only counts when it was written no earlier than the file was last
+/// modified. Bag manifests are read once per scan.
+#[derive(Default)]
+pub struct Sidecars {
+    // Keyed by folder; None for folders that are not a bag
+    bags: Mutex<HashMap<PathBuf, Option<Arc<Bag>>>>,
+}
+
+impl Sidecars {
+    /// The checksum recorded for `path`, last modified at `modified`
+    pub fn digest(&self, path: &Path, modified: Option<SystemTime>) -> Option<String> {
+        let modified = modified?;
+        beside(path, modified).or_else(|| self.in_bag(path, modified))
+    }
+
+    fn in_bag(&self, path: &Path, modified: SystemTime) -> Option<String> {
+        for dir in path.ancestors().skip(1) {
+            let bag = {
+                let mut bags = self.bags.lock().unwrap_or_else(|e| e.into_inner());
+                bags.entry(dir.to_path_buf()).or_insert_with(|| load_bag(dir).map(Arc::new)).clone()
+            };
+            // The nearest bag is the one that lists the file, if any does
+            if let Some(bag) = bag {
+                return bag.digests.get(path).filter(|_| bag.modified.is_some_and(|written| written >= modified)).cloned();
+            }
+        }
+        None
+    }
+}
+
+// `name.sha256` holds either `sha256sum` output or the bare digest
+fn beside(path: &Path, modified: SystemTime) -> Option<String> {
+    let name = path.file_name()?.to_string_lossy().into_owned();
+    for extension in SIDECAR_EXTENSIONS {
+        let sidecar = path.with_file_name(format!("{}.{}", name, extension));
+        let Ok(metadata) = fs::metadata(&sidecar) else {
+            continue;
+        };
+        if metadata.modified().ok().is_none_or(|written| written < modified) {
+            continue;
+        }
+        let Ok(text) = fs::read_to_string(&sidecar) else {
+            continue;
+        };
+        let bare = text.trim();
+        if is_digest(bare) {
+            return Some(bare.to_lowercase());
+        }
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let (entries, _) = parse_manifest(&text, dir, ManifestFormat::Sha256);
+        let listed = entries
+            .into_iter()
+            .filter(|entry| entry.format == ManifestFormat::Sha256)
+            .find(|entry| entry.path.file_name() == path.file_name());
+        if let Some(entry) = listed {
+            return Some(entry.digest);
+        }
+    }
+    None
+}
+
+fn load_bag(dir: &Path) -> Option<Bag> {
+    if !dir.join(BAG_DECLARATION).is_file() {
+        return None;
+    }
+    let manifest = dir.join(BAG_MANIFEST);
+    let modified = fs::metadata(&manifest).ok()?.modified().ok();
+    let text = fs::read_to_string(&manifest).ok()?;
+    let (entries, _) = parse_manifest(&text, dir, ManifestFormat::Sha256);
+    let digests = entries.into_iter().map(|entry| (entry.path, entry.digest)).collect();
+    Some(Bag { modified, digests })
+}
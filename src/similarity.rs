@@ -0,0 +1,218 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+use crate::scanner::{build_thread_pool, filtered_walk, HashGroup, ScanConfig, ScanError};
+
+// Languages whose comments start with "//" or "/*"
+const C_STYLE_EXTENSIONS: &[&str] = &[
+    "c", "h", "cc", "cpp", "cxx", "hpp", "hh", "cs", "java", "js", "jsx", "mjs", "ts", "tsx",
+    "go", "rs", "swift", "kt", "kts", "scala", "php", "dart",
+];
+// Languages whose comments start with "#"
+const HASH_COMMENT_EXTENSIONS: &[&str] = &["py", "rb", "sh", "bash", "pl", "r"];
+
+// Larger files are almost always generated or minified rather than hand-written
+const MAX_SOURCE_SIZE: u64 = 2 * 1024 * 1024;
+// Files this short share too many boilerplate shingles to compare meaningfully
+const MIN_TOKENS: usize = 50;
+const SHINGLE_TOKENS: usize = 4;
+const SIGNATURE_LEN: usize = 64;
+// Locality-sensitive hashing: only files agreeing on a whole band are compared
+const BANDS: usize = 16;
+const ROWS_PER_BAND: usize = SIGNATURE_LEN / BANDS;
+
+/// Source files whose normalized token streams are similar but not identical
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NearDuplicateGroup {
+    pub files: Vec<PathBuf>,
+    /// Lowest estimated similarity (0.0 - 1.0) among the pairs linking the group
+    pub similarity: f64,
+}
+
+fn comment_style(path: &Path) -> Option<bool> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    if C_STYLE_EXTENSIONS.contains(&ext.as_str()) {
+        Some(false)
+    } else if HASH_COMMENT_EXTENSIONS.contains(&ext.as_str()) {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+pub fn is_source_file(path: &Path) -> bool {
+    comment_style(path).is_some()
+}
+
+/// Split source text into tokens, dropping whitespace and comments so that
+/// reformatting or re-commenting a copy does not hide it
+fn normalized_tokens(text: &str, hash_comments: bool) -> Vec<&str> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let rest = &bytes[i..];
+        if c.is_ascii_whitespace() {
+            i += 1;
+        } else if (!hash_comments && rest.starts_with(b"//")) || (hash_comments && c == b'#') {
+            i += rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+        } else if !hash_comments && rest.starts_with(b"/*") {
+            i += rest[2..].windows(2).position(|w| w == b"*/").map_or(rest.len(), |end| end + 4);
+        } else if c == b'"' || c == b'\'' {
+            // String literal up to the closing quote on the same line; an
+            // unmatched quote (e.g. a Rust lifetime) is a single token
+            let mut end = i + 1;
+            while end < bytes.len() && bytes[end] != c && bytes[end] != b'\n' {
+                end += if bytes[end] == b'\\' { 2 } else { 1 };
+            }
+            if end < bytes.len() && bytes[end] == c {
+                tokens.push(&text[i..=end]);
+                i = end + 1;
+            } else {
+                tokens.push(&text[i..i + 1]);
+                i += 1;
+            }
+        } else if c.is_ascii_alphanumeric() || c == b'_' || !c.is_ascii() {
+            let start = i;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || !bytes[i].is_ascii()) {
+                i += 1;
+            }
+            tokens.push(&text[start..i]);
+        } else {
+            tokens.push(&text[i..i + 1]);
+            i += 1;
+        }
+    }
+    tokens
+}
+
+// SplitMix64 finalizer, used to derive independent hash functions from one shingle hash
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// MinHash signature of a file's token shingles, `None` for non-source,
+/// unreadable or trivially small files
+fn signature(path: &Path) -> Option<[u64; SIGNATURE_LEN]> {
+    let hash_comments = comment_style(path)?;
+    if std::fs::metadata(path).ok()?.len() > MAX_SOURCE_SIZE {
+        return None;
+    }
+    let bytes = std::fs::read(path).ok()?;
+    let text = String::from_utf8_lossy(&bytes);
+    let tokens = normalized_tokens(&text, hash_comments);
+    if tokens.len() < MIN_TOKENS {
+        return None;
+    }
+
+    let mut signature = [u64::MAX; SIGNATURE_LEN];
+    for shingle in tokens.windows(SHINGLE_TOKENS) {
+        let mut hasher = DefaultHasher::new();
+        shingle.hash(&mut hasher);
+        let base = hasher.finish();
+        for (i, slot) in signature.iter_mut().enumerate() {
+            *slot = (*slot).min(mix(base ^ (i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)));
+        }
+    }
+    Some(signature)
+}
+
+fn estimated_similarity(a: &[u64; SIGNATURE_LEN], b: &[u64; SIGNATURE_LEN]) -> f64 {
+    a.iter().zip(b).filter(|(x, y)| x == y).count() as f64 / SIGNATURE_LEN as f64
+}
+
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Group source files whose estimated token similarity is at least `threshold`
+pub fn group_near_duplicates(files: &[PathBuf], threshold: f64) -> Vec<NearDuplicateGroup> {
+    let signed: Vec<(&PathBuf, [u64; SIGNATURE_LEN])> = files
+        .par_iter()
+        .filter_map(|path| signature(path).map(|sig| (path, sig)))
+        .collect();
+
+    let mut buckets: HashMap<(usize, &[u64]), Vec<usize>> = HashMap::new();
+    for (idx, (_, sig)) in signed.iter().enumerate() {
+        for (band, rows) in sig.chunks(ROWS_PER_BAND).enumerate() {
+            buckets.entry((band, rows)).or_default().push(idx);
+        }
+    }
+
+    let mut parents: Vec<usize> = (0..signed.len()).collect();
+    let mut compared = HashSet::new();
+    let mut links = Vec::new();
+    for members in buckets.values().filter(|m| m.len() > 1) {
+        for (pos, &a) in members.iter().enumerate() {
+            for &b in &members[pos + 1..] {
+                if !compared.insert((a, b)) {
+                    continue;
+                }
+                let similarity = estimated_similarity(&signed[a].1, &signed[b].1);
+                if similarity >= threshold {
+                    links.push((a, b, similarity));
+                }
+            }
+        }
+    }
+    for &(a, b, _) in &links {
+        let (ra, rb) = (find_root(&mut parents, a), find_root(&mut parents, b));
+        parents[ra] = rb;
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for idx in 0..signed.len() {
+        let root = find_root(&mut parents, idx);
+        groups.entry(root).or_default().push(idx);
+    }
+    let mut weakest: HashMap<usize, f64> = HashMap::new();
+    for &(a, _, similarity) in &links {
+        let root = find_root(&mut parents, a);
+        let entry = weakest.entry(root).or_insert(1.0);
+        *entry = entry.min(similarity);
+    }
+
+    let mut result: Vec<NearDuplicateGroup> = groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(root, members)| {
+            let mut files: Vec<PathBuf> = members.iter().map(|&i| signed[i].0.clone()).collect();
+            files.sort();
+            NearDuplicateGroup { files, similarity: weakest.get(&root).copied().unwrap_or(1.0) }
+        })
+        .collect();
+    result.sort_by(|a, b| b.files.len().cmp(&a.files.len()).then_with(|| a.files.cmp(&b.files)));
+    result
+}
+
+/// Find near-duplicate source files under `roots`. Byte-identical copies already
+/// reported in `exact` take part through a single representative.
+pub fn find_near_duplicate_sources(roots: &[String], exact: &[HashGroup], config: &ScanConfig) -> Result<Vec<NearDuplicateGroup>, ScanError> {
+    let mut skipped: HashSet<PathBuf> = exact
+        .iter()
+        .flat_map(|group| group.files.iter().skip(1).map(|f| f.path.to_path_buf()))
+        .collect();
+    // Adding each file to `skipped` keeps nested roots from listing it twice
+    let files: Vec<PathBuf> = roots
+        .iter()
+        .flat_map(|root| filtered_walk(WalkDir::new(root), config))
+        .filter(|e| e.file_type().is_file() && is_source_file(e.path()))
+        .map(|e| e.into_path())
+        .filter(|path| skipped.insert(path.clone()))
+        .collect();
+
+    let pool = build_thread_pool(config)?;
+    Ok(pool.install(|| group_near_duplicates(&files, config.near_duplicate_threshold)))
+}
@@ -0,0 +1,20 @@
+use std::path::Path;
+
+/// Directory names under which filesystem snapshots appear: `.snapshots`
+/// (snapper on Btrfs), `.zfs` (ZFS datasets), `@snapshots` (Btrfs subvolume
+/// layouts), `timeshift-btrfs` and `.snapshot` (NetApp and other NAS shares)
+const SNAPSHOT_DIR_NAMES: &[&str] = &[".snapshots", ".zfs", "@snapshots", "timeshift-btrfs", ".snapshot"];
+
+pub fn is_snapshot_dir_name(name: &str) -> bool {
+    SNAPSHOT_DIR_NAMES.contains(&name)
+}
+
+pub fn is_snapshot_dir(path: &Path) -> bool {
+    path.file_name().and_then(|n| n.to_str()).is_some_and(is_snapshot_dir_name)
+}
+
+/// Whether `path` lies inside a snapshot, where files are read-only copies
+/// that vanish with the snapshot rather than when deleted
+pub fn is_in_snapshot(path: &Path) -> bool {
+    path.components().any(|c| c.as_os_str().to_str().is_some_and(is_snapshot_dir_name))
+}
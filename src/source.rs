@@ -0,0 +1,241 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+use crate::attrs::permission_bits;
+use crate::report::owner_id;
+use crate::scanner::{filtered_walk, ScanConfig};
+use crate::sparse::sparse_allocation;
+
+/// What a listed entry is; symlinks are reported as such, not followed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+    /// Sockets, devices and other entries that are never hashed
+    Other,
+}
+
+/// One entry found while listing a source
+#[derive(Clone, Debug)]
+pub struct SourceEntry {
+    pub path: PathBuf,
+    pub kind: EntryKind,
+}
+
+/// Identity of a file on its volume: two paths with the same id are hard
+/// links to one file rather than copies
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FileId {
+    pub device: u64,
+    pub inode: u64,
+}
+
+#[cfg(unix)]
+pub fn file_id(metadata: &fs::Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    Some(FileId { device: metadata.dev(), inode: metadata.ino() })
+}
+
+// The volume serial and file index need an open handle on Windows, which
+// the scan does not keep
+#[cfg(not(unix))]
+pub fn file_id(_metadata: &fs::Metadata) -> Option<FileId> {
+    None
+}
+
+#[cfg(unix)]
+pub fn change_time(metadata: &fs::Metadata) -> Option<SystemTime> {
+    use std::os::unix::fs::MetadataExt;
+    let secs = u64::try_from(metadata.ctime()).ok()?;
+    Some(SystemTime::UNIX_EPOCH + std::time::Duration::new(secs, metadata.ctime_nsec() as u32))
+}
+
+#[cfg(not(unix))]
+pub fn change_time(_metadata: &fs::Metadata) -> Option<SystemTime> {
+    None
+}
+
+/// Whether two paths name one file, through a symlink, a hard link or
+/// another spelling; deleting one would then delete the "other copy" too
+pub fn same_file(a: &Path, b: &Path) -> io::Result<bool> {
+    match (file_id(&fs::metadata(a)?), file_id(&fs::metadata(b)?)) {
+        (Some(id_a), Some(id_b)) => Ok(id_a == id_b),
+        _ => Ok(a.canonicalize()? == b.canonicalize()?),
+    }
+}
+
+/// Metadata of a file, as far as the source can tell
+#[derive(Clone, Debug, Default)]
+pub struct SourceMetadata {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub created: Option<SystemTime>,
+    /// Bytes actually allocated, when the file is sparse
+    pub allocated: Option<u64>,
+    pub owner: Option<u32>,
+    pub mode: Option<u32>,
+    pub file_id: Option<FileId>,
+    /// Inode change time, which every write updates, even one that sets the
+    /// modification time back; local files on Unix only
+    pub changed: Option<SystemTime>,
+    /// A digest of the contents the source knows without reading them, e.g.
+    /// an object store's MD5 ETag; only ever compared with other such digests
+    pub digest: Option<String>,
+}
+
+/// Where discovery finds files and hashing reads them. A remote store
+/// (SFTP, S3, Google Drive, an Android device) implements the same calls and the grouping pipeline
+/// stays as it is.
+pub trait FileSource: fmt::Debug + Send + Sync {
+    /// Entries under `root`, the root included, honouring the configuration's
+    /// hidden-file, exclusion and trash settings. `max_depth` of 1 lists only
+    /// the root's own entries. Unreadable entries are skipped.
+    fn list<'a>(&'a self, root: &Path, max_depth: Option<usize>, config: &'a ScanConfig) -> Box<dyn Iterator<Item = SourceEntry> + 'a>;
+
+    /// Metadata of the file at `path`, following symlinks
+    fn metadata(&self, path: &Path) -> io::Result<SourceMetadata>;
+
+    /// Open `path` for reading, with its length in bytes
+    fn open(&self, path: &Path) -> io::Result<(Box<dyn Read + Send>, u64)>;
+
+    /// How many files can usefully be read at once, when the source limits it
+    fn max_readers(&self) -> Option<usize> {
+        None
+    }
+
+    /// SHA-256 of the file worked out where it is stored, when the source
+    /// can, so its contents need not be sent here to be hashed
+    fn stored_sha256(&self, _path: &Path) -> Option<io::Result<String>> {
+        None
+    }
+}
+
+/// A remote store serving every path under its prefix, e.g. "sftp://me@nas:22"
+pub trait RemoteSource: FileSource {
+    fn prefix(&self) -> &Path;
+
+    /// Short description for the list of connected stores
+    fn label(&self) -> String;
+}
+
+/// Whether `path` names a file in a remote store rather than on a local or mounted disk
+pub fn is_remote(path: &Path) -> bool {
+    path.to_str().is_some_and(|p| p.starts_with("sftp://") || p.starts_with("s3://") || p.starts_with("gdrive://") || p.starts_with("adb://"))
+}
+
+/// The local disk plus connected remote stores, each reached through its prefix
+#[derive(Clone, Debug, Default)]
+pub struct MountedSources {
+    remotes: Vec<Arc<dyn RemoteSource>>,
+    local: LocalSource,
+}
+
+impl MountedSources {
+    pub fn is_empty(&self) -> bool {
+        self.remotes.is_empty()
+    }
+
+    pub fn remotes(&self) -> &[Arc<dyn RemoteSource>] {
+        &self.remotes
+    }
+
+    /// Add `remote`, replacing an earlier connection with the same prefix
+    pub fn mount(&mut self, remote: Arc<dyn RemoteSource>) {
+        self.unmount(remote.prefix());
+        self.remotes.push(remote);
+    }
+
+    /// Forget the store at `prefix`; its connections close once no scan uses them
+    pub fn unmount(&mut self, prefix: &Path) {
+        self.remotes.retain(|remote| remote.prefix() != prefix);
+    }
+
+    fn route(&self, path: &Path) -> Option<&dyn FileSource> {
+        if !is_remote(path) {
+            return Some(&self.local);
+        }
+        self.remotes.iter().find(|remote| path.starts_with(remote.prefix())).map(|remote| remote.as_ref() as &dyn FileSource)
+    }
+}
+
+fn not_connected(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::NotConnected, format!("{} is on a store that is not connected", path.display()))
+}
+
+impl FileSource for MountedSources {
+    fn list<'a>(&'a self, root: &Path, max_depth: Option<usize>, config: &'a ScanConfig) -> Box<dyn Iterator<Item = SourceEntry> + 'a> {
+        match self.route(root) {
+            Some(source) => source.list(root, max_depth, config),
+            None => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<SourceMetadata> {
+        self.route(path).ok_or_else(|| not_connected(path))?.metadata(path)
+    }
+
+    fn open(&self, path: &Path) -> io::Result<(Box<dyn Read + Send>, u64)> {
+        self.route(path).ok_or_else(|| not_connected(path))?.open(path)
+    }
+
+    fn max_readers(&self) -> Option<usize> {
+        let limits: Vec<usize> = self.remotes.iter().filter_map(|remote| remote.max_readers()).collect();
+        (!limits.is_empty()).then(|| limits.iter().sum())
+    }
+
+    fn stored_sha256(&self, path: &Path) -> Option<io::Result<String>> {
+        self.route(path)?.stored_sha256(path)
+    }
+}
+
+/// Files on a mounted local or network filesystem
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LocalSource;
+
+impl FileSource for LocalSource {
+    fn list<'a>(&'a self, root: &Path, max_depth: Option<usize>, config: &'a ScanConfig) -> Box<dyn Iterator<Item = SourceEntry> + 'a> {
+        let mut walker = WalkDir::new(root);
+        if let Some(depth) = max_depth {
+            walker = walker.max_depth(depth);
+        }
+        Box::new(filtered_walk(walker, config).map(|entry| {
+            let kind = if entry.file_type().is_file() {
+                EntryKind::File
+            } else if entry.path_is_symlink() {
+                EntryKind::Symlink
+            } else if entry.file_type().is_dir() {
+                EntryKind::Dir
+            } else {
+                EntryKind::Other
+            };
+            SourceEntry { path: entry.into_path(), kind }
+        }))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<SourceMetadata> {
+        let metadata = fs::metadata(path)?;
+        Ok(SourceMetadata {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            created: metadata.created().ok(),
+            allocated: sparse_allocation(&metadata),
+            owner: owner_id(&metadata),
+            mode: permission_bits(&metadata),
+            file_id: file_id(&metadata),
+            changed: change_time(&metadata),
+            digest: None,
+        })
+    }
+
+    fn open(&self, path: &Path) -> io::Result<(Box<dyn Read + Send>, u64)> {
+        let file = File::open(path)?;
+        let size = file.metadata()?.len();
+        Ok((Box::new(file), size))
+    }
+}
@@ -0,0 +1,48 @@
+use std::fs::Metadata;
+use serde::{Deserialize, Serialize};
+
+// Small files are never treated as sparse; their block rounding is noise
+const SPARSE_MIN_SIZE: u64 = 1024 * 1024;
+
+/// How sparse files (disk images, VM disks) are treated by a scan
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SparseMode {
+    /// Hash them like any other file
+    Include,
+    /// Hash them, but list their groups after all others
+    #[default]
+    Demote,
+    /// Leave them out of the scan; hashing a mostly empty image is slow
+    Skip,
+}
+
+impl SparseMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            SparseMode::Include => "Include",
+            SparseMode::Demote => "List last",
+            SparseMode::Skip => "Skip",
+        }
+    }
+}
+
+/// Bytes actually allocated on disk, where the platform reports it
+#[cfg(unix)]
+pub fn allocated_size(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    // st_blocks is always in 512-byte units, whatever the filesystem block size
+    Some(metadata.blocks() * 512)
+}
+
+#[cfg(not(unix))]
+pub fn allocated_size(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+/// Allocated size of a file that occupies less than half its logical size
+/// on disk, or None for a fully allocated file. Transparent compression
+/// (btrfs, ZFS) shrinks allocation too, so this is a heuristic.
+pub fn sparse_allocation(metadata: &Metadata) -> Option<u64> {
+    let len = metadata.len();
+    allocated_size(metadata).filter(|&allocated| len >= SPARSE_MIN_SIZE && allocated < len / 2)
+}
@@ -0,0 +1,111 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+// Same limit as most kernels before they report ELOOP
+const MAX_LINK_HOPS: usize = 40;
+
+/// A symlink whose target is a regular file also present in the scanned tree
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DuplicatingLink {
+    pub link: PathBuf,
+    pub target: PathBuf,
+}
+
+/// Several symlinks resolving to the same target
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SharedTarget {
+    pub target: PathBuf,
+    /// Sorted shortest path first; the first link is the one kept
+    pub links: Vec<PathBuf>,
+}
+
+/// Symlinks found in a scanned tree, reported apart from duplicate file groups
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SymlinkReport {
+    /// Links whose target does not exist (or that loop)
+    pub broken: Vec<PathBuf>,
+    pub duplicating: Vec<DuplicatingLink>,
+    pub shared_targets: Vec<SharedTarget>,
+    /// Links that point at another link, each listed hop by hop
+    pub chains: Vec<Vec<PathBuf>>,
+}
+
+impl SymlinkReport {
+    pub fn is_empty(&self) -> bool {
+        self.broken.is_empty() && self.duplicating.is_empty() && self.shared_targets.is_empty() && self.chains.is_empty()
+    }
+
+    /// Links that can go without losing access to any file: broken links,
+    /// links to files already in the tree, and all but one link per target
+    pub fn redundant_links(&self) -> Vec<PathBuf> {
+        let mut links = self.broken.clone();
+        links.extend(self.duplicating.iter().map(|d| d.link.clone()));
+        links.extend(self.shared_targets.iter().flat_map(|s| s.links.iter().skip(1).cloned()));
+        links
+    }
+
+    /// Drop links that no longer exist from every list
+    pub fn forget(&mut self, removed: &HashSet<PathBuf>) {
+        self.broken.retain(|link| !removed.contains(link));
+        self.duplicating.retain(|d| !removed.contains(&d.link));
+        for shared in &mut self.shared_targets {
+            shared.links.retain(|link| !removed.contains(link));
+        }
+        self.shared_targets.retain(|s| s.links.len() > 1);
+        self.chains.retain(|chain| !removed.contains(&chain[0]));
+    }
+}
+
+// Each hop of a link chain, starting with the link itself
+fn link_chain(link: &Path) -> Vec<PathBuf> {
+    let mut chain = vec![link.to_path_buf()];
+    let mut current = link.to_path_buf();
+    while chain.len() <= MAX_LINK_HOPS {
+        let Ok(target) = fs::read_link(&current) else {
+            break;
+        };
+        // Relative targets are relative to the directory holding the link
+        let next = current.parent().map_or(target.clone(), |dir| dir.join(&target));
+        let is_link = next.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink());
+        chain.push(next.clone());
+        if !is_link {
+            break;
+        }
+        current = next;
+    }
+    chain
+}
+
+/// Classify the symlinks found while scanning `root` as broken, redundant or chained
+pub fn analyze_links(root: &Path, links: Vec<PathBuf>) -> SymlinkReport {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let mut report = SymlinkReport::default();
+    let mut by_target: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+
+    for link in links {
+        let chain = link_chain(&link);
+        if chain.len() > 2 {
+            report.chains.push(chain);
+        }
+        match link.canonicalize() {
+            Ok(target) if target.starts_with(&root) && target.is_file() => {
+                report.duplicating.push(DuplicatingLink { link, target });
+            }
+            Ok(target) => by_target.entry(target).or_default().push(link),
+            Err(_) => report.broken.push(link),
+        }
+    }
+
+    for (target, mut links) in by_target {
+        if links.len() > 1 {
+            links.sort_by_key(|link| (link.as_os_str().len(), link.clone()));
+            report.shared_targets.push(SharedTarget { target, links });
+        }
+    }
+    report.broken.sort();
+    report.duplicating.sort_by(|a, b| a.link.cmp(&b.link));
+    report.chains.sort();
+    report
+}
@@ -0,0 +1,144 @@
+/// What the window's taskbar button shows behind its icon
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TaskbarProgress {
+    None,
+    /// Working without a known end, e.g. while files are still being discovered
+    Indeterminate,
+    Normal { done: u64, total: u64 },
+    Paused { done: u64, total: u64 },
+}
+
+/// Scan progress on the taskbar button. Only Windows has such a progress bar;
+/// elsewhere the window title carries the same summary and this does nothing.
+#[derive(Default)]
+pub struct Taskbar {
+    shown: Option<TaskbarProgress>,
+    #[cfg(windows)]
+    list: Option<windows::TaskbarList>,
+}
+
+impl Taskbar {
+    /// Show `progress` on the button of `frame`'s window, if it differs from what is shown
+    pub fn set(&mut self, frame: &eframe::Frame, progress: TaskbarProgress) {
+        if self.shown == Some(progress) {
+            return;
+        }
+        self.shown = Some(progress);
+        self.show(frame, progress);
+    }
+
+    #[cfg(windows)]
+    fn show(&mut self, frame: &eframe::Frame, progress: TaskbarProgress) {
+        use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+        let hwnd = match frame.window_handle().map(|handle| handle.as_raw()) {
+            Ok(RawWindowHandle::Win32(handle)) => handle.hwnd.get(),
+            _ => return,
+        };
+        if self.list.is_none() {
+            self.list = windows::TaskbarList::new();
+        }
+        if let Some(list) = &self.list {
+            list.show(hwnd, progress);
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn show(&mut self, _frame: &eframe::Frame, _progress: TaskbarProgress) {}
+}
+
+#[cfg(windows)]
+mod windows {
+    use super::TaskbarProgress;
+    use std::ffi::c_void;
+
+    #[repr(C)]
+    pub struct Guid(u32, u16, u16, [u8; 8]);
+
+    const CLSID_TASKBAR_LIST: Guid = Guid(0x56FD_F344, 0xFD6D, 0x11D0, [0x95, 0x8A, 0x00, 0x60, 0x97, 0xC9, 0xA0, 0x90]);
+    const IID_ITASKBAR_LIST3: Guid = Guid(0xEA1A_FB91, 0x9E28, 0x4B86, [0x90, 0xE9, 0x9E, 0x9F, 0x8A, 0x5E, 0xEF, 0xAF]);
+    const COINIT_APARTMENTTHREADED: u32 = 0x2;
+    const CLSCTX_INPROC_SERVER: u32 = 0x1;
+    const TBPF_NOPROGRESS: u32 = 0x0;
+    const TBPF_INDETERMINATE: u32 = 0x1;
+    const TBPF_NORMAL: u32 = 0x2;
+    const TBPF_PAUSED: u32 = 0x8;
+
+    type Hwnd = isize;
+
+    // ITaskbarList3 up to the methods used; the order follows the interface
+    // declaration (IUnknown, ITaskbarList, ITaskbarList2, ITaskbarList3)
+    #[repr(C)]
+    struct TaskbarListVtbl {
+        query_interface: usize,
+        add_ref: usize,
+        release: unsafe extern "system" fn(*mut c_void) -> u32,
+        hr_init: unsafe extern "system" fn(*mut c_void) -> i32,
+        add_tab: usize,
+        delete_tab: usize,
+        activate_tab: usize,
+        set_active_alt: usize,
+        mark_fullscreen_window: usize,
+        set_progress_value: unsafe extern "system" fn(*mut c_void, Hwnd, u64, u64) -> i32,
+        set_progress_state: unsafe extern "system" fn(*mut c_void, Hwnd, u32) -> i32,
+    }
+
+    /// The shell's ITaskbarList3, created on and used from the UI thread
+    pub struct TaskbarList(*mut c_void);
+
+    impl TaskbarList {
+        pub fn new() -> Option<Self> {
+            let mut list = std::ptr::null_mut();
+            // SAFETY: plain COM calls with valid GUIDs and an out pointer; COM may
+            // already be initialised on this thread, which only changes the result
+            unsafe {
+                CoInitializeEx(std::ptr::null_mut(), COINIT_APARTMENTTHREADED);
+                if CoCreateInstance(&CLSID_TASKBAR_LIST, std::ptr::null_mut(), CLSCTX_INPROC_SERVER, &IID_ITASKBAR_LIST3, &mut list) < 0 || list.is_null() {
+                    return None;
+                }
+            }
+            let list = TaskbarList(list);
+            // SAFETY: `list` holds a live ITaskbarList3
+            if unsafe { (list.vtbl().hr_init)(list.0) } < 0 {
+                return None;
+            }
+            Some(list)
+        }
+
+        // SAFETY (for callers): the object starts with its vtable pointer
+        unsafe fn vtbl(&self) -> &TaskbarListVtbl {
+            &**(self.0 as *const *const TaskbarListVtbl)
+        }
+
+        pub fn show(&self, hwnd: Hwnd, progress: TaskbarProgress) {
+            let (state, value) = match progress {
+                TaskbarProgress::None => (TBPF_NOPROGRESS, None),
+                TaskbarProgress::Indeterminate => (TBPF_INDETERMINATE, None),
+                TaskbarProgress::Normal { done, total } => (TBPF_NORMAL, Some((done, total))),
+                TaskbarProgress::Paused { done, total } => (TBPF_PAUSED, Some((done, total))),
+            };
+            // SAFETY: `self.0` is a live ITaskbarList3 and `hwnd` the app's window
+            unsafe {
+                let vtbl = self.vtbl();
+                (vtbl.set_progress_state)(self.0, hwnd, state);
+                if let Some((done, total)) = value {
+                    (vtbl.set_progress_value)(self.0, hwnd, done, total.max(1));
+                }
+            }
+        }
+    }
+
+    impl Drop for TaskbarList {
+        fn drop(&mut self) {
+            // SAFETY: releases the reference CoCreateInstance handed out
+            unsafe {
+                (self.vtbl().release)(self.0);
+            }
+        }
+    }
+
+    #[link(name = "ole32")]
+    extern "system" {
+        fn CoInitializeEx(reserved: *mut c_void, coinit: u32) -> i32;
+        fn CoCreateInstance(clsid: *const Guid, outer: *mut c_void, context: u32, iid: *const Guid, object: *mut *mut c_void) -> i32;
+    }
+}
@@ -0,0 +1,113 @@
+use eframe::egui::{self, Color32, Stroke};
+
+/// Colors used for status and warning text throughout the UI
+#[derive(Clone, Copy, Debug)]
+pub struct Palette {
+    pub critical: Color32,
+    pub warning: Color32,
+    pub caution: Color32,
+    pub preview: Color32,
+    pub success: Color32,
+    pub sensitive: Color32,
+}
+
+// Hues told apart at a glance, one per scan root, named so a choice reads as "keep all blue"
+const ROOT_COLORS: [(&str, Color32); 8] = [
+    ("blue", Color32::from_rgb(80, 150, 255)),
+    ("green", Color32::from_rgb(90, 200, 90)),
+    ("orange", Color32::from_rgb(255, 150, 50)),
+    ("purple", Color32::from_rgb(180, 120, 255)),
+    ("teal", Color32::from_rgb(40, 200, 190)),
+    ("pink", Color32::from_rgb(255, 120, 180)),
+    ("yellow", Color32::from_rgb(230, 210, 60)),
+    ("brown", Color32::from_rgb(190, 140, 90)),
+];
+
+/// Color chip of the scan root at `idx`; colors repeat after eight roots
+pub fn root_color(idx: usize) -> Color32 {
+    ROOT_COLORS[idx % ROOT_COLORS.len()].1
+}
+
+pub fn root_color_name(idx: usize) -> &'static str {
+    ROOT_COLORS[idx % ROOT_COLORS.len()].0
+}
+
+impl Palette {
+    pub fn new(high_contrast: bool) -> Self {
+        if high_contrast {
+            // Saturated colors that stay legible against a pure black background
+            Self {
+                critical: Color32::from_rgb(255, 110, 110),
+                warning: Color32::from_rgb(255, 255, 0),
+                caution: Color32::from_rgb(255, 200, 0),
+                preview: Color32::from_rgb(0, 255, 255),
+                success: Color32::from_rgb(0, 255, 0),
+                sensitive: Color32::from_rgb(255, 120, 255),
+            }
+        } else {
+            Self {
+                critical: Color32::from_rgb(255, 100, 100),
+                warning: Color32::from_rgb(200, 200, 100),
+                caution: Color32::from_rgb(255, 200, 100),
+                preview: Color32::from_rgb(100, 150, 200),
+                success: Color32::from_rgb(100, 200, 100),
+                sensitive: Color32::from_rgb(200, 130, 255),
+            }
+        }
+    }
+}
+
+/// Turn high contrast on or off. The visuals in use before it was turned on
+/// are kept in `saved` and put back when it is turned off; otherwise the
+/// visuals, e.g. a light theme, are left alone.
+pub fn apply_visuals(ctx: &egui::Context, high_contrast: bool, saved: &mut Option<egui::Visuals>) {
+    if !high_contrast {
+        if let Some(previous) = saved.take() {
+            ctx.set_visuals(previous);
+        }
+        return;
+    }
+    saved.get_or_insert_with(|| ctx.style().visuals.clone());
+
+    let mut visuals = egui::Visuals::dark();
+    visuals.override_text_color = Some(Color32::WHITE);
+    visuals.panel_fill = Color32::BLACK;
+    visuals.window_fill = Color32::BLACK;
+    visuals.extreme_bg_color = Color32::BLACK;
+    visuals.faint_bg_color = Color32::from_gray(20);
+    visuals.window_stroke = Stroke::new(2.0, Color32::WHITE);
+    visuals.selection.bg_fill = Color32::from_rgb(0, 90, 200);
+    visuals.selection.stroke = Stroke::new(2.0, Color32::WHITE);
+    visuals.hyperlink_color = Color32::from_rgb(0, 255, 255);
+
+    for widget in [
+        &mut visuals.widgets.noninteractive,
+        &mut visuals.widgets.inactive,
+        &mut visuals.widgets.hovered,
+        &mut visuals.widgets.active,
+        &mut visuals.widgets.open,
+    ] {
+        widget.fg_stroke = Stroke::new(1.5, Color32::WHITE);
+    }
+    visuals.widgets.noninteractive.bg_stroke = Stroke::new(1.0, Color32::from_gray(200));
+    visuals.widgets.inactive.bg_fill = Color32::from_gray(30);
+    visuals.widgets.inactive.weak_bg_fill = Color32::from_gray(30);
+    visuals.widgets.inactive.bg_stroke = Stroke::new(1.0, Color32::WHITE);
+    visuals.widgets.hovered.bg_stroke = Stroke::new(2.0, Color32::from_rgb(255, 255, 0));
+    visuals.widgets.active.bg_stroke = Stroke::new(2.0, Color32::from_rgb(255, 255, 0));
+
+    ctx.set_visuals(visuals);
+}
+
+/// Give a widget an explicit screen-reader name, e.g. for icon-only buttons
+pub fn accessible_label(response: &egui::Response, typ: egui::WidgetType, label: impl Into<String>) {
+    let label = label.into();
+    let enabled = response.enabled();
+    response.widget_info(|| egui::WidgetInfo::labeled(typ, enabled, &label));
+}
+
+pub fn accessible_checkbox(response: &egui::Response, checked: bool, label: impl Into<String>) {
+    let label = label.into();
+    let enabled = response.enabled();
+    response.widget_info(|| egui::WidgetInfo::selected(egui::WidgetType::Checkbox, enabled, checked, &label));
+}
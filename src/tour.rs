@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use eframe::egui::{self, Color32, Rect, Stroke};
+use crate::settings::data_dir;
+
+const COMPLETED_FILE: &str = "tour-completed";
+
+/// Parts of the main window the tour points at
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TourTarget {
+    Directory,
+    ScanButton,
+    PreviewMode,
+    Strategies,
+    LastCopyGuard,
+}
+
+pub struct TourStep {
+    pub target: TourTarget,
+    pub title: &'static str,
+    pub text: &'static str,
+}
+
+pub const STEPS: &[TourStep] = &[
+    TourStep {
+        target: TourTarget::Directory,
+        title: "1. Choose a folder",
+        text: "Type or browse to the folder to search for duplicates. Nothing is changed by scanning; files are only read and hashed.",
+    },
+    TourStep {
+        target: TourTarget::ScanButton,
+        title: "2. Scan",
+        text: "Scanning groups files with identical content. Every file in a group starts checked, which means kept.",
+    },
+    TourStep {
+        target: TourTarget::PreviewMode,
+        title: "3. Preview mode",
+        text: "While this is on, every delete, move or overwrite only reports what it would do. Leave it on until the selection looks right.",
+    },
+    TourStep {
+        target: TourTarget::Strategies,
+        title: "4. Selection strategies",
+        text: "These buttons choose which copy to keep in every shown group: the newest, the oldest or the likely original. Unchecked files are the ones deleted. They appear once a scan has found duplicates.",
+    },
+    TourStep {
+        target: TourTarget::LastCopyGuard,
+        title: "5. Deletion safety",
+        text: "A group with every file unchecked is never deleted unless this is on, and even then only after a separate confirmation. Deletions are recorded in the action log.",
+    },
+];
+
+/// Guided tour drawn over the main window: dims everything but the widget
+/// a step is about and explains it in a bubble next to it
+#[derive(Default)]
+pub struct Tour {
+    /// Index into `STEPS` while the tour is shown
+    pub step: Option<usize>,
+    /// Where each target was drawn this frame
+    anchors: HashMap<TourTarget, Rect>,
+}
+
+fn completed_marker() -> Option<PathBuf> {
+    Some(data_dir()?.join(COMPLETED_FILE))
+}
+
+impl Tour {
+    /// Start the tour unless it was finished or skipped in an earlier run
+    pub fn load() -> Self {
+        let completed = completed_marker().is_some_and(|marker| marker.exists());
+        Self { step: (!completed).then_some(0), anchors: HashMap::new() }
+    }
+
+    pub fn start(&mut self) {
+        self.step = Some(0);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.step.is_some()
+    }
+
+    /// Remember where a target widget was drawn; call while building the frame
+    pub fn anchor(&mut self, target: TourTarget, response: &egui::Response) {
+        if self.is_active() {
+            self.anchors.insert(target, response.rect);
+        }
+    }
+
+    fn finish(&mut self) {
+        self.step = None;
+        // Without a data directory the tour simply shows again next time
+        if let Some(marker) = completed_marker() {
+            if let Some(dir) = marker.parent() {
+                let _ = fs::create_dir_all(dir);
+            }
+            let _ = fs::write(marker, b"");
+        }
+    }
+
+    /// Draw the current step on top of the window; call after all panels
+    pub fn show(&mut self, ctx: &egui::Context, highlight: Color32) {
+        let Some(index) = self.step else {
+            return;
+        };
+        let step = &STEPS[index];
+        let target = self.anchors.get(&step.target).copied();
+        self.anchors.clear();
+
+        let screen = ctx.screen_rect();
+        let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("tour_dim")));
+        let shade = Color32::from_black_alpha(150);
+        match target {
+            Some(rect) => {
+                let hole = rect.expand(6.0);
+                // Four bands around the target leave it undimmed
+                painter.rect_filled(Rect::from_min_max(screen.min, egui::pos2(screen.max.x, hole.min.y)), 0.0, shade);
+                painter.rect_filled(Rect::from_min_max(egui::pos2(screen.min.x, hole.max.y), screen.max), 0.0, shade);
+                painter.rect_filled(Rect::from_min_max(egui::pos2(screen.min.x, hole.min.y), egui::pos2(hole.min.x, hole.max.y)), 0.0, shade);
+                painter.rect_filled(Rect::from_min_max(egui::pos2(hole.max.x, hole.min.y), egui::pos2(screen.max.x, hole.max.y)), 0.0, shade);
+                painter.rect_stroke(hole, 4.0, Stroke::new(2.5, highlight));
+            }
+            None => {
+                painter.rect_filled(screen, 0.0, shade);
+            }
+        }
+
+        // Below the target where there is room, otherwise above it; centered when it is not shown
+        let bubble_width = 360.0;
+        let position = match target {
+            Some(rect) if rect.max.y + 180.0 < screen.max.y => egui::pos2(rect.min.x, rect.max.y + 12.0),
+            Some(rect) => egui::pos2(rect.min.x, (rect.min.y - 180.0).max(screen.min.y)),
+            None => egui::pos2(screen.center().x - bubble_width / 2.0, screen.center().y - 80.0),
+        };
+        let position = egui::pos2(position.x.clamp(screen.min.x, (screen.max.x - bubble_width - 20.0).max(screen.min.x)), position.y);
+
+        let mut next = None;
+        let mut done = false;
+        egui::Area::new(egui::Id::new("tour_bubble"))
+            .order(egui::Order::Tooltip)
+            .fixed_pos(position)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).stroke(Stroke::new(2.0, highlight)).show(ui, |ui| {
+                    ui.set_width(bubble_width);
+                    ui.strong(step.title);
+                    ui.add_space(4.0);
+                    ui.label(step.text);
+                    if target.is_none() {
+                        ui.weak("(Not shown right now.)");
+                    }
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        ui.weak(format!("{} / {}", index + 1, STEPS.len()));
+                        if ui.add_enabled(index > 0, egui::Button::new("⬅ Back")).clicked() {
+                            next = Some(index - 1);
+                        }
+                        if index + 1 < STEPS.len() {
+                            if ui.button("Next ➡").clicked() {
+                                next = Some(index + 1);
+                            }
+                            if ui.button("Skip tour").clicked() {
+                                done = true;
+                            }
+                        } else if ui.button("✓ Finish").clicked() {
+                            done = true;
+                        }
+                    });
+                });
+            });
+
+        if done || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.finish();
+        } else if let Some(next) = next {
+            self.step = Some(next);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use serde::{Deserialize, Serialize};
+
+/// How files found in a trash or recycle bin are treated by a scan
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrashMode {
+    /// Treat trashed files like any other file
+    Include,
+    /// Don't descend into trash folders at all
+    Exclude,
+    /// List trashed copies of live files in their own section
+    #[default]
+    Separate,
+}
+
+impl TrashMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            TrashMode::Include => "Include",
+            TrashMode::Exclude => "Exclude",
+            TrashMode::Separate => "Show separately",
+        }
+    }
+}
+
+/// A trashed file whose content still exists outside the trash
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TrashedCopy {
+    pub path: PathBuf,
+    pub size: u64,
+    pub live_copy: PathBuf,
+}
+
+// The per-user trash of the freedesktop.org spec; macOS and Windows trash
+// folders are recognised by name instead
+fn home_trash() -> Option<&'static Path> {
+    static HOME_TRASH: OnceLock<Option<PathBuf>> = OnceLock::new();
+    HOME_TRASH
+        .get_or_init(|| {
+            let data_home = std::env::var_os("XDG_DATA_HOME")
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))?;
+            Some(data_home.join("Trash"))
+        })
+        .as_deref()
+}
+
+/// Whether a directory name marks a trash folder: `.Trash` (macOS home),
+/// `.Trashes` (macOS volumes), `.Trash-<uid>` (freedesktop volumes), `$Recycle.Bin`
+pub fn is_trash_dir_name(name: &str) -> bool {
+    name == ".Trash" || name == ".Trashes" || name.starts_with(".Trash-") || name.eq_ignore_ascii_case("$Recycle.Bin")
+}
+
+pub fn is_trash_dir(path: &Path) -> bool {
+    home_trash().is_some_and(|trash| path == trash)
+        || path.file_name().and_then(|n| n.to_str()).is_some_and(is_trash_dir_name)
+}
+
+pub fn is_in_trash(path: &Path) -> bool {
+    home_trash().is_some_and(|trash| path.starts_with(trash))
+        || path.components().any(|c| c.as_os_str().to_str().is_some_and(is_trash_dir_name))
+}
+
+/// Permanently delete a trashed file, along with its freedesktop `.trashinfo`
+/// record when it sits directly in a trash's `files` folder
+pub fn purge(path: &Path) -> io::Result<()> {
+    fs::remove_file(path)?;
+    let parent = path.parent();
+    if let (Some(files_dir), Some(name)) = (parent.filter(|p| p.file_name().is_some_and(|n| n == "files")), path.file_name()) {
+        if let Some(trash) = files_dir.parent() {
+            let mut info_name = name.to_os_string();
+            info_name.push(".trashinfo");
+            let _ = fs::remove_file(trash.join("info").join(info_name));
+        }
+    }
+    Ok(())
+}
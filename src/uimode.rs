@@ -0,0 +1,121 @@
+use std::fs;
+use serde::{Deserialize, Serialize};
+use crate::cleanup::{CleanupRun, RecommendedStrategy};
+use crate::settings::{data_dir, load_json, save_json};
+use crate::originals::KeepLikelyOriginalStrategy;
+use crate::scanner::{KeepNewestStrategy, KeepOldestStrategy, SelectionStrategy};
+
+const MODE_FILE: &str = "ui-mode.json";
+/// Groups listed on the cleanup wizard's review page
+pub const REVIEW_GROUPS: usize = 100;
+
+/// How much of the main window is shown
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UiMode {
+    /// A folder, the scan button and the guided cleanup
+    #[default]
+    Simple,
+    /// Every setting, tool and bulk action
+    Advanced,
+}
+
+// Anything in the data folder but the logs written at startup: settings,
+// history or a cache from a session before the mode could be chosen
+fn used_before() -> bool {
+    data_dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .is_some_and(|mut entries| entries.any(|entry| entry.is_ok_and(|entry| entry.file_name() != "logs")))
+}
+
+impl UiMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            UiMode::Simple => "Simple",
+            UiMode::Advanced => "Advanced",
+        }
+    }
+
+    /// The mode the last session was left in. New installations start
+    /// simple; earlier versions showed everything, so their users keep the
+    /// advanced mode.
+    pub fn load() -> Self {
+        if let Some(mode) = load_json(MODE_FILE) {
+            return mode;
+        }
+        let mode = if used_before() { UiMode::Advanced } else { UiMode::Simple };
+        mode.remember();
+        mode
+    }
+
+    /// Make later sessions start in this mode. Without a data directory every
+    /// session simply starts simple.
+    pub fn remember(self) {
+        let _ = save_json(MODE_FILE, &self);
+    }
+}
+
+/// Which copy the cleanup wizard keeps in every group
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CleanupStrategy {
+    /// A rule per kind of file
+    #[default]
+    Recommended,
+    LikelyOriginal,
+    Newest,
+    Oldest,
+}
+
+impl CleanupStrategy {
+    pub const ALL: [CleanupStrategy; 4] = [
+        CleanupStrategy::Recommended,
+        CleanupStrategy::LikelyOriginal,
+        CleanupStrategy::Newest,
+        CleanupStrategy::Oldest,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CleanupStrategy::Recommended => "🧭 Recommended: a rule per kind of file",
+            CleanupStrategy::LikelyOriginal => "⭐ Keep the likely original",
+            CleanupStrategy::Newest => "📅 Keep the newest copy",
+            CleanupStrategy::Oldest => "🕰 Keep the oldest copy",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            CleanupStrategy::Recommended => "Chooses by kind of file: the highest-quality copy of a photo (in the library rather than a thumbnail or export folder, under its camera name), the newest copy of a document, the copy of source code inside a Git repository and the likely original of anything else.",
+            CleanupStrategy::LikelyOriginal => "Keeps the file that looks like the original: created first, without \"(1)\" or \"Copy of\" in its name, outside backup folders, with the shortest path.",
+            CleanupStrategy::Newest => "Keeps the most recently modified copy, e.g. when the copies are older snapshots of the same file.",
+            CleanupStrategy::Oldest => "Keeps the copy modified first, e.g. when later copies were made by syncing or downloading again.",
+        }
+    }
+
+    pub fn selection(self) -> &'static dyn SelectionStrategy {
+        match self {
+            CleanupStrategy::Recommended => &RecommendedStrategy,
+            CleanupStrategy::LikelyOriginal => &KeepLikelyOriginalStrategy,
+            CleanupStrategy::Newest => &KeepNewestStrategy,
+            CleanupStrategy::Oldest => &KeepOldestStrategy,
+        }
+    }
+}
+
+/// Pages of the cleanup wizard, in order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WizardStep {
+    Strategy,
+    Review,
+    Confirm,
+    /// Deleting group by group, then the outcome
+    Progress,
+}
+
+/// State of the guided cleanup shown in simple mode
+#[derive(Default)]
+pub struct CleanupWizard {
+    /// The page shown while the wizard is open
+    pub step: Option<WizardStep>,
+    pub strategy: CleanupStrategy,
+    pub run: Option<CleanupRun>,
+}
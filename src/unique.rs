@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use crate::scanner::ScanRoot;
+
+/// A file whose content exists nowhere else under the scanned roots: losing
+/// the disk it is on loses it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UniqueFile {
+    pub path: PathBuf,
+    pub size: u64,
+    #[serde(default)]
+    pub modified: Option<SystemTime>,
+}
+
+/// Count and bytes of `files` under each of `roots`, by root index. A file
+/// under nested roots counts for the outer one.
+pub fn per_root(files: &[UniqueFile], roots: &[ScanRoot]) -> Vec<(usize, u64)> {
+    let mut totals = vec![(0, 0); roots.len()];
+    for file in files {
+        if let Some(root) = ScanRoot::containing(roots, &file.path) {
+            totals[root].0 += 1;
+            totals[root].1 += file.size;
+        }
+    }
+    totals
+}
@@ -0,0 +1,124 @@
+use std::io;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::settings::{load_json, save_json};
+
+const NETWORK_FILE: &str = "network.json";
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/ben-santora/dupe-finder-gui/releases/latest";
+const RELEASES_PAGE: &str = "https://github.com/ben-santora/dupe-finder-gui/releases";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const READ_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// What the program may reach over the network, kept across sessions
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct NetworkSettings {
+    /// Ask GitHub for a newer release at startup; off until turned on
+    #[serde(default)]
+    pub check_updates: bool,
+    /// No update checks and no SFTP, S3 or Google Drive connections
+    #[serde(default)]
+    pub offline: bool,
+}
+
+impl NetworkSettings {
+    pub fn load() -> Self {
+        load_json(NETWORK_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        save_json(NETWORK_FILE, self)
+    }
+
+    /// Whether startup may check for updates
+    pub fn checks_updates(&self) -> bool {
+        self.check_updates && !self.offline
+    }
+}
+
+/// A published release, as described by the GitHub releases API
+#[derive(Clone, Debug, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The release notes, in Markdown
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub html_url: Option<String>,
+}
+
+impl Release {
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    pub fn title(&self) -> String {
+        match self.name.as_deref().map(str::trim).filter(|name| !name.is_empty()) {
+            Some(name) => name.to_string(),
+            None => format!("Version {}", self.version()),
+        }
+    }
+
+    pub fn changelog(&self) -> &str {
+        self.body.as_deref().map(str::trim).filter(|body| !body.is_empty()).unwrap_or("No release notes were published.")
+    }
+
+    pub fn page(&self) -> &str {
+        self.html_url.as_deref().unwrap_or(RELEASES_PAGE)
+    }
+
+    /// Whether this release is later than the running program
+    pub fn is_newer(&self) -> bool {
+        version_parts(self.version()) > version_parts(env!("CARGO_PKG_VERSION"))
+    }
+}
+
+// "1.2.3-beta" → [1, 2, 3]; pre-release suffixes are ignored
+fn version_parts(version: &str) -> Vec<u64> {
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    let mut parts: Vec<u64> = core.split('.').map(|part| part.parse().unwrap_or(0)).collect();
+    while parts.last() == Some(&0) {
+        parts.pop();
+    }
+    parts
+}
+
+/// The latest release on GitHub. Only called with network access allowed.
+pub fn latest_release() -> Result<Release, String> {
+    let agent = ureq::AgentBuilder::new().timeout_connect(CONNECT_TIMEOUT).timeout_read(READ_TIMEOUT).build();
+    let response = agent
+        .get(LATEST_RELEASE_URL)
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", concat!("dupe-finder-gui/", env!("CARGO_PKG_VERSION")))
+        .call()
+        .map_err(|e| match e {
+            ureq::Error::Status(404, _) => "No release has been published yet".to_string(),
+            ureq::Error::Status(code, _) => format!("GitHub answered HTTP {}", code),
+            ureq::Error::Transport(transport) => transport.to_string(),
+        })?;
+    let body = response.into_string().map_err(|e| e.to_string())?;
+    serde_json::from_str(&body).map_err(|e| format!("Unexpected reply from GitHub: {}", e))
+}
+
+/// State of the update check and its banner
+#[derive(Default)]
+pub struct UpdateCheck {
+    pub receiver: Option<Receiver<Result<Release, String>>>,
+    /// The latest release, once a check succeeded; the banner shows when it is newer than this one
+    pub latest: Option<Release>,
+    /// Hide the banner for the rest of the session
+    pub dismissed: bool,
+    pub show_changelog: bool,
+    /// Outcome of a check asked for from the settings, where a failure or
+    /// "up to date" is worth showing
+    pub message: String,
+    pub manual: bool,
+}
+
+impl UpdateCheck {
+    pub fn newer(&self) -> Option<&Release> {
+        self.latest.as_ref().filter(|release| release.is_newer())
+    }
+}